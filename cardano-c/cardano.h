@@ -8,56 +8,276 @@
 extern "C" {
 #endif
 
+#include <stddef.h>
 #include <stdint.h>
 
 /*!
-* Type used to represent failure and success
+* Type used to represent failure and success, returned by every function
+* except the ones freeing memory.
+*
+* The values are stable: they are never renumbered and new codes are only
+* appended to their group.
+* \sa cardano_error_message()
 */
 typedef enum _cardano_result {
     CARDANO_RESULT_SUCCESS = 0,
-    CARDANO_RESULT_ERROR = 1
+    /*!Unspecified failure*/
+    CARDANO_RESULT_ERROR = 1,
+    /*!One of the given pointers is NULL*/
+    CARDANO_RESULT_NULL_POINTER = 2,
+    /*!The library panicked, this is a bug*/
+    CARDANO_RESULT_PANIC = 3,
+    /*!One of the given parameters is out of its domain*/
+    CARDANO_RESULT_INVALID_ARGUMENT = 4,
+    /*!The string is not a valid amount of ADA or Lovelace*/
+    CARDANO_RESULT_INVALID_AMOUNT = 5,
+    /*!The bytes are not a valid serialization of the object*/
+    CARDANO_RESULT_INVALID_SERIALIZATION = 6,
+
+    /*!The string is not valid base58*/
+    CARDANO_RESULT_INVALID_BASE58 = 10,
+    /*!The bytes are not a valid address*/
+    CARDANO_RESULT_INVALID_ADDRESS = 11,
+    /*!The bytes are not a valid extended private key*/
+    CARDANO_RESULT_INVALID_KEY = 12,
+    /*!The key cannot be derived with the given index*/
+    CARDANO_RESULT_INVALID_DERIVATION = 13,
+    /*!The address does not contain a derivation path*/
+    CARDANO_RESULT_NO_DERIVATION_PATH = 14,
+    /*!The derivation path cannot be decrypted with the given HD passphrase*/
+    CARDANO_RESULT_INVALID_HDPASSPHRASE = 15,
+    /*!The string is not valid bech32 or its checksum is incorrect*/
+    CARDANO_RESULT_INVALID_BECH32 = 16,
+
+    /*!A word is not in the dictionary*/
+    CARDANO_RESULT_BIP39_INVALID_WORD = 20,
+    /*!The mnemonic phrase checksum is incorrect*/
+    CARDANO_RESULT_BIP39_INVALID_CHECKSUM = 21,
+    /*!The number of words is not 9, 12, 15, 18, 21 or 24*/
+    CARDANO_RESULT_BIP39_INVALID_WORD_COUNT = 22,
+    /*!The entropy is not 12, 16, 20, 24, 28 or 32 bytes*/
+    CARDANO_RESULT_BIP39_INVALID_ENTROPY_SIZE = 23,
+
+    CARDANO_RESULT_TRANSACTION_NO_OUTPUT = 30,
+    CARDANO_RESULT_TRANSACTION_NO_INPUT = 31,
+    /*!The number of signatures doesn't match the number of inputs*/
+    CARDANO_RESULT_TRANSACTION_SIGNATURE_MISMATCH = 32,
+    /*!The transaction is too big*/
+    CARDANO_RESULT_TRANSACTION_OVER_LIMIT = 33,
+    /*!The number of signatures is greater than the number of inputs*/
+    CARDANO_RESULT_TRANSACTION_SIGNATURES_EXCEEDED = 34,
+    /*!The given value is greater than the maximum allowed coin value*/
+    CARDANO_RESULT_TRANSACTION_COIN_OUT_OF_BOUNDS = 35,
+    /*!The fee cannot be computed with the given fee algorithm*/
+    CARDANO_RESULT_TRANSACTION_FEE_ERROR = 36,
+    /*!The inputs do not cover the outputs and the fee*/
+    CARDANO_RESULT_TRANSACTION_NOT_ENOUGH_INPUT = 37,
+    /*!The leftover of the inputs cannot cover the fee of the change output*/
+    CARDANO_RESULT_TRANSACTION_OUTPUT_POLICY_NOT_ENOUGH_COINS = 38,
+    /*!The witness does not match the address of the spent output or does not sign the transaction*/
+    CARDANO_RESULT_TRANSACTION_INVALID_WITNESS = 39,
+
+    /*!The alias is already used by an account of the wallet*/
+    CARDANO_RESULT_ACCOUNT_ALIAS_TAKEN = 40,
+    /*!The index is not a valid BIP44 account index*/
+    CARDANO_RESULT_INVALID_ACCOUNT_INDEX = 41,
+    /*!There is no account with the given alias*/
+    CARDANO_RESULT_ACCOUNT_NOT_FOUND = 42
 } cardano_result;
 
+/*!
+* \brief Description of the error of the last failing call from the calling thread
+* \returns a string owned by the library, valid until the next failing call from the
+* same thread, or NULL if no call failed yet
+*/
+const char *cardano_error_message(void);
+
+/***********/
+/* Context */
+/***********/
+
+/*!
+* Context created once by the host and given to every function creating an
+* object, carrying the allocator, the logging callback and the random
+* generator of the host.
+*/
+typedef struct cardano_context cardano_context;
+
+/*!
+* \brief allocate `size` bytes aligned on `align`
+* \returns the allocated memory, or NULL on failure
+*/
+typedef void *(*cardano_alloc_fn)(size_t size, size_t align, void *user_data);
+
+/*!
+* \brief free the `size` bytes aligned on `align` returned by the `cardano_alloc_fn`
+*/
+typedef void (*cardano_free_fn)(void *ptr, size_t size, size_t align, void *user_data);
+
+/*!
+* \brief the levels of the diagnostics given to the logging callback
+*/
+typedef enum _cardano_log_level
+{
+    CARDANO_LOG_LEVEL_ERROR = 0,
+    CARDANO_LOG_LEVEL_WARNING = 1,
+    CARDANO_LOG_LEVEL_INFO = 2,
+    CARDANO_LOG_LEVEL_DEBUG = 3
+} cardano_log_level;
+
+/*!
+* \brief receive a diagnostic of the library, the message is valid during the call only
+*/
+typedef void (*cardano_log_fn)(cardano_log_level level, const char *message, void *user_data);
+
+/*!
+* \brief fill the `size` bytes with random bytes
+* \returns 0 on success
+*/
+typedef int (*cardano_rng_fn)(uint8_t *bytes, size_t size, void *user_data);
+
+/*!
+* \brief the callbacks of the host, each one can be NULL
+*
+* `alloc` and `free` are given together or not at all. Without a random
+* generator, the one of the operating system is used. `user_data` is given
+* back to every callback.
+*/
+typedef struct _cardano_context_config
+{
+    cardano_alloc_fn alloc;
+    cardano_free_fn free;
+    cardano_log_fn log;
+    cardano_rng_fn rng;
+    void *user_data;
+} cardano_context_config;
+
+/*!
+* \brief create a context with the callbacks of the host
+*
+* The allocator is global to the library: all the memory allocated by the
+* library afterwards comes from the allocator of the first context given
+* one, and a context with a different allocator cannot be created.
+*
+* \param [in] config the callbacks of the host, or NULL for the defaults
+* \param [out] context the new context, use `cardano_context_delete` to release the memory
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_ARGUMENT if only one of `alloc` and
+* `free` is given or if a different allocator is already used
+*/
+cardano_result cardano_context_new(const cardano_context_config *config, cardano_context **context);
+
+/*!
+* \brief free the context, the objects created with it stay valid
+*/
+void cardano_context_delete(cardano_context *context);
+
+/*****************/
+/* Serialization */
+/*****************/
+
+/*!
+* The `_to_bytes` functions give the serialization of an object in a buffer
+* starting with the size of the serialization, as a 4 bytes big endian
+* integer, followed by the serialization. The `_from_bytes` functions take
+* the serialization without the size.
+*
+* The keys are serialized as their raw bytes, the other objects in CBOR.
+*/
+#define CARDANO_BUFFER_PREFIX_SIZE 4
+
+/*!
+* \brief free a buffer returned by one of the `_to_bytes` functions
+*/
+void cardano_buffer_free(uint8_t *buffer);
+
 /*********/
 /* BIP39 */
 /*********/
 
-/* bip39 error definitions */
-typedef enum _bip39_config_error
-{
-    BIP39_SUCCESS = 0,
-    BIP39_INVALID_MNEMONIC = 1,
-    BIP39_INVALID_CHECKSUM = 2,
-    BIP39_INVALID_WORD_COUNT = 3
-} cardano_bip39_error_t;
-
 typedef uint8_t* cardano_entropy;
 
+/*!
+* \brief the languages of the BIP39 dictionaries
+*/
+typedef enum _bip39_language
+{
+    CARDANO_BIP39_LANGUAGE_ENGLISH = 0,
+    CARDANO_BIP39_LANGUAGE_JAPANESE = 1,
+    CARDANO_BIP39_LANGUAGE_KOREAN = 2,
+    CARDANO_BIP39_LANGUAGE_SPANISH = 3,
+    CARDANO_BIP39_LANGUAGE_CHINESE_SIMPLIFIED = 4,
+    CARDANO_BIP39_LANGUAGE_CHINESE_TRADITIONAL = 5,
+    CARDANO_BIP39_LANGUAGE_FRENCH = 6,
+    CARDANO_BIP39_LANGUAGE_ITALIAN = 7
+} cardano_bip39_language;
+
 /*!
 * \brief get entropy array from the given english mnemonics 
 * \param [in] mnemonics a string consisting of 9, 12, 15, 18, 21 or 24 english words
 * \param [out] entropy the returned entropy array, use `cardano_delete_entropy_array` to release the memory
 * \param [out] entropy_size the size of the the returned array
 * \sa cardano_delete_entropy_array()
-* \returns BIP39_SUCCESS or either BIP39_INVALID_MNEMONIC or BIP39_INVALID_CHECKSUM 
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_BIP39_INVALID_WORD | CARDANO_RESULT_BIP39_INVALID_CHECKSUM
 */
-cardano_bip39_error_t cardano_entropy_from_english_mnemonics(
+cardano_result cardano_entropy_from_english_mnemonics(
     const char *mnemonics,
     cardano_entropy *entropy,
     uint32_t *entropy_size
 );
 
+/*!
+* \brief get entropy array from the given mnemonics in the given language
+* \param [in] mnemonics a UTF-8 NFKD normalized string of 9, 12, 15, 18, 21 or 24 words separated with a space
+* \param [in] language the dictionary of the words
+* \param [out] entropy the returned entropy array, use `cardano_delete_entropy_array` to release the memory
+* \param [out] entropy_size the size of the the returned array
+* \sa cardano_delete_entropy_array()
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_BIP39_INVALID_WORD | CARDANO_RESULT_BIP39_INVALID_CHECKSUM |
+* CARDANO_RESULT_INVALID_ARGUMENT if the language is unknown
+*/
+cardano_result cardano_bip39_mnemonics_to_entropy(
+    const char *mnemonics,
+    cardano_bip39_language language,
+    cardano_entropy *entropy,
+    uint32_t *entropy_size
+);
+
+/*!
+* \brief generate the mnemonic phrase of the given entropy, for example from the random generator of the host
+* \param [in] entropy_raw A pointer to a byte array of either 12, 16, 20, 24, 28 or 32 bytes
+* \param [in] entropy_size the size of the entropy array
+* \param [in] language the dictionary of the words
+* \param [out] mnemonics the returned UTF-8 NFKD normalized phrase, the words are separated with a space,
+* use `cardano_bip39_delete_mnemonics` to release the memory
+* \sa cardano_bip39_delete_mnemonics()
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_BIP39_INVALID_ENTROPY_SIZE |
+* CARDANO_RESULT_INVALID_ARGUMENT if the language is unknown
+*/
+cardano_result cardano_bip39_generate_from_entropy(
+    const uint8_t * const entropy_raw,
+    unsigned long entropy_size,
+    cardano_bip39_language language,
+    char **mnemonics
+);
+
+/*!
+* delete the mnemonic phrase, zeroing its memory
+* \param [in] mnemonics the phrase returned by `cardano_bip39_generate_from_entropy`
+* \sa cardano_bip39_generate_from_entropy()
+*/
+void cardano_bip39_delete_mnemonics(char *mnemonics);
+
 /*!
 * \brief encode a entropy into its equivalent words represented by their index (0 to 2047) in the BIP39 dictionary
+* \param [in] ctx the context whose random generator makes the entropy
 * \param [in] number_of_words one of 9, 12, 15, 18, 21 or 24 representing the number of words of the equivalent mnemonic
-* \param [in] random_generator a function that generates random bytes  
 * \param [out] entropy the returned entropy array
 * \param [out] entropy_size the size of the the returned array
-* \returns BIP39_SUCCESS or BIP39_INVALID_WORD_COUNT 
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_BIP39_INVALID_WORD_COUNT
 */
-cardano_bip39_error_t cardano_entropy_from_random(
+cardano_result cardano_entropy_from_random(
+    cardano_context *ctx,
     uint8_t number_of_words,
-    uint8_t (*random_generator)(),
     cardano_entropy *entropy,
     uint32_t *entropy_size
 );
@@ -68,6 +288,7 @@ cardano_bip39_error_t cardano_entropy_from_random(
 * \param [in] entropy_size the length of the entropy array
 * \sa cardano_entropy_from_random()
 * \sa cardano_entropy_from_english_mnemonics()
+* \sa cardano_bip39_mnemonics_to_entropy()
 */
 void cardano_delete_entropy_array(uint8_t *entropy, uint32_t entropy_size);
 
@@ -77,7 +298,8 @@ void cardano_delete_entropy_array(uint8_t *entropy, uint32_t entropy_size);
 * \param [in] entropy_size of the entropy array
 * \param [out] mnemonic_index the indexes of the encoded words  
 * \param [in] mnemonic_size the number of encoded words 
-* \returns success or failure 
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_BIP39_INVALID_ENTROPY_SIZE |
+* CARDANO_RESULT_INVALID_ARGUMENT if mnemonic_size is not the number of encoded words
 */
 cardano_result cardano_bip39_encode(const char * const entropy_raw, unsigned long entropy_size, unsigned short *mnemonic_index, unsigned long mnemonic_size);
 
@@ -100,6 +322,12 @@ cardano_result cardano_bip39_encode(const char * const entropy_raw, unsigned lon
 */
 typedef struct cardano_xprv cardano_xprv;
 
+/*!
+* Size of cardano_xpub
+* Public key (32 bytes) followed by a chain code (32 bytes)
+*/
+#define XPUB_SIZE 64
+
 /*!
 * Extended Public Key (Point + ChainCode)
 */
@@ -108,35 +336,87 @@ typedef struct cardano_xpub cardano_xpub;
 /*!
 * Free the associated memory
 */
-cardano_xpub *cardano_xprv_delete(cardano_xprv *privkey);
+void cardano_xprv_delete(cardano_xprv *privkey);
 
 /*!
-* Get the associated cardano_xpub
+* Get the associated cardano_xpub, to free with `cardano_xpub_delete`
 */
-cardano_xpub *cardano_xprv_to_xpub(cardano_xprv *privkey);
+cardano_result cardano_xprv_to_xpub(cardano_context *ctx, cardano_xprv *privkey, cardano_xpub **xpub);
 
 /*!
-* Get the bytes representation of cardano_xprv
-* \sa cardano_xprv_bytes_delete
+* \brief the XPRV_SIZE bytes of the cardano_xprv
+* \param [out] buffer the length-prefixed bytes, to free with `cardano_buffer_free`
 */
-uint8_t *cardano_xprv_to_bytes(cardano_xprv *privkey);
+cardano_result cardano_xprv_to_bytes(cardano_xprv *privkey, uint8_t **buffer);
 
 /*!
-* Free the memory allocated with `cardano_xprv_to_bytes`
+* \brief Construct cardano_xprv from the given XPRV_SIZE bytes
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_SERIALIZATION if there are not XPRV_SIZE bytes |
+* CARDANO_RESULT_INVALID_KEY if the representation is invalid
+* \sa cardano_xprv_delete
 */
-void cardano_xprv_bytes_delete(uint8_t  *bytes);
+cardano_result cardano_xprv_from_bytes(cardano_context *ctx, const uint8_t *bytes, size_t bytes_size, cardano_xprv **xprv_out);
 
 /*!
-* \brief Construct cardano_xprv from the given bytes
-* \returns 1 if the representation is invalid 0 otherwise
-* \sa cardano_xprv_delete
+* Derive the child key of the given index (hardened if index >= 0x80000000)
 */
-cardano_result cardano_xprv_from_bytes(uint8_t *bytes, cardano_xprv **xprv_out);
+cardano_result cardano_xprv_derive(cardano_context *ctx, cardano_xprv *privkey, uint32_t index, cardano_xprv **child);
+
+/*!
+* \brief Derive the child key of the given (soft) index
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_DERIVATION if the index is hardened
+*/
+cardano_result cardano_xpub_derive(cardano_context *ctx, cardano_xpub *pubkey, uint32_t index, cardano_xpub **child);
 
 /*!
 * Free the associated memory
 */
-cardano_xpub *cardano_xpub_delete(cardano_xpub *pubkey);
+void cardano_xpub_delete(cardano_xpub *pubkey);
+
+/*!
+* \brief the serialization of the public key, its XPUB_SIZE bytes
+* \param [out] buffer the length-prefixed serialization, to free with `cardano_buffer_free`
+*/
+cardano_result cardano_xpub_to_bytes(cardano_xpub *pubkey, uint8_t **buffer);
+
+/*!
+* \brief decode the public key from its serialization, without the length prefix
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_SERIALIZATION
+*/
+cardano_result cardano_xpub_from_bytes(cardano_context *ctx, const uint8_t *bytes, size_t bytes_size, cardano_xpub **pubkey);
+
+/*!
+* Size of the signature of a message
+*/
+#define SIGNATURE_SIZE 64
+
+/*!
+* \brief Sign the message with the private key, to prove its ownership without exporting it
+* \param [in] privkey the private key
+* \param [in] message the message to sign
+* \param [in] message_size the size of the message
+* \param [out] signature the SIGNATURE_SIZE bytes of the signature
+* \returns CARDANO_RESULT_SUCCESS
+* \sa cardano_key_verify_message()
+*/
+cardano_result cardano_key_sign_message(cardano_xprv *privkey,
+                                        const uint8_t *message, unsigned long message_size,
+                                        uint8_t *signature);
+
+/*!
+* \brief Verify the signature of the message with the public key
+* \param [in] pubkey the public key of the signer
+* \param [in] message the signed message
+* \param [in] message_size the size of the message
+* \param [in] signature the SIGNATURE_SIZE bytes of the signature
+* \param [out] valid set to 1 if the signature is valid, 0 otherwise
+* \returns CARDANO_RESULT_SUCCESS
+* \sa cardano_key_sign_message()
+*/
+cardano_result cardano_key_verify_message(cardano_xpub *pubkey,
+                                          const uint8_t *message, unsigned long message_size,
+                                          const uint8_t *signature,
+                                          int *valid);
 
 /*************/
 /* addresses */
@@ -144,15 +424,91 @@ cardano_xpub *cardano_xpub_delete(cardano_xpub *pubkey);
 
 typedef struct cardano_address cardano_address;
 
+/*!
+* Type of the spending data of an address
+*/
+typedef enum _cardano_address_kind {
+    CARDANO_ADDRESS_KIND_PUBKEY = 0,
+    CARDANO_ADDRESS_KIND_SCRIPT = 1,
+    CARDANO_ADDRESS_KIND_REDEEM = 2
+} cardano_address_kind;
+
+/*!
+* Size of the HD passphrase encrypting the derivation path of the addresses
+*/
+#define HDPASSPHRASE_SIZE 32
+
 /*! check if an address is a valid protocol address.
- * return 0 on success, !0 on failure. */
-int cardano_address_is_valid(const char * address_base58);
+ * \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_BASE58 | CARDANO_RESULT_INVALID_ADDRESS */
+cardano_result cardano_address_is_valid(const char * address_base58);
 
-cardano_address *cardano_address_new_from_pubkey(cardano_xpub *publickey);
+/*!
+* Create the address of the given public key for the network of the given protocol magic,
+* to free with `cardano_address_delete`
+*/
+cardano_result cardano_address_new_from_pubkey(cardano_context *ctx, cardano_xpub *publickey, uint32_t protocol_magic, cardano_address **address);
 void cardano_address_delete(cardano_address *address);
 
-char *cardano_address_export_base58(cardano_address *address);
-cardano_address *cardano_address_import_base58(const char * address_bytes);
+/*!
+* \brief the serialization of the address, its binary (CBOR) serialization, the one encoded in base58
+* \param [out] buffer the length-prefixed serialization, to free with `cardano_buffer_free`
+*/
+cardano_result cardano_address_to_bytes(cardano_address *address, uint8_t **buffer);
+
+/*!
+* \brief decode the address from its serialization, without the length prefix
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_SERIALIZATION
+*/
+cardano_result cardano_address_from_bytes(cardano_context *ctx, const uint8_t *bytes, size_t bytes_size, cardano_address **address);
+
+/*!
+* Encode the address in base58, the string is allocated by the library
+*/
+cardano_result cardano_address_export_base58(cardano_address *address, char **address_base58);
+
+/*!
+* \brief Parse a base58 address, to free with `cardano_address_delete`
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_BASE58 | CARDANO_RESULT_INVALID_ADDRESS
+*/
+cardano_result cardano_address_import_base58(cardano_context *ctx, const char * address_base58, cardano_address **address);
+
+/*!
+* \brief Get the type of the spending data of the address
+* \param [out] kind the address is a public key, script or redeem address
+*/
+cardano_result cardano_address_get_kind(cardano_address *address, cardano_address_kind *kind);
+
+/*!
+* \brief Get the protocol magic of the network the address is for
+* \param [in] address the address
+* \param [out] has_protocol_magic 0 if the address does not contain a protocol magic, as the mainnet addresses, 1 otherwise
+* \param [out] protocol_magic the protocol magic, written only if the address contains one
+*/
+cardano_result cardano_address_get_network(cardano_address *address, int *has_protocol_magic, uint32_t *protocol_magic);
+
+/*!
+* \brief Compute the HD passphrase of the wallet of the given root public key
+* \param [in] root_xpub the root public key of the wallet
+* \param [out] hdpassphrase HDPASSPHRASE_SIZE bytes
+*/
+cardano_result cardano_xpub_to_hdpassphrase(cardano_xpub *root_xpub, uint8_t *hdpassphrase);
+
+/*!
+* \brief Decrypt the derivation path of the address
+* \param [in] address the address
+* \param [in] hdpassphrase the HDPASSPHRASE_SIZE bytes HD passphrase of the wallet of the address
+* \param [out] path the derivation indices, use `cardano_address_delete_derivation_path` to release the memory
+* \param [out] path_size the number of indices
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_NO_DERIVATION_PATH if the address has no derivation path,
+* as the BIP44 addresses | CARDANO_RESULT_INVALID_HDPASSPHRASE if it cannot be decrypted with the passphrase
+* \sa cardano_address_delete_derivation_path()
+*/
+cardano_result cardano_address_get_derivation_path(cardano_address *address, const uint8_t *hdpassphrase, uint32_t **path, unsigned long *path_size);
+
+/*!
+* Free the derivation path returned by `cardano_address_get_derivation_path`
+*/
+void cardano_address_delete_derivation_path(uint32_t *path, unsigned long path_size);
 
 /***********/
 /* Wallet  */
@@ -160,6 +516,10 @@ cardano_address *cardano_address_import_base58(const char * address_bytes);
 
 /*!
 * HD BIP44 compliant wallet
+*
+* The wallet can be used from several threads: the calls are serialized.
+* `cardano_wallet_clone_handle` gives another handle to the wallet, so each
+* thread can own and free its own handle.
 */
 typedef struct cardano_wallet cardano_wallet;
 typedef struct cardano_account cardano_account;
@@ -172,16 +532,25 @@ typedef struct cardano_account cardano_account;
 * \param [in] password_ptr  A string with the password
 * \param [in] password_size The size of the password string
 * \param [out] wallet pointer to the created cardano_wallet that must be freed with `cardano_wallet_delete`
-* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_ERROR if the entropy is of an invalid size
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_BIP39_INVALID_ENTROPY_SIZE
 */
-cardano_result cardano_wallet_new(const uint8_t * const entropy_ptr, unsigned long entropy_size,
+cardano_result cardano_wallet_new(cardano_context *ctx, const uint8_t * const entropy_ptr, unsigned long entropy_size,
                                    const char * const password_ptr, unsigned long password_size,
                                    cardano_wallet** wallet);
 /*!
-* Free the memory of a wallet allocated with `cardano_wallet_new`
+* Free a handle of a wallet allocated with `cardano_wallet_new` or
+* `cardano_wallet_clone_handle`, the wallet is freed with its last handle
 */
 void cardano_wallet_delete(cardano_wallet *);
 
+/*!
+* \brief create another handle to the same wallet
+* \param [in] wallet the wallet to share
+* \param [out] clone the new handle, to free with `cardano_wallet_delete`
+* \returns CARDANO_RESULT_SUCCESS
+*/
+cardano_result cardano_wallet_clone_handle(cardano_context *ctx, cardano_wallet *wallet, cardano_wallet **clone);
+
 /*!
 * \brief Create a new account, the account is given an alias and an index.
 *
@@ -193,9 +562,9 @@ void cardano_wallet_delete(cardano_wallet *);
 * \param [in] wallet A pointer to a wallet created with `cardano_wallet_new` 
 * \param [in] alias A C string that can be used to retrieve an account from a wallet
 * \param [in] index The derivation key 
-* \returns pointer to the created account that must be freed with `cardano_account_delete` 
+* \param [out] account the created account that must be freed with `cardano_account_delete`
 */
-cardano_account *cardano_account_create(cardano_wallet *wallet, const char *alias, unsigned int index);
+cardano_result cardano_account_create(cardano_context *ctx, cardano_wallet *wallet, const char *alias, unsigned int index, cardano_account **account);
 
 /*!
 * Free the memory allocated with `cardano_account_create`
@@ -203,6 +572,18 @@ cardano_account *cardano_account_create(cardano_wallet *wallet, const char *alia
 */
 void cardano_account_delete(cardano_account *account);
 
+/*!
+* \brief the serialization of the account, the CBOR array of the version of its derivation scheme and of its public key
+* \param [out] buffer the length-prefixed serialization, to free with `cardano_buffer_free`
+*/
+cardano_result cardano_account_to_bytes(cardano_account *account, uint8_t **buffer);
+
+/*!
+* \brief decode the account from its serialization, without the length prefix
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_SERIALIZATION
+*/
+cardano_result cardano_account_from_bytes(cardano_context *ctx, const uint8_t *bytes, size_t bytes_size, cardano_account **account);
+
 /*!
 * \brief Generate addressess
 * The generated addresses are C strings in base58
@@ -211,33 +592,192 @@ void cardano_account_delete(cardano_account *account);
 * \param [in] from_index  
 * \param [in] num_indices
 * \param [out] addresses_ptr array of strings consisting of the base58 representation of the addresses
-* \returns the number of generated addresses
+* \param [in] protocol_magic the protocol magic of the network the addresses are for
+* \param [out] num_addresses the number of generated addresses, lower than num_indices only
+* if the derivation indices would go past the soft derivation indices
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_DERIVATION
 * \sa cardano_address_import_base58()
-* \sa cardano_address_delete() 
+* \sa cardano_account_delete_addresses()
 */
-unsigned long cardano_account_generate_addresses(cardano_account *account, int internal, unsigned int from_index, unsigned long num_indices, char *addresses_ptr[]);
+cardano_result cardano_account_generate_addresses(cardano_account *account, int internal, unsigned int from_index, unsigned long num_indices, char *addresses_ptr[], uint32_t protocol_magic, unsigned long *num_addresses);
 void cardano_account_delete_addresses(char *addresses_ptr[], unsigned long length);
 
+/*!
+* \brief Derive a batch of addresses of the account in one call
+* The derived addresses are C strings in base58, in an array allocated by the library
+* \param [in] account an account created with `cardano_account_create`
+* \param [in] chain the BIP44 chain: 0 for the external addresses, 1 for the internal ones
+* \param [in] from_index the index of the first address
+* \param [in] count the number of addresses to derive
+* \param [in] protocol_magic the protocol magic of the network the addresses are for
+* \param [out] addresses the array of the addresses, to free with `cardano_wallet_addresses_delete`
+* \param [out] num_addresses the number of derived addresses, lower than count only
+* if the derivation indices would go past the soft derivation indices
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_DERIVATION | CARDANO_RESULT_INVALID_ARGUMENT
+* \sa cardano_wallet_addresses_delete()
+*/
+cardano_result cardano_wallet_addresses_derive(cardano_account *account, uint32_t chain, uint32_t from_index, unsigned long count, uint32_t protocol_magic, char ***addresses, unsigned long *num_addresses);
+
+/*!
+* Free the array of addresses returned by `cardano_wallet_addresses_derive`
+*/
+void cardano_wallet_addresses_delete(char **addresses, unsigned long num_addresses);
+
+/*!
+* \brief Create a new account and register it in the wallet
+*
+* Unlike `cardano_account_create`, the alias must not be used by another account
+* of the wallet and the index must be a valid BIP44 account index (lower than 2^31).
+*
+* \param [in] wallet A pointer to a wallet created with `cardano_wallet_new`
+* \param [in] alias A C string to retrieve the account from the wallet
+* \param [in] index The derivation index of the account
+* \param [out] account the created account, to free with `cardano_account_delete`
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_ACCOUNT_ALIAS_TAKEN | CARDANO_RESULT_INVALID_ACCOUNT_INDEX
+* \sa cardano_wallet_account_get()
+*/
+cardano_result cardano_wallet_account_create(cardano_context *ctx, cardano_wallet *wallet, const char *alias, unsigned int index, cardano_account **account);
+
+/*!
+* \brief Retrieve an account registered in the wallet
+* \param [out] account the account, to free with `cardano_account_delete`
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_ACCOUNT_NOT_FOUND if there is no account with this alias
+*/
+cardano_result cardano_wallet_account_get(cardano_context *ctx, cardano_wallet *wallet, const char *alias, cardano_account **account);
+
+/*!
+* \brief The number of accounts registered in the wallet
+*/
+cardano_result cardano_wallet_account_count(cardano_wallet *wallet, unsigned long *count);
+
+/*!
+* \brief List the aliases of the accounts registered in the wallet, sorted
+* \param [in] wallet A pointer to a wallet created with `cardano_wallet_new`
+* \param [out] aliases_ptr array of at least `size` strings
+* \param [in] size the maximum number of aliases to write
+* \param [out] num_aliases the number of written aliases
+* \sa cardano_wallet_account_count()
+* \sa cardano_wallet_account_list_delete()
+*/
+cardano_result cardano_wallet_account_list(cardano_wallet *wallet, char *aliases_ptr[], unsigned long size, unsigned long *num_aliases);
+
+/*!
+* Free the aliases returned by `cardano_wallet_account_list`
+*/
+void cardano_wallet_account_list_delete(char *aliases_ptr[], unsigned long size);
+
+/*!
+* \brief Discover the used accounts of the wallet (BIP44 account discovery)
+*
+* Accounts are scanned in order from index 0: the external addresses of an account
+* are derived until `gap_limit` consecutive addresses are not in `used_addresses`.
+* The discovery stops at the first account without any used address.
+*
+* Every discovered account is registered in the wallet with its index in decimal
+* as alias ("0", "1", ...).
+*
+* \param [in] wallet A pointer to a wallet created with `cardano_wallet_new`
+* \param [in] used_addresses the base58 addresses known to be used, e.g. from the blockchain
+* \param [in] num_used_addresses the number of used addresses
+* \param [in] gap_limit the number of consecutive unused addresses ending the scan of an account (20 in BIP44)
+* \param [in] protocol_magic the protocol magic of the network of the addresses
+* \param [out] num_accounts the number of discovered accounts
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_BASE58 | CARDANO_RESULT_INVALID_ADDRESS if an address is invalid
+* | CARDANO_RESULT_INVALID_ARGUMENT if gap_limit is 0
+*/
+cardano_result cardano_wallet_account_discover(cardano_wallet *wallet,
+                                               const char * const used_addresses[], unsigned long num_used_addresses,
+                                               uint32_t gap_limit, uint32_t protocol_magic,
+                                               uint32_t *num_accounts);
+
+/*******************/
+/* Daedalus wallet */
+/*******************/
+
+/*!
+* Daedalus wallet, with 2 levels of randomly chosen hard derivation indexes
+*
+* Like `cardano_wallet`, it can be used from several threads.
+*/
+typedef struct cardano_daedalus_wallet cardano_daedalus_wallet;
+
+/*!
+* \brief Retrieve the entropy of the wallet of a Daedalus paper wallet
+*
+* The 27 english words of a paper wallet are the 18 words of the scrambled entropy
+* followed by the 9 words of the passphrase it is scrambled with.
+*
+* \param [in] mnemonics the 27 words of the paper wallet, separated with a space
+* \param [out] entropy the entropy of the 12 words of the wallet, use `cardano_delete_entropy_array` to release the memory
+* \param [out] entropy_size the size of the the returned array
+* \sa cardano_daedalus_wallet_new()
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_BIP39_INVALID_WORD_COUNT if there are not 27 words |
+* CARDANO_RESULT_BIP39_INVALID_WORD | CARDANO_RESULT_BIP39_INVALID_CHECKSUM
+*/
+cardano_result cardano_paper_wallet_unscramble(
+    const char *mnemonics,
+    cardano_entropy *entropy,
+    uint32_t *entropy_size
+);
+
+/*!
+* Create a Daedalus wallet from the entropy of its mnemonics
+* \param [in] entropy_ptr A pointer to a uint8_t array of either 12, 16, 20, 24, 28 or 32 bytes
+* \param [in] entropy_size The size of the entropy array
+* \param [out] wallet the created wallet that must be freed with `cardano_daedalus_wallet_delete`
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_BIP39_INVALID_ENTROPY_SIZE
+*/
+cardano_result cardano_daedalus_wallet_new(cardano_context *ctx, const uint8_t * const entropy_ptr, unsigned long entropy_size,
+                                           cardano_daedalus_wallet **wallet);
+
+/*!
+* Free a handle of a wallet allocated with `cardano_daedalus_wallet_new` or
+* `cardano_daedalus_wallet_clone_handle`, the wallet is freed with its last handle
+*/
+void cardano_daedalus_wallet_delete(cardano_daedalus_wallet *wallet);
+
+/*!
+* \brief create another handle to the same Daedalus wallet
+* \param [in] wallet the wallet to share
+* \param [out] clone the new handle, to free with `cardano_daedalus_wallet_delete`
+* \returns CARDANO_RESULT_SUCCESS
+*/
+cardano_result cardano_daedalus_wallet_clone_handle(cardano_context *ctx, cardano_daedalus_wallet *wallet,
+                                                    cardano_daedalus_wallet **clone);
+
+/*!
+* \brief Generate the address of the wallet at the given account and address indexes
+* \param [out] address the generated address that must be freed with `cardano_address_delete`
+* \returns CARDANO_RESULT_SUCCESS
+*/
+cardano_result cardano_daedalus_wallet_generate_address(cardano_context *ctx, cardano_daedalus_wallet *wallet,
+                                                        uint32_t account_index, uint32_t address_index,
+                                                        uint32_t protocol_magic,
+                                                        cardano_address **address);
+
+/*!
+* \brief Test whether the address belongs to the wallet
+* \param [out] is_ours set to 1 if the address belongs to the wallet, 0 otherwise
+* \param [out] account_index the account index of the address, if it belongs to the wallet
+* \param [out] address_index the address index of the address, if it belongs to the wallet
+* \returns CARDANO_RESULT_SUCCESS
+*/
+cardano_result cardano_daedalus_wallet_check_address(cardano_daedalus_wallet *wallet,
+                                                     cardano_address *address,
+                                                     int *is_ours,
+                                                     uint32_t *account_index,
+                                                     uint32_t *address_index);
+
 /****************/
 /* Transactions */
 /****************/
 
-/* transaction error definitions */
-typedef enum _transaction_config_error
-{
-    CARDANO_TRANSACTION_SUCCESS = 0,
-    CARDANO_TRANSACTION_NO_OUTPUT = 1,
-    CARDANO_TRANSACTION_NO_INPUT = 2,
-    /*!The number of signatures doesn't match the number of inputs*/
-    CARDANO_TRANSACTION_SIGNATURE_MISMATCH = 3,
-    /*!The transaction is too big*/
-    CARDANO_TRANSACTION_OVER_LIMIT = 4,
-    /*!The number of signatures is greater than the number of inputs*/
-    CARDANO_TRANSACTION_SIGNATURES_EXCEEDED = 5,
-    /*!The given value is greater than the maximum allowed coin value*/
-    CARDANO_TRANSACTION_COIN_OUT_OF_BOUNDS = 6,
-} cardano_transaction_error_t;
-
+/*!
+* Builder of a transaction
+*
+* The builder can be used from several threads: the calls are serialized.
+* `cardano_transaction_builder_clone_handle` gives another handle to the builder.
+*/
 typedef struct cardano_transaction_builder cardano_transaction_builder;
 typedef struct cardano_transaction_finalized cardano_transaction_finalized;
 /*!
@@ -253,33 +793,75 @@ typedef struct cardano_txoutput cardano_txoutput;
 typedef struct cardano_transaction cardano_transaction;
 typedef struct cardano_signed_transaction cardano_signed_transaction;
 
+/*!
+* Linear fee algorithm: `constant + coefficient * size`, where size is the
+* number of bytes of the signed transaction
+* \sa cardano_fee_linear_new()
+*/
+typedef struct cardano_linear_fee cardano_linear_fee;
+
 /*!
 * Create object used for addressing a specific output of a transaction built from a TxId (hash of the tx) and the offset in the outputs of this transaction.
 * The memory must be freed with cardano_transaction_output_ptr_delete
 * \sa cardano_transaction_output_ptr_delete()
 */
-cardano_txoptr * cardano_transaction_output_ptr_new(uint8_t txid[32], uint32_t index);
+cardano_result cardano_transaction_output_ptr_new(cardano_context *ctx, uint8_t txid[32], uint32_t index, cardano_txoptr **txo);
 
 /*!
 * Free the memory allocated with `cardano_transaction_output_ptr_new`
 */
 void cardano_transaction_output_ptr_delete(cardano_txoptr *txo);
 
+/*!
+* \brief the serialization of the transaction output pointer, its CBOR serialization
+* \param [out] buffer the length-prefixed serialization, to free with `cardano_buffer_free`
+*/
+cardano_result cardano_transaction_output_ptr_to_bytes(cardano_txoptr *txo, uint8_t **buffer);
+
+/*!
+* \brief decode the transaction output pointer from its serialization, without the length prefix
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_SERIALIZATION
+*/
+cardano_result cardano_transaction_output_ptr_from_bytes(cardano_context *ctx, const uint8_t *bytes, size_t bytes_size, cardano_txoptr **txo);
+
 /*!
 * Create output for a transaction 
 * The memory must be freed with `cardano_transaction_output_delete`
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_COIN_OUT_OF_BOUNDS
 * \sa cardano_transaction_output_delete()
 */
-cardano_txoutput * cardano_transaction_output_new(cardano_address *c_addr, uint64_t value);
+cardano_result cardano_transaction_output_new(cardano_context *ctx, cardano_address *c_addr, uint64_t value, cardano_txoutput **output);
+
+/*!
+* Get a copy of the address of the output, to free with `cardano_address_delete`
+*/
+cardano_result cardano_transaction_output_get_address(cardano_context *ctx, cardano_txoutput *output, cardano_address **address);
+
+/*!
+* Get the value of the output, in Lovelace
+*/
+cardano_result cardano_transaction_output_get_value(cardano_txoutput *output, uint64_t *value);
 
 /*!
 * Free the memory allocated with `cardano_transaction_output_delete`
 */
 void cardano_transaction_output_delete(cardano_txoutput *output);
 
+/*!
+* \brief the serialization of the transaction output, its CBOR serialization
+* \param [out] buffer the length-prefixed serialization, to free with `cardano_buffer_free`
+*/
+cardano_result cardano_transaction_output_to_bytes(cardano_txoutput *output, uint8_t **buffer);
+
+/*!
+* \brief decode the transaction output from its serialization, without the length prefix
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_SERIALIZATION
+*/
+cardano_result cardano_transaction_output_from_bytes(cardano_context *ctx, const uint8_t *bytes, size_t bytes_size, cardano_txoutput **output);
+
 /*!
 * \brief Create builder for a transaction
-* \returns builder object
+* \param [out] tb the builder object
 * \sa cardano_transaction_builder_delete()
 * \sa cardano_transaction_builder_add_output()
 * \sa cardano_transaction_builder_add_input()
@@ -287,20 +869,41 @@ void cardano_transaction_output_delete(cardano_txoutput *output);
 * \sa cardano_transaction_builder_fee()
 * \sa cardano_transaction_builder_finalize()
 */
-cardano_transaction_builder * cardano_transaction_builder_new(void);
+cardano_result cardano_transaction_builder_new(cardano_context *ctx, cardano_transaction_builder **tb);
 
 /*!
-* \brief Delete cardano_transaction_builder and free the associated memory
+* \brief Delete a handle of a cardano_transaction_builder, the builder is freed with its last handle
 */
 void cardano_transaction_builder_delete(cardano_transaction_builder *tb);
 
+/*!
+* \brief create another handle to the same builder
+* \param [in] tb the builder to share
+* \param [out] clone the new handle, to free with `cardano_transaction_builder_delete`
+* \returns CARDANO_RESULT_SUCCESS
+*/
+cardano_result cardano_transaction_builder_clone_handle(cardano_context *ctx, cardano_transaction_builder *tb,
+                                                        cardano_transaction_builder **clone);
+
+/*!
+* \brief the serialization of the transaction builder, the CBOR arrays of its inputs, of the values of its inputs and of its outputs
+* \param [out] buffer the length-prefixed serialization, to free with `cardano_buffer_free`
+*/
+cardano_result cardano_transaction_builder_to_bytes(cardano_transaction_builder *tb, uint8_t **buffer);
+
+/*!
+* \brief decode the transaction builder from its serialization, without the length prefix
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_SERIALIZATION
+*/
+cardano_result cardano_transaction_builder_from_bytes(cardano_context *ctx, const uint8_t *bytes, size_t bytes_size, cardano_transaction_builder **tb);
+
 /*!
 * \brief Add output to transaction
 * \param [in] tb the builder for the transaction
 * \param [in] txo created with `cardano_transaction_output_new`
 * \sa cardano_transaction_output_new()
 */
-void cardano_transaction_builder_add_output(cardano_transaction_builder *tb, cardano_txoutput *txo);
+cardano_result cardano_transaction_builder_add_output(cardano_transaction_builder *tb, cardano_txoutput *txo);
 
 /*!
 * \brief Add input to the transaction
@@ -308,9 +911,9 @@ void cardano_transaction_builder_add_output(cardano_transaction_builder *tb, car
 * \param [in] c_txo created with `cardano_transaction_output_ptr_new`
 * \param [in] value the cost 
 * \sa cardano_transaction_output_ptr_new()
-* \returns CARDANO_TRANSACTION_SUCCESS | CARDANO_TRANSACTION_COIN_OUT_OF_BOUNDS
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_COIN_OUT_OF_BOUNDS
 */
-cardano_transaction_error_t cardano_transaction_builder_add_input(cardano_transaction_builder *tb, cardano_txoptr *c_txo, uint64_t value);
+cardano_result cardano_transaction_builder_add_input(cardano_transaction_builder *tb, cardano_txoptr *c_txo, uint64_t value);
 
 /*!
 * \brief This associate all the leftover values, if any to an output with the specified address.
@@ -327,15 +930,108 @@ cardano_transaction_error_t cardano_transaction_builder_add_input(cardano_transa
 *
 * \param [in] tb the builder for the transaction
 * \param [in] change_addr used for the change (leftover values) output 
-* \returns 0 for success 0! for failure
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_NOT_ENOUGH_INPUT |
+* CARDANO_RESULT_TRANSACTION_OUTPUT_POLICY_NOT_ENOUGH_COINS
 */
 cardano_result cardano_transaction_builder_add_change_addr(cardano_transaction_builder *tb, cardano_address *change_addr);
 
 /*!
-* \brief Calculate the fee for the transaction with a linear algorithm
-* \returns fee
+* \brief Add an output sending the leftover of the inputs to the change address
+*
+* Same as `cardano_transaction_builder_add_change_addr`, with the fee computed by the given
+* fee algorithm.
+*
+* \param [in] tb the builder for the transaction
+* \param [in] fee the fee algorithm
+* \param [in] change_addr used for the change (leftover values) output
+* \param [out] change_value the value of the change output, 0 if the transaction was already
+* balanced and no output was added
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_NOT_ENOUGH_INPUT |
+* CARDANO_RESULT_TRANSACTION_OUTPUT_POLICY_NOT_ENOUGH_COINS
+*/
+cardano_result cardano_transaction_builder_add_change_output(cardano_transaction_builder *tb, cardano_linear_fee *fee, cardano_address *change_addr, uint64_t *change_value);
+
+/*!
+* \brief The number of inputs added to the builder
+*/
+cardano_result cardano_transaction_builder_input_count(cardano_transaction_builder *tb, unsigned long *count);
+
+/*!
+* \brief The number of outputs added to the builder, including the change output
+*/
+cardano_result cardano_transaction_builder_output_count(cardano_transaction_builder *tb, unsigned long *count);
+
+/*!
+* \brief Get a copy of an input of the builder and its value
+* \param [in] tb the builder for the transaction
+* \param [in] index the index of the input, in the order they were added
+* \param [out] txo the input, to free with `cardano_transaction_output_ptr_delete`
+* \param [out] value the value of the input, in Lovelace
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_ARGUMENT if the index is out of range
+*/
+cardano_result cardano_transaction_builder_get_input(cardano_context *ctx, cardano_transaction_builder *tb, unsigned long index, cardano_txoptr **txo, uint64_t *value);
+
+/*!
+* \brief Get a copy of an output of the builder
+* \param [in] tb the builder for the transaction
+* \param [in] index the index of the output, in the order they were added
+* \param [out] output the output, to free with `cardano_transaction_output_delete`
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_ARGUMENT if the index is out of range
+*/
+cardano_result cardano_transaction_builder_get_output(cardano_context *ctx, cardano_transaction_builder *tb, unsigned long index, cardano_txoutput **output);
+
+/*!
+* \brief Calculate the fee for the transaction with the default linear algorithm
+* \param [out] fee the fee, in Lovelace
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_FEE_ERROR
+*/
+cardano_result cardano_transaction_builder_fee(cardano_transaction_builder *tb, uint64_t *fee);
+
+/*!
+* An unspent transaction output available to plan a transaction
+* \sa cardano_transaction_plan()
+*/
+typedef struct cardano_utxo {
+    cardano_txoptr *ptr;
+    cardano_address *address;
+    /*! the value of the output, in Lovelace */
+    uint64_t value;
+} cardano_utxo_t;
+
+/*!
+* Strategy to select the UTxOs spent by a transaction
+* \sa cardano_transaction_plan()
 */
-uint64_t cardano_transaction_builder_fee(cardano_transaction_builder *tb);
+typedef enum _cardano_selection_policy {
+    /*! select the UTxOs in the given order */
+    CARDANO_SELECTION_FIRST_MATCH_FIRST,
+    /*! select the largest UTxOs first */
+    CARDANO_SELECTION_LARGEST_FIRST,
+    /*! select random UTxOs, then improve the selection so the change is about the size of the payments */
+    CARDANO_SELECTION_RANDOM_IMPROVE,
+} cardano_selection_policy;
+
+/*!
+* \brief Plan a transaction: select the UTxOs to spend to pay the outputs and add the change output
+*
+* The leftover of the selected UTxOs, once the outputs and the fee computed with the given fee
+* algorithm are paid, is sent to the change address. The returned builder can be inspected with
+* `cardano_transaction_builder_get_input` and `cardano_transaction_builder_get_output`, and
+* finalized with `cardano_transaction_builder_finalize`.
+*
+* \param [in] utxos the UTxOs available to spend
+* \param [in] utxos_size the number of UTxOs
+* \param [in] outputs the outputs of the transaction
+* \param [in] outputs_size the number of outputs
+* \param [in] fee the fee algorithm
+* \param [in] policy the strategy to select the UTxOs
+* \param [in] change_addr used for the change (leftover values) output
+* \param [out] tb the builder of the planned transaction, to free with `cardano_transaction_builder_delete`
+* \param [out] change_value the value of the change output, 0 if there is no change output
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_NOT_ENOUGH_INPUT |
+* CARDANO_RESULT_TRANSACTION_NO_OUTPUT | CARDANO_RESULT_TRANSACTION_COIN_OUT_OF_BOUNDS
+*/
+cardano_result cardano_transaction_plan(cardano_context *ctx, cardano_utxo_t *utxos, unsigned long utxos_size, cardano_txoutput **outputs, unsigned long outputs_size, cardano_linear_fee *fee, cardano_selection_policy policy, cardano_address *change_addr, cardano_transaction_builder **tb, uint64_t *change_value);
 
 /*!
 * struct for representing the sign in cardano_transaction_coin_diff_t
@@ -372,10 +1068,25 @@ void cardano_transaction_balance_delete(cardano_transaction_coin_diff_t *balance
 *   .
 * and the value field indicates the quantity (in -1 and 1 cases)
 * Excessive input goes to larger fee.
-* \returns CARDANO_TRANSACTION_SUCCESS | CARDANO_TRANSACTION_COIN_OUT_OF_BOUNDS if the total is too big
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_COIN_OUT_OF_BOUNDS if the total is too big
+* \sa cardano_transaction_balance_delete()
+*/
+cardano_result cardano_transaction_builder_balance(cardano_context *ctx, cardano_transaction_builder *tb, cardano_transaction_coin_diff_t **out);
+
+/*!
+* Same as `cardano_transaction_builder_balance`, with the fee computed by the given fee algorithm
 * \sa cardano_transaction_balance_delete()
 */
-cardano_transaction_error_t cardano_transaction_builder_balance(cardano_transaction_builder *tb, cardano_transaction_coin_diff_t **out);
+cardano_result cardano_transaction_builder_balance_with_fee(cardano_context *ctx, cardano_transaction_builder *tb, cardano_linear_fee *fee, cardano_transaction_coin_diff_t **out);
+
+/*!
+* \brief Check the inputs cover the outputs and the fee, before finalizing the transaction
+* \param [in] tb the builder for the transaction
+* \param [in] fee the fee algorithm
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_NOT_ENOUGH_INPUT, the error message
+* giving the missing value
+*/
+cardano_result cardano_transaction_builder_check_fee(cardano_transaction_builder *tb, cardano_linear_fee *fee);
 
 /*!
 * Try to return the differential between the outputs (excluding fees) and the inputs
@@ -386,40 +1097,64 @@ cardano_transaction_error_t cardano_transaction_builder_balance(cardano_transact
 *   - (sign == DIFF_POSITIVE) means inputs > (outputs). 
 *   .
 * and the value field indicates the quantity (in -1 and 1 cases)
-* \returns CARDANO_TRANSACTION_SUCCESS | CARDANO_TRANSACTION_COIN_OUT_OF_BOUNDS if the total is too big
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_COIN_OUT_OF_BOUNDS if the total is too big
 * \sa cardano_transaction_balance_delete()
 */
-cardano_transaction_error_t cardano_transaction_builder_balance_without_fees(cardano_transaction_builder *tb, cardano_transaction_coin_diff_t **out);
+cardano_result cardano_transaction_builder_balance_without_fees(cardano_context *ctx, cardano_transaction_builder *tb, cardano_transaction_coin_diff_t **out);
 
 /*!
 * Try to return the sum of the inputs
 * \param [in] tb the builder for the transaction
 * \param [out] output the sum
-* \returns CARDANO_TRANSACTION_SUCCESS | CARDANO_TRANSACTION_COIN_OUT_OF_BOUNDS if the total is too big
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_COIN_OUT_OF_BOUNDS if the total is too big
 */
-cardano_transaction_error_t cardano_transaction_builder_get_input_total(cardano_transaction_builder *tb, uint64_t *output);
+cardano_result cardano_transaction_builder_get_input_total(cardano_transaction_builder *tb, uint64_t *output);
 
 /*!
 * Try to return the sum of the outputs
 * \param [in] tb the builder for the transaction
 * \param [out] output the sum
-* \returns CARDANO_TRANSACTION_SUCCESS | CARDANO_TRANSACTION_COIN_OUT_OF_BOUNDS if the total is too big
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_COIN_OUT_OF_BOUNDS if the total is too big
 */
-cardano_transaction_error_t cardano_transaction_builder_get_output_total(cardano_transaction_builder *tb, uint64_t *output);
+cardano_result cardano_transaction_builder_get_output_total(cardano_transaction_builder *tb, uint64_t *output);
 
 /*!
 * \brief Get a transaction object
-* \returns CARDANO_TRANSACTION_SUCCESS | CARDANO_TRANSACTION_NO_INPUT | CARDANO_TRANSACTION_NO_OUTPUT
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_NO_INPUT | CARDANO_RESULT_TRANSACTION_NO_OUTPUT
 */
-cardano_transaction_error_t cardano_transaction_builder_finalize(cardano_transaction_builder *tb, cardano_transaction **tx);
+cardano_result cardano_transaction_builder_finalize(cardano_context *ctx, cardano_transaction_builder *tb, cardano_transaction **tx);
 void cardano_transaction_delete(cardano_transaction *c_tx);
 
+/*!
+* \brief the serialization of the transaction, its CBOR serialization
+* \param [out] buffer the length-prefixed serialization, to free with `cardano_buffer_free`
+*/
+cardano_result cardano_transaction_to_bytes(cardano_transaction *tx, uint8_t **buffer);
+
+/*!
+* \brief decode the transaction from its serialization, without the length prefix
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_SERIALIZATION
+*/
+cardano_result cardano_transaction_from_bytes(cardano_context *ctx, const uint8_t *bytes, size_t bytes_size, cardano_transaction **tx);
+
 /*!
 * \brief Take a transaction and create a working area for adding witnesses
 */
-cardano_transaction_finalized * cardano_transaction_finalized_new(cardano_transaction *c_tx);
+cardano_result cardano_transaction_finalized_new(cardano_context *ctx, cardano_transaction *c_tx, cardano_transaction_finalized **tf);
 void cardano_transaction_finalized_delete(cardano_transaction_finalized *tf);
 
+/*!
+* \brief the serialization of the finalized transaction, the CBOR array of the transaction and of its witnesses
+* \param [out] buffer the length-prefixed serialization, to free with `cardano_buffer_free`
+*/
+cardano_result cardano_transaction_finalized_to_bytes(cardano_transaction_finalized *tf, uint8_t **buffer);
+
+/*!
+* \brief decode the finalized transaction from its serialization, without the length prefix
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_SERIALIZATION
+*/
+cardano_result cardano_transaction_finalized_from_bytes(cardano_context *ctx, const uint8_t *bytes, size_t bytes_size, cardano_transaction_finalized **tf);
+
 /*!
 * Add a witness associated with the next input.
 *
@@ -429,19 +1164,332 @@ void cardano_transaction_finalized_delete(cardano_transaction_finalized *tf);
 * \param protocol_magic
 * \param c_txid
 * \sa cardano_transaction_builder_new
-* \returns CARDANO_TRANSACTION_SUCCESS | CARDANO_TRANSACTION_SIGNATURES_EXCEEDED
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_SIGNATURES_EXCEEDED
 */
-cardano_transaction_error_t cardano_transaction_finalized_add_witness(cardano_transaction_finalized *tf, uint8_t c_xprv[96], uint32_t protocol_magic, uint8_t c_txid[32]);
+cardano_result cardano_transaction_finalized_add_witness(cardano_transaction_finalized *tf, cardano_xprv *c_xprv, uint32_t protocol_magic, uint8_t c_txid[32]);
 
 /*!
 * \brief A finalized transaction with the vector of witnesses
 * \param tf a finalized transaction with witnesses
 * \sa cardano_transaction_finalized_add_witness()
-* \returns CARDANO_TRANSACTION_SUCCESS | CARDANO_TRANSACTION_SIGNATURE_MISMATCH | CARDANO_TRANSACTION_OVER_LIMIT
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_SIGNATURE_MISMATCH | CARDANO_RESULT_TRANSACTION_OVER_LIMIT
 */
-cardano_transaction_error_t cardano_transaction_finalized_output(cardano_transaction_finalized *tf, cardano_signed_transaction **txaux);
+cardano_result cardano_transaction_finalized_output(cardano_context *ctx, cardano_transaction_finalized *tf, cardano_signed_transaction **txaux);
 void cardano_transaction_signed_delete(cardano_signed_transaction *txaux);
 
+/*!
+* \brief the serialization of the signed transaction, its CBOR serialization, the one sent to the network
+* \param [out] buffer the length-prefixed serialization, to free with `cardano_buffer_free`
+*/
+cardano_result cardano_transaction_signed_to_bytes(cardano_signed_transaction *txaux, uint8_t **buffer);
+
+/*!
+* \brief decode the signed transaction from its serialization, without the length prefix
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_SERIALIZATION
+*/
+cardano_result cardano_transaction_signed_from_bytes(cardano_context *ctx, const uint8_t *bytes, size_t bytes_size, cardano_signed_transaction **txaux);
+
+/*********************/
+/* Offline signing   */
+/*********************/
+
+/*!
+* Size of a witness exchanged with the offline signer: the public key followed by the signature
+* \sa cardano_transaction_apply_witness()
+*/
+#define WITNESS_SIZE (XPUB_SIZE + SIGNATURE_SIZE)
+
+/*!
+* The output spent by an input of a transaction to sign offline and the derivation path,
+* from the root key, of the key to sign it with
+* \sa cardano_transaction_builder_export_unsigned()
+*/
+typedef struct cardano_signer_input {
+    cardano_address *address;
+    const uint32_t *path;
+    unsigned long path_size;
+} cardano_signer_input_t;
+
+/*!
+* \brief Export the transaction of the builder to be signed offline (e.g. by an air-gapped device)
+*
+* The exported bytes hold the transaction, the output spent by each of its inputs and the
+* derivation path of the key to sign it with, so the signer does not need the UTxOs.
+*
+* \param [in] tb the builder for the transaction
+* \param [in] protocol_magic the protocol magic of the network
+* \param [in] inputs the spent outputs, in the order of the builder's inputs
+* \param [in] inputs_size the number of inputs
+* \param [out] bytes the unsigned transaction, to free with `cardano_transaction_bytes_delete`
+* \param [out] bytes_size the number of bytes
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_NO_INPUT | CARDANO_RESULT_TRANSACTION_NO_OUTPUT |
+* CARDANO_RESULT_INVALID_ARGUMENT if the number of inputs does not match the builder's
+*/
+cardano_result cardano_transaction_builder_export_unsigned(cardano_transaction_builder *tb, uint32_t protocol_magic, const cardano_signer_input_t *inputs, unsigned long inputs_size, uint8_t **bytes, unsigned long *bytes_size);
+
+/*!
+* \brief Sign the exported unsigned transaction with the keys derived from the root key along the derivation paths of its inputs
+* \param [in] unsigned_bytes the unsigned transaction
+* \param [in] unsigned_size the number of bytes of the unsigned transaction
+* \param [in] root_xprv the root private key
+* \param [out] witnesses WITNESS_SIZE bytes per input, to free with `cardano_transaction_bytes_delete`
+* \param [out] witnesses_size the number of bytes of the witnesses
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_ARGUMENT if the unsigned transaction is invalid
+*/
+cardano_result cardano_transaction_unsigned_sign(const uint8_t *unsigned_bytes, unsigned long unsigned_size, cardano_xprv *root_xprv, uint8_t **witnesses, unsigned long *witnesses_size);
+
+/*!
+* \brief Apply the witnesses produced offline to the exported unsigned transaction
+*
+* Each witness is checked against the address of the spent output and the transaction.
+*
+* \param [in] unsigned_bytes the unsigned transaction
+* \param [in] unsigned_size the number of bytes of the unsigned transaction
+* \param [in] witnesses WITNESS_SIZE bytes per input, in the order of the inputs
+* \param [in] witnesses_count the number of witnesses
+* \param [out] txaux the signed transaction, to free with `cardano_transaction_signed_delete`
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_SIGNATURE_MISMATCH |
+* CARDANO_RESULT_TRANSACTION_INVALID_WITNESS | CARDANO_RESULT_INVALID_ARGUMENT if the unsigned transaction is invalid
+*/
+cardano_result cardano_transaction_apply_witness(cardano_context *ctx, const uint8_t *unsigned_bytes, unsigned long unsigned_size, const uint8_t *witnesses, unsigned long witnesses_count, cardano_signed_transaction **txaux);
+
+/*!
+* Free the memory allocated with `cardano_transaction_builder_export_unsigned` or `cardano_transaction_unsigned_sign`
+*/
+void cardano_transaction_bytes_delete(uint8_t *bytes, unsigned long bytes_size);
+
+/***************************/
+/* Redeem (ADA vouchers)   */
+/***************************/
+
+/*!
+* Size of the private key of a redeem address
+*/
+#define REDEEM_PRIVATEKEY_SIZE 32
+
+/*!
+* Size of the public key of a redeem address
+*/
+#define REDEEM_PUBLICKEY_SIZE 32
+
+/*!
+* Private key of a redeem address (ADA voucher)
+*/
+typedef struct cardano_redeem_private_key cardano_redeem_private_key;
+
+/*!
+* \brief Construct the redeem key from the given REDEEM_PRIVATEKEY_SIZE bytes
+* \param [out] key the redeem key, to free with `cardano_redeem_private_key_delete`
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_SERIALIZATION if there are not REDEEM_PRIVATEKEY_SIZE bytes
+*/
+cardano_result cardano_redeem_private_key_from_bytes(cardano_context *ctx, const uint8_t *bytes, size_t bytes_size,
+                                                     cardano_redeem_private_key **key);
+
+/*!
+* \brief the REDEEM_PRIVATEKEY_SIZE bytes of the redeem key
+* \param [out] buffer the length-prefixed bytes, to free with `cardano_buffer_free`
+*/
+cardano_result cardano_redeem_private_key_to_bytes(cardano_redeem_private_key *key, uint8_t **buffer);
+
+/*!
+* Free the associated memory
+*/
+void cardano_redeem_private_key_delete(cardano_redeem_private_key *key);
+
+/*!
+* \brief Get the public key of the redeem key
+* \param [out] public_key the REDEEM_PUBLICKEY_SIZE bytes of the public key
+*/
+cardano_result cardano_redeem_public_key(cardano_redeem_private_key *key, uint8_t *public_key);
+
+/*!
+* \brief Get the redeem address of the key, to free with `cardano_address_delete`
+*/
+cardano_result cardano_redeem_address_new(cardano_context *ctx, cardano_redeem_private_key *key, uint32_t protocol_magic, cardano_address **address);
+
+/*!
+* Add the witness of the redeem key associated with the next input.
+* \sa cardano_transaction_finalized_add_witness()
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_SIGNATURES_EXCEEDED
+*/
+cardano_result cardano_redeem_add_witness(cardano_transaction_finalized *tf, cardano_redeem_private_key *key, uint32_t protocol_magic);
+
+/*!
+* \brief Build the signed transaction redeeming the whole value of a redeem address UTxO
+*
+* The value of the UTxO, once the fee computed with the given fee algorithm is paid, is sent to
+* the given address.
+*
+* \param [in] key the redeem key of the UTxO's address
+* \param [in] protocol_magic the protocol magic of the network
+* \param [in] txo the UTxO to redeem
+* \param [in] value the value of the UTxO, in Lovelace
+* \param [in] to the address receiving the redeemed value
+* \param [in] fee the fee algorithm
+* \param [out] txaux the signed transaction, to free with `cardano_transaction_signed_delete`
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_NOT_ENOUGH_INPUT |
+* CARDANO_RESULT_TRANSACTION_OUTPUT_POLICY_NOT_ENOUGH_COINS | CARDANO_RESULT_TRANSACTION_COIN_OUT_OF_BOUNDS
+*/
+cardano_result cardano_redeem_transaction(cardano_context *ctx, cardano_redeem_private_key *key, uint32_t protocol_magic, cardano_txoptr *txo, uint64_t value, cardano_address *to, cardano_linear_fee *fee, cardano_signed_transaction **txaux);
+
+/*********/
+/* Units */
+/*********/
+
+/*!
+* \brief Parse an amount in ADA into Lovelace (1 ADA = 1000000 Lovelace)
+*
+* The decimal separator is '.' whatever the locale, there is no thousands separator,
+* there are at most 6 decimals and the amount may be followed by " ADA".
+* e.g. "12.345678 ADA", "0.5", "3"
+*
+* \param [in] amount the null terminated amount
+* \param [out] lovelace the parsed amount, in Lovelace
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_AMOUNT | CARDANO_RESULT_TRANSACTION_COIN_OUT_OF_BOUNDS
+*/
+cardano_result cardano_units_parse_ada(const char *amount, uint64_t *lovelace);
+
+/*!
+* \brief Parse an amount in Lovelace, optionally followed by " lovelace", e.g. "42 lovelace"
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_AMOUNT | CARDANO_RESULT_TRANSACTION_COIN_OUT_OF_BOUNDS
+*/
+cardano_result cardano_units_parse_lovelace(const char *amount, uint64_t *lovelace);
+
+/*!
+* \brief Format the Lovelace amount in ADA with its 6 decimals, e.g. "12.345678 ADA"
+* \param [out] string the formatted amount, to free with `cardano_units_delete_string`
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_COIN_OUT_OF_BOUNDS
+* \sa cardano_units_delete_string()
+*/
+cardano_result cardano_units_format_ada(uint64_t lovelace, char **string);
+
+/*!
+* \brief Format the Lovelace amount, e.g. "12345678 lovelace"
+* \param [out] string the formatted amount, to free with `cardano_units_delete_string`
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_COIN_OUT_OF_BOUNDS
+* \sa cardano_units_delete_string()
+*/
+cardano_result cardano_units_format_lovelace(uint64_t lovelace, char **string);
+
+/*!
+* \brief Free a string returned by `cardano_units_format_ada` or `cardano_units_format_lovelace`
+*/
+void cardano_units_delete_string(char *string);
+
+/*************/
+/* Encodings */
+/*************/
+
+/*!
+* \brief Encode the bytes in base58, as the addresses
+* \param [out] string the null terminated base58 string, to free with `cardano_encoding_delete_string`
+* \sa cardano_encoding_delete_string()
+*/
+cardano_result cardano_encode_base58(const uint8_t *bytes, unsigned long bytes_size, char **string);
+
+/*!
+* \brief Decode the base58 string
+* \param [out] bytes the decoded bytes, to free with `cardano_encoding_delete_bytes`
+* \param [out] bytes_size the number of decoded bytes
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_BASE58
+* \sa cardano_encoding_delete_bytes()
+*/
+cardano_result cardano_decode_base58(const char *string, uint8_t **bytes, unsigned long *bytes_size);
+
+/*!
+* \brief Encode the bytes in bech32 with the given human readable part, e.g. "ed25519_pk"
+* \param [in] hrp the null terminated human readable part
+* \param [out] string the null terminated bech32 string, to free with `cardano_encoding_delete_string`
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_BECH32 if the human readable part is invalid
+* \sa cardano_encoding_delete_string()
+*/
+cardano_result cardano_encode_bech32(const char *hrp, const uint8_t *bytes, unsigned long bytes_size, char **string);
+
+/*!
+* \brief Decode the bech32 string and check its checksum
+* \param [out] hrp the human readable part, to free with `cardano_encoding_delete_string`
+* \param [out] bytes the decoded bytes, to free with `cardano_encoding_delete_bytes`
+* \param [out] bytes_size the number of decoded bytes
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_BECH32
+*/
+cardano_result cardano_decode_bech32(const char *string, char **hrp, uint8_t **bytes, unsigned long *bytes_size);
+
+/*!
+* \brief Free a string returned by `cardano_encode_base58`, `cardano_encode_bech32` or `cardano_decode_bech32`
+*/
+void cardano_encoding_delete_string(char *string);
+
+/*!
+* \brief Free the bytes returned by `cardano_decode_base58` or `cardano_decode_bech32`
+*/
+void cardano_encoding_delete_bytes(uint8_t *bytes, unsigned long bytes_size);
+
+/*********/
+/* Fees  */
+/*********/
+
+/*!
+* \brief Create a linear fee algorithm
+* \param [in] constant the minimal fee, in Lovelace
+* \param [in] coefficient_millis the cost per byte, in thousandths of Lovelace (43946 for 43.946)
+* \param [out] fee the fee algorithm that must be freed with `cardano_fee_linear_delete`
+*/
+cardano_result cardano_fee_linear_new(cardano_context *ctx, uint64_t constant, uint64_t coefficient_millis, cardano_linear_fee **fee);
+
+/*!
+* \brief Create the linear fee algorithm of the mainnet (155381 + 43.946 per byte)
+* \param [out] fee the fee algorithm that must be freed with `cardano_fee_linear_delete`
+*/
+cardano_result cardano_fee_linear_default(cardano_context *ctx, cardano_linear_fee **fee);
+
+/*!
+* Free the memory allocated with `cardano_fee_linear_new` or `cardano_fee_linear_default`
+*/
+void cardano_fee_linear_delete(cardano_linear_fee *fee);
+
+/*!
+* \brief the serialization of the linear fee, the CBOR array of its constant and of its coefficient in thousandths of Lovelace
+* \param [out] buffer the length-prefixed serialization, to free with `cardano_buffer_free`
+*/
+cardano_result cardano_fee_linear_to_bytes(cardano_linear_fee *fee, uint8_t **buffer);
+
+/*!
+* \brief decode the linear fee from its serialization, without the length prefix
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_INVALID_SERIALIZATION
+*/
+cardano_result cardano_fee_linear_from_bytes(cardano_context *ctx, const uint8_t *bytes, size_t bytes_size, cardano_linear_fee **fee);
+
+/*!
+* \brief Compute the fee of a signed transaction of the given size
+* \param [in] fee the fee algorithm
+* \param [in] num_bytes the size of the signed transaction
+* \param [out] out the fee, in Lovelace
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_FEE_ERROR
+*/
+cardano_result cardano_fee_linear_estimate(cardano_linear_fee *fee, uint64_t num_bytes, uint64_t *out);
+
+/*!
+* \brief Compute the fee of the transaction being built, once it is signed
+*
+* The witnesses are accounted for, one per input, so the result is the exact
+* fee of the transaction finalized from the builder.
+*
+* \param [in] fee the fee algorithm
+* \param [in] tb the builder for the transaction
+* \param [out] out the fee, in Lovelace
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_FEE_ERROR
+*/
+cardano_result cardano_fee_transaction_builder(cardano_linear_fee *fee, cardano_transaction_builder *tb, uint64_t *out);
+
+/*!
+* \brief Compute the fee of a signed transaction
+* \param [in] fee the fee algorithm
+* \param [in] txaux the signed transaction
+* \param [out] out the fee, in Lovelace
+* \returns CARDANO_RESULT_SUCCESS | CARDANO_RESULT_TRANSACTION_FEE_ERROR
+*/
+cardano_result cardano_fee_signed_transaction(cardano_linear_fee *fee, cardano_signed_transaction *txaux, uint64_t *out);
+
 #ifdef __cplusplus
 }
 #endif
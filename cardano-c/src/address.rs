@@ -1,13 +1,17 @@
 use std::os::raw::{c_char, c_int};
-use std::{ffi, ptr};
+use std::{ffi, mem, slice};
 
 use cardano::{
     address::ExtendedAddr,
-    config::ProtocolMagic,
+    config::{NetworkMagic, ProtocolMagic},
+    hdpayload,
     util::{base58, try_from_slice::TryFromSlice},
 };
 
-use super::{AddressPtr, XPubPtr};
+use super::{AddressPtr, CardanoAddressKind, CardanoResult, XPubPtr};
+use buffer;
+use context::{self, ContextPtr};
+use error::{self, Error};
 
 // FFI helper internal call
 pub fn ffi_address_to_base58(address: &ExtendedAddr) -> ffi::CString {
@@ -17,36 +21,46 @@ pub fn ffi_address_to_base58(address: &ExtendedAddr) -> ffi::CString {
     c_address
 }
 
+// FFI helper internal call
+pub fn ffi_address_from_base58(address_base58: &ffi::CStr) -> error::Result<ExtendedAddr> {
+    let address_raw = base58::decode_bytes(address_base58.to_bytes()).map_err(|err| {
+        Error::new(
+            CardanoResult::InvalidBase58,
+            format!("invalid base58 address: {}", err),
+        )
+    })?;
+    ExtendedAddr::try_from_slice(&address_raw[..]).map_err(|err| {
+        Error::new(
+            CardanoResult::InvalidAddress,
+            format!("invalid address: {:?}", err),
+        )
+    })
+}
+
 /// Take a string as parameter and returns whether or not it's a valid base58 address
 ///
-/// On valid address, the return value is 0
-/// On invalid address, the return value is different from 0.
-///
-/// Invalid cases returns different code depending on the issue
-///
+/// On valid address, the return value is `Success`, otherwise
+/// `InvalidBase58` or `InvalidAddress` depending on the issue.
 #[no_mangle]
-pub extern "C" fn cardano_address_is_valid(c_address: *mut c_char) -> c_int {
-    let address_base58 = unsafe { ffi::CStr::from_ptr(c_address).to_bytes() };
-    if let Ok(address_raw) = base58::decode_bytes(address_base58) {
-        if let Ok(_) = ExtendedAddr::try_from_slice(&address_raw[..]) {
-            return 0;
-        } else {
-            return 2;
-        }
-    } else {
-        return 1;
-    }
+pub extern "C" fn cardano_address_is_valid(c_address: *const c_char) -> CardanoResult {
+    error::handle_exception(|| {
+        let address_base58 = unsafe { error::as_c_str(c_address, "address") }?;
+        ffi_address_from_base58(address_base58).map(|_| ())
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn cardano_address_new_from_pubkey(
+    ctx: ContextPtr,
     c_xpubkey: XPubPtr,
     protocol_magic: ProtocolMagic,
-) -> AddressPtr {
-    let xpub = unsafe { c_xpubkey.as_ref() }.expect("Not a NULL PTR");
-    let ea = ExtendedAddr::new_simple(xpub.clone(), protocol_magic.into());
-    let address = Box::new(ea);
-    Box::into_raw(address)
+    address_out: *mut AddressPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let xpub = unsafe { error::as_ref(c_xpubkey, "publickey") }?;
+        let ea = ExtendedAddr::new_simple(xpub.clone(), protocol_magic.into());
+        unsafe { error::write_boxed(address_out, ea, "address") }
+    })
 }
 
 #[no_mangle]
@@ -54,23 +68,171 @@ pub extern "C" fn cardano_address_delete(c_addr: AddressPtr) {
     unsafe { Box::from_raw(c_addr) };
 }
 
+/// decode the address from its binary (CBOR) serialization, the one of
+/// the base58 encoding
+#[no_mangle]
+pub extern "C" fn cardano_address_from_bytes(
+    ctx: ContextPtr,
+    c_bytes: *const u8,
+    bytes_size: usize,
+    address_out: *mut AddressPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let bytes = unsafe { error::as_slice(c_bytes, bytes_size, "bytes") }?;
+        let address: ExtendedAddr = buffer::read_cbor(bytes, "address")?;
+        unsafe { error::write_boxed(address_out, address, "address") }
+    })
+}
+
+/// the binary serialization of the address, to free with
+/// `cardano_buffer_free`
+#[no_mangle]
+pub extern "C" fn cardano_address_to_bytes(
+    c_addr: AddressPtr,
+    buffer_out: *mut *mut u8,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let address = unsafe { error::as_ref(c_addr, "address") }?;
+        buffer::write_cbor(address, buffer_out)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn cardano_address_import_base58(
+    ctx: ContextPtr,
+    c_address: *const c_char,
+    address_out: *mut AddressPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let address_base58 = unsafe { error::as_c_str(c_address, "address_base58") }?;
+        let ea = ffi_address_from_base58(address_base58)?;
+        unsafe { error::write_boxed(address_out, ea, "address") }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn cardano_address_export_base58(
+    c_addr: AddressPtr,
+    address_base58_out: *mut *mut c_char,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let address = unsafe { error::as_ref(c_addr, "address") }?;
+        let out = unsafe { error::as_mut(address_base58_out, "address_base58") }?;
+        *out = ffi_address_to_base58(address).into_raw();
+        Ok(())
+    })
+}
+
+/// get the type of the spending data of the address: public key, script
+/// or redeem key
+#[no_mangle]
+pub extern "C" fn cardano_address_get_kind(
+    c_addr: AddressPtr,
+    kind_out: *mut CardanoAddressKind,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let address = unsafe { error::as_ref(c_addr, "address") }?;
+        unsafe { error::write(kind_out, address.addr_type.into(), "kind") }
+    })
+}
+
+/// get the protocol magic of the network the address is for
+///
+/// `has_protocol_magic_out` is set to 0 if the address does not contain a
+/// protocol magic, which is the case of the mainnet addresses. Otherwise
+/// it is set to 1 and the protocol magic is written in `protocol_magic_out`.
+#[no_mangle]
+pub extern "C" fn cardano_address_get_network(
+    c_addr: AddressPtr,
+    has_protocol_magic_out: *mut c_int,
+    protocol_magic_out: *mut u32,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let address = unsafe { error::as_ref(c_addr, "address") }?;
+        match address.attributes.network_magic {
+            NetworkMagic::NoMagic => unsafe {
+                error::write(has_protocol_magic_out, 0, "has_protocol_magic")
+            },
+            NetworkMagic::Magic(protocol_magic) => unsafe {
+                error::write(protocol_magic_out, protocol_magic, "protocol_magic")?;
+                error::write(has_protocol_magic_out, 1, "has_protocol_magic")
+            },
+        }
+    })
+}
+
+/// write in `hdpassphrase_out` (HDKEY_SIZE bytes) the HD passphrase of the
+/// wallet of the given root public key, which encrypts the derivation
+/// path of the addresses of the wallet
+#[no_mangle]
+pub extern "C" fn cardano_xpub_to_hdpassphrase(
+    c_root_xpub: XPubPtr,
+    hdpassphrase_out: *mut u8,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let root_xpub = unsafe { error::as_ref(c_root_xpub, "root_xpub") }?;
+        let out = unsafe {
+            error::as_mut_slice(hdpassphrase_out, hdpayload::HDKEY_SIZE, "hdpassphrase")
+        }?;
+        let hdkey = hdpayload::HDKey::new(root_xpub);
+        out.copy_from_slice(hdkey.as_ref());
+        Ok(())
+    })
+}
+
+/// decrypt the derivation path of the address with the HD passphrase
+/// (HDKEY_SIZE bytes) of its wallet
+///
+/// Fails if the address does not contain a derivation path, which is the
+/// case of the BIP44 wallets addresses, or if it cannot be decrypted with
+/// the given passphrase. The path must be freed with
+/// `cardano_address_delete_derivation_path`.
 #[no_mangle]
-pub extern "C" fn cardano_address_import_base58(c_address: *mut c_char) -> AddressPtr {
-    let address_base58 = unsafe { ffi::CStr::from_ptr(c_address).to_bytes() };
-    if let Ok(address_raw) = base58::decode_bytes(address_base58) {
-        if let Ok(ea) = ExtendedAddr::try_from_slice(&address_raw[..]) {
-            let address = Box::new(ea);
-            Box::into_raw(address)
-        } else {
-            ptr::null_mut()
+pub extern "C" fn cardano_address_get_derivation_path(
+    c_addr: AddressPtr,
+    hdpassphrase: *const u8,
+    path_out: *mut *mut u32,
+    path_size_out: *mut usize,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let address = unsafe { error::as_ref(c_addr, "address") }?;
+        let hdpassphrase =
+            unsafe { error::as_slice(hdpassphrase, hdpayload::HDKEY_SIZE, "hdpassphrase") }?;
+        if path_out.is_null() || path_size_out.is_null() {
+            return Err(Error::new(
+                CardanoResult::NullPointer,
+                "`path` or `path_size` is a NULL pointer",
+            ));
+        }
+        let payload = match address.attributes.derivation_path {
+            Some(ref payload) => payload,
+            None => {
+                return Err(Error::new(
+                    CardanoResult::NoDerivationPath,
+                    "the address does not contain a derivation path",
+                ))
+            }
+        };
+        let hdkey = hdpayload::HDKey::from_slice(hdpassphrase).expect("HDKEY_SIZE bytes");
+        let path = hdkey.decrypt_path(payload).map_err(|err| {
+            Error::new(
+                CardanoResult::InvalidHDPassphrase,
+                format!("cannot decrypt the derivation path: {}", err),
+            )
+        })?;
+
+        let mut path = path.as_ref().to_vec().into_boxed_slice();
+        unsafe {
+            error::write(path_size_out, path.len(), "path_size")?;
+            error::write(path_out, path.as_mut_ptr(), "path")?;
         }
-    } else {
-        ptr::null_mut()
-    }
+        mem::forget(path);
+        Ok(())
+    })
 }
 
+/// free the derivation path returned by `cardano_address_get_derivation_path`
 #[no_mangle]
-pub extern "C" fn cardano_address_export_base58(c_addr: AddressPtr) -> *const c_char {
-    let address = unsafe { c_addr.as_ref() }.expect("Not a NULL PTR");
-    ffi_address_to_base58(address).into_raw()
+pub extern "C" fn cardano_address_delete_derivation_path(path: *mut u32, path_size: usize) {
+    unsafe { Box::from_raw(slice::from_raw_parts_mut(path, path_size)) };
 }
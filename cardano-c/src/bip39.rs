@@ -1,15 +1,31 @@
-use std::slice;
-
 use cardano::bip::bip39;
-use types::CardanoBIP39ErrorCode;
+use context::{self, ContextPtr};
+use error::{self, Error};
 use types::CardanoResult;
 
 use std::{
-    os::raw::{c_char, c_uchar, c_uint},
+    ffi::CString,
+    os::raw::{c_char, c_int, c_uchar, c_uint},
     ptr,
 };
 
-use std::ffi::CStr;
+/// the dictionaries of the `cardano_bip39_language` values of the C API
+fn dictionary(language: c_int) -> error::Result<&'static bip39::dictionary::DefaultDictionary> {
+    match language {
+        0 => Ok(&bip39::dictionary::ENGLISH),
+        1 => Ok(&bip39::dictionary::JAPANESE),
+        2 => Ok(&bip39::dictionary::KOREAN),
+        3 => Ok(&bip39::dictionary::SPANISH),
+        4 => Ok(&bip39::dictionary::CHINESE_SIMPLIFIED),
+        5 => Ok(&bip39::dictionary::CHINESE_TRADITIONAL),
+        6 => Ok(&bip39::dictionary::FRENCH),
+        7 => Ok(&bip39::dictionary::ITALIAN),
+        _ => Err(Error::new(
+            CardanoResult::InvalidArgument,
+            format!("unknown language {}", language),
+        )),
+    }
+}
 
 /// encode a entropy into its equivalent words represented by their index (0 to 2047) in the BIP39 dictionary
 #[no_mangle]
@@ -19,14 +35,26 @@ pub extern "C" fn cardano_bip39_encode(
     encoded: *mut bip39::MnemonicIndex, /* the encoded entropy */
     encoded_size: usize,
 ) -> CardanoResult {
-    let in_slice = unsafe { slice::from_raw_parts(entropy_raw, entropy_bytes) };
-    let out_slice = unsafe { slice::from_raw_parts_mut(encoded, encoded_size) };
-    let entropy = match bip39::Entropy::from_slice(in_slice) {
-        Ok(e) => e,
-        Err(_) => return CardanoResult::failure(),
-    };
-    out_slice.copy_from_slice(entropy.to_mnemonics().as_ref());
-    CardanoResult::success()
+    error::handle_exception(|| {
+        let in_slice = unsafe { error::as_slice(entropy_raw, entropy_bytes, "entropy_raw") }?;
+        let out_slice = unsafe { error::as_mut_slice(encoded, encoded_size, "mnemonic_index") }?;
+        let entropy = bip39::Entropy::from_slice(in_slice).map_err(|err| {
+            Error::new(CardanoResult::Bip39InvalidEntropySize, format!("{}", err))
+        })?;
+        let mnemonics = entropy.to_mnemonics();
+        if mnemonics.as_ref().len() != out_slice.len() {
+            return Err(Error::new(
+                CardanoResult::InvalidArgument,
+                format!(
+                    "the entropy is encoded in {} words, not {}",
+                    mnemonics.as_ref().len(),
+                    out_slice.len()
+                ),
+            ));
+        }
+        out_slice.copy_from_slice(mnemonics.as_ref());
+        Ok(())
+    })
 }
 
 ///retrieve the entropy from the given english mnemonics
@@ -35,50 +63,104 @@ pub extern "C" fn cardano_entropy_from_english_mnemonics(
     mnemonics: *const c_char,
     entropy_ptr: *mut *const c_uchar,
     entropy_size: *mut c_uint,
-) -> CardanoBIP39ErrorCode {
-    let rust_string = unsafe { CStr::from_ptr(mnemonics) }.to_string_lossy();
+) -> CardanoResult {
+    cardano_bip39_mnemonics_to_entropy(mnemonics, 0, entropy_ptr, entropy_size)
+}
 
-    let dictionary = bip39::dictionary::ENGLISH;
+///retrieve the entropy from the given mnemonics in the given language
+///
+///The mnemonics are expected to be normalized (UTF-8 NFKD), so the words
+///are separated with a space, including in Japanese.
+#[no_mangle]
+pub extern "C" fn cardano_bip39_mnemonics_to_entropy(
+    mnemonics: *const c_char,
+    language: c_int,
+    entropy_ptr: *mut *const c_uchar,
+    entropy_size: *mut c_uint,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let dictionary = dictionary(language)?;
+        let rust_string = unsafe { error::as_c_str(mnemonics, "mnemonics") }?.to_string_lossy();
 
-    let mnemonics = match bip39::Mnemonics::from_string(&dictionary, &rust_string) {
-        Ok(m) => m,
         //The error happens when a word is not in the dictionary
-        Err(_) => return CardanoBIP39ErrorCode::invalid_word(),
-    };
+        let mnemonics = bip39::Mnemonics::from_string(dictionary, &rust_string)
+            .map_err(|err| Error::new(CardanoResult::Bip39InvalidWord, format!("{}", err)))?;
 
-    let entropy = match bip39::Entropy::from_mnemonics(&mnemonics) {
-        Ok(e) => e,
         //The error happens because the phrase doesn't have a valid checksum
-        Err(_) => return CardanoBIP39ErrorCode::invalid_checksum(),
-    };
+        let entropy = bip39::Entropy::from_mnemonics(&mnemonics)
+            .map_err(|err| Error::new(CardanoResult::Bip39InvalidChecksum, format!("{}", err)))?;
 
-    out_return_vector(entropy.to_vec(), entropy_ptr, entropy_size);
+        out_return_vector(entropy.to_vec(), entropy_ptr, entropy_size)
+    })
+}
 
-    CardanoBIP39ErrorCode::success()
+///generate the mnemonic phrase of the given entropy in the given language
+///
+///The entropy can come from the random generator of the host. The words
+///of the returned phrase are separated with a space and the memory must
+///be then deallocated with cardano_bip39_delete_mnemonics
+#[no_mangle]
+pub extern "C" fn cardano_bip39_generate_from_entropy(
+    entropy_raw: *const u8,
+    entropy_bytes: usize,
+    language: c_int,
+    mnemonics_out: *mut *mut c_char,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let dictionary = dictionary(language)?;
+        let in_slice = unsafe { error::as_slice(entropy_raw, entropy_bytes, "entropy_raw") }?;
+        let out = unsafe { error::as_mut(mnemonics_out, "mnemonics") }?;
+        let entropy = bip39::Entropy::from_slice(in_slice).map_err(|err| {
+            Error::new(CardanoResult::Bip39InvalidEntropySize, format!("{}", err))
+        })?;
+
+        let mnemonics = entropy.to_mnemonics().to_string(dictionary);
+        let c_mnemonics =
+            CString::new(mnemonics.as_bytes()).expect("the dictionaries have no null byte");
+        *out = c_mnemonics.into_raw();
+        Ok(())
+    })
 }
 
-///generate entropy from the given random generator
+//Deallocate the mnemonic phrase returned by cardano_bip39_generate_from_entropy
+#[no_mangle]
+pub extern "C" fn cardano_bip39_delete_mnemonics(mnemonics: *mut c_char) {
+    let mnemonics = unsafe { CString::from_raw(mnemonics) };
+    let mut bytes = mnemonics.into_bytes();
+    cardano::util::securemem::zero(&mut bytes);
+}
+
+///generate entropy with the random generator of the context
 #[no_mangle]
 pub extern "C" fn cardano_entropy_from_random(
+    ctx: ContextPtr,
     words: u8,
-    gen: extern "C" fn() -> c_uchar,
     entropy_ptr: *mut *const c_uchar,
     entropy_size: *mut c_uint,
-) -> CardanoBIP39ErrorCode {
-    let words = match bip39::Type::from_word_count(words as usize) {
-        Ok(v) => v,
-        Err(_) => return CardanoBIP39ErrorCode::invalid_word_count(),
-    };
-
-    let entropy = bip39::Entropy::generate(words, || gen()).to_vec();
-
-    out_return_vector(entropy, entropy_ptr, entropy_size);
-
-    CardanoBIP39ErrorCode::success()
+) -> CardanoResult {
+    context::handle_exception(ctx, |context| {
+        let words = bip39::Type::from_word_count(words as usize)
+            .map_err(|err| Error::new(CardanoResult::Bip39InvalidWordCount, format!("{}", err)))?;
+
+        let mut bytes = vec![0u8; words.to_key_size() / 8];
+        context.fill_random(&mut bytes)?;
+        out_return_vector(bytes, entropy_ptr, entropy_size)
+    })
 }
 
 ///return C array as an out parameter, the memory must be then deallocated with cardano_delete_entropy_array
-fn out_return_vector(mut to_return: Vec<u8>, out_pointer: *mut *const c_uchar, size: *mut c_uint) {
+pub(crate) fn out_return_vector(
+    mut to_return: Vec<u8>,
+    out_pointer: *mut *const c_uchar,
+    size: *mut c_uint,
+) -> error::Result<()> {
+    if out_pointer.is_null() || size.is_null() {
+        return Err(Error::new(
+            CardanoResult::NullPointer,
+            "`entropy` or `entropy_size` is a NULL pointer",
+        ));
+    }
+
     //Make sure the capacity is the same as the length to make deallocation simpler
     to_return.shrink_to_fit();
 
@@ -93,6 +175,8 @@ fn out_return_vector(mut to_return: Vec<u8>, out_pointer: *mut *const c_uchar, s
 
     //Copy the pointer to the out parameter
     unsafe { ptr::write(out_pointer, pointer) };
+
+    Ok(())
 }
 
 //Deallocate the rust-allocated memory for a Entropy array
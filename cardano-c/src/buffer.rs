@@ -0,0 +1,85 @@
+//! flat serialization of the objects of the C API
+//!
+//! The `cardano_<object>_to_bytes` functions give a buffer starting with
+//! the size of the serialization, as a 4 bytes big endian integer,
+//! followed by the serialization. The `cardano_<object>_from_bytes`
+//! functions take the serialization without the size. Every buffer is
+//! freed with `cardano_buffer_free`, so the bindings of the scripting
+//! languages (e.g. Python CFFI) can marshal the objects without knowing
+//! their layout.
+//!
+//! The keys are serialized as their raw bytes, the other objects in CBOR.
+
+use std::io::Cursor;
+use std::{mem, slice};
+
+use cardano::util::securemem;
+use cbor_event::{self, de::Deserializer};
+use error::{self, Error, Result};
+use types::CardanoResult;
+
+/// the size of the prefix holding the size of the serialization
+pub const BUFFER_PREFIX_SIZE: usize = 4;
+
+/// give the serialization to the C code as a length-prefixed buffer
+pub fn write_buffer(bytes: &[u8], buffer_out: *mut *mut u8) -> Result<()> {
+    let out = unsafe { error::as_mut(buffer_out, "buffer") }?;
+    if bytes.len() > u32::max_value() as usize {
+        return Err(Error::new(
+            CardanoResult::Error,
+            "the serialization is too big for a buffer",
+        ));
+    }
+    let mut buffer = Vec::with_capacity(BUFFER_PREFIX_SIZE + bytes.len());
+    let size = bytes.len() as u32;
+    buffer.extend_from_slice(&[
+        (size >> 24) as u8,
+        (size >> 16) as u8,
+        (size >> 8) as u8,
+        size as u8,
+    ]);
+    buffer.extend_from_slice(bytes);
+    let mut buffer = buffer.into_boxed_slice();
+    *out = buffer.as_mut_ptr();
+    mem::forget(buffer);
+    Ok(())
+}
+
+/// give the CBOR serialization of the value to the C code
+pub fn write_cbor<T: cbor_event::se::Serialize>(value: &T, buffer_out: *mut *mut u8) -> Result<()> {
+    let bytes = cbor!(value).expect("serialize in memory");
+    write_buffer(&bytes, buffer_out)
+}
+
+/// decode the value from its CBOR serialization, all the bytes must be
+/// used
+pub fn read_cbor<T: cbor_event::de::Deserialize>(bytes: &[u8], name: &str) -> Result<T> {
+    Deserializer::from(Cursor::new(bytes))
+        .deserialize_complete()
+        .map_err(|err: cbor_event::Error| invalid_serialization(name, err))
+}
+
+pub fn invalid_serialization<E: ::std::fmt::Display>(name: &str, error: E) -> Error {
+    Error::new(
+        CardanoResult::InvalidSerialization,
+        format!("invalid serialization of the {}: {}", name, error),
+    )
+}
+
+/// free a buffer returned by one of the `_to_bytes` functions, the bytes
+/// are zeroed first as the buffer may hold a key
+#[no_mangle]
+pub extern "C" fn cardano_buffer_free(buffer: *mut u8) {
+    if buffer.is_null() {
+        return;
+    }
+    let size = {
+        let prefix = unsafe { slice::from_raw_parts(buffer, BUFFER_PREFIX_SIZE) };
+        prefix
+            .iter()
+            .fold(0usize, |size, byte| (size << 8) | *byte as usize)
+    };
+    let mut buffer =
+        unsafe { Box::from_raw(slice::from_raw_parts_mut(buffer, BUFFER_PREFIX_SIZE + size)) };
+    securemem::zero(&mut buffer);
+}
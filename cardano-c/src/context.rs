@@ -0,0 +1,265 @@
+//! context of the C API
+//!
+//! The host creates a `cardano_context` once, with its allocator, its
+//! logging callback and its random generator, and gives it to every
+//! function of the C API creating an object.
+//!
+//! The allocator is global to the library: every allocation of the
+//! library, made with a context or not, goes through the allocator of the
+//! first context created with one. The memory allocated before is still
+//! freed with the system allocator, and a context with a different
+//! allocator cannot be created afterwards.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::os::raw::{c_char, c_int, c_void};
+use std::sync::atomic::{AtomicPtr, Ordering};
+use std::{cmp, ffi::CString, mem, ptr};
+
+use error::{self, Error, Result};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use types::CardanoResult;
+
+/// allocate `size` bytes aligned on `align`, NULL on failure
+pub type AllocFn = extern "C" fn(size: usize, align: usize, user_data: *mut c_void) -> *mut c_void;
+
+/// free the `size` bytes aligned on `align` allocated by the `AllocFn`
+pub type FreeFn =
+    extern "C" fn(ptr: *mut c_void, size: usize, align: usize, user_data: *mut c_void);
+
+/// receive a diagnostic of the library
+pub type LogFn = extern "C" fn(level: LogLevel, message: *const c_char, user_data: *mut c_void);
+
+/// fill the `size` bytes with random bytes, returning 0 on success
+pub type RngFn = extern "C" fn(bytes: *mut u8, size: usize, user_data: *mut c_void) -> c_int;
+
+/// level of a diagnostic given to the logging callback
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogLevel {
+    Error = 0,
+    Warning = 1,
+    Info = 2,
+    Debug = 3,
+}
+
+/// the callbacks of the host, each one is optional
+///
+/// The `user_data` is given back to every callback.
+#[repr(C)]
+pub struct ContextConfig {
+    pub alloc: Option<AllocFn>,
+    pub free: Option<FreeFn>,
+    pub log: Option<LogFn>,
+    pub rng: Option<RngFn>,
+    pub user_data: *mut c_void,
+}
+
+pub struct Context {
+    log: Option<LogFn>,
+    rng: Option<RngFn>,
+    user_data: *mut c_void,
+}
+
+/// C pointer to a context
+pub type ContextPtr = *mut Context;
+
+impl Context {
+    /// give the diagnostic to the logging callback of the host, if any
+    pub fn log(&self, level: LogLevel, message: &str) {
+        if let Some(log) = self.log {
+            let message = CString::new(message.replace('\0', " ")).unwrap();
+            log(level, message.as_ptr(), self.user_data);
+        }
+    }
+
+    /// fill the bytes with the random generator of the host, or the one
+    /// of the operating system if the host did not give one
+    pub fn fill_random(&self, bytes: &mut [u8]) -> Result<()> {
+        match self.rng {
+            Some(rng) => {
+                if rng(bytes.as_mut_ptr(), bytes.len(), self.user_data) != 0 {
+                    return Err(Error::new(
+                        CardanoResult::Error,
+                        "the random generator of the host failed",
+                    ));
+                }
+            }
+            None => {
+                let mut os_rng = OsRng::new().map_err(|err| {
+                    Error::new(
+                        CardanoResult::Error,
+                        format!("no random generator available: {}", err),
+                    )
+                })?;
+                os_rng.fill_bytes(bytes);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// run the body of a C API function taking a context like
+/// `error::handle_exception`, giving the description of the failure to
+/// the logging callback of the context
+pub fn handle_exception<F>(ctx: ContextPtr, f: F) -> CardanoResult
+where
+    F: FnOnce(&Context) -> Result<()>,
+{
+    let context = unsafe { ctx.as_ref() };
+    let result = error::handle_exception(|| f(unsafe { error::as_ref(ctx, "context") }?));
+    if let Some(context) = context {
+        if result != CardanoResult::Success {
+            error::with_last_error(|message| context.log(LogLevel::Error, message));
+        }
+    }
+    result
+}
+
+struct HostAllocator {
+    alloc: AllocFn,
+    free: FreeFn,
+    user_data: *mut c_void,
+}
+
+/// the allocator of the host, installed once and never freed: the memory
+/// it allocated can be freed until the end of the program
+static HOST_ALLOCATOR: AtomicPtr<HostAllocator> = AtomicPtr::new(ptr::null_mut());
+
+fn install_allocator(alloc: AllocFn, free: FreeFn, user_data: *mut c_void) -> Result<()> {
+    let allocator = Box::into_raw(Box::new(HostAllocator {
+        alloc,
+        free,
+        user_data,
+    }));
+    let installed = match HOST_ALLOCATOR.compare_exchange(
+        ptr::null_mut(),
+        allocator,
+        Ordering::AcqRel,
+        Ordering::Acquire,
+    ) {
+        Ok(_) => return Ok(()),
+        Err(installed) => installed,
+    };
+    unsafe { drop(Box::from_raw(allocator)) };
+    let installed = unsafe { &*installed };
+    if installed.alloc as usize == alloc as usize
+        && installed.free as usize == free as usize
+        && installed.user_data == user_data
+    {
+        Ok(())
+    } else {
+        Err(Error::new(
+            CardanoResult::InvalidArgument,
+            "a different allocator is already used by the library",
+        ))
+    }
+}
+
+const SYSTEM_ALLOCATION: u8 = 0;
+const HOST_ALLOCATION: u8 = 1;
+
+/// the allocator of the library, forwarding to the allocator of the host
+/// once installed.
+///
+/// Every allocation is prefixed with a header whose last byte tells which
+/// allocator made it, so the memory allocated before the allocator of the
+/// host was installed is freed with the system allocator.
+struct LibraryAllocator;
+
+#[global_allocator]
+static ALLOCATOR: LibraryAllocator = LibraryAllocator;
+
+/// the size of the header of the allocation, keeping the alignment
+fn header_size(layout: &Layout) -> usize {
+    cmp::max(layout.align(), mem::size_of::<usize>())
+}
+
+unsafe impl GlobalAlloc for LibraryAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let header = header_size(&layout);
+        let full = match Layout::from_size_align(layout.size() + header, layout.align()) {
+            Ok(full) => full,
+            Err(_) => return ptr::null_mut(),
+        };
+        let host = HOST_ALLOCATOR.load(Ordering::Acquire);
+        let (base, kind) = match host.as_ref() {
+            None => (System.alloc(full), SYSTEM_ALLOCATION),
+            Some(host) => (
+                (host.alloc)(full.size(), full.align(), host.user_data) as *mut u8,
+                HOST_ALLOCATION,
+            ),
+        };
+        if base.is_null() {
+            return base;
+        }
+        let ptr = base.add(header);
+        *ptr.sub(1) = kind;
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let header = header_size(&layout);
+        let full = Layout::from_size_align_unchecked(layout.size() + header, layout.align());
+        let base = ptr.sub(header);
+        if *ptr.sub(1) == SYSTEM_ALLOCATION {
+            System.dealloc(base, full)
+        } else {
+            let host = &*HOST_ALLOCATOR.load(Ordering::Acquire);
+            (host.free)(
+                base as *mut c_void,
+                full.size(),
+                full.align(),
+                host.user_data,
+            )
+        }
+    }
+}
+
+/// create a context with the given callbacks of the host, or the default
+/// ones if `config` is NULL.
+///
+/// `alloc` and `free` are given together or not at all. The context is to
+/// free with `cardano_context_delete`.
+#[no_mangle]
+pub extern "C" fn cardano_context_new(
+    config: *const ContextConfig,
+    context_out: *mut ContextPtr,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let context = match unsafe { config.as_ref() } {
+            None => Context {
+                log: None,
+                rng: None,
+                user_data: ptr::null_mut(),
+            },
+            Some(config) => {
+                match (config.alloc, config.free) {
+                    (None, None) => {}
+                    (Some(alloc), Some(free)) => install_allocator(alloc, free, config.user_data)?,
+                    _ => {
+                        return Err(Error::new(
+                            CardanoResult::InvalidArgument,
+                            "`alloc` and `free` are to be given together",
+                        ))
+                    }
+                }
+                Context {
+                    log: config.log,
+                    rng: config.rng,
+                    user_data: config.user_data,
+                }
+            }
+        };
+        unsafe { error::write_boxed(context_out, context, "context") }
+    })
+}
+
+/// free the context
+///
+/// The objects created with the context stay valid, and the allocator of
+/// the context stays in use by the library.
+#[no_mangle]
+pub extern "C" fn cardano_context_delete(context: ContextPtr) {
+    unsafe { Box::from_raw(context) };
+}
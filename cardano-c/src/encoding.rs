@@ -0,0 +1,105 @@
+//! base58 and bech32 encodings, the ones of the addresses and keys, for
+//! the host applications to display them
+
+use bech32::{Bech32, FromBase32, ToBase32};
+use cardano::util::base58;
+use error::{self, Error};
+use std::ffi::CString;
+use std::os::raw::c_char;
+use std::slice;
+use types::*;
+
+fn write_string(string: String, string_out: *mut *mut c_char) -> error::Result<()> {
+    let out = unsafe { error::as_mut(string_out, "string") }?;
+    *out = CString::new(string)
+        .expect("base58 and bech32 strings are ASCII")
+        .into_raw();
+    Ok(())
+}
+
+fn bech32_error(error: bech32::Error) -> Error {
+    Error::new(CardanoResult::InvalidBech32, format!("{}", error))
+}
+
+/// encode the bytes in base58, to free with `cardano_encoding_delete_string`
+#[no_mangle]
+pub extern "C" fn cardano_encode_base58(
+    c_bytes: *const u8,
+    bytes_size: usize,
+    string_out: *mut *mut c_char,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let bytes = unsafe { error::as_slice(c_bytes, bytes_size, "bytes") }?;
+        write_string(base58::encode(bytes), string_out)
+    })
+}
+
+/// decode the base58 string, the bytes are to free with
+/// `cardano_encoding_delete_bytes`
+#[no_mangle]
+pub extern "C" fn cardano_decode_base58(
+    c_string: *const c_char,
+    bytes_out: *mut *mut u8,
+    bytes_size_out: *mut usize,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let string = unsafe { error::as_c_str(c_string, "string") }?;
+        let bytes = base58::decode_bytes(string.to_bytes())
+            .map_err(|err| Error::new(CardanoResult::InvalidBase58, format!("{}", err)))?;
+        unsafe { error::write_bytes(bytes, bytes_out, bytes_size_out) }
+    })
+}
+
+/// encode the bytes in bech32 with the given human readable part (e.g.
+/// "ed25519_pk"), to free with `cardano_encoding_delete_string`
+#[no_mangle]
+pub extern "C" fn cardano_encode_bech32(
+    c_hrp: *const c_char,
+    c_bytes: *const u8,
+    bytes_size: usize,
+    string_out: *mut *mut c_char,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let hrp = unsafe { error::as_c_str(c_hrp, "hrp") }?
+            .to_str()
+            .map_err(|_| Error::new(CardanoResult::InvalidBech32, "hrp is not valid UTF-8"))?;
+        let bytes = unsafe { error::as_slice(c_bytes, bytes_size, "bytes") }?;
+        let bech32 = Bech32::new(hrp.to_owned(), bytes.to_base32()).map_err(bech32_error)?;
+        write_string(bech32.to_string(), string_out)
+    })
+}
+
+/// decode the bech32 string, checking its checksum. The human readable
+/// part is to free with `cardano_encoding_delete_string` and the bytes
+/// with `cardano_encoding_delete_bytes`
+#[no_mangle]
+pub extern "C" fn cardano_decode_bech32(
+    c_string: *const c_char,
+    hrp_out: *mut *mut c_char,
+    bytes_out: *mut *mut u8,
+    bytes_size_out: *mut usize,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let string = unsafe { error::as_c_str(c_string, "string") }?
+            .to_str()
+            .map_err(|_| Error::new(CardanoResult::InvalidBech32, "string is not valid UTF-8"))?;
+        let bech32: Bech32 = string.parse().map_err(bech32_error)?;
+        let bytes = Vec::<u8>::from_base32(bech32.data()).map_err(bech32_error)?;
+        // checked before giving the ownership of anything to the C code
+        unsafe { error::as_mut(hrp_out, "hrp") }?;
+        unsafe { error::write_bytes(bytes, bytes_out, bytes_size_out) }?;
+        write_string(bech32.hrp().to_owned(), hrp_out)
+    })
+}
+
+/// free a string returned by the encoding functions
+#[no_mangle]
+pub extern "C" fn cardano_encoding_delete_string(string: *mut c_char) {
+    unsafe { CString::from_raw(string) };
+}
+
+/// free the bytes returned by the decoding functions
+#[no_mangle]
+pub extern "C" fn cardano_encoding_delete_bytes(bytes: *mut u8, bytes_size: usize) {
+    unsafe { Box::from_raw(slice::from_raw_parts_mut(bytes, bytes_size)) };
+}
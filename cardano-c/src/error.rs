@@ -0,0 +1,241 @@
+//! error handling of the C API
+//!
+//! Every function of the C API (except the ones freeing memory) returns a
+//! `CardanoResult`. On failure, a description of the error is kept for the
+//! calling thread and can be retrieved with `cardano_error_message`.
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::{fmt, mem, panic, ptr, slice};
+
+use cardano::wallet::offline;
+use cardano::{coin, fee, input_selection, txbuild, units};
+use types::CardanoResult;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = RefCell::new(None);
+}
+
+/// error of a call to the C API: the code returned to the caller and the
+/// description kept for `cardano_error_message`
+#[derive(Debug)]
+pub struct Error {
+    code: CardanoResult,
+    message: String,
+}
+
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+impl Error {
+    pub fn new<S: Into<String>>(code: CardanoResult, message: S) -> Self {
+        Error {
+            code: code,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl From<txbuild::Error> for Error {
+    fn from(error: txbuild::Error) -> Self {
+        Error::new(CardanoResult::from(&error), format!("{}", error))
+    }
+}
+
+impl From<coin::Error> for Error {
+    fn from(error: coin::Error) -> Self {
+        Error::new(
+            CardanoResult::TransactionCoinOutOfBounds,
+            format!("{}", error),
+        )
+    }
+}
+
+impl From<fee::Error> for Error {
+    fn from(error: fee::Error) -> Self {
+        Error::new(CardanoResult::TransactionFeeError, format!("{}", error))
+    }
+}
+
+impl From<input_selection::Error> for Error {
+    fn from(error: input_selection::Error) -> Self {
+        let code = match error {
+            input_selection::Error::NoInputs => CardanoResult::TransactionNoInput,
+            input_selection::Error::NoOutputs => CardanoResult::TransactionNoOutput,
+            input_selection::Error::NotEnoughInput | input_selection::Error::NotEnoughFees => {
+                CardanoResult::TransactionNotEnoughInput
+            }
+            input_selection::Error::TxBuildError(error) => return error.into(),
+            input_selection::Error::CoinError(error) => return error.into(),
+            input_selection::Error::FeeError(error) => return error.into(),
+            input_selection::Error::CborError(_) => CardanoResult::Error,
+        };
+        Error::new(code, format!("{}", error))
+    }
+}
+
+impl From<offline::Error> for Error {
+    fn from(error: offline::Error) -> Self {
+        let code = match error {
+            offline::Error::InputsMismatch(_, _) | offline::Error::InputPointerMismatch(_) => {
+                CardanoResult::InvalidArgument
+            }
+            offline::Error::WitnessesMismatch(_, _) => CardanoResult::TransactionSignatureMismatch,
+            offline::Error::InvalidWitness(_) => CardanoResult::TransactionInvalidWitness,
+            offline::Error::TxBuildError(error) => return error.into(),
+        };
+        Error::new(code, format!("{}", error))
+    }
+}
+
+impl From<units::Error> for Error {
+    fn from(error: units::Error) -> Self {
+        match error {
+            units::Error::CoinError(error) => error.into(),
+            _ => Error::new(CardanoResult::InvalidAmount, format!("{}", error)),
+        }
+    }
+}
+
+fn set_last_error(message: String) {
+    // a description is not expected to contain a null byte, but it must
+    // not hide the error code either
+    let message =
+        CString::new(message).unwrap_or_else(|_| CString::new("invalid error message").unwrap());
+    LAST_ERROR.with(|last_error| *last_error.borrow_mut() = Some(message));
+}
+
+/// give the description of the last error of the calling thread, if any
+pub fn with_last_error<F>(f: F)
+where
+    F: FnOnce(&str),
+{
+    LAST_ERROR.with(|last_error| {
+        if let Some(ref message) = *last_error.borrow() {
+            f(&message.to_string_lossy())
+        }
+    })
+}
+
+/// run the body of a C API function, converting its outcome (including
+/// a panic, which must not unwind into the C code) into a `CardanoResult`
+pub fn handle_exception<F>(f: F) -> CardanoResult
+where
+    F: FnOnce() -> Result<()>,
+{
+    match panic::catch_unwind(panic::AssertUnwindSafe(f)) {
+        Ok(Ok(())) => CardanoResult::Success,
+        Ok(Err(error)) => {
+            set_last_error(error.message);
+            error.code
+        }
+        Err(_) => {
+            set_last_error("unexpected panic in the cardano library".to_owned());
+            CardanoResult::Panic
+        }
+    }
+}
+
+fn null_pointer(name: &str) -> Error {
+    Error::new(
+        CardanoResult::NullPointer,
+        format!("`{}` is a NULL pointer", name),
+    )
+}
+
+/// borrow the object behind the given C pointer
+pub unsafe fn as_ref<'a, T>(ptr: *const T, name: &str) -> Result<&'a T> {
+    ptr.as_ref().ok_or_else(|| null_pointer(name))
+}
+
+/// mutably borrow the object behind the given C pointer
+pub unsafe fn as_mut<'a, T>(ptr: *mut T, name: &str) -> Result<&'a mut T> {
+    ptr.as_mut().ok_or_else(|| null_pointer(name))
+}
+
+/// borrow the `len` elements array behind the given C pointer
+pub unsafe fn as_slice<'a, T>(ptr: *const T, len: usize, name: &str) -> Result<&'a [T]> {
+    if ptr.is_null() {
+        Err(null_pointer(name))
+    } else {
+        Ok(slice::from_raw_parts(ptr, len))
+    }
+}
+
+/// mutably borrow the `len` elements array behind the given C pointer
+pub unsafe fn as_mut_slice<'a, T>(ptr: *mut T, len: usize, name: &str) -> Result<&'a mut [T]> {
+    if ptr.is_null() {
+        Err(null_pointer(name))
+    } else {
+        Ok(slice::from_raw_parts_mut(ptr, len))
+    }
+}
+
+/// borrow the null terminated C string
+pub unsafe fn as_c_str<'a>(ptr: *const c_char, name: &str) -> Result<&'a CStr> {
+    if ptr.is_null() {
+        Err(null_pointer(name))
+    } else {
+        Ok(CStr::from_ptr(ptr))
+    }
+}
+
+/// write the value in the out parameter
+pub unsafe fn write<T>(out: *mut T, value: T, name: &str) -> Result<()> {
+    if out.is_null() {
+        Err(null_pointer(name))
+    } else {
+        ptr::write(out, value);
+        Ok(())
+    }
+}
+
+/// give the ownership of the value to the C code through the out parameter
+pub unsafe fn write_boxed<T>(out: *mut *mut T, value: T, name: &str) -> Result<()> {
+    // checked before boxing so the value is not leaked
+    if out.is_null() {
+        Err(null_pointer(name))
+    } else {
+        ptr::write(out, Box::into_raw(Box::new(value)));
+        Ok(())
+    }
+}
+
+/// give the ownership of the bytes to the C code through the out
+/// parameters, they are to free with the `_delete` function of the module
+pub unsafe fn write_bytes(
+    bytes: Vec<u8>,
+    bytes_out: *mut *mut u8,
+    bytes_size_out: *mut usize,
+) -> Result<()> {
+    if bytes_out.is_null() || bytes_size_out.is_null() {
+        return Err(Error::new(
+            CardanoResult::NullPointer,
+            "`bytes` or `bytes_size` is a NULL pointer",
+        ));
+    }
+    let mut bytes = bytes.into_boxed_slice();
+    write(bytes_size_out, bytes.len(), "bytes_size")?;
+    write(bytes_out, bytes.as_mut_ptr(), "bytes")?;
+    mem::forget(bytes);
+    Ok(())
+}
+
+/// description of the last error of a call to the C API from the calling
+/// thread, or NULL if no call failed yet
+///
+/// The returned string is owned by the library and stays valid until the
+/// next failing call from the same thread.
+#[no_mangle]
+pub extern "C" fn cardano_error_message() -> *const c_char {
+    LAST_ERROR.with(|last_error| match *last_error.borrow() {
+        Some(ref message) => message.as_ptr(),
+        None => ptr::null(),
+    })
+}
@@ -0,0 +1,48 @@
+//! objects shared between the threads of the host
+//!
+//! The wallets and the transaction builders are behind a `Handle`: every
+//! call of the C API locks the object for its duration, so the host (e.g.
+//! a Java or Go runtime) can use the same handle from several threads
+//! without data races. A handle can also be cloned, each clone being
+//! deleted independently and the object being freed with the last one.
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use error::{Error, Result};
+use types::CardanoResult;
+
+/// an internally synchronized, reference counted object
+pub struct Handle<T>(Arc<Mutex<T>>);
+
+impl<T> Handle<T> {
+    pub fn new(value: T) -> Self {
+        Handle(Arc::new(Mutex::new(value)))
+    }
+
+    /// another handle to the same object
+    pub fn clone_handle(&self) -> Self {
+        Handle(self.0.clone())
+    }
+}
+
+/// lock the object behind the given C pointer for the duration of the call
+///
+/// An object whose lock was held by a call which panicked may be left
+/// inconsistent and is not used anymore.
+pub unsafe fn lock<'a, T>(ptr: *const Handle<T>, name: &str) -> Result<MutexGuard<'a, T>> {
+    let handle = match ptr.as_ref() {
+        Some(handle) => handle,
+        None => {
+            return Err(Error::new(
+                CardanoResult::NullPointer,
+                format!("`{}` is a NULL pointer", name),
+            ))
+        }
+    };
+    handle.0.lock().map_err(|_| {
+        Error::new(
+            CardanoResult::Panic,
+            format!("`{}` was left inconsistent by a panic", name),
+        )
+    })
+}
@@ -1,85 +1,174 @@
+use buffer;
 use cardano::hdwallet;
-use std::{ptr, slice};
+use context::{self, ContextPtr};
+use error::{self, Error};
+use std::os::raw::c_int;
 use types::{CardanoResult, XPrvPtr, XPubPtr};
 
 #[no_mangle]
-pub extern "C" fn cardano_xprv_derive(c_xprv: XPrvPtr, index: u32) -> XPrvPtr {
-    let xprv = unsafe { c_xprv.as_mut() }.expect("Not a NULL PTR");
-    let child = xprv.derive(hdwallet::DerivationScheme::V2, index);
-    let child = Box::new(child);
-    Box::into_raw(child)
+pub extern "C" fn cardano_xprv_derive(
+    ctx: ContextPtr,
+    c_xprv: XPrvPtr,
+    index: u32,
+    xprv_out: *mut XPrvPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let xprv = unsafe { error::as_ref(c_xprv, "privkey") }?;
+        let child = xprv.derive(hdwallet::DerivationScheme::V2, index);
+        unsafe { error::write_boxed(xprv_out, child, "xprv") }
+    })
 }
 
+/// decode the private key from its XPRV_SIZE bytes
 #[no_mangle]
 pub extern "C" fn cardano_xprv_from_bytes(
-    c_xprv: *const u8,
+    ctx: ContextPtr,
+    c_bytes: *const u8,
+    bytes_size: usize,
     xprv_out: *mut XPrvPtr,
 ) -> CardanoResult {
-    let xprv_data = unsafe { slice::from_raw_parts(c_xprv, hdwallet::XPRV_SIZE) };
-    let array = {
-        let mut array = [0u8; 96];
-        array.copy_from_slice(xprv_data);
-        array
-    };
-    match hdwallet::XPrv::from_bytes_verified(array) {
-        Ok(r) => {
-            let xprv = Box::new(r);
-            unsafe { ptr::write(xprv_out, Box::into_raw(xprv)) };
-            CardanoResult::success()
+    context::handle_exception(ctx, |_| {
+        let bytes = unsafe { error::as_slice(c_bytes, bytes_size, "bytes") }?;
+        if bytes.len() != hdwallet::XPRV_SIZE {
+            return Err(buffer::invalid_serialization(
+                "private key",
+                format!("{} bytes instead of {}", bytes.len(), hdwallet::XPRV_SIZE),
+            ));
         }
-        Err(_) => CardanoResult::failure(),
-    }
+        let mut array = [0u8; hdwallet::XPRV_SIZE];
+        array.copy_from_slice(bytes);
+        let xprv = hdwallet::XPrv::from_bytes_verified(array)
+            .map_err(|err| Error::new(CardanoResult::InvalidKey, format!("{}", err)))?;
+        unsafe { error::write_boxed(xprv_out, xprv, "xprv") }
+    })
 }
 
+/// the XPRV_SIZE bytes of the private key, to free with `cardano_buffer_free`
 #[no_mangle]
-pub extern "C" fn cardano_xprv_to_bytes(c_xprv: XPrvPtr) -> *const u8 {
-    //Get the inner byte array without taking ownership
-    let slice: &[u8] = unsafe { (*c_xprv).as_ref() };
+pub extern "C" fn cardano_xprv_to_bytes(
+    c_xprv: XPrvPtr,
+    buffer_out: *mut *mut u8,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let xprv = unsafe { error::as_ref(c_xprv, "privkey") }?;
+        buffer::write_buffer(xprv.as_ref(), buffer_out)
+    })
+}
 
-    let mut vector: Vec<u8> = Vec::with_capacity(hdwallet::XPRV_SIZE);
-    vector.extend_from_slice(slice);
+#[no_mangle]
+pub extern "C" fn cardano_xprv_to_xpub(
+    ctx: ContextPtr,
+    c_xprv: XPrvPtr,
+    xpub_out: *mut XPubPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let xprv = unsafe { error::as_ref(c_xprv, "privkey") }?;
+        unsafe { error::write_boxed(xpub_out, xprv.public(), "xpub") }
+    })
+}
 
-    //Get pointer to the inner value
-    let ptr = vector.as_ptr();
+#[no_mangle]
+pub extern "C" fn cardano_xprv_delete(c_xprv: XPrvPtr) {
+    unsafe { Box::from_raw(c_xprv) };
+}
 
-    //Avoid running the destructor
-    std::mem::forget(vector);
-    ptr
+#[no_mangle]
+pub extern "C" fn cardano_xpub_derive(
+    ctx: ContextPtr,
+    c_xpub: XPubPtr,
+    index: u32,
+    xpub_out: *mut XPubPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let xpub = unsafe { error::as_ref(c_xpub, "pubkey") }?;
+        let child = xpub
+            .derive(hdwallet::DerivationScheme::V2, index)
+            .map_err(|err| Error::new(CardanoResult::InvalidDerivation, format!("{}", err)))?;
+        unsafe { error::write_boxed(xpub_out, child, "xpub") }
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn cardano_xprv_bytes_delete(bytes: *mut u8) {
-    let mut vector =
-        unsafe { Vec::from_raw_parts(bytes, hdwallet::XPRV_SIZE, hdwallet::XPRV_SIZE) };
-    cardano::util::securemem::zero(&mut vector);
-    std::mem::drop(vector)
+pub extern "C" fn cardano_xpub_delete(c_xpub: XPubPtr) {
+    unsafe { Box::from_raw(c_xpub) };
 }
 
+/// decode the public key from its XPUB_SIZE bytes
 #[no_mangle]
-pub extern "C" fn cardano_xprv_to_xpub(c_xprv: XPrvPtr) -> XPubPtr {
-    let xprv = unsafe { c_xprv.as_mut() }.expect("Not a NULL PTR");
-    let xpub = Box::new(xprv.public());
-    Box::into_raw(xpub)
+pub extern "C" fn cardano_xpub_from_bytes(
+    ctx: ContextPtr,
+    c_bytes: *const u8,
+    bytes_size: usize,
+    xpub_out: *mut XPubPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let bytes = unsafe { error::as_slice(c_bytes, bytes_size, "bytes") }?;
+        let xpub = hdwallet::XPub::from_slice(bytes)
+            .map_err(|err| buffer::invalid_serialization("public key", err))?;
+        unsafe { error::write_boxed(xpub_out, xpub, "xpub") }
+    })
 }
 
+/// the XPUB_SIZE bytes of the public key, to free with `cardano_buffer_free`
 #[no_mangle]
-pub extern "C" fn cardano_xprv_delete(c_xprv: XPrvPtr) {
-    unsafe { Box::from_raw(c_xprv) };
+pub extern "C" fn cardano_xpub_to_bytes(
+    c_xpub: XPubPtr,
+    buffer_out: *mut *mut u8,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let xpub = unsafe { error::as_ref(c_xpub, "pubkey") }?;
+        buffer::write_buffer(xpub.as_ref(), buffer_out)
+    })
 }
 
+/// sign the given message with the private key, writing the signature
+/// (SIGNATURE_SIZE bytes) in `signature_out`
+///
+/// This allows to prove the ownership of the key (and of its addresses)
+/// without exporting it.
 #[no_mangle]
-pub extern "C" fn cardano_xpub_derive(c_xpub: XPubPtr, index: u32) -> XPubPtr {
-    let xpub = unsafe { c_xpub.as_mut() }.expect("Not a NULL PTR");
-    match xpub.derive(hdwallet::DerivationScheme::V2, index) {
-        Ok(r) => {
-            let child = Box::new(r);
-            Box::into_raw(child)
-        }
-        Err(_) => ptr::null_mut(),
-    }
+pub extern "C" fn cardano_key_sign_message(
+    c_xprv: XPrvPtr,
+    message: *const u8,
+    message_size: usize,
+    signature_out: *mut u8,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let xprv = unsafe { error::as_ref(c_xprv, "privkey") }?;
+        let message = unsafe { error::as_slice(message, message_size, "message") }?;
+        let out =
+            unsafe { error::as_mut_slice(signature_out, hdwallet::SIGNATURE_SIZE, "signature") }?;
+        let signature: hdwallet::Signature<()> = xprv.sign(message);
+        out.copy_from_slice(signature.as_ref());
+        Ok(())
+    })
 }
 
+/// verify the signature (SIGNATURE_SIZE bytes) of the given message
+/// with the public key
+///
+/// `valid_out` is set to 1 if the message was signed by the private key
+/// of the given public key, 0 otherwise.
 #[no_mangle]
-pub extern "C" fn cardano_xpub_delete(c_xpub: XPubPtr) {
-    unsafe { Box::from_raw(c_xpub) };
+pub extern "C" fn cardano_key_verify_message(
+    c_xpub: XPubPtr,
+    message: *const u8,
+    message_size: usize,
+    signature: *const u8,
+    valid_out: *mut c_int,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let xpub = unsafe { error::as_ref(c_xpub, "pubkey") }?;
+        let message = unsafe { error::as_slice(message, message_size, "message") }?;
+        let signature =
+            unsafe { error::as_slice(signature, hdwallet::SIGNATURE_SIZE, "signature") }?;
+        let signature = hdwallet::Signature::<()>::from_slice(signature)
+            .expect("the signature is SIGNATURE_SIZE bytes");
+        let valid = if xpub.verify(message, &signature) {
+            1
+        } else {
+            0
+        };
+        unsafe { error::write(valid_out, valid, "valid") }
+    })
 }
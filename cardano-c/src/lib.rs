@@ -1,15 +1,32 @@
+extern crate bech32;
 extern crate cardano;
+#[macro_use]
+extern crate cbor_event;
+extern crate rand;
 
 pub mod address;
 pub mod bip39;
+pub mod buffer;
+pub mod context;
+pub mod encoding;
+pub mod error;
+pub mod handle;
 pub mod key;
+pub mod redeem;
 pub mod transaction;
 pub mod types;
+pub mod units;
 pub mod wallet;
 
 pub use address::*;
 pub use bip39::*;
+pub use buffer::cardano_buffer_free;
+pub use context::*;
+pub use encoding::*;
+pub use error::cardano_error_message;
 pub use key::*;
+pub use redeem::*;
 pub use transaction::*;
 pub use types::*;
+pub use units::*;
 pub use wallet::*;
@@ -0,0 +1,119 @@
+//! redeem addresses (ADA vouchers): their keys, addresses and the
+//! transactions redeeming them
+
+use buffer;
+use cardano::address::ExtendedAddr;
+use cardano::coin::Coin;
+use cardano::config::ProtocolMagic;
+use cardano::redeem;
+use cardano::txbuild;
+use context::{self, ContextPtr};
+use error;
+use types::*;
+
+/// decode the redeem key from its PRIVATEKEY_SIZE bytes
+#[no_mangle]
+pub extern "C" fn cardano_redeem_private_key_from_bytes(
+    ctx: ContextPtr,
+    c_bytes: *const u8,
+    bytes_size: usize,
+    key_out: *mut RedeemPrivateKeyPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let bytes = unsafe { error::as_slice(c_bytes, bytes_size, "bytes") }?;
+        let key = redeem::PrivateKey::from_slice(bytes)
+            .map_err(|err| buffer::invalid_serialization("redeem key", err))?;
+        unsafe { error::write_boxed(key_out, key, "key") }
+    })
+}
+
+/// the PRIVATEKEY_SIZE bytes of the redeem key, to free with
+/// `cardano_buffer_free`
+#[no_mangle]
+pub extern "C" fn cardano_redeem_private_key_to_bytes(
+    c_key: RedeemPrivateKeyPtr,
+    buffer_out: *mut *mut u8,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let key = unsafe { error::as_ref(c_key, "key") }?;
+        buffer::write_buffer(key.as_ref(), buffer_out)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn cardano_redeem_private_key_delete(c_key: RedeemPrivateKeyPtr) {
+    unsafe { Box::from_raw(c_key) };
+}
+
+/// write the public key (PUBLICKEY_SIZE bytes) of the redeem key in
+/// `public_key_out`
+#[no_mangle]
+pub extern "C" fn cardano_redeem_public_key(
+    c_key: RedeemPrivateKeyPtr,
+    public_key_out: *mut u8,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let key = unsafe { error::as_ref(c_key, "key") }?;
+        let out =
+            unsafe { error::as_mut_slice(public_key_out, redeem::PUBLICKEY_SIZE, "public_key") }?;
+        out.copy_from_slice(key.public().as_ref());
+        Ok(())
+    })
+}
+
+/// the redeem address of the key, to free with `cardano_address_delete`
+#[no_mangle]
+pub extern "C" fn cardano_redeem_address_new(
+    ctx: ContextPtr,
+    c_key: RedeemPrivateKeyPtr,
+    protocol_magic: ProtocolMagic,
+    address_out: *mut AddressPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let key = unsafe { error::as_ref(c_key, "key") }?;
+        let address = ExtendedAddr::new_redeem(key.public(), protocol_magic.into());
+        unsafe { error::write_boxed(address_out, address, "address") }
+    })
+}
+
+/// add the witness of the redeem key associated with the next input
+#[no_mangle]
+pub extern "C" fn cardano_redeem_add_witness(
+    tf: TransactionFinalizedPtr,
+    c_key: RedeemPrivateKeyPtr,
+    protocol_magic: ProtocolMagic,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let finalized = unsafe { error::as_mut(tf, "tf") }?;
+        let key = unsafe { error::as_ref(c_key, "key") }?;
+        finalized.add_redeem_witness(protocol_magic, key)?;
+        Ok(())
+    })
+}
+
+/// build the signed transaction redeeming the whole value of the redeem
+/// address UTxO to the given address, once the fee computed with the given
+/// fee algorithm is paid
+///
+/// The signed transaction is to free with `cardano_transaction_signed_delete`.
+#[no_mangle]
+pub extern "C" fn cardano_redeem_transaction(
+    ctx: ContextPtr,
+    c_key: RedeemPrivateKeyPtr,
+    protocol_magic: ProtocolMagic,
+    c_txo: TransactionOutputPointerPtr,
+    value: u64,
+    c_to: AddressPtr,
+    c_fee: LinearFeePtr,
+    txaux_out: *mut SignedTransactionPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let key = unsafe { error::as_ref(c_key, "key") }?;
+        let txo = unsafe { error::as_ref(c_txo, "txo") }?;
+        let to = unsafe { error::as_ref(c_to, "to") }?;
+        let fee_algo = unsafe { error::as_ref(c_fee, "fee") }?;
+        let value = Coin::new(value)?;
+        let txaux = txbuild::redeem(fee_algo, protocol_magic, key, txo, value, to.clone())?;
+        unsafe { error::write_boxed(txaux_out, txaux, "txaux") }
+    })
+}
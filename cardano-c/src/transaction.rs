@@ -1,23 +1,41 @@
-use cardano::coin::Coin;
+use buffer;
+use cardano::coin::{Coin, CoinDiff};
 use cardano::config::ProtocolMagic;
-use cardano::fee::{self, LinearFee};
+use cardano::fee::{FeeAlgorithm, LinearFee, Milli};
+use cardano::hdpayload::Path;
+use cardano::hdwallet::{self, Signature, XPub};
 use cardano::tx::{self, TxId, TxInWitness};
-use cardano::txbuild::{Error, TxBuilder, TxFinalized};
-use cardano::txutils::OutputPolicy;
+use cardano::txbuild::{self, TxBuilder, TxFinalized};
+use cardano::txutils::{Input, OutputPolicy};
 use cardano::util::try_from_slice::TryFromSlice;
-use std::{ptr, slice};
+use cardano::wallet::offline::{self, UnsignedTx};
+use cardano::wallet::{coin_selection::CoinSelection, scheme::SelectionPolicy};
+use cbor_event::{self, de::Deserializer, se::Serializer};
+use context::{self, ContextPtr};
+use error::{self, Error};
+use handle::{self, Handle};
 use types::*;
 
+use std::io::{BufRead, Cursor, Write};
+use std::slice;
+
+fn txid_from_ptr(c_txid: *const u8) -> error::Result<TxId> {
+    let txid_slice = unsafe { error::as_slice(c_txid, TxId::HASH_SIZE, "txid") }?;
+    Ok(TxId::try_from_slice(txid_slice).expect("HASH_SIZE bytes"))
+}
+
 #[no_mangle]
 pub extern "C" fn cardano_transaction_output_ptr_new(
+    ctx: ContextPtr,
     c_txid: *mut u8,
     index: u32,
-) -> TransactionOutputPointerPtr {
-    let txid_slice = unsafe { slice::from_raw_parts(c_txid, TxId::HASH_SIZE) };
-    let txid = TxId::try_from_slice(txid_slice).unwrap();
-    let txo = tx::TxoPointer::new(txid, index);
-    let b = Box::new(txo);
-    Box::into_raw(b)
+    txo_out: *mut TransactionOutputPointerPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let txid = txid_from_ptr(c_txid)?;
+        let txo = tx::TxoPointer::new(txid, index);
+        unsafe { error::write_boxed(txo_out, txo, "txo") }
+    })
 }
 
 #[no_mangle]
@@ -25,19 +43,72 @@ pub extern "C" fn cardano_transaction_output_ptr_delete(txo: TransactionOutputPo
     unsafe { Box::from_raw(txo) };
 }
 
+/// decode the transaction output pointer from its serialization (CBOR)
+#[no_mangle]
+pub extern "C" fn cardano_transaction_output_ptr_from_bytes(
+    ctx: ContextPtr,
+    c_bytes: *const u8,
+    bytes_size: usize,
+    txo_out: *mut TransactionOutputPointerPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let bytes = unsafe { error::as_slice(c_bytes, bytes_size, "bytes") }?;
+        let txo: tx::TxoPointer = buffer::read_cbor(bytes, "transaction output pointer")?;
+        unsafe { error::write_boxed(txo_out, txo, "txo") }
+    })
+}
+
+/// the serialization (CBOR) of the transaction output pointer, to free with
+/// `cardano_buffer_free`
+#[no_mangle]
+pub extern "C" fn cardano_transaction_output_ptr_to_bytes(
+    c_txo: TransactionOutputPointerPtr,
+    buffer_out: *mut *mut u8,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let txo = unsafe { error::as_ref(c_txo, "txo") }?;
+        buffer::write_cbor(txo, buffer_out)
+    })
+}
+
 #[no_mangle]
 pub extern "C" fn cardano_transaction_output_new(
+    ctx: ContextPtr,
     c_addr: AddressPtr,
     value: u64,
-) -> TransactionOutputPtr {
-    let address = unsafe { c_addr.as_ref() }.expect("Not a NULL PTR");
-    if let Ok(coin) = Coin::new(value) {
+    output_out: *mut TransactionOutputPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let address = unsafe { error::as_ref(c_addr, "c_addr") }?;
+        let coin = Coin::new(value)?;
         let txout = tx::TxOut::new(address.clone(), coin);
-        let b = Box::new(txout);
-        Box::into_raw(b)
-    } else {
-        ptr::null_mut()
-    }
+        unsafe { error::write_boxed(output_out, txout, "output") }
+    })
+}
+
+/// get a copy of the address of the output, to free with `cardano_address_delete`
+#[no_mangle]
+pub extern "C" fn cardano_transaction_output_get_address(
+    ctx: ContextPtr,
+    c_out: TransactionOutputPtr,
+    address_out: *mut AddressPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let txout = unsafe { error::as_ref(c_out, "output") }?;
+        unsafe { error::write_boxed(address_out, txout.address.clone(), "address") }
+    })
+}
+
+/// get the value of the output, in Lovelace
+#[no_mangle]
+pub extern "C" fn cardano_transaction_output_get_value(
+    c_out: TransactionOutputPtr,
+    value_out: *mut u64,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let txout = unsafe { error::as_ref(c_out, "output") }?;
+        unsafe { error::write(value_out, u64::from(txout.value), "value") }
+    })
 }
 
 #[no_mangle]
@@ -45,26 +116,105 @@ pub extern "C" fn cardano_transaction_output_delete(output: TransactionOutputPtr
     unsafe { Box::from_raw(output) };
 }
 
+/// decode the transaction output from its serialization (CBOR)
 #[no_mangle]
-pub extern "C" fn cardano_transaction_builder_new() -> TransactionBuilderPtr {
-    let builder = TxBuilder::new();
-    let b = Box::new(builder);
-    Box::into_raw(b)
+pub extern "C" fn cardano_transaction_output_from_bytes(
+    ctx: ContextPtr,
+    c_bytes: *const u8,
+    bytes_size: usize,
+    output_out: *mut TransactionOutputPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let bytes = unsafe { error::as_slice(c_bytes, bytes_size, "bytes") }?;
+        let output: tx::TxOut = buffer::read_cbor(bytes, "transaction output")?;
+        unsafe { error::write_boxed(output_out, output, "output") }
+    })
 }
 
+/// the serialization (CBOR) of the transaction output, to free with
+/// `cardano_buffer_free`
+#[no_mangle]
+pub extern "C" fn cardano_transaction_output_to_bytes(
+    c_output: TransactionOutputPtr,
+    buffer_out: *mut *mut u8,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let output = unsafe { error::as_ref(c_output, "output") }?;
+        buffer::write_cbor(output, buffer_out)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn cardano_transaction_builder_new(
+    ctx: ContextPtr,
+    builder_out: *mut TransactionBuilderPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| unsafe {
+        error::write_boxed(builder_out, Handle::new(TxBuilder::new()), "tb")
+    })
+}
+
+/// free the handle, the builder is freed with its last handle
 #[no_mangle]
 pub extern "C" fn cardano_transaction_builder_delete(tb: TransactionBuilderPtr) {
     unsafe { Box::from_raw(tb) };
 }
 
+/// create another handle to the same builder, to give to another thread
+///
+/// The calls on the handles of a builder are serialized. Each handle is to
+/// free with `cardano_transaction_builder_delete`.
+#[no_mangle]
+pub extern "C" fn cardano_transaction_builder_clone_handle(
+    ctx: ContextPtr,
+    tb: TransactionBuilderPtr,
+    builder_out: *mut TransactionBuilderPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let builder = unsafe { error::as_ref(tb, "tb") }?;
+        unsafe { error::write_boxed(builder_out, builder.clone_handle(), "tb") }
+    })
+}
+
+/// decode the transaction builder from its serialization (CBOR)
+#[no_mangle]
+pub extern "C" fn cardano_transaction_builder_from_bytes(
+    ctx: ContextPtr,
+    c_bytes: *const u8,
+    bytes_size: usize,
+    builder_out: *mut TransactionBuilderPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let bytes = unsafe { error::as_slice(c_bytes, bytes_size, "bytes") }?;
+        let builder: TxBuilder = buffer::read_cbor(bytes, "transaction builder")?;
+        unsafe { error::write_boxed(builder_out, Handle::new(builder), "builder") }
+    })
+}
+
+/// the serialization (CBOR) of the transaction builder, to free with
+/// `cardano_buffer_free`
+#[no_mangle]
+pub extern "C" fn cardano_transaction_builder_to_bytes(
+    tb: TransactionBuilderPtr,
+    buffer_out: *mut *mut u8,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let builder = unsafe { handle::lock(tb, "tb") }?;
+        buffer::write_cbor(&*builder, buffer_out)
+    })
+}
+
 #[no_mangle]
 pub extern "C" fn cardano_transaction_builder_add_output(
     tb: TransactionBuilderPtr,
     c_out: TransactionOutputPtr,
-) {
-    let builder = unsafe { tb.as_mut() }.expect("Not a NULL PTR");
-    let out = unsafe { c_out.as_ref() }.expect("Not a NULL PTR");
-    builder.add_output_value(out)
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let mut builder = unsafe { handle::lock(tb, "tb") }?;
+        let out = unsafe { error::as_ref(c_out, "txo") }?;
+        builder.add_output_value(out);
+        Ok(())
+    })
 }
 
 #[no_mangle]
@@ -72,15 +222,14 @@ pub extern "C" fn cardano_transaction_builder_add_input(
     tb: TransactionBuilderPtr,
     c_txo: TransactionOutputPointerPtr,
     value: u64,
-) -> CardanoTransactionErrorCode {
-    let builder = unsafe { tb.as_mut() }.expect("Not a NULL PTR");
-    let txo = unsafe { c_txo.as_ref() }.expect("Not a NULL PTR");
-    if let Ok(coin) = Coin::new(value) {
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let mut builder = unsafe { handle::lock(tb, "tb") }?;
+        let txo = unsafe { error::as_ref(c_txo, "c_txo") }?;
+        let coin = Coin::new(value)?;
         builder.add_input(txo, coin);
-        CardanoTransactionErrorCode::success()
-    } else {
-        CardanoTransactionErrorCode::coin_out_of_bounds()
-    }
+        Ok(())
+    })
 }
 
 #[no_mangle]
@@ -88,61 +237,197 @@ pub extern "C" fn cardano_transaction_builder_add_change_addr(
     tb: TransactionBuilderPtr,
     change_addr: AddressPtr,
 ) -> CardanoResult {
-    let builder = unsafe { tb.as_mut() }.expect("Not a NULL PTR");
-    let addr = unsafe { change_addr.as_ref() }.expect("Not a NULL PTR");
-    let fee = LinearFee::default();
+    error::handle_exception(|| {
+        let mut builder = unsafe { handle::lock(tb, "tb") }?;
+        let addr = unsafe { error::as_ref(change_addr, "change_addr") }?;
+        let fee = LinearFee::default();
 
-    let output_policy = OutputPolicy::One(addr.clone());
-    if let Ok(_) = builder.add_output_policy(&fee, &output_policy) {
-        CardanoResult::success()
-    } else {
-        CardanoResult::failure()
-    }
+        let output_policy = OutputPolicy::One(addr.clone());
+        builder.add_output_policy(&fee, &output_policy)?;
+        Ok(())
+    })
+}
+
+/// add an output sending the leftover of the inputs, once the outputs and
+/// the fee computed with the given algorithm are paid, to the change address
+///
+/// The value of the change output is written in `change_value_out`, it is
+/// 0 if the transaction is already balanced and no output is added.
+#[no_mangle]
+pub extern "C" fn cardano_transaction_builder_add_change_output(
+    tb: TransactionBuilderPtr,
+    c_fee: LinearFeePtr,
+    change_addr: AddressPtr,
+    change_value_out: *mut u64,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let mut builder = unsafe { handle::lock(tb, "tb") }?;
+        let fee_algo = unsafe { error::as_ref(c_fee, "fee") }?;
+        let addr = unsafe { error::as_ref(change_addr, "change_addr") }?;
+        let change_value = unsafe { error::as_mut(change_value_out, "change_value") }?;
+
+        let output_policy = OutputPolicy::One(addr.clone());
+        let change_outputs = builder.add_output_policy(fee_algo, &output_policy)?;
+        *change_value = change_outputs
+            .iter()
+            .map(|txout| u64::from(txout.value))
+            .sum();
+        Ok(())
+    })
+}
+
+/// the number of inputs added to the builder
+#[no_mangle]
+pub extern "C" fn cardano_transaction_builder_input_count(
+    tb: TransactionBuilderPtr,
+    count_out: *mut usize,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let builder = unsafe { handle::lock(tb, "tb") }?;
+        unsafe { error::write(count_out, builder.number_inputs(), "count") }
+    })
 }
 
+/// the number of outputs added to the builder, including the change output
 #[no_mangle]
-pub extern "C" fn cardano_transaction_builder_fee(tb: TransactionBuilderPtr) -> u64 {
-    let builder = unsafe { tb.as_mut() }.expect("Not a NULL PTR");
-    let fee_algo = LinearFee::default();
+pub extern "C" fn cardano_transaction_builder_output_count(
+    tb: TransactionBuilderPtr,
+    count_out: *mut usize,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let builder = unsafe { handle::lock(tb, "tb") }?;
+        unsafe { error::write(count_out, builder.number_outputs(), "count") }
+    })
+}
 
-    if let Ok(fee) = builder.calculate_fee(&fee_algo) {
-        u64::from(fee.to_coin())
-    } else {
-        // failed to calculate transaction fee, return zero
-        u64::from(fee::Fee::new(Coin::zero()).to_coin())
-    }
+fn out_of_range(index: usize, len: usize) -> Error {
+    Error::new(
+        CardanoResult::InvalidArgument,
+        format!("index {} is out of range, the builder has {}", index, len),
+    )
 }
 
+/// get a copy of the input of the given index and its value, the input is
+/// to free with `cardano_transaction_output_ptr_delete`
 #[no_mangle]
-pub extern "C" fn cardano_transaction_builder_balance(
+pub extern "C" fn cardano_transaction_builder_get_input(
+    ctx: ContextPtr,
     tb: TransactionBuilderPtr,
-    out: *mut *mut Balance,
-) -> CardanoTransactionErrorCode {
-    let builder = unsafe { tb.as_mut() }.expect("Not a NULL PTR");
-    let balance: Box<Balance> = match builder.balance(&LinearFee::default()) {
-        Ok(v) => Box::new(v.into()),
-        Err(e) => return e.into(),
-    };
+    index: usize,
+    txo_out: *mut TransactionOutputPointerPtr,
+    value_out: *mut u64,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let builder = unsafe { handle::lock(tb, "tb") }?;
+        let inputs = builder.inputs();
+        let &(ref txo, value) = inputs
+            .get(index)
+            .ok_or_else(|| out_of_range(index, inputs.len()))?;
+        unsafe {
+            error::write(value_out, u64::from(value), "value")?;
+            error::write_boxed(txo_out, txo.clone(), "txo")
+        }
+    })
+}
 
-    unsafe { ptr::write(out, Box::into_raw(balance)) };
+/// get a copy of the output of the given index, to free with
+/// `cardano_transaction_output_delete`
+#[no_mangle]
+pub extern "C" fn cardano_transaction_builder_get_output(
+    ctx: ContextPtr,
+    tb: TransactionBuilderPtr,
+    index: usize,
+    output_out: *mut TransactionOutputPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let builder = unsafe { handle::lock(tb, "tb") }?;
+        let outputs = builder.outputs();
+        let txout = outputs
+            .get(index)
+            .ok_or_else(|| out_of_range(index, outputs.len()))?;
+        unsafe { error::write_boxed(output_out, txout.clone(), "output") }
+    })
+}
 
-    CardanoTransactionErrorCode::success()
+#[no_mangle]
+pub extern "C" fn cardano_transaction_builder_fee(
+    tb: TransactionBuilderPtr,
+    fee_out: *mut u64,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let builder = unsafe { handle::lock(tb, "tb") }?;
+        let fee = builder.calculate_fee(&LinearFee::default())?;
+        unsafe { error::write(fee_out, u64::from(fee.to_coin()), "fee") }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn cardano_transaction_builder_balance(
+    ctx: ContextPtr,
+    tb: TransactionBuilderPtr,
+    out: *mut *mut Balance,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let builder = unsafe { handle::lock(tb, "tb") }?;
+        let balance: Balance = builder.balance(&LinearFee::default())?.into();
+        unsafe { error::write_boxed(out, balance, "out") }
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn cardano_transaction_builder_balance_without_fees(
+    ctx: ContextPtr,
     tb: TransactionBuilderPtr,
     out: *mut *mut Balance,
-) -> CardanoTransactionErrorCode {
-    let builder = unsafe { tb.as_mut() }.expect("Not a NULL PTR");
-    let balance: Box<Balance> = match builder.balance_without_fees() {
-        Ok(v) => Box::new(v.into()),
-        Err(e) => return e.into(),
-    };
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let builder = unsafe { handle::lock(tb, "tb") }?;
+        let balance: Balance = builder.balance_without_fees()?.into();
+        unsafe { error::write_boxed(out, balance, "out") }
+    })
+}
 
-    unsafe { ptr::write(out, Box::into_raw(balance)) };
+/// same as `cardano_transaction_builder_balance` with the fee computed by
+/// the given fee algorithm
+#[no_mangle]
+pub extern "C" fn cardano_transaction_builder_balance_with_fee(
+    ctx: ContextPtr,
+    tb: TransactionBuilderPtr,
+    c_fee: LinearFeePtr,
+    out: *mut *mut Balance,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let builder = unsafe { handle::lock(tb, "tb") }?;
+        let fee_algo = unsafe { error::as_ref(c_fee, "fee") }?;
+        let balance: Balance = builder.balance(fee_algo)?.into();
+        unsafe { error::write_boxed(out, balance, "out") }
+    })
+}
 
-    CardanoTransactionErrorCode::success()
+/// check the inputs cover the outputs and the fee computed by the given
+/// fee algorithm, so the transaction can be finalized
+///
+/// Returns `TransactionNotEnoughInput` if they do not, the error message
+/// gives the missing value.
+#[no_mangle]
+pub extern "C" fn cardano_transaction_builder_check_fee(
+    tb: TransactionBuilderPtr,
+    c_fee: LinearFeePtr,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let builder = unsafe { handle::lock(tb, "tb") }?;
+        let fee_algo = unsafe { error::as_ref(c_fee, "fee") }?;
+        match builder.balance(fee_algo)? {
+            CoinDiff::Negative(missing) => Err(Error::new(
+                CardanoResult::TransactionNotEnoughInput,
+                format!(
+                    "the inputs miss {} to cover the outputs and the fee",
+                    missing
+                ),
+            )),
+            CoinDiff::Zero | CoinDiff::Positive(_) => Ok(()),
+        }
+    })
 }
 
 #[no_mangle]
@@ -154,46 +439,109 @@ pub extern "C" fn cardano_transaction_balance_delete(balance: *mut Balance) {
 pub extern "C" fn cardano_transaction_builder_get_input_total(
     tb: TransactionBuilderPtr,
     out: *mut u64,
-) -> CardanoTransactionErrorCode {
-    let builder = unsafe { tb.as_mut() }.expect("Not a NULL PTR");
-    let result: u64 = match builder.get_input_total() {
-        Ok(number) => number.into(),
-        Err(e) => return e.into(),
-    };
-    unsafe { ptr::write(out, result) };
-    CardanoTransactionErrorCode::success()
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let builder = unsafe { handle::lock(tb, "tb") }?;
+        let result: u64 = builder.get_input_total()?.into();
+        unsafe { error::write(out, result, "output") }
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn cardano_transaction_builder_get_output_total(
     tb: TransactionBuilderPtr,
     out: *mut u64,
-) -> CardanoTransactionErrorCode {
-    let builder = unsafe { tb.as_mut() }.expect("Not a NULL PTR");
-    let result: u64 = match builder.get_output_total() {
-        Ok(number) => number.into(),
-        Err(e) => return e.into(),
-    };
-    unsafe { ptr::write(out, result) };
-    CardanoTransactionErrorCode::success()
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let builder = unsafe { handle::lock(tb, "tb") }?;
+        let result: u64 = builder.get_output_total()?.into();
+        unsafe { error::write(out, result, "output") }
+    })
+}
+
+/// plan a transaction paying the given outputs: select the UTxOs to spend
+/// with the given policy and send the leftover, once the fee computed
+/// with the given fee algorithm is paid, to the change address
+///
+/// The builder written in `builder_out` holds the selected inputs, the
+/// outputs and the change output, it is to free with
+/// `cardano_transaction_builder_delete`. The value of the change output
+/// is written in `change_value_out`, it is 0 if there is no change output
+/// (the leftover, if any, being too small to pay for its fee).
+#[no_mangle]
+pub extern "C" fn cardano_transaction_plan(
+    ctx: ContextPtr,
+    c_utxos: *const Utxo,
+    utxos_size: usize,
+    c_outputs: *const TransactionOutputPtr,
+    outputs_size: usize,
+    c_fee: LinearFeePtr,
+    policy: CardanoSelectionPolicy,
+    change_addr: AddressPtr,
+    builder_out: *mut TransactionBuilderPtr,
+    change_value_out: *mut u64,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let utxos = unsafe { error::as_slice(c_utxos, utxos_size, "utxos") }?;
+        let c_outputs = unsafe { error::as_slice(c_outputs, outputs_size, "outputs") }?;
+        let fee_algo = unsafe { error::as_ref(c_fee, "fee") }?;
+        let addr = unsafe { error::as_ref(change_addr, "change_addr") }?;
+
+        let mut inputs = Vec::with_capacity(utxos.len());
+        for utxo in utxos {
+            let txo = unsafe { error::as_ref(utxo.ptr, "utxo.ptr") }?;
+            let address = unsafe { error::as_ref(utxo.address, "utxo.address") }?;
+            let txout = tx::TxOut::new(address.clone(), Coin::new(utxo.value)?);
+            inputs.push(Input::new(txo.clone(), txout, ()));
+        }
+        let mut outputs = Vec::with_capacity(c_outputs.len());
+        for &c_out in c_outputs {
+            outputs.push(unsafe { error::as_ref(c_out, "output") }?.clone());
+        }
+
+        let output_policy = OutputPolicy::One(addr.clone());
+        let selection = SelectionPolicy::from(policy).select(
+            fee_algo,
+            inputs,
+            outputs.clone(),
+            &output_policy,
+        )?;
+
+        let mut builder = TxBuilder::new();
+        for input in selection.selected_inputs {
+            builder.add_input(&input.ptr, input.value.value);
+        }
+        for output in outputs.iter() {
+            builder.add_output_value(output);
+        }
+        let change_value: u64 = match builder.add_output_policy(fee_algo, &output_policy) {
+            Ok(change_outputs) => change_outputs
+                .iter()
+                .map(|txout| u64::from(txout.value))
+                .sum(),
+            // the leftover is lost in the fee
+            Err(txbuild::Error::TxOutputPolicyNotEnoughCoins(_)) => 0,
+            Err(err) => return Err(err.into()),
+        };
+
+        unsafe {
+            error::write(change_value_out, change_value, "change_value")?;
+            error::write_boxed(builder_out, Handle::new(builder), "tb")
+        }
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn cardano_transaction_builder_finalize(
+    ctx: ContextPtr,
     tb: TransactionBuilderPtr,
     tx_out: *mut TransactionPtr,
-) -> CardanoTransactionErrorCode {
-    let builder = unsafe { tb.as_mut() }.expect("Not a NULL PTR");
-    match builder.clone().make_tx() {
-        Ok(tx) => {
-            let boxed = Box::new(tx);
-            unsafe { ptr::write(tx_out, Box::into_raw(boxed)) };
-            CardanoTransactionErrorCode::success()
-        }
-        Err(Error::TxInvalidNoInput) => CardanoTransactionErrorCode::no_inputs(),
-        Err(Error::TxInvalidNoOutput) => CardanoTransactionErrorCode::no_outputs(),
-        _ => panic!("Shouldn't happen"),
-    }
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let builder = unsafe { handle::lock(tb, "tb") }?;
+        let tx = builder.clone().make_tx()?;
+        unsafe { error::write_boxed(tx_out, tx, "tx") }
+    })
 }
 
 #[no_mangle]
@@ -201,14 +549,45 @@ pub extern "C" fn cardano_transaction_delete(tx: TransactionPtr) {
     unsafe { Box::from_raw(tx) };
 }
 
+/// decode the transaction from its serialization (CBOR)
+#[no_mangle]
+pub extern "C" fn cardano_transaction_from_bytes(
+    ctx: ContextPtr,
+    c_bytes: *const u8,
+    bytes_size: usize,
+    tx_out: *mut TransactionPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let bytes = unsafe { error::as_slice(c_bytes, bytes_size, "bytes") }?;
+        let tx: tx::Tx = buffer::read_cbor(bytes, "transaction")?;
+        unsafe { error::write_boxed(tx_out, tx, "tx") }
+    })
+}
+
+/// the serialization (CBOR) of the transaction, to free with
+/// `cardano_buffer_free`
+#[no_mangle]
+pub extern "C" fn cardano_transaction_to_bytes(
+    c_tx: TransactionPtr,
+    buffer_out: *mut *mut u8,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let tx = unsafe { error::as_ref(c_tx, "tx") }?;
+        buffer::write_cbor(tx, buffer_out)
+    })
+}
+
 #[no_mangle]
 pub extern "C" fn cardano_transaction_finalized_new(
+    ctx: ContextPtr,
     c_tx: TransactionPtr,
-) -> TransactionFinalizedPtr {
-    let tx = unsafe { c_tx.as_ref() }.expect("Not a NULL PTR");
-    let finalized = TxFinalized::new(tx.clone());
-    let b = Box::new(finalized);
-    Box::into_raw(b)
+    finalized_out: *mut TransactionFinalizedPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let tx = unsafe { error::as_ref(c_tx, "c_tx") }?;
+        let finalized = TxFinalized::new(tx.clone());
+        unsafe { error::write_boxed(finalized_out, finalized, "tf") }
+    })
 }
 
 #[no_mangle]
@@ -216,45 +595,355 @@ pub extern "C" fn cardano_transaction_finalized_delete(c_txf: TransactionFinaliz
     unsafe { Box::from_raw(c_txf) };
 }
 
+/// decode the finalized transaction from its serialization (CBOR)
+#[no_mangle]
+pub extern "C" fn cardano_transaction_finalized_from_bytes(
+    ctx: ContextPtr,
+    c_bytes: *const u8,
+    bytes_size: usize,
+    tf_out: *mut TransactionFinalizedPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let bytes = unsafe { error::as_slice(c_bytes, bytes_size, "bytes") }?;
+        let tf: TxFinalized = buffer::read_cbor(bytes, "finalized transaction")?;
+        unsafe { error::write_boxed(tf_out, tf, "tf") }
+    })
+}
+
+/// the serialization (CBOR) of the finalized transaction, to free with
+/// `cardano_buffer_free`
+#[no_mangle]
+pub extern "C" fn cardano_transaction_finalized_to_bytes(
+    c_tf: TransactionFinalizedPtr,
+    buffer_out: *mut *mut u8,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let tf = unsafe { error::as_ref(c_tf, "tf") }?;
+        buffer::write_cbor(tf, buffer_out)
+    })
+}
+
 #[no_mangle]
 pub extern "C" fn cardano_transaction_finalized_add_witness(
     tb: TransactionFinalizedPtr,
     c_xprv: XPrvPtr,
     protocol_magic: ProtocolMagic,
     c_txid: *mut u8,
-) -> CardanoTransactionErrorCode {
-    let tf = unsafe { tb.as_mut() }.expect("Not a NULL PTR");
-    let xprv = unsafe { c_xprv.as_ref() }.expect("Not a NULL PTR");
-    let txid_slice = unsafe { slice::from_raw_parts(c_txid, TxId::HASH_SIZE) };
-    let txid = TxId::try_from_slice(txid_slice).unwrap();
-
-    let witness = TxInWitness::new(protocol_magic, xprv, &txid);
-    if let Ok(()) = tf.add_witness(witness) {
-        CardanoTransactionErrorCode::success()
-    } else {
-        CardanoTransactionErrorCode::signatures_exceeded()
-    }
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let tf = unsafe { error::as_mut(tb, "tf") }?;
+        let xprv = unsafe { error::as_ref(c_xprv, "c_xprv") }?;
+        let txid = txid_from_ptr(c_txid)?;
+
+        let witness = TxInWitness::new(protocol_magic, xprv, &txid);
+        tf.add_witness(witness)?;
+        Ok(())
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn cardano_transaction_finalized_output(
+    ctx: ContextPtr,
     tb: TransactionFinalizedPtr,
     txaux_out: *mut SignedTransactionPtr,
-) -> CardanoTransactionErrorCode {
-    let tf = unsafe { tb.as_mut() }.expect("Not a NULL PTR");
-    match tf.clone().make_txaux() {
-        Ok(txaux) => {
-            let boxed = Box::new(txaux);
-            unsafe { ptr::write(txaux_out, Box::into_raw(boxed)) };
-            CardanoTransactionErrorCode::success()
-        }
-        Err(Error::TxSignaturesMismatch) => CardanoTransactionErrorCode::signature_mismatch(),
-        Err(Error::TxOverLimit(_)) => CardanoTransactionErrorCode::over_limit(),
-        _ => panic!("Shouldn't happen"),
-    }
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let tf = unsafe { error::as_ref(tb, "tf") }?;
+        let txaux = tf.clone().make_txaux()?;
+        unsafe { error::write_boxed(txaux_out, txaux, "txaux") }
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn cardano_transaction_signed_delete(txaux: SignedTransactionPtr) {
     unsafe { Box::from_raw(txaux) };
 }
+
+/// decode the signed transaction from its serialization (CBOR)
+#[no_mangle]
+pub extern "C" fn cardano_transaction_signed_from_bytes(
+    ctx: ContextPtr,
+    c_bytes: *const u8,
+    bytes_size: usize,
+    txaux_out: *mut SignedTransactionPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let bytes = unsafe { error::as_slice(c_bytes, bytes_size, "bytes") }?;
+        let txaux: tx::TxAux = buffer::read_cbor(bytes, "signed transaction")?;
+        unsafe { error::write_boxed(txaux_out, txaux, "txaux") }
+    })
+}
+
+/// the serialization (CBOR) of the signed transaction, to free with
+/// `cardano_buffer_free`
+#[no_mangle]
+pub extern "C" fn cardano_transaction_signed_to_bytes(
+    c_txaux: SignedTransactionPtr,
+    buffer_out: *mut *mut u8,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let txaux = unsafe { error::as_ref(c_txaux, "txaux") }?;
+        buffer::write_cbor(txaux, buffer_out)
+    })
+}
+
+/// the size of a witness exchanged with the offline signer: the public key
+/// followed by the signature
+const WITNESS_SIZE: usize = hdwallet::XPUB_SIZE + hdwallet::SIGNATURE_SIZE;
+
+fn unsigned_from_bytes(c_unsigned: *const u8, unsigned_size: usize) -> error::Result<UnsignedTx> {
+    let bytes = unsafe { error::as_slice(c_unsigned, unsigned_size, "unsigned") }?;
+    Deserializer::from(Cursor::new(bytes))
+        .deserialize_complete()
+        .map_err(|err: cbor_event::Error| {
+            Error::new(
+                CardanoResult::InvalidArgument,
+                format!("invalid unsigned transaction: {}", err),
+            )
+        })
+}
+
+/// export the transaction of the builder, to be signed offline (e.g. by an
+/// air-gapped device), with the output spent by each of its inputs and the
+/// derivation path of the key to sign it with
+///
+/// The `inputs` are given in the order of the builder's inputs. The bytes
+/// written in `bytes_out` are to free with `cardano_transaction_bytes_delete`.
+#[no_mangle]
+pub extern "C" fn cardano_transaction_builder_export_unsigned(
+    tb: TransactionBuilderPtr,
+    protocol_magic: ProtocolMagic,
+    c_inputs: *const SignerInput,
+    inputs_size: usize,
+    bytes_out: *mut *mut u8,
+    bytes_size_out: *mut usize,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let builder = unsafe { handle::lock(tb, "tb") }?;
+        let c_inputs = unsafe { error::as_slice(c_inputs, inputs_size, "inputs") }?;
+        let tx = builder.clone().make_tx()?;
+        if c_inputs.len() != builder.inputs().len() {
+            return Err(
+                offline::Error::InputsMismatch(builder.inputs().len(), c_inputs.len()).into(),
+            );
+        }
+
+        let mut inputs = Vec::with_capacity(c_inputs.len());
+        for (c_input, &(_, value)) in c_inputs.iter().zip(builder.inputs().iter()) {
+            let address = unsafe { error::as_ref(c_input.address, "input.address") }?;
+            let path = unsafe { error::as_slice(c_input.path, c_input.path_size, "input.path") }?;
+            inputs.push(offline::SignerInput::new(
+                tx::TxOut::new(address.clone(), value),
+                Path::new(path.to_vec()),
+            ));
+        }
+        let unsigned = UnsignedTx::new(protocol_magic, tx, inputs)?;
+        let bytes = cbor!(&unsigned).expect("serialize the unsigned transaction");
+        unsafe { error::write_bytes(bytes, bytes_out, bytes_size_out) }
+    })
+}
+
+/// sign the exported unsigned transaction with the keys derived from the
+/// root key along the derivation paths of its inputs
+///
+/// The witnesses written in `witnesses_out`, `WITNESS_SIZE` bytes per input
+/// in the order of the inputs, are to free with
+/// `cardano_transaction_bytes_delete`.
+#[no_mangle]
+pub extern "C" fn cardano_transaction_unsigned_sign(
+    c_unsigned: *const u8,
+    unsigned_size: usize,
+    c_root_xprv: XPrvPtr,
+    witnesses_out: *mut *mut u8,
+    witnesses_size_out: *mut usize,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let unsigned = unsigned_from_bytes(c_unsigned, unsigned_size)?;
+        let root_xprv = unsafe { error::as_ref(c_root_xprv, "root_xprv") }?;
+
+        let witnesses = unsigned.sign(root_xprv, hdwallet::DerivationScheme::V2);
+        let mut bytes = Vec::with_capacity(witnesses.len() * WITNESS_SIZE);
+        for witness in witnesses {
+            match witness {
+                TxInWitness::PkWitness(xpub, signature) => {
+                    bytes.extend_from_slice(xpub.as_ref());
+                    bytes.extend_from_slice(signature.as_ref());
+                }
+                _ => unreachable!("the offline signer only produces public key witnesses"),
+            }
+        }
+        unsafe { error::write_bytes(bytes, witnesses_out, witnesses_size_out) }
+    })
+}
+
+/// apply the witnesses produced offline to the exported unsigned
+/// transaction
+///
+/// The witnesses are `witnesses_count` times `WITNESS_SIZE` bytes, the
+/// public key followed by the signature, in the order of the inputs. Each
+/// of them is checked against the address of the spent output and the
+/// transaction. The signed transaction is to free with
+/// `cardano_transaction_signed_delete`.
+#[no_mangle]
+pub extern "C" fn cardano_transaction_apply_witness(
+    ctx: ContextPtr,
+    c_unsigned: *const u8,
+    unsigned_size: usize,
+    c_witnesses: *const u8,
+    witnesses_count: usize,
+    txaux_out: *mut SignedTransactionPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let unsigned = unsigned_from_bytes(c_unsigned, unsigned_size)?;
+        let bytes =
+            unsafe { error::as_slice(c_witnesses, witnesses_count * WITNESS_SIZE, "witnesses") }?;
+
+        let witnesses = bytes
+            .chunks(WITNESS_SIZE)
+            .map(|witness| {
+                let (xpub, signature) = witness.split_at(hdwallet::XPUB_SIZE);
+                TxInWitness::PkWitness(
+                    XPub::from_slice(xpub).expect("XPUB_SIZE bytes"),
+                    Signature::from_slice(signature).expect("SIGNATURE_SIZE bytes"),
+                )
+            })
+            .collect();
+        let txaux = unsigned.apply_witnesses(witnesses)?;
+        unsafe { error::write_boxed(txaux_out, txaux, "txaux") }
+    })
+}
+
+/// free the bytes returned by `cardano_transaction_builder_export_unsigned`
+/// or `cardano_transaction_unsigned_sign`
+#[no_mangle]
+pub extern "C" fn cardano_transaction_bytes_delete(bytes: *mut u8, bytes_size: usize) {
+    unsafe { Box::from_raw(slice::from_raw_parts_mut(bytes, bytes_size)) };
+}
+
+#[no_mangle]
+pub extern "C" fn cardano_fee_linear_new(
+    ctx: ContextPtr,
+    constant: u64,
+    coefficient_millis: u64,
+    fee_out: *mut LinearFeePtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let fee = LinearFee::new(
+            Milli::integral(constant),
+            Milli::new(coefficient_millis / 1000, coefficient_millis % 1000),
+        );
+        unsafe { error::write_boxed(fee_out, fee, "fee") }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn cardano_fee_linear_default(
+    ctx: ContextPtr,
+    fee_out: *mut LinearFeePtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| unsafe {
+        error::write_boxed(fee_out, LinearFee::default(), "fee")
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn cardano_fee_linear_delete(c_fee: LinearFeePtr) {
+    unsafe { Box::from_raw(c_fee) };
+}
+
+/// the linear fee is serialized as the array of its constant and of its
+/// coefficient, in thousandths of Lovelace
+struct LinearFeeSerialization(LinearFee);
+
+impl cbor_event::se::Serialize for LinearFeeSerialization {
+    fn serialize<'se, W: Write>(
+        &self,
+        serializer: &'se mut Serializer<W>,
+    ) -> cbor_event::Result<&'se mut Serializer<W>> {
+        serializer
+            .write_array(cbor_event::Len::Len(2))?
+            .write_unsigned_integer(self.0.constant.as_millis())?
+            .write_unsigned_integer(self.0.coefficient.as_millis())
+    }
+}
+
+impl cbor_event::de::Deserialize for LinearFeeSerialization {
+    fn deserialize<R: BufRead>(raw: &mut Deserializer<R>) -> cbor_event::Result<Self> {
+        raw.tuple(2, "linear fee")?;
+        let constant = raw.unsigned_integer()?;
+        let coefficient = raw.unsigned_integer()?;
+        Ok(LinearFeeSerialization(LinearFee::new(
+            Milli::new(constant / 1000, constant % 1000),
+            Milli::new(coefficient / 1000, coefficient % 1000),
+        )))
+    }
+}
+
+/// decode the linear fee from its serialization (CBOR)
+#[no_mangle]
+pub extern "C" fn cardano_fee_linear_from_bytes(
+    ctx: ContextPtr,
+    c_bytes: *const u8,
+    bytes_size: usize,
+    fee_out: *mut LinearFeePtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let bytes = unsafe { error::as_slice(c_bytes, bytes_size, "bytes") }?;
+        let fee: LinearFeeSerialization = buffer::read_cbor(bytes, "linear fee")?;
+        unsafe { error::write_boxed(fee_out, fee.0, "fee") }
+    })
+}
+
+/// the serialization (CBOR) of the linear fee, to free with
+/// `cardano_buffer_free`
+#[no_mangle]
+pub extern "C" fn cardano_fee_linear_to_bytes(
+    c_fee: LinearFeePtr,
+    buffer_out: *mut *mut u8,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let fee = unsafe { error::as_ref(c_fee, "fee") }?;
+        buffer::write_cbor(&LinearFeeSerialization(*fee), buffer_out)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn cardano_fee_linear_estimate(
+    c_fee: LinearFeePtr,
+    num_bytes: u64,
+    out: *mut u64,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let fee_algo = unsafe { error::as_ref(c_fee, "fee") }?;
+        let fee = fee_algo.estimate(num_bytes as usize)?;
+        unsafe { error::write(out, u64::from(fee.to_coin()), "out") }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn cardano_fee_transaction_builder(
+    c_fee: LinearFeePtr,
+    tb: TransactionBuilderPtr,
+    out: *mut u64,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let fee_algo = unsafe { error::as_ref(c_fee, "fee") }?;
+        let builder = unsafe { handle::lock(tb, "tb") }?;
+        let fee = builder.calculate_fee(fee_algo)?;
+        unsafe { error::write(out, u64::from(fee.to_coin()), "out") }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn cardano_fee_signed_transaction(
+    c_fee: LinearFeePtr,
+    txaux: SignedTransactionPtr,
+    out: *mut u64,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let fee_algo = unsafe { error::as_ref(c_fee, "fee") }?;
+        let txaux = unsafe { error::as_ref(txaux, "txaux") }?;
+        let fee = fee_algo.calculate_for_txaux(txaux)?;
+        unsafe { error::write(out, u64::from(fee.to_coin()), "out") }
+    })
+}
@@ -1,100 +1,161 @@
 use cardano::address;
 use cardano::coin::CoinDiff;
+use cardano::fee;
 use cardano::hdwallet;
+use cardano::redeem;
 use cardano::tx;
 use cardano::txbuild;
-use cardano::wallet::bip44;
-use std::os::raw::c_int;
+use cardano::wallet::{bip44, rindex, scheme};
+use handle::Handle;
 
 /// C result type, where 0 is success and !0 is failure
+///
+/// The values are part of the C API (`cardano_result` in cardano.h): they
+/// must not be renumbered, new codes are only appended to their group.
 #[repr(C)]
-pub struct CardanoResult(c_int);
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CardanoResult {
+    Success = 0,
+    /// unspecified failure
+    Error = 1,
+    /// one of the given pointers is NULL
+    NullPointer = 2,
+    /// the library panicked, this is a bug
+    Panic = 3,
+    /// one of the given parameters is out of its domain
+    InvalidArgument = 4,
+    /// the string is not a valid amount of ADA or Lovelace
+    InvalidAmount = 5,
+    /// the bytes are not a valid serialization of the object
+    InvalidSerialization = 6,
+
+    /// the string is not valid base58
+    InvalidBase58 = 10,
+    /// the bytes are not a valid address
+    InvalidAddress = 11,
+    /// the bytes are not a valid extended private key
+    InvalidKey = 12,
+    /// the key cannot be derived with the given index
+    InvalidDerivation = 13,
+    /// the address does not contain a derivation path
+    NoDerivationPath = 14,
+    /// the derivation path cannot be decrypted with the given HD passphrase
+    InvalidHDPassphrase = 15,
+    /// the string is not valid bech32
+    InvalidBech32 = 16,
+
+    /// a word is not in the dictionary
+    Bip39InvalidWord = 20,
+    /// the mnemonic phrase checksum is incorrect
+    Bip39InvalidChecksum = 21,
+    /// the word count is not one of the supported ones
+    Bip39InvalidWordCount = 22,
+    /// the entropy size is not one of the supported ones
+    Bip39InvalidEntropySize = 23,
+
+    /// the transaction has no outputs
+    TransactionNoOutput = 30,
+    /// the transaction has no inputs
+    TransactionNoInput = 31,
+    /// the number of signatures does not match the number of inputs
+    TransactionSignatureMismatch = 32,
+    /// the transaction is too big
+    TransactionOverLimit = 33,
+    /// the transaction has already enough signatures
+    TransactionSignaturesExceeded = 34,
+    /// value is to big, max = 45000000000000000
+    TransactionCoinOutOfBounds = 35,
+    /// the fee cannot be computed with the given fee algorithm
+    TransactionFeeError = 36,
+    /// the inputs do not cover the outputs and the fee
+    TransactionNotEnoughInput = 37,
+    /// the leftover cannot cover the fee of the change output
+    TransactionOutputPolicyNotEnoughCoins = 38,
+    /// the witness does not match the address of the spent output or does
+    /// not sign the transaction
+    TransactionInvalidWitness = 39,
+
+    /// the alias is already used by an account of the wallet
+    AccountAliasTaken = 40,
+    /// the index is not a valid BIP44 account index
+    InvalidAccountIndex = 41,
+    /// there is no account with the given alias
+    AccountNotFound = 42,
+}
 
-impl CardanoResult {
-    pub fn success() -> CardanoResult {
-        CardanoResult(0)
-    }
-    pub fn failure() -> CardanoResult {
-        CardanoResult(1)
+impl<'a> From<&'a txbuild::Error> for CardanoResult {
+    fn from(err: &'a txbuild::Error) -> Self {
+        match err {
+            &txbuild::Error::TxInvalidNoInput => CardanoResult::TransactionNoInput,
+            &txbuild::Error::TxInvalidNoOutput => CardanoResult::TransactionNoOutput,
+            &txbuild::Error::TxNotEnoughTotalInput => CardanoResult::TransactionNotEnoughInput,
+            &txbuild::Error::TxOverLimit(_) => CardanoResult::TransactionOverLimit,
+            &txbuild::Error::TxOutputPolicyNotEnoughCoins(_) => {
+                CardanoResult::TransactionOutputPolicyNotEnoughCoins
+            }
+            &txbuild::Error::TxSignaturesExceeded => CardanoResult::TransactionSignaturesExceeded,
+            &txbuild::Error::TxSignaturesMismatch => CardanoResult::TransactionSignatureMismatch,
+            &txbuild::Error::CoinError(_) => CardanoResult::TransactionCoinOutOfBounds,
+            &txbuild::Error::FeeError(_) => CardanoResult::TransactionFeeError,
+        }
     }
 }
 
-///Struct for representing the possible BIP39 error codes
+/// Type of the spending data of an address
 #[repr(C)]
-pub struct CardanoBIP39ErrorCode(c_int);
-
-impl CardanoBIP39ErrorCode {
-    pub fn success() -> Self {
-        CardanoBIP39ErrorCode(0)
-    }
-
-    ///Error representing a word not in the dictionary
-    pub fn invalid_word() -> Self {
-        CardanoBIP39ErrorCode(1)
-    }
-
-    ///Error representing that a mnemonic phrase checksum is incorrect
-    pub fn invalid_checksum() -> Self {
-        CardanoBIP39ErrorCode(2)
-    }
+pub enum CardanoAddressKind {
+    PubKey,
+    Script,
+    Redeem,
+}
 
-    ///Error representing that the word count is not one of the supported ones
-    pub fn invalid_word_count() -> Self {
-        CardanoBIP39ErrorCode(3)
+impl From<address::AddrType> for CardanoAddressKind {
+    fn from(addr_type: address::AddrType) -> Self {
+        match addr_type {
+            address::AddrType::ATPubKey => CardanoAddressKind::PubKey,
+            address::AddrType::ATScript => CardanoAddressKind::Script,
+            address::AddrType::ATRedeem => CardanoAddressKind::Redeem,
+        }
     }
 }
 
+/// An unspent transaction output available to plan a transaction
 #[repr(C)]
-pub struct CardanoTransactionErrorCode(c_int);
-
-impl CardanoTransactionErrorCode {
-    pub fn success() -> Self {
-        CardanoTransactionErrorCode(0)
-    }
-
-    ///Transaction has no outputs
-    pub fn no_outputs() -> Self {
-        CardanoTransactionErrorCode(1)
-    }
-
-    ///Transaction has no inputs
-    pub fn no_inputs() -> Self {
-        CardanoTransactionErrorCode(2)
-    }
-
-    ///Number of signatures does not match the number of witnesses
-    pub fn signature_mismatch() -> Self {
-        CardanoTransactionErrorCode(3)
-    }
-
-    ///Transaction is too big
-    pub fn over_limit() -> Self {
-        CardanoTransactionErrorCode(4)
-    }
+pub struct Utxo {
+    pub ptr: TransactionOutputPointerPtr,
+    pub address: AddressPtr,
+    /// the value of the output, in Lovelace
+    pub value: u64,
+}
 
-    ///Transaction has already enough signatures
-    pub fn signatures_exceeded() -> Self {
-        CardanoTransactionErrorCode(5)
-    }
+/// The output spent by an input of a transaction to sign offline and the
+/// derivation path, from the root key, of the key to sign it with
+#[repr(C)]
+pub struct SignerInput {
+    pub address: AddressPtr,
+    pub path: *const u32,
+    pub path_size: usize,
+}
 
-    ///value is to big, max = 45000000000000000
-    pub fn coin_out_of_bounds() -> Self {
-        CardanoTransactionErrorCode(6)
-    }
+/// Strategy to select the UTxOs spent by a transaction
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub enum CardanoSelectionPolicy {
+    /// select the UTxOs in the given order
+    FirstMatchFirst,
+    /// select the largest UTxOs first
+    LargestFirst,
+    /// select random UTxOs, then improve the selection so the change is
+    /// about the size of the payments
+    RandomImprove,
 }
 
-impl From<txbuild::Error> for CardanoTransactionErrorCode {
-    fn from(err: txbuild::Error) -> Self {
-        match err {
-            txbuild::Error::TxInvalidNoInput => Self::no_inputs(),
-            txbuild::Error::TxInvalidNoOutput => Self::no_outputs(),
-            txbuild::Error::TxNotEnoughTotalInput => unimplemented!(),
-            txbuild::Error::TxOverLimit(_) => Self::over_limit(),
-            txbuild::Error::TxOutputPolicyNotEnoughCoins(_) => unimplemented!(),
-            txbuild::Error::TxSignaturesExceeded => Self::signatures_exceeded(),
-            txbuild::Error::TxSignaturesMismatch => Self::signature_mismatch(),
-            txbuild::Error::CoinError(_) => Self::coin_out_of_bounds(),
-            txbuild::Error::FeeError(_) => unimplemented!(),
+impl From<CardanoSelectionPolicy> for scheme::SelectionPolicy {
+    fn from(policy: CardanoSelectionPolicy) -> Self {
+        match policy {
+            CardanoSelectionPolicy::FirstMatchFirst => scheme::SelectionPolicy::FirstMatchFirst,
+            CardanoSelectionPolicy::LargestFirst => scheme::SelectionPolicy::LargestFirst,
+            CardanoSelectionPolicy::RandomImprove => scheme::SelectionPolicy::RandomImprove,
         }
     }
 }
@@ -140,11 +201,18 @@ pub type XPubPtr = *mut hdwallet::XPub;
 /// C pointer to a signature
 pub type SignaturePtr = *mut hdwallet::Signature<tx::Tx>;
 
+/// C pointer to a redeem (ADA voucher) private key
+pub type RedeemPrivateKeyPtr = *mut redeem::PrivateKey;
+
 /// C pointer to a (parsed) Extended Address
 pub type AddressPtr = *mut address::ExtendedAddr;
 
-/// C pointer to a Wallet
-pub type WalletPtr = *mut bip44::Wallet;
+/// C pointer to a Wallet, shareable between threads
+pub type WalletPtr = *mut Handle<bip44::Wallet>;
+
+/// C pointer to a Daedalus (2 levels of random indexes) Wallet, shareable
+/// between threads
+pub type DaedalusWalletPtr = *mut Handle<rindex::Wallet>;
 
 /// C pointer to an Account;
 pub type AccountPtr = *mut bip44::Account<hdwallet::XPub>;
@@ -161,8 +229,11 @@ pub type TransactionPtr = *mut tx::Tx;
 /// C pointer to a signed Transaction;
 pub type SignedTransactionPtr = *mut tx::TxAux;
 
-/// C pointer to a Transaction builder;
-pub type TransactionBuilderPtr = *mut txbuild::TxBuilder;
+/// C pointer to a Transaction builder, shareable between threads;
+pub type TransactionBuilderPtr = *mut Handle<txbuild::TxBuilder>;
 
 /// C pointer to a Transaction finalized;
 pub type TransactionFinalizedPtr = *mut txbuild::TxFinalized;
+
+/// C pointer to a linear fee algorithm;
+pub type LinearFeePtr = *mut fee::LinearFee;
@@ -0,0 +1,79 @@
+//! amounts in ADA and Lovelace as strings, see `cardano::units`
+
+use cardano::coin::Coin;
+use cardano::units;
+use error::{self, Error};
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use types::*;
+
+fn amount_str<'a>(c_amount: &'a CStr) -> error::Result<&'a str> {
+    c_amount
+        .to_str()
+        .map_err(|_| Error::new(CardanoResult::InvalidAmount, "amount is not valid UTF-8"))
+}
+
+fn write_string(string: String, string_out: *mut *mut c_char) -> error::Result<()> {
+    let out = unsafe { error::as_mut(string_out, "string") }?;
+    *out = CString::new(string)
+        .expect("amounts have no null byte")
+        .into_raw();
+    Ok(())
+}
+
+/// parse an amount in ADA, e.g. "12.345678 ADA" or "0.5", into Lovelace
+#[no_mangle]
+pub extern "C" fn cardano_units_parse_ada(
+    c_amount: *const c_char,
+    lovelace_out: *mut u64,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let amount = amount_str(unsafe { error::as_c_str(c_amount, "amount") }?)?;
+        let coin = units::parse_ada(amount)?;
+        unsafe { error::write(lovelace_out, u64::from(coin), "lovelace") }
+    })
+}
+
+/// parse an amount in Lovelace, e.g. "12345678 lovelace" or "42"
+#[no_mangle]
+pub extern "C" fn cardano_units_parse_lovelace(
+    c_amount: *const c_char,
+    lovelace_out: *mut u64,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let amount = amount_str(unsafe { error::as_c_str(c_amount, "amount") }?)?;
+        let coin = units::parse_lovelace(amount)?;
+        unsafe { error::write(lovelace_out, u64::from(coin), "lovelace") }
+    })
+}
+
+/// format the Lovelace amount in ADA, e.g. "12.345678 ADA", to free with
+/// `cardano_units_delete_string`
+#[no_mangle]
+pub extern "C" fn cardano_units_format_ada(
+    lovelace: u64,
+    string_out: *mut *mut c_char,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let coin = Coin::new(lovelace)?;
+        write_string(units::format_ada(coin), string_out)
+    })
+}
+
+/// format the Lovelace amount, e.g. "12345678 lovelace", to free with
+/// `cardano_units_delete_string`
+#[no_mangle]
+pub extern "C" fn cardano_units_format_lovelace(
+    lovelace: u64,
+    string_out: *mut *mut c_char,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let coin = Coin::new(lovelace)?;
+        write_string(units::format_lovelace(coin), string_out)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn cardano_units_delete_string(string: *mut c_char) {
+    unsafe { CString::from_raw(string) };
+}
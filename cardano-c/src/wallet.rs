@@ -1,22 +1,30 @@
 use cardano::address;
 use cardano::bip;
+use cardano::bip::bip44::BIP44_SOFT_UPPER_BOUND;
 use cardano::config::ProtocolMagic;
 use cardano::hdwallet;
-use cardano::wallet::bip44;
+use cardano::paperwallet;
 use cardano::wallet::scheme::Wallet;
+use cardano::wallet::{bip44, rindex};
 
-use std::os::raw::c_char;
+use cbor_event::{self, de::Deserializer, se::Serializer};
+use std::collections::HashSet;
+use std::io::{BufRead, Write};
+use std::os::raw::{c_char, c_int, c_uchar, c_uint};
 use std::{ffi, ptr, slice};
 
-use address::ffi_address_to_base58;
-use types::{AccountPtr, CardanoResult, WalletPtr};
+use address::{ffi_address_from_base58, ffi_address_to_base58};
+use bip39::out_return_vector;
+use buffer;
+use context::{self, ContextPtr};
+use error::{self, Error};
+use handle::{self, Handle};
+use types::{AccountPtr, AddressPtr, CardanoResult, DaedalusWalletPtr, WalletPtr};
 
 /* ******************************************************************************* *
  *                                  Wallet object                                  *
  * ******************************************************************************* */
 
-// TODO: one of the major missing element is a proper clean error handling
-
 /// Create a HD BIP44 compliant Wallet from the given entropy and a password
 ///
 /// Password can be empty
@@ -24,41 +32,58 @@ use types::{AccountPtr, CardanoResult, WalletPtr};
 /// use the function `cardano_wallet_delete` to free all the memory associated to the returned
 /// object. This function may fail if:
 ///
-/// - panic: if there is no more memory to allocate the object to return
-/// - return `failure` if the given seed_ptr is of invalid length
-/// - return 'success' when not
+/// - return `Bip39InvalidEntropySize` if the given entropy is of invalid length
+/// - return `Success` when not
 ///
 #[no_mangle]
 pub extern "C" fn cardano_wallet_new(
+    ctx: ContextPtr,
     entropy_ptr: *const u8,  /* expecting entropy ptr ... */
     entropy_size: usize,     /* entropy size */
     password_ptr: *const u8, /* password ptr */
     password_size: usize,    /* password size */
     wallet_out: *mut WalletPtr,
 ) -> CardanoResult {
-    let entropy_slice = unsafe { slice::from_raw_parts(entropy_ptr, entropy_size) };
-    let password = unsafe { slice::from_raw_parts(password_ptr, password_size) };
+    context::handle_exception(ctx, |_| {
+        let entropy_slice = unsafe { error::as_slice(entropy_ptr, entropy_size, "entropy_ptr") }?;
+        let password = unsafe { error::as_slice(password_ptr, password_size, "password_ptr") }?;
 
-    let entropy = match bip::bip39::Entropy::from_slice(entropy_slice) {
-        Err(_) => return CardanoResult::failure(),
-        Ok(e) => e,
-    };
+        let entropy = bip::bip39::Entropy::from_slice(entropy_slice).map_err(|err| {
+            Error::new(CardanoResult::Bip39InvalidEntropySize, format!("{}", err))
+        })?;
 
-    let wallet = bip44::Wallet::from_entropy(&entropy, &password, hdwallet::DerivationScheme::V2);
+        let wallet =
+            bip44::Wallet::from_entropy(&entropy, &password, hdwallet::DerivationScheme::V2);
 
-    let wallet_box = Box::new(wallet);
-    unsafe { ptr::write(wallet_out, Box::into_raw(wallet_box)) };
-    CardanoResult::success()
+        unsafe { error::write_boxed(wallet_out, Handle::new(wallet), "wallet") }
+    })
 }
 
 /// take ownership of the given pointer and free the associated data
 ///
-/// The data must be a valid Wallet created by `cardano_wallet_new`.
+/// The data must be a valid Wallet created by `cardano_wallet_new` or
+/// `cardano_wallet_clone_handle`. The wallet is freed with its last handle.
 #[no_mangle]
 pub extern "C" fn cardano_wallet_delete(wallet_ptr: WalletPtr) {
     unsafe { Box::from_raw(wallet_ptr) };
 }
 
+/// create another handle to the same wallet, to give to another thread
+///
+/// The calls on the handles of a wallet are serialized. Each handle is to
+/// free with `cardano_wallet_delete`.
+#[no_mangle]
+pub extern "C" fn cardano_wallet_clone_handle(
+    ctx: ContextPtr,
+    wallet_ptr: WalletPtr,
+    wallet_out: *mut WalletPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let wallet = unsafe { error::as_ref(wallet_ptr, "wallet") }?;
+        unsafe { error::write_boxed(wallet_out, wallet.clone_handle(), "wallet_out") }
+    })
+}
+
 /* ******************************************************************************* *
  *                                 Account object                                  *
  * ******************************************************************************* */
@@ -73,17 +98,25 @@ pub extern "C" fn cardano_wallet_delete(wallet_ptr: WalletPtr) {
 ///
 #[no_mangle]
 pub extern "C" fn cardano_account_create(
+    ctx: ContextPtr,
     wallet_ptr: WalletPtr,
-    account_alias: *mut c_char,
+    account_alias: *const c_char,
     account_index: u32,
-) -> AccountPtr {
-    let wallet = unsafe { wallet_ptr.as_mut() }.expect("Not a NULL PTR");
-    let account_alias = unsafe { ffi::CStr::from_ptr(account_alias).to_string_lossy() };
-
-    let account = wallet.create_account(&account_alias, account_index);
-    let account = Box::new(account.public());
+    account_out: *mut AccountPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let mut wallet = unsafe { handle::lock(wallet_ptr, "wallet") }?;
+        let account_alias = unsafe { error::as_c_str(account_alias, "alias") }?.to_string_lossy();
+        if account_out.is_null() {
+            return Err(Error::new(
+                CardanoResult::NullPointer,
+                "`account` is a NULL pointer",
+            ));
+        }
 
-    Box::into_raw(account)
+        let account = wallet.create_account(&account_alias, account_index);
+        unsafe { error::write_boxed(account_out, account.public(), "account") }
+    })
 }
 
 /// take ownership of the given pointer and free the memory associated
@@ -92,6 +125,80 @@ pub extern "C" fn cardano_account_delete(account_ptr: AccountPtr) {
     unsafe { Box::from_raw(account_ptr) };
 }
 
+/// the account is serialized as the array of the version of its
+/// derivation scheme (1 or 2) and of its public key
+struct AccountSerialization(bip44::Account<hdwallet::XPub>);
+
+impl cbor_event::se::Serialize for AccountSerialization {
+    fn serialize<'se, W: Write>(
+        &self,
+        serializer: &'se mut Serializer<W>,
+    ) -> cbor_event::Result<&'se mut Serializer<W>> {
+        let version = match self.0.derivation_scheme() {
+            hdwallet::DerivationScheme::V1 => 1,
+            hdwallet::DerivationScheme::V2 => 2,
+        };
+        serializer
+            .write_array(cbor_event::Len::Len(2))?
+            .write_unsigned_integer(version)?
+            .serialize(&**self.0)
+    }
+}
+
+impl cbor_event::de::Deserialize for AccountSerialization {
+    fn deserialize<R: BufRead>(raw: &mut Deserializer<R>) -> cbor_event::Result<Self> {
+        raw.tuple(2, "account")?;
+        let derivation_scheme = match raw.unsigned_integer()? {
+            1 => hdwallet::DerivationScheme::V1,
+            2 => hdwallet::DerivationScheme::V2,
+            version => {
+                return Err(cbor_event::Error::CustomError(format!(
+                    "unknown derivation scheme version {}",
+                    version
+                )))
+            }
+        };
+        let xpub: hdwallet::XPub = cbor_event::de::Deserialize::deserialize(raw)?;
+        Ok(AccountSerialization(bip44::Account::new(
+            xpub.into(),
+            derivation_scheme,
+        )))
+    }
+}
+
+/// decode the account from its serialization
+#[no_mangle]
+pub extern "C" fn cardano_account_from_bytes(
+    ctx: ContextPtr,
+    c_bytes: *const u8,
+    bytes_size: usize,
+    account_out: *mut AccountPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let bytes = unsafe { error::as_slice(c_bytes, bytes_size, "bytes") }?;
+        let account: AccountSerialization = buffer::read_cbor(bytes, "account")?;
+        unsafe { error::write_boxed(account_out, account.0, "account") }
+    })
+}
+
+/// the serialization (CBOR) of the account, to free with
+/// `cardano_buffer_free`
+#[no_mangle]
+pub extern "C" fn cardano_account_to_bytes(
+    account_ptr: AccountPtr,
+    buffer_out: *mut *mut u8,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let account = unsafe { error::as_ref(account_ptr, "account") }?;
+        buffer::write_cbor(&AccountSerialization(account.clone()), buffer_out)
+    })
+}
+
+/// generate `num_indices` addresses of the account, starting at `from_index`
+///
+/// Fewer addresses are generated if the last index would not be a soft
+/// derivation index, the number of written addresses is written in
+/// `num_addresses_out`.
 #[no_mangle]
 pub extern "C" fn cardano_account_generate_addresses(
     account_ptr: AccountPtr,
@@ -100,23 +207,29 @@ pub extern "C" fn cardano_account_generate_addresses(
     num_indices: usize,
     addresses_ptr: *mut *mut c_char,
     protocol_magic: ProtocolMagic,
-) -> usize {
-    let account = unsafe { account_ptr.as_mut() }.expect("Not a NULL PTR");
+    num_addresses_out: *mut usize,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let account = unsafe { error::as_ref(account_ptr, "account") }?;
+        if addresses_ptr.is_null() || num_addresses_out.is_null() {
+            return Err(Error::new(
+                CardanoResult::NullPointer,
+                "`addresses_ptr` or `num_addresses` is a NULL pointer",
+            ));
+        }
 
-    let addr_type = if internal {
-        bip44::AddrType::Internal
-    } else {
-        bip44::AddrType::External
-    };
+        let addr_type = if internal {
+            bip44::AddrType::Internal
+        } else {
+            bip44::AddrType::External
+        };
 
-    account
-        .address_generator(addr_type, from_index)
-        .expect("we expect the derivation to happen successfully")
-        .take(num_indices)
-        .enumerate()
-        .map(|(idx, xpub)| {
-            let address = address::ExtendedAddr::new_simple(*xpub.unwrap(), protocol_magic.into());
-            let c_address = ffi_address_to_base58(&address);
+        // derive all the addresses first, so nothing is leaked on failure
+        let addresses =
+            derive_addresses(account, addr_type, from_index, num_indices, protocol_magic)?;
+
+        let num_addresses = addresses.len();
+        for (idx, c_address) in addresses.into_iter().enumerate() {
             // make sure the ptr is stored at the right place with alignments and all
             unsafe {
                 ptr::write(
@@ -124,8 +237,9 @@ pub extern "C" fn cardano_account_generate_addresses(
                     c_address.into_raw(),
                 )
             };
-        })
-        .count()
+        }
+        unsafe { error::write(num_addresses_out, num_addresses, "num_addresses") }
+    })
 }
 
 #[no_mangle]
@@ -137,3 +251,475 @@ pub extern "C" fn cardano_account_delete_addresses(addresses_ptr: *mut *mut c_ch
         };
     }
 }
+
+/// derive the addresses of the account in one call: `count` addresses of
+/// the given BIP44 chain (0 for the external addresses, 1 for the internal
+/// ones), starting at `from_index`
+///
+/// The array of the addresses, in base58, is allocated by the library and
+/// is to free with `cardano_wallet_addresses_delete`. Fewer addresses are
+/// derived if the last index would not be a soft derivation index, the
+/// number of addresses is written in `count_out`.
+#[no_mangle]
+pub extern "C" fn cardano_wallet_addresses_derive(
+    account_ptr: AccountPtr,
+    chain: u32,
+    from_index: u32,
+    count: usize,
+    protocol_magic: ProtocolMagic,
+    addresses_out: *mut *mut *mut c_char,
+    count_out: *mut usize,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let account = unsafe { error::as_ref(account_ptr, "account") }?;
+        if addresses_out.is_null() || count_out.is_null() {
+            return Err(Error::new(
+                CardanoResult::NullPointer,
+                "`addresses` or `count` is a NULL pointer",
+            ));
+        }
+        let addr_type = match chain {
+            0 => bip44::AddrType::External,
+            1 => bip44::AddrType::Internal,
+            _ => {
+                return Err(Error::new(
+                    CardanoResult::InvalidArgument,
+                    format!("invalid BIP44 chain {}, expected 0 or 1", chain),
+                ))
+            }
+        };
+
+        let addresses = derive_addresses(account, addr_type, from_index, count, protocol_magic)?;
+        let addresses: Box<[*mut c_char]> = addresses
+            .into_iter()
+            .map(ffi::CString::into_raw)
+            .collect::<Vec<_>>()
+            .into_boxed_slice();
+        let count = addresses.len();
+        unsafe {
+            error::write(count_out, count, "count")?;
+            error::write(
+                addresses_out,
+                Box::into_raw(addresses) as *mut *mut c_char,
+                "addresses",
+            )
+        }
+    })
+}
+
+/// free the array of addresses returned by `cardano_wallet_addresses_derive`
+#[no_mangle]
+pub extern "C" fn cardano_wallet_addresses_delete(addresses: *mut *mut c_char, count: usize) {
+    let addresses = unsafe { Box::from_raw(slice::from_raw_parts_mut(addresses, count)) };
+    for &address in addresses.iter() {
+        unsafe { ffi::CString::from_raw(address) };
+    }
+}
+
+fn derive_addresses(
+    account: &bip44::Account<hdwallet::XPub>,
+    addr_type: bip44::AddrType,
+    from_index: u32,
+    count: usize,
+    protocol_magic: ProtocolMagic,
+) -> error::Result<Vec<ffi::CString>> {
+    account
+        .address_generator(addr_type, from_index)
+        .map_err(invalid_derivation)?
+        .take(count)
+        .map(|xpub| -> error::Result<ffi::CString> {
+            let xpub = xpub.map_err(invalid_derivation)?;
+            let address = address::ExtendedAddr::new_simple(*xpub, protocol_magic.into());
+            Ok(ffi_address_to_base58(&address))
+        })
+        .collect()
+}
+
+fn invalid_derivation(err: hdwallet::Error) -> Error {
+    Error::new(CardanoResult::InvalidDerivation, format!("{}", err))
+}
+
+/* ******************************************************************************* *
+ *                                Wallet's accounts                                *
+ * ******************************************************************************* */
+
+/// create a new account and register it in the wallet under the given alias
+///
+/// Unlike `cardano_account_create`, this function fails if the alias is
+/// already used by an account of the wallet (`AccountAliasTaken`) or if the
+/// index is not a valid BIP44 account index (`InvalidAccountIndex`, it needs
+/// to be lower than 2^31).
+///
+/// The returned account is a copy of the one registered in the wallet, use
+/// `cardano_account_delete` to free it.
+#[no_mangle]
+pub extern "C" fn cardano_wallet_account_create(
+    ctx: ContextPtr,
+    wallet_ptr: WalletPtr,
+    account_alias: *const c_char,
+    account_index: u32,
+    account_out: *mut AccountPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let mut wallet = unsafe { handle::lock(wallet_ptr, "wallet") }?;
+        let account_alias = unsafe { error::as_c_str(account_alias, "alias") }?.to_string_lossy();
+        if account_out.is_null() {
+            return Err(Error::new(
+                CardanoResult::NullPointer,
+                "`account` is a NULL pointer",
+            ));
+        }
+
+        if account_index >= BIP44_SOFT_UPPER_BOUND {
+            return Err(Error::new(
+                CardanoResult::InvalidAccountIndex,
+                format!("{} is not a BIP44 account index", account_index),
+            ));
+        }
+        if wallet.list_accounts().contains_key(account_alias.as_ref()) {
+            return Err(Error::new(
+                CardanoResult::AccountAliasTaken,
+                format!("the alias `{}` is already used", account_alias),
+            ));
+        }
+
+        let account = wallet.create_account(&account_alias, account_index);
+        unsafe { error::write_boxed(account_out, account.public(), "account") }
+    })
+}
+
+/// retrieve the account registered in the wallet with the given alias
+///
+/// returns `AccountNotFound` if there is no such account, use
+/// `cardano_account_delete` to free the returned account.
+#[no_mangle]
+pub extern "C" fn cardano_wallet_account_get(
+    ctx: ContextPtr,
+    wallet_ptr: WalletPtr,
+    account_alias: *const c_char,
+    account_out: *mut AccountPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let wallet = unsafe { handle::lock(wallet_ptr, "wallet") }?;
+        let account_alias = unsafe { error::as_c_str(account_alias, "alias") }?.to_string_lossy();
+
+        match wallet.list_accounts().get(account_alias.as_ref()) {
+            None => Err(Error::new(
+                CardanoResult::AccountNotFound,
+                format!("no account with the alias `{}`", account_alias),
+            )),
+            Some(account) => unsafe {
+                error::write_boxed(account_out, account.public(), "account")
+            },
+        }
+    })
+}
+
+/// the number of accounts registered in the wallet
+#[no_mangle]
+pub extern "C" fn cardano_wallet_account_count(
+    wallet_ptr: WalletPtr,
+    count_out: *mut usize,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let wallet = unsafe { handle::lock(wallet_ptr, "wallet") }?;
+        unsafe { error::write(count_out, wallet.list_accounts().len(), "count") }
+    })
+}
+
+/// list the aliases of the accounts registered in the wallet, sorted
+///
+/// at most `size` aliases are written in `aliases_ptr`, the number of
+/// written aliases is written in `num_aliases_out`. Free them with
+/// `cardano_wallet_account_list_delete`.
+#[no_mangle]
+pub extern "C" fn cardano_wallet_account_list(
+    wallet_ptr: WalletPtr,
+    aliases_ptr: *mut *mut c_char,
+    size: usize,
+    num_aliases_out: *mut usize,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let wallet = unsafe { handle::lock(wallet_ptr, "wallet") }?;
+        if aliases_ptr.is_null() || num_aliases_out.is_null() {
+            return Err(Error::new(
+                CardanoResult::NullPointer,
+                "`aliases_ptr` or `num_aliases` is a NULL pointer",
+            ));
+        }
+
+        let num_aliases = wallet
+            .list_accounts()
+            .keys()
+            .take(size)
+            .enumerate()
+            .map(|(idx, alias)| {
+                let c_alias =
+                    ffi::CString::new(alias.as_str()).expect("aliases are created from C strings");
+                unsafe {
+                    ptr::write(
+                        aliases_ptr.wrapping_offset(idx as isize),
+                        c_alias.into_raw(),
+                    )
+                };
+            })
+            .count();
+        unsafe { error::write(num_aliases_out, num_aliases, "num_aliases") }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn cardano_wallet_account_list_delete(aliases_ptr: *mut *mut c_char, size: usize) {
+    for i in 0..size {
+        unsafe {
+            let ptr = aliases_ptr.offset(i as isize);
+            ffi::CString::from_raw(*ptr);
+        };
+    }
+}
+
+fn account_is_used(
+    account: &bip44::Account<hdwallet::XPub>,
+    used_addresses: &HashSet<String>,
+    gap_limit: u32,
+    protocol_magic: ProtocolMagic,
+) -> bool {
+    let addresses = account
+        .address_generator(bip44::AddrType::External, 0)
+        .expect("we expect the derivation to happen successfully");
+
+    let mut gap = 0;
+    for xpub in addresses {
+        let address = address::ExtendedAddr::new_simple(*xpub.unwrap(), protocol_magic.into());
+        if used_addresses.contains(&format!("{}", address)) {
+            return true;
+        }
+        gap += 1;
+        if gap >= gap_limit {
+            break;
+        }
+    }
+    false
+}
+
+/// discover the accounts of the wallet, following BIP44's account discovery
+///
+/// Accounts are scanned in order from index 0: the external addresses of
+/// an account are derived until `gap_limit` consecutive addresses are not
+/// in the given used addresses (base58 C strings). The discovery stops at
+/// the first account without any used address.
+///
+/// Every discovered account is registered in the wallet (if not already)
+/// with its index in decimal as alias ("0", "1", ...) and the number of
+/// discovered accounts is written in `num_accounts_out`.
+///
+/// This function fails if one of the used addresses is not a valid address
+/// (`InvalidBase58` or `InvalidAddress`) or if `gap_limit` is zero
+/// (`InvalidArgument`).
+#[no_mangle]
+pub extern "C" fn cardano_wallet_account_discover(
+    wallet_ptr: WalletPtr,
+    used_addresses_ptr: *const *const c_char,
+    num_used_addresses: usize,
+    gap_limit: u32,
+    protocol_magic: ProtocolMagic,
+    num_accounts_out: *mut u32,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let mut wallet = unsafe { handle::lock(wallet_ptr, "wallet") }?;
+        if gap_limit == 0 {
+            return Err(Error::new(
+                CardanoResult::InvalidArgument,
+                "the gap limit must be greater than 0",
+            ));
+        }
+
+        let used_addresses_ptr =
+            unsafe { error::as_slice(used_addresses_ptr, num_used_addresses, "used_addresses") }?;
+        let mut used_addresses = HashSet::with_capacity(num_used_addresses);
+        for c_address in used_addresses_ptr {
+            let address_base58 = unsafe { error::as_c_str(*c_address, "used_addresses") }?;
+            let address = ffi_address_from_base58(address_base58)?;
+            // normalize the encoding before comparing with the derived addresses
+            used_addresses.insert(format!("{}", address));
+        }
+
+        let derivation_scheme = wallet.derivation_scheme();
+        let mut account_index = 0;
+        while account_index < BIP44_SOFT_UPPER_BOUND {
+            let account = bip44::Account::new(
+                wallet.account(derivation_scheme, account_index).public(),
+                derivation_scheme,
+            );
+            if !account_is_used(&account, &used_addresses, gap_limit, protocol_magic) {
+                break;
+            }
+            let alias = format!("{}", account_index);
+            if !wallet.list_accounts().contains_key(&alias) {
+                wallet.create_account(&alias, account_index);
+            }
+            account_index += 1;
+        }
+
+        unsafe { error::write(num_accounts_out, account_index, "num_accounts") }
+    })
+}
+
+/* ******************************************************************************* *
+ *                                 Daedalus wallet                                 *
+ * ******************************************************************************* */
+
+/// number of words of the scrambled entropy of a Daedalus paper wallet
+const PAPER_WALLET_SCRAMBLED_WORDS: usize = 18;
+
+/// number of words of the passphrase of a Daedalus paper wallet
+const PAPER_WALLET_PASSPHRASE_WORDS: usize = 9;
+
+/// retrieve the entropy of the wallet of a Daedalus paper wallet
+///
+/// The 27 english words of a paper wallet are the 18 words of the scrambled
+/// entropy (its IV followed by the shielded entropy) followed by the 9 words
+/// of the passphrase it is scrambled with. The returned entropy is the one
+/// of the 12 words of the original wallet, use `cardano_daedalus_wallet_new`
+/// to restore the wallet and `cardano_delete_entropy_array` to free it.
+///
+/// This function fails if the phrase is not made of 27 words
+/// (`Bip39InvalidWordCount`) or if the 18 first words are not valid
+/// mnemonics (`Bip39InvalidWord` or `Bip39InvalidChecksum`).
+#[no_mangle]
+pub extern "C" fn cardano_paper_wallet_unscramble(
+    mnemonics: *const c_char,
+    entropy_ptr: *mut *const c_uchar,
+    entropy_size: *mut c_uint,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let mnemonics = unsafe { error::as_c_str(mnemonics, "mnemonics") }?.to_string_lossy();
+        let words: Vec<&str> = mnemonics.split_whitespace().collect();
+        if words.len() != PAPER_WALLET_SCRAMBLED_WORDS + PAPER_WALLET_PASSPHRASE_WORDS {
+            return Err(Error::new(
+                CardanoResult::Bip39InvalidWordCount,
+                format!(
+                    "a paper wallet is made of {} words, not {}",
+                    PAPER_WALLET_SCRAMBLED_WORDS + PAPER_WALLET_PASSPHRASE_WORDS,
+                    words.len()
+                ),
+            ));
+        }
+        let (scrambled, passphrase) = words.split_at(PAPER_WALLET_SCRAMBLED_WORDS);
+
+        let scrambled = bip::bip39::Mnemonics::from_string(
+            &bip::bip39::dictionary::ENGLISH,
+            &scrambled.join(" "),
+        )
+        .map_err(|err| Error::new(CardanoResult::Bip39InvalidWord, format!("{}", err)))?;
+        let scrambled = bip::bip39::Entropy::from_mnemonics(&scrambled)
+            .map_err(|err| Error::new(CardanoResult::Bip39InvalidChecksum, format!("{}", err)))?;
+
+        let entropy = paperwallet::unscramble(passphrase.join(" ").as_bytes(), scrambled.as_ref());
+        out_return_vector(entropy, entropy_ptr, entropy_size)
+    })
+}
+
+/// Create a Daedalus wallet (2 levels of randomly chosen hard derivation
+/// indexes) from the entropy of its mnemonics
+///
+/// use the function `cardano_daedalus_wallet_delete` to free the returned
+/// wallet. This function fails with `Bip39InvalidEntropySize` if the given
+/// entropy is of invalid length.
+#[no_mangle]
+pub extern "C" fn cardano_daedalus_wallet_new(
+    ctx: ContextPtr,
+    entropy_ptr: *const u8,
+    entropy_size: usize,
+    wallet_out: *mut DaedalusWalletPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let entropy_slice = unsafe { error::as_slice(entropy_ptr, entropy_size, "entropy_ptr") }?;
+        let entropy = bip::bip39::Entropy::from_slice(entropy_slice).map_err(|err| {
+            Error::new(CardanoResult::Bip39InvalidEntropySize, format!("{}", err))
+        })?;
+
+        let wallet =
+            rindex::Wallet::from_daedalus_entropy(hdwallet::DerivationScheme::V1, &entropy)
+                .map_err(|err| Error::new(CardanoResult::Error, format!("{}", err)))?;
+
+        unsafe { error::write_boxed(wallet_out, Handle::new(wallet), "wallet") }
+    })
+}
+
+/// take ownership of the given pointer and free the associated data
+///
+/// The data must be a valid Wallet created by `cardano_daedalus_wallet_new`
+/// or `cardano_daedalus_wallet_clone_handle`. The wallet is freed with its
+/// last handle.
+#[no_mangle]
+pub extern "C" fn cardano_daedalus_wallet_delete(wallet_ptr: DaedalusWalletPtr) {
+    unsafe { Box::from_raw(wallet_ptr) };
+}
+
+/// create another handle to the same Daedalus wallet, to give to another
+/// thread. Each handle is to free with `cardano_daedalus_wallet_delete`.
+#[no_mangle]
+pub extern "C" fn cardano_daedalus_wallet_clone_handle(
+    ctx: ContextPtr,
+    wallet_ptr: DaedalusWalletPtr,
+    wallet_out: *mut DaedalusWalletPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let wallet = unsafe { error::as_ref(wallet_ptr, "wallet") }?;
+        unsafe { error::write_boxed(wallet_out, wallet.clone_handle(), "wallet_out") }
+    })
+}
+
+/// generate the address of the Daedalus wallet at the given account and
+/// address indexes
+///
+/// use `cardano_address_delete` to free the returned address.
+#[no_mangle]
+pub extern "C" fn cardano_daedalus_wallet_generate_address(
+    ctx: ContextPtr,
+    wallet_ptr: DaedalusWalletPtr,
+    account_index: u32,
+    address_index: u32,
+    protocol_magic: ProtocolMagic,
+    address_out: *mut AddressPtr,
+) -> CardanoResult {
+    context::handle_exception(ctx, |_| {
+        let wallet = unsafe { handle::lock(wallet_ptr, "wallet") }?;
+        let addressing = [rindex::Addressing::new(account_index, address_index)];
+        let address = wallet
+            .list_accounts()
+            .address_generator()
+            .iter_with(addressing.iter(), protocol_magic.into())
+            .next()
+            .expect("an address is generated for every addressing");
+        unsafe { error::write_boxed(address_out, address, "address") }
+    })
+}
+
+/// test that the given address belongs to the Daedalus wallet
+///
+/// `is_ours_out` is set to 1 if the address belongs to the wallet, and its
+/// account and address indexes are written in `account_index_out` and
+/// `address_index_out`. Otherwise it is set to 0.
+#[no_mangle]
+pub extern "C" fn cardano_daedalus_wallet_check_address(
+    wallet_ptr: DaedalusWalletPtr,
+    c_addr: AddressPtr,
+    is_ours_out: *mut c_int,
+    account_index_out: *mut u32,
+    address_index_out: *mut u32,
+) -> CardanoResult {
+    error::handle_exception(|| {
+        let wallet = unsafe { handle::lock(wallet_ptr, "wallet") }?;
+        let address = unsafe { error::as_ref(c_addr, "address") }?;
+        match wallet.check_address(address) {
+            None => unsafe { error::write(is_ours_out, 0, "is_ours") },
+            Some(addressing) => unsafe {
+                error::write(account_index_out, addressing.account(), "account_index")?;
+                error::write(address_index_out, addressing.index(), "address_index")?;
+                error::write(is_ours_out, 1, "is_ours")
+            },
+        }
+    })
+}
@@ -5,6 +5,8 @@
 #include "../cardano.h"
 #include "unity/unity.h"
 
+static cardano_context *ctx;
+
 static const uint8_t static_wallet_entropy[16] = {0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15};
 
 void test_can_create_address(void)
@@ -15,6 +17,7 @@ void test_can_create_address(void)
 
     cardano_wallet *wallet;
     cardano_result wallet_rc = cardano_wallet_new(
+        ctx,
         static_wallet_entropy,
         sizeof(static_wallet_entropy),
         "abc",
@@ -23,13 +26,16 @@ void test_can_create_address(void)
 
     TEST_ASSERT_EQUAL_MESSAGE(0, wallet_rc, "The wallet creation failed");
 
-    cardano_account *account = cardano_account_create(wallet, alias, 0);
+    cardano_account *account;
+    cardano_result account_rc = cardano_account_create(ctx, wallet, alias, 0, &account);
 
-    TEST_ASSERT_MESSAGE(account, "The account creation failed");
+    TEST_ASSERT_EQUAL_MESSAGE(CARDANO_RESULT_SUCCESS, account_rc, "The account creation failed");
 
-    cardano_account_generate_addresses(account, 0, 0, NUMBER_OF_ADDRESSES, address);
+    unsigned long num_addresses;
+    cardano_account_generate_addresses(account, 0, 0, NUMBER_OF_ADDRESSES, address, 1, &num_addresses);
 
-    TEST_ASSERT_MESSAGE(!cardano_address_is_valid(address[0]), "The generated address is invalid");
+    TEST_ASSERT_EQUAL(NUMBER_OF_ADDRESSES, num_addresses);
+    TEST_ASSERT_EQUAL_MESSAGE(CARDANO_RESULT_SUCCESS, cardano_address_is_valid(address[0]), "The generated address is invalid");
 
     cardano_account_delete_addresses(address, NUMBER_OF_ADDRESSES);
 
@@ -45,9 +51,10 @@ void invalid_entropy_size_returns_failure()
 
     cardano_wallet *wallet;
     cardano_result wallet_rc = cardano_wallet_new(
-        invalid_wallet_entropy, sizeof(invalid_wallet_entropy), password, strlen(password), &wallet);
+        ctx, invalid_wallet_entropy, sizeof(invalid_wallet_entropy), password, strlen(password), &wallet);
 
-    TEST_ASSERT_EQUAL(1, wallet_rc);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_BIP39_INVALID_ENTROPY_SIZE, wallet_rc);
+    TEST_ASSERT_NOT_NULL(cardano_error_message());
 }
 
 void valid_entropy_size_returns_success()
@@ -60,17 +67,350 @@ void valid_entropy_size_returns_success()
         uint8_t *valid_wallet_entropy = malloc(size);
         cardano_wallet *wallet;
         cardano_result wallet_rc = cardano_wallet_new(
-            valid_wallet_entropy, size, password, strlen(password), &wallet);
-        TEST_ASSERT_EQUAL(0, wallet_rc);
+            ctx, valid_wallet_entropy, size, password, strlen(password), &wallet);
+        TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, wallet_rc);
+        cardano_wallet_delete(wallet);
         free(valid_wallet_entropy);
     }
 }
 
+void wallet_account_create_rejects_duplicate_alias()
+{
+    cardano_wallet *wallet;
+    cardano_wallet_new(ctx, static_wallet_entropy, sizeof(static_wallet_entropy), "abc", strlen("abc"), &wallet);
+
+    cardano_account *account;
+    cardano_result rc1 = cardano_wallet_account_create(ctx, wallet, "savings", 0, &account);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, rc1);
+    cardano_account_delete(account);
+
+    cardano_account *duplicate;
+    cardano_result rc2 = cardano_wallet_account_create(ctx, wallet, "savings", 1, &duplicate);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_ACCOUNT_ALIAS_TAKEN, rc2);
+
+    cardano_account *hardened;
+    cardano_result rc3 = cardano_wallet_account_create(ctx, wallet, "hardened", 0x80000000, &hardened);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_INVALID_ACCOUNT_INDEX, rc3);
+
+    cardano_wallet_delete(wallet);
+}
+
+void wallet_account_list()
+{
+    cardano_wallet *wallet;
+    cardano_wallet_new(ctx, static_wallet_entropy, sizeof(static_wallet_entropy), "abc", strlen("abc"), &wallet);
+
+    cardano_account *account;
+    cardano_wallet_account_create(ctx, wallet, "savings", 1, &account);
+    cardano_account_delete(account);
+    cardano_wallet_account_create(ctx, wallet, "daily", 0, &account);
+    cardano_account_delete(account);
+
+    unsigned long count;
+    cardano_wallet_account_count(wallet, &count);
+    TEST_ASSERT_EQUAL(2, count);
+
+    char *aliases[2];
+    unsigned long n;
+    cardano_wallet_account_list(wallet, aliases, 2, &n);
+    TEST_ASSERT_EQUAL(2, n);
+    TEST_ASSERT_EQUAL_STRING("daily", aliases[0]);
+    TEST_ASSERT_EQUAL_STRING("savings", aliases[1]);
+    cardano_wallet_account_list_delete(aliases, n);
+
+    cardano_account *found;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_wallet_account_get(ctx, wallet, "savings", &found));
+    cardano_account_delete(found);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_ACCOUNT_NOT_FOUND, cardano_wallet_account_get(ctx, wallet, "unknown", &found));
+
+    cardano_wallet_delete(wallet);
+}
+
+void wallet_account_discover()
+{
+    cardano_wallet *wallet;
+    cardano_wallet_new(ctx, static_wallet_entropy, sizeof(static_wallet_entropy), "abc", strlen("abc"), &wallet);
+
+    /* the addresses of a second instance of the wallet, as seen on the blockchain */
+    cardano_wallet *other;
+    cardano_wallet_new(ctx, static_wallet_entropy, sizeof(static_wallet_entropy), "abc", strlen("abc"), &other);
+
+    char *account0[5];
+    char *account1[1];
+    unsigned long num_addresses;
+    cardano_account *account;
+    cardano_account_create(ctx, other, "0", 0, &account);
+    cardano_account_generate_addresses(account, 0, 0, 5, account0, 1, &num_addresses);
+    cardano_account_delete(account);
+    cardano_account_create(ctx, other, "1", 1, &account);
+    cardano_account_generate_addresses(account, 0, 0, 1, account1, 1, &num_addresses);
+    cardano_account_delete(account);
+
+    /* account 0 uses its 5th address, account 1 its first one */
+    const char *used[] = {account0[4], account1[0]};
+    uint32_t num_accounts;
+
+    cardano_result rc1 = cardano_wallet_account_discover(wallet, used, 2, 20, 1, &num_accounts);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, rc1);
+    TEST_ASSERT_EQUAL(2, num_accounts);
+    unsigned long count;
+    cardano_wallet_account_count(wallet, &count);
+    TEST_ASSERT_EQUAL(2, count);
+
+    /* the used address of account 0 is beyond the gap limit */
+    cardano_result rc2 = cardano_wallet_account_discover(wallet, used, 2, 4, 1, &num_accounts);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, rc2);
+    TEST_ASSERT_EQUAL(0, num_accounts);
+
+    const char *invalid[] = {"not an address"};
+    cardano_result rc3 = cardano_wallet_account_discover(wallet, invalid, 1, 20, 1, &num_accounts);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_INVALID_BASE58, rc3);
+
+    cardano_result rc4 = cardano_wallet_account_discover(wallet, used, 2, 0, 1, &num_accounts);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_INVALID_ARGUMENT, rc4);
+
+    cardano_account_delete_addresses(account0, 5);
+    cardano_account_delete_addresses(account1, 1);
+    cardano_wallet_delete(other);
+    cardano_wallet_delete(wallet);
+}
+
+void wallet_addresses_derive()
+{
+    cardano_wallet *wallet;
+    cardano_wallet_new(ctx, static_wallet_entropy, sizeof(static_wallet_entropy), "abc", strlen("abc"), &wallet);
+    cardano_account *account;
+    cardano_account_create(ctx, wallet, "0", 0, &account);
+
+    char **batch;
+    unsigned long num_batch;
+    cardano_result rc = cardano_wallet_addresses_derive(account, 1, 10, 100, 1, &batch, &num_batch);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, rc);
+    TEST_ASSERT_EQUAL(100, num_batch);
+
+    /* the same addresses as the ones generated one by one */
+    char *address[1];
+    unsigned long num_addresses;
+    cardano_account_generate_addresses(account, 1, 109, 1, address, 1, &num_addresses);
+    TEST_ASSERT_EQUAL_STRING(address[0], batch[99]);
+    cardano_account_delete_addresses(address, 1);
+    cardano_wallet_addresses_delete(batch, num_batch);
+
+    /* the derivation stops at the last soft index */
+    rc = cardano_wallet_addresses_derive(account, 0, 0x7ffffffe, 10, 1, &batch, &num_batch);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, rc);
+    TEST_ASSERT_EQUAL(2, num_batch);
+    cardano_wallet_addresses_delete(batch, num_batch);
+
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_INVALID_ARGUMENT,
+                      cardano_wallet_addresses_derive(account, 2, 0, 10, 1, &batch, &num_batch));
+
+    cardano_account_delete(account);
+    cardano_wallet_delete(wallet);
+}
+
+void address_introspection()
+{
+    char *address[1];
+    cardano_wallet *wallet;
+    cardano_wallet_new(ctx, static_wallet_entropy, sizeof(static_wallet_entropy), "abc", strlen("abc"), &wallet);
+    cardano_account *account;
+    cardano_account_create(ctx, wallet, "0", 0, &account);
+    unsigned long num_addresses;
+
+    /* mainnet addresses do not contain the protocol magic */
+    cardano_account_generate_addresses(account, 0, 0, 1, address, 764824073, &num_addresses);
+    cardano_address *mainnet;
+    cardano_address_import_base58(ctx, address[0], &mainnet);
+    cardano_account_delete_addresses(address, 1);
+    cardano_address_kind kind;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_address_get_kind(mainnet, &kind));
+    TEST_ASSERT_EQUAL(CARDANO_ADDRESS_KIND_PUBKEY, kind);
+    int has_protocol_magic;
+    uint32_t protocol_magic = 0;
+    cardano_address_get_network(mainnet, &has_protocol_magic, &protocol_magic);
+    TEST_ASSERT_EQUAL(0, has_protocol_magic);
+
+    cardano_account_generate_addresses(account, 0, 0, 1, address, 1097911063, &num_addresses);
+    cardano_address *testnet;
+    cardano_address_import_base58(ctx, address[0], &testnet);
+    cardano_account_delete_addresses(address, 1);
+    cardano_address_get_network(testnet, &has_protocol_magic, &protocol_magic);
+    TEST_ASSERT_EQUAL(1, has_protocol_magic);
+    TEST_ASSERT_EQUAL(1097911063, protocol_magic);
+
+    /* BIP44 addresses do not contain a derivation path */
+    uint8_t hdpassphrase[HDPASSPHRASE_SIZE] = {0};
+    uint32_t *path;
+    unsigned long path_size;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_NO_DERIVATION_PATH,
+                      cardano_address_get_derivation_path(testnet, hdpassphrase, &path, &path_size));
+
+    cardano_address_delete(mainnet);
+    cardano_address_delete(testnet);
+    cardano_account_delete(account);
+    cardano_wallet_delete(wallet);
+}
+
+void error_message_describes_the_last_error()
+{
+    cardano_address *address;
+    cardano_result rc = cardano_address_import_base58(ctx, "not an address", &address);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_INVALID_BASE58, rc);
+    TEST_ASSERT_NOT_NULL(cardano_error_message());
+
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_NULL_POINTER, cardano_address_import_base58(ctx, NULL, &address));
+    TEST_ASSERT_EQUAL_STRING("`address_base58` is a NULL pointer", cardano_error_message());
+}
+
+static void log_last_message(cardano_log_level level, const char *message, void *user_data)
+{
+    char *last = (char *)user_data;
+    strncpy(last, message, 255);
+    last[255] = 0;
+}
+
+void context_logs_the_errors()
+{
+    char last_message[256] = {0};
+    cardano_context_config config = {0};
+    config.log = log_last_message;
+    config.user_data = last_message;
+
+    cardano_context *logging;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_context_new(&config, &logging));
+
+    cardano_address *address;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_NULL_POINTER, cardano_address_import_base58(logging, NULL, &address));
+    TEST_ASSERT_EQUAL_STRING("`address_base58` is a NULL pointer", last_message);
+
+    /* the allocator and the deallocator are given together */
+    config.alloc = (cardano_alloc_fn)malloc;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_INVALID_ARGUMENT, cardano_context_new(&config, &logging));
+
+    cardano_context_delete(logging);
+}
+
+void paper_wallet_restoration()
+{
+    /* paper wallet of "edge club wrap where juice nephew whip entry cover bullet cause jeans" */
+    static const char *paper_wallet =
+        "clay eyebrow melody february pencil benefit index math grab tuna install prepare "
+        "shine learn hedgehog give mesh easily absurd avoid scissors anxiety gather lottery category door around";
+    static const uint8_t expected[16] = {0x46, 0x45, 0x87, 0xf8, 0x7d, 0x27, 0x8d, 0x28,
+                                         0xbe, 0x9a, 0x5d, 0x31, 0x83, 0xc4, 0x92, 0x3b};
+
+    cardano_entropy entropy;
+    uint32_t entropy_size;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_paper_wallet_unscramble(paper_wallet, &entropy, &entropy_size));
+    TEST_ASSERT_EQUAL(sizeof(expected), entropy_size);
+    TEST_ASSERT_EQUAL_HEX8_ARRAY(expected, entropy, sizeof(expected));
+
+    cardano_daedalus_wallet *wallet;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_daedalus_wallet_new(ctx, entropy, entropy_size, &wallet));
+    cardano_delete_entropy_array(entropy, entropy_size);
+
+    cardano_address *address;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS,
+                      cardano_daedalus_wallet_generate_address(ctx, wallet, 0x80000000, 0x80000001, 764824073, &address));
+
+    int is_ours = 0;
+    uint32_t account_index = 0;
+    uint32_t address_index = 0;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS,
+                      cardano_daedalus_wallet_check_address(wallet, address, &is_ours, &account_index, &address_index));
+    TEST_ASSERT_EQUAL(1, is_ours);
+    TEST_ASSERT_EQUAL_HEX32(0x80000000, account_index);
+    TEST_ASSERT_EQUAL_HEX32(0x80000001, address_index);
+
+    cardano_address_delete(address);
+    cardano_daedalus_wallet_delete(wallet);
+}
+
+void paper_wallet_rejects_word_count()
+{
+    static const char *mnemonics = "edge club wrap where juice nephew whip entry cover bullet cause jeans";
+
+    cardano_entropy entropy;
+    uint32_t entropy_size;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_BIP39_INVALID_WORD_COUNT,
+                      cardano_paper_wallet_unscramble(mnemonics, &entropy, &entropy_size));
+}
+
+void units_parse_and_format()
+{
+    uint64_t lovelace;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_units_parse_ada("12.345678 ADA", &lovelace));
+    TEST_ASSERT_EQUAL_UINT64(12345678, lovelace);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_units_parse_ada("0.5", &lovelace));
+    TEST_ASSERT_EQUAL_UINT64(500000, lovelace);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_units_parse_lovelace("42 lovelace", &lovelace));
+    TEST_ASSERT_EQUAL_UINT64(42, lovelace);
+
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_INVALID_AMOUNT, cardano_units_parse_ada("1,5", &lovelace));
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_INVALID_AMOUNT, cardano_units_parse_ada("0.0000001", &lovelace));
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_TRANSACTION_COIN_OUT_OF_BOUNDS,
+                      cardano_units_parse_ada("45000000001", &lovelace));
+
+    char *string;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_units_format_ada(12345678, &string));
+    TEST_ASSERT_EQUAL_STRING("12.345678 ADA", string);
+    cardano_units_delete_string(string);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_units_format_lovelace(42, &string));
+    TEST_ASSERT_EQUAL_STRING("42 lovelace", string);
+    cardano_units_delete_string(string);
+}
+
+void encoding_roundtrips()
+{
+    const uint8_t bytes[] = {0xde, 0xad, 0xbe, 0xef, 0x01, 0x02};
+    char *string;
+    uint8_t *decoded;
+    unsigned long decoded_size;
+
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_encode_base58(bytes, sizeof(bytes), &string));
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_decode_base58(string, &decoded, &decoded_size));
+    TEST_ASSERT_EQUAL(sizeof(bytes), decoded_size);
+    TEST_ASSERT_EQUAL_HEX8_ARRAY(bytes, decoded, sizeof(bytes));
+    cardano_encoding_delete_bytes(decoded, decoded_size);
+    cardano_encoding_delete_string(string);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_INVALID_BASE58, cardano_decode_base58("0OIl", &decoded, &decoded_size));
+
+    char *hrp;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_encode_bech32("ed25519_pk", bytes, sizeof(bytes), &string));
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_decode_bech32(string, &hrp, &decoded, &decoded_size));
+    TEST_ASSERT_EQUAL_STRING("ed25519_pk", hrp);
+    TEST_ASSERT_EQUAL(sizeof(bytes), decoded_size);
+    TEST_ASSERT_EQUAL_HEX8_ARRAY(bytes, decoded, sizeof(bytes));
+    cardano_encoding_delete_bytes(decoded, decoded_size);
+    cardano_encoding_delete_string(hrp);
+
+    /* corrupt the checksum */
+    size_t length = strlen(string);
+    string[length - 1] = string[length - 1] == 'q' ? 'p' : 'q';
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_INVALID_BECH32, cardano_decode_bech32(string, &hrp, &decoded, &decoded_size));
+    cardano_encoding_delete_string(string);
+}
+
 int main(void)
 {
+    cardano_context_new(NULL, &ctx);
     UNITY_BEGIN();
     RUN_TEST(test_can_create_address);
     RUN_TEST(invalid_entropy_size_returns_failure);
     RUN_TEST(valid_entropy_size_returns_success);
-    return UNITY_END();
+    RUN_TEST(wallet_account_create_rejects_duplicate_alias);
+    RUN_TEST(wallet_account_list);
+    RUN_TEST(wallet_account_discover);
+    RUN_TEST(wallet_addresses_derive);
+    RUN_TEST(address_introspection);
+    RUN_TEST(error_message_describes_the_last_error);
+    RUN_TEST(context_logs_the_errors);
+    RUN_TEST(paper_wallet_restoration);
+    RUN_TEST(paper_wallet_rejects_word_count);
+    RUN_TEST(units_parse_and_format);
+    RUN_TEST(encoding_roundtrips);
+    int failures = UNITY_END();
+    cardano_context_delete(ctx);
+    return failures;
 }
@@ -1,3 +1,4 @@
+#include <string.h>
 #include "../cardano.h"
 #include "unity/unity.h"
 
@@ -6,7 +7,7 @@ void test_generate_entropy_from_mnemonics(void) {
 
     cardano_entropy entropy;
     uint32_t bytes;
-    cardano_bip39_error_t error = cardano_entropy_from_english_mnemonics(mnemonics, &entropy, &bytes);
+    cardano_result error = cardano_entropy_from_english_mnemonics(mnemonics, &entropy, &bytes);
 
     uint8_t expected[16] = {0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0};
     TEST_ASSERT_EQUAL_HEX8_ARRAY(expected, entropy, 16);
@@ -19,9 +20,9 @@ void test_generate_entropy_from_mnemonics_error_code_invalid_word(void) {
 
     cardano_entropy entropy;
     uint32_t bytes;
-    cardano_bip39_error_t error = cardano_entropy_from_english_mnemonics(mnemonics, &entropy, &bytes);
+    cardano_result error = cardano_entropy_from_english_mnemonics(mnemonics, &entropy, &bytes);
 
-    TEST_ASSERT_EQUAL_HEX32(BIP39_INVALID_MNEMONIC, error);
+    TEST_ASSERT_EQUAL_HEX32(CARDANO_RESULT_BIP39_INVALID_WORD, error);
 }
 
 void test_generate_entropy_from_mnemonics_invalid_checksum(void) {
@@ -29,20 +30,26 @@ void test_generate_entropy_from_mnemonics_invalid_checksum(void) {
 
     cardano_entropy entropy;
     uint32_t bytes;
-    cardano_bip39_error_t error = cardano_entropy_from_english_mnemonics(mnemonics, &entropy, &bytes);
+    cardano_result error = cardano_entropy_from_english_mnemonics(mnemonics, &entropy, &bytes);
 
-    TEST_ASSERT_EQUAL_HEX32(BIP39_INVALID_CHECKSUM, error);
+    TEST_ASSERT_EQUAL_HEX32(CARDANO_RESULT_BIP39_INVALID_CHECKSUM, error);
 }
 
-uint8_t gen() {
-    return 1;
+static int gen(uint8_t *bytes, size_t size, void *user_data) {
+    memset(bytes, 1, size);
+    return 0;
 }
 
 void test_generate_entropy_from_random_generator(void) {
     const uint8_t NUMBER_OF_WORDS = 12; 
+    cardano_context_config config = {0};
+    config.rng = gen;
+    cardano_context *ctx;
+    cardano_context_new(&config, &ctx);
+
     cardano_entropy entropy;
     uint32_t bytes;
-    cardano_bip39_error_t error = cardano_entropy_from_random(NUMBER_OF_WORDS, gen, &entropy, &bytes);
+    cardano_result error = cardano_entropy_from_random(ctx, NUMBER_OF_WORDS, &entropy, &bytes);
 
     uint8_t expected[16] = {1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1};
 
@@ -50,15 +57,77 @@ void test_generate_entropy_from_random_generator(void) {
     TEST_ASSERT_EQUAL_HEX8_ARRAY(expected, entropy, 16);
 
     cardano_delete_entropy_array(entropy, bytes);
+    cardano_context_delete(ctx);
 }
 
 void test_generate_entropy_from_random_generator_word_count_error(void) {
     const uint8_t NUMBER_OF_WORDS = 13; 
+    cardano_context *ctx;
+    cardano_context_new(NULL, &ctx);
+
     cardano_entropy entropy;
     uint32_t bytes;
-    cardano_bip39_error_t error = cardano_entropy_from_random(NUMBER_OF_WORDS, gen, &entropy, &bytes);
+    cardano_result error = cardano_entropy_from_random(ctx, NUMBER_OF_WORDS, &entropy, &bytes);
+
+    TEST_ASSERT_EQUAL_HEX32(CARDANO_RESULT_BIP39_INVALID_WORD_COUNT, error);
+    cardano_context_delete(ctx);
+}
+
+void test_generate_mnemonics_from_entropy_in_french(void) {
+    static const char *expected = "abaisser abaisser abaisser abaisser abaisser abaisser abaisser abaisser abaisser abaisser abaisser abeille";
+    const uint8_t entropy[16] = {0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0};
+
+    char *mnemonics;
+    cardano_result error = cardano_bip39_generate_from_entropy(entropy, sizeof(entropy), CARDANO_BIP39_LANGUAGE_FRENCH, &mnemonics);
+
+    TEST_ASSERT_EQUAL_HEX32(CARDANO_RESULT_SUCCESS, error);
+    TEST_ASSERT_EQUAL_STRING(expected, mnemonics);
+
+    cardano_bip39_delete_mnemonics(mnemonics);
+}
+
+void test_mnemonics_entropy_roundtrip(void) {
+    const uint8_t entropy[32] = {
+        0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f, 0x7f,
+        0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80, 0x80
+    };
+    const cardano_bip39_language languages[] = {
+        CARDANO_BIP39_LANGUAGE_ENGLISH,
+        CARDANO_BIP39_LANGUAGE_JAPANESE,
+        CARDANO_BIP39_LANGUAGE_KOREAN,
+        CARDANO_BIP39_LANGUAGE_SPANISH,
+        CARDANO_BIP39_LANGUAGE_CHINESE_SIMPLIFIED,
+        CARDANO_BIP39_LANGUAGE_CHINESE_TRADITIONAL,
+        CARDANO_BIP39_LANGUAGE_FRENCH,
+        CARDANO_BIP39_LANGUAGE_ITALIAN
+    };
+
+    for (unsigned int i = 0; i < sizeof(languages) / sizeof(languages[0]); i++) {
+        char *mnemonics;
+        cardano_result error = cardano_bip39_generate_from_entropy(entropy, sizeof(entropy), languages[i], &mnemonics);
+        TEST_ASSERT_EQUAL_HEX32(CARDANO_RESULT_SUCCESS, error);
+
+        cardano_entropy decoded;
+        uint32_t bytes;
+        error = cardano_bip39_mnemonics_to_entropy(mnemonics, languages[i], &decoded, &bytes);
+        TEST_ASSERT_EQUAL_HEX32(CARDANO_RESULT_SUCCESS, error);
+        TEST_ASSERT_EQUAL(sizeof(entropy), bytes);
+        TEST_ASSERT_EQUAL_HEX8_ARRAY(entropy, decoded, sizeof(entropy));
+
+        cardano_delete_entropy_array(decoded, bytes);
+        cardano_bip39_delete_mnemonics(mnemonics);
+    }
+}
+
+void test_generate_mnemonics_from_entropy_errors(void) {
+    const uint8_t entropy[15] = {0};
+    char *mnemonics;
+
+    cardano_result error = cardano_bip39_generate_from_entropy(entropy, sizeof(entropy), CARDANO_BIP39_LANGUAGE_ENGLISH, &mnemonics);
+    TEST_ASSERT_EQUAL_HEX32(CARDANO_RESULT_BIP39_INVALID_ENTROPY_SIZE, error);
 
-    TEST_ASSERT_EQUAL_HEX32(BIP39_INVALID_WORD_COUNT, error);
+    error = cardano_bip39_generate_from_entropy(entropy, 12, (cardano_bip39_language) 42, &mnemonics);
+    TEST_ASSERT_EQUAL_HEX32(CARDANO_RESULT_INVALID_ARGUMENT, error);
 }
 
 int main(void) {
@@ -68,5 +137,8 @@ int main(void) {
     RUN_TEST(test_generate_entropy_from_mnemonics_invalid_checksum);
     RUN_TEST(test_generate_entropy_from_random_generator);
     RUN_TEST(test_generate_entropy_from_random_generator_word_count_error);
+    RUN_TEST(test_generate_mnemonics_from_entropy_in_french);
+    RUN_TEST(test_mnemonics_entropy_roundtrip);
+    RUN_TEST(test_generate_mnemonics_from_entropy_errors);
     return UNITY_END();
 }
\ No newline at end of file
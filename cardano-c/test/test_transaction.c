@@ -2,10 +2,12 @@
 #include <stdlib.h>
 #include <stdint.h>
 #include <string.h>
+#include <pthread.h>
 #include "../cardano.h"
 #include "unity/unity.h"
 
 //Variables for the setUp function
+cardano_context *ctx;
 cardano_wallet *wallet;
 cardano_account *account;
 cardano_address *input_address;
@@ -13,39 +15,46 @@ cardano_address *output_address;
 cardano_transaction_builder *txbuilder;
 cardano_txoptr *input;
 cardano_txoutput *output;
+cardano_xprv *input_xprv;
 
 //Constants
 static uint32_t PROTOCOL_MAGIC = 1;
-static uint8_t input_xprv[XPRV_SIZE] = {0};
+static uint8_t input_xprv_bytes[XPRV_SIZE] = {[31] = 0b01000000};
 static const uint8_t static_wallet_entropy[16] = {0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15};
 static uint8_t txid[32] = {0};
 const uint64_t MAX_COIN = 45000000000000000;
 
 void setUp()
 {
+    cardano_context_new(NULL, &ctx);
+
     cardano_result wallet_rc = cardano_wallet_new(
+        ctx,
         static_wallet_entropy,
         sizeof(static_wallet_entropy),
         "password",
         strlen("password"),
         &wallet);
 
-    account = cardano_account_create(wallet, "main", 0);
+    cardano_account_create(ctx, wallet, "main", 0, &account);
 
     char *addresses[2];
     size_t NUMBER_OF_ADDRESSES = sizeof(addresses) / sizeof(char *);
 
-    int rc = cardano_account_generate_addresses(account, 0, 0, NUMBER_OF_ADDRESSES, addresses);
+    unsigned long num_addresses;
+    cardano_account_generate_addresses(account, 0, 0, NUMBER_OF_ADDRESSES, addresses, PROTOCOL_MAGIC, &num_addresses);
 
-    input_address = cardano_address_import_base58(addresses[0]);
-    output_address = cardano_address_import_base58(addresses[1]);
+    cardano_address_import_base58(ctx, addresses[0], &input_address);
+    cardano_address_import_base58(ctx, addresses[1], &output_address);
 
     cardano_account_delete_addresses(addresses, sizeof(addresses) / sizeof(char *));
 
-    txbuilder = cardano_transaction_builder_new();
+    cardano_transaction_builder_new(ctx, &txbuilder);
     
-    input = cardano_transaction_output_ptr_new(txid, 1);
-    output = cardano_transaction_output_new(output_address, 1000);
+    cardano_transaction_output_ptr_new(ctx, txid, 1, &input);
+    cardano_transaction_output_new(ctx, output_address, 1000, &output);
+
+    cardano_xprv_from_bytes(ctx, input_xprv_bytes, XPRV_SIZE, &input_xprv);
 }
 
 void tearDown()
@@ -63,20 +72,24 @@ void tearDown()
     cardano_address_delete(output_address);
 
     cardano_account_delete(account);
+
+    cardano_xprv_delete(input_xprv);
+
+    cardano_context_delete(ctx);
 }
 
 void test_add_input_returns_success_with_valid_value()
 {
-    cardano_transaction_error_t irc = cardano_transaction_builder_add_input(txbuilder, input, 1000);
+    cardano_result irc = cardano_transaction_builder_add_input(txbuilder, input, 1000);
 
     TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, irc);
 }
 
 void test_add_input_returns_error_with_big_value()
 {
-    cardano_transaction_error_t irc = cardano_transaction_builder_add_input(txbuilder, input, MAX_COIN + 1);
+    cardano_result irc = cardano_transaction_builder_add_input(txbuilder, input, MAX_COIN + 1);
 
-    TEST_ASSERT_EQUAL(CARDANO_TRANSACTION_COIN_OUT_OF_BOUNDS, irc);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_TRANSACTION_COIN_OUT_OF_BOUNDS, irc);
 }
 
 void test_add_witness_returns_error_with_less_inputs()
@@ -86,20 +99,22 @@ void test_add_witness_returns_error_with_less_inputs()
     /* the builder finalize fails without outputs*/
     cardano_transaction_builder_add_output(txbuilder, output);
 
-    cardano_transaction *tx; cardano_transaction_error_t tx_rc = cardano_transaction_builder_finalize(txbuilder, &tx);
+    cardano_transaction *tx;
+    cardano_result tx_rc = cardano_transaction_builder_finalize(ctx, txbuilder, &tx);
 
-    TEST_ASSERT_EQUAL(CARDANO_TRANSACTION_SUCCESS, tx_rc);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, tx_rc);
 
-    cardano_transaction_finalized *tf = cardano_transaction_finalized_new(tx);
+    cardano_transaction_finalized *tf;
+    cardano_transaction_finalized_new(ctx, tx, &tf);
 
-    cardano_transaction_error_t rc1 = cardano_transaction_finalized_add_witness(tf, input_xprv, PROTOCOL_MAGIC, txid);
+    cardano_result rc1 = cardano_transaction_finalized_add_witness(tf, input_xprv, PROTOCOL_MAGIC, txid);
 
-    TEST_ASSERT_EQUAL(CARDANO_TRANSACTION_SUCCESS, rc1);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, rc1);
 
-    cardano_transaction_error_t rc2 = cardano_transaction_finalized_add_witness(tf, input_xprv, PROTOCOL_MAGIC, txid);
+    cardano_result rc2 = cardano_transaction_finalized_add_witness(tf, input_xprv, PROTOCOL_MAGIC, txid);
 
     //#witnesses > #inputs
-    TEST_ASSERT_EQUAL(CARDANO_TRANSACTION_SIGNATURES_EXCEEDED, rc2);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_TRANSACTION_SIGNATURES_EXCEEDED, rc2);
 
     cardano_transaction_delete(tx);
     cardano_transaction_finalized_delete(tf);
@@ -110,38 +125,40 @@ void test_builder_finalize_error_code_no_inputs()
     cardano_transaction_builder_add_output(txbuilder, output);
 
     cardano_transaction *tx;
-    cardano_transaction_error_t tx_rc = cardano_transaction_builder_finalize(txbuilder, &tx);
-    TEST_ASSERT_EQUAL(CARDANO_TRANSACTION_NO_INPUT, tx_rc);
+    cardano_result tx_rc = cardano_transaction_builder_finalize(ctx, txbuilder, &tx);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_TRANSACTION_NO_INPUT, tx_rc);
+    TEST_ASSERT_NOT_NULL(cardano_error_message());
 }
 
 void test_builder_finalize_error_code_no_outputs()
 {
-    cardano_transaction_error_t irc = cardano_transaction_builder_add_input(txbuilder, input, 1000);
+    cardano_result irc = cardano_transaction_builder_add_input(txbuilder, input, 1000);
 
     cardano_transaction *tx;
-    cardano_transaction_error_t tx_rc = cardano_transaction_builder_finalize(txbuilder, &tx);
-    TEST_ASSERT_EQUAL(CARDANO_TRANSACTION_NO_OUTPUT, tx_rc);
+    cardano_result tx_rc = cardano_transaction_builder_finalize(ctx, txbuilder, &tx);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_TRANSACTION_NO_OUTPUT, tx_rc);
 }
 
 void test_transaction_finalized_output_error_code_signature_mismatch()
 {
-    cardano_transaction_error_t irc1 = cardano_transaction_builder_add_input(txbuilder, input, 1000);
-    cardano_transaction_error_t irc2 = cardano_transaction_builder_add_input(txbuilder, input, 1000);
+    cardano_result irc1 = cardano_transaction_builder_add_input(txbuilder, input, 1000);
+    cardano_result irc2 = cardano_transaction_builder_add_input(txbuilder, input, 1000);
 
     cardano_transaction_builder_add_output(txbuilder, output);
 
     cardano_transaction *tx;
-    cardano_transaction_error_t tx_rc = cardano_transaction_builder_finalize(txbuilder, &tx);
+    cardano_result tx_rc = cardano_transaction_builder_finalize(ctx, txbuilder, &tx);
 
-    cardano_transaction_finalized *tf = cardano_transaction_finalized_new(tx);
+    cardano_transaction_finalized *tf;
+    cardano_transaction_finalized_new(ctx, tx, &tf);
 
-    cardano_transaction_error_t rc1 = cardano_transaction_finalized_add_witness(tf, input_xprv, PROTOCOL_MAGIC, txid);
+    cardano_result rc1 = cardano_transaction_finalized_add_witness(tf, input_xprv, PROTOCOL_MAGIC, txid);
 
     cardano_signed_transaction *txaux;
-    cardano_transaction_error_t rc = cardano_transaction_finalized_output(tf, &txaux);
+    cardano_result rc = cardano_transaction_finalized_output(ctx, tf, &txaux);
 
     //#inputs (2) > #witnesses (1)
-    TEST_ASSERT_EQUAL(CARDANO_TRANSACTION_SIGNATURE_MISMATCH, rc);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_TRANSACTION_SIGNATURE_MISMATCH, rc);
 
     cardano_transaction_delete(tx);
     cardano_transaction_finalized_delete(tf);
@@ -149,20 +166,21 @@ void test_transaction_finalized_output_error_code_signature_mismatch()
 
 void test_transaction_finalized_output_success()
 {
-    cardano_transaction_error_t irc1 = cardano_transaction_builder_add_input(txbuilder, input, 1000);
+    cardano_result irc1 = cardano_transaction_builder_add_input(txbuilder, input, 1000);
     cardano_transaction_builder_add_output(txbuilder, output);
 
     cardano_transaction *tx;
-    cardano_transaction_error_t tx_rc = cardano_transaction_builder_finalize(txbuilder, &tx);
+    cardano_result tx_rc = cardano_transaction_builder_finalize(ctx, txbuilder, &tx);
 
-    cardano_transaction_finalized *tf = cardano_transaction_finalized_new(tx);
+    cardano_transaction_finalized *tf;
+    cardano_transaction_finalized_new(ctx, tx, &tf);
 
-    cardano_transaction_error_t rc1 = cardano_transaction_finalized_add_witness(tf, input_xprv, PROTOCOL_MAGIC, txid);
+    cardano_result rc1 = cardano_transaction_finalized_add_witness(tf, input_xprv, PROTOCOL_MAGIC, txid);
 
     cardano_signed_transaction *txaux;
-    cardano_transaction_error_t rc = cardano_transaction_finalized_output(tf, &txaux);
+    cardano_result rc = cardano_transaction_finalized_output(ctx, tf, &txaux);
 
-    TEST_ASSERT_EQUAL(CARDANO_TRANSACTION_SUCCESS, rc);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, rc);
 
     cardano_transaction_delete(tx);
     cardano_transaction_finalized_delete(tf);
@@ -174,8 +192,9 @@ void test_transaction_balance_positive() {
 
     cardano_transaction_builder_add_input(txbuilder, input, 1000000);
 
-    cardano_transaction_error_t rc = cardano_transaction_builder_balance(txbuilder, &balance);
-    uint64_t fee = cardano_transaction_builder_fee(txbuilder);
+    cardano_result rc = cardano_transaction_builder_balance(ctx, txbuilder, &balance);
+    uint64_t fee;
+    cardano_transaction_builder_fee(txbuilder, &fee);
 
     TEST_ASSERT_EQUAL(1000000 - fee, (*balance).value);
     //TEST_ASSERT_EQUAL(DIFF_POSITIVE, (*balance).sign);
@@ -183,9 +202,10 @@ void test_transaction_balance_positive() {
 
 void test_transaction_balance_negative() {
     cardano_transaction_coin_diff_t *balance;
-    cardano_transaction_error_t rc = cardano_transaction_builder_balance(txbuilder, &balance);
+    cardano_result rc = cardano_transaction_builder_balance(ctx, txbuilder, &balance);
 
-    uint64_t fee = cardano_transaction_builder_fee(txbuilder);
+    uint64_t fee;
+    cardano_transaction_builder_fee(txbuilder, &fee);
 
     TEST_ASSERT_EQUAL(fee, (*balance).value);
     TEST_ASSERT_EQUAL(DIFF_NEGATIVE, (*balance).sign);
@@ -199,39 +219,43 @@ void test_transaction_balance_zero() {
     cardano_result add_change_rc = cardano_transaction_builder_add_change_addr(txbuilder, output_address);
 
     cardano_transaction_coin_diff_t *balance;
-    cardano_transaction_error_t rc = cardano_transaction_builder_balance(txbuilder, &balance);
+    cardano_result rc = cardano_transaction_builder_balance(ctx, txbuilder, &balance);
 
     TEST_ASSERT_EQUAL(0, (*balance).value);
     TEST_ASSERT_EQUAL(DIFF_ZERO, (*balance).sign);
 }
 
 void test_transaction_builder_balance_too_big() {
-    cardano_txoptr *input1 = cardano_transaction_output_ptr_new(txid, 1);
-    cardano_txoptr *input2 = cardano_transaction_output_ptr_new(txid, 2);
+    cardano_txoptr *input1;
+    cardano_transaction_output_ptr_new(ctx, txid, 1, &input1);
+    cardano_txoptr *input2;
+    cardano_transaction_output_ptr_new(ctx, txid, 2, &input2);
 
     cardano_result irc1 = cardano_transaction_builder_add_input(txbuilder, input1, MAX_COIN);
     cardano_result irc2 = cardano_transaction_builder_add_input(txbuilder, input1, 1);
 
     cardano_transaction_coin_diff_t *balance; 
-    cardano_transaction_error_t brc1 = cardano_transaction_builder_balance(txbuilder, &balance);
+    cardano_result brc1 = cardano_transaction_builder_balance(ctx, txbuilder, &balance);
 
-    TEST_ASSERT_EQUAL(CARDANO_TRANSACTION_COIN_OUT_OF_BOUNDS, brc1);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_TRANSACTION_COIN_OUT_OF_BOUNDS, brc1);
 
     cardano_transaction_output_ptr_delete(input1);
     cardano_transaction_output_ptr_delete(input2);
 }
 
 void test_transaction_builder_balance_without_fee_too_big() {
-    cardano_txoptr *input1 = cardano_transaction_output_ptr_new(txid, 1);
-    cardano_txoptr *input2 = cardano_transaction_output_ptr_new(txid, 2);
+    cardano_txoptr *input1;
+    cardano_transaction_output_ptr_new(ctx, txid, 1, &input1);
+    cardano_txoptr *input2;
+    cardano_transaction_output_ptr_new(ctx, txid, 2, &input2);
 
     cardano_result irc1 = cardano_transaction_builder_add_input(txbuilder, input1, MAX_COIN);
     cardano_result irc2 = cardano_transaction_builder_add_input(txbuilder, input1, 1);
 
     cardano_transaction_coin_diff_t *balance; 
-    cardano_transaction_error_t brc1 = cardano_transaction_builder_balance_without_fees(txbuilder, &balance);
+    cardano_result brc1 = cardano_transaction_builder_balance_without_fees(ctx, txbuilder, &balance);
 
-    TEST_ASSERT_EQUAL(CARDANO_TRANSACTION_COIN_OUT_OF_BOUNDS, brc1);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_TRANSACTION_COIN_OUT_OF_BOUNDS, brc1);
 
     cardano_transaction_output_ptr_delete(input1);
     cardano_transaction_output_ptr_delete(input2);
@@ -240,18 +264,19 @@ void test_transaction_builder_balance_without_fee_too_big() {
 void test_transaction_balance_without_fee_positive() {
     cardano_transaction_builder_add_input(txbuilder, input, 1000);
     cardano_transaction_coin_diff_t *balance;
-    cardano_transaction_error_t rc = cardano_transaction_builder_balance_without_fees(txbuilder, &balance);
+    cardano_result rc = cardano_transaction_builder_balance_without_fees(ctx, txbuilder, &balance);
 
     TEST_ASSERT_EQUAL(1000, (*balance).value);
     TEST_ASSERT_EQUAL(DIFF_POSITIVE, (*balance).sign);
 }
 
 void test_transaction_balance_without_fee_negative() {
-    cardano_txoutput *output = cardano_transaction_output_new(output_address, 1000);
+    cardano_txoutput *output;
+    cardano_transaction_output_new(ctx, output_address, 1000, &output);
 
     cardano_transaction_builder_add_output(txbuilder, output);
     cardano_transaction_coin_diff_t *balance;
-    cardano_transaction_error_t rc = cardano_transaction_builder_balance_without_fees(txbuilder, &balance);
+    cardano_result rc = cardano_transaction_builder_balance_without_fees(ctx, txbuilder, &balance);
 
     TEST_ASSERT_EQUAL(1000, (*balance).value);
     TEST_ASSERT_EQUAL(DIFF_NEGATIVE, (*balance).sign);
@@ -259,13 +284,14 @@ void test_transaction_balance_without_fee_negative() {
 }
 
 void test_transaction_balance_without_fee_zero() {
-    cardano_txoutput *output = cardano_transaction_output_new(output_address, 1000);
+    cardano_txoutput *output;
+    cardano_transaction_output_new(ctx, output_address, 1000, &output);
 
     cardano_transaction_builder_add_input(txbuilder, input, 1000);
     cardano_transaction_builder_add_output(txbuilder, output);
 
     cardano_transaction_coin_diff_t *balance;
-    cardano_transaction_error_t rc = cardano_transaction_builder_balance_without_fees(txbuilder, &balance);
+    cardano_result rc = cardano_transaction_builder_balance_without_fees(ctx, txbuilder, &balance);
 
     TEST_ASSERT_EQUAL(0, (*balance).value);
     TEST_ASSERT_EQUAL(DIFF_ZERO, (*balance).sign);
@@ -273,58 +299,389 @@ void test_transaction_balance_without_fee_zero() {
 }
 
 void test_transaction_get_input_total() {
-    cardano_transaction_error_t irc = cardano_transaction_builder_add_input(txbuilder, input, 1000);
+    cardano_result irc = cardano_transaction_builder_add_input(txbuilder, input, 1000);
     uint64_t input_total;
-    cardano_transaction_error_t rc = cardano_transaction_builder_get_input_total(txbuilder, &input_total);
+    cardano_result rc = cardano_transaction_builder_get_input_total(txbuilder, &input_total);
     TEST_ASSERT_EQUAL(1000, input_total);
 }
 
 void test_transaction_get_output_total() {
     cardano_transaction_builder_add_output(txbuilder, output);
     uint64_t output_total;
-    cardano_transaction_error_t rc = cardano_transaction_builder_get_output_total(txbuilder, &output_total);
+    cardano_result rc = cardano_transaction_builder_get_output_total(txbuilder, &output_total);
     TEST_ASSERT_EQUAL(1000, output_total);
 }
 
 void test_transaction_get_input_total_no_inputs() {
     uint64_t input_total;
-    cardano_transaction_error_t rc = cardano_transaction_builder_get_input_total(txbuilder, &input_total);
+    cardano_result rc = cardano_transaction_builder_get_input_total(txbuilder, &input_total);
     TEST_ASSERT_EQUAL(0, input_total);
 }
 
 void test_transaction_get_output_total_no_outputs() {
     uint64_t output_total;
-    cardano_transaction_error_t rc = cardano_transaction_builder_get_output_total(txbuilder, &output_total);
+    cardano_result rc = cardano_transaction_builder_get_output_total(txbuilder, &output_total);
     TEST_ASSERT_EQUAL(0, output_total);
 }
 
 void test_transaction_get_input_total_too_big()
 {
-    cardano_transaction_error_t irc1 = cardano_transaction_builder_add_input(txbuilder, input, MAX_COIN);
-    cardano_transaction_error_t irc2 = cardano_transaction_builder_add_input(txbuilder, input, 1);
-    TEST_ASSERT_EQUAL(CARDANO_TRANSACTION_SUCCESS, irc1);
-    TEST_ASSERT_EQUAL(CARDANO_TRANSACTION_SUCCESS, irc2);
+    cardano_result irc1 = cardano_transaction_builder_add_input(txbuilder, input, MAX_COIN);
+    cardano_result irc2 = cardano_transaction_builder_add_input(txbuilder, input, 1);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, irc1);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, irc2);
 
     uint64_t input_total;
-    cardano_transaction_error_t rc = cardano_transaction_builder_get_input_total(txbuilder, &input_total);
-    TEST_ASSERT_EQUAL(CARDANO_TRANSACTION_COIN_OUT_OF_BOUNDS, rc);
+    cardano_result rc = cardano_transaction_builder_get_input_total(txbuilder, &input_total);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_TRANSACTION_COIN_OUT_OF_BOUNDS, rc);
 }
 
 void test_transaction_get_output_total_too_big()
 {
-    cardano_txoutput *output1 = cardano_transaction_output_new(output_address, MAX_COIN);
-    cardano_txoutput *output2 = cardano_transaction_output_new(output_address, 1);
+    cardano_txoutput *output1;
+    cardano_transaction_output_new(ctx, output_address, MAX_COIN, &output1);
+    cardano_txoutput *output2;
+    cardano_transaction_output_new(ctx, output_address, 1, &output2);
 
     cardano_transaction_builder_add_output(txbuilder, output1);
     cardano_transaction_builder_add_output(txbuilder, output2);
     uint64_t output_total;
-    cardano_transaction_error_t rc = cardano_transaction_builder_get_output_total(txbuilder, &output_total);
-    TEST_ASSERT_EQUAL(CARDANO_TRANSACTION_COIN_OUT_OF_BOUNDS, rc);
+    cardano_result rc = cardano_transaction_builder_get_output_total(txbuilder, &output_total);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_TRANSACTION_COIN_OUT_OF_BOUNDS, rc);
 
     cardano_transaction_output_delete(output1);
     cardano_transaction_output_delete(output2);
 }
 
+void test_fee_linear_estimate()
+{
+    cardano_linear_fee *fee;
+    cardano_fee_linear_new(ctx, 1000, 2500, &fee);
+
+    uint64_t value;
+    cardano_result rc = cardano_fee_linear_estimate(fee, 100, &value);
+
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, rc);
+    TEST_ASSERT_EQUAL(1250, value);
+
+    cardano_fee_linear_delete(fee);
+}
+
+void test_fee_default_matches_builder_fee()
+{
+    cardano_linear_fee *fee;
+    cardano_fee_linear_default(ctx, &fee);
+
+    cardano_transaction_builder_add_input(txbuilder, input, 1000000);
+    cardano_transaction_builder_add_output(txbuilder, output);
+
+    uint64_t value;
+    cardano_result rc = cardano_fee_transaction_builder(fee, txbuilder, &value);
+
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, rc);
+    uint64_t builder_fee;
+    cardano_transaction_builder_fee(txbuilder, &builder_fee);
+    TEST_ASSERT_EQUAL(builder_fee, value);
+
+    cardano_fee_linear_delete(fee);
+}
+
+void test_fee_builder_matches_signed_transaction()
+{
+    cardano_linear_fee *fee;
+    cardano_fee_linear_new(ctx, 100000, 50000, &fee);
+
+    cardano_transaction_builder_add_input(txbuilder, input, 1000000);
+    cardano_transaction_builder_add_output(txbuilder, output);
+
+    uint64_t builder_fee;
+    cardano_result brc = cardano_fee_transaction_builder(fee, txbuilder, &builder_fee);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, brc);
+
+    cardano_transaction *tx;
+    cardano_transaction_builder_finalize(ctx, txbuilder, &tx);
+    cardano_transaction_finalized *tf;
+    cardano_transaction_finalized_new(ctx, tx, &tf);
+    cardano_transaction_finalized_add_witness(tf, input_xprv, PROTOCOL_MAGIC, txid);
+    cardano_signed_transaction *txaux;
+    cardano_transaction_finalized_output(ctx, tf, &txaux);
+
+    uint64_t signed_fee;
+    cardano_result src = cardano_fee_signed_transaction(fee, txaux, &signed_fee);
+
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, src);
+    TEST_ASSERT_EQUAL(builder_fee, signed_fee);
+
+    cardano_transaction_delete(tx);
+    cardano_transaction_finalized_delete(tf);
+    cardano_transaction_signed_delete(txaux);
+    cardano_fee_linear_delete(fee);
+}
+
+void test_transaction_builder_inspection()
+{
+    cardano_transaction_builder_add_input(txbuilder, input, 1000000);
+    cardano_transaction_builder_add_output(txbuilder, output);
+
+    unsigned long input_count, output_count;
+    cardano_transaction_builder_input_count(txbuilder, &input_count);
+    cardano_transaction_builder_output_count(txbuilder, &output_count);
+    TEST_ASSERT_EQUAL(1, input_count);
+    TEST_ASSERT_EQUAL(1, output_count);
+
+    cardano_txoptr *txo;
+    uint64_t input_value;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_transaction_builder_get_input(ctx, txbuilder, 0, &txo, &input_value));
+    TEST_ASSERT_EQUAL(1000000, input_value);
+    cardano_transaction_output_ptr_delete(txo);
+
+    cardano_txoutput *txout;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_transaction_builder_get_output(ctx, txbuilder, 0, &txout));
+    uint64_t output_value;
+    cardano_transaction_output_get_value(txout, &output_value);
+    TEST_ASSERT_EQUAL(1000, output_value);
+
+    cardano_address *address;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_transaction_output_get_address(ctx, txout, &address));
+    cardano_address_delete(address);
+    cardano_transaction_output_delete(txout);
+
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_INVALID_ARGUMENT, cardano_transaction_builder_get_output(ctx, txbuilder, 1, &txout));
+}
+
+void test_transaction_builder_check_fee()
+{
+    cardano_linear_fee *fee;
+    cardano_fee_linear_default(ctx, &fee);
+
+    /* the input covers the output but not the fee */
+    cardano_transaction_builder_add_input(txbuilder, input, 1000);
+    cardano_transaction_builder_add_output(txbuilder, output);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_TRANSACTION_NOT_ENOUGH_INPUT, cardano_transaction_builder_check_fee(txbuilder, fee));
+    TEST_ASSERT_NOT_NULL(cardano_error_message());
+
+    cardano_transaction_builder_add_input(txbuilder, input, 1000000);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_transaction_builder_check_fee(txbuilder, fee));
+
+    cardano_fee_linear_delete(fee);
+}
+
+void test_transaction_builder_add_change_output()
+{
+    cardano_linear_fee *fee;
+    cardano_fee_linear_new(ctx, 100000, 50000, &fee);
+
+    cardano_transaction_builder_add_input(txbuilder, input, 1000000);
+    cardano_transaction_builder_add_output(txbuilder, output);
+
+    uint64_t change_value;
+    cardano_result rc = cardano_transaction_builder_add_change_output(txbuilder, fee, input_address, &change_value);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, rc);
+
+    unsigned long output_count;
+    cardano_transaction_builder_output_count(txbuilder, &output_count);
+    TEST_ASSERT_EQUAL(2, output_count);
+
+    cardano_txoutput *change;
+    cardano_transaction_builder_get_output(ctx, txbuilder, 1, &change);
+    uint64_t value;
+    cardano_transaction_output_get_value(change, &value);
+    TEST_ASSERT_EQUAL(change_value, value);
+    cardano_transaction_output_delete(change);
+
+    /* the fee is paid by the leftover */
+    cardano_transaction_coin_diff_t *balance;
+    cardano_transaction_builder_balance_with_fee(ctx, txbuilder, fee, &balance);
+    TEST_ASSERT_NOT_EQUAL(DIFF_NEGATIVE, balance->sign);
+    cardano_transaction_balance_delete(balance);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_transaction_builder_check_fee(txbuilder, fee));
+
+    cardano_fee_linear_delete(fee);
+}
+
+void test_transaction_plan()
+{
+    cardano_linear_fee *fee;
+    cardano_fee_linear_default(ctx, &fee);
+
+    cardano_txoptr *ptrs[3];
+    cardano_utxo_t utxos[3];
+    uint64_t values[3] = {200000, 5000000, 300000};
+    for (uint32_t i = 0; i < 3; i++) {
+        cardano_transaction_output_ptr_new(ctx, txid, i, &ptrs[i]);
+        utxos[i].ptr = ptrs[i];
+        utxos[i].address = input_address;
+        utxos[i].value = values[i];
+    }
+    cardano_txoutput *outputs[1] = {output};
+
+    cardano_transaction_builder *plan;
+    uint64_t change_value;
+    cardano_result rc = cardano_transaction_plan(ctx, utxos, 3, outputs, 1, fee, CARDANO_SELECTION_LARGEST_FIRST, input_address, &plan, &change_value);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, rc);
+
+    /* the largest UTxO covers the output and the fee */
+    unsigned long input_count;
+    cardano_transaction_builder_input_count(plan, &input_count);
+    TEST_ASSERT_EQUAL(1, input_count);
+    cardano_txoptr *selected;
+    uint64_t selected_value;
+    cardano_transaction_builder_get_input(ctx, plan, 0, &selected, &selected_value);
+    TEST_ASSERT_EQUAL(5000000, selected_value);
+    cardano_transaction_output_ptr_delete(selected);
+
+    unsigned long output_count;
+    cardano_transaction_builder_output_count(plan, &output_count);
+    TEST_ASSERT_EQUAL(2, output_count);
+    TEST_ASSERT_NOT_EQUAL(0, change_value);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_transaction_builder_check_fee(plan, fee));
+    cardano_transaction_builder_delete(plan);
+
+    /* the UTxOs cannot cover a big output */
+    cardano_txoutput *big_output;
+    cardano_transaction_output_new(ctx, output_address, 6000000, &big_output);
+    outputs[0] = big_output;
+    rc = cardano_transaction_plan(ctx, utxos, 3, outputs, 1, fee, CARDANO_SELECTION_FIRST_MATCH_FIRST, input_address, &plan, &change_value);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_TRANSACTION_NOT_ENOUGH_INPUT, rc);
+    cardano_transaction_output_delete(big_output);
+
+    for (int i = 0; i < 3; i++) {
+        cardano_transaction_output_ptr_delete(ptrs[i]);
+    }
+    cardano_fee_linear_delete(fee);
+}
+
+void test_transaction_offline_signing()
+{
+    cardano_xpub *input_xpub;
+    cardano_xprv_to_xpub(ctx, input_xprv, &input_xpub);
+    cardano_address *signer_address;
+    cardano_address_new_from_pubkey(ctx, input_xpub, PROTOCOL_MAGIC, &signer_address);
+    cardano_xpub_delete(input_xpub);
+
+    cardano_transaction_builder_add_input(txbuilder, input, 1000);
+    cardano_transaction_builder_add_output(txbuilder, output);
+
+    /* the input is signed with the root key itself: the derivation path is empty */
+    uint32_t path[1] = {0};
+    cardano_signer_input_t inputs[1] = {{signer_address, path, 0}};
+
+    uint8_t *unsigned_bytes;
+    unsigned long unsigned_size;
+    cardano_result rc = cardano_transaction_builder_export_unsigned(txbuilder, PROTOCOL_MAGIC, inputs, 1, &unsigned_bytes, &unsigned_size);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, rc);
+
+    uint8_t *witnesses;
+    unsigned long witnesses_size;
+    rc = cardano_transaction_unsigned_sign(unsigned_bytes, unsigned_size, input_xprv, &witnesses, &witnesses_size);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, rc);
+    TEST_ASSERT_EQUAL(WITNESS_SIZE, witnesses_size);
+
+    cardano_signed_transaction *txaux;
+    rc = cardano_transaction_apply_witness(ctx, unsigned_bytes, unsigned_size, witnesses, 1, &txaux);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, rc);
+    cardano_transaction_signed_delete(txaux);
+
+    /* a tampered signature is rejected */
+    witnesses[witnesses_size - 1] ^= 1;
+    rc = cardano_transaction_apply_witness(ctx, unsigned_bytes, unsigned_size, witnesses, 1, &txaux);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_TRANSACTION_INVALID_WITNESS, rc);
+
+    rc = cardano_transaction_apply_witness(ctx, unsigned_bytes, unsigned_size, witnesses, 0, &txaux);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_TRANSACTION_SIGNATURE_MISMATCH, rc);
+
+    cardano_transaction_bytes_delete(witnesses, witnesses_size);
+    cardano_transaction_bytes_delete(unsigned_bytes, unsigned_size);
+    cardano_address_delete(signer_address);
+}
+
+void test_redeem_transaction()
+{
+    uint8_t key_bytes[REDEEM_PRIVATEKEY_SIZE] = {5};
+    cardano_redeem_private_key *key;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_redeem_private_key_from_bytes(ctx, key_bytes, sizeof(key_bytes), &key));
+
+    cardano_address *voucher;
+    cardano_redeem_address_new(ctx, key, PROTOCOL_MAGIC, &voucher);
+    cardano_address_kind kind;
+    cardano_address_get_kind(voucher, &kind);
+    TEST_ASSERT_EQUAL(CARDANO_ADDRESS_KIND_REDEEM, kind);
+    cardano_address_delete(voucher);
+
+    cardano_linear_fee *fee;
+    cardano_fee_linear_default(ctx, &fee);
+
+    cardano_signed_transaction *txaux;
+    cardano_result rc = cardano_redeem_transaction(ctx, key, PROTOCOL_MAGIC, input, 1000000, output_address, fee, &txaux);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, rc);
+    cardano_transaction_signed_delete(txaux);
+
+    /* the value does not cover the fee */
+    rc = cardano_redeem_transaction(ctx, key, PROTOCOL_MAGIC, input, 1000, output_address, fee, &txaux);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_TRANSACTION_NOT_ENOUGH_INPUT, rc);
+
+    cardano_fee_linear_delete(fee);
+    cardano_redeem_private_key_delete(key);
+}
+
+#define SHARING_THREADS 4
+#define INPUTS_PER_THREAD 100
+
+static void *add_inputs(void *tb)
+{
+    for (int i = 0; i < INPUTS_PER_THREAD; i++) {
+        cardano_transaction_builder_add_input((cardano_transaction_builder *)tb, input, 1);
+    }
+    cardano_transaction_builder_delete((cardano_transaction_builder *)tb);
+    return NULL;
+}
+
+void test_transaction_builder_serialization()
+{
+    cardano_transaction_builder_add_input(txbuilder, input, 1000000);
+    cardano_transaction_builder_add_output(txbuilder, output);
+
+    uint8_t *buffer;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_transaction_builder_to_bytes(txbuilder, &buffer));
+    size_t size = ((size_t)buffer[0] << 24) | ((size_t)buffer[1] << 16) | ((size_t)buffer[2] << 8) | buffer[3];
+
+    cardano_transaction_builder *copy;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS,
+                      cardano_transaction_builder_from_bytes(ctx, buffer + CARDANO_BUFFER_PREFIX_SIZE, size, &copy));
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_INVALID_SERIALIZATION,
+                      cardano_transaction_builder_from_bytes(ctx, buffer + CARDANO_BUFFER_PREFIX_SIZE, size - 1, &copy));
+    cardano_buffer_free(buffer);
+
+    unsigned long input_count, output_count;
+    cardano_transaction_builder_input_count(copy, &input_count);
+    cardano_transaction_builder_output_count(copy, &output_count);
+    TEST_ASSERT_EQUAL(1, input_count);
+    TEST_ASSERT_EQUAL(1, output_count);
+
+    uint64_t input_total;
+    cardano_transaction_builder_get_input_total(copy, &input_total);
+    TEST_ASSERT_EQUAL(1000000, input_total);
+
+    cardano_transaction_builder_delete(copy);
+}
+
+void test_transaction_builder_shared_between_threads()
+{
+    pthread_t threads[SHARING_THREADS];
+    for (int i = 0; i < SHARING_THREADS; i++) {
+        cardano_transaction_builder *clone;
+        TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_transaction_builder_clone_handle(ctx, txbuilder, &clone));
+        pthread_create(&threads[i], NULL, add_inputs, clone);
+    }
+    for (int i = 0; i < SHARING_THREADS; i++) {
+        pthread_join(threads[i], NULL);
+    }
+
+    unsigned long count;
+    cardano_transaction_builder_input_count(txbuilder, &count);
+    TEST_ASSERT_EQUAL(SHARING_THREADS * INPUTS_PER_THREAD, count);
+}
+
 int main(void)
 {
     UNITY_BEGIN();
@@ -349,5 +706,16 @@ int main(void)
     RUN_TEST(test_transaction_get_output_total_no_outputs);
     RUN_TEST(test_transaction_get_input_total_too_big);
     RUN_TEST(test_transaction_get_output_total_too_big);
+    RUN_TEST(test_fee_linear_estimate);
+    RUN_TEST(test_fee_default_matches_builder_fee);
+    RUN_TEST(test_fee_builder_matches_signed_transaction);
+    RUN_TEST(test_transaction_builder_inspection);
+    RUN_TEST(test_transaction_builder_check_fee);
+    RUN_TEST(test_transaction_builder_add_change_output);
+    RUN_TEST(test_transaction_plan);
+    RUN_TEST(test_transaction_offline_signing);
+    RUN_TEST(test_redeem_transaction);
+    RUN_TEST(test_transaction_builder_serialization);
+    RUN_TEST(test_transaction_builder_shared_between_threads);
     return UNITY_END();
 }
\ No newline at end of file
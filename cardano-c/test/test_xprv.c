@@ -5,6 +5,8 @@
 #include "../cardano.h"
 #include "unity/unity.h"
 
+static cardano_context *ctx;
+
 void can_serialize_xprv(void)
 {
     uint8_t bytes[XPRV_SIZE] = {0};
@@ -12,21 +14,34 @@ void can_serialize_xprv(void)
     bytes[31] = 0b01000000;
 
     cardano_xprv *xprv;
-    cardano_result rc = cardano_xprv_from_bytes(bytes, &xprv);
+    cardano_result rc = cardano_xprv_from_bytes(ctx, bytes, sizeof(bytes), &xprv);
 
-    uint8_t *new_bytes = cardano_xprv_to_bytes(xprv);
+    uint8_t *new_bytes;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, cardano_xprv_to_bytes(xprv, &new_bytes));
 
-    TEST_ASSERT_EQUAL_HEX8_ARRAY(bytes, new_bytes, XPRV_SIZE);
-    cardano_xprv_bytes_delete(new_bytes);
+    uint8_t prefix[CARDANO_BUFFER_PREFIX_SIZE] = {0, 0, 0, XPRV_SIZE};
+    TEST_ASSERT_EQUAL_HEX8_ARRAY(prefix, new_bytes, CARDANO_BUFFER_PREFIX_SIZE);
+    TEST_ASSERT_EQUAL_HEX8_ARRAY(bytes, new_bytes + CARDANO_BUFFER_PREFIX_SIZE, XPRV_SIZE);
+    cardano_buffer_free(new_bytes);
     cardano_xprv_delete(xprv);
 }
 
+void xprv_from_bytes_of_wrong_size_returns_failure()
+{
+    uint8_t bytes[XPRV_SIZE] = {0};
+    bytes[31] = 0b01000000;
+    cardano_xprv *xprv;
+    cardano_result rc = cardano_xprv_from_bytes(ctx, bytes, XPRV_SIZE - 1, &xprv);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_INVALID_SERIALIZATION, rc);
+}
+
 void xprv_from_invalid_bytes_returns_failure()
 {
     uint8_t bytes[XPRV_SIZE] = {0};
     cardano_xprv *xprv;
-    cardano_result rc = cardano_xprv_from_bytes(bytes, &xprv);
-    TEST_ASSERT_EQUAL(1, rc);
+    cardano_result rc = cardano_xprv_from_bytes(ctx, bytes, sizeof(bytes), &xprv);
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_INVALID_KEY, rc);
+    TEST_ASSERT_NOT_NULL(cardano_error_message());
 }
 
 void xprv_from_valid_bytes_returns_success()
@@ -36,17 +51,51 @@ void xprv_from_valid_bytes_returns_success()
     bytes[31] = 0b01000000;
 
     cardano_xprv *xprv;
-    cardano_result rc = cardano_xprv_from_bytes(bytes, &xprv);
+    cardano_result rc = cardano_xprv_from_bytes(ctx, bytes, sizeof(bytes), &xprv);
+
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS, rc);
+    cardano_xprv_delete(xprv);
+}
+
+void can_sign_and_verify_message()
+{
+    uint8_t bytes[XPRV_SIZE] = {0};
+    bytes[31] = 0b01000000;
+    static const char *message = "proof of ownership";
+
+    cardano_xprv *xprv;
+    cardano_xprv_from_bytes(ctx, bytes, sizeof(bytes), &xprv);
+    cardano_xpub *xpub;
+    cardano_xprv_to_xpub(ctx, xprv, &xpub);
+
+    uint8_t signature[SIGNATURE_SIZE];
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS,
+                      cardano_key_sign_message(xprv, (const uint8_t *)message, strlen(message), signature));
+
+    int valid = 0;
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS,
+                      cardano_key_verify_message(xpub, (const uint8_t *)message, strlen(message), signature, &valid));
+    TEST_ASSERT_EQUAL(1, valid);
+
+    /* the signature does not match another message */
+    TEST_ASSERT_EQUAL(CARDANO_RESULT_SUCCESS,
+                      cardano_key_verify_message(xpub, (const uint8_t *)message, strlen(message) - 1, signature, &valid));
+    TEST_ASSERT_EQUAL(0, valid);
 
-    TEST_ASSERT_EQUAL(0, rc);
+    cardano_xpub_delete(xpub);
     cardano_xprv_delete(xprv);
 }
 
 int main(void)
 {
+    cardano_context_new(NULL, &ctx);
     UNITY_BEGIN();
     RUN_TEST(can_serialize_xprv);
     RUN_TEST(xprv_from_invalid_bytes_returns_failure);
+    RUN_TEST(xprv_from_bytes_of_wrong_size_returns_failure);
     RUN_TEST(xprv_from_valid_bytes_returns_success);
-    return UNITY_END();
+    RUN_TEST(can_sign_and_verify_message);
+    int failures = UNITY_END();
+    cardano_context_delete(ctx);
+    return failures;
 }
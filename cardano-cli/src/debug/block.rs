@@ -0,0 +1,367 @@
+//! dump blocks of a `cardano-storage` directory as JSON
+//!
+//! Blocks are selected by header hash or by date (`EPOCH.SLOT`, or
+//! `EPOCH.GENESIS` for the epoch boundary block). Given a `--to` block,
+//! every block of the range is printed, one JSON document per line.
+
+use cardano::{
+    block::{boundary, normal, update, Block, BlockDate, EpochId, HeaderHash, SlotId},
+    tx::TxAux,
+    util::hex,
+};
+use cardano_storage::{self as storage, tag, types::header_to_blockhash, Storage, StorageConfig};
+use storage_units::hash::BlockHash;
+use structopt::StructOpt;
+
+use std::{fmt, io, path::PathBuf, str::FromStr};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Storage(storage::Error),
+    Cbor(cbor_event::Error),
+    Json(serde_json::Error),
+    StorageNotFound(PathBuf),
+    NoTip,
+    DateNotFound(BlockDate),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::Storage(err) => write!(f, "storage error: {}", err),
+            Error::Cbor(err) => write!(f, "cannot decode the block: {}", err),
+            Error::Json(err) => write!(f, "cannot encode the block in JSON: {}", err),
+            Error::StorageNotFound(path) => {
+                write!(f, "no blockchain storage at `{}'", path.display())
+            }
+            Error::NoTip => write!(f, "the storage has no `{}' tag", tag::HEAD),
+            Error::DateNotFound(date) => write!(f, "no block found at date {}", date),
+        }
+    }
+}
+impl std::error::Error for Error {}
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+impl From<storage::Error> for Error {
+    fn from(e: storage::Error) -> Self {
+        Error::Storage(e)
+    }
+}
+impl From<cbor_event::Error> for Error {
+    fn from(e: cbor_event::Error) -> Self {
+        Error::Cbor(e)
+    }
+}
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+/// reference to a block: either its header hash or its date
+#[derive(Debug, PartialEq)]
+pub enum BlockRef {
+    Hash(HeaderHash),
+    Date(BlockDate),
+}
+impl FromStr for BlockRef {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() == 64 {
+            HeaderHash::from_str(s)
+                .map(BlockRef::Hash)
+                .map_err(|e| format!("invalid block hash: {}", e))
+        } else {
+            BlockDate::from_str(s)
+                .map(BlockRef::Date)
+                .map_err(|e| e.to_string())
+        }
+    }
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct Command {
+    /// the block to print: its header hash or its date (`EPOCH.SLOT`, or
+    /// `EPOCH.GENESIS` for the boundary block)
+    #[structopt(name = "BLOCK")]
+    block: BlockRef,
+
+    /// print every block from BLOCK up to (and including) this one
+    #[structopt(long = "to")]
+    to: Option<BlockRef>,
+
+    /// the blockchain storage directory (as created by the node or by
+    /// `cardano-cli`'s sync)
+    #[structopt(long = "storage", parse(from_os_str))]
+    storage: PathBuf,
+
+    /// indent the JSON output
+    #[structopt(long = "pretty")]
+    pretty: bool,
+}
+
+impl Command {
+    pub fn exec(self) -> Result<(), Error> {
+        if !self.storage.is_dir() {
+            return Err(Error::StorageNotFound(self.storage));
+        }
+        let storage = Storage::init(&StorageConfig::new(&self.storage))?;
+
+        let from = resolve(&storage, &self.block)?;
+        let hashes = match &self.to {
+            None => vec![from],
+            Some(to) => storage.range(from, resolve(&storage, to)?)?.collect(),
+        };
+
+        for hash in hashes {
+            let raw = storage.read_block(&hash)?;
+            let block = raw.decode()?;
+            let view = BlockView::new(&block, raw.as_ref().len());
+            let json = if self.pretty {
+                serde_json::to_string_pretty(&view)?
+            } else {
+                serde_json::to_string(&view)?
+            };
+            println!("{}", json);
+        }
+        Ok(())
+    }
+}
+
+fn resolve(storage: &Storage, block: &BlockRef) -> Result<BlockHash, Error> {
+    match block {
+        BlockRef::Hash(hash) => Ok(header_to_blockhash(hash)),
+        BlockRef::Date(date) => {
+            let tip = tag::read_hash(storage, &tag::HEAD).ok_or(Error::NoTip)?;
+            storage::resolve_date_to_blockhash(storage, &tip, date)?
+                .ok_or(Error::DateNotFound(*date))
+        }
+    }
+}
+
+fn cbor_hex<T: cbor_event::se::Serialize>(t: &T) -> String {
+    let mut se = cbor_event::se::Serializer::new_vec();
+    // serializing in memory only fails on allocation failure
+    se.serialize(t).unwrap();
+    hex::encode(&se.finalize())
+}
+
+#[derive(Serialize)]
+struct BlockView {
+    hash: String,
+    previous: String,
+    date: String,
+    epoch: EpochId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    slot: Option<SlotId>,
+    boundary: bool,
+    size: usize,
+    protocol_magic: u32,
+    chain_difficulty: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    leader: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    slot_leaders: Vec<String>,
+    transactions: Vec<TransactionView>,
+    certificates: CertificatesView,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    update: Option<UpdateView>,
+}
+
+#[derive(Serialize)]
+struct TransactionView {
+    id: String,
+    inputs: Vec<InputView>,
+    outputs: Vec<OutputView>,
+    witnesses: usize,
+}
+
+#[derive(Serialize)]
+struct InputView {
+    id: String,
+    index: u32,
+}
+
+#[derive(Serialize)]
+struct OutputView {
+    address: String,
+    value: u64,
+}
+
+#[derive(Serialize, Default)]
+struct CertificatesView {
+    vss: Vec<VssCertificateView>,
+    /// the heavyweight delegation payload, in CBOR hex as it is not decoded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    delegation: Option<String>,
+}
+
+#[derive(Serialize)]
+struct VssCertificateView {
+    signing_key: String,
+    expiry_epoch: EpochId,
+}
+
+#[derive(Serialize)]
+struct UpdateView {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    proposal: Option<ProposalView>,
+    votes: Vec<VoteView>,
+}
+
+#[derive(Serialize)]
+struct ProposalView {
+    block_version: String,
+    software_version: String,
+    from: String,
+}
+
+#[derive(Serialize)]
+struct VoteView {
+    key: String,
+    proposal_id: String,
+    decision: bool,
+}
+
+impl BlockView {
+    fn new(block: &Block, size: usize) -> Self {
+        let header = block.header();
+        let date = header.blockdate();
+        let mut view = BlockView {
+            hash: header.compute_hash().to_string(),
+            previous: header.previous_header().to_string(),
+            date: date.to_string(),
+            epoch: date.get_epochid(),
+            slot: match date {
+                BlockDate::Boundary(_) => None,
+                BlockDate::Normal(slot) => Some(slot.slotid),
+            },
+            boundary: block.is_boundary_block(),
+            size,
+            protocol_magic: *block.get_protocol_magic(),
+            chain_difficulty: 0,
+            leader: None,
+            slot_leaders: Vec::new(),
+            transactions: Vec::new(),
+            certificates: CertificatesView::default(),
+            update: None,
+        };
+        match block {
+            Block::BoundaryBlock(blk) => view.fill_boundary(blk),
+            Block::MainBlock(blk) => view.fill_normal(blk),
+        }
+        view
+    }
+
+    fn fill_boundary(&mut self, blk: &boundary::Block) {
+        self.chain_difficulty = blk.header.consensus.chain_difficulty.into();
+        self.slot_leaders = blk
+            .body
+            .slot_leaders
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+    }
+
+    fn fill_normal(&mut self, blk: &normal::Block) {
+        self.chain_difficulty = blk.header.consensus.chain_difficulty.into();
+        self.leader = Some(blk.header.consensus.leader_key.to_string());
+        self.transactions = blk.body.tx.iter().map(TransactionView::new).collect();
+        self.certificates = CertificatesView {
+            vss: blk
+                .body
+                .ssc
+                .get_vss_certificates()
+                .iter()
+                .map(|cert| VssCertificateView {
+                    signing_key: cert.signing_key.to_string(),
+                    expiry_epoch: cert.expiry_epoch,
+                })
+                .collect(),
+            delegation: match &blk.body.delegation.0 {
+                cbor_event::Value::Array(dlgs) if dlgs.is_empty() => None,
+                _ => Some(cbor_hex(&blk.body.delegation)),
+            },
+        };
+        self.update = UpdateView::new(&blk.body.update);
+    }
+}
+
+impl TransactionView {
+    fn new(txaux: &TxAux) -> Self {
+        TransactionView {
+            id: txaux.tx.id().to_string(),
+            inputs: txaux
+                .tx
+                .inputs
+                .iter()
+                .map(|input| InputView {
+                    id: input.id.to_string(),
+                    index: input.index,
+                })
+                .collect(),
+            outputs: txaux
+                .tx
+                .outputs
+                .iter()
+                .map(|output| OutputView {
+                    address: output.address.to_string(),
+                    value: output.value.into(),
+                })
+                .collect(),
+            witnesses: txaux.witness.len(),
+        }
+    }
+}
+
+impl UpdateView {
+    fn new(update: &update::UpdatePayload) -> Option<Self> {
+        if update.proposal.is_none() && update.votes.is_empty() {
+            return None;
+        }
+        Some(UpdateView {
+            proposal: update.proposal.as_ref().map(|proposal| ProposalView {
+                block_version: proposal.block_version.to_string(),
+                software_version: format!(
+                    "{}:{}",
+                    proposal.software_version.application_name(),
+                    proposal.software_version.application_version
+                ),
+                from: proposal.from.to_string(),
+            }),
+            votes: update
+                .votes
+                .iter()
+                .map(|vote| VoteView {
+                    key: vote.key.to_string(),
+                    proposal_id: vote.proposal_id.to_string(),
+                    decision: vote.decision,
+                })
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_block_ref() {
+        let hash = "3da2b1a4b6c0e8b2e2d3b5d6c0e4b4f5c4e5a5b3a1d2c7e8f9e0a1b2c3d4e5f6";
+        assert_eq!(
+            hash.parse::<BlockRef>().unwrap(),
+            BlockRef::Hash(hash.parse().unwrap())
+        );
+        assert_eq!(
+            "12.GENESIS".parse::<BlockRef>().unwrap(),
+            BlockRef::Date(BlockDate::Boundary(12))
+        );
+        assert!("12.slot".parse::<BlockRef>().is_err());
+    }
+}
@@ -0,0 +1,522 @@
+//! annotated CBOR pretty-printer
+//!
+//! Any CBOR value is printed item by item, with its offset in the input.
+//! When the value is a known Cardano structure (a block, a transaction or
+//! an address) the items are annotated with their field names, and the
+//! CBOR embedded in tagged (24) bytes is decoded too.
+
+use cardano::{address::ExtendedAddr, block::Block, tx, util::hex};
+use cbor_event::{self, de::Deserializer, Len, Special, Type};
+use structopt::StructOpt;
+
+use std::{
+    fmt,
+    io::{self, Cursor, Read},
+    str::FromStr,
+};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Encoding,
+    Cbor(cbor_event::Error),
+    TrailingBytes(usize),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::Encoding => write!(f, "input is neither hexadecimal nor base64"),
+            Error::Cbor(err) => write!(f, "invalid CBOR: {}", err),
+            Error::TrailingBytes(offset) => {
+                write!(f, "unexpected trailing bytes at offset {}", offset)
+            }
+        }
+    }
+}
+impl std::error::Error for Error {}
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+impl From<cbor_event::Error> for Error {
+    fn from(e: cbor_event::Error) -> Self {
+        Error::Cbor(e)
+    }
+}
+
+/// the known structures the input can be decoded as
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Structure {
+    Auto,
+    Raw,
+    Block,
+    TxAux,
+    Tx,
+    Address,
+}
+impl FromStr for Structure {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Structure::Auto),
+            "raw" => Ok(Structure::Raw),
+            "block" => Ok(Structure::Block),
+            "txaux" => Ok(Structure::TxAux),
+            "tx" => Ok(Structure::Tx),
+            "address" => Ok(Structure::Address),
+            _ => Err(format!(
+                "unknown structure `{}', expected one of: auto, raw, block, txaux, tx, address",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct Command {
+    /// the CBOR to decode, in hexadecimal or base64 (read from the standard
+    /// input if not given)
+    #[structopt(name = "INPUT")]
+    input: Option<String>,
+
+    /// the structure to annotate the CBOR with: auto, raw, block, txaux, tx
+    /// or address
+    #[structopt(long = "as", default_value = "auto")]
+    structure: Structure,
+}
+
+impl Command {
+    pub fn exec(self) -> Result<(), Error> {
+        let input = match self.input {
+            Some(input) => input,
+            None => {
+                let mut input = String::new();
+                io::stdin().read_to_string(&mut input)?;
+                input
+            }
+        };
+        let bytes = decode_input(&input)?;
+
+        let schema = match self.structure {
+            Structure::Auto => detect(&bytes),
+            Structure::Raw => Schema::Unknown,
+            Structure::Block => block_schema(&bytes),
+            Structure::TxAux => Schema::TxAux,
+            Structure::Tx => Schema::Tx,
+            Structure::Address => Schema::Address,
+        };
+        println!("{}", schema.name());
+        for line in annotate(&bytes, schema)? {
+            println!("{}", line);
+        }
+        Ok(())
+    }
+}
+
+fn decode_input(input: &str) -> Result<Vec<u8>, Error> {
+    let input: String = input.split_whitespace().collect();
+    hex::decode(&input)
+        .ok()
+        .or_else(|| base64::decode(&input).ok())
+        .ok_or(Error::Encoding)
+}
+
+/// find the first known structure the bytes fully decode as
+fn detect(bytes: &[u8]) -> Schema {
+    fn decodes<T: cbor_event::de::Deserialize>(bytes: &[u8]) -> bool {
+        let mut raw = Deserializer::from(Cursor::new(bytes));
+        T::deserialize(&mut raw).is_ok() && raw.as_ref().position() as usize == bytes.len()
+    }
+
+    if decodes::<Block>(bytes) {
+        block_schema(bytes)
+    } else if decodes::<tx::TxAux>(bytes) {
+        Schema::TxAux
+    } else if decodes::<tx::Tx>(bytes) {
+        Schema::Tx
+    } else if decodes::<ExtendedAddr>(bytes) {
+        Schema::Address
+    } else {
+        Schema::Unknown
+    }
+}
+
+/// the schema of a block, given by its type tag: `[0, boundary block]` or
+/// `[1, main block]`
+fn block_schema(bytes: &[u8]) -> Schema {
+    let mut raw = Deserializer::from(Cursor::new(bytes));
+    match raw.array().and_then(|_| raw.unsigned_integer()) {
+        Ok(0) => Schema::BoundaryBlock,
+        Ok(1) => Schema::MainBlock,
+        _ => Schema::Unknown,
+    }
+}
+
+/// the layout of the CBOR encoding of the Cardano structures, used to
+/// name the items
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Schema {
+    Unknown,
+    Address,
+    AddressPayload,
+    MainBlock,
+    MainBlockContent,
+    MainHeader,
+    MainBody,
+    MainConsensus,
+    BoundaryBlock,
+    BoundaryBlockContent,
+    BoundaryHeader,
+    TxPayload,
+    TxAux,
+    Tx,
+    TxIns,
+    TxIn,
+    TxoPointer,
+    TxOuts,
+    TxOut,
+    TxWitnesses,
+    TxInWitness,
+    TxInWitnessPayload,
+}
+impl Schema {
+    fn name(self) -> &'static str {
+        match self {
+            Schema::Unknown => "unknown structure",
+            Schema::Address => "address",
+            Schema::MainBlock => "main block",
+            Schema::BoundaryBlock => "boundary block",
+            Schema::TxAux => "signed transaction",
+            Schema::Tx => "transaction",
+            _ => "",
+        }
+    }
+
+    /// the name and the schema of the `index`th element of an array
+    fn element(self, index: u64) -> (Option<&'static str>, Schema) {
+        use self::Schema::*;
+
+        let fields: &[(&'static str, Schema)] = match self {
+            Address => &[("payload", AddressPayload), ("crc32", Unknown)],
+            AddressPayload => &[
+                ("root", Unknown),
+                ("attributes", Unknown),
+                ("type", Unknown),
+            ],
+            MainBlock => &[("era", Unknown), ("block", MainBlockContent)],
+            MainBlockContent => &[
+                ("header", MainHeader),
+                ("body", MainBody),
+                ("extra", Unknown),
+            ],
+            MainHeader => &[
+                ("protocol_magic", Unknown),
+                ("previous_header", Unknown),
+                ("body_proof", Unknown),
+                ("consensus", MainConsensus),
+                ("extra_data", Unknown),
+            ],
+            MainConsensus => &[
+                ("slot_id", Unknown),
+                ("leader_key", Unknown),
+                ("chain_difficulty", Unknown),
+                ("block_signature", Unknown),
+            ],
+            MainBody => &[
+                ("transactions", TxPayload),
+                ("ssc", Unknown),
+                ("delegation", Unknown),
+                ("update", Unknown),
+            ],
+            BoundaryBlock => &[("era", Unknown), ("block", BoundaryBlockContent)],
+            BoundaryBlockContent => &[
+                ("header", BoundaryHeader),
+                ("body", Unknown),
+                ("extra", Unknown),
+            ],
+            BoundaryHeader => &[
+                ("protocol_magic", Unknown),
+                ("previous_header", Unknown),
+                ("body_proof", Unknown),
+                ("consensus", Unknown),
+                ("extra_data", Unknown),
+            ],
+            TxAux => &[("tx", Tx), ("witnesses", TxWitnesses)],
+            Tx => &[
+                ("inputs", TxIns),
+                ("outputs", TxOuts),
+                ("attributes", Unknown),
+            ],
+            TxIn => &[("type", Unknown), ("pointer", TxoPointer)],
+            TxoPointer => &[("txid", Unknown), ("index", Unknown)],
+            TxOut => &[("address", Address), ("value", Unknown)],
+            TxInWitness => &[("type", Unknown), ("payload", TxInWitnessPayload)],
+            TxInWitnessPayload => &[("public_key", Unknown), ("signature", Unknown)],
+
+            // homogeneous lists
+            TxPayload => return (Some("signed transaction"), TxAux),
+            TxIns => return (Some("input"), TxIn),
+            TxOuts => return (Some("output"), TxOut),
+            TxWitnesses => return (Some("witness"), TxInWitness),
+
+            Unknown => &[],
+        };
+        match fields.get(index as usize) {
+            Some(&(name, schema)) => (Some(name), schema),
+            None => (None, Unknown),
+        }
+    }
+}
+
+/// an item of the CBOR input: its offset, its depth in the structure, the
+/// name of the field it is (if known) and its description
+struct Line {
+    offset: usize,
+    depth: usize,
+    field: Option<&'static str>,
+    description: String,
+}
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:06x}  {:2$}", self.offset, "", self.depth * 2)?;
+        if let Some(field) = self.field {
+            write!(f, "{}: ", field)?;
+        }
+        write!(f, "{}", self.description)
+    }
+}
+
+/// the items nested deeper are rejected, so a malicious input cannot
+/// overflow the stack of the recursive annotation
+const MAX_DEPTH: usize = 128;
+
+struct Annotator {
+    lines: Vec<Line>,
+}
+
+fn annotate(bytes: &[u8], schema: Schema) -> Result<Vec<Line>, Error> {
+    let mut annotator = Annotator { lines: Vec::new() };
+    let mut raw = Deserializer::from(Cursor::new(bytes));
+    annotator.item(&mut raw, 0, 0, None, schema)?;
+    let end = raw.as_ref().position() as usize;
+    if end != bytes.len() {
+        return Err(Error::TrailingBytes(end));
+    }
+    Ok(annotator.lines)
+}
+
+impl Annotator {
+    fn push(&mut self, offset: usize, depth: usize, field: Option<&'static str>, desc: String) {
+        self.lines.push(Line {
+            offset,
+            depth,
+            field,
+            description: desc,
+        })
+    }
+
+    fn item(
+        &mut self,
+        raw: &mut Deserializer<Cursor<&[u8]>>,
+        base: usize,
+        depth: usize,
+        field: Option<&'static str>,
+        schema: Schema,
+    ) -> cbor_event::Result<()> {
+        if depth > MAX_DEPTH {
+            return Err(cbor_event::Error::CustomError(format!(
+                "items nested deeper than {} levels",
+                MAX_DEPTH
+            )));
+        }
+        let offset = base + raw.as_ref().position() as usize;
+        match raw.cbor_type()? {
+            Type::UnsignedInteger => {
+                let v = raw.unsigned_integer()?;
+                self.push(offset, depth, field, format!("uint {}", v));
+            }
+            Type::NegativeInteger => {
+                let v = raw.negative_integer()?;
+                self.push(offset, depth, field, format!("nint {}", v));
+            }
+            Type::Bytes => {
+                let v = raw.bytes()?;
+                let desc = format!("bytes({}) {}", v.len(), hex::encode(&v));
+                self.push(offset, depth, field, desc);
+            }
+            Type::Text => {
+                let v = raw.text()?;
+                self.push(offset, depth, field, format!("text({}) {:?}", v.len(), v));
+            }
+            Type::Array => {
+                let len = raw.array()?;
+                self.push(offset, depth, field, format!("array({})", len_str(len)));
+                let mut index = 0;
+                while self.has_next(raw, base, depth + 1, len, index)? {
+                    let (field, schema) = schema.element(index);
+                    self.item(raw, base, depth + 1, field, schema)?;
+                    index += 1;
+                }
+            }
+            Type::Map => {
+                let len = raw.map()?;
+                self.push(offset, depth, field, format!("map({})", len_str(len)));
+                let mut index = 0;
+                while self.has_next(raw, base, depth + 1, len, index)? {
+                    self.item(raw, base, depth + 1, Some("key"), Schema::Unknown)?;
+                    self.item(raw, base, depth + 1, Some("value"), Schema::Unknown)?;
+                    index += 1;
+                }
+            }
+            Type::Tag => {
+                let tag = raw.tag()?;
+                self.push(offset, depth, field, format!("tag({})", tag));
+                if tag == 24 && raw.cbor_type()? == Type::Bytes {
+                    self.embedded(raw, base, depth + 1, schema)?;
+                } else {
+                    self.item(raw, base, depth + 1, None, schema)?;
+                }
+            }
+            Type::Special => {
+                let v = raw.special()?;
+                self.push(offset, depth, field, format!("special {:?}", v));
+            }
+        }
+        Ok(())
+    }
+
+    /// tells if there is another element in the array or map, consuming the
+    /// break of the indefinite length ones
+    fn has_next(
+        &mut self,
+        raw: &mut Deserializer<Cursor<&[u8]>>,
+        base: usize,
+        depth: usize,
+        len: Len,
+        index: u64,
+    ) -> cbor_event::Result<bool> {
+        match len {
+            Len::Len(len) => Ok(index < len),
+            Len::Indefinite => {
+                if raw.cbor_type()? != Type::Special {
+                    return Ok(true);
+                }
+                let offset = base + raw.as_ref().position() as usize;
+                let special = raw.special()?;
+                if special != Special::Break {
+                    return Err(cbor_event::Error::CustomError(format!(
+                        "unexpected {:?} in indefinite length item",
+                        special
+                    )));
+                }
+                self.push(offset, depth, None, "break".to_owned());
+                Ok(false)
+            }
+        }
+    }
+
+    /// the CBOR encoded in tagged (24) bytes, decoded as an item of its own
+    /// if it is valid CBOR
+    fn embedded(
+        &mut self,
+        raw: &mut Deserializer<Cursor<&[u8]>>,
+        base: usize,
+        depth: usize,
+        schema: Schema,
+    ) -> cbor_event::Result<()> {
+        let offset = base + raw.as_ref().position() as usize;
+        let bytes = raw.bytes()?;
+        let content = base + raw.as_ref().position() as usize - bytes.len();
+
+        let mut inner = Annotator { lines: Vec::new() };
+        let mut inner_raw = Deserializer::from(Cursor::new(&bytes[..]));
+        let valid = inner
+            .item(&mut inner_raw, content, depth + 1, None, schema)
+            .is_ok()
+            && inner_raw.as_ref().position() as usize == bytes.len();
+
+        if valid {
+            let desc = format!("bytes({}) embedded CBOR", bytes.len());
+            self.push(offset, depth, None, desc);
+            self.lines.extend(inner.lines);
+        } else {
+            let desc = format!("bytes({}) {}", bytes.len(), hex::encode(&bytes));
+            self.push(offset, depth, None, desc);
+        }
+        Ok(())
+    }
+}
+
+fn len_str(len: Len) -> String {
+    match len {
+        Len::Len(len) => len.to_string(),
+        Len::Indefinite => "*".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "DdzFFzCqrhsyhumccfGyEj3WZzztSPr92ntRWB6UVVwzcMTpwoafVQ5vD9mdZ5Xind8ycugbmA8esxmo7NycjQFGSbDeKrxabTz8MVzf";
+
+    fn address_cbor() -> Vec<u8> {
+        let addr = ExtendedAddr::from_str(ADDRESS).unwrap();
+        let mut se = cbor_event::se::Serializer::new_vec();
+        se.serialize(&addr).unwrap();
+        se.finalize()
+    }
+
+    #[test]
+    fn detect_address() {
+        assert_eq!(detect(&address_cbor()), Schema::Address);
+        assert_eq!(detect(&[0x01]), Schema::Unknown);
+    }
+
+    #[test]
+    fn annotate_address() {
+        let lines = annotate(&address_cbor(), Schema::Address).unwrap();
+        let fields: Vec<_> = lines.iter().filter_map(|l| l.field).collect();
+        assert_eq!(&fields[..3], &["payload", "root", "attributes"]);
+        assert_eq!(&fields[fields.len() - 2..], &["type", "crc32"]);
+        assert_eq!(lines[0].offset, 0);
+    }
+
+    #[test]
+    fn block_schema_from_type_tag() {
+        assert_eq!(block_schema(&[0x82, 0x00, 0x80]), Schema::BoundaryBlock);
+        assert_eq!(block_schema(&[0x82, 0x01, 0x80]), Schema::MainBlock);
+        assert_eq!(block_schema(&[0x82, 0x02, 0x80]), Schema::Unknown);
+        assert_eq!(block_schema(&[0x01]), Schema::Unknown);
+    }
+
+    #[test]
+    fn nesting_depth_limited() {
+        let mut nested = vec![0x81; MAX_DEPTH];
+        nested.push(0x00);
+        assert_eq!(
+            annotate(&nested, Schema::Unknown).unwrap().len(),
+            MAX_DEPTH + 1
+        );
+
+        // tagged embedded CBOR counts in the depth too
+        let mut nested = vec![0xd8, 0x18, 0x59, 0x27, 0x11];
+        nested.extend(vec![0x81; 10_000]);
+        nested.push(0x00);
+        let lines = annotate(&nested, Schema::Unknown).unwrap();
+        assert!(lines[1].description.starts_with("bytes(10001) 8181"));
+
+        let mut nested = vec![0x81; 10_000];
+        nested.push(0x00);
+        assert!(annotate(&nested, Schema::Unknown).is_err());
+    }
+
+    #[test]
+    fn decode_hex_or_base64() {
+        assert_eq!(decode_input("8201\n").unwrap(), vec![0x82, 0x01]);
+        assert_eq!(decode_input("ggE=").unwrap(), vec![0x82, 0x01]);
+    }
+}
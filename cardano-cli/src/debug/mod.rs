@@ -0,0 +1,56 @@
+//! `debug` subcommand: helpers to inspect the data of a node without an
+//! external explorer
+
+use structopt::StructOpt;
+
+use std::fmt;
+
+mod block;
+mod cbor;
+
+#[derive(Debug)]
+pub enum Error {
+    Block(block::Error),
+    Cbor(cbor::Error),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Block(err) => write!(f, "{}", err),
+            Error::Cbor(err) => write!(f, "{}", err),
+        }
+    }
+}
+impl std::error::Error for Error {}
+impl From<block::Error> for Error {
+    fn from(e: block::Error) -> Self {
+        Error::Block(e)
+    }
+}
+impl From<cbor::Error> for Error {
+    fn from(e: cbor::Error) -> Self {
+        Error::Cbor(e)
+    }
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub enum Command {
+    /// read blocks from a `cardano-storage` directory and print them as JSON
+    #[structopt(name = "block")]
+    Block(block::Command),
+
+    /// decode hexadecimal or base64 CBOR, annotated with the field names of
+    /// the Cardano structures it matches
+    #[structopt(name = "cbor")]
+    Cbor(cbor::Command),
+}
+
+impl Command {
+    pub fn exec(self) -> Result<(), Error> {
+        match self {
+            Command::Block(cmd) => Ok(cmd.exec()?),
+            Command::Cbor(cmd) => Ok(cmd.exec()?),
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! Command line tooling over the Cardano library
+
+#[macro_use]
+extern crate serde_derive;
+
+use structopt::StructOpt;
+
+mod debug;
+mod tx;
+mod utils;
+mod wallet;
+
+#[derive(StructOpt)]
+#[structopt(name = "cardano-cli", rename_all = "kebab-case")]
+enum Command {
+    /// wallet creation, recovery and address derivation
+    #[structopt(name = "wallet")]
+    Wallet(wallet::Command),
+
+    /// offline transaction building and signing
+    #[structopt(name = "tx")]
+    Tx(tx::Command),
+
+    /// inspect the blockchain data (blocks of a storage directory)
+    #[structopt(name = "debug")]
+    Debug(debug::Command),
+}
+
+fn main() {
+    let result = match Command::from_args() {
+        Command::Wallet(cmd) => cmd.exec().map_err(|e| e.to_string()),
+        Command::Tx(cmd) => cmd.exec().map_err(|e| e.to_string()),
+        Command::Debug(cmd) => cmd.exec().map_err(|e| e.to_string()),
+    };
+
+    if let Err(err) = result {
+        eprintln!("error: {}", err);
+        std::process::exit(1)
+    }
+}
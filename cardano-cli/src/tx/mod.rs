@@ -0,0 +1,283 @@
+//! `tx` subcommand: offline transaction building and signing
+//!
+//! The transaction is described by a JSON specification (see the `spec`
+//! module). `build` displays the fee and the balance of the transaction,
+//! `sign` outputs the signed transaction in CBOR hex, ready to be sent to
+//! the network. Nothing requires a network connection so the signing can
+//! happen on an air-gapped machine.
+
+use cardano::{
+    coin::CoinDiff,
+    config::ProtocolMagic,
+    fee::LinearFee,
+    hdwallet,
+    tx::{TxAux, TxInWitness, TxOut, TxoPointer},
+    txbuild::{self, TxBuilder, TxFinalized},
+    txutils::OutputPolicy,
+    util::hex,
+};
+use structopt::StructOpt;
+
+use std::{
+    fmt,
+    io::{self, BufRead},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::wallet::{self, WalletArgs};
+
+mod spec;
+
+use self::spec::Spec;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Wallet(wallet::Error),
+    Build(txbuild::Error),
+    Cbor(cbor_event::Error),
+    InvalidKey(hdwallet::Error),
+    MissingPath(usize),
+    KeyCountMismatch { inputs: usize, keys: usize },
+    StdinConflict,
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::Wallet(err) => write!(f, "{}", err),
+            Error::Build(err) => write!(f, "cannot build the transaction: {}", err),
+            Error::Cbor(err) => write!(f, "cannot encode the transaction: {}", err),
+            Error::InvalidKey(err) => write!(f, "invalid private key: {}", err),
+            Error::MissingPath(idx) => {
+                write!(f, "input {} has no derivation path to sign with", idx)
+            }
+            Error::KeyCountMismatch { inputs, keys } => write!(
+                f,
+                "expected one key per input ({} inputs) but received {} keys",
+                inputs, keys
+            ),
+            Error::StdinConflict => write!(
+                f,
+                "cannot read both the specification and the keys from the standard input"
+            ),
+        }
+    }
+}
+impl std::error::Error for Error {}
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+impl From<wallet::Error> for Error {
+    fn from(e: wallet::Error) -> Self {
+        Error::Wallet(e)
+    }
+}
+impl From<txbuild::Error> for Error {
+    fn from(e: txbuild::Error) -> Self {
+        Error::Build(e)
+    }
+}
+impl From<cbor_event::Error> for Error {
+    fn from(e: cbor_event::Error) -> Self {
+        Error::Cbor(e)
+    }
+}
+impl From<hdwallet::Error> for Error {
+    fn from(e: hdwallet::Error) -> Self {
+        Error::InvalidKey(e)
+    }
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub enum Command {
+    /// build the transaction from the given specification and display
+    /// its fee and balance
+    #[structopt(name = "build")]
+    Build {
+        /// the JSON specification of the transaction (`-` for the
+        /// standard input)
+        #[structopt(name = "SPEC", parse(from_os_str))]
+        spec: PathBuf,
+    },
+    /// build and sign the transaction, output the signed transaction in
+    /// CBOR hex
+    #[structopt(name = "sign")]
+    Sign {
+        /// the JSON specification of the transaction (`-` for the
+        /// standard input, unless the keys are read from it)
+        #[structopt(name = "SPEC", parse(from_os_str))]
+        spec: PathBuf,
+
+        /// read the private keys (hexadecimal, one per line, in the order of
+        /// the inputs) from the standard input instead of a stored wallet.
+        /// A single key signs all the inputs.
+        #[structopt(long = "keys-from-stdin")]
+        keys_from_stdin: bool,
+
+        /// the name of the wallet to sign with, using the inputs' paths
+        #[structopt(long = "wallet", required_unless = "keys-from-stdin")]
+        wallet: Option<String>,
+
+        /// the directory where the wallets are stored
+        #[structopt(long = "wallet-dir", parse(from_os_str), default_value = "wallets")]
+        wallet_dir: PathBuf,
+    },
+}
+
+impl Command {
+    pub fn exec(self) -> Result<(), Error> {
+        match self {
+            Command::Build { spec } => {
+                let mut spec = Spec::load(spec)?;
+                let fee_algorithm = spec.fee_algorithm();
+                let builder = build(&spec, &fee_algorithm)?;
+                display_summary(&builder, &fee_algorithm)
+            }
+            Command::Sign {
+                spec,
+                keys_from_stdin,
+                wallet,
+                wallet_dir,
+            } => {
+                if keys_from_stdin && spec == Path::new("-") {
+                    return Err(Error::StdinConflict);
+                }
+                let mut spec = Spec::load(spec)?;
+                let fee_algorithm = spec.fee_algorithm();
+                let builder = build(&spec, &fee_algorithm)?;
+                let keys = if keys_from_stdin {
+                    keys_from_input(io::stdin().lock(), spec.inputs.len())?
+                } else {
+                    let args = WalletArgs {
+                        // `required_unless` guarantees the wallet is set
+                        name: wallet.unwrap_or_default(),
+                        wallet_dir,
+                    };
+                    keys_from_wallet(&args, &spec)?
+                };
+                let txaux = sign(builder, spec.protocol_magic, &keys)?;
+
+                let mut se = cbor_event::se::Serializer::new_vec();
+                se.serialize(&txaux)?;
+                println!("{}", hex::encode(&se.finalize()));
+                Ok(())
+            }
+        }
+    }
+}
+
+fn build(spec: &Spec, fee_algorithm: &LinearFee) -> Result<TxBuilder, Error> {
+    let mut builder = TxBuilder::new();
+    for input in spec.inputs.iter() {
+        builder.add_input(&TxoPointer::new(input.txid, input.index), input.value);
+    }
+    for output in spec.outputs.iter() {
+        builder.add_output_value(&TxOut::new(output.address.clone(), output.value));
+    }
+    if let Some(change) = &spec.change {
+        builder.add_output_policy(fee_algorithm, &OutputPolicy::One(change.clone()))?;
+    }
+    Ok(builder)
+}
+
+fn display_summary(builder: &TxBuilder, fee_algorithm: &LinearFee) -> Result<(), Error> {
+    let fee = builder.calculate_fee(fee_algorithm)?;
+    let input_total = builder.get_input_total()?;
+    let output_total = builder.get_output_total()?;
+    let tx = builder.clone().make_tx()?;
+
+    println!("transaction id: {}", tx.id());
+    println!("inputs:         {}", input_total);
+    println!("outputs:        {}", output_total);
+    println!("fee:            {}", fee.to_coin());
+    match builder.balance(fee_algorithm)? {
+        CoinDiff::Zero => println!("balance:        balanced"),
+        CoinDiff::Positive(c) => println!("balance:        {} in excess (paid as fees)", c),
+        CoinDiff::Negative(c) => println!("balance:        {} missing", c),
+    }
+    Ok(())
+}
+
+/// read the private keys, one hexadecimal key per line
+fn keys_from_input<R: BufRead>(input: R, inputs: usize) -> Result<Vec<hdwallet::XPrv>, Error> {
+    let mut keys = Vec::new();
+    for line in input.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        keys.push(hdwallet::XPrv::from_str(line)?);
+    }
+    if keys.len() == 1 {
+        let key = keys.pop().unwrap();
+        keys = vec![key; inputs];
+    }
+    if keys.len() != inputs {
+        return Err(Error::KeyCountMismatch {
+            inputs,
+            keys: keys.len(),
+        });
+    }
+    Ok(keys)
+}
+
+fn keys_from_wallet(args: &WalletArgs, spec: &Spec) -> Result<Vec<hdwallet::XPrv>, Error> {
+    let wallet = wallet::open(args)?;
+    spec.inputs
+        .iter()
+        .enumerate()
+        .map(|(idx, input)| match input.path {
+            None => Err(Error::MissingPath(idx)),
+            Some(path) => Ok(wallet::private_key(&wallet, path)),
+        })
+        .collect()
+}
+
+fn sign(
+    builder: TxBuilder,
+    protocol_magic: ProtocolMagic,
+    keys: &[hdwallet::XPrv],
+) -> Result<TxAux, Error> {
+    let tx = builder.make_tx()?;
+    let txid = tx.id();
+    let mut finalized = TxFinalized::new(tx);
+    for key in keys {
+        finalized.add_witness(TxInWitness::new_extended_pk(protocol_magic, key, &txid))?;
+    }
+    Ok(finalized.make_txaux()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn single_key_signs_all_inputs() {
+        let key = hdwallet::XPrv::normalize_bytes([7; hdwallet::XPRV_SIZE]);
+        let input = format!("{}\n\n", key);
+        let keys = keys_from_input(input.as_bytes(), 3).unwrap();
+        assert_eq!(keys, vec![key; 3]);
+    }
+
+    #[test]
+    fn key_count_mismatch() {
+        let key = hdwallet::XPrv::normalize_bytes([7; hdwallet::XPRV_SIZE]);
+        let input = format!("{}\n{}\n", key, key);
+        assert!(keys_from_input(input.as_bytes(), 3).is_err());
+    }
+
+    #[test]
+    fn spec_and_keys_from_stdin_rejected() {
+        let command = Command::from_iter(&["tx", "sign", "-", "--keys-from-stdin"]);
+        match command.exec() {
+            Err(Error::StdinConflict) => {}
+            _ => panic!("expected the standard input conflict"),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+//! JSON specification of a transaction
+//!
+//! ```json
+//! {
+//!   "protocol_magic": 764824073,
+//!   "fee": { "constant": 155381, "coefficient": 43.946 },
+//!   "inputs": [
+//!     { "txid": "<hex>", "index": 0, "value": 1000000, "path": "m/44'/1815'/0'/0/0" }
+//!   ],
+//!   "outputs": [
+//!     { "address": "<base58>", "value": 500000 }
+//!   ],
+//!   "change": "<base58>"
+//! }
+//! ```
+//!
+//! `protocol_magic` and `fee` are optional (default to the mainnet's),
+//! so is `change`: without it the leftover of the inputs goes to the fees.
+//! The inputs' `path` is only needed to sign with a stored wallet.
+
+use cardano::{address::ExtendedAddr, coin::Coin, config::ProtocolMagic, fee, tx::TxId};
+use exe_common::txspec::LinearFee;
+use serde::{Deserialize, Deserializer};
+
+use std::{fs, io, path::Path};
+
+use crate::wallet::DerivationPath;
+
+#[derive(Deserialize)]
+pub struct Input {
+    pub txid: TxId,
+    pub index: u32,
+    pub value: Coin,
+    #[serde(default, deserialize_with = "deserialize_path")]
+    pub path: Option<DerivationPath>,
+}
+
+#[derive(Deserialize)]
+pub struct Output {
+    pub address: ExtendedAddr,
+    pub value: Coin,
+}
+
+#[derive(Deserialize)]
+pub struct Spec {
+    #[serde(default)]
+    pub protocol_magic: ProtocolMagic,
+    #[serde(default)]
+    fee: Option<LinearFee>,
+    pub inputs: Vec<Input>,
+    pub outputs: Vec<Output>,
+    #[serde(default)]
+    pub change: Option<ExtendedAddr>,
+}
+
+impl Spec {
+    /// read the specification from the given file, or the standard input
+    /// if the file is `-`
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, io::Error> {
+        let spec = if path.as_ref() == Path::new("-") {
+            serde_json::from_reader(io::stdin())
+        } else {
+            serde_json::from_reader(fs::File::open(path)?)
+        };
+        spec.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn fee_algorithm(&mut self) -> fee::LinearFee {
+        self.fee
+            .take()
+            .map(fee::LinearFee::from)
+            .unwrap_or_default()
+    }
+}
+
+fn deserialize_path<'de, D>(deserializer: D) -> Result<Option<DerivationPath>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let path: Option<String> = Option::deserialize(deserializer)?;
+    match path {
+        None => Ok(None),
+        Some(path) => path.parse().map(Some).map_err(serde::de::Error::custom),
+    }
+}
@@ -0,0 +1,26 @@
+use std::io::{self, BufRead, Write};
+
+/// prompt the user (on the standard error) for a line of the standard input
+pub fn prompt_line(prompt: &str) -> io::Result<String> {
+    eprint!("{}", prompt);
+    io::stderr().flush()?;
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)?;
+    Ok(line.trim().to_owned())
+}
+
+/// prompt the user for a password, without echoing it
+pub fn prompt_password(prompt: &str) -> io::Result<String> {
+    rpassword::prompt_password_stderr(prompt)
+}
+
+/// prompt the user for a new password, asking for a confirmation
+pub fn prompt_new_password(prompt: &str) -> io::Result<Option<String>> {
+    let password = prompt_password(prompt)?;
+    let confirmation = prompt_password("confirm password: ")?;
+    if password == confirmation {
+        Ok(Some(password))
+    } else {
+        Ok(None)
+    }
+}
@@ -0,0 +1,327 @@
+//! `wallet` subcommand: creation, recovery and address derivation
+
+use cardano::{
+    address::ExtendedAddr,
+    bip::bip39,
+    config::ProtocolMagic,
+    hdpayload::Path,
+    hdwallet,
+    wallet::{
+        bip44::{self, AddrType, Addressing},
+        keygen,
+        scheme::Wallet as _,
+    },
+};
+use exe_common::wallet as storage;
+use rand::{rngs::OsRng, RngCore};
+use structopt::StructOpt;
+
+use std::{fmt, io, path::PathBuf, str::FromStr};
+
+use crate::utils;
+
+pub use self::storage::WalletFile;
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Storage(storage::Error),
+    Bip39(bip39::Error),
+    Bip44(bip44::Error),
+    Derivation(hdwallet::Error),
+    PasswordMismatch,
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::Storage(err) => write!(f, "{}", err),
+            Error::Bip39(err) => write!(f, "invalid mnemonics: {}", err),
+            Error::Bip44(err) => write!(f, "invalid derivation path: {}", err),
+            Error::Derivation(err) => write!(f, "derivation failed: {}", err),
+            Error::PasswordMismatch => write!(f, "passwords do not match"),
+        }
+    }
+}
+impl std::error::Error for Error {}
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+impl From<storage::Error> for Error {
+    fn from(e: storage::Error) -> Self {
+        Error::Storage(e)
+    }
+}
+impl From<bip39::Error> for Error {
+    fn from(e: bip39::Error) -> Self {
+        Error::Bip39(e)
+    }
+}
+impl From<bip44::Error> for Error {
+    fn from(e: bip44::Error) -> Self {
+        Error::Bip44(e)
+    }
+}
+impl From<hdwallet::Error> for Error {
+    fn from(e: hdwallet::Error) -> Self {
+        Error::Derivation(e)
+    }
+}
+
+/// a BIP44 derivation path: `m/44'/1815'/account'/change/index`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DerivationPath(Addressing);
+impl FromStr for DerivationPath {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut elements = s.split('/');
+        if elements.next() != Some("m") {
+            return Err(format!("derivation path should start with `m/': {}", s));
+        }
+        let path = elements
+            .map(|e| {
+                let (e, hard) = if e.ends_with('\'') {
+                    (&e[..e.len() - 1], true)
+                } else {
+                    (e, false)
+                };
+                let index = e
+                    .parse::<u32>()
+                    .map_err(|err| format!("invalid derivation index `{}': {}", e, err))?;
+                if hard {
+                    Ok(index | 0x8000_0000)
+                } else {
+                    Ok(index)
+                }
+            })
+            .collect::<Result<Vec<u32>, String>>()?;
+        Addressing::from_path(Path::new(path))
+            .map(DerivationPath)
+            .map_err(|err| format!("{}", err))
+    }
+}
+impl fmt::Display for DerivationPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "m/44'/1815'/{}'/{}/{}",
+            self.0.account.get_account_number(),
+            self.0.change,
+            self.0.index.get_scheme_value()
+        )
+    }
+}
+
+#[derive(StructOpt)]
+pub struct WalletArgs {
+    /// the name of the wallet
+    #[structopt(name = "WALLET")]
+    pub name: String,
+
+    /// the directory where the wallets are stored
+    #[structopt(long = "wallet-dir", parse(from_os_str), default_value = "wallets")]
+    pub wallet_dir: PathBuf,
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub enum Command {
+    /// create a new wallet, the mnemonic phrase is displayed once and
+    /// needs to be written down to recover the wallet
+    #[structopt(name = "create")]
+    Create {
+        #[structopt(flatten)]
+        wallet: WalletArgs,
+
+        /// number of words of the mnemonic phrase (9, 12, 15, 18, 21 or 24)
+        #[structopt(long = "words", default_value = "24")]
+        words: usize,
+    },
+    /// recover a wallet from its mnemonic phrase, read from the standard input
+    #[structopt(name = "recover")]
+    Recover {
+        #[structopt(flatten)]
+        wallet: WalletArgs,
+    },
+    /// derive the addresses of the wallet, either of a given derivation path
+    /// or a batch of addresses of a given account
+    #[structopt(name = "address")]
+    Address {
+        #[structopt(flatten)]
+        wallet: WalletArgs,
+
+        /// protocol magic of the network (default: mainnet)
+        #[structopt(long = "protocol-magic", default_value = "764824073")]
+        protocol_magic: u32,
+
+        /// the BIP44 derivation path of the address (e.g. `m/44'/1815'/0'/0/5`)
+        #[structopt(long = "path")]
+        path: Option<DerivationPath>,
+
+        /// the account of the batch of addresses
+        #[structopt(long = "account", default_value = "0")]
+        account: u32,
+
+        /// derive the internal (change) addresses
+        #[structopt(long = "internal")]
+        internal: bool,
+
+        /// index of the first address of the batch
+        #[structopt(long = "from", default_value = "0")]
+        from: u32,
+
+        /// number of addresses of the batch
+        #[structopt(long = "count", default_value = "1")]
+        count: usize,
+    },
+}
+
+impl Command {
+    pub fn exec(self) -> Result<(), Error> {
+        match self {
+            Command::Create { wallet, words } => create(wallet, words),
+            Command::Recover { wallet } => recover(wallet),
+            Command::Address {
+                wallet,
+                protocol_magic,
+                path,
+                account,
+                internal,
+                from,
+                count,
+            } => {
+                let protocol_magic = ProtocolMagic::from(protocol_magic);
+                match path {
+                    Some(path) => address_path(wallet, protocol_magic, path),
+                    None => {
+                        let addr_type = if internal {
+                            AddrType::Internal
+                        } else {
+                            AddrType::External
+                        };
+                        address_batch(wallet, protocol_magic, account, addr_type, from, count)
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// encrypt and store the wallet associated to the given entropy
+fn store(args: &WalletArgs, entropy: &bip39::Entropy) -> Result<(), Error> {
+    let password = match utils::prompt_new_password("spending password: ")? {
+        None => return Err(Error::PasswordMismatch),
+        Some(password) => password,
+    };
+
+    let mut seed = [0; hdwallet::XPRV_SIZE];
+    keygen::generate_seed(entropy, &[], &mut seed);
+    let root_key = hdwallet::XPrv::normalize_bytes(seed);
+
+    let file = WalletFile::new(&root_key, Default::default(), password.as_bytes())?;
+    file.save(&args.wallet_dir, &args.name)?;
+    eprintln!("wallet `{}' created", args.name);
+    Ok(())
+}
+
+fn create(args: WalletArgs, words: usize) -> Result<(), Error> {
+    let t = bip39::Type::from_word_count(words)?;
+    let mut bytes = vec![0; t.to_key_size() / 8];
+    OsRng::new()
+        .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+        .fill_bytes(&mut bytes);
+    let entropy = bip39::Entropy::from_slice(&bytes)?;
+
+    println!(
+        "{}",
+        entropy
+            .to_mnemonics()
+            .to_string(&bip39::dictionary::ENGLISH)
+    );
+    eprintln!("write down the mnemonic phrase above, it is needed to recover the wallet");
+
+    store(&args, &entropy)
+}
+
+fn recover(args: WalletArgs) -> Result<(), Error> {
+    let phrase = utils::prompt_line("mnemonic phrase: ")?;
+    let mnemonics = bip39::Mnemonics::from_string(&bip39::dictionary::ENGLISH, &phrase)?;
+    let entropy = bip39::Entropy::from_mnemonics(&mnemonics)?;
+
+    store(&args, &entropy)
+}
+
+/// load the stored wallet, prompting for its spending password
+pub fn open(args: &WalletArgs) -> Result<bip44::Wallet, Error> {
+    let file = WalletFile::load(&args.wallet_dir, &args.name)?;
+    let password = utils::prompt_password("spending password: ")?;
+    let root_key = file.decrypt(password.as_bytes())?;
+    Ok(bip44::Wallet::from_root_key(
+        root_key,
+        file.derivation_scheme,
+    ))
+}
+
+/// the private key of the address of the given derivation path
+pub fn private_key(wallet: &bip44::Wallet, path: DerivationPath) -> hdwallet::XPrv {
+    let scheme = wallet.derivation_scheme();
+    let key = wallet
+        .account(scheme, path.0.account.get_account_number())
+        .change(scheme, path.0.address_type())
+        .index(scheme, path.0.index.get_scheme_value());
+    (*key).clone()
+}
+
+fn address_path(
+    args: WalletArgs,
+    protocol_magic: ProtocolMagic,
+    path: DerivationPath,
+) -> Result<(), Error> {
+    let wallet = open(&args)?;
+    let key = private_key(&wallet, path).public();
+    let address = ExtendedAddr::new_simple(key, protocol_magic.into());
+    println!("{} {}", path, address);
+    Ok(())
+}
+
+fn address_batch(
+    args: WalletArgs,
+    protocol_magic: ProtocolMagic,
+    account: u32,
+    addr_type: AddrType,
+    from: u32,
+    count: usize,
+) -> Result<(), Error> {
+    let mut wallet = open(&args)?;
+    Addressing::new(account, addr_type, from)?;
+    let account_key = wallet.create_account("", account).public();
+    for (i, key) in account_key
+        .address_generator(addr_type, from)?
+        .take(count)
+        .enumerate()
+    {
+        let key = key?;
+        let path = DerivationPath(Addressing::new(account, addr_type, from + i as u32)?);
+        let address = ExtendedAddr::new_simple((*key).clone(), protocol_magic.into());
+        println!("{} {}", path, address);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_derivation_path() {
+        let path: DerivationPath = "m/44'/1815'/2'/1/42".parse().unwrap();
+        assert_eq!(path.0, Addressing::new(2, AddrType::Internal, 42).unwrap());
+        assert_eq!(path.to_string(), "m/44'/1815'/2'/1/42");
+
+        assert!("m/44'/1815'/2/1/42".parse::<DerivationPath>().is_err());
+        assert!("44'/1815'/2'/1/42".parse::<DerivationPath>().is_err());
+        assert!("m/44'/1815'/2'/1/42'".parse::<DerivationPath>().is_err());
+    }
+}
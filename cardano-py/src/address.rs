@@ -0,0 +1,13 @@
+use pyo3::prelude::*;
+
+use cardano::address::ExtendedAddr;
+
+use std::str::FromStr;
+
+/// check whether the given string is a valid base58 address
+///
+/// equivalent to `cardano_address_is_valid`
+#[pyfunction]
+pub fn address_is_valid(address: &str) -> bool {
+    ExtendedAddr::from_str(address).is_ok()
+}
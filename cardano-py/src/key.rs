@@ -0,0 +1,89 @@
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use cardano::{address::ExtendedAddr, config::ProtocolMagic, hdwallet};
+
+use super::value_error;
+
+/// Extended Private Key
+#[pyclass]
+pub struct XPrv {
+    pub(crate) key: hdwallet::XPrv,
+}
+impl From<hdwallet::XPrv> for XPrv {
+    fn from(key: hdwallet::XPrv) -> Self {
+        XPrv { key }
+    }
+}
+
+#[pymethods]
+impl XPrv {
+    /// import a private key from its 96 bytes, checking it is a valid
+    /// extended private key
+    #[staticmethod]
+    fn from_bytes(bytes: &[u8]) -> PyResult<XPrv> {
+        if bytes.len() != hdwallet::XPRV_SIZE {
+            return Err(value_error(hdwallet::Error::InvalidXPrvSize(bytes.len())));
+        }
+        let mut array = [0u8; hdwallet::XPRV_SIZE];
+        array.copy_from_slice(bytes);
+        hdwallet::XPrv::from_bytes_verified(array)
+            .map(XPrv::from)
+            .map_err(value_error)
+    }
+
+    fn to_bytes(&self, py: Python) -> PyObject {
+        PyBytes::new(py, self.key.as_ref()).to_object(py)
+    }
+
+    /// derive the private key with the given index (V2 derivation scheme)
+    fn derive(&self, index: u32) -> PyResult<XPrv> {
+        Ok(XPrv::from(
+            self.key.derive(hdwallet::DerivationScheme::V2, index),
+        ))
+    }
+
+    fn public(&self) -> PyResult<XPub> {
+        Ok(XPub::from(self.key.public()))
+    }
+}
+
+/// Extended Public Key
+#[pyclass]
+pub struct XPub {
+    pub(crate) key: hdwallet::XPub,
+}
+impl From<hdwallet::XPub> for XPub {
+    fn from(key: hdwallet::XPub) -> Self {
+        XPub { key }
+    }
+}
+
+#[pymethods]
+impl XPub {
+    #[staticmethod]
+    fn from_bytes(bytes: &[u8]) -> PyResult<XPub> {
+        hdwallet::XPub::from_slice(bytes)
+            .map(XPub::from)
+            .map_err(value_error)
+    }
+
+    fn to_bytes(&self, py: Python) -> PyObject {
+        PyBytes::new(py, self.key.as_ref()).to_object(py)
+    }
+
+    /// derive the public key with the given soft index (V2 derivation scheme)
+    fn derive(&self, index: u32) -> PyResult<XPub> {
+        self.key
+            .derive(hdwallet::DerivationScheme::V2, index)
+            .map(XPub::from)
+            .map_err(value_error)
+    }
+
+    /// the bootstrap era address (base58) of this public key
+    fn address(&self, protocol_magic: u32) -> PyResult<String> {
+        let address =
+            ExtendedAddr::new_simple(self.key.clone(), ProtocolMagic::from(protocol_magic).into());
+        Ok(format!("{}", address))
+    }
+}
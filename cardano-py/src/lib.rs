@@ -0,0 +1,38 @@
+//! Python bindings of the Cardano wallet primitives
+//!
+//! The exported surface mirrors the one of `cardano-c`: wallet restore,
+//! BIP44 address derivation, transaction building and signing. Binary
+//! objects are exchanged as `bytes`, addresses as base58 `str` and values
+//! as `int` (Lovelace).
+//!
+#![feature(specialization)]
+
+use pyo3::prelude::*;
+use pyo3::wrap_pyfunction;
+
+pub mod address;
+pub mod key;
+pub mod transaction;
+pub mod wallet;
+
+/// convert any displayable error into a Python `ValueError`
+fn value_error<E: std::fmt::Display>(e: E) -> PyErr {
+    pyo3::exceptions::ValueError::py_err(format!("{}", e))
+}
+
+#[pymodule]
+fn cardano_py(_py: Python, m: &PyModule) -> PyResult<()> {
+    m.add_class::<wallet::Wallet>()?;
+    m.add_class::<wallet::Account>()?;
+    m.add_class::<key::XPrv>()?;
+    m.add_class::<key::XPub>()?;
+    m.add_class::<transaction::TransactionBuilder>()?;
+    m.add_class::<transaction::Transaction>()?;
+    m.add_class::<transaction::TransactionFinalized>()?;
+    m.add_class::<transaction::SignedTransaction>()?;
+
+    m.add_wrapped(wrap_pyfunction!(wallet::entropy_from_english_mnemonics))?;
+    m.add_wrapped(wrap_pyfunction!(wallet::entropy_to_english_mnemonics))?;
+    m.add_wrapped(wrap_pyfunction!(address::address_is_valid))?;
+    Ok(())
+}
@@ -0,0 +1,209 @@
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use cardano::{
+    address::ExtendedAddr,
+    coin::{Coin, CoinDiff},
+    config::ProtocolMagic,
+    fee::LinearFee,
+    tx::{self, TxId, TxInWitness},
+    txbuild::{TxBuilder, TxFinalized},
+    txutils::OutputPolicy,
+    util::try_from_slice::TryFromSlice,
+};
+
+use std::str::FromStr;
+
+use super::key::XPrv;
+use super::value_error;
+
+fn cbor_bytes<T: cbor_event::se::Serialize>(py: Python, t: &T) -> PyResult<PyObject> {
+    let mut se = cbor_event::se::Serializer::new_vec();
+    se.serialize(t).map_err(value_error)?;
+    Ok(PyBytes::new(py, &se.finalize()).to_object(py))
+}
+
+fn parse_address(address: &str) -> PyResult<ExtendedAddr> {
+    ExtendedAddr::from_str(address)
+        .map_err(|e| value_error(format!("invalid address: {:?}", e)))
+}
+
+fn signed_balance(diff: CoinDiff) -> i64 {
+    match diff {
+        CoinDiff::Positive(v) => u64::from(v) as i64,
+        CoinDiff::Negative(v) => -(u64::from(v) as i64),
+        CoinDiff::Zero => 0,
+    }
+}
+
+/// build a transaction step by step, the fees are computed with the
+/// default linear fee algorithm
+#[pyclass]
+pub struct TransactionBuilder {
+    builder: TxBuilder,
+    fee: LinearFee,
+}
+
+#[pymethods]
+impl TransactionBuilder {
+    #[new]
+    fn new(obj: &PyRawObject) {
+        obj.init(TransactionBuilder {
+            builder: TxBuilder::new(),
+            fee: LinearFee::default(),
+        })
+    }
+
+    /// add the output `index` of the transaction `txid` (32 bytes) holding
+    /// the given value
+    fn add_input(&mut self, txid: &[u8], index: u32, value: u64) -> PyResult<()> {
+        let txid = TxId::try_from_slice(txid).map_err(value_error)?;
+        let value = Coin::new(value).map_err(value_error)?;
+        self.builder
+            .add_input(&tx::TxoPointer::new(txid, index), value);
+        Ok(())
+    }
+
+    fn add_output(&mut self, address: &str, value: u64) -> PyResult<()> {
+        let address = parse_address(address)?;
+        let value = Coin::new(value).map_err(value_error)?;
+        self.builder
+            .add_output_value(&tx::TxOut::new(address, value));
+        Ok(())
+    }
+
+    /// send the leftover of the inputs, minus the fees, to the given
+    /// address. Returns the list of `(address, value)` outputs added.
+    fn add_change_address(&mut self, address: &str) -> PyResult<Vec<(String, u64)>> {
+        let address = parse_address(address)?;
+        let outputs = self
+            .builder
+            .add_output_policy(&self.fee, &OutputPolicy::One(address))
+            .map_err(value_error)?;
+        Ok(outputs
+            .into_iter()
+            .map(|out| (format!("{}", out.address), u64::from(out.value)))
+            .collect())
+    }
+
+    /// the fee required by the current state of the transaction
+    fn fee(&self) -> PyResult<u64> {
+        self.builder
+            .calculate_fee(&self.fee)
+            .map(|fee| u64::from(fee.to_coin()))
+            .map_err(value_error)
+    }
+
+    /// inputs - (outputs + fees): positive if the inputs are greater,
+    /// negative if more inputs are needed
+    fn balance(&self) -> PyResult<i64> {
+        self.builder
+            .balance(&self.fee)
+            .map(signed_balance)
+            .map_err(value_error)
+    }
+
+    /// inputs - outputs
+    fn balance_without_fees(&self) -> PyResult<i64> {
+        self.builder
+            .balance_without_fees()
+            .map(signed_balance)
+            .map_err(value_error)
+    }
+
+    fn input_total(&self) -> PyResult<u64> {
+        self.builder
+            .get_input_total()
+            .map(u64::from)
+            .map_err(value_error)
+    }
+
+    fn output_total(&self) -> PyResult<u64> {
+        self.builder
+            .get_output_total()
+            .map(u64::from)
+            .map_err(value_error)
+    }
+
+    /// build the transaction, the builder is left unchanged
+    fn finalize(&self) -> PyResult<Transaction> {
+        self.builder
+            .clone()
+            .make_tx()
+            .map(|tx| Transaction { tx })
+            .map_err(value_error)
+    }
+}
+
+/// a transaction without witnesses
+#[pyclass]
+pub struct Transaction {
+    tx: tx::Tx,
+}
+
+#[pymethods]
+impl Transaction {
+    fn id(&self, py: Python) -> PyObject {
+        PyBytes::new(py, self.tx.id().as_ref()).to_object(py)
+    }
+
+    /// the CBOR encoded transaction
+    fn to_bytes(&self, py: Python) -> PyResult<PyObject> {
+        cbor_bytes(py, &self.tx)
+    }
+}
+
+/// collect the witnesses of a transaction, in the same order as the inputs
+#[pyclass]
+pub struct TransactionFinalized {
+    finalized: TxFinalized,
+    txid: TxId,
+}
+
+#[pymethods]
+impl TransactionFinalized {
+    #[new]
+    fn new(obj: &PyRawObject, transaction: &Transaction) {
+        obj.init(TransactionFinalized {
+            finalized: TxFinalized::new(transaction.tx.clone()),
+            txid: transaction.tx.id(),
+        })
+    }
+
+    /// sign the transaction with the private key of the next input
+    fn add_witness(&mut self, xprv: &XPrv, protocol_magic: u32) -> PyResult<()> {
+        let witness = TxInWitness::new_extended_pk(
+            ProtocolMagic::from(protocol_magic),
+            &xprv.key,
+            &self.txid,
+        );
+        self.finalized.add_witness(witness).map_err(value_error)
+    }
+
+    /// the signed transaction, fails if there is not one witness per input
+    fn output(&self) -> PyResult<SignedTransaction> {
+        self.finalized
+            .clone()
+            .make_txaux()
+            .map(|txaux| SignedTransaction { txaux })
+            .map_err(value_error)
+    }
+}
+
+/// a transaction with its witnesses, ready to be sent to the network
+#[pyclass]
+pub struct SignedTransaction {
+    txaux: tx::TxAux,
+}
+
+#[pymethods]
+impl SignedTransaction {
+    fn id(&self, py: Python) -> PyObject {
+        PyBytes::new(py, self.txaux.tx.id().as_ref()).to_object(py)
+    }
+
+    /// the CBOR encoded signed transaction
+    fn to_bytes(&self, py: Python) -> PyResult<PyObject> {
+        cbor_bytes(py, &self.txaux)
+    }
+}
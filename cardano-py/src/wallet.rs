@@ -0,0 +1,134 @@
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+
+use cardano::{
+    address::ExtendedAddr,
+    bip::{
+        bip39,
+        bip44::{Account as AccountIndex, Index},
+    },
+    config::ProtocolMagic,
+    hdwallet,
+    wallet::{
+        bip44::{self, AddrType},
+        scheme::Wallet as _,
+    },
+};
+
+use super::key::{XPrv, XPub};
+use super::value_error;
+
+pub(crate) fn addr_type(internal: bool) -> AddrType {
+    if internal {
+        AddrType::Internal
+    } else {
+        AddrType::External
+    }
+}
+
+/// retrieve the entropy from the given english mnemonics
+#[pyfunction]
+pub fn entropy_from_english_mnemonics(py: Python, mnemonics: &str) -> PyResult<PyObject> {
+    let mnemonics = bip39::Mnemonics::from_string(&bip39::dictionary::ENGLISH, mnemonics)
+        .map_err(value_error)?;
+    let entropy = bip39::Entropy::from_mnemonics(&mnemonics).map_err(value_error)?;
+    Ok(PyBytes::new(py, entropy.as_ref()).to_object(py))
+}
+
+/// retrieve the english mnemonics associated to the given entropy
+#[pyfunction]
+pub fn entropy_to_english_mnemonics(entropy: &[u8]) -> PyResult<String> {
+    let entropy = bip39::Entropy::from_slice(entropy).map_err(value_error)?;
+    Ok(format!(
+        "{}",
+        entropy
+            .to_mnemonics()
+            .to_string(&bip39::dictionary::ENGLISH)
+    ))
+}
+
+/// HD BIP44 compliant Wallet
+#[pyclass]
+pub struct Wallet {
+    wallet: bip44::Wallet,
+}
+
+#[pymethods]
+impl Wallet {
+    /// create a wallet from the given entropy and password (can be empty)
+    ///
+    /// equivalent to `cardano_wallet_new`
+    #[staticmethod]
+    fn from_entropy(entropy: &[u8], password: &[u8]) -> PyResult<Wallet> {
+        let entropy = bip39::Entropy::from_slice(entropy).map_err(value_error)?;
+        let wallet =
+            bip44::Wallet::from_entropy(&entropy, password, hdwallet::DerivationScheme::V2);
+        Ok(Wallet { wallet })
+    }
+
+    /// create a new account, the alias is only an handy tool to retrieve
+    /// a created account from a wallet. We do not check if there is already
+    /// an account with the given index.
+    ///
+    /// equivalent to `cardano_account_create`
+    fn create_account(&mut self, alias: &str, index: u32) -> PyResult<Account> {
+        AccountIndex::new(index).map_err(value_error)?;
+        let account = self.wallet.create_account(alias, index);
+        Ok(Account {
+            account: account.public(),
+        })
+    }
+
+    /// the private key of the address `m/44'/1815'/account'/change/index`,
+    /// to sign the transaction inputs spending from this address
+    fn address_private_key(&self, account: u32, internal: bool, index: u32) -> PyResult<XPrv> {
+        AccountIndex::new(account).map_err(value_error)?;
+        Index::new(index).map_err(value_error)?;
+        let scheme = self.wallet.derivation_scheme();
+        let key = self
+            .wallet
+            .account(scheme, account)
+            .change(scheme, addr_type(internal))
+            .index(scheme, index);
+        Ok(XPrv::from((*key).clone()))
+    }
+}
+
+/// public part of a BIP44 account, to generate addresses
+#[pyclass]
+pub struct Account {
+    account: bip44::Account<hdwallet::XPub>,
+}
+
+#[pymethods]
+impl Account {
+    /// generate `num_indices` base58 addresses starting from `from_index`
+    /// on the internal (change) chain or the external chain.
+    ///
+    /// equivalent to `cardano_account_generate_addresses`
+    fn generate_addresses(
+        &self,
+        internal: bool,
+        from_index: u32,
+        num_indices: usize,
+        protocol_magic: u32,
+    ) -> PyResult<Vec<String>> {
+        let network_magic = ProtocolMagic::from(protocol_magic).into();
+        self.account
+            .address_generator(addr_type(internal), from_index)
+            .map_err(value_error)?
+            .take(num_indices)
+            .map(|xpub| {
+                let xpub = xpub.map_err(value_error)?;
+                let address = ExtendedAddr::new_simple((*xpub).clone(), network_magic);
+                Ok(format!("{}", address))
+            })
+            .collect()
+    }
+
+    /// the account's public key
+    fn public_key(&self) -> PyResult<XPub> {
+        let xpub: &hdwallet::XPub = &self.account;
+        Ok(XPub::from(xpub.clone()))
+    }
+}
@@ -0,0 +1,364 @@
+//! the local HTTP API
+//!
+//! ```text
+//! GET  /api/v1/tip                 the tip of the local storage
+//! GET  /api/v1/utxos/<address>     the UTxOs of the address
+//! GET  /api/v1/balance/<address>   the sum of the UTxOs of the address
+//! GET  /api/v1/addresses/<address> the indexed balance of the address
+//! GET  /api/v1/addresses/<address>/transactions?offset=<n>&limit=<n>
+//!                                  the indexed transactions of the address,
+//!                                  the newest first
+//! POST /api/v1/txs                 submit a signed transaction (CBOR hex)
+//! GET  /metrics                    the metrics in the Prometheus text format,
+//!                                  with the `prometheus` feature
+//! ```
+//!
+//! Every response but the metrics is JSON. Errors are returned as
+//! `{ "error": "..." }`.
+
+use cardano::{address::ExtendedAddr, block::BlockDate, coin, tx::TxAux, util::hex};
+use exe_common::{network::Api as NetworkApi, sync};
+use futures::{future, Future, Stream};
+use futures_cpupool::CpuPool;
+use hyper::{
+    header::ContentType,
+    server::{Request, Response, Service},
+    Method, StatusCode,
+};
+use serde::Serialize;
+
+use std::{io::Cursor, str::FromStr, sync::Arc};
+
+use crate::state::{State, Tip};
+
+const PREFIX: &str = "/api/v1/";
+
+/// number of transactions of a page of the history of an address
+const DEFAULT_PAGE_LIMIT: usize = 50;
+const MAX_PAGE_LIMIT: usize = 500;
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+#[derive(Serialize)]
+struct Utxo {
+    txid: String,
+    index: u32,
+    address: String,
+    value: u64,
+}
+
+#[derive(Serialize)]
+struct Balance {
+    address: String,
+    balance: u64,
+    utxos: usize,
+}
+
+#[derive(Serialize)]
+struct AddressSummary {
+    address: String,
+    balance: u64,
+    transactions: usize,
+    /// the last block taken into account by the index
+    indexed_tip: Option<Tip>,
+}
+
+#[derive(Serialize)]
+struct AddressTx {
+    txid: String,
+    block: String,
+    date: BlockDate,
+    received: u64,
+    sent: u64,
+}
+
+#[derive(Serialize)]
+struct AddressHistory {
+    address: String,
+    total: usize,
+    offset: usize,
+    limit: usize,
+    transactions: Vec<AddressTx>,
+}
+
+#[derive(Serialize)]
+struct Submitted {
+    txid: String,
+    sent: bool,
+}
+
+pub struct Api {
+    state: Arc<State>,
+    /// the threads of the blocking queries (restoring the UTxOs), kept out
+    /// of the event loop of the server
+    pool: CpuPool,
+}
+
+impl Api {
+    pub fn new(state: Arc<State>, pool: CpuPool) -> Self {
+        Api { state, pool }
+    }
+
+    fn blocking<F>(&self, f: F) -> Box<dyn Future<Item = Response, Error = hyper::Error>>
+    where
+        F: FnOnce(&State) -> Response + Send + 'static,
+    {
+        let state = self.state.clone();
+        Box::new(
+            self.pool
+                .spawn_fn(move || Ok::<_, ()>(f(&state)))
+                .map_err(|()| -> hyper::Error { unreachable!() }),
+        )
+    }
+}
+
+impl Service for Api {
+    type Request = Request;
+    type Response = Response;
+    type Error = hyper::Error;
+    type Future = Box<dyn Future<Item = Self::Response, Error = Self::Error>>;
+
+    fn call(&self, req: Request) -> Self::Future {
+        #[cfg(feature = "prometheus")]
+        {
+            if req.method() == &Method::Get && req.path() == "/metrics" {
+                return Box::new(future::ok(
+                    Response::new()
+                        .with_header(ContentType::plaintext())
+                        .with_body(self.state.metrics.render()),
+                ));
+            }
+        }
+        if !req.path().starts_with(PREFIX) {
+            return Box::new(future::ok(not_found()));
+        }
+        let path = req.path()[PREFIX.len()..].to_owned();
+        let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+        let method = req.method().clone();
+
+        match (&method, &segments[..]) {
+            (&Method::Get, ["tip"]) => Box::new(future::ok(self.tip())),
+            (&Method::Get, ["utxos", address]) => {
+                let address = address.to_string();
+                self.blocking(move |state| utxos(state, &address))
+            }
+            (&Method::Get, ["balance", address]) => {
+                let address = address.to_string();
+                self.blocking(move |state| balance(state, &address))
+            }
+            (&Method::Get, ["addresses", address]) => Box::new(future::ok(self.address(address))),
+            (&Method::Get, ["addresses", address, "transactions"]) => Box::new(future::ok(
+                self.address_transactions(address, req.query().unwrap_or("")),
+            )),
+            (&Method::Post, ["txs"]) => {
+                let state = self.state.clone();
+                Box::new(
+                    req.body()
+                        .concat2()
+                        .map(move |body| submit(&state, &body)),
+                )
+            }
+            _ => Box::new(future::ok(not_found())),
+        }
+    }
+}
+
+impl Api {
+    fn tip(&self) -> Response {
+        match self.state.tip() {
+            Ok(Some(tip)) => json(StatusCode::Ok, &tip),
+            Ok(None) => error(StatusCode::ServiceUnavailable, "not synchronised yet"),
+            Err(err) => error(StatusCode::InternalServerError, err),
+        }
+    }
+
+    fn address(&self, address: &str) -> Response {
+        let address = match parse_address(address) {
+            Ok(address) => address.to_string(),
+            Err(response) => return response,
+        };
+        let summary = self.state.with_index(|index| AddressSummary {
+            balance: index.balance(&address),
+            transactions: index.history(&address).len(),
+            indexed_tip: index.tip().map(|(hash, date)| Tip {
+                hash: hash.clone(),
+                date: date.clone(),
+            }),
+            address,
+        });
+        match summary {
+            Some(summary) => json(StatusCode::Ok, &summary),
+            None => error(StatusCode::ServiceUnavailable, "address index not loaded yet"),
+        }
+    }
+
+    fn address_transactions(&self, address: &str, query: &str) -> Response {
+        let address = match parse_address(address) {
+            Ok(address) => address.to_string(),
+            Err(response) => return response,
+        };
+        let (offset, limit) = match parse_page(query) {
+            Ok(page) => page,
+            Err(response) => return response,
+        };
+        let history = self.state.with_index(|index| {
+            let history = index.history(&address);
+            let transactions = history
+                .iter()
+                .rev()
+                .skip(offset)
+                .take(limit)
+                .map(|record| AddressTx {
+                    txid: record.txid.to_string(),
+                    block: record.block.to_string(),
+                    date: record.date.clone(),
+                    received: record.received,
+                    sent: record.sent,
+                })
+                .collect();
+            AddressHistory {
+                address,
+                total: history.len(),
+                offset,
+                limit,
+                transactions,
+            }
+        });
+        match history {
+            Some(history) => json(StatusCode::Ok, &history),
+            None => error(StatusCode::ServiceUnavailable, "address index not loaded yet"),
+        }
+    }
+}
+
+/// the UTxOs of an address, blocking until they are restored
+fn utxos(state: &State, address: &str) -> Response {
+    let address = match parse_address(address) {
+        Ok(address) => address,
+        Err(response) => return response,
+    };
+    match state.utxos_of(&address) {
+        Ok(utxos) => {
+            let utxos: Vec<_> = utxos
+                .into_iter()
+                .map(|(ptr, txout)| Utxo {
+                    txid: ptr.id.to_string(),
+                    index: ptr.index,
+                    address: txout.address.to_string(),
+                    value: txout.value.into(),
+                })
+                .collect();
+            json(StatusCode::Ok, &utxos)
+        }
+        Err(err) => error(StatusCode::InternalServerError, err),
+    }
+}
+
+/// the balance of an address, blocking until the UTxOs are restored
+fn balance(state: &State, address: &str) -> Response {
+    let address = match parse_address(address) {
+        Ok(address) => address,
+        Err(response) => return response,
+    };
+    let utxos = match state.utxos_of(&address) {
+        Ok(utxos) => utxos,
+        Err(err) => return error(StatusCode::InternalServerError, err),
+    };
+    match coin::sum_coins(utxos.iter().map(|(_, txout)| txout.value)) {
+        Ok(balance) => json(
+            StatusCode::Ok,
+            &Balance {
+                address: address.to_string(),
+                balance: balance.into(),
+                utxos: utxos.len(),
+            },
+        ),
+        Err(err) => error(StatusCode::InternalServerError, err),
+    }
+}
+
+/// decode the signed transaction and send it to the network through a new
+/// connection to one of the configured peers
+fn submit(state: &State, body: &[u8]) -> Response {
+    let bytes = match std::str::from_utf8(body).map(|s| hex::decode(s.trim())) {
+        Ok(Ok(bytes)) => bytes,
+        _ => return error(StatusCode::BadRequest, "expected a CBOR hex encoded transaction"),
+    };
+    let txaux: TxAux = match cbor_event::de::Deserializer::from(Cursor::new(bytes)).deserialize() {
+        Ok(txaux) => txaux,
+        Err(err) => return error(StatusCode::BadRequest, err),
+    };
+    let txid = txaux.tx.id();
+
+    if state.net_cfg.peers.natives().is_empty() {
+        return error(StatusCode::ServiceUnavailable, "no native peer configured");
+    }
+    let mut peer = sync::get_peer(&state.network, &state.net_cfg, true);
+    match peer.send_transaction(txaux) {
+        Ok(sent) => json(
+            StatusCode::Ok,
+            &Submitted {
+                txid: txid.to_string(),
+                sent,
+            },
+        ),
+        Err(err) => error(StatusCode::BadGateway, err),
+    }
+}
+
+fn parse_address(address: &str) -> Result<ExtendedAddr, Response> {
+    ExtendedAddr::from_str(address)
+        .map_err(|e| error(StatusCode::BadRequest, format!("invalid address: {:?}", e)))
+}
+
+/// the `offset` and `limit` parameters of the query string
+fn parse_page(query: &str) -> Result<(usize, usize), Response> {
+    let mut offset = 0;
+    let mut limit = DEFAULT_PAGE_LIMIT;
+    for param in query.split('&').filter(|param| !param.is_empty()) {
+        let mut kv = param.splitn(2, '=');
+        let (key, value) = (kv.next().unwrap(), kv.next().unwrap_or(""));
+        let value = usize::from_str(value)
+            .map_err(|_| error(StatusCode::BadRequest, format!("invalid {}: {}", key, value)))?;
+        match key {
+            "offset" => offset = value,
+            "limit" if value > 0 && value <= MAX_PAGE_LIMIT => limit = value,
+            "limit" => {
+                return Err(error(
+                    StatusCode::BadRequest,
+                    format!("limit must be between 1 and {}", MAX_PAGE_LIMIT),
+                ))
+            }
+            _ => {}
+        }
+    }
+    Ok((offset, limit))
+}
+
+fn json<T: Serialize>(status: StatusCode, t: &T) -> Response {
+    match serde_json::to_string(t) {
+        Ok(body) => Response::new()
+            .with_status(status)
+            .with_header(ContentType::json())
+            .with_body(body),
+        Err(err) => error(StatusCode::InternalServerError, err),
+    }
+}
+
+fn error<E: ToString>(status: StatusCode, err: E) -> Response {
+    let body = ErrorResponse {
+        error: err.to_string(),
+    };
+    Response::new()
+        .with_status(status)
+        .with_header(ContentType::json())
+        // serializing a struct of a single string can't fail
+        .with_body(serde_json::to_string(&body).unwrap())
+}
+
+fn not_found() -> Response {
+    error(StatusCode::NotFound, "unknown endpoint")
+}
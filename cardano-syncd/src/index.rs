@@ -0,0 +1,352 @@
+//! persistent index of the transactions of the addresses
+//!
+//! The index follows the blocks imported in the local storage: every
+//! transaction is recorded in the history of the addresses of its outputs
+//! and of the outputs it spends, and the balances of the addresses are kept
+//! up to date with the UTxOs at the last indexed block.
+//!
+//! The index is saved as a journal in the storage directory, one JSON line
+//! per block with transactions, each batch of blocks ending with a line
+//! committing the last indexed block. A batch interrupted before its commit
+//! line is discarded when the journal is loaded again.
+
+use cardano::{
+    block::{normal::TxPayload, BlockDate, ChainState, HeaderHash, Utxos},
+    config::GenesisData,
+    tx::{TxId, TxoPointer},
+};
+use cardano_storage::{
+    chain_state,
+    iter::{Bound, Direction},
+    tag, Storage,
+};
+
+use std::{
+    collections::HashMap,
+    fmt,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, BufReader, Seek, SeekFrom, Write},
+    path::PathBuf,
+};
+
+/// name of the journal of the index, in the storage directory
+const JOURNAL_FILE: &str = "address-index";
+
+/// a transaction of the history of an address
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TxRecord {
+    pub txid: TxId,
+    pub block: HeaderHash,
+    pub date: BlockDate,
+    /// the sum of the outputs of the transaction to the address
+    pub received: u64,
+    /// the sum of the outputs of the address spent by the transaction
+    pub sent: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+enum JournalEntry {
+    /// the records of the transactions of a block
+    Block(Vec<(String, TxRecord)>),
+    /// the last block of a batch, all the entries before are indexed
+    Commit(HeaderHash, BlockDate),
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Storage(cardano_storage::Error),
+    Io(io::Error),
+    Journal(serde_json::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Storage(err) => write!(f, "storage error: {}", err),
+            Error::Io(err) => write!(f, "cannot write the address index: {}", err),
+            Error::Journal(err) => write!(f, "invalid address index entry: {}", err),
+        }
+    }
+}
+
+impl From<cardano_storage::Error> for Error {
+    fn from(e: cardano_storage::Error) -> Self {
+        Error::Storage(e)
+    }
+}
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Journal(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+pub struct AddressIndex {
+    path: PathBuf,
+    /// the last indexed block, `None` until the first batch is indexed
+    tip: Option<(HeaderHash, BlockDate)>,
+    /// the UTxOs at the last indexed block, to resolve the spent inputs
+    utxos: Utxos,
+    balances: HashMap<String, u64>,
+    /// the transactions of each address, from the oldest to the newest
+    history: HashMap<String, Vec<TxRecord>>,
+}
+
+impl AddressIndex {
+    /// load the index saved in the storage directory, the UTxOs at the last
+    /// indexed block are restored from the chain state
+    pub fn load(storage: &Storage, genesis_data: &GenesisData) -> Result<Self> {
+        let path = storage.config.root_path.join(JOURNAL_FILE);
+        let mut index = AddressIndex::empty(path.clone(), genesis_data);
+
+        let file = match File::open(&path) {
+            Ok(file) => file,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(index),
+            Err(err) => return Err(err.into()),
+        };
+        let mut pending = Vec::new();
+        let mut committed_len = 0;
+        let mut len = 0;
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            len += line.len() as u64 + 1;
+            match serde_json::from_str(&line) {
+                Ok(JournalEntry::Block(records)) => pending.push(records),
+                Ok(JournalEntry::Commit(hash, date)) => {
+                    for records in pending.drain(..) {
+                        index.add_history(records);
+                    }
+                    index.tip = Some((hash, date));
+                    committed_len = len;
+                }
+                // the end of an interrupted batch
+                Err(_) => break,
+            }
+        }
+        OpenOptions::new()
+            .write(true)
+            .open(&path)?
+            .set_len(committed_len)?;
+
+        if let Some((hash, _)) = &index.tip {
+            index.utxos = chain_state::restore_chain_state(storage, genesis_data, hash)?.utxos;
+        }
+        index.balances = balances(&index.utxos);
+        Ok(index)
+    }
+
+    fn empty(path: PathBuf, genesis_data: &GenesisData) -> Self {
+        let utxos = ChainState::new(genesis_data).utxos;
+        AddressIndex {
+            path,
+            tip: None,
+            balances: balances(&utxos),
+            utxos,
+            history: HashMap::new(),
+        }
+    }
+
+    /// the last indexed block
+    pub fn tip(&self) -> Option<&(HeaderHash, BlockDate)> {
+        self.tip.as_ref()
+    }
+
+    /// the balance of the address at the last indexed block
+    pub fn balance(&self, address: &str) -> u64 {
+        self.balances.get(address).cloned().unwrap_or(0)
+    }
+
+    /// the transactions of the address, from the oldest to the newest
+    pub fn history(&self, address: &str) -> &[TxRecord] {
+        self.history.get(address).map_or(&[], |txs| &txs[..])
+    }
+
+    /// index at most `max_blocks` of the blocks imported since the last
+    /// indexed block, returning the number of blocks indexed
+    ///
+    /// If the last indexed block is no longer on the chain of the storage
+    /// (a rollback), the index is built again from the genesis.
+    pub fn update(
+        &mut self,
+        storage: &Storage,
+        genesis_data: &GenesisData,
+        max_blocks: usize,
+    ) -> Result<usize> {
+        let head = match tag::read_hash(storage, &tag::HEAD) {
+            None => return Ok(0),
+            Some(hash) => hash,
+        };
+        let from = match &self.tip {
+            Some((hash, _)) if hash == &head => return Ok(0),
+            Some((hash, _)) => Bound::Hash(hash.clone()),
+            None => Bound::Date(BlockDate::Boundary(0)),
+        };
+
+        let mut entries = Vec::new();
+        let mut indexed = 0;
+        let mut last = self.tip.clone();
+        for block in storage.blocks(from, Bound::Hash(head), Direction::Forward)? {
+            let block = match block {
+                Ok((_, block)) => block,
+                Err(err) if indexed == 0 => return Err(err.into()),
+                Err(err) => {
+                    // commit the blocks indexed so far, the UTxOs are
+                    // already updated with them
+                    warn!("cannot read the next block to index: {}", err);
+                    break;
+                }
+            };
+            let header = block.header();
+            let hash = header.compute_hash();
+            match &last {
+                Some((last_hash, _)) if last_hash == &hash => continue,
+                Some((last_hash, _)) if last_hash != &header.previous_header() => {
+                    warn!(
+                        "block {} is no longer indexed, rebuilding the index",
+                        last_hash
+                    );
+                    *self = AddressIndex::empty(self.path.clone(), genesis_data);
+                    File::create(&self.path)?;
+                    return Ok(0);
+                }
+                _ => {}
+            }
+            let date = header.blockdate();
+            let records = self.apply_block(&hash, &date, block.get_transactions());
+            if !records.is_empty() {
+                entries.push(JournalEntry::Block(records));
+            }
+            last = Some((hash, date));
+            indexed += 1;
+            if indexed == max_blocks {
+                break;
+            }
+        }
+
+        let (hash, date) = match last {
+            Some(last) if indexed > 0 => last,
+            _ => return Ok(0),
+        };
+        entries.push(JournalEntry::Commit(hash.clone(), date.clone()));
+        if let Err(err) = self.write_journal(&entries) {
+            // the UTxOs are ahead of the journal, start again from it
+            *self = AddressIndex::load(storage, genesis_data)?;
+            return Err(err);
+        }
+
+        for entry in entries {
+            if let JournalEntry::Block(records) = entry {
+                self.add_history(records);
+            }
+        }
+        self.tip = Some((hash, date));
+        Ok(indexed)
+    }
+
+    fn write_journal(&self, entries: &[JournalEntry]) -> Result<()> {
+        let mut journal = Vec::new();
+        for entry in entries {
+            serde_json::to_writer(&mut journal, entry)?;
+            journal.push(b'\n');
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .open(&self.path)?;
+        file.seek(SeekFrom::End(0))?;
+        file.write_all(&journal)?;
+        file.sync_data()?;
+        Ok(())
+    }
+
+    /// spend the inputs and add the outputs of the transactions of the
+    /// block, returning the records of the addresses involved
+    fn apply_block(
+        &mut self,
+        hash: &HeaderHash,
+        date: &BlockDate,
+        txs: Option<TxPayload>,
+    ) -> Vec<(String, TxRecord)> {
+        let mut records = Vec::new();
+        for txaux in txs.into_iter().flatten() {
+            let txid = txaux.tx.id();
+            // the received and sent amounts of each address
+            let mut amounts: Vec<(String, u64, u64)> = Vec::new();
+            for input in txaux.tx.inputs.iter() {
+                if let Some(txout) = self.utxos.remove(input) {
+                    let address = txout.address.to_string();
+                    let value = u64::from(txout.value);
+                    sub_balance(&mut self.balances, &address, value);
+                    amount_of(&mut amounts, address).2 += value;
+                }
+            }
+            for (index, txout) in txaux.tx.outputs.iter().enumerate() {
+                let address = txout.address.to_string();
+                let value = u64::from(txout.value);
+                *self.balances.entry(address.clone()).or_insert(0) += value;
+                amount_of(&mut amounts, address).1 += value;
+                self.utxos
+                    .insert(TxoPointer::new(txid, index as u32), txout.clone());
+            }
+            records.extend(amounts.into_iter().map(|(address, received, sent)| {
+                (
+                    address,
+                    TxRecord {
+                        txid,
+                        block: hash.clone(),
+                        date: date.clone(),
+                        received,
+                        sent,
+                    },
+                )
+            }));
+        }
+        records
+    }
+
+    fn add_history(&mut self, records: Vec<(String, TxRecord)>) {
+        for (address, record) in records {
+            self.history
+                .entry(address)
+                .or_insert_with(Vec::new)
+                .push(record);
+        }
+    }
+}
+
+fn amount_of(amounts: &mut Vec<(String, u64, u64)>, address: String) -> &mut (String, u64, u64) {
+    match amounts.iter().position(|(a, _, _)| a == &address) {
+        Some(i) => &mut amounts[i],
+        None => {
+            amounts.push((address, 0, 0));
+            amounts.last_mut().unwrap()
+        }
+    }
+}
+
+fn balances(utxos: &Utxos) -> HashMap<String, u64> {
+    let mut balances = HashMap::new();
+    for txout in utxos.values() {
+        *balances.entry(txout.address.to_string()).or_insert(0) += u64::from(txout.value);
+    }
+    balances
+}
+
+fn sub_balance(balances: &mut HashMap<String, u64>, address: &str, value: u64) {
+    let remove = match balances.get_mut(address) {
+        Some(balance) => {
+            *balance -= value;
+            *balance == 0
+        }
+        None => false,
+    };
+    if remove {
+        balances.remove(address);
+    }
+}
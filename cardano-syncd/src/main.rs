@@ -0,0 +1,150 @@
+//! Daemon keeping a local blockchain storage in sync with the network and
+//! serving it over a local HTTP API (see the `api` module).
+
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate serde_derive;
+
+use cardano_storage::{Storage, StorageConfig};
+use exe_common::{config::net, genesisdata, network::PeerStore, sync};
+use futures_cpupool::CpuPool;
+use hyper::server::Http;
+use slog::{o, Drain};
+use structopt::StructOpt;
+
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, thread, time::Duration};
+
+mod api;
+mod index;
+mod state;
+mod subscription;
+
+use self::state::State;
+use self::subscription::Subscriptions;
+
+/// delay before synchronising again once all the peers failed
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// the file of the peer store, in the storage directory
+const PEER_STORE_FILE: &str = "peers.yaml";
+
+/// delay between two updates of the index of the addresses
+const INDEX_DELAY: Duration = Duration::from_secs(10);
+
+#[derive(StructOpt)]
+#[structopt(name = "cardano-syncd", rename_all = "kebab-case")]
+struct Args {
+    /// the blockchain storage directory
+    #[structopt(long = "storage", parse(from_os_str))]
+    storage: PathBuf,
+
+    /// the network to synchronise with: mainnet, staging or testnet
+    #[structopt(long = "network", default_value = "mainnet")]
+    network: String,
+
+    /// a network configuration file, instead of one of the known networks
+    #[structopt(long = "network-config", parse(from_os_str))]
+    network_config: Option<PathBuf>,
+
+    /// the address to serve the HTTP API on
+    #[structopt(long = "listen", default_value = "127.0.0.1:8090")]
+    listen: SocketAddr,
+
+    /// the address to serve the WebSocket subscriptions on
+    #[structopt(long = "ws-listen", default_value = "127.0.0.1:8091")]
+    ws_listen: SocketAddr,
+}
+
+fn network_config(args: &Args) -> Result<net::Config, String> {
+    if let Some(path) = &args.network_config {
+        return net::Config::from_file(path)
+            .ok_or_else(|| format!("cannot read network configuration `{}'", path.display()));
+    }
+    match args.network.as_str() {
+        "mainnet" => Ok(net::Config::mainnet()),
+        "staging" => Ok(net::Config::staging()),
+        "testnet" => Ok(net::Config::testnet()),
+        network => Err(format!("unknown network `{}'", network)),
+    }
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let net_cfg = network_config(&args)?;
+    if net_cfg.peers.natives().is_empty() {
+        return Err("the network configuration has no native peer".to_owned());
+    }
+    let genesis_data = genesisdata::data::get_genesis_data(&net_cfg.genesis_prev)
+        .map(|json| genesisdata::parse::parse(json.as_bytes()))
+        .map_err(|hash| format!("no genesis data for {}", hash))?;
+    let storage = Storage::init(&StorageConfig::new(&args.storage)).map_err(|e| e.to_string())?;
+    let mut peer_store = PeerStore::open(args.storage.join(PEER_STORE_FILE), &net_cfg.peers);
+
+    let state = Arc::new(State::new(
+        storage,
+        genesis_data,
+        net_cfg,
+        args.network.clone(),
+    ));
+
+    // the events of the synchronisation are forwarded to the `log` crate
+    let logger = slog::Logger::root(
+        slog_stdlog::StdLog.fuse(),
+        o!("network" => args.network.clone()),
+    );
+
+    let sync_state = state.clone();
+    thread::spawn(move || loop {
+        let state = &sync_state;
+        let result = sync::net_sync_peers(
+            &state.network,
+            &state.net_cfg,
+            &mut peer_store,
+            &state.genesis_data,
+            state.storage.clone(),
+            false,
+            &logger,
+        );
+        if let Err(err) = result {
+            error!("synchronisation failed: {}, retrying", err);
+        }
+        thread::sleep(RETRY_DELAY);
+    });
+
+    let index_state = state.clone();
+    thread::spawn(move || loop {
+        match index_state.update_index() {
+            Ok(0) => {}
+            Ok(indexed) => info!("{} blocks added to the address index", indexed),
+            Err(err) => error!("address indexing failed: {}", err),
+        }
+        thread::sleep(INDEX_DELAY);
+    });
+
+    let subscriptions = Arc::new(Subscriptions::default());
+    let watch_state = state.clone();
+    let watch_subscriptions = subscriptions.clone();
+    thread::spawn(move || subscription::watch(watch_state, watch_subscriptions));
+    let ws_listen = args.ws_listen;
+    thread::spawn(move || {
+        if let Err(err) = subscription::serve(ws_listen, subscriptions) {
+            error!("cannot serve the subscriptions: {}", err);
+        }
+    });
+
+    let pool = CpuPool::new_num_cpus();
+    let server = Http::new()
+        .bind(&args.listen, move || Ok(api::Api::new(state.clone(), pool.clone())))
+        .map_err(|e| e.to_string())?;
+    info!("serving the API on http://{}", args.listen);
+    server.run().map_err(|e| e.to_string())
+}
+
+fn main() {
+    env_logger::init();
+
+    if let Err(err) = run(Args::from_args()) {
+        eprintln!("error: {}", err);
+        std::process::exit(1)
+    }
+}
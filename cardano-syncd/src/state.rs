@@ -0,0 +1,159 @@
+//! state shared between the synchronisation thread and the HTTP API
+
+use cardano::{
+    address::ExtendedAddr,
+    block::{BlockDate, HeaderHash, Utxos},
+    config::GenesisData,
+    tx::{TxOut, TxoPointer},
+};
+use cardano_storage::{chain_state, tag, Storage};
+use exe_common::config::net;
+
+#[cfg(feature = "prometheus")]
+use chain_core::metrics::prometheus::PrometheusMetrics;
+
+use std::sync::{Arc, Mutex, RwLock};
+
+use crate::index::{self, AddressIndex};
+
+/// maximum number of blocks indexed while holding the storage lock
+const INDEX_BATCH: usize = 2160;
+
+/// the tip of the local storage
+#[derive(Debug, Clone, Serialize)]
+pub struct Tip {
+    pub hash: HeaderHash,
+    pub date: BlockDate,
+}
+
+pub struct State {
+    pub storage: Arc<RwLock<Storage>>,
+    pub genesis_data: GenesisData,
+    pub net_cfg: net::Config,
+    pub network: String,
+
+    /// the metrics reported by the storage and the synchronisation
+    #[cfg(feature = "prometheus")]
+    pub metrics: Arc<PrometheusMetrics>,
+
+    /// the UTxOs at the last queried tip, restoring the chain state is
+    /// expensive so it is only done once per new tip
+    utxos: Mutex<Option<(HeaderHash, Arc<Utxos>)>>,
+
+    /// the index of the addresses, `None` until it is loaded by the first
+    /// call to `update_index`
+    index: RwLock<Option<AddressIndex>>,
+}
+
+impl State {
+    pub fn new(
+        storage: Storage,
+        genesis_data: GenesisData,
+        net_cfg: net::Config,
+        network: String,
+    ) -> Self {
+        #[cfg(feature = "prometheus")]
+        let metrics = Arc::new(PrometheusMetrics::new());
+        #[cfg(feature = "prometheus")]
+        let storage = {
+            let mut storage = storage;
+            storage.set_metrics(metrics.clone());
+            storage
+        };
+        State {
+            storage: Arc::new(RwLock::new(storage)),
+            genesis_data,
+            net_cfg,
+            network,
+            #[cfg(feature = "prometheus")]
+            metrics,
+            utxos: Mutex::new(None),
+            index: RwLock::new(None),
+        }
+    }
+
+    /// the current tip of the local storage, `None` until the first blocks
+    /// are synchronised
+    pub fn tip(&self) -> cardano_storage::Result<Option<Tip>> {
+        let storage = self.storage.read().unwrap();
+        match storage.get_block_from_tag(&tag::HEAD) {
+            Err(cardano_storage::Error::NoSuchTag) => Ok(None),
+            Err(err) => Err(err),
+            Ok(block) => {
+                let header = block.header();
+                Ok(Some(Tip {
+                    hash: header.compute_hash(),
+                    date: header.blockdate(),
+                }))
+            }
+        }
+    }
+
+    /// all the UTxOs at the current tip, restoring them is blocking: call
+    /// it out of the event loop of the API
+    pub fn utxos(&self) -> cardano_storage::Result<Arc<Utxos>> {
+        let tip = match self.tip()? {
+            None => return Ok(Arc::new(Utxos::new())),
+            Some(tip) => tip.hash,
+        };
+
+        if let Some((hash, utxos)) = self.utxos.lock().unwrap().as_ref() {
+            if hash == &tip {
+                return Ok(utxos.clone());
+            }
+        }
+
+        // the cache is not locked during the restoration, so the queries of
+        // the cached UTxOs do not wait for it
+        let chain_state = {
+            let storage = self.storage.read().unwrap();
+            chain_state::restore_chain_state(&storage, &self.genesis_data, &tip)?
+        };
+        let utxos = Arc::new(chain_state.utxos);
+        *self.utxos.lock().unwrap() = Some((tip, utxos.clone()));
+        Ok(utxos)
+    }
+
+    /// the UTxOs of the given address at the current tip
+    pub fn utxos_of(
+        &self,
+        address: &ExtendedAddr,
+    ) -> cardano_storage::Result<Vec<(TxoPointer, TxOut)>> {
+        Ok(self
+            .utxos()?
+            .iter()
+            .filter(|(_, txout)| &txout.address == address)
+            .map(|(ptr, txout)| (ptr.clone(), txout.clone()))
+            .collect())
+    }
+
+    /// index the blocks imported since the last update, loading the index
+    /// from the storage directory first if needed. Returns the number of
+    /// blocks indexed.
+    pub fn update_index(&self) -> index::Result<usize> {
+        if self.index.read().unwrap().is_none() {
+            let storage = self.storage.read().unwrap();
+            let index = AddressIndex::load(&storage, &self.genesis_data)?;
+            *self.index.write().unwrap() = Some(index);
+        }
+
+        let mut total = 0;
+        loop {
+            let storage = self.storage.read().unwrap();
+            let mut index = self.index.write().unwrap();
+            let index = index.as_mut().unwrap();
+            match index.update(&storage, &self.genesis_data, INDEX_BATCH)? {
+                0 => return Ok(total),
+                indexed => total += indexed,
+            }
+        }
+    }
+
+    /// query the index of the addresses, `None` if it is not loaded yet
+    pub fn with_index<T, F>(&self, f: F) -> Option<T>
+    where
+        F: FnOnce(&AddressIndex) -> T,
+    {
+        self.index.read().unwrap().as_ref().map(f)
+    }
+}
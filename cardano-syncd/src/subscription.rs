@@ -0,0 +1,234 @@
+//! WebSocket subscriptions to the blocks imported in the local storage
+//!
+//! ```text
+//! ws://<ws-listen>/tip      the header of every new tip
+//! ws://<ws-listen>/blocks   every new block of the chain, in order
+//! ```
+//!
+//! Every message is a JSON text message, the header or the block being
+//! encoded in CBOR hex. The storage is watched for a new tip every second.
+
+use cardano::{
+    block::{BlockDate, HeaderHash},
+    util::hex,
+};
+use cardano_storage::{tag, Storage};
+use serde::Serialize;
+use tungstenite::{
+    handshake::server::Request,
+    protocol::{Message, WebSocket},
+};
+
+use std::{
+    collections::VecDeque,
+    io,
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{mpsc, Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use crate::state::State;
+
+/// delay between two checks of the tip of the storage
+const WATCH_DELAY: Duration = Duration::from_secs(1);
+
+/// maximum number of blocks sent to the `blocks` subscribers when the tip
+/// moves, the older blocks of a bigger jump (e.g. the initial
+/// synchronisation) are skipped
+const MAX_CATCH_UP: usize = 2160;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Topic {
+    Tip,
+    Blocks,
+}
+
+impl Topic {
+    fn from_path(path: &str) -> Option<Self> {
+        match path.trim_end_matches('/') {
+            "/tip" => Some(Topic::Tip),
+            "/blocks" => Some(Topic::Blocks),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TipMessage {
+    hash: HeaderHash,
+    date: BlockDate,
+    header: String,
+}
+
+#[derive(Serialize)]
+struct BlockMessage {
+    hash: HeaderHash,
+    date: BlockDate,
+    block: String,
+}
+
+/// the senders to the connections of the subscribers
+#[derive(Default)]
+pub struct Subscriptions {
+    subscribers: Mutex<Vec<(Topic, mpsc::Sender<Arc<String>>)>>,
+}
+
+impl Subscriptions {
+    fn subscribe(&self, topic: Topic) -> mpsc::Receiver<Arc<String>> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.lock().unwrap().push((topic, sender));
+        receiver
+    }
+
+    fn has_subscribers(&self, topic: Topic) -> bool {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(t, _)| *t == topic)
+    }
+
+    /// send the message to the subscribers of the topic, forgetting the
+    /// subscribers which are disconnected
+    fn publish<T: Serialize>(&self, topic: Topic, message: &T) {
+        let message = match serde_json::to_string(message) {
+            Ok(message) => Arc::new(message),
+            Err(err) => {
+                error!("cannot encode the {:?} message: {}", topic, err);
+                return;
+            }
+        };
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|(t, sender)| *t != topic || sender.send(message.clone()).is_ok());
+    }
+}
+
+/// accept the WebSocket connections, one thread per subscriber
+pub fn serve(listen: SocketAddr, subscriptions: Arc<Subscriptions>) -> io::Result<()> {
+    let listener = TcpListener::bind(listen)?;
+    info!("serving the subscriptions on ws://{}", listen);
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(err) => {
+                warn!("cannot accept a subscriber: {}", err);
+                continue;
+            }
+        };
+        let subscriptions = subscriptions.clone();
+        thread::spawn(move || {
+            if let Err(err) = subscriber(stream, &subscriptions) {
+                debug!("subscriber disconnected: {}", err);
+            }
+        });
+    }
+    Ok(())
+}
+
+fn subscriber(stream: TcpStream, subscriptions: &Subscriptions) -> tungstenite::Result<()> {
+    let mut topic = None;
+    let mut websocket: WebSocket<TcpStream> = tungstenite::accept_hdr(stream, |req: &Request| {
+        topic = Topic::from_path(&req.path);
+        match topic {
+            Some(_) => Ok(None),
+            None => Err(tungstenite::Error::Protocol(
+                format!("unknown subscription `{}'", req.path).into(),
+            )),
+        }
+    })
+    .map_err(|err| match err {
+        tungstenite::HandshakeError::Failure(err) => err,
+        tungstenite::HandshakeError::Interrupted(_) => {
+            tungstenite::Error::Protocol("interrupted handshake".into())
+        }
+    })?;
+
+    // the topic is always set by a successful handshake
+    let receiver = subscriptions.subscribe(topic.unwrap());
+    for message in receiver {
+        websocket.write_message(Message::Text(message.to_string()))?;
+    }
+    Ok(())
+}
+
+/// publish the new tips and blocks of the storage to the subscribers
+pub fn watch(state: Arc<State>, subscriptions: Arc<Subscriptions>) {
+    let mut last = None;
+    loop {
+        thread::sleep(WATCH_DELAY);
+
+        let storage = state.storage.read().unwrap();
+        let head = match tag::read_hash(&*storage, &tag::HEAD) {
+            Some(head) => head,
+            None => continue,
+        };
+        if last.as_ref().map(|(hash, _)| hash) == Some(&head) {
+            continue;
+        }
+        match publish_blocks(&storage, &subscriptions, last.as_ref(), &head) {
+            Ok(date) => last = Some((head, date)),
+            Err(err) => error!("cannot publish the block {}: {}", head, err),
+        }
+    }
+}
+
+fn publish_blocks(
+    storage: &Storage,
+    subscriptions: &Subscriptions,
+    last: Option<&(HeaderHash, BlockDate)>,
+    head: &HeaderHash,
+) -> cardano_storage::Result<BlockDate> {
+    let raw = storage.read_block(head.as_hash_bytes())?;
+    let header = raw.decode_header()?;
+    let head_date = header.get_blockdate();
+    subscriptions.publish(
+        Topic::Tip,
+        &TipMessage {
+            hash: head.clone(),
+            date: head_date,
+            header: hex::encode(header.to_raw().as_ref()),
+        },
+    );
+
+    if !subscriptions.has_subscribers(Topic::Blocks) {
+        return Ok(head_date);
+    }
+    // walk back from the new tip to the last published block. After a
+    // rollback, the blocks are published from the first one following the
+    // date of the last published block. Only the tip is published the
+    // first time.
+    let mut blocks = VecDeque::new();
+    let mut current = (head.clone(), raw);
+    loop {
+        let (hash, raw) = current;
+        let header = raw.decode_header()?;
+        let previous = header.get_previous_header();
+        let date = header.get_blockdate();
+        let done = last.map_or(true, |(last, last_date)| {
+            last == &previous || &date <= last_date
+        });
+        blocks.push_front((hash, date, raw));
+        if done
+            || blocks.len() == MAX_CATCH_UP
+            || !storage.block_exists(previous.as_hash_bytes())?
+        {
+            break;
+        }
+        let raw = storage.read_block(previous.as_hash_bytes())?;
+        current = (previous, raw);
+    }
+    for (hash, date, raw) in blocks {
+        subscriptions.publish(
+            Topic::Blocks,
+            &BlockMessage {
+                hash,
+                date,
+                block: hex::encode(raw.as_ref()),
+            },
+        );
+    }
+    Ok(head_date)
+}
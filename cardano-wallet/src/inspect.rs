@@ -0,0 +1,218 @@
+//! Address inspection and validation
+//!
+//! Both the bootstrap era addresses (base58) and the addresses of the new
+//! chain (bech32) are supported. The result is a plain JavaScript object
+//! so front-ends can validate a deposit address without calling a backend.
+
+use wasm_bindgen::prelude::*;
+
+use cardano::{
+    address::{AddrType, ExtendedAddr, ParseExtendedAddrError, StakeDistribution},
+    config::NetworkMagic,
+};
+use chain_addr::{AddressReadable, Discrimination, Kind};
+
+use std::str::FromStr;
+
+use super::{js_error, BlockchainSettings};
+
+const BECH32_CHARSET: &'static str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+#[derive(Serialize, Debug, Default, PartialEq, Eq)]
+struct AddressInfo {
+    /// the address is well formed
+    valid: bool,
+    /// `base58` for the bootstrap era addresses, `bech32` for the new chain
+    format: Option<&'static str>,
+    /// `public_key`, `script` or `redeem` for the bootstrap era addresses;
+    /// `single`, `group` or `account` for the new chain
+    kind: Option<&'static str>,
+    /// bootstrap era only: the network magic embedded in the address, `null`
+    /// for the addresses without network magic (mainnet)
+    network_magic: Option<u32>,
+    /// bootstrap era only: whether the address embeds an (encrypted)
+    /// derivation path
+    has_derivation_path: Option<bool>,
+    /// bootstrap era only: `bootstrap` or `single_key`
+    stake_distribution: Option<&'static str>,
+    /// new chain only: `production` or `test`
+    discrimination: Option<&'static str>,
+    /// whether the address is for the network of the given settings, `null`
+    /// if no settings were given or if it does not apply
+    network_matches: Option<bool>,
+    /// description of the failure if the address is not valid
+    error: Option<String>,
+}
+
+/// check whether the given string is likely to be a bech32 string, i.e.
+/// a human readable part followed by the separator and data in the bech32
+/// charset. Base58 strings never contain `0` and bech32 data never contain
+/// `1`, `b`, `i` or `o`. Bech32 strings are either all lowercase or all
+/// uppercase.
+fn looks_like_bech32(s: &str) -> bool {
+    let lowercase = s.chars().any(|c| c.is_ascii_lowercase());
+    let uppercase = s.chars().any(|c| c.is_ascii_uppercase());
+    if lowercase && uppercase {
+        return false;
+    }
+    let s = s.to_ascii_lowercase();
+    match s.rfind('1') {
+        None | Some(0) => false,
+        Some(pos) => {
+            let data = &s[pos + 1..];
+            data.len() >= 6 && data.chars().all(|c| BECH32_CHARSET.contains(c))
+        }
+    }
+}
+
+fn inspect_byron(address: &ExtendedAddr, settings: Option<&BlockchainSettings>) -> AddressInfo {
+    let kind = match address.addr_type {
+        AddrType::ATPubKey => "public_key",
+        AddrType::ATScript => "script",
+        AddrType::ATRedeem => "redeem",
+    };
+    let network_magic = match address.attributes.network_magic {
+        NetworkMagic::NoMagic => None,
+        NetworkMagic::Magic(magic) => Some(magic),
+    };
+    let stake_distribution = match address.attributes.stake_distribution {
+        StakeDistribution::BootstrapEraDistr => "bootstrap",
+        StakeDistribution::SingleKeyDistr(_) => "single_key",
+    };
+    AddressInfo {
+        valid: true,
+        format: Some("base58"),
+        kind: Some(kind),
+        network_magic,
+        has_derivation_path: Some(address.attributes.derivation_path.is_some()),
+        stake_distribution: Some(stake_distribution),
+        network_matches: settings
+            .map(|settings| settings.network_magic() == address.attributes.network_magic),
+        ..AddressInfo::default()
+    }
+}
+
+fn inspect_chain(address: &AddressReadable) -> AddressInfo {
+    let address = address.to_address();
+    let kind = match address.kind() {
+        Kind::Single(_) => "single",
+        Kind::Group(_, _) => "group",
+        Kind::Account(_) => "account",
+        Kind::Multisig(_) => "multisig",
+        Kind::Script(_) => "script",
+    };
+    let discrimination = match address.discrimination() {
+        Discrimination::Production => "production",
+        Discrimination::Test => "test",
+    };
+    AddressInfo {
+        valid: true,
+        format: Some("bech32"),
+        kind: Some(kind),
+        discrimination: Some(discrimination),
+        ..AddressInfo::default()
+    }
+}
+
+fn invalid(format: &'static str, error: String) -> AddressInfo {
+    AddressInfo {
+        valid: false,
+        format: Some(format),
+        error: Some(error),
+        ..AddressInfo::default()
+    }
+}
+
+fn inspect(address: &str, settings: Option<&BlockchainSettings>) -> AddressInfo {
+    if looks_like_bech32(address) {
+        match AddressReadable::from_string(address) {
+            Ok(readable) => inspect_chain(&readable),
+            Err(err) => invalid("bech32", format!("{}", err)),
+        }
+    } else {
+        match ExtendedAddr::from_str(address) {
+            Ok(ea) => inspect_byron(&ea, settings),
+            Err(ParseExtendedAddrError::Base58Error(err)) => {
+                invalid("base58", format!("invalid base58: {}", err))
+            }
+            Err(ParseExtendedAddrError::EncodingError(err)) => {
+                invalid("base58", format!("invalid address encoding: {}", err))
+            }
+        }
+    }
+}
+
+/// inspect the given address, returning an object:
+///
+/// ```text
+/// { valid: bool,
+///   format: "base58" | "bech32",
+///   kind: string | null,
+///   network_magic: number | null,
+///   has_derivation_path: bool | null,
+///   stake_distribution: "bootstrap" | "single_key" | null,
+///   discrimination: "production" | "test" | null,
+///   network_matches: bool | null,
+///   error: string | null
+/// }
+/// ```
+///
+/// If the blockchain settings are given, `network_matches` tells whether
+/// a bootstrap era address belongs to the settings' network.
+#[wasm_bindgen]
+pub fn inspect_address(
+    address: &str,
+    settings: Option<BlockchainSettings>,
+) -> Result<JsValue, JsValue> {
+    JsValue::from_serde(&inspect(address, settings.as_ref())).map_err(js_error)
+}
+
+/// check the given string is a valid address (bootstrap era or new chain)
+#[wasm_bindgen]
+pub fn is_valid_address(address: &str) -> bool {
+    inspect(address, None).valid
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BYRON_MAINNET: &'static str =
+        "DdzFFzCqrhsyhumccfGyEj3WZzztSPr92ntRWB6UVVwzcMTpwoafVQ5vD9mdZ5Xind8ycugbmA8esxmo7NycjQFGSbDeKrxabTz8MVzf";
+
+    #[test]
+    fn inspect_byron_address() {
+        let info = inspect(BYRON_MAINNET, Some(&BlockchainSettings::mainnet()));
+        assert!(info.valid, "{:?}", info.error);
+        assert_eq!(info.format, Some("base58"));
+        assert_eq!(info.kind, Some("public_key"));
+        assert_eq!(info.network_magic, None);
+        assert_eq!(info.has_derivation_path, Some(true));
+        assert_eq!(info.network_matches, Some(true));
+
+        let info = inspect(BYRON_MAINNET, Some(&BlockchainSettings::new(1097911063)));
+        assert_eq!(info.network_matches, Some(false));
+    }
+
+    #[test]
+    fn inspect_invalid_byron_address() {
+        let info = inspect(&BYRON_MAINNET[1..], None);
+        assert!(!info.valid);
+        assert_eq!(info.format, Some("base58"));
+        assert!(info.error.is_some());
+    }
+
+    #[test]
+    fn bech32_detection() {
+        assert!(!looks_like_bech32(BYRON_MAINNET));
+        assert!(looks_like_bech32(
+            "ca1qvqsyqcyq5rqwzqfpg9scrgwpugpzysnzs23v9ccrydpk8qarc0jqxuzx4s"
+        ));
+        assert!(looks_like_bech32(
+            "CA1QVQSYQCYQ5RQWZQFPG9SCRGWPUGPZYSNZS23V9CCRYDPK8QARC0JQXUZX4S"
+        ));
+        assert!(!looks_like_bech32(
+            "Ca1qvqsyqcyq5rqwzqfpg9scrgwpugpzysnzs23v9ccrydpk8qarc0jqxuzx4s"
+        ));
+    }
+}
@@ -0,0 +1,987 @@
+//! WebAssembly bindings of the Cardano wallet primitives
+//!
+//! This crate exposes, through `wasm-bindgen`, the key derivation,
+//! address generation, transaction building and signing of the `cardano`
+//! crate so web applications do not need to maintain their own port of it.
+//!
+//! The intermediate states of the transaction building (`TransactionBuilder`,
+//! `TransactionFinalized`) can be serialized to JSON so a web application
+//! can save them and resume the building later.
+//!
+//! Errors are returned to JavaScript as exceptions holding a description
+//! of the failure.
+
+#[macro_use]
+extern crate serde_derive;
+
+use wasm_bindgen::prelude::*;
+
+use cardano::{
+    address,
+    bip::{bip39, bip44},
+    coin,
+    config::{NetworkMagic, ProtocolMagic},
+    fee::{self, FeeAlgorithm},
+    hdwallet, tx, txbuild, txutils,
+    util::hex,
+    wallet::{bip44::AddrType, keygen},
+};
+
+use chain_crypto::{AsymmetricSigner, Ed25519Bip32, SignerError, VerificationAlgorithm};
+
+use std::str::FromStr;
+
+pub mod inspect;
+pub mod mnemonic;
+
+/// convert any displayable error into a JavaScript exception
+fn js_error<E: std::fmt::Display>(e: E) -> JsValue {
+    JsValue::from_str(&format!("{}", e))
+}
+
+/// encode the given object in CBOR and return the hexadecimal representation
+fn cbor_hex<T: cbor_event::se::Serialize>(t: &T) -> Result<String, JsValue> {
+    let mut se = cbor_event::se::Serializer::new_vec();
+    se.serialize(t).map_err(js_error)?;
+    Ok(hex::encode(&se.finalize()))
+}
+
+/* ******************************************************************************* *
+ *                                   Settings                                      *
+ * ******************************************************************************* */
+
+/// setting of the blockchain
+///
+/// This includes the `ProtocolMagic` a discriminant value to differentiate
+/// different instances of the cardano blockchain (Mainnet, Testnet... ).
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize)]
+pub struct BlockchainSettings {
+    protocol_magic: ProtocolMagic,
+}
+#[wasm_bindgen]
+impl BlockchainSettings {
+    /// serialize into a JsValue object. Allowing the client to store the settings
+    /// or see changes in the settings or change the settings.
+    ///
+    /// Note that this is not recommended to change the settings on the fly. Doing
+    /// so you might not be able to recover your funds anymore or to send new
+    /// transactions.
+    pub fn to_json(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(self).map_err(js_error)
+    }
+
+    /// retrieve the object from a JsValue.
+    pub fn from_json(value: JsValue) -> Result<BlockchainSettings, JsValue> {
+        value.into_serde().map_err(js_error)
+    }
+
+    /// default settings to work with Cardano Mainnet
+    pub fn mainnet() -> BlockchainSettings {
+        BlockchainSettings {
+            protocol_magic: ProtocolMagic::default(),
+        }
+    }
+
+    /// settings for a network identified by the given protocol magic
+    pub fn new(protocol_magic: u32) -> BlockchainSettings {
+        BlockchainSettings {
+            protocol_magic: ProtocolMagic::from(protocol_magic),
+        }
+    }
+}
+impl BlockchainSettings {
+    fn network_magic(&self) -> NetworkMagic {
+        NetworkMagic::from(self.protocol_magic)
+    }
+}
+
+/* ******************************************************************************* *
+ *                                   Entropy                                       *
+ * ******************************************************************************* */
+
+/// the entropy associated to mnemonics. This is a bytes representation of the
+/// mnemonics the user has to remember how to generate the root key of an
+/// HD Wallet.
+///
+/// A new entropy is generated from random bytes with `Entropy::generate`,
+/// the mnemonics of the other languages are supported by the `mnemonic`
+/// module.
+///
+/// # Security considerations
+///
+/// * do not store this value without encrypting it;
+/// * do not leak the mnemonics;
+/// * make sure the user remembers the mnemonics string;
+///
+#[wasm_bindgen]
+pub struct Entropy(bip39::Entropy);
+#[wasm_bindgen]
+impl Entropy {
+    /// retrieve the initial entropy of a wallet from the given
+    /// english mnemonics.
+    pub fn from_english_mnemonics(mnemonics: &str) -> Result<Entropy, JsValue> {
+        let mnemonics = bip39::Mnemonics::from_string(&bip39::dictionary::ENGLISH, mnemonics)
+            .map_err(js_error)?;
+        bip39::Entropy::from_mnemonics(&mnemonics)
+            .map(Entropy)
+            .map_err(js_error)
+    }
+
+    /// retrieve the english mnemonics associated to this entropy
+    pub fn to_english_mnemonics(&self) -> String {
+        format!(
+            "{}",
+            self.0.to_mnemonics().to_string(&bip39::dictionary::ENGLISH)
+        )
+    }
+}
+
+/* ******************************************************************************* *
+ *                                  Private key                                    *
+ * ******************************************************************************* */
+
+/// There is a special function to use when deriving Addresses. This function
+/// has been revised to offer stronger properties. This is why there is a
+/// V2 derivation scheme. The V1 being the legacy one still used in daedalus
+/// now a days.
+///
+/// It is strongly advised to use V2 as the V1 is deprecated since April 2018.
+/// Its support is already provided for backward compatibility with old
+/// addresses.
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub struct DerivationScheme(hdwallet::DerivationScheme);
+#[wasm_bindgen]
+impl DerivationScheme {
+    /// deprecated, provided here only for backward compatibility with
+    /// Daedalus' addresses
+    pub fn v1() -> DerivationScheme {
+        DerivationScheme(hdwallet::DerivationScheme::V1)
+    }
+
+    /// the recommended settings
+    pub fn v2() -> DerivationScheme {
+        DerivationScheme(hdwallet::DerivationScheme::V2)
+    }
+}
+
+/// A given private key. You can use this key to sign transactions.
+///
+/// # security considerations
+///
+/// * do not store this key without encrypting it;
+/// * if leaked anyone can _spend_ a UTxO (Unspent Transaction Output)
+///   with it;
+///
+#[wasm_bindgen]
+pub struct PrivateKey(hdwallet::XPrv);
+#[wasm_bindgen]
+impl PrivateKey {
+    /// create a new private key from a given Entropy
+    pub fn new(entropy: &Entropy, password: &str) -> PrivateKey {
+        let mut bytes = [0; hdwallet::XPRV_SIZE];
+        keygen::generate_seed(&entropy.0, password.as_bytes(), &mut bytes);
+        PrivateKey(hdwallet::XPrv::normalize_bytes(bytes))
+    }
+
+    /// retrieve a private key from the given hexadecimal string
+    pub fn from_hex(hex: &str) -> Result<PrivateKey, JsValue> {
+        hdwallet::XPrv::from_str(hex)
+            .map(PrivateKey)
+            .map_err(js_error)
+    }
+
+    /// convert the private key to an hexadecimal string
+    pub fn to_hex(&self) -> String {
+        format!("{}", self.0)
+    }
+
+    /// get the public key associated to this private key
+    pub fn public(&self) -> PublicKey {
+        PublicKey(self.0.public())
+    }
+
+    /// sign some bytes with this private key
+    pub fn sign(&self, data: &[u8]) -> Signature {
+        Signature(self.0.sign(data))
+    }
+
+    /// derive this private key with the given index.
+    ///
+    /// # Security considerations
+    ///
+    /// * prefer the use of DerivationScheme::v2 when possible;
+    /// * hard derivation index cannot be soft derived with the public key
+    ///
+    /// # Hard derivation vs Soft derivation
+    ///
+    /// If you pass an index below 0x80000000 then it is a soft derivation.
+    /// The advantage of soft derivation is that it is possible to derive the
+    /// public key too. I.e. derivation the private key with a soft derivation
+    /// index and then retrieving the associated public key is equivalent to
+    /// deriving the public key associated to the parent private key.
+    ///
+    /// Hard derivation index does not allow public key derivation.
+    ///
+    /// This is why, for privacy reason, it is advised to use hard derivation
+    /// for the account levels.
+    pub fn derive(&self, derivation_scheme: DerivationScheme, index: u32) -> PrivateKey {
+        PrivateKey(self.0.derive(derivation_scheme.0, index))
+    }
+}
+
+/// the private key signs directly; other signers (hardware wallets...)
+/// are used through `Witness::new_with_signer`
+impl AsymmetricSigner<Ed25519Bip32> for PrivateKey {
+    fn public_key(&self) -> Result<chain_crypto::PublicKey<Ed25519Bip32>, SignerError> {
+        Ok(
+            chain_crypto::PublicKey::from_binary(self.0.public().as_ref())
+                .expect("the extended public keys have the same format"),
+        )
+    }
+
+    fn sign_bytes(
+        &self,
+        msg: &[u8],
+    ) -> Result<<Ed25519Bip32 as VerificationAlgorithm>::Signature, SignerError> {
+        let signature: hdwallet::Signature<()> = self.0.sign(msg);
+        Ok(Ed25519Bip32::signature_from_bytes(signature.as_ref())?)
+    }
+}
+
+/// The public key associated to a given private key.
+///
+/// It is not possible to sign (and then spend) with a private key.
+/// However it is possible to verify a Signature.
+///
+/// # Security Consideration
+///
+/// * it is rather harmless to leak a public key, in the worst case
+///   only the privacy is leaked;
+///
+#[wasm_bindgen]
+pub struct PublicKey(hdwallet::XPub);
+#[wasm_bindgen]
+impl PublicKey {
+    /// retrieve a public key from the given hexadecimal string
+    pub fn from_hex(hex: &str) -> Result<PublicKey, JsValue> {
+        hdwallet::XPub::from_str(hex)
+            .map(PublicKey)
+            .map_err(js_error)
+    }
+
+    /// convert the public key to an hexadecimal string
+    pub fn to_hex(&self) -> String {
+        format!("{}", self.0)
+    }
+
+    /// verify the signature of the given data against this public key
+    pub fn verify(&self, data: &[u8], signature: &Signature) -> bool {
+        self.0.verify(data, &signature.0)
+    }
+
+    /// derive this public key with the given index.
+    ///
+    /// # Errors
+    ///
+    /// If the index is not a soft derivation index (< 0x80000000) then
+    /// calling this method will fail.
+    pub fn derive(
+        &self,
+        derivation_scheme: DerivationScheme,
+        index: u32,
+    ) -> Result<PublicKey, JsValue> {
+        self.0
+            .derive(derivation_scheme.0, index)
+            .map(PublicKey)
+            .map_err(js_error)
+    }
+
+    /// get the bootstrap era address. I.E. this is an address without
+    /// stake delegation.
+    pub fn bootstrap_era_address(&self, blockchain_settings: &BlockchainSettings) -> Address {
+        Address(address::ExtendedAddr::new_simple(
+            self.0.clone(),
+            blockchain_settings.network_magic(),
+        ))
+    }
+}
+
+/// a signature produced by a `PrivateKey`
+#[wasm_bindgen]
+pub struct Signature(hdwallet::Signature<()>);
+#[wasm_bindgen]
+impl Signature {
+    pub fn from_hex(hex: &str) -> Result<Signature, JsValue> {
+        hdwallet::Signature::from_hex(hex)
+            .map(Signature)
+            .map_err(js_error)
+    }
+
+    pub fn to_hex(&self) -> String {
+        format!("{}", self.0)
+    }
+}
+
+/* ******************************************************************************* *
+ *                                     BIP44                                       *
+ * ******************************************************************************* */
+
+/// the index of a BIP44 account, i.e. the hard derivation level of the
+/// account in the `m/44'/1815'/account'` path.
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub struct AccountIndex(u32);
+#[wasm_bindgen]
+impl AccountIndex {
+    /// create an account index from the account number (starting from 0)
+    ///
+    /// the account number must be below 0x80000000, the hard derivation
+    /// bit is added when deriving the account's keys.
+    pub fn new(index: u32) -> Result<AccountIndex, JsValue> {
+        bip44::Account::new(index)
+            .map(|_| AccountIndex(index))
+            .map_err(js_error)
+    }
+}
+
+/// the index of an address key, i.e. the soft derivation level of the
+/// address in the `m/44'/1815'/account'/change/index` path.
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub struct AddressKeyIndex(u32);
+#[wasm_bindgen]
+impl AddressKeyIndex {
+    pub fn new(index: u32) -> Result<AddressKeyIndex, JsValue> {
+        bip44::Index::new(index)
+            .map(|_| AddressKeyIndex(index))
+            .map_err(js_error)
+    }
+}
+
+fn addr_type(internal: bool) -> AddrType {
+    if internal {
+        AddrType::Internal
+    } else {
+        AddrType::External
+    }
+}
+
+/// Root Private Key of a BIP44 HD Wallet
+#[wasm_bindgen]
+pub struct Bip44RootPrivateKey {
+    key: PrivateKey,
+    derivation_scheme: DerivationScheme,
+}
+#[wasm_bindgen]
+impl Bip44RootPrivateKey {
+    pub fn new(key: PrivateKey, derivation_scheme: DerivationScheme) -> Bip44RootPrivateKey {
+        Bip44RootPrivateKey {
+            key,
+            derivation_scheme,
+        }
+    }
+
+    /// recover a wallet from the given mnemonic Entropy and the given password
+    ///
+    /// (see BIP39 for the mnemonic generation and the entropy's computation)
+    ///
+    /// This is equivalent to `cardano_wallet_new` of the C bindings and uses
+    /// the V2 derivation scheme.
+    pub fn recover(entropy: &Entropy, password: &str) -> Bip44RootPrivateKey {
+        Bip44RootPrivateKey::new(PrivateKey::new(entropy, password), DerivationScheme::v2())
+    }
+
+    /// derive the BIP44 account `m/44'/1815'/index'`
+    pub fn bip44_account(&self, index: AccountIndex) -> Bip44AccountPrivate {
+        let scheme = self.derivation_scheme.0;
+        let key = self
+            .key
+            .0
+            .derive(scheme, bip44::BIP44_PURPOSE)
+            .derive(scheme, bip44::BIP44_COIN_TYPE)
+            .derive(scheme, bip44::BIP44_SOFT_UPPER_BOUND | index.0);
+        Bip44AccountPrivate {
+            key: PrivateKey(key),
+            derivation_scheme: self.derivation_scheme,
+        }
+    }
+
+    pub fn key(&self) -> PrivateKey {
+        PrivateKey(self.key.0.clone())
+    }
+}
+
+/// Private key of a BIP44 account
+#[wasm_bindgen]
+pub struct Bip44AccountPrivate {
+    key: PrivateKey,
+    derivation_scheme: DerivationScheme,
+}
+#[wasm_bindgen]
+impl Bip44AccountPrivate {
+    pub fn new(key: PrivateKey, derivation_scheme: DerivationScheme) -> Bip44AccountPrivate {
+        Bip44AccountPrivate {
+            key,
+            derivation_scheme,
+        }
+    }
+
+    /// get the public account, to use for address generation without
+    /// exposing the private key
+    pub fn public(&self) -> Bip44AccountPublic {
+        Bip44AccountPublic {
+            key: self.key.public(),
+            derivation_scheme: self.derivation_scheme,
+        }
+    }
+
+    /// derive the private key of the given address, from the internal
+    /// (change) chain if `internal` is true or the external chain otherwise.
+    pub fn address_key(&self, internal: bool, index: AddressKeyIndex) -> PrivateKey {
+        let scheme = self.derivation_scheme.0;
+        let change = match addr_type(internal) {
+            AddrType::External => 0,
+            AddrType::Internal => 1,
+        };
+        PrivateKey(self.key.0.derive(scheme, change).derive(scheme, index.0))
+    }
+
+    pub fn key(&self) -> PrivateKey {
+        PrivateKey(self.key.0.clone())
+    }
+}
+
+/// Public key of a BIP44 account
+#[wasm_bindgen]
+pub struct Bip44AccountPublic {
+    key: PublicKey,
+    derivation_scheme: DerivationScheme,
+}
+#[wasm_bindgen]
+impl Bip44AccountPublic {
+    pub fn new(key: PublicKey, derivation_scheme: DerivationScheme) -> Bip44AccountPublic {
+        Bip44AccountPublic {
+            key,
+            derivation_scheme,
+        }
+    }
+
+    /// derive the public key of the given address, from the internal
+    /// (change) chain if `internal` is true or the external chain otherwise.
+    pub fn address_key(
+        &self,
+        internal: bool,
+        index: AddressKeyIndex,
+    ) -> Result<PublicKey, JsValue> {
+        let scheme = self.derivation_scheme.0;
+        let change = match addr_type(internal) {
+            AddrType::External => 0,
+            AddrType::Internal => 1,
+        };
+        self.key
+            .0
+            .derive(scheme, change)
+            .and_then(|key| key.derive(scheme, index.0))
+            .map(PublicKey)
+            .map_err(js_error)
+    }
+
+    pub fn key(&self) -> PublicKey {
+        PublicKey(self.key.0.clone())
+    }
+}
+
+/* ******************************************************************************* *
+ *                                    Address                                      *
+ * ******************************************************************************* */
+
+#[wasm_bindgen]
+pub struct Address(address::ExtendedAddr);
+#[wasm_bindgen]
+impl Address {
+    pub fn to_base58(&self) -> String {
+        format!("{}", self.0)
+    }
+
+    pub fn from_base58(s: &str) -> Result<Address, JsValue> {
+        address::ExtendedAddr::from_str(s)
+            .map(Address)
+            .map_err(|e| JsValue::from_str(&format!("{:?}", e)))
+    }
+}
+
+/* ******************************************************************************* *
+ *                                  Transaction                                    *
+ * ******************************************************************************* */
+
+/// the identifier of a transaction, i.e. the hash of the transaction body
+#[wasm_bindgen]
+pub struct TransactionId(tx::TxId);
+#[wasm_bindgen]
+impl TransactionId {
+    pub fn from_hex(s: &str) -> Result<TransactionId, JsValue> {
+        tx::TxId::from_str(s).map(TransactionId).map_err(js_error)
+    }
+
+    pub fn to_hex(&self) -> String {
+        format!("{}", self.0)
+    }
+}
+
+/// the witness of a transaction input, i.e. the proof the owner of the
+/// input's address has signed the transaction
+#[wasm_bindgen]
+pub struct Witness(tx::TxInWitness);
+#[wasm_bindgen]
+impl Witness {
+    /// sign the given transaction id with the address' private key
+    pub fn new_extended_key(
+        blockchain_settings: &BlockchainSettings,
+        signing_key: &PrivateKey,
+        transaction_id: &TransactionId,
+    ) -> Witness {
+        Witness(tx::TxInWitness::new_extended_pk(
+            blockchain_settings.protocol_magic,
+            &signing_key.0,
+            &transaction_id.0,
+        ))
+    }
+
+    pub fn to_json(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&self.0).map_err(js_error)
+    }
+
+    pub fn from_json(value: JsValue) -> Result<Witness, JsValue> {
+        value.into_serde().map(Witness).map_err(js_error)
+    }
+
+    /// check the witness' public key is the one associated to the given address
+    pub fn verify_address(&self, address: &Address) -> bool {
+        self.0.verify_address(&address.0)
+    }
+}
+
+impl Witness {
+    /// sign the given transaction id with the given signer, which may hold
+    /// the address' private key out of this process (e.g. a hardware wallet)
+    pub fn new_with_signer<S>(
+        blockchain_settings: &BlockchainSettings,
+        signer: &S,
+        transaction_id: &TransactionId,
+    ) -> Result<Witness, SignerError>
+    where
+        S: AsymmetricSigner<Ed25519Bip32> + ?Sized,
+    {
+        let data = tx::TxInWitness::extended_pk_data_to_sign(
+            blockchain_settings.protocol_magic,
+            &transaction_id.0,
+        );
+        let public_key = signer.public_key()?;
+        let signature = signer.sign_bytes(&data)?;
+        Ok(Witness(tx::TxInWitness::PkWitness(
+            hdwallet::XPub::from_slice(public_key.as_ref())
+                .expect("the extended public keys have the same format"),
+            hdwallet::Signature::from_slice(signature.as_ref())
+                .expect("the signatures have the same format"),
+        )))
+    }
+}
+
+/* ******************************************************************************* *
+ *                                 Coin and fees                                   *
+ * ******************************************************************************* */
+
+/// a value in Lovelace (1 Ada = 1,000,000 Lovelace)
+///
+/// The value is exchanged with JavaScript as a string as it may not fit in
+/// a JavaScript number without loss of precision.
+#[wasm_bindgen]
+#[derive(Clone, Copy)]
+pub struct Coin(coin::Coin);
+#[wasm_bindgen]
+impl Coin {
+    pub fn new() -> Coin {
+        Coin(coin::Coin::zero())
+    }
+
+    /// parse the given string as a number of Lovelace
+    pub fn from_str(s: &str) -> Result<Coin, JsValue> {
+        s.parse().map(Coin).map_err(js_error)
+    }
+
+    /// the number of Lovelace, as a string
+    pub fn to_str(&self) -> String {
+        format!("{}", u64::from(self.0))
+    }
+
+    /// create a coin of the given value in Ada and Lovelace
+    pub fn from(ada: u32, lovelace: u32) -> Result<Coin, JsValue> {
+        let value = (ada as u64) * 1_000_000 + (lovelace as u64);
+        coin::Coin::new(value).map(Coin).map_err(js_error)
+    }
+
+    /// the integral part of the value in Ada
+    pub fn ada(&self) -> u32 {
+        (u64::from(self.0) / 1_000_000) as u32
+    }
+
+    /// the fractional part of the value, in Lovelace
+    pub fn lovelace(&self) -> u32 {
+        (u64::from(self.0) % 1_000_000) as u32
+    }
+
+    pub fn add(&self, other: &Coin) -> Result<Coin, JsValue> {
+        (self.0 + other.0).map(Coin).map_err(js_error)
+    }
+}
+
+/// the difference between the inputs and the outputs (including the fees
+/// when applicable) of a transaction being built
+#[wasm_bindgen]
+pub struct Balance(coin::CoinDiff);
+#[wasm_bindgen]
+impl Balance {
+    /// the inputs are greater than the outputs, the leftover would go
+    /// to the fees unless an output policy is applied
+    pub fn is_positive(&self) -> bool {
+        match self.0 {
+            coin::CoinDiff::Positive(_) => true,
+            _ => false,
+        }
+    }
+
+    /// the outputs are greater than the inputs, more inputs are needed
+    pub fn is_negative(&self) -> bool {
+        match self.0 {
+            coin::CoinDiff::Negative(_) => true,
+            _ => false,
+        }
+    }
+
+    /// the transaction is balanced
+    pub fn is_zero(&self) -> bool {
+        match self.0 {
+            coin::CoinDiff::Zero => true,
+            _ => false,
+        }
+    }
+
+    /// the absolute value of the difference
+    pub fn value(&self) -> Coin {
+        match self.0 {
+            coin::CoinDiff::Positive(v) => Coin(v),
+            coin::CoinDiff::Negative(v) => Coin(v),
+            coin::CoinDiff::Zero => Coin(coin::Coin::zero()),
+        }
+    }
+}
+
+/// the linear fee algorithm: `constant + coefficient * size_of_txaux`
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize)]
+pub struct LinearFeeAlgorithm(fee::LinearFee);
+#[wasm_bindgen]
+impl LinearFeeAlgorithm {
+    /// the default fee algorithm of the Cardano mainnet
+    pub fn default() -> LinearFeeAlgorithm {
+        LinearFeeAlgorithm(fee::LinearFee::default())
+    }
+
+    /// create a linear fee algorithm, both values are given in
+    /// thousandth of Lovelace
+    pub fn new(constant_millis: u32, coefficient_millis: u32) -> LinearFeeAlgorithm {
+        let constant = constant_millis as u64;
+        let coefficient = coefficient_millis as u64;
+        LinearFeeAlgorithm(fee::LinearFee::new(
+            fee::Milli::new(constant / 1000, constant % 1000),
+            fee::Milli::new(coefficient / 1000, coefficient % 1000),
+        ))
+    }
+
+    /// estimate the fee of a serialized transaction of the given size
+    pub fn estimate(&self, size: u32) -> Result<Coin, JsValue> {
+        self.0
+            .estimate(size as usize)
+            .map(|fee| Coin(fee.to_coin()))
+            .map_err(js_error)
+    }
+
+    pub fn to_json(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(self).map_err(js_error)
+    }
+
+    pub fn from_json(value: JsValue) -> Result<LinearFeeAlgorithm, JsValue> {
+        value.into_serde().map_err(js_error)
+    }
+}
+
+/* ******************************************************************************* *
+ *                              Transaction building                               *
+ * ******************************************************************************* */
+
+/// a pointer to an unspent output of a previous transaction
+#[wasm_bindgen]
+pub struct TxoPointer(tx::TxoPointer);
+#[wasm_bindgen]
+impl TxoPointer {
+    pub fn new(id: &TransactionId, index: u32) -> TxoPointer {
+        TxoPointer(tx::TxoPointer::new(id.0, index))
+    }
+
+    pub fn to_json(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&self.0).map_err(js_error)
+    }
+
+    pub fn from_json(value: JsValue) -> Result<TxoPointer, JsValue> {
+        value.into_serde().map(TxoPointer).map_err(js_error)
+    }
+}
+
+/// a transaction output: the value to send to a given address
+#[wasm_bindgen]
+pub struct TxOut(tx::TxOut);
+#[wasm_bindgen]
+impl TxOut {
+    pub fn new(address: &Address, value: &Coin) -> TxOut {
+        TxOut(tx::TxOut::new(address.0.clone(), value.0))
+    }
+
+    pub fn to_json(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&self.0).map_err(js_error)
+    }
+
+    pub fn from_json(value: JsValue) -> Result<TxOut, JsValue> {
+        value.into_serde().map(TxOut).map_err(js_error)
+    }
+}
+
+/// the policy to apply to the leftover of the inputs (the change)
+#[wasm_bindgen]
+pub struct OutputPolicy(txutils::OutputPolicy);
+#[wasm_bindgen]
+impl OutputPolicy {
+    /// send all the change to the given address
+    pub fn change_to_one_address(address: &Address) -> OutputPolicy {
+        OutputPolicy(txutils::OutputPolicy::One(address.0.clone()))
+    }
+}
+
+/// a transaction body: the inputs and the outputs. It still needs to be
+/// signed with `TransactionFinalized`.
+#[wasm_bindgen]
+pub struct Transaction(tx::Tx);
+#[wasm_bindgen]
+impl Transaction {
+    pub fn id(&self) -> TransactionId {
+        TransactionId(self.0.id())
+    }
+
+    pub fn to_json(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&self.0).map_err(js_error)
+    }
+
+    pub fn from_json(value: JsValue) -> Result<Transaction, JsValue> {
+        value.into_serde().map(Transaction).map_err(js_error)
+    }
+
+    /// the hexadecimal representation of the CBOR encoded transaction
+    pub fn to_hex(&self) -> Result<String, JsValue> {
+        cbor_hex(&self.0)
+    }
+}
+
+/// build a transaction step by step: add the inputs and the outputs,
+/// then apply the output policy to send the change, taking the fees into
+/// account.
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize)]
+pub struct TransactionBuilder(txbuild::TxBuilder);
+#[wasm_bindgen]
+impl TransactionBuilder {
+    pub fn new() -> TransactionBuilder {
+        TransactionBuilder(txbuild::TxBuilder::new())
+    }
+
+    /// add an input, the value associated to the pointed output is required
+    /// to compute the balance of the transaction
+    pub fn add_input(&mut self, txo_pointer: &TxoPointer, value: &Coin) {
+        self.0.add_input(&txo_pointer.0, value.0)
+    }
+
+    pub fn add_output(&mut self, output: &TxOut) {
+        self.0.add_output_value(&output.0)
+    }
+
+    /// send the leftover of the inputs, minus the fees, according to the
+    /// given policy. Returns the JSON array of the outputs that have been
+    /// added to the transaction (empty if the transaction was already
+    /// balanced).
+    ///
+    /// This should be the last operation before calling
+    /// `make_transaction`.
+    pub fn apply_output_policy(
+        &mut self,
+        fee_algorithm: &LinearFeeAlgorithm,
+        policy: &OutputPolicy,
+    ) -> Result<JsValue, JsValue> {
+        let outputs = self
+            .0
+            .add_output_policy(&fee_algorithm.0, &policy.0)
+            .map_err(js_error)?;
+        JsValue::from_serde(&outputs).map_err(js_error)
+    }
+
+    /// the fee required by the current state of the transaction
+    pub fn estimate_fee(&self, fee_algorithm: &LinearFeeAlgorithm) -> Result<Coin, JsValue> {
+        self.0
+            .calculate_fee(&fee_algorithm.0)
+            .map(|fee| Coin(fee.to_coin()))
+            .map_err(js_error)
+    }
+
+    pub fn get_input_total(&self) -> Result<Coin, JsValue> {
+        self.0.get_input_total().map(Coin).map_err(js_error)
+    }
+
+    pub fn get_output_total(&self) -> Result<Coin, JsValue> {
+        self.0.get_output_total().map(Coin).map_err(js_error)
+    }
+
+    /// the balance of the inputs against the outputs and the fees
+    pub fn get_balance(&self, fee_algorithm: &LinearFeeAlgorithm) -> Result<Balance, JsValue> {
+        self.0
+            .balance(&fee_algorithm.0)
+            .map(Balance)
+            .map_err(js_error)
+    }
+
+    /// the balance of the inputs against the outputs only
+    pub fn get_balance_without_fees(&self) -> Result<Balance, JsValue> {
+        self.0.balance_without_fees().map(Balance).map_err(js_error)
+    }
+
+    /// check the fee algorithm is satisfied by the transaction, i.e. the
+    /// inputs cover the outputs and the fees.
+    pub fn is_fee_covered(&self, fee_algorithm: &LinearFeeAlgorithm) -> Result<bool, JsValue> {
+        let balance = self.get_balance(fee_algorithm)?;
+        Ok(!balance.is_negative())
+    }
+
+    /// build the transaction. The builder is left unchanged.
+    pub fn make_transaction(&self) -> Result<Transaction, JsValue> {
+        self.0.clone().make_tx().map(Transaction).map_err(js_error)
+    }
+
+    pub fn to_json(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(self).map_err(js_error)
+    }
+
+    pub fn from_json(value: JsValue) -> Result<TransactionBuilder, JsValue> {
+        value.into_serde().map_err(js_error)
+    }
+}
+
+/// collect the witnesses of a transaction. The witnesses need to be added
+/// in the same order as the inputs.
+#[wasm_bindgen]
+#[derive(Serialize, Deserialize)]
+pub struct TransactionFinalized {
+    tx_id: tx::TxId,
+    finalized: txbuild::TxFinalized,
+}
+#[wasm_bindgen]
+impl TransactionFinalized {
+    pub fn new(transaction: &Transaction) -> TransactionFinalized {
+        TransactionFinalized {
+            tx_id: transaction.0.id(),
+            finalized: txbuild::TxFinalized::new(transaction.0.clone()),
+        }
+    }
+
+    pub fn id(&self) -> TransactionId {
+        TransactionId(self.tx_id)
+    }
+
+    /// sign the transaction with the private key of the next input
+    pub fn sign(
+        &mut self,
+        blockchain_settings: &BlockchainSettings,
+        key: &PrivateKey,
+    ) -> Result<(), JsValue> {
+        let witness = Witness::new_extended_key(blockchain_settings, key, &self.id());
+        self.add_witness(witness)
+    }
+
+    /// add the witness of the next input
+    pub fn add_witness(&mut self, witness: Witness) -> Result<(), JsValue> {
+        self.finalized.add_witness(witness.0).map_err(js_error)
+    }
+
+    /// build the signed transaction, fails if the number of witnesses does
+    /// not match the number of inputs.
+    pub fn finalize(&self) -> Result<SignedTransaction, JsValue> {
+        self.finalized
+            .clone()
+            .make_txaux()
+            .map(SignedTransaction)
+            .map_err(js_error)
+    }
+
+    pub fn to_json(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(self).map_err(js_error)
+    }
+
+    pub fn from_json(value: JsValue) -> Result<TransactionFinalized, JsValue> {
+        value.into_serde().map_err(js_error)
+    }
+}
+impl TransactionFinalized {
+    /// sign the transaction with the signer of the next input
+    pub fn sign_with<S>(
+        &mut self,
+        blockchain_settings: &BlockchainSettings,
+        signer: &S,
+    ) -> Result<(), JsValue>
+    where
+        S: AsymmetricSigner<Ed25519Bip32> + ?Sized,
+    {
+        let witness =
+            Witness::new_with_signer(blockchain_settings, signer, &self.id()).map_err(js_error)?;
+        self.add_witness(witness)
+    }
+}
+
+/// a transaction with its witnesses, ready to be sent to the network
+#[wasm_bindgen]
+pub struct SignedTransaction(tx::TxAux);
+#[wasm_bindgen]
+impl SignedTransaction {
+    pub fn id(&self) -> TransactionId {
+        TransactionId(self.0.tx.id())
+    }
+
+    /// the fee paid by this transaction with the given algorithm
+    pub fn fee(&self, fee_algorithm: &LinearFeeAlgorithm) -> Result<Coin, JsValue> {
+        fee_algorithm
+            .0
+            .calculate_for_txaux(&self.0)
+            .map(|fee| Coin(fee.to_coin()))
+            .map_err(js_error)
+    }
+
+    pub fn to_json(&self) -> Result<JsValue, JsValue> {
+        JsValue::from_serde(&self.0).map_err(js_error)
+    }
+
+    pub fn from_json(value: JsValue) -> Result<SignedTransaction, JsValue> {
+        value.into_serde().map(SignedTransaction).map_err(js_error)
+    }
+
+    /// the hexadecimal representation of the CBOR encoded transaction, as
+    /// expected by the network
+    pub fn to_hex(&self) -> Result<String, JsValue> {
+        cbor_hex(&self.0)
+    }
+}
@@ -0,0 +1,279 @@
+//! BIP39 mnemonics: entropy generation, conversion from and to mnemonic
+//! phrases in the supported languages and validation of mnemonic phrases
+//! with per-word feedback.
+//!
+//! The dictionaries expect the phrases to be normalized (UTF-8 NFKD), it is
+//! up to the caller to normalize the user inputs (`String.normalize('NFKD')`).
+
+use wasm_bindgen::prelude::*;
+
+use cardano::bip::bip39::{
+    self,
+    dictionary::{self, DefaultDictionary, Language},
+};
+
+use super::{js_error, Entropy};
+
+/// the languages of the BIP39 dictionaries
+#[wasm_bindgen]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Bip39Language {
+    English,
+    French,
+    Japanese,
+    Korean,
+    ChineseSimplified,
+    ChineseTraditional,
+    Italian,
+    Spanish,
+}
+
+const LANGUAGES: [Bip39Language; 8] = [
+    Bip39Language::English,
+    Bip39Language::French,
+    Bip39Language::Japanese,
+    Bip39Language::Korean,
+    Bip39Language::ChineseSimplified,
+    Bip39Language::ChineseTraditional,
+    Bip39Language::Italian,
+    Bip39Language::Spanish,
+];
+
+impl Bip39Language {
+    fn dictionary(self) -> &'static DefaultDictionary {
+        match self {
+            Bip39Language::English => &dictionary::ENGLISH,
+            Bip39Language::French => &dictionary::FRENCH,
+            Bip39Language::Japanese => &dictionary::JAPANESE,
+            Bip39Language::Korean => &dictionary::KOREAN,
+            Bip39Language::ChineseSimplified => &dictionary::CHINESE_SIMPLIFIED,
+            Bip39Language::ChineseTraditional => &dictionary::CHINESE_TRADITIONAL,
+            Bip39Language::Italian => &dictionary::ITALIAN,
+            Bip39Language::Spanish => &dictionary::SPANISH,
+        }
+    }
+}
+
+/// list the names of the supported languages (`english`, `french`,
+/// `japanese`, `korean`, `chinese-simplified`, `chinese-traditional`,
+/// `italian` and `spanish`)
+#[wasm_bindgen]
+pub fn bip39_supported_languages() -> Vec<JsValue> {
+    LANGUAGES
+        .iter()
+        .map(|lang| JsValue::from_str(lang.dictionary().name()))
+        .collect()
+}
+
+/// retrieve the language from its name as listed by
+/// `bip39_supported_languages`
+#[wasm_bindgen]
+pub fn bip39_language_from_name(name: &str) -> Result<Bip39Language, JsValue> {
+    LANGUAGES
+        .iter()
+        .find(|lang| lang.dictionary().name() == name)
+        .cloned()
+        .ok_or_else(|| JsValue::from_str(&format!("Unsupported BIP39 language: {}", name)))
+}
+
+/// the name of the given language
+#[wasm_bindgen]
+pub fn bip39_language_name(language: Bip39Language) -> String {
+    language.dictionary().name().to_owned()
+}
+
+/// feedback regarding a given word of a mnemonic phrase
+#[derive(Serialize)]
+struct WordValidation {
+    /// position of the word in the phrase, starting from 0
+    index: usize,
+    word: String,
+    valid: bool,
+}
+
+/// result of the validation of a mnemonic phrase
+#[derive(Serialize)]
+struct MnemonicsValidation {
+    /// all the words are valid, the word count is supported and the
+    /// checksum matches
+    valid: bool,
+    word_count: usize,
+    word_count_valid: bool,
+    /// `null` if the checksum could not be verified (invalid words or
+    /// unsupported word count)
+    checksum_valid: Option<bool>,
+    words: Vec<WordValidation>,
+}
+
+fn validate(language: Bip39Language, phrase: &str) -> MnemonicsValidation {
+    let dic = language.dictionary();
+
+    let mut indices = Vec::new();
+    let words: Vec<WordValidation> = phrase
+        .split(dic.separator())
+        .enumerate()
+        .map(|(index, word)| {
+            let valid = match bip39::MnemonicIndex::from_word(dic, word) {
+                Ok(mnemonic) => {
+                    indices.push(mnemonic);
+                    true
+                }
+                Err(_) => false,
+            };
+            WordValidation {
+                index,
+                word: word.to_owned(),
+                valid,
+            }
+        })
+        .collect();
+
+    let word_count = words.len();
+    let word_count_valid = bip39::Type::from_word_count(word_count).is_ok();
+    let checksum_valid = if word_count_valid && indices.len() == word_count {
+        let valid = bip39::Mnemonics::from_mnemonics(indices)
+            .and_then(|mnemonics| bip39::Entropy::from_mnemonics(&mnemonics))
+            .is_ok();
+        Some(valid)
+    } else {
+        None
+    };
+
+    MnemonicsValidation {
+        valid: checksum_valid == Some(true),
+        word_count,
+        word_count_valid,
+        checksum_valid,
+        words,
+    }
+}
+
+/// validate the given mnemonic phrase, returning an object:
+///
+/// ```text
+/// { valid: bool,
+///   word_count: number,
+///   word_count_valid: bool,
+///   checksum_valid: bool | null,
+///   words: [ { index: number, word: string, valid: bool } ]
+/// }
+/// ```
+///
+/// so the user interface can highlight the exact invalid word(s).
+#[wasm_bindgen]
+pub fn bip39_validate_mnemonics(
+    language: Bip39Language,
+    phrase: &str,
+) -> Result<JsValue, JsValue> {
+    JsValue::from_serde(&validate(language, phrase)).map_err(js_error)
+}
+
+/// check whether the given word is part of the dictionary of the
+/// given language
+#[wasm_bindgen]
+pub fn bip39_is_valid_word(language: Bip39Language, word: &str) -> bool {
+    language.dictionary().lookup_mnemonic(word).is_ok()
+}
+
+#[wasm_bindgen]
+impl Entropy {
+    /// generate a new entropy for a mnemonic phrase of `word_count` words
+    /// (9, 12, 15, 18, 21 or 24) from the given random bytes (use
+    /// `crypto.getRandomValues`).
+    ///
+    /// `random_bytes` must hold at least the entropy size of the requested
+    /// word count (from 12 bytes for 9 words up to 32 bytes for 24 words),
+    /// the extra bytes are ignored.
+    pub fn generate(word_count: u32, random_bytes: &[u8]) -> Result<Entropy, JsValue> {
+        let t = bip39::Type::from_word_count(word_count as usize).map_err(js_error)?;
+        let size = t.to_key_size() / 8;
+        if random_bytes.len() < size {
+            return Err(JsValue::from_str(&format!(
+                "not enough random bytes, expected {} but received {}",
+                size,
+                random_bytes.len()
+            )));
+        }
+        Entropy::from_bytes(&random_bytes[..size])
+    }
+
+    /// retrieve the entropy from its raw bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Entropy, JsValue> {
+        bip39::Entropy::from_slice(bytes)
+            .map(Entropy)
+            .map_err(js_error)
+    }
+
+    /// the raw bytes of the entropy
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.0.to_vec()
+    }
+
+    /// the number of words of the associated mnemonic phrase
+    pub fn word_count(&self) -> u32 {
+        self.0.get_type().mnemonic_count() as u32
+    }
+
+    /// retrieve the entropy from the given mnemonic phrase in the given
+    /// language
+    pub fn from_mnemonics(language: Bip39Language, phrase: &str) -> Result<Entropy, JsValue> {
+        let mnemonics =
+            bip39::Mnemonics::from_string(language.dictionary(), phrase).map_err(js_error)?;
+        bip39::Entropy::from_mnemonics(&mnemonics)
+            .map(Entropy)
+            .map_err(js_error)
+    }
+
+    /// the mnemonic phrase of this entropy in the given language
+    pub fn to_mnemonics(&self, language: Bip39Language) -> String {
+        format!("{}", self.0.to_mnemonics().to_string(language.dictionary()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MNEMONICS: &'static str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn validate_valid_mnemonics() {
+        let validation = validate(Bip39Language::English, MNEMONICS);
+        assert!(validation.valid);
+        assert_eq!(validation.word_count, 12);
+        assert_eq!(validation.checksum_valid, Some(true));
+        assert!(validation.words.iter().all(|w| w.valid));
+    }
+
+    #[test]
+    fn validate_reports_invalid_word() {
+        let phrase = MNEMONICS.replacen("abandon", "abandonn", 1);
+        let validation = validate(Bip39Language::English, &phrase);
+        assert!(!validation.valid);
+        assert_eq!(validation.checksum_valid, None);
+        let invalid: Vec<_> = validation.words.iter().filter(|w| !w.valid).collect();
+        assert_eq!(invalid.len(), 1);
+        assert_eq!(invalid[0].index, 0);
+        assert_eq!(invalid[0].word, "abandonn");
+    }
+
+    #[test]
+    fn validate_reports_invalid_checksum() {
+        let phrase = MNEMONICS.replace("about", "abandon");
+        let validation = validate(Bip39Language::English, &phrase);
+        assert!(!validation.valid);
+        assert!(validation.word_count_valid);
+        assert_eq!(validation.checksum_valid, Some(false));
+    }
+
+    #[test]
+    fn language_names() {
+        for lang in LANGUAGES.iter() {
+            assert_eq!(
+                bip39_language_from_name(lang.dictionary().name()).ok(),
+                Some(*lang)
+            );
+        }
+    }
+}
@@ -0,0 +1,442 @@
+//! the authenticated HTTP API
+//!
+//! ```text
+//! GET  /api/v1/wallets                        the names of the wallets
+//! POST /api/v1/wallets                        create a wallet
+//! POST /api/v1/wallets/restore                restore a wallet from its mnemonic phrase
+//! POST /api/v1/wallets/<name>/addresses       derive addresses of the wallet
+//! POST /api/v1/wallets/<name>/transactions    build and sign a transaction
+//! POST /api/v1/transactions/fee               build a transaction, display its fee
+//! POST /api/v1/transactions                   submit a signed transaction
+//! ```
+//!
+//! Every request needs the `Authorization: Bearer <token>` header. Requests
+//! and responses are JSON. Errors are returned as `{ "error": "..." }`.
+
+use cardano::{tx::TxAux, util::hex, wallet::bip44::AddrType};
+use cryptoxide::util::fixed_time_eq;
+use exe_common::{network::Api as NetworkApi, sync};
+use futures::{future, Future, Stream};
+use hyper::{
+    header::{Authorization, Bearer, ContentType},
+    server::{Request, Response, Service},
+    Method, StatusCode,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use std::{io::Cursor, sync::Arc};
+
+use crate::{
+    state::State,
+    transaction::{self, Spec},
+    wallets,
+};
+
+const PREFIX: &str = "/api/v1/";
+
+fn default_words() -> usize {
+    24
+}
+fn default_count() -> usize {
+    1
+}
+
+#[derive(Serialize)]
+struct ErrorResponse {
+    error: String,
+}
+
+#[derive(Deserialize)]
+struct CreateWallet {
+    name: String,
+    password: String,
+    #[serde(default = "default_words")]
+    words: usize,
+}
+
+#[derive(Serialize)]
+struct Created {
+    name: String,
+    mnemonic: String,
+}
+
+#[derive(Deserialize)]
+struct RestoreWallet {
+    name: String,
+    password: String,
+    mnemonic: String,
+}
+
+#[derive(Serialize)]
+struct Restored {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct DeriveAddresses {
+    password: String,
+    #[serde(default)]
+    protocol_magic: cardano::config::ProtocolMagic,
+    #[serde(default)]
+    account: u32,
+    #[serde(default)]
+    internal: bool,
+    #[serde(default)]
+    from: u32,
+    #[serde(default = "default_count")]
+    count: usize,
+}
+
+#[derive(Serialize)]
+struct Address {
+    path: String,
+    address: String,
+}
+
+#[derive(Deserialize)]
+struct SignTransaction {
+    password: String,
+    transaction: Spec,
+}
+
+#[derive(Serialize)]
+struct Signed {
+    txid: String,
+    /// the signed transaction, in CBOR hex
+    transaction: String,
+}
+
+#[derive(Deserialize)]
+struct SubmitTransaction {
+    /// the signed transaction, in CBOR hex
+    transaction: String,
+}
+
+#[derive(Serialize)]
+struct Submitted {
+    txid: String,
+    sent: bool,
+}
+
+/// the largest accepted request body
+const MAX_BODY_SIZE: usize = 1024 * 1024;
+
+/// the maximum number of addresses derived in one request
+const MAX_ADDRESSES: usize = 1000;
+
+pub struct Api {
+    state: Arc<State>,
+}
+
+impl Api {
+    pub fn new(state: Arc<State>) -> Self {
+        Api { state }
+    }
+
+    fn authorized(&self, req: &Request) -> bool {
+        match req.headers().get::<Authorization<Bearer>>() {
+            None => false,
+            Some(auth) => fixed_time_eq(auth.0.token.as_bytes(), self.state.token.as_bytes()),
+        }
+    }
+}
+
+impl Service for Api {
+    type Request = Request;
+    type Response = Response;
+    type Error = hyper::Error;
+    type Future = Box<dyn Future<Item = Self::Response, Error = Self::Error>>;
+
+    fn call(&self, req: Request) -> Self::Future {
+        if !req.path().starts_with(PREFIX) {
+            return Box::new(future::ok(not_found()));
+        }
+        if !self.authorized(&req) {
+            return Box::new(future::ok(error(
+                StatusCode::Unauthorized,
+                "missing or invalid API token",
+            )));
+        }
+        let path = req.path()[PREFIX.len()..].to_owned();
+        let method = req.method().clone();
+        let state = self.state.clone();
+
+        let segments: Vec<&str> = path.trim_end_matches('/').split('/').collect();
+        let handler: fn(&State, &str, &[u8]) -> Response = match (&method, &segments[..]) {
+            (&Method::Get, ["wallets"]) => return Box::new(future::ok(list_wallets(&state))),
+            (&Method::Post, ["wallets"]) => create_wallet,
+            (&Method::Post, ["wallets", "restore"]) => restore_wallet,
+            (&Method::Post, ["wallets", _, "addresses"]) => derive_addresses,
+            (&Method::Post, ["wallets", _, "transactions"]) => sign_transaction,
+            (&Method::Post, ["transactions", "fee"]) => transaction_fee,
+            (&Method::Post, ["transactions"]) => submit_transaction,
+            _ => return Box::new(future::ok(not_found())),
+        };
+        // the wallet name, for the routes of a given wallet
+        let name = segments.get(1).map(|s| s.to_string()).unwrap_or_default();
+
+        Box::new(read_body(req.body()).map(move |body| match body {
+            None => error(StatusCode::PayloadTooLarge, "request body too large"),
+            Some(body) => handler(&state, &name, &body),
+        }))
+    }
+}
+
+enum BodyError {
+    Hyper(hyper::Error),
+    TooLarge,
+}
+
+/// reads the request body, stopping as soon as it exceeds `MAX_BODY_SIZE`
+/// so an oversized request is never buffered in memory. Returns `None`
+/// if the body is too large.
+fn read_body(body: hyper::Body) -> impl Future<Item = Option<Vec<u8>>, Error = hyper::Error> {
+    body.map_err(BodyError::Hyper)
+        .fold(Vec::new(), |mut body, chunk| {
+            if body.len() + chunk.len() > MAX_BODY_SIZE {
+                return Err(BodyError::TooLarge);
+            }
+            body.extend_from_slice(&chunk);
+            Ok(body)
+        })
+        .then(|body| match body {
+            Ok(body) => Ok(Some(body)),
+            Err(BodyError::TooLarge) => Ok(None),
+            Err(BodyError::Hyper(err)) => Err(err),
+        })
+}
+
+fn list_wallets(state: &State) -> Response {
+    match state.wallets.list() {
+        Ok(names) => json(StatusCode::Ok, &names),
+        Err(err) => wallet_error(err),
+    }
+}
+
+fn create_wallet(state: &State, _: &str, body: &[u8]) -> Response {
+    let req: CreateWallet = match parse_body(body) {
+        Ok(req) => req,
+        Err(response) => return response,
+    };
+    match state
+        .wallets
+        .create(&req.name, req.words, req.password.as_bytes())
+    {
+        Ok(mnemonic) => {
+            info!("wallet `{}' created", req.name);
+            json(
+                StatusCode::Created,
+                &Created {
+                    name: req.name,
+                    mnemonic,
+                },
+            )
+        }
+        Err(err) => wallet_error(err),
+    }
+}
+
+fn restore_wallet(state: &State, _: &str, body: &[u8]) -> Response {
+    let req: RestoreWallet = match parse_body(body) {
+        Ok(req) => req,
+        Err(response) => return response,
+    };
+    match state
+        .wallets
+        .restore(&req.name, &req.mnemonic, req.password.as_bytes())
+    {
+        Ok(()) => {
+            info!("wallet `{}' restored", req.name);
+            json(StatusCode::Created, &Restored { name: req.name })
+        }
+        Err(err) => wallet_error(err),
+    }
+}
+
+fn derive_addresses(state: &State, name: &str, body: &[u8]) -> Response {
+    let req: DeriveAddresses = match parse_body(body) {
+        Ok(req) => req,
+        Err(response) => return response,
+    };
+    if req.count > MAX_ADDRESSES {
+        return error(
+            StatusCode::BadRequest,
+            format!(
+                "cannot derive more than {} addresses at once",
+                MAX_ADDRESSES
+            ),
+        );
+    }
+    let mut wallet = match state.wallets.open(name, req.password.as_bytes()) {
+        Ok(wallet) => wallet,
+        Err(err) => return wallet_error(err),
+    };
+    let addr_type = if req.internal {
+        AddrType::Internal
+    } else {
+        AddrType::External
+    };
+    match wallets::addresses(
+        &mut wallet,
+        req.protocol_magic,
+        req.account,
+        addr_type,
+        req.from,
+        req.count,
+    ) {
+        Ok(addresses) => {
+            let addresses: Vec<_> = addresses
+                .into_iter()
+                .map(|(path, address)| Address {
+                    path: transaction::format_path(&path),
+                    address: address.to_string(),
+                })
+                .collect();
+            json(StatusCode::Ok, &addresses)
+        }
+        Err(err) => wallet_error(err),
+    }
+}
+
+fn transaction_fee(_: &State, _: &str, body: &[u8]) -> Response {
+    let mut spec: Spec = match parse_body(body) {
+        Ok(spec) => spec,
+        Err(response) => return response,
+    };
+    let fee_algorithm = spec.fee_algorithm();
+    match transaction::build(&spec, &fee_algorithm)
+        .and_then(|builder| transaction::summary(&builder, &fee_algorithm))
+    {
+        Ok(summary) => json(StatusCode::Ok, &summary),
+        Err(err) => error(StatusCode::BadRequest, err),
+    }
+}
+
+fn sign_transaction(state: &State, name: &str, body: &[u8]) -> Response {
+    let SignTransaction {
+        password,
+        transaction: mut spec,
+    } = match parse_body(body) {
+        Ok(req) => req,
+        Err(response) => return response,
+    };
+    let wallet = match state.wallets.open(name, password.as_bytes()) {
+        Ok(wallet) => wallet,
+        Err(err) => return wallet_error(err),
+    };
+    let fee_algorithm = spec.fee_algorithm();
+    let txaux = match transaction::build(&spec, &fee_algorithm)
+        .and_then(|builder| transaction::sign(builder, &spec, &wallet))
+    {
+        Ok(txaux) => txaux,
+        Err(err) => return error(StatusCode::BadRequest, err),
+    };
+
+    let mut se = cbor_event::se::Serializer::new_vec();
+    if let Err(err) = se.serialize(&txaux) {
+        return error(StatusCode::InternalServerError, err);
+    }
+    json(
+        StatusCode::Ok,
+        &Signed {
+            txid: txaux.tx.id().to_string(),
+            transaction: hex::encode(&se.finalize()),
+        },
+    )
+}
+
+/// decode the signed transaction and send it to the network through a new
+/// connection to one of the configured peers
+fn submit_transaction(state: &State, _: &str, body: &[u8]) -> Response {
+    let req: SubmitTransaction = match parse_body(body) {
+        Ok(req) => req,
+        Err(response) => return response,
+    };
+    let bytes = match hex::decode(req.transaction.trim()) {
+        Ok(bytes) => bytes,
+        Err(_) => {
+            return error(
+                StatusCode::BadRequest,
+                "expected a CBOR hex encoded transaction",
+            )
+        }
+    };
+    let txaux: TxAux = match cbor_event::de::Deserializer::from(Cursor::new(bytes)).deserialize() {
+        Ok(txaux) => txaux,
+        Err(err) => return error(StatusCode::BadRequest, err),
+    };
+    let txid = txaux.tx.id();
+
+    if state.net_cfg.peers.natives().is_empty() {
+        return error(StatusCode::ServiceUnavailable, "no native peer configured");
+    }
+    let mut peer = sync::get_peer(&state.network, &state.net_cfg, true);
+    match peer.send_transaction(txaux) {
+        Ok(sent) => {
+            info!("transaction {} submitted", txid);
+            json(
+                StatusCode::Ok,
+                &Submitted {
+                    txid: txid.to_string(),
+                    sent,
+                },
+            )
+        }
+        Err(err) => error(StatusCode::BadGateway, err),
+    }
+}
+
+fn parse_body<T: DeserializeOwned>(body: &[u8]) -> Result<T, Response> {
+    serde_json::from_slice(body)
+        .map_err(|e| error(StatusCode::BadRequest, format!("invalid request: {}", e)))
+}
+
+fn wallet_error(err: wallets::Error) -> Response {
+    let status = match err {
+        wallets::Error::WalletNotFound(_) => StatusCode::NotFound,
+        wallets::Error::WalletAlreadyExists(_) => StatusCode::Conflict,
+        wallets::Error::CannotDecrypt => StatusCode::Forbidden,
+        ref err if err.is_client_error() => StatusCode::BadRequest,
+        _ => StatusCode::InternalServerError,
+    };
+    error(status, err)
+}
+
+fn json<T: Serialize>(status: StatusCode, t: &T) -> Response {
+    match serde_json::to_string(t) {
+        Ok(body) => Response::new()
+            .with_status(status)
+            .with_header(ContentType::json())
+            .with_body(body),
+        Err(err) => error(StatusCode::InternalServerError, err),
+    }
+}
+
+fn error<E: ToString>(status: StatusCode, err: E) -> Response {
+    let body = ErrorResponse {
+        error: err.to_string(),
+    };
+    Response::new()
+        .with_status(status)
+        .with_header(ContentType::json())
+        // serializing a struct of a single string can't fail
+        .with_body(serde_json::to_string(&body).unwrap())
+}
+
+fn not_found() -> Response {
+    error(StatusCode::NotFound, "unknown endpoint")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn body_size_limit() {
+        let body = read_body(vec![1; MAX_BODY_SIZE].into()).wait().unwrap();
+        assert_eq!(body.map(|body| body.len()), Some(MAX_BODY_SIZE));
+
+        let body = read_body(vec![1; MAX_BODY_SIZE + 1].into()).wait().unwrap();
+        assert!(body.is_none());
+    }
+}
@@ -0,0 +1,101 @@
+//! Self-hosted wallet backend serving the `cardano` wallet APIs over an
+//! authenticated HTTP API (see the `api` module).
+
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate serde_derive;
+
+use exe_common::config::net;
+use hyper::server::Http;
+use structopt::StructOpt;
+
+use std::{fs, net::SocketAddr, path::PathBuf, sync::Arc};
+
+mod api;
+mod state;
+mod transaction;
+mod wallets;
+
+use self::{state::State, wallets::Wallets};
+
+/// the shortest accepted API token
+const MIN_TOKEN_LENGTH: usize = 16;
+
+#[derive(StructOpt)]
+#[structopt(name = "cardano-walletd", rename_all = "kebab-case")]
+struct Args {
+    /// the directory where the wallets are stored
+    #[structopt(long = "wallet-dir", parse(from_os_str), default_value = "wallets")]
+    wallet_dir: PathBuf,
+
+    /// a file containing the API token the clients need to present
+    #[structopt(long = "token-file", parse(from_os_str))]
+    token_file: PathBuf,
+
+    /// the network to submit the transactions to: mainnet, staging or testnet
+    #[structopt(long = "network", default_value = "mainnet")]
+    network: String,
+
+    /// a network configuration file, instead of one of the known networks
+    #[structopt(long = "network-config", parse(from_os_str))]
+    network_config: Option<PathBuf>,
+
+    /// the address to serve the HTTP API on
+    #[structopt(long = "listen", default_value = "127.0.0.1:8091")]
+    listen: SocketAddr,
+}
+
+fn network_config(args: &Args) -> Result<net::Config, String> {
+    if let Some(path) = &args.network_config {
+        return net::Config::from_file(path)
+            .ok_or_else(|| format!("cannot read network configuration `{}'", path.display()));
+    }
+    match args.network.as_str() {
+        "mainnet" => Ok(net::Config::mainnet()),
+        "staging" => Ok(net::Config::staging()),
+        "testnet" => Ok(net::Config::testnet()),
+        network => Err(format!("unknown network `{}'", network)),
+    }
+}
+
+fn read_token(args: &Args) -> Result<String, String> {
+    let token = fs::read_to_string(&args.token_file)
+        .map_err(|e| format!("cannot read `{}': {}", args.token_file.display(), e))?;
+    let token = token.trim().to_owned();
+    if token.len() < MIN_TOKEN_LENGTH {
+        return Err(format!(
+            "the API token should be at least {} characters long",
+            MIN_TOKEN_LENGTH
+        ));
+    }
+    Ok(token)
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let net_cfg = network_config(&args)?;
+    let token = read_token(&args)?;
+    let wallets = Wallets::new(&args.wallet_dir).map_err(|e| e.to_string())?;
+
+    let state = Arc::new(State {
+        wallets,
+        token,
+        net_cfg,
+        network: args.network.clone(),
+    });
+
+    let server = Http::new()
+        .bind(&args.listen, move || Ok(api::Api::new(state.clone())))
+        .map_err(|e| e.to_string())?;
+    info!("serving the API on http://{}", args.listen);
+    server.run().map_err(|e| e.to_string())
+}
+
+fn main() {
+    env_logger::init();
+
+    if let Err(err) = run(Args::from_args()) {
+        eprintln!("error: {}", err);
+        std::process::exit(1)
+    }
+}
@@ -0,0 +1,13 @@
+//! state shared between the requests of the HTTP API
+
+use exe_common::config::net;
+
+use crate::wallets::Wallets;
+
+pub struct State {
+    pub wallets: Wallets,
+    /// the token every request needs to present
+    pub token: String,
+    pub net_cfg: net::Config,
+    pub network: String,
+}
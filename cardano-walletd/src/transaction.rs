@@ -0,0 +1,228 @@
+//! building and signing of the transactions
+//!
+//! The service does not track the UTxOs of the wallets: the integrator
+//! provides the inputs to spend (e.g. from `cardano-syncd`) along with the
+//! BIP44 derivation path of the address they belong to.
+//!
+//! ```json
+//! {
+//!   "protocol_magic": 764824073,
+//!   "fee": { "constant": 155381, "coefficient": 43.946 },
+//!   "inputs": [
+//!     { "txid": "<hex>", "index": 0, "value": 1000000, "path": "m/44'/1815'/0'/0/0" }
+//!   ],
+//!   "outputs": [
+//!     { "address": "<base58>", "value": 500000 }
+//!   ],
+//!   "change": "<base58>"
+//! }
+//! ```
+
+use cardano::{
+    address::ExtendedAddr,
+    coin::{Coin, CoinDiff},
+    config::ProtocolMagic,
+    fee,
+    hdpayload::Path,
+    tx::{TxAux, TxId, TxInWitness, TxOut, TxoPointer},
+    txbuild::{self, TxBuilder, TxFinalized},
+    txutils::OutputPolicy,
+    wallet::bip44::{self, Addressing},
+};
+use exe_common::txspec::LinearFee;
+use serde::{Deserialize, Deserializer};
+
+use std::fmt;
+
+use crate::wallets;
+
+#[derive(Debug)]
+pub enum Error {
+    Build(txbuild::Error),
+    MissingPath(usize),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Build(err) => write!(f, "cannot build the transaction: {}", err),
+            Error::MissingPath(idx) => {
+                write!(f, "input {} has no derivation path to sign with", idx)
+            }
+        }
+    }
+}
+impl std::error::Error for Error {}
+impl From<txbuild::Error> for Error {
+    fn from(e: txbuild::Error) -> Self {
+        Error::Build(e)
+    }
+}
+
+#[derive(Deserialize)]
+pub struct Input {
+    pub txid: TxId,
+    pub index: u32,
+    pub value: Coin,
+    #[serde(default, deserialize_with = "deserialize_path")]
+    pub path: Option<Addressing>,
+}
+
+#[derive(Deserialize)]
+pub struct Output {
+    pub address: ExtendedAddr,
+    pub value: Coin,
+}
+
+#[derive(Deserialize)]
+pub struct Spec {
+    #[serde(default)]
+    pub protocol_magic: ProtocolMagic,
+    #[serde(default)]
+    fee: Option<LinearFee>,
+    pub inputs: Vec<Input>,
+    pub outputs: Vec<Output>,
+    #[serde(default)]
+    pub change: Option<ExtendedAddr>,
+}
+
+/// the fee and balance of a built transaction
+#[derive(Serialize)]
+pub struct Summary {
+    pub txid: String,
+    pub inputs: u64,
+    pub outputs: u64,
+    pub fee: u64,
+    /// what is left once the outputs and the fee are paid: positive values
+    /// are paid as extra fees, negative values are missing from the inputs
+    pub balance: i64,
+}
+
+impl Spec {
+    pub fn fee_algorithm(&mut self) -> fee::LinearFee {
+        self.fee
+            .take()
+            .map(fee::LinearFee::from)
+            .unwrap_or_default()
+    }
+}
+
+pub fn build(spec: &Spec, fee_algorithm: &fee::LinearFee) -> Result<TxBuilder, Error> {
+    let mut builder = TxBuilder::new();
+    for input in spec.inputs.iter() {
+        builder.add_input(&TxoPointer::new(input.txid, input.index), input.value);
+    }
+    for output in spec.outputs.iter() {
+        builder.add_output_value(&TxOut::new(output.address.clone(), output.value));
+    }
+    if let Some(change) = &spec.change {
+        builder.add_output_policy(fee_algorithm, &OutputPolicy::One(change.clone()))?;
+    }
+    Ok(builder)
+}
+
+pub fn summary(builder: &TxBuilder, fee_algorithm: &fee::LinearFee) -> Result<Summary, Error> {
+    let fee = builder.calculate_fee(fee_algorithm)?;
+    let tx = builder.clone().make_tx()?;
+    let balance = match builder.balance(fee_algorithm)? {
+        CoinDiff::Zero => 0,
+        CoinDiff::Positive(c) => u64::from(c) as i64,
+        CoinDiff::Negative(c) => -(u64::from(c) as i64),
+    };
+    Ok(Summary {
+        txid: tx.id().to_string(),
+        inputs: builder.get_input_total()?.into(),
+        outputs: builder.get_output_total()?.into(),
+        fee: fee.to_coin().into(),
+        balance,
+    })
+}
+
+/// sign every input with the key of its derivation path
+pub fn sign(builder: TxBuilder, spec: &Spec, wallet: &bip44::Wallet) -> Result<TxAux, Error> {
+    let keys = spec
+        .inputs
+        .iter()
+        .enumerate()
+        .map(|(idx, input)| match &input.path {
+            None => Err(Error::MissingPath(idx)),
+            Some(path) => Ok(wallets::private_key(wallet, path)),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let tx = builder.make_tx()?;
+    let txid = tx.id();
+    let mut finalized = TxFinalized::new(tx);
+    for key in keys.iter() {
+        finalized.add_witness(TxInWitness::new_extended_pk(
+            spec.protocol_magic,
+            key,
+            &txid,
+        ))?;
+    }
+    Ok(finalized.make_txaux()?)
+}
+
+/// parse a BIP44 derivation path: `m/44'/1815'/account'/change/index`
+pub fn parse_path(s: &str) -> Result<Addressing, String> {
+    let mut elements = s.split('/');
+    if elements.next() != Some("m") {
+        return Err(format!("derivation path should start with `m/': {}", s));
+    }
+    let path = elements
+        .map(|e| {
+            let (e, hard) = if e.ends_with('\'') {
+                (&e[..e.len() - 1], true)
+            } else {
+                (e, false)
+            };
+            let index = e
+                .parse::<u32>()
+                .map_err(|err| format!("invalid derivation index `{}': {}", e, err))?;
+            if hard {
+                Ok(index | 0x8000_0000)
+            } else {
+                Ok(index)
+            }
+        })
+        .collect::<Result<Vec<u32>, String>>()?;
+    Addressing::from_path(Path::new(path)).map_err(|err| format!("{}", err))
+}
+
+/// display a BIP44 derivation path, the reverse of `parse_path`
+pub fn format_path(path: &Addressing) -> String {
+    format!(
+        "m/44'/1815'/{}'/{}/{}",
+        path.account.get_account_number(),
+        path.change,
+        path.index.get_scheme_value()
+    )
+}
+
+fn deserialize_path<'de, D>(deserializer: D) -> Result<Option<Addressing>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let path: Option<String> = Option::deserialize(deserializer)?;
+    match path {
+        None => Ok(None),
+        Some(path) => parse_path(&path)
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cardano::wallet::bip44::AddrType;
+
+    #[test]
+    fn derivation_path() {
+        let path = parse_path("m/44'/1815'/2'/1/42").unwrap();
+        assert_eq!(path, Addressing::new(2, AddrType::Internal, 42).unwrap());
+        assert_eq!(format_path(&path), "m/44'/1815'/2'/1/42");
+
+        assert!(parse_path("m/44'/1815'/2/1/42").is_err());
+        assert!(parse_path("44'/1815'/2'/1/42").is_err());
+    }
+}
@@ -0,0 +1,236 @@
+//! the wallets managed by the service
+//!
+//! Every wallet is stored in its own file of the wallet directory, in the
+//! same format as `cardano-cli`'s wallets (see `exe_common::wallet`). The
+//! service never keeps a decrypted key beyond the request that needed it.
+
+use cardano::{
+    address::ExtendedAddr,
+    bip::bip39,
+    config::ProtocolMagic,
+    hdwallet,
+    wallet::{
+        bip44::{self, AddrType, Addressing},
+        keygen,
+        scheme::Wallet as _,
+    },
+};
+use exe_common::wallet::{self as storage, WalletFile};
+use rand::{rngs::OsRng, RngCore};
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Json(serde_json::Error),
+    Bip39(bip39::Error),
+    Bip44(bip44::Error),
+    Derivation(hdwallet::Error),
+    InvalidName(String),
+    WalletAlreadyExists(String),
+    WalletNotFound(String),
+    InvalidEncoding,
+    CannotDecrypt,
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::Json(err) => write!(f, "invalid wallet file: {}", err),
+            Error::Bip39(err) => write!(f, "invalid mnemonics: {}", err),
+            Error::Bip44(err) => write!(f, "invalid derivation path: {}", err),
+            Error::Derivation(err) => write!(f, "derivation failed: {}", err),
+            Error::InvalidName(name) => write!(
+                f,
+                "invalid wallet name `{}', only ASCII letters, digits, `-' and `_' are allowed",
+                name
+            ),
+            Error::WalletAlreadyExists(name) => write!(f, "wallet `{}' already exists", name),
+            Error::WalletNotFound(name) => write!(f, "wallet `{}' not found", name),
+            Error::InvalidEncoding => write!(f, "invalid wallet file encoding"),
+            Error::CannotDecrypt => write!(f, "cannot decrypt the wallet, invalid password?"),
+        }
+    }
+}
+impl std::error::Error for Error {}
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+impl From<storage::Error> for Error {
+    fn from(e: storage::Error) -> Self {
+        // the wallet files are named after the wallets
+        let name = |path: &Path| {
+            path.file_stem()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default()
+        };
+        match e {
+            storage::Error::Io(e) => Error::Io(e),
+            storage::Error::Json(e) => Error::Json(e),
+            storage::Error::WalletAlreadyExists(path) => Error::WalletAlreadyExists(name(&path)),
+            storage::Error::WalletNotFound(path) => Error::WalletNotFound(name(&path)),
+            storage::Error::InvalidEncoding => Error::InvalidEncoding,
+            storage::Error::CannotDecrypt => Error::CannotDecrypt,
+        }
+    }
+}
+impl From<bip39::Error> for Error {
+    fn from(e: bip39::Error) -> Self {
+        Error::Bip39(e)
+    }
+}
+impl From<bip44::Error> for Error {
+    fn from(e: bip44::Error) -> Self {
+        Error::Bip44(e)
+    }
+}
+impl From<hdwallet::Error> for Error {
+    fn from(e: hdwallet::Error) -> Self {
+        Error::Derivation(e)
+    }
+}
+
+impl Error {
+    /// the error is caused by the request rather than by the service
+    pub fn is_client_error(&self) -> bool {
+        match self {
+            Error::Io(_) | Error::Json(_) | Error::InvalidEncoding => false,
+            _ => true,
+        }
+    }
+}
+
+/// the directory of the wallets
+pub struct Wallets {
+    dir: PathBuf,
+}
+
+/// a wallet name is used as a file name, restrict it to a safe subset
+fn check_name(name: &str) -> Result<(), Error> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidName(name.to_owned()))
+    }
+}
+
+impl Wallets {
+    pub fn new<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        fs::create_dir_all(dir.as_ref())?;
+        Ok(Wallets {
+            dir: dir.as_ref().to_path_buf(),
+        })
+    }
+
+    /// the names of all the stored wallets, sorted
+    pub fn list(&self) -> Result<Vec<String>, Error> {
+        Ok(WalletFile::list(&self.dir)?)
+    }
+
+    /// create a new wallet of the given number of mnemonic words, returns
+    /// the mnemonic phrase: it is the only way to restore the wallet
+    pub fn create(&self, name: &str, words: usize, password: &[u8]) -> Result<String, Error> {
+        let t = bip39::Type::from_word_count(words)?;
+        let mut bytes = vec![0; t.to_key_size() / 8];
+        OsRng::new()
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+            .fill_bytes(&mut bytes);
+        let entropy = bip39::Entropy::from_slice(&bytes)?;
+
+        self.store(name, &entropy, password)?;
+        Ok(entropy
+            .to_mnemonics()
+            .to_string(&bip39::dictionary::ENGLISH))
+    }
+
+    /// restore the wallet of the given (English) mnemonic phrase
+    pub fn restore(&self, name: &str, phrase: &str, password: &[u8]) -> Result<(), Error> {
+        let mnemonics = bip39::Mnemonics::from_string(&bip39::dictionary::ENGLISH, phrase)?;
+        let entropy = bip39::Entropy::from_mnemonics(&mnemonics)?;
+        self.store(name, &entropy, password)
+    }
+
+    fn store(&self, name: &str, entropy: &bip39::Entropy, password: &[u8]) -> Result<(), Error> {
+        check_name(name)?;
+        let mut seed = [0; hdwallet::XPRV_SIZE];
+        keygen::generate_seed(entropy, &[], &mut seed);
+        let root_key = hdwallet::XPrv::normalize_bytes(seed);
+        let file = WalletFile::new(&root_key, Default::default(), password)?;
+        file.save(&self.dir, name)?;
+        Ok(())
+    }
+
+    /// decrypt the stored wallet with its spending password
+    pub fn open(&self, name: &str, password: &[u8]) -> Result<bip44::Wallet, Error> {
+        check_name(name)?;
+        let file = WalletFile::load(&self.dir, name)?;
+        let root_key = file.decrypt(password)?;
+        Ok(bip44::Wallet::from_root_key(
+            root_key,
+            file.derivation_scheme,
+        ))
+    }
+}
+
+/// the private key of the address of the given derivation path
+pub fn private_key(wallet: &bip44::Wallet, path: &Addressing) -> hdwallet::XPrv {
+    let scheme = wallet.derivation_scheme();
+    let key = wallet
+        .account(scheme, path.account.get_account_number())
+        .change(scheme, path.address_type())
+        .index(scheme, path.index.get_scheme_value());
+    (*key).clone()
+}
+
+/// derive `count` addresses of the given account, starting at `from`
+pub fn addresses(
+    wallet: &mut bip44::Wallet,
+    protocol_magic: ProtocolMagic,
+    account: u32,
+    addr_type: AddrType,
+    from: u32,
+    count: usize,
+) -> Result<Vec<(Addressing, ExtendedAddr)>, Error> {
+    Addressing::new(account, addr_type, from)?;
+    let account_key = wallet.create_account("", account).public();
+    account_key
+        .address_generator(addr_type, from)?
+        .take(count)
+        .enumerate()
+        .map(|(i, key)| {
+            let key = key?;
+            let path = Addressing::new(account, addr_type, from + i as u32)?;
+            let address = ExtendedAddr::new_simple((*key).clone(), protocol_magic.into());
+            Ok((path, address))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wallet_names() {
+        assert!(check_name("my-wallet_2").is_ok());
+        assert!(check_name("").is_err());
+        assert!(check_name("../wallet").is_err());
+        assert!(check_name("my wallet").is_err());
+    }
+}
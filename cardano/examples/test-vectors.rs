@@ -0,0 +1,13 @@
+//! emit the test vectors of the wallets' keys and addresses in JSON
+//!
+//! ```text
+//! cargo run -p cardano --example test-vectors > test-vectors/vectors/cardano_wallets.json
+//! ```
+
+extern crate cardano;
+
+use cardano::wallet::vectors;
+
+fn main() {
+    print!("{}", vectors::to_json(&vectors::vectors()));
+}
@@ -16,7 +16,7 @@ use hash::{Blake2b224, Sha3_256};
 use cbor;
 use cbor_event::{self, de::Deserializer, se::Serializer};
 use config::NetworkMagic;
-use hdpayload::HDAddressPayload;
+use hdpayload::{self, HDAddressPayload, HDKey, Path};
 use hdwallet::XPub;
 use redeem;
 use util::{base58, try_from_slice::TryFromSlice};
@@ -345,6 +345,7 @@ impl HashedSpendingData {
         // to serialize 64 bytes in cbor (2 bytes of cbor overhead).
         let buf = cbor!(&(&addr_type, spending_data, attrs))
             .expect("serialize the HashedSpendingData's digest data");
+        debug_assert_eq!(cbor::canonical::validate(&buf), Ok(()));
 
         let hash = Sha3_256::new(&buf);
         HashedSpendingData(Blake2b224::new(hash.as_ref()))
@@ -553,6 +554,50 @@ impl ExtendedAddr {
         )
     }
 
+    // bootstrap era address with the derivation path of the key encrypted
+    // in its attributes (random indexes wallets)
+    pub fn new_with_derivation_path(
+        xpub: XPub,
+        hdkey: &HDKey,
+        derivation_path: &Path,
+        network_magic: NetworkMagic,
+    ) -> Self {
+        let payload = hdkey.encrypt_path(derivation_path);
+        ExtendedAddr::new(
+            AddrType::ATPubKey,
+            SpendingData::PubKeyASD(xpub),
+            Attributes::new_bootstrap_era(Some(payload), network_magic),
+        )
+    }
+
+    /// decrypt the derivation path in the address attributes with the given
+    /// `HDKey`
+    ///
+    /// Returns `None` if the address has no derivation path or if it was
+    /// not encrypted with this key (the address belongs to another wallet).
+    /// The derivation path of an address can be copied in another one: the
+    /// address is to be generated again from the path to check it really
+    /// belongs to the wallet.
+    pub fn decrypt_derivation_path(&self, hdkey: &HDKey) -> hdpayload::Result<Option<Path>> {
+        match self.attributes.derivation_path {
+            None => Ok(None),
+            Some(ref payload) => match hdkey.decrypt_path(payload) {
+                Ok(path) => Ok(Some(path)),
+                Err(hdpayload::Error::CannotDecrypt) => Ok(None),
+                Err(err) => Err(err),
+            },
+        }
+    }
+
+    // bootstrap era redeem address (ADA voucher)
+    pub fn new_redeem(public_key: redeem::PublicKey, network_magic: NetworkMagic) -> Self {
+        ExtendedAddr::new(
+            AddrType::ATRedeem,
+            SpendingData::RedeemASD(public_key),
+            Attributes::new_bootstrap_era(None, network_magic),
+        )
+    }
+
     pub fn to_address(&self) -> Addr {
         Addr(cbor!(self).unwrap()) // unwrap should never fail from strongly typed extended addr to addr
     }
@@ -592,6 +637,12 @@ impl cbor_event::se::Serialize for ExtendedAddr {
 impl cbor_event::de::Deserialize for ExtendedAddr {
     fn deserialize<R: BufRead>(reader: &mut Deserializer<R>) -> cbor_event::Result<Self> {
         let bytes = cbor::hs::util::raw_with_crc32(reader)?;
+        // addresses are compared by their encoding: reject the encodings
+        // that are not canonical, as the same address would have several
+        // representations
+        cbor::canonical::validate(&bytes).map_err(|err| {
+            cbor_event::Error::CustomError(format!("Invalid ExtendedAddr encoding: {}", err))
+        })?;
         let mut raw = Deserializer::from(std::io::Cursor::new(bytes));
         raw.tuple(3, "ExtendedAddr")?;
         let addr = cbor_event::de::Deserialize::deserialize(&mut raw)?;
@@ -720,8 +771,10 @@ impl cbor_event::se::Serialize for SpendingData {
 #[cfg(test)]
 mod tests {
     use address::*;
+    use cbor_event::{self, se::Serializer};
     use hdwallet;
     use util::base58;
+    use util::try_from_slice::TryFromSlice;
 
     #[test]
     fn test_make_address() {
@@ -745,6 +798,36 @@ mod tests {
         assert_eq!(ea.addr, addr);
     }
 
+    #[test]
+    fn non_canonical_extended_address() {
+        let seed = hdwallet::Seed::from_bytes([0; hdwallet::SEED_SIZE]);
+        let pk = hdwallet::XPrv::generate_from_seed(&seed).public();
+        let attrs = Attributes::new_single_key(&pk, None, NetworkMagic::NoMagic);
+        let ea = ExtendedAddr::new(AddrType::ATPubKey, SpendingData::PubKeyASD(pk), attrs);
+
+        let with_payload = |payload: &[u8]| {
+            let mut se = Serializer::new_vec();
+            se.write_array(cbor_event::Len::Len(2))
+                .unwrap()
+                .write_tag(24)
+                .unwrap()
+                .write_bytes(payload)
+                .unwrap()
+                .write_unsigned_integer(::crc32::crc32(payload) as u64)
+                .unwrap();
+            se.finalize()
+        };
+        let mut payload = cbor!(&(&ea.addr, &ea.attributes, &ea.addr_type)).unwrap();
+        let bytes = with_payload(&payload);
+        assert_eq!(bytes, cbor!(&ea).unwrap());
+        assert!(ExtendedAddr::try_from_slice(&bytes).is_ok());
+
+        // the same address, with a non minimal encoding of its type
+        assert_eq!(payload.pop(), Some(0x00));
+        payload.extend_from_slice(&[0x18, 0x00]);
+        assert!(ExtendedAddr::try_from_slice(&with_payload(&payload)).is_err());
+    }
+
     #[test]
     fn test_encode_extended_address() {
         let v = vec![
@@ -898,6 +981,19 @@ mod tests {
         assert_eq!(r.attributes.network_magic, NetworkMagic::Magic(1097911063));
         assert_eq!(&bytes[..], &cbor!(r).unwrap()[..])
     }
+
+    #[test]
+    fn golden_base58_addresses() {
+        for vector in ::test_vectors::cardano_addresses() {
+            let addr: ExtendedAddr = vector
+                .base58
+                .parse()
+                .unwrap_or_else(|e| panic!("{}: {:?}", vector.name, e));
+            assert_eq!(addr.to_string(), vector.base58, "{}", vector.name);
+            let bytes = base58::decode(&vector.base58).unwrap();
+            assert_eq!(&cbor!(addr).unwrap()[..], &bytes[..], "{}", vector.name);
+        }
+    }
 }
 
 #[cfg(feature = "with-bench")]
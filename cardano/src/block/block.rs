@@ -4,11 +4,13 @@
 use std::ops::{Deref, DerefMut};
 use std::{
     fmt,
-    io::{BufRead, Cursor, Write},
+    io::{BufRead, Cursor, Read, Write},
 };
 
 use super::super::cbor::hs::util::decode_sum_type;
+use super::super::cbor::{canonical, stream};
 use super::super::config::ProtocolMagic;
+use super::super::tx::{Tx, TxId};
 use super::boundary;
 use super::date::BlockDate;
 use super::normal;
@@ -45,6 +47,36 @@ impl RawBlockHeader {
     pub fn compute_hash(&self) -> HeaderHash {
         HeaderHash::new(&self.0)
     }
+
+    /// Read the header of a block from the serialization of the block,
+    /// streamed from the reader. The body and the extra data of the block
+    /// are skipped without being decoded nor held in memory.
+    pub fn read_from_block<R: Read>(reader: R) -> cbor_event::Result<Self> {
+        let mut reader = stream::Reader::new(reader);
+        let offset = reader.offset();
+        if reader.array()? != Some(2) {
+            return Err(stream::Error::Unexpected(offset, "a Block").into());
+        }
+        let offset = reader.offset();
+        let tag = reader.unsigned_integer()?;
+        if tag > 1 {
+            return Err(cbor_event::Error::CustomError(format!(
+                "Unsupported Block: {}",
+                tag
+            )));
+        }
+        let offset = reader.offset();
+        if reader.array()? != Some(3) {
+            return Err(stream::Error::Unexpected(offset, "a Block").into());
+        }
+        // the header is hashed as the sum type `[tag, header]`
+        let mut header = vec![0x82, tag as u8];
+        header.extend(reader.raw_item()?);
+        reader.skip()?;
+        reader.skip()?;
+        reader.expect_end()?;
+        Ok(RawBlockHeader(header))
+    }
 }
 impl RawBlock {
     pub fn from_dat(dat: Vec<u8>) -> Self {
@@ -55,9 +87,75 @@ impl RawBlock {
         de.deserialize_complete()
     }
     pub fn to_header(&self) -> cbor_event::Result<RawBlockHeader> {
-        // TODO optimise if possible with the CBOR structure by skipping some prefix and some suffix ...
-        let blk = self.decode()?;
-        Ok(blk.header().to_raw())
+        Ok(self.decode_header()?.to_raw())
+    }
+
+    /// Decode only the header of the block, the body and the extra data
+    /// of the block are left undecoded.
+    pub fn decode_header(&self) -> cbor_event::Result<BlockHeader> {
+        let mut de = Deserializer::from(Cursor::new(&self.0));
+        match decode_sum_type(&mut de)? {
+            0 => {
+                de.tuple(3, "Block")?;
+                Ok(BlockHeader::BoundaryBlockHeader(de.deserialize()?))
+            }
+            1 => {
+                de.tuple(3, "Block")?;
+                Ok(BlockHeader::MainBlockHeader(de.deserialize()?))
+            }
+            idx => Err(cbor_event::Error::CustomError(format!(
+                "Unsupported Block: {}",
+                idx
+            ))),
+        }
+    }
+
+    /// Decode only the identifiers of the transactions of the block, in
+    /// order. The witnesses of the transactions are skipped and the other
+    /// payloads of the body (ssc, delegation, update) are not decoded.
+    ///
+    /// A boundary block has no transactions.
+    pub fn decode_tx_ids(&self) -> cbor_event::Result<Vec<TxId>> {
+        let mut de = Deserializer::from(Cursor::new(&self.0));
+        match decode_sum_type(&mut de)? {
+            0 => Ok(Vec::new()),
+            1 => {
+                de.tuple(3, "Block")?;
+                // skip the header
+                de.deserialize::<cbor_event::Value>()?;
+                de.tuple(4, "Body")?;
+                let len = de.array()?;
+                let mut ids = Vec::new();
+                loop {
+                    match len {
+                        cbor_event::Len::Len(n) if ids.len() as u64 == n => break,
+                        cbor_event::Len::Len(_) => {}
+                        cbor_event::Len::Indefinite => {
+                            if de.cbor_type()? == cbor_event::Type::Special {
+                                let special = de.special()?;
+                                if special != cbor_event::Special::Break {
+                                    return Err(cbor_event::Error::CustomError(format!(
+                                        "Invalid TxPayload end: {:?}",
+                                        special
+                                    )));
+                                }
+                                break;
+                            }
+                        }
+                    }
+                    de.tuple(2, "TxAux")?;
+                    let tx: Tx = de.deserialize()?;
+                    // skip the witnesses
+                    de.deserialize::<cbor_event::Value>()?;
+                    ids.push(tx.id());
+                }
+                Ok(ids)
+            }
+            idx => Err(cbor_event::Error::CustomError(format!(
+                "Unsupported Block: {}",
+                idx
+            ))),
+        }
     }
 }
 
@@ -238,7 +336,9 @@ impl BlockHeader {
         // the only reason this would fail is if there was no more memory
         // to allocate. This would be the users' last concern if it was the
         // case
-        RawBlockHeader(cbor!(self).unwrap())
+        let v = cbor!(self).unwrap();
+        debug_assert_eq!(canonical::validate_byron(&v), Ok(()));
+        RawBlockHeader(v)
     }
 
     pub fn compute_hash(&self) -> HeaderHash {
@@ -246,6 +346,7 @@ impl BlockHeader {
         // to allocate. This would be the users' last concern if it was the
         // case
         let v = cbor!(self).unwrap();
+        debug_assert_eq!(canonical::validate_byron(&v), Ok(()));
         HeaderHash::new(&v[..])
     }
 }
@@ -578,6 +679,13 @@ mod test {
     fn check_main_block() {
         check_blockheader_serialization(&MAINBLOCK_HEX[..], MAINBLOCK_HASH);
     }
+
+    #[test]
+    fn golden_block_headers() {
+        for vector in ::test_vectors::cardano_block_headers() {
+            check_blockheader_serialization(&vector.cbor, &vector.hash);
+        }
+    }
 }
 
 #[cfg(test)]
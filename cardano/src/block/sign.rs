@@ -1,5 +1,6 @@
 use self::normal::BodyProof;
 use block::*;
+use cbor::canonical;
 use cbor_event::{
     self,
     de::Deserializer,
@@ -64,6 +65,8 @@ fn verify_signature_with<T>(
 where
     T: se::Serialize,
 {
+    debug_assert_eq!(canonical::validate_byron(&cbor!(data).unwrap()), Ok(()));
+
     let mut buf = vec!['0' as u8, '1' as u8];
 
     buf.extend(proxy_sig.psk.issuer_pk.as_ref());
@@ -711,6 +711,27 @@ mod tests {
         // TODO: SelfSignedPSK, WrongBoundaryProof
     }
 
+    #[test]
+    fn test_lazy_decoding() {
+        for raw in &[BLOCK1, BLOCK2, BLOCK3] {
+            let rblk = RawBlock(raw.to_vec());
+            let blk = rblk.decode().unwrap();
+
+            let header = rblk.decode_header().unwrap();
+            assert_eq!(header.compute_hash(), blk.header().compute_hash());
+
+            let raw_header = RawBlockHeader::read_from_block(&raw[..]).unwrap();
+            assert_eq!(raw_header.compute_hash(), header.compute_hash());
+            assert!(RawBlockHeader::read_from_block(&raw[..raw.len() - 1]).is_err());
+
+            let tx_ids: Vec<_> = blk
+                .get_transactions()
+                .map(|txs| txs.iter().map(|txaux| txaux.tx.id()).collect())
+                .unwrap_or_default();
+            assert_eq!(rblk.decode_tx_ids().unwrap(), tx_ids);
+        }
+    }
+
     // a block with 6 transactions
     const HEADER_HASH1: &str = "ae443ffffe52cc29de83312d2819b3955fc306ce65ae6aa5b26f1d3c76e91842";
     const BLOCK1: &'static [u8] = &[
@@ -0,0 +1,520 @@
+//! Canonical CBOR encoding (RFC 7049 section 3.9)
+//!
+//! The hash of a structure is only stable if every encoder produces the
+//! same bytes for it. An item is canonical when:
+//!
+//! * it only uses definite lengths;
+//! * integers, lengths and tags use the shortest possible encoding;
+//! * the keys of a map are sorted (shorter keys first, then in bytewise
+//!   lexical order) and unique.
+//!
+//! `validate` rejects the input that is not canonical, `serialize` is the
+//! canonical mode of the serializer: the output of `cbor_event`'s
+//! serializer is re-encoded in the canonical form.
+//!
+//! The Byron structures (e.g. the inputs and the outputs of a transaction)
+//! are encoded with indefinite length arrays, so the encoding of the bytes
+//! hashed or signed by the Byron protocol is checked with `validate_byron`,
+//! which accepts those arrays and applies the other rules.
+//!
+
+use cbor_event::{self, se};
+
+use std::cmp::Ordering;
+use std::{error, fmt, str};
+
+/// maximum nesting of arrays, maps and tags accepted by the validator
+pub const MAX_DEPTH: usize = 128;
+
+const BREAK: u8 = 0xff;
+
+/// reason why an input is not canonical, with the offset of the
+/// offending item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    EndOfInput,
+    TrailingBytes(usize),
+    InvalidAdditionalInfo(usize),
+    IndefiniteLength(usize),
+    NonMinimalEncoding(usize),
+    UnsortedMapKeys(usize),
+    DuplicatedMapKey(usize),
+    InvalidUtf8(usize),
+    TooDeep(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::EndOfInput => write!(f, "unexpected end of input"),
+            Error::TrailingBytes(offset) => write!(f, "trailing bytes at offset {}", offset),
+            Error::InvalidAdditionalInfo(offset) => {
+                write!(f, "invalid additional information at offset {}", offset)
+            }
+            Error::IndefiniteLength(offset) => {
+                write!(f, "indefinite length item at offset {}", offset)
+            }
+            Error::NonMinimalEncoding(offset) => {
+                write!(f, "non minimal encoding at offset {}", offset)
+            }
+            Error::UnsortedMapKeys(offset) => write!(f, "unsorted map key at offset {}", offset),
+            Error::DuplicatedMapKey(offset) => {
+                write!(f, "duplicated map key at offset {}", offset)
+            }
+            Error::InvalidUtf8(offset) => write!(f, "invalid UTF-8 text at offset {}", offset),
+            Error::TooDeep(offset) => write!(f, "nesting too deep at offset {}", offset),
+        }
+    }
+}
+impl error::Error for Error {}
+
+/// check the given bytes are exactly one canonically encoded CBOR item
+pub fn validate(bytes: &[u8]) -> Result<(), Error> {
+    validate_complete(bytes, false)
+}
+
+/// check the given bytes are exactly one CBOR item encoded like the Byron
+/// structures: canonically, except that arrays may be of indefinite length
+pub fn validate_byron(bytes: &[u8]) -> Result<(), Error> {
+    validate_complete(bytes, true)
+}
+
+fn validate_complete(bytes: &[u8], indefinite_arrays: bool) -> Result<(), Error> {
+    let end = validate_item(bytes, 0, 0, indefinite_arrays)?;
+    if end != bytes.len() {
+        return Err(Error::TrailingBytes(end));
+    }
+    Ok(())
+}
+
+/// serialize the value in the canonical form
+pub fn serialize<T: se::Serialize>(value: &T) -> cbor_event::Result<Vec<u8>> {
+    let mut se = se::Serializer::new_vec();
+    se.serialize(value)?;
+    canonicalize(&se.finalize()).map_err(|err| cbor_event::Error::CustomError(err.to_string()))
+}
+
+/// re-encode the given CBOR item in the canonical form. Fails if the item
+/// is not well formed or if a map has duplicated keys.
+pub fn canonicalize(bytes: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut out = Vec::with_capacity(bytes.len());
+    let end = canonicalize_item(bytes, 0, 0, &mut out)?;
+    if end != bytes.len() {
+        return Err(Error::TrailingBytes(end));
+    }
+    Ok(out)
+}
+
+/// check the item starting at `offset` and return the offset of the
+/// following item
+fn validate_item(
+    bytes: &[u8],
+    offset: usize,
+    depth: usize,
+    indefinite_arrays: bool,
+) -> Result<usize, Error> {
+    if depth > MAX_DEPTH {
+        return Err(Error::TooDeep(offset));
+    }
+    let (major, arg, mut next) = match read_head(bytes, offset)? {
+        // indefinite array
+        None if indefinite_arrays && major_type(bytes, offset) == 4 => {
+            let mut next = offset + 1;
+            while *bytes.get(next).ok_or(Error::EndOfInput)? != BREAK {
+                next = validate_item(bytes, next, depth + 1, indefinite_arrays)?;
+            }
+            return Ok(next + 1);
+        }
+        None => return Err(Error::IndefiniteLength(offset)),
+        Some(head) => head,
+    };
+    if !is_minimal(bytes, offset, arg) {
+        return Err(Error::NonMinimalEncoding(offset));
+    }
+    match major {
+        // unsigned and negative integers, simple values and floats
+        0 | 1 | 7 => Ok(next),
+        // bytes and text
+        2 | 3 => read_string(bytes, offset, major, arg, next).map(|(_, next)| next),
+        // array
+        4 => {
+            for _ in 0..arg {
+                next = validate_item(bytes, next, depth + 1, indefinite_arrays)?;
+            }
+            Ok(next)
+        }
+        // map
+        5 => {
+            let mut previous_key: Option<&[u8]> = None;
+            for _ in 0..arg {
+                let key_start = next;
+                next = validate_item(bytes, next, depth + 1, indefinite_arrays)?;
+                let key = &bytes[key_start..next];
+                if let Some(previous_key) = previous_key {
+                    match compare_keys(previous_key, key) {
+                        Ordering::Less => {}
+                        Ordering::Equal => return Err(Error::DuplicatedMapKey(key_start)),
+                        Ordering::Greater => return Err(Error::UnsortedMapKeys(key_start)),
+                    }
+                }
+                previous_key = Some(key);
+                next = validate_item(bytes, next, depth + 1, indefinite_arrays)?;
+            }
+            Ok(next)
+        }
+        // tag
+        6 => validate_item(bytes, next, depth + 1, indefinite_arrays),
+        _ => unreachable!(),
+    }
+}
+
+/// re-encode the item starting at `offset` at the end of `out` and return
+/// the offset of the following item
+fn canonicalize_item(
+    bytes: &[u8],
+    offset: usize,
+    depth: usize,
+    out: &mut Vec<u8>,
+) -> Result<usize, Error> {
+    if depth > MAX_DEPTH {
+        return Err(Error::TooDeep(offset));
+    }
+    let head = read_head(bytes, offset)?;
+    let major = major_type(bytes, offset);
+    let (length, mut next) = match head {
+        Some((0, arg, next)) | Some((1, arg, next)) | Some((6, arg, next)) => {
+            write_head(out, major, arg);
+            return if major == 6 {
+                canonicalize_item(bytes, next, depth + 1, out)
+            } else {
+                Ok(next)
+            };
+        }
+        Some((7, arg, next)) => {
+            // floats are copied as they are, their size is part of the value
+            if next - offset > 2 {
+                out.extend_from_slice(&bytes[offset..next]);
+            } else {
+                write_head(out, major, arg);
+            }
+            return Ok(next);
+        }
+        Some((2, arg, next)) | Some((3, arg, next)) => {
+            let (content, next) = read_string(bytes, offset, major, arg, next)?;
+            write_head(out, major, content.len() as u64);
+            out.extend_from_slice(content);
+            return Ok(next);
+        }
+        Some((_, arg, next)) => (Some(arg), next),
+        None => (None, offset + 1),
+    };
+
+    // the items of an indefinite length item end with a break
+    let has_next = |next: usize, count: u64| -> Result<bool, Error> {
+        match length {
+            Some(length) => Ok(count < length),
+            None => Ok(*bytes.get(next).ok_or(Error::EndOfInput)? != BREAK),
+        }
+    };
+    let mut count = 0;
+    match major {
+        // bytes and text of indefinite length: the concatenation of the
+        // definite length chunks
+        2 | 3 => {
+            let mut content = Vec::new();
+            while has_next(next, count)? {
+                match read_head(bytes, next)? {
+                    Some((chunk_major, arg, chunk_next)) if chunk_major == major => {
+                        let (chunk, chunk_next) = read_string(bytes, next, major, arg, chunk_next)?;
+                        content.extend_from_slice(chunk);
+                        next = chunk_next;
+                    }
+                    _ => return Err(Error::InvalidAdditionalInfo(next)),
+                }
+                count += 1;
+            }
+            write_head(out, major, content.len() as u64);
+            out.extend_from_slice(&content);
+        }
+        // array
+        4 => {
+            let mut items = Vec::new();
+            while has_next(next, count)? {
+                next = canonicalize_item(bytes, next, depth + 1, &mut items)?;
+                count += 1;
+            }
+            write_head(out, major, count);
+            out.extend_from_slice(&items);
+        }
+        // map
+        5 => {
+            let mut entries = Vec::new();
+            while has_next(next, count)? {
+                let key_start = next;
+                let mut key = Vec::new();
+                next = canonicalize_item(bytes, next, depth + 1, &mut key)?;
+                let mut value = Vec::new();
+                next = canonicalize_item(bytes, next, depth + 1, &mut value)?;
+                entries.push((key, value, key_start));
+                count += 1;
+            }
+            entries.sort_by(|a, b| compare_keys(&a.0, &b.0));
+            for pair in entries.windows(2) {
+                if pair[0].0 == pair[1].0 {
+                    return Err(Error::DuplicatedMapKey(pair[1].2));
+                }
+            }
+            write_head(out, major, count);
+            for (key, value, _) in entries {
+                out.extend_from_slice(&key);
+                out.extend_from_slice(&value);
+            }
+        }
+        _ => return Err(Error::InvalidAdditionalInfo(offset)),
+    }
+    // skip the break of the indefinite length items
+    Ok(if length.is_none() { next + 1 } else { next })
+}
+
+/// the order of the keys of a canonical map
+fn compare_keys(a: &[u8], b: &[u8]) -> Ordering {
+    (a.len(), a).cmp(&(b.len(), b))
+}
+
+fn major_type(bytes: &[u8], offset: usize) -> u8 {
+    bytes[offset] >> 5
+}
+
+/// the content of the definite length bytes or text of the given head,
+/// and the offset following it
+fn read_string(
+    bytes: &[u8],
+    offset: usize,
+    major: u8,
+    len: u64,
+    start: usize,
+) -> Result<(&[u8], usize), Error> {
+    if len > (bytes.len() - start) as u64 {
+        return Err(Error::EndOfInput);
+    }
+    let end = start + len as usize;
+    let content = &bytes[start..end];
+    if major == 3 && str::from_utf8(content).is_err() {
+        return Err(Error::InvalidUtf8(offset));
+    }
+    Ok((content, end))
+}
+
+/// read the head of the item at `offset`: the major type, its argument
+/// and the offset following the head. Returns `None` for the head of an
+/// indefinite length item.
+fn read_head(bytes: &[u8], offset: usize) -> Result<Option<(u8, u64, usize)>, Error> {
+    let initial = *bytes.get(offset).ok_or(Error::EndOfInput)?;
+    let major = initial >> 5;
+    let additional = initial & 0x1f;
+    let size = match additional {
+        0..=23 => return Ok(Some((major, additional as u64, offset + 1))),
+        24 => 1,
+        25 => 2,
+        26 => 4,
+        27 => 8,
+        31 if major >= 2 && major <= 5 => return Ok(None),
+        _ => return Err(Error::InvalidAdditionalInfo(offset)),
+    };
+    let start = offset + 1;
+    if bytes.len() - start < size {
+        return Err(Error::EndOfInput);
+    }
+    let arg = bytes[start..start + size]
+        .iter()
+        .fold(0u64, |acc, byte| acc << 8 | *byte as u64);
+    Ok(Some((major, arg, start + size)))
+}
+
+/// whether the argument of the head at `offset` uses the shortest
+/// possible encoding
+fn is_minimal(bytes: &[u8], offset: usize, arg: u64) -> bool {
+    let major = bytes[offset] >> 5;
+    let size = match bytes[offset] & 0x1f {
+        24 => 1,
+        25 => 2,
+        26 => 4,
+        27 => 8,
+        _ => return true,
+    };
+    match size {
+        1 => arg >= 24 && (major != 7 || arg >= 32),
+        // floats are not length-prefixed integers, their size is part of
+        // the value
+        _ if major == 7 => true,
+        2 => arg > 0xff,
+        4 => arg > 0xffff,
+        _ => arg > 0xffff_ffff,
+    }
+}
+
+/// write the head of the given major type with the shortest encoding of
+/// its argument
+fn write_head(out: &mut Vec<u8>, major: u8, arg: u64) {
+    let major = major << 5;
+    if arg < 24 {
+        out.push(major | arg as u8);
+    } else if arg <= 0xff {
+        out.extend_from_slice(&[major | 24, arg as u8]);
+    } else if arg <= 0xffff {
+        out.push(major | 25);
+        out.extend_from_slice(&(arg as u16).to_be_bytes());
+    } else if arg <= 0xffff_ffff {
+        out.push(major | 26);
+        out.extend_from_slice(&(arg as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&arg.to_be_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cbor_event::{se::Serializer, Len};
+
+    #[test]
+    fn canonical_items() {
+        validate(&[0x00]).unwrap();
+        validate(&[0x17]).unwrap();
+        validate(&[0x18, 0x18]).unwrap();
+        validate(&[0x19, 0x01, 0x00]).unwrap();
+        validate(&[0x3a, 0x00, 0x01, 0x00, 0x00]).unwrap();
+        validate(&[0x43, 0x01, 0x02, 0x03]).unwrap();
+        validate(&[0x82, 0x01, 0x61, 0x61]).unwrap();
+        validate(&[0xd8, 0x18, 0x40]).unwrap();
+        validate(&[0xf5]).unwrap();
+        validate(&[0xf9, 0x00, 0x00]).unwrap();
+        // keys sorted by length, then bytewise
+        validate(&[0xa3, 0x0a, 0x00, 0x18, 0x64, 0x00, 0x61, 0x61, 0x00]).unwrap();
+    }
+
+    #[test]
+    fn non_canonical_items() {
+        assert_eq!(validate(&[0x18, 0x17]), Err(Error::NonMinimalEncoding(0)));
+        assert_eq!(
+            validate(&[0x19, 0x00, 0xff]),
+            Err(Error::NonMinimalEncoding(0))
+        );
+        assert_eq!(
+            validate(&[0x81, 0x5a, 0x00, 0x00, 0x00, 0x01, 0x00]),
+            Err(Error::NonMinimalEncoding(1))
+        );
+        assert_eq!(validate(&[0xf8, 0x14]), Err(Error::NonMinimalEncoding(0)));
+        assert_eq!(
+            validate(&[0x9f, 0x01, 0xff]),
+            Err(Error::IndefiniteLength(0))
+        );
+        assert_eq!(
+            validate(&[0xa2, 0x18, 0x64, 0x00, 0x0a, 0x00]),
+            Err(Error::UnsortedMapKeys(4))
+        );
+        assert_eq!(
+            validate(&[0xa2, 0x01, 0x00, 0x01, 0x00]),
+            Err(Error::DuplicatedMapKey(3))
+        );
+        assert_eq!(validate(&[0x1c]), Err(Error::InvalidAdditionalInfo(0)));
+        assert_eq!(validate(&[0x01, 0x01]), Err(Error::TrailingBytes(1)));
+        assert_eq!(validate(&[0x82, 0x01]), Err(Error::EndOfInput));
+        assert_eq!(validate(&[0x44, 0x01]), Err(Error::EndOfInput));
+        assert_eq!(
+            validate(&[0x81; MAX_DEPTH + 2]),
+            Err(Error::TooDeep(MAX_DEPTH + 1))
+        );
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        validate(&[0x62, 0xc3, 0xa9]).unwrap();
+        assert_eq!(validate(&[0x62, 0xc3, 0x28]), Err(Error::InvalidUtf8(0)));
+        assert_eq!(validate(&[0x81, 0x61, 0xff]), Err(Error::InvalidUtf8(1)));
+        assert_eq!(canonicalize(&[0x61, 0x80]), Err(Error::InvalidUtf8(0)));
+        // the chunks of an indefinite text are valid on their own
+        assert_eq!(
+            canonicalize(&[0x7f, 0x61, 0xc3, 0xff]),
+            Err(Error::InvalidUtf8(1))
+        );
+    }
+
+    #[test]
+    fn byron_indefinite_arrays() {
+        let tx = [0x83, 0x9f, 0x01, 0x02, 0xff, 0x9f, 0xff, 0xa0];
+        validate_byron(&tx).unwrap();
+        assert_eq!(validate(&tx), Err(Error::IndefiniteLength(1)));
+
+        // only arrays may be of indefinite length, the other rules apply
+        assert_eq!(
+            validate_byron(&[0xbf, 0x01, 0x00, 0xff]),
+            Err(Error::IndefiniteLength(0))
+        );
+        assert_eq!(
+            validate_byron(&[0x9f, 0x18, 0x01, 0xff]),
+            Err(Error::NonMinimalEncoding(1))
+        );
+        assert_eq!(validate_byron(&[0x9f, 0x01]), Err(Error::EndOfInput));
+    }
+
+    #[test]
+    fn canonical_serialization() {
+        let items: &[(&[u8], &[u8])] = &[
+            (&[0x18, 0x17], &[0x17]),
+            (&[0x1b, 0, 0, 0, 0, 0, 0, 0x01, 0x00], &[0x19, 0x01, 0x00]),
+            (&[0x9f, 0x01, 0x9f, 0xff, 0xff], &[0x82, 0x01, 0x80]),
+            (
+                &[0x5f, 0x41, 0x01, 0x42, 0x02, 0x03, 0xff],
+                &[0x43, 0x01, 0x02, 0x03],
+            ),
+            (&[0xd9, 0x00, 0x18, 0x40], &[0xd8, 0x18, 0x40]),
+            (
+                &[0xfa, 0x00, 0x00, 0x00, 0x00],
+                &[0xfa, 0x00, 0x00, 0x00, 0x00],
+            ),
+            (
+                &[0xbf, 0x61, 0x61, 0x00, 0x18, 0x64, 0x00, 0x0a, 0x00, 0xff],
+                &[0xa3, 0x0a, 0x00, 0x18, 0x64, 0x00, 0x61, 0x61, 0x00],
+            ),
+        ];
+        for (input, expected) in items {
+            let output = canonicalize(input).unwrap();
+            assert_eq!(&output[..], *expected);
+            validate(&output).unwrap();
+        }
+
+        assert_eq!(
+            canonicalize(&[0xa2, 0x01, 0x00, 0x01, 0x02]),
+            Err(Error::DuplicatedMapKey(3))
+        );
+        assert_eq!(canonicalize(&[0x9f, 0x01]), Err(Error::EndOfInput));
+        assert_eq!(canonicalize(&[0x01, 0x01]), Err(Error::TrailingBytes(1)));
+        assert_eq!(canonicalize(&[0xff]), Err(Error::InvalidAdditionalInfo(0)));
+
+        let tx = ::tx::Tx::new();
+        let bytes = serialize(&tx).unwrap();
+        assert_eq!(bytes, vec![0x83, 0x80, 0x80, 0xa0]);
+        validate_byron(&cbor!(&tx).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn serializer_definite_lengths() {
+        let mut se = Serializer::new_vec();
+        se.write_array(Len::Len(2))
+            .unwrap()
+            .write_unsigned_integer(1_000_000)
+            .unwrap()
+            .write_bytes(&[1, 2, 3])
+            .unwrap();
+        validate(&se.finalize()).unwrap();
+
+        let mut se = Serializer::new_vec();
+        se.write_array(Len::Indefinite)
+            .unwrap()
+            .write_special(cbor_event::Special::Break)
+            .unwrap();
+        assert_eq!(validate(&se.finalize()), Err(Error::IndefiniteLength(0)));
+    }
+}
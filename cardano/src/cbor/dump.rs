@@ -0,0 +1,636 @@
+//! Rendering of CBOR items in diagnostic notation (RFC 7049 section 6)
+//! and structural diff between two CBOR items
+//!
+//! Meant for debugging the serialization: the items are decoded as they
+//! are encoded (indefinite lengths, order of the map keys...) without
+//! knowing the types they hold, so any blob can be rendered and two blobs
+//! compared without an external tool:
+//!
+//! ```
+//! use cardano::cbor::dump;
+//!
+//! let left = [0x82, 0x01, 0x43, 0x01, 0x02, 0x03];
+//! let right = [0x82, 0x01, 0x43, 0x01, 0x02, 0x04];
+//!
+//! assert_eq!(dump::diagnostic(&left).unwrap(), "[1, h'010203']");
+//! let diff = dump::diff(&left, &right).unwrap();
+//! assert_eq!(diff[0].to_string(), "[1]: h'010203' -> h'010204'");
+//! ```
+//!
+
+use super::canonical::MAX_DEPTH;
+use std::fmt;
+use std::{error, str};
+use util::hex;
+
+/// reason why an input cannot be decoded, with the offset of the
+/// offending item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    EndOfInput,
+    TrailingBytes(usize),
+    InvalidAdditionalInfo(usize),
+    InvalidUtf8(usize),
+    InvalidChunk(usize),
+    UnexpectedBreak(usize),
+    TooDeep(usize),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::EndOfInput => write!(f, "unexpected end of input"),
+            Error::TrailingBytes(offset) => write!(f, "trailing bytes at offset {}", offset),
+            Error::InvalidAdditionalInfo(offset) => {
+                write!(f, "invalid additional information at offset {}", offset)
+            }
+            Error::InvalidUtf8(offset) => write!(f, "invalid UTF-8 text at offset {}", offset),
+            Error::InvalidChunk(offset) => {
+                write!(f, "invalid indefinite length chunk at offset {}", offset)
+            }
+            Error::UnexpectedBreak(offset) => write!(f, "unexpected break at offset {}", offset),
+            Error::TooDeep(offset) => write!(f, "nesting too deep at offset {}", offset),
+        }
+    }
+}
+impl error::Error for Error {}
+
+/// a decoded CBOR item, as it was encoded
+#[derive(Debug, Clone, PartialEq)]
+pub enum Item {
+    Unsigned(u64),
+    /// the negative integer `-1 - n`
+    Negative(u64),
+    Bytes(Vec<u8>),
+    /// the chunks of indefinite length bytes
+    IndefiniteBytes(Vec<Vec<u8>>),
+    Text(String),
+    /// the chunks of indefinite length text
+    IndefiniteText(Vec<String>),
+    Array(Vec<Item>),
+    IndefiniteArray(Vec<Item>),
+    Map(Vec<(Item, Item)>),
+    IndefiniteMap(Vec<(Item, Item)>),
+    Tag(u64, Box<Item>),
+    Simple(u8),
+    Float(f64),
+}
+
+/// decode the given bytes, they must be exactly one CBOR item
+pub fn decode(bytes: &[u8]) -> Result<Item, Error> {
+    let mut decoder = Decoder { bytes, offset: 0 };
+    let item = decoder.item(0)?;
+    if decoder.offset != bytes.len() {
+        return Err(Error::TrailingBytes(decoder.offset));
+    }
+    Ok(item)
+}
+
+/// render the CBOR item in diagnostic notation, on one line
+pub fn diagnostic(bytes: &[u8]) -> Result<String, Error> {
+    Ok(decode(bytes)?.to_string())
+}
+
+/// render the CBOR item in diagnostic notation, the arrays and the maps
+/// are indented over multiple lines
+pub fn pretty(bytes: &[u8]) -> Result<String, Error> {
+    let mut out = String::new();
+    decode(bytes)?.pretty(&mut out, 0).unwrap();
+    Ok(out)
+}
+
+/// a difference between two items, at the given path
+///
+/// The path is made of the array indices (`[1]`), the map keys (`{"key"}`)
+/// and the tags (`<24>`) leading to the items. The items are `None` when
+/// they are present in one side only.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    pub path: String,
+    pub left: Option<Item>,
+    pub right: Option<Item>,
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let side = |item: &Option<Item>| match item {
+            None => "(none)".to_owned(),
+            Some(item) => item.to_string(),
+        };
+        let path = if self.path.is_empty() {
+            "$"
+        } else {
+            self.path.as_str()
+        };
+        write!(f, "{}: {} -> {}", path, side(&self.left), side(&self.right))
+    }
+}
+
+/// the structural differences between the two CBOR items, empty if they
+/// are equal
+pub fn diff(left: &[u8], right: &[u8]) -> Result<Vec<Difference>, Error> {
+    let mut differences = Vec::new();
+    diff_items(
+        &decode(left)?,
+        &decode(right)?,
+        String::new(),
+        &mut differences,
+    );
+    Ok(differences)
+}
+
+fn diff_items(left: &Item, right: &Item, path: String, differences: &mut Vec<Difference>) {
+    if left == right {
+        return;
+    }
+    match (left, right) {
+        (Item::Array(l), Item::Array(r)) | (Item::IndefiniteArray(l), Item::IndefiniteArray(r)) => {
+            for i in 0..l.len().max(r.len()) {
+                let path = format!("{}[{}]", path, i);
+                match (l.get(i), r.get(i)) {
+                    (Some(l), Some(r)) => diff_items(l, r, path, differences),
+                    (l, r) => differences.push(Difference {
+                        path,
+                        left: l.cloned(),
+                        right: r.cloned(),
+                    }),
+                }
+            }
+        }
+        (Item::Map(l), Item::Map(r)) | (Item::IndefiniteMap(l), Item::IndefiniteMap(r)) => {
+            for (key, value) in l.iter() {
+                let path = format!("{}{{{}}}", path, key);
+                match r.iter().find(|(k, _)| k == key) {
+                    Some((_, other)) => diff_items(value, other, path, differences),
+                    None => differences.push(Difference {
+                        path,
+                        left: Some(value.clone()),
+                        right: None,
+                    }),
+                }
+            }
+            for (key, value) in r.iter() {
+                if !l.iter().any(|(k, _)| k == key) {
+                    differences.push(Difference {
+                        path: format!("{}{{{}}}", path, key),
+                        left: None,
+                        right: Some(value.clone()),
+                    });
+                }
+            }
+        }
+        (Item::Tag(l_tag, l), Item::Tag(r_tag, r)) if l_tag == r_tag => {
+            diff_items(l, r, format!("{}<{}>", path, l_tag), differences)
+        }
+        _ => differences.push(Difference {
+            path,
+            left: Some(left.clone()),
+            right: Some(right.clone()),
+        }),
+    }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+/// the head of an item: its major type and its argument, `None` for the
+/// indefinite lengths and the break
+struct Head {
+    major: u8,
+    additional: u8,
+    arg: Option<u64>,
+}
+
+impl<'a> Decoder<'a> {
+    fn head(&mut self) -> Result<Head, Error> {
+        let offset = self.offset;
+        let initial = *self.bytes.get(offset).ok_or(Error::EndOfInput)?;
+        let major = initial >> 5;
+        let additional = initial & 0x1f;
+        let size = match additional {
+            0..=23 => {
+                self.offset += 1;
+                return Ok(Head {
+                    major,
+                    additional,
+                    arg: Some(additional as u64),
+                });
+            }
+            24 => 1,
+            25 => 2,
+            26 => 4,
+            27 => 8,
+            31 if major >= 2 => {
+                self.offset += 1;
+                return Ok(Head {
+                    major,
+                    additional,
+                    arg: None,
+                });
+            }
+            _ => return Err(Error::InvalidAdditionalInfo(offset)),
+        };
+        let start = offset + 1;
+        if self.bytes.len() - start < size {
+            return Err(Error::EndOfInput);
+        }
+        let arg = self.bytes[start..start + size]
+            .iter()
+            .fold(0u64, |acc, byte| acc << 8 | *byte as u64);
+        self.offset = start + size;
+        Ok(Head {
+            major,
+            additional,
+            arg: Some(arg),
+        })
+    }
+
+    fn slice(&mut self, len: u64) -> Result<&'a [u8], Error> {
+        if len > (self.bytes.len() - self.offset) as u64 {
+            return Err(Error::EndOfInput);
+        }
+        let start = self.offset;
+        self.offset += len as usize;
+        Ok(&self.bytes[start..self.offset])
+    }
+
+    fn text(&mut self, len: u64) -> Result<String, Error> {
+        let offset = self.offset;
+        let bytes = self.slice(len)?;
+        str::from_utf8(bytes)
+            .map(|s| s.to_owned())
+            .map_err(|_| Error::InvalidUtf8(offset))
+    }
+
+    /// true, and the break consumed, if the next item is the break
+    fn is_break(&mut self) -> Result<bool, Error> {
+        match self.bytes.get(self.offset) {
+            None => Err(Error::EndOfInput),
+            Some(0xff) => {
+                self.offset += 1;
+                Ok(true)
+            }
+            Some(_) => Ok(false),
+        }
+    }
+
+    /// the definite length chunk of indefinite length bytes or text
+    fn chunk(&mut self, major: u8) -> Result<u64, Error> {
+        let offset = self.offset;
+        match self.head()? {
+            Head {
+                major: m,
+                arg: Some(len),
+                ..
+            } if m == major => Ok(len),
+            _ => Err(Error::InvalidChunk(offset)),
+        }
+    }
+
+    fn item(&mut self, depth: usize) -> Result<Item, Error> {
+        let offset = self.offset;
+        if depth > MAX_DEPTH {
+            return Err(Error::TooDeep(offset));
+        }
+        let head = self.head()?;
+        let item = match (head.major, head.arg) {
+            (0, Some(n)) => Item::Unsigned(n),
+            (1, Some(n)) => Item::Negative(n),
+            (2, Some(len)) => Item::Bytes(self.slice(len)?.to_vec()),
+            (2, None) => {
+                let mut chunks = Vec::new();
+                while !self.is_break()? {
+                    let len = self.chunk(2)?;
+                    chunks.push(self.slice(len)?.to_vec());
+                }
+                Item::IndefiniteBytes(chunks)
+            }
+            (3, Some(len)) => Item::Text(self.text(len)?),
+            (3, None) => {
+                let mut chunks = Vec::new();
+                while !self.is_break()? {
+                    let len = self.chunk(3)?;
+                    chunks.push(self.text(len)?);
+                }
+                Item::IndefiniteText(chunks)
+            }
+            (4, Some(len)) => {
+                let mut items = Vec::new();
+                for _ in 0..len {
+                    items.push(self.item(depth + 1)?);
+                }
+                Item::Array(items)
+            }
+            (4, None) => {
+                let mut items = Vec::new();
+                while !self.is_break()? {
+                    items.push(self.item(depth + 1)?);
+                }
+                Item::IndefiniteArray(items)
+            }
+            (5, Some(len)) => {
+                let mut entries = Vec::new();
+                for _ in 0..len {
+                    let key = self.item(depth + 1)?;
+                    entries.push((key, self.item(depth + 1)?));
+                }
+                Item::Map(entries)
+            }
+            (5, None) => {
+                let mut entries = Vec::new();
+                while !self.is_break()? {
+                    let key = self.item(depth + 1)?;
+                    entries.push((key, self.item(depth + 1)?));
+                }
+                Item::IndefiniteMap(entries)
+            }
+            (6, Some(tag)) => Item::Tag(tag, Box::new(self.item(depth + 1)?)),
+            (6, None) => return Err(Error::InvalidAdditionalInfo(offset)),
+            (7, None) => return Err(Error::UnexpectedBreak(offset)),
+            (7, Some(arg)) => match head.additional {
+                25 => Item::Float(f16_to_f64(arg as u16)),
+                26 => Item::Float(f32::from_bits(arg as u32) as f64),
+                27 => Item::Float(f64::from_bits(arg)),
+                _ => Item::Simple(arg as u8),
+            },
+            _ => unreachable!(),
+        };
+        Ok(item)
+    }
+}
+
+pub(super) fn f16_to_f64(half: u16) -> f64 {
+    let exponent = (half >> 10) & 0x1f;
+    let mantissa = (half & 0x3ff) as f64;
+    let value = match exponent {
+        0 => mantissa * 2f64.powi(-24),
+        31 if mantissa == 0.0 => ::std::f64::INFINITY,
+        31 => ::std::f64::NAN,
+        _ => (mantissa + 1024.0) * 2f64.powi(exponent as i32 - 25),
+    };
+    if half & 0x8000 != 0 {
+        -value
+    } else {
+        value
+    }
+}
+
+fn write_text(f: &mut dyn fmt::Write, text: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in text.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+impl Item {
+    /// the items which are neither arrays nor maps, rendered on one line
+    /// by `pretty`
+    fn is_nested(&self) -> bool {
+        match self {
+            Item::Array(items) | Item::IndefiniteArray(items) => !items.is_empty(),
+            Item::Map(entries) | Item::IndefiniteMap(entries) => !entries.is_empty(),
+            Item::Tag(_, item) => item.is_nested(),
+            _ => false,
+        }
+    }
+
+    fn pretty(&self, f: &mut dyn fmt::Write, indent: usize) -> fmt::Result {
+        if !self.is_nested() {
+            return write!(f, "{}", self);
+        }
+        let pad = "  ".repeat(indent + 1);
+        match self {
+            Item::Array(items) | Item::IndefiniteArray(items) => {
+                let open = if let Item::Array(_) = self { "[" } else { "[_" };
+                writeln!(f, "{}", open)?;
+                for (i, item) in items.iter().enumerate() {
+                    f.write_str(&pad)?;
+                    item.pretty(f, indent + 1)?;
+                    writeln!(f, "{}", if i + 1 < items.len() { "," } else { "" })?;
+                }
+                write!(f, "{}]", "  ".repeat(indent))
+            }
+            Item::Map(entries) | Item::IndefiniteMap(entries) => {
+                let open = if let Item::Map(_) = self { "{" } else { "{_" };
+                writeln!(f, "{}", open)?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    f.write_str(&pad)?;
+                    key.pretty(f, indent + 1)?;
+                    f.write_str(": ")?;
+                    value.pretty(f, indent + 1)?;
+                    writeln!(f, "{}", if i + 1 < entries.len() { "," } else { "" })?;
+                }
+                write!(f, "{}}}", "  ".repeat(indent))
+            }
+            Item::Tag(tag, item) => {
+                write!(f, "{}(", tag)?;
+                item.pretty(f, indent)?;
+                f.write_str(")")
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl fmt::Display for Item {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn list<T, F>(f: &mut fmt::Formatter, items: &[T], write_item: F) -> fmt::Result
+        where
+            F: Fn(&mut fmt::Formatter, &T) -> fmt::Result,
+        {
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    f.write_str(", ")?;
+                }
+                write_item(f, item)?;
+            }
+            Ok(())
+        }
+
+        match self {
+            Item::Unsigned(n) => write!(f, "{}", n),
+            Item::Negative(n) => write!(f, "{}", -1 - *n as i128),
+            Item::Bytes(bytes) => write!(f, "h'{}'", hex::encode(bytes)),
+            Item::IndefiniteBytes(chunks) => {
+                f.write_str("(_ ")?;
+                list(f, chunks, |f, chunk| write!(f, "h'{}'", hex::encode(chunk)))?;
+                f.write_str(")")
+            }
+            Item::Text(text) => write_text(f, text),
+            Item::IndefiniteText(chunks) => {
+                f.write_str("(_ ")?;
+                list(f, chunks, |f, chunk| write_text(f, chunk))?;
+                f.write_str(")")
+            }
+            Item::Array(items) => {
+                f.write_str("[")?;
+                list(f, items, |f, item| write!(f, "{}", item))?;
+                f.write_str("]")
+            }
+            Item::IndefiniteArray(items) => {
+                f.write_str("[_ ")?;
+                list(f, items, |f, item| write!(f, "{}", item))?;
+                f.write_str("]")
+            }
+            Item::Map(entries) => {
+                f.write_str("{")?;
+                list(f, entries, |f, (k, v)| write!(f, "{}: {}", k, v))?;
+                f.write_str("}")
+            }
+            Item::IndefiniteMap(entries) => {
+                f.write_str("{_ ")?;
+                list(f, entries, |f, (k, v)| write!(f, "{}: {}", k, v))?;
+                f.write_str("}")
+            }
+            Item::Tag(tag, item) => write!(f, "{}({})", tag, item),
+            Item::Simple(20) => f.write_str("false"),
+            Item::Simple(21) => f.write_str("true"),
+            Item::Simple(22) => f.write_str("null"),
+            Item::Simple(23) => f.write_str("undefined"),
+            Item::Simple(n) => write!(f, "simple({})", n),
+            Item::Float(v) if v.is_nan() => f.write_str("NaN"),
+            Item::Float(v) if v.is_infinite() => {
+                f.write_str(if *v > 0.0 { "Infinity" } else { "-Infinity" })
+            }
+            Item::Float(v) => write!(f, "{:?}", v),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diagnostic_rfc7049() {
+        // examples of the RFC 7049 appendix A
+        let vectors: &[(&[u8], &str)] = &[
+            (&[0x00], "0"),
+            (
+                &[0x1b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+                "18446744073709551615",
+            ),
+            (
+                &[0x3b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+                "-18446744073709551616",
+            ),
+            (&[0x38, 0x63], "-100"),
+            (&[0xf9, 0x3c, 0x00], "1.0"),
+            (&[0xf9, 0xc4, 0x00], "-4.0"),
+            (&[0xfa, 0x47, 0xc3, 0x50, 0x00], "100000.0"),
+            (&[0xf9, 0x7c, 0x00], "Infinity"),
+            (&[0xf9, 0x7e, 0x00], "NaN"),
+            (&[0xf4], "false"),
+            (&[0xf6], "null"),
+            (&[0xf8, 0xff], "simple(255)"),
+            (&[0xc1, 0x1a, 0x51, 0x4b, 0x67, 0xb0], "1(1363896240)"),
+            (&[0x44, 0x01, 0x02, 0x03, 0x04], "h'01020304'"),
+            (&[0x62, 0x22, 0x5c], "\"\\\"\\\\\""),
+            (
+                &[0x83, 0x01, 0x82, 0x02, 0x03, 0x82, 0x04, 0x05],
+                "[1, [2, 3], [4, 5]]",
+            ),
+            (
+                &[0xa2, 0x61, 0x61, 0x01, 0x61, 0x62, 0x82, 0x02, 0x03],
+                "{\"a\": 1, \"b\": [2, 3]}",
+            ),
+            (
+                &[0x5f, 0x42, 0x01, 0x02, 0x43, 0x03, 0x04, 0x05, 0xff],
+                "(_ h'0102', h'030405')",
+            ),
+            (
+                &[
+                    0x7f, 0x65, 0x73, 0x74, 0x72, 0x65, 0x61, 0x64, 0x6d, 0x69, 0x6e, 0x67, 0xff,
+                ],
+                "(_ \"strea\", \"ming\")",
+            ),
+            (
+                &[0x9f, 0x01, 0x82, 0x02, 0x03, 0x9f, 0x04, 0x05, 0xff, 0xff],
+                "[_ 1, [2, 3], [_ 4, 5]]",
+            ),
+            (
+                &[
+                    0xbf, 0x63, 0x46, 0x75, 0x6e, 0xf5, 0x63, 0x41, 0x6d, 0x74, 0x21, 0xff,
+                ],
+                "{_ \"Fun\": true, \"Amt\": -2}",
+            ),
+        ];
+        for (bytes, expected) in vectors {
+            assert_eq!(&diagnostic(bytes).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn invalid_items() {
+        assert_eq!(decode(&[]), Err(Error::EndOfInput));
+        assert_eq!(decode(&[0x01, 0x01]), Err(Error::TrailingBytes(1)));
+        assert_eq!(decode(&[0x1c]), Err(Error::InvalidAdditionalInfo(0)));
+        assert_eq!(decode(&[0x1f]), Err(Error::InvalidAdditionalInfo(0)));
+        assert_eq!(decode(&[0x81, 0xff]), Err(Error::UnexpectedBreak(1)));
+        assert_eq!(decode(&[0x62, 0xc3, 0x28]), Err(Error::InvalidUtf8(1)));
+        assert_eq!(decode(&[0x5f, 0x01, 0xff]), Err(Error::InvalidChunk(1)));
+        assert_eq!(decode(&[0x9f, 0x01]), Err(Error::EndOfInput));
+        assert_eq!(
+            decode(&[0x81; MAX_DEPTH + 2]),
+            Err(Error::TooDeep(MAX_DEPTH + 1))
+        );
+    }
+
+    #[test]
+    fn pretty_print() {
+        let bytes = [0x82, 0xd8, 0x18, 0x41, 0x00, 0xa1, 0x01, 0x82, 0x80, 0x02];
+        assert_eq!(
+            pretty(&bytes).unwrap(),
+            "[\n  24(h'00'),\n  {\n    1: [\n      [],\n      2\n    ]\n  }\n]"
+        );
+        assert_eq!(pretty(&[0x01]).unwrap(), "1");
+    }
+
+    #[test]
+    fn diff_equal() {
+        let bytes = [0x83, 0x01, 0xa1, 0x01, 0x02, 0xc1, 0x00];
+        assert!(diff(&bytes, &bytes).unwrap().is_empty());
+    }
+
+    #[test]
+    fn diff_items() {
+        // [1, {1: 2, 2: 3}, 24(h'00')] and [1, {1: 4, 3: 3}, 24(h'01'), 5]
+        let left = [
+            0x83, 0x01, 0xa2, 0x01, 0x02, 0x02, 0x03, 0xd8, 0x18, 0x41, 0x00,
+        ];
+        let right = [
+            0x84, 0x01, 0xa2, 0x01, 0x04, 0x03, 0x03, 0xd8, 0x18, 0x41, 0x01, 0x05,
+        ];
+        let differences: Vec<String> = diff(&left, &right)
+            .unwrap()
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(
+            differences,
+            vec![
+                "[1]{1}: 2 -> 4",
+                "[1]{2}: 3 -> (none)",
+                "[1]{3}: (none) -> 3",
+                "[2]<24>: h'00' -> h'01'",
+                "[3]: (none) -> 5",
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_encoding() {
+        // the same values, with definite and indefinite lengths
+        let differences = diff(&[0x81, 0x01], &[0x9f, 0x01, 0xff]).unwrap();
+        assert_eq!(differences.len(), 1);
+        assert_eq!(differences[0].to_string(), "$: [1] -> [_ 1]");
+    }
+}
@@ -1 +1,7 @@
+pub mod canonical;
+pub mod dump;
 pub mod hs;
+#[cfg(feature = "generic-serialization")]
+pub mod serde;
+pub mod stream;
+pub mod tags;
@@ -0,0 +1,712 @@
+//! serde adapters over `cbor_event`
+//!
+//! Allow any type implementing `serde::Serialize` / `serde::Deserialize`
+//! to be written and read as CBOR without a hand written
+//! `cbor_event::Serialize` / `cbor_event::Deserialize` implementation.
+//!
+//! The serde data model is mapped as follow:
+//!
+//! * booleans and unit are the `true`/`false` and `null` specials;
+//! * `None` is an empty array and `Some` a single element array, so
+//!   `Some(())` or `Some(None)` are not mistaken for `None`;
+//! * integers are unsigned or negative integers, floats are floats;
+//! * strings and chars are text, bytes are bytes;
+//! * sequences and tuples are arrays, maps and structs are maps (the
+//!   struct fields being text keys);
+//! * enum variants are their name as text if they have no content,
+//!   otherwise a single entry map from their name to their content.
+//!
+//! Deserializing rejects the items nested deeper than `MAX_DEPTH`, so an
+//! input cannot overflow the stack.
+//!
+//! ```
+//! # extern crate cardano;
+//! # use cardano::cbor::serde::{from_slice, to_vec};
+//! let bytes = to_vec(&(1u32, "text".to_owned())).unwrap();
+//! let decoded: (u32, String) = from_slice(&bytes).unwrap();
+//! assert_eq!(decoded, (1, "text".to_owned()));
+//! ```
+
+use cbor_event::{self, de, se, Len, Special, Type};
+use serde::de::{
+    DeserializeSeed, EnumAccess, IntoDeserializer, MapAccess, SeqAccess, VariantAccess, Visitor,
+};
+use serde::{ser, Deserialize, Serialize};
+use std::io::{Cursor, Write};
+use std::{error, fmt};
+
+/// maximum nesting of the arrays, maps and tags of a deserialized item
+pub const MAX_DEPTH: usize = 128;
+
+#[derive(Debug)]
+pub enum Error {
+    Cbor(cbor_event::Error),
+    TrailingBytes(usize),
+    DepthLimitExceeded,
+    Custom(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Cbor(error) => write!(f, "{}", error),
+            Error::TrailingBytes(offset) => write!(f, "trailing bytes at offset {}", offset),
+            Error::DepthLimitExceeded => {
+                write!(f, "items nested deeper than {} levels", MAX_DEPTH)
+            }
+            Error::Custom(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+impl error::Error for Error {}
+
+impl From<cbor_event::Error> for Error {
+    fn from(e: cbor_event::Error) -> Self {
+        Error::Cbor(e)
+    }
+}
+
+impl ser::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Custom(msg.to_string())
+    }
+}
+
+impl ::serde::de::Error for Error {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        Error::Custom(msg.to_string())
+    }
+}
+
+pub type Result<T> = ::std::result::Result<T, Error>;
+
+/// serialize the given value as CBOR
+pub fn to_vec<T: ?Sized + Serialize>(value: &T) -> Result<Vec<u8>> {
+    let mut serializer = Serializer::new(se::Serializer::new_vec());
+    value.serialize(&mut serializer)?;
+    Ok(serializer.into_inner().finalize())
+}
+
+/// deserialize a value from the given CBOR bytes, which must not contain
+/// anything else
+pub fn from_slice<'a, T: Deserialize<'a>>(bytes: &'a [u8]) -> Result<T> {
+    let mut deserializer = Deserializer::new(bytes);
+    let value = T::deserialize(&mut deserializer)?;
+    let end = deserializer.position();
+    if end != bytes.len() {
+        return Err(Error::TrailingBytes(end));
+    }
+    Ok(value)
+}
+
+/// serde `Serializer` writing into a `cbor_event` serializer
+pub struct Serializer<W: Write>(se::Serializer<W>);
+
+impl<W: Write> Serializer<W> {
+    pub fn new(serializer: se::Serializer<W>) -> Self {
+        Serializer(serializer)
+    }
+
+    pub fn into_inner(self) -> se::Serializer<W> {
+        self.0
+    }
+
+    fn write_variant(&mut self, variant: &'static str) -> Result<()> {
+        self.0.write_map(Len::Len(1))?.write_text(variant)?;
+        Ok(())
+    }
+
+    fn compound(&mut self, len: Option<usize>, map: bool) -> Result<Compound<W>> {
+        let cbor_len = len.map_or(Len::Indefinite, |len| Len::Len(len as u64));
+        if map {
+            self.0.write_map(cbor_len)?;
+        } else {
+            self.0.write_array(cbor_len)?;
+        }
+        Ok(Compound {
+            serializer: self,
+            indefinite: len.is_none(),
+        })
+    }
+}
+
+impl<'a, W: Write> ser::Serializer for &'a mut Serializer<W> {
+    type Ok = ();
+    type Error = Error;
+    type SerializeSeq = Compound<'a, W>;
+    type SerializeTuple = Compound<'a, W>;
+    type SerializeTupleStruct = Compound<'a, W>;
+    type SerializeTupleVariant = Compound<'a, W>;
+    type SerializeMap = Compound<'a, W>;
+    type SerializeStruct = Compound<'a, W>;
+    type SerializeStructVariant = Compound<'a, W>;
+
+    fn serialize_bool(self, v: bool) -> Result<()> {
+        self.0.write_special(Special::Bool(v))?;
+        Ok(())
+    }
+    fn serialize_i8(self, v: i8) -> Result<()> {
+        self.serialize_i64(v as i64)
+    }
+    fn serialize_i16(self, v: i16) -> Result<()> {
+        self.serialize_i64(v as i64)
+    }
+    fn serialize_i32(self, v: i32) -> Result<()> {
+        self.serialize_i64(v as i64)
+    }
+    fn serialize_i64(self, v: i64) -> Result<()> {
+        if v < 0 {
+            self.0.write_negative_integer(v)?;
+        } else {
+            self.0.write_unsigned_integer(v as u64)?;
+        }
+        Ok(())
+    }
+    fn serialize_u8(self, v: u8) -> Result<()> {
+        self.serialize_u64(v as u64)
+    }
+    fn serialize_u16(self, v: u16) -> Result<()> {
+        self.serialize_u64(v as u64)
+    }
+    fn serialize_u32(self, v: u32) -> Result<()> {
+        self.serialize_u64(v as u64)
+    }
+    fn serialize_u64(self, v: u64) -> Result<()> {
+        self.0.write_unsigned_integer(v)?;
+        Ok(())
+    }
+    fn serialize_f32(self, v: f32) -> Result<()> {
+        self.serialize_f64(v as f64)
+    }
+    fn serialize_f64(self, v: f64) -> Result<()> {
+        self.0.write_special(Special::Float(v))?;
+        Ok(())
+    }
+    fn serialize_char(self, v: char) -> Result<()> {
+        self.serialize_str(&v.to_string())
+    }
+    fn serialize_str(self, v: &str) -> Result<()> {
+        self.0.write_text(v)?;
+        Ok(())
+    }
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        self.0.write_bytes(v)?;
+        Ok(())
+    }
+    fn serialize_none(self) -> Result<()> {
+        self.0.write_array(Len::Len(0))?;
+        Ok(())
+    }
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<()> {
+        self.0.write_array(Len::Len(1))?;
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<()> {
+        self.0.write_special(Special::Null)?;
+        Ok(())
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+        self.serialize_unit()
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<()> {
+        self.serialize_str(variant)
+    }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.write_variant(variant)?;
+        value.serialize(self)
+    }
+    fn serialize_seq(self, len: Option<usize>) -> Result<Compound<'a, W>> {
+        self.compound(len, false)
+    }
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'a, W>> {
+        self.compound(Some(len), false)
+    }
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Compound<'a, W>> {
+        self.compound(Some(len), false)
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a, W>> {
+        self.write_variant(variant)?;
+        self.compound(Some(len), false)
+    }
+    fn serialize_map(self, len: Option<usize>) -> Result<Compound<'a, W>> {
+        self.compound(len, true)
+    }
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Compound<'a, W>> {
+        self.compound(Some(len), true)
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> Result<Compound<'a, W>> {
+        self.write_variant(variant)?;
+        self.compound(Some(len), true)
+    }
+}
+
+/// serializer of the elements of an array or a map
+pub struct Compound<'a, W: Write + 'a> {
+    serializer: &'a mut Serializer<W>,
+    indefinite: bool,
+}
+
+impl<'a, W: Write> Compound<'a, W> {
+    fn element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        value.serialize(&mut *self.serializer)
+    }
+
+    fn end(self) -> Result<()> {
+        if self.indefinite {
+            self.serializer.0.write_special(Special::Break)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, W: Write> ser::SerializeSeq for Compound<'a, W> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+    fn end(self) -> Result<()> {
+        Compound::end(self)
+    }
+}
+
+impl<'a, W: Write> ser::SerializeTuple for Compound<'a, W> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+    fn end(self) -> Result<()> {
+        Compound::end(self)
+    }
+}
+
+impl<'a, W: Write> ser::SerializeTupleStruct for Compound<'a, W> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+    fn end(self) -> Result<()> {
+        Compound::end(self)
+    }
+}
+
+impl<'a, W: Write> ser::SerializeTupleVariant for Compound<'a, W> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+    fn end(self) -> Result<()> {
+        Compound::end(self)
+    }
+}
+
+impl<'a, W: Write> ser::SerializeMap for Compound<'a, W> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<()> {
+        self.element(key)
+    }
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<()> {
+        self.element(value)
+    }
+    fn end(self) -> Result<()> {
+        Compound::end(self)
+    }
+}
+
+impl<'a, W: Write> ser::SerializeStruct for Compound<'a, W> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.element(key)?;
+        self.element(value)
+    }
+    fn end(self) -> Result<()> {
+        Compound::end(self)
+    }
+}
+
+impl<'a, W: Write> ser::SerializeStructVariant for Compound<'a, W> {
+    type Ok = ();
+    type Error = Error;
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<()> {
+        self.element(key)?;
+        self.element(value)
+    }
+    fn end(self) -> Result<()> {
+        Compound::end(self)
+    }
+}
+
+/// serde `Deserializer` reading from a `cbor_event` deserializer over
+/// a slice, along with the depth of the item being read
+pub struct Deserializer<'de>(de::Deserializer<Cursor<&'de [u8]>>, usize);
+
+impl<'de> Deserializer<'de> {
+    pub fn new(bytes: &'de [u8]) -> Self {
+        Deserializer(de::Deserializer::from(Cursor::new(bytes)), 0)
+    }
+
+    /// deserialize the content of an array, a map or a tag, one level
+    /// deeper
+    fn nested<T, F>(&mut self, f: F) -> Result<T>
+    where
+        F: FnOnce(&mut Self) -> Result<T>,
+    {
+        if self.1 == MAX_DEPTH {
+            return Err(Error::DepthLimitExceeded);
+        }
+        self.1 += 1;
+        let result = f(self);
+        self.1 -= 1;
+        result
+    }
+
+    /// offset of the next item to read
+    pub fn position(&self) -> usize {
+        self.0.as_ref().position() as usize
+    }
+
+    fn peek_byte(&self) -> Option<u8> {
+        self.0.as_ref().get_ref().get(self.position()).cloned()
+    }
+
+    /// consume the break ending an indefinite length item if it is next
+    fn special_break(&mut self) -> Result<bool> {
+        if self.peek_byte() == Some(0xff) {
+            self.0.special()?;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+}
+
+impl<'de, 'a> ::serde::Deserializer<'de> for &'a mut Deserializer<'de> {
+    type Error = Error;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.0.cbor_type()? {
+            Type::UnsignedInteger => visitor.visit_u64(self.0.unsigned_integer()?),
+            Type::NegativeInteger => visitor.visit_i64(self.0.negative_integer()?),
+            Type::Bytes => visitor.visit_byte_buf(self.0.bytes()?),
+            Type::Text => visitor.visit_string(self.0.text()?),
+            Type::Array => {
+                let len = self.0.array()?;
+                self.nested(|de| visitor.visit_seq(Access::new(de, len)))
+            }
+            Type::Map => {
+                let len = self.0.map()?;
+                self.nested(|de| visitor.visit_map(Access::new(de, len)))
+            }
+            Type::Tag => {
+                self.0.tag()?;
+                self.nested(|de| ::serde::Deserializer::deserialize_any(de, visitor))
+            }
+            Type::Special => match self.0.special()? {
+                Special::Bool(b) => visitor.visit_bool(b),
+                Special::Null | Special::Undefined => visitor.visit_unit(),
+                Special::Float(f) => visitor.visit_f64(f),
+                special => Err(Error::Custom(format!("unexpected {:?}", special))),
+            },
+        }
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value> {
+        match self.0.array()? {
+            Len::Len(0) => visitor.visit_none(),
+            Len::Len(1) => self.nested(|de| visitor.visit_some(de)),
+            len => Err(Error::Custom(format!(
+                "expected empty or single element array for option, found {:?}",
+                len
+            ))),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        match self.0.cbor_type()? {
+            Type::Text => visitor.visit_enum(self.0.text()?.into_deserializer()),
+            Type::Map => {
+                let len = self.0.map()?;
+                if len != Len::Len(1) {
+                    return Err(Error::Custom(format!(
+                        "expected single entry map for enum variant, found {:?}",
+                        len
+                    )));
+                }
+                self.nested(|de| visitor.visit_enum(de))
+            }
+            t => Err(Error::Custom(format!(
+                "expected enum variant, found {:?}",
+                t
+            ))),
+        }
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+impl<'de, 'a> EnumAccess<'de> for &'a mut Deserializer<'de> {
+    type Error = Error;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self)> {
+        let variant = seed.deserialize(&mut *self)?;
+        Ok((variant, self))
+    }
+}
+
+impl<'de, 'a> VariantAccess<'de> for &'a mut Deserializer<'de> {
+    type Error = Error;
+
+    fn unit_variant(self) -> Result<()> {
+        ::serde::de::IgnoredAny::deserialize(self).map(|_| ())
+    }
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value> {
+        seed.deserialize(self)
+    }
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value> {
+        ::serde::Deserializer::deserialize_any(self, visitor)
+    }
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value> {
+        ::serde::Deserializer::deserialize_any(self, visitor)
+    }
+}
+
+/// accessor to the elements of an array or a map
+struct Access<'a, 'de: 'a> {
+    deserializer: &'a mut Deserializer<'de>,
+    len: Len,
+    index: u64,
+}
+
+impl<'a, 'de> Access<'a, 'de> {
+    fn new(deserializer: &'a mut Deserializer<'de>, len: Len) -> Self {
+        Access {
+            deserializer,
+            len,
+            index: 0,
+        }
+    }
+
+    fn has_next(&mut self) -> Result<bool> {
+        let has_next = match self.len {
+            Len::Len(len) => self.index < len,
+            Len::Indefinite => !self.deserializer.special_break()?,
+        };
+        self.index += 1;
+        Ok(has_next)
+    }
+}
+
+impl<'a, 'de> SeqAccess<'de> for Access<'a, 'de> {
+    type Error = Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>> {
+        if !self.has_next()? {
+            return Ok(None);
+        }
+        seed.deserialize(&mut *self.deserializer).map(Some)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.len {
+            Len::Len(len) => Some(len.saturating_sub(self.index) as usize),
+            Len::Indefinite => None,
+        }
+    }
+}
+
+impl<'a, 'de> MapAccess<'de> for Access<'a, 'de> {
+    type Error = Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>> {
+        if !self.has_next()? {
+            return Ok(None);
+        }
+        seed.deserialize(&mut *self.deserializer).map(Some)
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value> {
+        seed.deserialize(&mut *self.deserializer)
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        match self.len {
+            Len::Len(len) => Some(len.saturating_sub(self.index) as usize),
+            Len::Indefinite => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    enum Kind {
+        Unit,
+        Newtype(u8),
+        Tuple(i32, bool),
+        Struct { name: String },
+    }
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Record {
+        id: u64,
+        delta: i64,
+        ratio: f64,
+        label: Option<String>,
+        missing: Option<u32>,
+        kinds: Vec<Kind>,
+        attributes: BTreeMap<String, Vec<u8>>,
+        pair: (char, ()),
+    }
+
+    #[test]
+    fn roundtrip() {
+        let mut attributes = BTreeMap::new();
+        attributes.insert("a".to_owned(), vec![1, 2, 3]);
+        let record = Record {
+            id: 1_000_000,
+            delta: -42,
+            ratio: 0.5,
+            label: Some("label".to_owned()),
+            missing: None,
+            kinds: vec![
+                Kind::Unit,
+                Kind::Newtype(7),
+                Kind::Tuple(-1, true),
+                Kind::Struct {
+                    name: "name".to_owned(),
+                },
+            ],
+            attributes,
+            pair: ('x', ()),
+        };
+        let bytes = to_vec(&record).unwrap();
+        let decoded: Record = from_slice(&bytes).unwrap();
+        assert_eq!(decoded, record);
+    }
+
+    #[test]
+    fn encoding() {
+        assert_eq!(to_vec(&10u8).unwrap(), vec![0x0a]);
+        assert_eq!(to_vec(&-1i32).unwrap(), vec![0x20]);
+        assert_eq!(
+            to_vec(&Kind::Unit).unwrap(),
+            vec![0x64, b'U', b'n', b'i', b't']
+        );
+        assert_eq!(
+            to_vec(&Kind::Newtype(1)).unwrap(),
+            vec![0xa1, 0x67, b'N', b'e', b'w', b't', b'y', b'p', b'e', 0x01]
+        );
+        assert_eq!(to_vec(&vec![1u8, 2]).unwrap(), vec![0x82, 0x01, 0x02]);
+    }
+
+    #[test]
+    fn indefinite_array() {
+        let decoded: Vec<u32> = from_slice(&[0x9f, 0x01, 0x02, 0xff]).unwrap();
+        assert_eq!(decoded, vec![1, 2]);
+    }
+
+    #[test]
+    fn options() {
+        assert_eq!(to_vec(&None::<()>).unwrap(), vec![0x80]);
+        assert_eq!(to_vec(&Some(())).unwrap(), vec![0x81, 0xf6]);
+        assert_eq!(to_vec(&Some(None::<u8>)).unwrap(), vec![0x81, 0x80]);
+
+        let options = vec![None, Some(None), Some(Some(()))];
+        let bytes = to_vec(&options).unwrap();
+        let decoded: Vec<Option<Option<()>>> = from_slice(&bytes).unwrap();
+        assert_eq!(decoded, options);
+
+        assert!(from_slice::<Option<u8>>(&[0x82, 0x01, 0x02]).is_err());
+        assert!(from_slice::<Option<u8>>(&[0xf6]).is_err());
+    }
+
+    #[test]
+    fn depth_limit() {
+        use serde::de::IgnoredAny;
+
+        let mut nested = vec![0x81; MAX_DEPTH];
+        nested.push(0x00);
+        assert!(from_slice::<IgnoredAny>(&nested).is_ok());
+
+        for item in [0x81, 0xa1, 0xc1].iter() {
+            let mut nested = vec![*item; 100_000];
+            nested.push(0x00);
+            match from_slice::<IgnoredAny>(&nested) {
+                Err(Error::DepthLimitExceeded) => {}
+                result => panic!("expected the depth limit, got {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn trailing_bytes() {
+        assert!(from_slice::<u32>(&[0x01, 0x02]).is_err());
+    }
+}
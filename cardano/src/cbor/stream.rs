@@ -0,0 +1,550 @@
+//! Pull-based reader of CBOR events
+//!
+//! `cbor_event::de::Deserializer` decodes typed values from a buffer. The
+//! `Reader` of this module consumes the input from any `Read` instead, and
+//! yields the items one event at a time: the start of the arrays, maps and
+//! indefinite length strings, the tags, the primitive values and the
+//! breaks. Only the current byte or text string is held in memory and its
+//! length is capped, so a multi-megabyte blob (e.g. a block of an epoch
+//! pack) can be walked through with a bounded memory.
+//!
+//! The reader keeps track of the nesting of the items: the end of the input
+//! is only accepted between two top level items, a break is only accepted
+//! at the end of an indefinite length item.
+//!
+//! The input is read a few bytes at a time, an unbuffered source (e.g. a
+//! `File`) is better wrapped in a `BufReader`.
+//!
+//! ```
+//! use cardano::cbor::stream::{Event, Reader};
+//!
+//! let bytes = [0x82, 0x01, 0x43, 0x01, 0x02, 0x03];
+//! let mut reader = Reader::new(&bytes[..]);
+//!
+//! assert_eq!(reader.next_event().unwrap(), Some(Event::Array(Some(2))));
+//! assert_eq!(reader.next_event().unwrap(), Some(Event::Unsigned(1)));
+//! assert_eq!(reader.next_event().unwrap(), Some(Event::Bytes(vec![1, 2, 3])));
+//! assert_eq!(reader.next_event().unwrap(), None);
+//! ```
+//!
+
+use super::canonical::MAX_DEPTH;
+use super::dump::f16_to_f64;
+use std::io::{self, Read};
+use std::{error, fmt};
+
+/// default maximum length of the byte and text strings, and of the raw
+/// items read with `Reader::raw_item`
+pub const DEFAULT_MAX_LEN: usize = 20_000_000;
+
+/// an event of the input, see `Reader::next_event`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    Unsigned(u64),
+    /// the negative integer `-1 - n`
+    Negative(u64),
+    /// definite length bytes, or a chunk of indefinite length bytes
+    Bytes(Vec<u8>),
+    /// definite length text, or a chunk of indefinite length text
+    Text(String),
+    /// start of indefinite length bytes, followed by the chunks and a break
+    IndefiniteBytes,
+    /// start of indefinite length text, followed by the chunks and a break
+    IndefiniteText,
+    /// start of an array of the given number of items, `None` if the array
+    /// is of indefinite length and ends with a break
+    Array(Option<u64>),
+    /// start of a map of the given number of entries, `None` if the map is
+    /// of indefinite length and ends with a break
+    Map(Option<u64>),
+    /// a tag, followed by the tagged item
+    Tag(u64),
+    Simple(u8),
+    Float(f64),
+    /// the end of an indefinite length item
+    Break,
+}
+
+/// reason why the input cannot be read, with the offset of the offending
+/// item
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    /// the input ends within an item
+    EndOfInput(u64),
+    InvalidAdditionalInfo(u64),
+    InvalidUtf8(u64),
+    InvalidChunk(u64),
+    UnexpectedBreak(u64),
+    TooDeep(u64),
+    /// a string or a raw item longer than the maximum length of the reader
+    TooLong(u64, u64),
+    /// the item is not the one expected by the caller
+    Unexpected(u64, &'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(_) => write!(f, "I/O error"),
+            Error::EndOfInput(offset) => write!(f, "unexpected end of input at offset {}", offset),
+            Error::InvalidAdditionalInfo(offset) => {
+                write!(f, "invalid additional information at offset {}", offset)
+            }
+            Error::InvalidUtf8(offset) => write!(f, "invalid UTF-8 text at offset {}", offset),
+            Error::InvalidChunk(offset) => {
+                write!(f, "invalid indefinite length chunk at offset {}", offset)
+            }
+            Error::UnexpectedBreak(offset) => write!(f, "unexpected break at offset {}", offset),
+            Error::TooDeep(offset) => write!(f, "nesting too deep at offset {}", offset),
+            Error::TooLong(offset, len) => {
+                write!(f, "item of {} bytes too long at offset {}", len, offset)
+            }
+            Error::Unexpected(offset, expected) => {
+                write!(f, "expected {} at offset {}", expected, offset)
+            }
+        }
+    }
+}
+impl error::Error for Error {
+    fn cause(&self) -> Option<&error::Error> {
+        match self {
+            Error::Io(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<Error> for ::cbor_event::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(err) => ::cbor_event::Error::IoError(err),
+            err => ::cbor_event::Error::CustomError(err.to_string()),
+        }
+    }
+}
+
+/// the items still expected by an item being read
+enum Pending {
+    /// the remaining items of a definite length array or map, or the
+    /// item of a tag
+    Items(u64),
+    /// the items of an indefinite length array or map, up to the break
+    Indefinite,
+    /// the chunks of indefinite length bytes or text (of the given major
+    /// type), up to the break
+    Chunks(u8),
+}
+
+pub struct Reader<R> {
+    reader: R,
+    offset: u64,
+    max_len: usize,
+    pending: Vec<Pending>,
+    /// the bytes read since the start of `raw_item`
+    raw: Option<Vec<u8>>,
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(reader: R) -> Self {
+        Reader {
+            reader,
+            offset: 0,
+            max_len: DEFAULT_MAX_LEN,
+            pending: Vec::new(),
+            raw: None,
+        }
+    }
+
+    /// set the maximum length of the byte and text strings, and of the raw
+    /// items read with `raw_item`
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// the number of bytes read so far
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    /// whether the reader is between two top level items
+    pub fn is_at_top_level(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+        let offset = self.offset;
+        self.reader.read_exact(buf).map_err(|err| {
+            if err.kind() == io::ErrorKind::UnexpectedEof {
+                Error::EndOfInput(offset)
+            } else {
+                Error::Io(err)
+            }
+        })?;
+        self.offset += buf.len() as u64;
+        if let Some(raw) = self.raw.as_mut() {
+            if raw.len() + buf.len() > self.max_len {
+                return Err(Error::TooLong(offset, (raw.len() + buf.len()) as u64));
+            }
+            raw.extend_from_slice(buf);
+        }
+        Ok(())
+    }
+
+    /// the first byte of the next item, `None` at the end of the input
+    fn initial_byte(&mut self) -> Result<Option<u8>, Error> {
+        let mut byte = [0u8];
+        loop {
+            match self.reader.read(&mut byte) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(ref err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(Error::Io(err)),
+            }
+        }
+        self.offset += 1;
+        if let Some(raw) = self.raw.as_mut() {
+            if raw.len() >= self.max_len {
+                return Err(Error::TooLong(self.offset - 1, raw.len() as u64 + 1));
+            }
+            raw.push(byte[0]);
+        }
+        Ok(Some(byte[0]))
+    }
+
+    fn argument(&mut self, size: usize) -> Result<u64, Error> {
+        let mut buf = [0u8; 8];
+        self.read_exact(&mut buf[..size])?;
+        Ok(buf[..size]
+            .iter()
+            .fold(0u64, |acc, byte| acc << 8 | *byte as u64))
+    }
+
+    fn string(&mut self, len: u64, offset: u64) -> Result<Vec<u8>, Error> {
+        if len > self.max_len as u64 {
+            return Err(Error::TooLong(offset, len));
+        }
+        let mut bytes = vec![0; len as usize];
+        self.read_exact(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// an item was fully read, update the items pending
+    fn complete(&mut self) {
+        loop {
+            match self.pending.last_mut() {
+                Some(Pending::Items(ref mut remaining)) => {
+                    *remaining -= 1;
+                    if *remaining > 0 {
+                        return;
+                    }
+                }
+                _ => return,
+            }
+            self.pending.pop();
+        }
+    }
+
+    /// start an item made of the given number of items
+    fn open(&mut self, pending: Pending, offset: u64) -> Result<(), Error> {
+        match pending {
+            Pending::Items(0) => {
+                self.complete();
+                Ok(())
+            }
+            pending => {
+                if self.pending.len() >= MAX_DEPTH {
+                    return Err(Error::TooDeep(offset));
+                }
+                self.pending.push(pending);
+                Ok(())
+            }
+        }
+    }
+
+    /// read the next event, `None` at the end of the input between two top
+    /// level items
+    pub fn next_event(&mut self) -> Result<Option<Event>, Error> {
+        let offset = self.offset;
+        let initial = match self.initial_byte()? {
+            Some(initial) => initial,
+            None if self.pending.is_empty() => return Ok(None),
+            None => return Err(Error::EndOfInput(offset)),
+        };
+        let major = initial >> 5;
+        let additional = initial & 0x1f;
+        let arg = match additional {
+            0..=23 => Some(additional as u64),
+            24 => Some(self.argument(1)?),
+            25 => Some(self.argument(2)?),
+            26 => Some(self.argument(4)?),
+            27 => Some(self.argument(8)?),
+            31 if major >= 2 && major != 6 => None,
+            _ => return Err(Error::InvalidAdditionalInfo(offset)),
+        };
+
+        if let Some(Pending::Chunks(chunk_major)) = self.pending.last() {
+            match (major, arg) {
+                (7, None) => {}
+                (m, Some(_)) if m == *chunk_major => {}
+                _ => return Err(Error::InvalidChunk(offset)),
+            }
+        }
+
+        let event = match (major, arg) {
+            (0, Some(n)) => Event::Unsigned(n),
+            (1, Some(n)) => Event::Negative(n),
+            (2, Some(len)) => Event::Bytes(self.string(len, offset)?),
+            (3, Some(len)) => {
+                let bytes = self.string(len, offset)?;
+                Event::Text(String::from_utf8(bytes).map_err(|_| Error::InvalidUtf8(offset))?)
+            }
+            (2, None) => {
+                self.open(Pending::Chunks(2), offset)?;
+                return Ok(Some(Event::IndefiniteBytes));
+            }
+            (3, None) => {
+                self.open(Pending::Chunks(3), offset)?;
+                return Ok(Some(Event::IndefiniteText));
+            }
+            (4, len) => {
+                self.open(len.map_or(Pending::Indefinite, Pending::Items), offset)?;
+                return Ok(Some(Event::Array(len)));
+            }
+            (5, len) => {
+                let items = len.map(|len| len.checked_mul(2).ok_or(Error::TooLong(offset, len)));
+                let pending = match items {
+                    None => Pending::Indefinite,
+                    Some(items) => Pending::Items(items?),
+                };
+                self.open(pending, offset)?;
+                return Ok(Some(Event::Map(len)));
+            }
+            (6, Some(tag)) => {
+                self.open(Pending::Items(1), offset)?;
+                return Ok(Some(Event::Tag(tag)));
+            }
+            (7, None) => {
+                match self.pending.last() {
+                    Some(Pending::Indefinite) | Some(Pending::Chunks(_)) => {}
+                    _ => return Err(Error::UnexpectedBreak(offset)),
+                }
+                self.pending.pop();
+                self.complete();
+                return Ok(Some(Event::Break));
+            }
+            (7, Some(arg)) => match additional {
+                25 => Event::Float(f16_to_f64(arg as u16)),
+                26 => Event::Float(f32::from_bits(arg as u32) as f64),
+                27 => Event::Float(f64::from_bits(arg)),
+                _ => Event::Simple(arg as u8),
+            },
+            _ => unreachable!(),
+        };
+        // a chunk does not complete the indefinite length bytes or text
+        match self.pending.last() {
+            Some(Pending::Chunks(_)) => {}
+            _ => self.complete(),
+        }
+        Ok(Some(event))
+    }
+
+    /// read the next event, the end of the input is an error
+    pub fn event(&mut self) -> Result<Event, Error> {
+        let offset = self.offset;
+        self.next_event()?.ok_or(Error::EndOfInput(offset))
+    }
+
+    /// read the next item and all the items it holds, the first event
+    /// (e.g. the start of the array) is returned
+    fn item(&mut self) -> Result<Event, Error> {
+        let offset = self.offset;
+        let depth = self.pending.len();
+        let first = self.event()?;
+        if first == Event::Break {
+            return Err(Error::UnexpectedBreak(offset));
+        }
+        while self.pending.len() > depth {
+            self.event()?;
+        }
+        Ok(first)
+    }
+
+    /// skip the next item, with all the items it holds
+    pub fn skip(&mut self) -> Result<(), Error> {
+        self.item().map(|_| ())
+    }
+
+    /// the raw bytes of the next item, with all the items it holds
+    pub fn raw_item(&mut self) -> Result<Vec<u8>, Error> {
+        self.raw = Some(Vec::new());
+        let result = self.item();
+        let raw = self.raw.take().unwrap();
+        result.map(|_| raw)
+    }
+
+    /// read the start of an array, `None` if of indefinite length
+    pub fn array(&mut self) -> Result<Option<u64>, Error> {
+        let offset = self.offset;
+        match self.event()? {
+            Event::Array(len) => Ok(len),
+            _ => Err(Error::Unexpected(offset, "an array")),
+        }
+    }
+
+    pub fn unsigned_integer(&mut self) -> Result<u64, Error> {
+        let offset = self.offset;
+        match self.event()? {
+            Event::Unsigned(n) => Ok(n),
+            _ => Err(Error::Unexpected(offset, "an unsigned integer")),
+        }
+    }
+
+    /// check that the input ends after the last top level item
+    pub fn expect_end(&mut self) -> Result<(), Error> {
+        let offset = self.offset;
+        match self.next_event()? {
+            None => Ok(()),
+            Some(_) => Err(Error::Unexpected(offset, "the end of the input")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn events(bytes: &[u8]) -> Result<Vec<Event>, Error> {
+        let mut reader = Reader::new(bytes);
+        let mut events = Vec::new();
+        while let Some(event) = reader.next_event()? {
+            events.push(event);
+        }
+        Ok(events)
+    }
+
+    #[test]
+    fn read_events() {
+        // [1, -2, {"a": [_ h'01', h'02']}, 24(h'00'), true, 1.5, [_ ]]
+        let bytes = [
+            0x87, 0x01, 0x21, 0xa1, 0x61, 0x61, 0x5f, 0x41, 0x01, 0x41, 0x02, 0xff, 0xd8, 0x18,
+            0x41, 0x00, 0xf5, 0xf9, 0x3e, 0x00, 0x9f, 0xff,
+        ];
+        assert_eq!(
+            events(&bytes).unwrap(),
+            vec![
+                Event::Array(Some(7)),
+                Event::Unsigned(1),
+                Event::Negative(1),
+                Event::Map(Some(1)),
+                Event::Text("a".to_owned()),
+                Event::IndefiniteBytes,
+                Event::Bytes(vec![1]),
+                Event::Bytes(vec![2]),
+                Event::Break,
+                Event::Tag(24),
+                Event::Bytes(vec![0]),
+                Event::Simple(21),
+                Event::Float(1.5),
+                Event::Array(None),
+                Event::Break,
+            ]
+        );
+    }
+
+    #[test]
+    fn skip_and_raw_items() {
+        let bytes = [
+            0x83, 0x82, 0x01, 0x02, 0x9f, 0x80, 0xff, 0x43, 0x01, 0x02, 0x03,
+        ];
+        let mut reader = Reader::new(&bytes[..]);
+        assert_eq!(reader.array().unwrap(), Some(3));
+        reader.skip().unwrap();
+        assert_eq!(reader.raw_item().unwrap(), vec![0x9f, 0x80, 0xff]);
+        assert_eq!(reader.raw_item().unwrap(), vec![0x43, 0x01, 0x02, 0x03]);
+        assert!(reader.is_at_top_level());
+        reader.expect_end().unwrap();
+    }
+
+    #[test]
+    fn truncated_input() {
+        // the end of the input is only accepted between top level items
+        for bytes in &[
+            &[0x82, 0x01][..],
+            &[0x9f, 0x01][..],
+            &[0x5f, 0x41, 0x01][..],
+            &[0xd8, 0x18][..],
+            &[0x43, 0x01, 0x02][..],
+            &[0x19, 0x01][..],
+        ] {
+            match events(bytes) {
+                Err(Error::EndOfInput(_)) => {}
+                r => panic!("unexpected result {:?} for {:?}", r, bytes),
+            }
+        }
+        let mut reader = Reader::new(&[0x82, 0x01][..]);
+        assert_eq!(reader.array().unwrap(), Some(2));
+        match reader.skip() {
+            Ok(()) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+        match reader.skip() {
+            Err(Error::EndOfInput(2)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+    }
+
+    #[test]
+    fn oversized_input() {
+        // a length announcing 4GB of bytes is rejected before allocating
+        match events(&[0x5b, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00]) {
+            Err(Error::TooLong(0, 0x1_0000_0000)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+        let mut reader = Reader::new(&[0x44, 0x01, 0x02, 0x03, 0x04][..]).with_max_len(3);
+        match reader.next_event() {
+            Err(Error::TooLong(0, 4)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+        let bytes = [0x84, 0x01, 0x02, 0x03, 0x04];
+        let mut reader = Reader::new(&bytes[..]).with_max_len(4);
+        match reader.raw_item() {
+            Err(Error::TooLong(4, 5)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+        match events(&[0x81; MAX_DEPTH + 1]) {
+            Err(Error::TooDeep(offset)) => assert_eq!(offset, MAX_DEPTH as u64),
+            r => panic!("unexpected result {:?}", r),
+        }
+    }
+
+    #[test]
+    fn invalid_input() {
+        match events(&[0x01, 0xff]) {
+            Err(Error::UnexpectedBreak(1)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+        match events(&[0x5f, 0x61, 0x61, 0xff]) {
+            Err(Error::InvalidChunk(1)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+        match events(&[0x62, 0xc3, 0x28]) {
+            Err(Error::InvalidUtf8(0)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+        match events(&[0x1c]) {
+            Err(Error::InvalidAdditionalInfo(0)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+        let mut reader = Reader::new(&[0x01][..]);
+        match reader.array() {
+            Err(Error::Unexpected(0, _)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+    }
+}
@@ -0,0 +1,344 @@
+//! Typed encoding and decoding of the CBOR semantic tags used by Cardano
+//!
+//! * tags 2 and 3 (RFC 7049 section 2.4.2): positive and negative bignums,
+//!   the magnitude is given as big endian bytes;
+//! * tag 24 (RFC 7049 section 2.4.4.1): a CBOR item encoded in bytes, e.g.
+//!   the content of the addresses and of the block headers;
+//! * tag 30: a rational number, the array of its numerator (an integer) and
+//!   of its denominator (a non zero unsigned integer).
+//!
+//! The `TagDeserializer` and `TagSerializer` traits add the accessors to the
+//! `cbor_event` deserializer and serializer so the callers do not need to
+//! check the raw tag values.
+//!
+
+use cbor_event::{self, de::Deserializer, se::Serializer, Len, Type};
+use std::io::{BufRead, Cursor, Write};
+
+pub const TAG_POSITIVE_BIGNUM: u64 = 2;
+pub const TAG_NEGATIVE_BIGNUM: u64 = 3;
+pub const TAG_ENCODED_CBOR: u64 = 24;
+pub const TAG_RATIONAL: u64 = 30;
+
+/// an arbitrary precision integer, as encoded with the tags 2 and 3
+///
+/// The negative bignums hold `-1 - n`, as the CBOR negative integers.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BigNum {
+    negative: bool,
+    /// big endian, without leading zeros
+    magnitude: Vec<u8>,
+}
+
+fn strip_leading_zeros(bytes: &[u8]) -> Vec<u8> {
+    let start = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len());
+    bytes[start..].to_vec()
+}
+
+impl BigNum {
+    /// the positive bignum of the given big endian bytes
+    pub fn positive(magnitude: &[u8]) -> Self {
+        BigNum {
+            negative: false,
+            magnitude: strip_leading_zeros(magnitude),
+        }
+    }
+
+    /// the negative bignum `-1 - n` where `n` is given in big endian bytes
+    pub fn negative(magnitude: &[u8]) -> Self {
+        BigNum {
+            negative: true,
+            magnitude: strip_leading_zeros(magnitude),
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    /// the big endian bytes of the content of the tag
+    pub fn magnitude(&self) -> &[u8] {
+        &self.magnitude
+    }
+
+    /// the value of the bignum if it fits in an `i128`
+    pub fn to_i128(&self) -> Option<i128> {
+        if self.magnitude.len() > 16 {
+            return None;
+        }
+        let n = self
+            .magnitude
+            .iter()
+            .fold(0u128, |acc, b| (acc << 8) | *b as u128);
+        if n > i128::max_value() as u128 {
+            return None;
+        }
+        if self.negative {
+            Some(-1 - n as i128)
+        } else {
+            Some(n as i128)
+        }
+    }
+}
+
+impl From<u64> for BigNum {
+    fn from(v: u64) -> Self {
+        BigNum::positive(&v.to_be_bytes())
+    }
+}
+
+impl From<i64> for BigNum {
+    fn from(v: i64) -> Self {
+        if v < 0 {
+            BigNum::negative(&((-1 - v) as u64).to_be_bytes())
+        } else {
+            BigNum::positive(&(v as u64).to_be_bytes())
+        }
+    }
+}
+
+/// a rational number, as encoded with the tag 30
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    pub numerator: i64,
+    pub denominator: u64,
+}
+
+impl Rational {
+    /// `None` if the denominator is zero
+    pub fn new(numerator: i64, denominator: u64) -> Option<Self> {
+        if denominator == 0 {
+            None
+        } else {
+            Some(Rational {
+                numerator,
+                denominator,
+            })
+        }
+    }
+}
+
+fn expect_tag<R: BufRead>(raw: &mut Deserializer<R>, expected: u64) -> cbor_event::Result<()> {
+    let tag = raw.tag()?;
+    if tag != expected {
+        return Err(cbor_event::Error::CustomError(format!(
+            "Invalid Tag: {} but expected {}",
+            tag, expected
+        )));
+    }
+    Ok(())
+}
+
+/// typed accessors of the tagged items
+pub trait TagDeserializer {
+    /// a bignum, tagged 2 or 3
+    fn bignum(&mut self) -> cbor_event::Result<BigNum>;
+
+    /// a rational, tagged 30
+    fn rational(&mut self) -> cbor_event::Result<Rational>;
+
+    /// the bytes of the CBOR item tagged 24, not decoded
+    fn encoded_cbor(&mut self) -> cbor_event::Result<Vec<u8>>;
+
+    /// decode the CBOR item tagged 24, the whole content of the bytes
+    /// must be consumed
+    fn deserialize_encoded<T: cbor_event::Deserialize>(&mut self) -> cbor_event::Result<T> {
+        let bytes = self.encoded_cbor()?;
+        Deserializer::from(Cursor::new(bytes)).deserialize_complete()
+    }
+}
+
+impl<R: BufRead> TagDeserializer for Deserializer<R> {
+    fn bignum(&mut self) -> cbor_event::Result<BigNum> {
+        let negative = match self.tag()? {
+            TAG_POSITIVE_BIGNUM => false,
+            TAG_NEGATIVE_BIGNUM => true,
+            tag => {
+                return Err(cbor_event::Error::CustomError(format!(
+                    "Invalid Tag: {} but expected a bignum (2 or 3)",
+                    tag
+                )));
+            }
+        };
+        let bytes = self.bytes()?;
+        Ok(BigNum {
+            negative,
+            magnitude: strip_leading_zeros(&bytes),
+        })
+    }
+
+    fn rational(&mut self) -> cbor_event::Result<Rational> {
+        expect_tag(self, TAG_RATIONAL)?;
+        self.tuple(2, "Rational")?;
+        let numerator = match self.cbor_type()? {
+            Type::UnsignedInteger => {
+                let n = self.unsigned_integer()?;
+                if n > i64::max_value() as u64 {
+                    return Err(cbor_event::Error::CustomError(format!(
+                        "Rational numerator too large: {}",
+                        n
+                    )));
+                }
+                n as i64
+            }
+            Type::NegativeInteger => self.negative_integer()?,
+            t => return Err(cbor_event::Error::Expected(Type::UnsignedInteger, t)),
+        };
+        let denominator = self.unsigned_integer()?;
+        Rational::new(numerator, denominator).ok_or_else(|| {
+            cbor_event::Error::CustomError("Invalid Rational: null denominator".to_owned())
+        })
+    }
+
+    fn encoded_cbor(&mut self) -> cbor_event::Result<Vec<u8>> {
+        expect_tag(self, TAG_ENCODED_CBOR)?;
+        self.bytes()
+    }
+}
+
+/// writers of the tagged items
+pub trait TagSerializer: Sized {
+    fn write_bignum(&mut self, bignum: &BigNum) -> cbor_event::Result<&mut Self>;
+
+    fn write_rational(&mut self, rational: &Rational) -> cbor_event::Result<&mut Self>;
+
+    /// write the given bytes, already encoded in CBOR, tagged 24
+    fn write_encoded_cbor(&mut self, bytes: &[u8]) -> cbor_event::Result<&mut Self>;
+
+    /// encode the item in CBOR and write it tagged 24
+    fn serialize_encoded<T: cbor_event::Serialize>(
+        &mut self,
+        t: &T,
+    ) -> cbor_event::Result<&mut Self> {
+        let bytes = cbor!(t)?;
+        self.write_encoded_cbor(&bytes)
+    }
+}
+
+impl<W: Write + Sized> TagSerializer for Serializer<W> {
+    fn write_bignum(&mut self, bignum: &BigNum) -> cbor_event::Result<&mut Self> {
+        let tag = if bignum.negative {
+            TAG_NEGATIVE_BIGNUM
+        } else {
+            TAG_POSITIVE_BIGNUM
+        };
+        self.write_tag(tag)?.write_bytes(&bignum.magnitude)
+    }
+
+    fn write_rational(&mut self, rational: &Rational) -> cbor_event::Result<&mut Self> {
+        self.write_tag(TAG_RATIONAL)?.write_array(Len::Len(2))?;
+        if rational.numerator < 0 {
+            self.write_negative_integer(rational.numerator)?;
+        } else {
+            self.write_unsigned_integer(rational.numerator as u64)?;
+        }
+        self.write_unsigned_integer(rational.denominator)
+    }
+
+    fn write_encoded_cbor(&mut self, bytes: &[u8]) -> cbor_event::Result<&mut Self> {
+        self.write_tag(TAG_ENCODED_CBOR)?.write_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn deserializer(bytes: &[u8]) -> Deserializer<Cursor<Vec<u8>>> {
+        Deserializer::from(Cursor::new(bytes.to_vec()))
+    }
+
+    #[test]
+    fn bignum_rfc7049() {
+        // 18446744073709551616 and -18446744073709551617 (RFC 7049 appendix A)
+        let positive = [0xc2, 0x49, 0x01, 0, 0, 0, 0, 0, 0, 0, 0];
+        let negative = [0xc3, 0x49, 0x01, 0, 0, 0, 0, 0, 0, 0, 0];
+
+        let big = deserializer(&positive).bignum().unwrap();
+        assert!(!big.is_negative());
+        assert_eq!(big.to_i128(), Some(18446744073709551616));
+        let big = deserializer(&negative).bignum().unwrap();
+        assert!(big.is_negative());
+        assert_eq!(big.to_i128(), Some(-18446744073709551617));
+
+        let mut se = Serializer::new_vec();
+        se.write_bignum(&BigNum::positive(&[1, 0, 0, 0, 0, 0, 0, 0, 0]))
+            .unwrap();
+        assert_eq!(se.finalize(), &positive[..]);
+    }
+
+    #[test]
+    fn bignum_from_integers() {
+        for v in &[
+            0i64,
+            1,
+            255,
+            256,
+            -1,
+            -256,
+            -257,
+            i64::max_value(),
+            i64::min_value(),
+        ] {
+            let mut se = Serializer::new_vec();
+            se.write_bignum(&BigNum::from(*v)).unwrap();
+            let bytes = se.finalize();
+            let big = deserializer(&bytes).bignum().unwrap();
+            assert_eq!(big, BigNum::from(*v));
+            assert_eq!(big.to_i128(), Some(*v as i128));
+        }
+        assert_eq!(BigNum::from(0u64).magnitude(), &[] as &[u8]);
+    }
+
+    #[test]
+    fn rational_roundtrip() {
+        for (n, d) in &[
+            (1i64, 2u64),
+            (-3, 4),
+            (0, 1),
+            (i64::min_value(), u64::max_value()),
+        ] {
+            let rational = Rational::new(*n, *d).unwrap();
+            let mut se = Serializer::new_vec();
+            se.write_rational(&rational).unwrap();
+            let bytes = se.finalize();
+            assert_eq!(&bytes[..2], &[0xd8, 0x1e]);
+            assert_eq!(deserializer(&bytes).rational().unwrap(), rational);
+        }
+    }
+
+    #[test]
+    fn rational_null_denominator() {
+        assert_eq!(Rational::new(1, 0), None);
+        assert!(deserializer(&[0xd8, 0x1e, 0x82, 0x01, 0x00])
+            .rational()
+            .is_err());
+    }
+
+    #[test]
+    fn encoded_cbor_roundtrip() {
+        let mut se = Serializer::new_vec();
+        se.serialize_encoded(&42u64).unwrap();
+        let bytes = se.finalize();
+        assert_eq!(bytes, vec![0xd8, 0x18, 0x42, 0x18, 0x2a]);
+        assert_eq!(
+            deserializer(&bytes).encoded_cbor().unwrap(),
+            vec![0x18, 0x2a]
+        );
+        let v: u64 = deserializer(&bytes).deserialize_encoded().unwrap();
+        assert_eq!(v, 42);
+    }
+
+    #[test]
+    fn encoded_cbor_trailing_data() {
+        let r: cbor_event::Result<u64> =
+            deserializer(&[0xd8, 0x18, 0x42, 0x01, 0x02]).deserialize_encoded();
+        assert!(r.is_err());
+    }
+
+    #[test]
+    fn unexpected_tag() {
+        assert!(deserializer(&[0xc1, 0x00]).bignum().is_err());
+        assert!(deserializer(&[0xc2, 0x41, 0x01]).encoded_cbor().is_err());
+    }
+}
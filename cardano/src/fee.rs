@@ -4,8 +4,10 @@ use cbor_event;
 use coin;
 use coin::Coin;
 use std::{
+    fmt,
     ops::{Add, Mul},
     result,
+    str::FromStr,
 };
 use tx::{txaux_serialize_size, Tx, TxAux, TxInWitness};
 
@@ -26,6 +28,7 @@ impl Fee {
 pub enum Error {
     CoinError(coin::Error),
     CborError(cbor_event::Error),
+    InvalidMilli(String),
 }
 
 pub type Result<T> = result::Result<T, Error>;
@@ -45,6 +48,7 @@ impl ::std::error::Error for Error {
         match self {
             Error::CborError(ref err) => Some(err),
             Error::CoinError(ref err) => Some(err),
+            Error::InvalidMilli(_) => None,
         }
     }
 }
@@ -53,12 +57,17 @@ impl ::std::fmt::Display for Error {
         match self {
             Error::CborError(_) => write!(f, "invalid cbor encoding"),
             Error::CoinError(_) => write!(f, "invalid Ada value"),
+            Error::InvalidMilli(s) => write!(f, "invalid milli value '{}'", s),
         }
     }
 }
 
+/// A fixed point number with 3 decimals, e.g. a coefficient of the fee or
+/// a ratio of a reward
+///
+/// It is written as a decimal number (`"43.946"`, `"0.22"`) or as a
+/// percentage (`"22%"`, `"22.5%"`).
 #[derive(PartialEq, Eq, PartialOrd, Debug, Clone, Copy)]
-#[cfg_attr(feature = "generic-serialization", derive(Serialize, Deserialize))]
 pub struct Milli(u64);
 impl Milli {
     pub fn new(i: u64, f: u64) -> Self {
@@ -81,6 +90,125 @@ impl Milli {
     pub fn as_millis(self) -> u64 {
         self.0
     }
+
+    /// the coin times this number, truncated to the lovelace. `None` if the
+    /// result is above `MAX_COIN`
+    pub fn checked_mul_coin(self, coin: Coin) -> Option<Coin> {
+        let v = u64::from(coin) as u128 * self.0 as u128 / 1000;
+        if v > coin::MAX_COIN as u128 {
+            return None;
+        }
+        Coin::new(v as u64).ok()
+    }
+
+    /// the coin divided by this number, truncated to the lovelace. `None` if
+    /// this number is zero or if the result is above `MAX_COIN`
+    pub fn checked_div_coin(self, coin: Coin) -> Option<Coin> {
+        if self.0 == 0 {
+            return None;
+        }
+        let v = u64::from(coin) as u128 * 1000 / self.0 as u128;
+        if v > coin::MAX_COIN as u128 {
+            return None;
+        }
+        Coin::new(v as u64).ok()
+    }
+}
+
+impl fmt::Display for Milli {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{:03}", self.0 / 1000, self.0 % 1000)
+    }
+}
+
+impl FromStr for Milli {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::InvalidMilli(s.to_string());
+        // a thousandth is a tenth of percent
+        let (number, decimals) = if s.ends_with('%') {
+            (&s[..s.len() - 1], 1)
+        } else {
+            (s, 3)
+        };
+        let (integral, fractional) = match number.find('.') {
+            Some(dot) => (&number[..dot], &number[dot + 1..]),
+            None => (number, ""),
+        };
+        let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+        if integral.is_empty()
+            || !is_digits(integral)
+            || !is_digits(fractional)
+            || fractional.len() > decimals
+            || (number.contains('.') && fractional.is_empty())
+        {
+            return Err(invalid());
+        }
+        let scale = 10u64.pow(decimals as u32);
+        let fractional = if fractional.is_empty() {
+            0
+        } else {
+            fractional.parse::<u64>().map_err(|_| invalid())?
+                * 10u64.pow((decimals - fractional.len()) as u32)
+        };
+        integral
+            .parse::<u64>()
+            .ok()
+            .and_then(|i| i.checked_mul(scale))
+            .and_then(|v| v.checked_add(fractional))
+            .map(Milli)
+            .ok_or_else(invalid)
+    }
+}
+
+/// serialized as a decimal number string (`"43.946"`), deserialized from
+/// such a string, a percentage (`"22%"`) or, in the self-describing formats,
+/// an integer: `43946` has the same meaning as `"43946"`, 43946.000
+#[cfg(feature = "generic-serialization")]
+impl serde::Serialize for Milli {
+    fn serialize<S>(&self, serializer: S) -> result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+#[cfg(feature = "generic-serialization")]
+impl<'de> serde::Deserialize<'de> for Milli {
+    fn deserialize<D>(deserializer: D) -> result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct MilliVisitor;
+        impl<'de> serde::de::Visitor<'de> for MilliVisitor {
+            type Value = Milli;
+
+            fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+                write!(fmt, "Expecting a decimal number")
+            }
+
+            fn visit_u64<E>(self, v: u64) -> result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                v.checked_mul(1000)
+                    .map(Milli)
+                    .ok_or_else(|| E::custom(Error::InvalidMilli(v.to_string())))
+            }
+
+            fn visit_str<'a, E>(self, v: &'a str) -> result::Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                v.parse().map_err(|err: Error| E::custom(err))
+            }
+        }
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(MilliVisitor)
+        } else {
+            deserializer.deserialize_str(MilliVisitor)
+        }
+    }
 }
 
 impl Add for Milli {
@@ -201,4 +329,70 @@ mod test {
         test_milli_mul_eq(241, 900001_900);
         test_milli_mul_eq(241, 400);
     }
+
+    #[test]
+    fn milli_from_str() {
+        assert_eq!("43.946".parse::<Milli>().unwrap(), Milli::new(43, 946));
+        assert_eq!("0.22".parse::<Milli>().unwrap(), Milli::new(0, 220));
+        assert_eq!("155381".parse::<Milli>().unwrap(), Milli::integral(155381));
+        assert_eq!("22%".parse::<Milli>().unwrap(), Milli::new(0, 220));
+        assert_eq!("22.5%".parse::<Milli>().unwrap(), Milli::new(0, 225));
+        assert_eq!("150%".parse::<Milli>().unwrap(), Milli::new(1, 500));
+        for invalid in &[
+            "", ".5", "1.", "0.2225", "22.25%", "%", "-1", "1e3", "1.2.3",
+        ] {
+            assert!(invalid.parse::<Milli>().is_err(), "{}", invalid);
+        }
+        assert!("18446744073709551615".parse::<Milli>().is_err());
+
+        let milli = Milli::new(43, 6);
+        assert_eq!(milli.to_string(), "43.006");
+        assert_eq!(milli.to_string().parse::<Milli>().unwrap(), milli);
+    }
+
+    #[test]
+    fn milli_checked_coin() {
+        let coin = Coin::new(1_000_001).unwrap();
+        let ratio = Milli::new(0, 220);
+        assert_eq!(ratio.checked_mul_coin(coin), Coin::new(220_000).ok());
+        assert_eq!(ratio.checked_div_coin(coin), Coin::new(4_545_459).ok());
+        assert_eq!(Milli::integral(0).checked_div_coin(coin), None);
+
+        let max = Coin::new(coin::MAX_COIN).unwrap();
+        assert_eq!(Milli::integral(1).checked_mul_coin(max), Some(max));
+        assert_eq!(Milli::new(1, 1).checked_mul_coin(max), None);
+        assert_eq!(Milli::new(0, 999).checked_div_coin(max), None);
+    }
+
+    #[cfg(feature = "generic-serialization")]
+    #[test]
+    fn milli_serde() {
+        let milli = Milli::new(43, 946);
+        assert_eq!(::serde_json::to_string(&milli).unwrap(), "\"43.946\"");
+        let decoded: Milli = ::serde_json::from_str("\"43.946\"").unwrap();
+        assert_eq!(decoded, milli);
+
+        // an integer and its string have the same meaning
+        let decoded: Milli = ::serde_json::from_str("43946").unwrap();
+        assert_eq!(decoded, Milli::integral(43946));
+        let decoded: Milli = ::serde_json::from_str("\"43946\"").unwrap();
+        assert_eq!(decoded, Milli::integral(43946));
+        assert!(::serde_json::from_str::<Milli>(&u64::max_value().to_string()).is_err());
+
+        let decoded: Milli = ::serde_json::from_str("\"22%\"").unwrap();
+        assert_eq!(decoded, Milli::new(0, 220));
+        assert!(::serde_json::from_str::<Milli>("\"22.25%\"").is_err());
+
+        for milli in &[
+            Milli::integral(0),
+            Milli::new(0, 1),
+            milli,
+            Milli::new(155381, 0),
+        ] {
+            let json = ::serde_json::to_string(milli).unwrap();
+            assert_eq!(::serde_json::from_str::<Milli>(&json).unwrap(), *milli);
+            let cbor = ::cbor::serde::to_vec(milli).unwrap();
+            assert_eq!(::cbor::serde::from_slice::<Milli>(&cbor).unwrap(), *milli);
+        }
+    }
 }
@@ -19,12 +19,14 @@ use std::{
     ops::Deref,
 };
 
+use address::ExtendedAddr;
 use cbor_event::{
     self,
     de::Deserializer,
     se::{self, Serializer},
 };
 use hdwallet::XPub;
+use std::borrow::Borrow;
 
 use util::{hex, securemem};
 
@@ -206,6 +208,31 @@ impl HDKey {
         Path::from_cbor(&out)
     }
 }
+
+/// find the addresses whose derivation path is encrypted with the given
+/// `HDKey`, with their decrypted path (e.g. to restore a random indexes
+/// wallet from the addresses of the blockchain)
+///
+/// The addresses of other wallets, and the ones whose payload cannot be
+/// decoded, are skipped. See `ExtendedAddr::decrypt_derivation_path` about
+/// checking the addresses really belong to the wallet.
+pub fn scan_addresses<'a, I>(
+    hdkey: &'a HDKey,
+    addresses: I,
+) -> impl Iterator<Item = (I::Item, Path)> + 'a
+where
+    I: IntoIterator,
+    I::IntoIter: 'a,
+    I::Item: Borrow<ExtendedAddr>,
+{
+    addresses.into_iter().filter_map(move |address| {
+        match address.borrow().decrypt_derivation_path(hdkey) {
+            Ok(Some(path)) => Some((address, path)),
+            Ok(None) | Err(_) => None,
+        }
+    })
+}
+
 impl Drop for HDKey {
     fn drop(&mut self) {
         securemem::zero(&mut self.0);
@@ -337,6 +364,45 @@ mod tests {
         assert_eq!(path, key.decrypt_path(&payload).unwrap())
     }
 
+    #[test]
+    fn scan() {
+        use config::NetworkMagic;
+
+        let seed = hdwallet::Seed::from_bytes([0; hdwallet::SEED_SIZE]);
+        let sk = hdwallet::XPrv::generate_from_seed(&seed);
+        let key = HDKey::new(&sk.public());
+        let other_key = HDKey::from_bytes([1; HDKEY_SIZE]);
+
+        let address = |hdkey: &HDKey, index: u32| {
+            let path = Path::new(vec![0x80000000, 0x80000000 + index]);
+            let xpub = sk
+                .derive(hdwallet::DerivationScheme::V2, path[0])
+                .derive(hdwallet::DerivationScheme::V2, path[1])
+                .public();
+            ExtendedAddr::new_with_derivation_path(xpub, hdkey, &path, NetworkMagic::NoMagic)
+        };
+        let addresses = vec![
+            address(&key, 0),
+            address(&other_key, 1),
+            ExtendedAddr::new_simple(sk.public(), NetworkMagic::NoMagic),
+            address(&key, 2),
+        ];
+
+        let found: Vec<_> = scan_addresses(&key, addresses.iter()).collect();
+        assert_eq!(
+            found,
+            vec![
+                (&addresses[0], Path::new(vec![0x80000000, 0x80000000])),
+                (&addresses[3], Path::new(vec![0x80000000, 0x80000002])),
+            ]
+        );
+        assert_eq!(
+            addresses[1].decrypt_derivation_path(&other_key).unwrap(),
+            Some(Path::new(vec![0x80000000, 0x80000001]))
+        );
+        assert_eq!(addresses[2].decrypt_derivation_path(&key).unwrap(), None);
+    }
+
     #[test]
     fn unit1() {
         let key = HDKey::from_bytes([0u8; 32]);
@@ -26,7 +26,8 @@ use std::{
     io::{BufRead, Write},
     result,
 };
-use util::{hex, securemem};
+use util::hex;
+use util::securemem::{self, SecureMem};
 
 use cbor_event::{self, de::Deserializer, se::Serializer};
 
@@ -190,7 +191,7 @@ impl Drop for Seed {
 /// HDWallet extended private key
 ///
 /// Effectively this is ed25519 extended secret key (64 bytes) followed by a chain code (32 bytes)
-pub struct XPrv([u8; XPRV_SIZE]);
+pub struct XPrv(SecureMem);
 impl XPrv {
     /// create the Root private key `XPrv` of the HDWallet associated to this `Seed`
     ///
@@ -232,7 +233,7 @@ impl XPrv {
             iter = iter + 1;
         }
 
-        Self::from_bytes(out)
+        Self::from_bytes(&mut out)
     }
 
     pub fn generate_from_bip39(bytes: &bip39::Seed) -> Self {
@@ -242,7 +243,7 @@ impl XPrv {
         out[31] &= 0b1101_1111; // set 3rd highest bit to 0 as per the spec
         out[64..96].clone_from_slice(&bytes.as_ref()[32..64]);
 
-        Self::from_bytes(out)
+        Self::from_bytes(&mut out)
     }
 
     /// takes the given raw bytes and perform some modifications to normalize
@@ -253,25 +254,29 @@ impl XPrv {
         bytes[31] &= 0b0001_1111;
         bytes[31] |= 0b0100_0000;;
 
-        Self::from_bytes(bytes)
+        Self::from_bytes(&mut bytes)
     }
 
     // Create a XPrv from the given bytes.
     //
     // This function does not perform any validity check and should not be used outside
     // of this module.
-    fn from_bytes(bytes: [u8; XPRV_SIZE]) -> Self {
-        XPrv(bytes)
+    //
+    // The given array is zeroed once copied to the secure memory.
+    fn from_bytes(bytes: &mut [u8; XPRV_SIZE]) -> Self {
+        let xprv = XPrv(SecureMem::from_slice(bytes));
+        securemem::zero(bytes);
+        xprv
     }
 
     /// Create a `XPrv` by taking ownership of the given array
     ///
     /// This function may returns an error if it does not have the expected
     /// format.
-    pub fn from_bytes_verified(bytes: [u8; XPRV_SIZE]) -> Result<Self> {
-        let scalar = &bytes[0..32];
-        let last = scalar[31];
-        let first = scalar[0];
+    pub fn from_bytes_verified(mut bytes: [u8; XPRV_SIZE]) -> Result<Self> {
+        let last = bytes[31];
+        let first = bytes[0];
+        let xprv = XPrv::from_bytes(&mut bytes);
 
         if (last & 0b1110_0000) != 0b0100_0000 {
             return Err(Error::InvalidXPrv("expected 3 highest bits to be 0b010"));
@@ -280,7 +285,7 @@ impl XPrv {
             return Err(Error::InvalidXPrv("expected 3 lowest bits to be 0b000"));
         }
 
-        Ok(XPrv(bytes))
+        Ok(xprv)
     }
 
     /// Create a `XPrv` from the given slice. This slice must be of size `XPRV_SIZE`
@@ -290,9 +295,7 @@ impl XPrv {
         if bytes.len() != XPRV_SIZE {
             return Err(Error::InvalidXPrvSize(bytes.len()));
         }
-        let mut buf = [0u8; XPRV_SIZE];
-        buf[..].clone_from_slice(bytes);
-        Ok(XPrv::from_bytes(buf))
+        Ok(XPrv(SecureMem::from_slice(bytes)))
     }
 
     /// Create a `XPrv` from a given hexadecimal string
@@ -376,12 +379,7 @@ impl ::std::str::FromStr for XPrv {
 }
 impl AsRef<[u8]> for XPrv {
     fn as_ref(&self) -> &[u8] {
-        &self.0
-    }
-}
-impl Drop for XPrv {
-    fn drop(&mut self) {
-        securemem::zero(&mut self.0);
+        self.0.as_ref()
     }
 }
 #[cfg(feature = "generic-serialization")]
@@ -979,7 +977,7 @@ fn derive_private(xprv: &XPrv, index: DerivationIndex, scheme: DerivationScheme)
     imac.reset();
     zmac.reset();
 
-    XPrv::from_bytes(out)
+    XPrv::from_bytes(&mut out)
 }
 
 fn point_of_trunc28_mul8(sk: &[u8], scheme: DerivationScheme) -> [u8; 32] {
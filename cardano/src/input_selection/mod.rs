@@ -8,7 +8,7 @@ use txutils::{output_sum, Input, OutputPolicy};
 
 mod simple_selections;
 
-pub use self::simple_selections::{Blackjack, HeadFirst, LargestFirst};
+pub use self::simple_selections::{Blackjack, HeadFirst, LargestFirst, RandomImprove};
 
 #[derive(Debug)]
 pub enum Error {
@@ -187,35 +187,49 @@ pub trait InputSelectionAlgorithm<Addressing> {
             }
         }
 
-        let (change, loss) = match builder.add_output_policy(fee_algorithm, output_policy) {
-            Err(txbuild::Error::TxNotEnoughTotalInput) => {
-                return Err(Error::NotEnoughInput);
-            }
-            Err(txbuild::Error::TxOutputPolicyNotEnoughCoins(loss)) => (None, Some(loss)),
-            Err(txbuild_err) => {
-                return Err(Error::TxBuildError(txbuild_err));
-            }
-            Ok(change_outputs) => (
-                if change_outputs.is_empty() {
-                    None
-                } else {
-                    Some(output_sum(change_outputs.iter())?)
-                },
-                None,
-            ),
-        };
-
-        let fees = builder.calculate_fee(fee_algorithm).unwrap();
-        let fees = if let Some(loss) = loss {
-            Fee::new((fees.to_coin() + loss)?)
-        } else {
-            fees
-        };
-        let result = InputSelectionResult {
-            estimated_fees: fees,
-            estimated_change: change,
-            selected_inputs: selected,
-        };
-        Ok(result)
+        finalize(builder, fee_algorithm, output_policy, selected)
     }
 }
+
+/// apply the output policy to the builder of the selected inputs and
+/// compute the resulting change and fees
+fn finalize<Addressing, F>(
+    mut builder: TxBuilder,
+    fee_algorithm: &F,
+    output_policy: &OutputPolicy,
+    selected: Vec<Input<Addressing>>,
+) -> Result<InputSelectionResult<Addressing>>
+where
+    F: FeeAlgorithm,
+{
+    let (change, loss) = match builder.add_output_policy(fee_algorithm, output_policy) {
+        Err(txbuild::Error::TxNotEnoughTotalInput) => {
+            return Err(Error::NotEnoughInput);
+        }
+        Err(txbuild::Error::TxOutputPolicyNotEnoughCoins(loss)) => (None, Some(loss)),
+        Err(txbuild_err) => {
+            return Err(Error::TxBuildError(txbuild_err));
+        }
+        Ok(change_outputs) => (
+            if change_outputs.is_empty() {
+                None
+            } else {
+                Some(output_sum(change_outputs.iter())?)
+            },
+            None,
+        ),
+    };
+
+    let fees = builder.calculate_fee(fee_algorithm).unwrap();
+    let fees = if let Some(loss) = loss {
+        Fee::new((fees.to_coin() + loss)?)
+    } else {
+        fees
+    };
+    let result = InputSelectionResult {
+        estimated_fees: fees,
+        estimated_change: change,
+        selected_inputs: selected,
+    };
+    Ok(result)
+}
@@ -163,6 +163,155 @@ impl<Addressing: Clone> InputSelectionAlgorithm<Addressing> for Blackjack<Addres
     }
 }
 
+/// Random-Improve input selection, as described in the wallet specification
+/// of Cardano (the default of the Cardano wallet)
+///
+/// For every output, from the largest to the smallest, inputs are selected
+/// at random until the value of the output is covered. Then every output
+/// tries to improve its selection with more random inputs, as long as the
+/// selected total gets closer to twice the output value without going over
+/// three times the output value. The change outputs are then about the size
+/// of the payments, which keeps the UTxO of the wallet healthy.
+///
+/// Random inputs are then added if needed to cover the fees.
+///
+pub struct RandomImprove<Addressing> {
+    inputs: Vec<Input<Addressing>>,
+    random_generator: BasicRandom,
+}
+impl<Addressing> RandomImprove<Addressing> {
+    pub fn new(seed: u32, inputs: Vec<Input<Addressing>>) -> Self {
+        RandomImprove {
+            inputs: inputs,
+            random_generator: BasicRandom::new(seed),
+        }
+    }
+
+    fn pick(&mut self) -> Option<Input<Addressing>> {
+        if self.inputs.is_empty() {
+            return None;
+        }
+        let index = self.random_generator.next() as usize % self.inputs.len();
+        Some(self.inputs.swap_remove(index))
+    }
+}
+impl<Addressing> From<Vec<Input<Addressing>>> for RandomImprove<Addressing> {
+    /// the selection is seeded from entropy, use `RandomImprove::new` for a
+    /// reproducible one
+    fn from(inputs: Vec<Input<Addressing>>) -> Self {
+        RandomImprove::new(entropy_seed(), inputs)
+    }
+}
+
+/// a seed taken from the random keys of the standard library's `HashMap`,
+/// which come from the operating system
+fn entropy_seed() -> u32 {
+    use std::collections::hash_map::RandomState;
+    use std::hash::{BuildHasher, Hasher};
+
+    RandomState::new().build_hasher().finish() as u32
+}
+impl<Addressing> InputSelectionAlgorithm<Addressing> for RandomImprove<Addressing> {
+    fn select_input<F>(
+        &mut self,
+        _fee_algorithm: &F,
+        _estimated_needed_output: Coin,
+    ) -> Result<Option<Input<Addressing>>>
+    where
+        F: FeeAlgorithm,
+    {
+        Ok(self.pick())
+    }
+
+    fn compute<F>(
+        &mut self,
+        fee_algorithm: &F,
+        outputs: Vec<TxOut>,
+        output_policy: &OutputPolicy,
+    ) -> Result<InputSelectionResult<Addressing>>
+    where
+        F: FeeAlgorithm,
+    {
+        if outputs.is_empty() {
+            return Err(Error::NoOutputs);
+        }
+
+        let mut builder = TxBuilder::new();
+        for output in outputs.iter() {
+            builder.add_output_value(output);
+        }
+
+        // the largest outputs are the most difficult to cover, start with them
+        let mut targets: Vec<u64> = outputs.iter().map(|o| u64::from(o.value)).collect();
+        targets.sort_unstable_by(|t1, t2| t2.cmp(t1));
+
+        // random phase: cover every output with random inputs
+        let mut selections = Vec::with_capacity(targets.len());
+        for target in targets.iter() {
+            let mut total = Coin::zero();
+            let mut selection = Vec::new();
+            while u64::from(total) < *target {
+                match self.pick() {
+                    None => return Err(Error::NotEnoughInput),
+                    Some(input) => {
+                        total = (total + input.value.value)?;
+                        selection.push(input);
+                    }
+                }
+            }
+            selections.push((total, selection));
+        }
+
+        // improve phase: get every selection closer to twice its output
+        let distance = |total: Coin, ideal: u64| (u64::from(total) as i128 - ideal as i128).abs();
+        for (target, (total, selection)) in targets.iter().zip(selections.iter_mut()) {
+            let ideal = 2 * target;
+            let upper_bound = 3 * target;
+            while let Some(input) = self.pick() {
+                let improved = (*total + input.value.value)?;
+                if u64::from(improved) <= upper_bound
+                    && distance(improved, ideal) < distance(*total, ideal)
+                {
+                    *total = improved;
+                    selection.push(input);
+                } else {
+                    self.inputs.push(input);
+                    break;
+                }
+            }
+        }
+
+        let mut selected = Vec::new();
+        for (_, selection) in selections {
+            for input in selection {
+                builder.add_input(&input.ptr, input.value.value);
+                selected.push(input);
+            }
+        }
+
+        // add random inputs until the fees are covered too
+        loop {
+            match builder
+                .clone()
+                .add_output_policy(fee_algorithm, output_policy)
+            {
+                Err(txbuild::Error::TxNotEnoughTotalInput) => match self.pick() {
+                    None => return Err(Error::NotEnoughInput),
+                    Some(input) => {
+                        builder.add_input(&input.ptr, input.value.value);
+                        selected.push(input);
+                    }
+                },
+                Err(txbuild::Error::TxOutputPolicyNotEnoughCoins(_)) => break,
+                Err(txbuild_err) => return Err(Error::TxBuildError(txbuild_err)),
+                Ok(_) => break,
+            }
+        }
+
+        finalize(builder, fee_algorithm, output_policy, selected)
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -371,6 +520,12 @@ mod test {
             let max_fee = fee_alg.estimate(TX_SIZE_LIMIT).expect("max fee");
             test_fee(value, |i| Blackjack::new(Coin::from(100_000), i), fee_alg, max_fee)
         }
+
+        fn random_improve(value: (Wrapper<ProtocolMagic>, Inputs, Outputs)) -> bool {
+            let fee_alg = LinearFee::default();
+            let max_fee = fee_alg.estimate(TX_SIZE_LIMIT).expect("max fee");
+            test_fee(value, RandomImprove::from, fee_alg, max_fee)
+        }
     }
 }
 
@@ -609,6 +764,58 @@ mod unit_tests {
         );
     }
 
+    #[test]
+    fn random_improve_is_reproducible() {
+        let inputs: Vec<_> = (1..=10)
+            .map(|i| mk_icarus_style_input(Coin::new(i * 1_000_000).unwrap()))
+            .collect();
+        let outputs = vec![mk_icarus_style_txout(Coin::new(3_000_000).unwrap())];
+        let change_address = mk_random_icarus_style_address();
+
+        let compute = || {
+            RandomImprove::new(42, inputs.clone())
+                .compute(
+                    &LinearFee::default(),
+                    outputs.clone(),
+                    &OutputPolicy::One(change_address.clone()),
+                )
+                .unwrap()
+        };
+        assert_eq!(compute(), compute());
+    }
+
+    #[test]
+    fn random_improve_total_overflow() {
+        let max = Coin::new(coin::MAX_COIN).unwrap();
+        let inputs = vec![
+            mk_icarus_style_input(Coin::new(1).unwrap()),
+            mk_icarus_style_input(max),
+        ];
+        let outputs = vec![mk_icarus_style_txout(max)];
+        let change_address = mk_random_icarus_style_address();
+
+        for seed in 0..4 {
+            let result = RandomImprove::new(seed, inputs.clone()).compute(
+                &LinearFee::default(),
+                outputs.clone(),
+                &OutputPolicy::One(change_address.clone()),
+            );
+            match result {
+                Err(Error::CoinError(coin::Error::OutOfBound(_))) => {}
+                result => panic!("expected a coin overflow, got {:?}", result),
+            }
+        }
+    }
+
+    #[test]
+    fn not_enough_ada_random_improve() {
+        let input1 = mk_icarus_style_input(Coin::new(1_000000).unwrap());
+        let input2 = mk_icarus_style_input(Coin::new(1_000000).unwrap());
+        let output1 = mk_icarus_style_txout(Coin::new(2_000000).unwrap());
+
+        test_no_enough(RandomImprove::from(vec![input1, input2]), vec![output1]);
+    }
+
     #[test]
     fn not_enough_ada_blackjack() {
         let input1 = mk_icarus_style_input(Coin::new(25_029_238_000000).unwrap());
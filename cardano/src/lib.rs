@@ -19,6 +19,7 @@
 #[macro_use]
 extern crate serde_derive;
 #[cfg(feature = "generic-serialization")]
+#[macro_use]
 extern crate serde;
 
 #[cfg(test)]
@@ -43,9 +44,12 @@ extern crate cryptoxide;
 extern crate cbor_event;
 
 extern crate chain_core;
+extern crate ed25519_bip32;
 
 #[cfg(test)]
 extern crate base64;
+#[cfg(test)]
+extern crate test_vectors;
 
 pub mod address;
 pub mod coin;
@@ -61,6 +65,7 @@ pub mod redeem;
 pub mod tx;
 pub mod txbuild;
 pub mod txutils;
+pub mod units;
 pub mod util;
 
 pub mod bip;
@@ -13,6 +13,7 @@ use std::{
 
 use crate::{
     address::{AddrType, Attributes, ExtendedAddr, SpendingData},
+    cbor::canonical,
     coin::{self, Coin},
     config::ProtocolMagic,
     hash::Blake2b256,
@@ -130,6 +131,12 @@ impl TxInWitness {
         TxInWitness::PkWitness(key.public(), key.sign(&vec))
     }
 
+    /// the bytes signed by the `PkWitness` of the given transaction id, so
+    /// they can be signed out of this process (e.g. by a hardware wallet)
+    pub fn extended_pk_data_to_sign(protocol_magic: ProtocolMagic, txid: &TxId) -> Vec<u8> {
+        Self::prepare_byte_to_sign(protocol_magic, SigningTag::Tx, txid)
+    }
+
     /// create a TxInWitness from a given Redeem key
     pub fn new_redeem_pk(
         protocol_magic: ProtocolMagic,
@@ -370,6 +377,7 @@ impl Tx {
     }
     pub fn id(&self) -> TxId {
         let buf = cbor!(self).expect("encode Tx");
+        debug_assert_eq!(canonical::validate_byron(&buf), Ok(()));
         TxId::new(&buf)
     }
     pub fn add_input(&mut self, i: TxoPointer) {
@@ -953,6 +961,16 @@ mod tests {
 
         assert!(cbor_event::test_encode_decode(&txaux).expect("encode/decode TxAux"));
     }
+
+    #[test]
+    fn golden_txaux() {
+        for vector in ::test_vectors::cardano_transactions() {
+            let mut raw = Deserializer::from(std::io::Cursor::new(&vector.cbor));
+            let txaux: TxAux = raw.deserialize_complete().expect("to decode a TxAux");
+            assert_eq!(txaux.tx.id().to_string(), vector.txid, "{}", vector.name);
+            assert_eq!(cbor!(&txaux).unwrap(), vector.cbor, "{}", vector.name);
+        }
+    }
 }
 
 #[cfg(feature = "with-bench")]
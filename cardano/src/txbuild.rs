@@ -8,16 +8,21 @@
 //! total flexibility and abstraction/helpers.
 //!
 
+use address::ExtendedAddr;
+use cbor_event::{self, de::Deserializer, se::Serializer};
 use coin::{Coin, CoinDiff};
+use config::ProtocolMagic;
 use fee::{Fee, FeeAlgorithm};
+use std::io::{BufRead, Write};
 use std::iter::Iterator;
 use std::{error, fmt, iter, result};
 use tx::{txaux_serialize_size, Tx, TxAux, TxInWitness, TxOut, TxWitness, TxoPointer};
 use txutils::OutputPolicy;
-use {coin, fee};
+use {coin, fee, redeem};
 
 /// Transaction Builder composed of inputs, outputs
 #[derive(Clone)]
+#[cfg_attr(feature = "generic-serialization", derive(Serialize, Deserialize))]
 pub struct TxBuilder {
     inputs: Vec<(TxoPointer, Coin)>,
     outputs: Vec<TxOut>,
@@ -105,6 +110,21 @@ impl TxBuilder {
         self.inputs.len()
     }
 
+    /// Return the number of outputs in this builder
+    pub fn number_outputs(&self) -> usize {
+        self.outputs.len()
+    }
+
+    /// The inputs added so far, with their associated value
+    pub fn inputs(&self) -> &[(TxoPointer, Coin)] {
+        &self.inputs
+    }
+
+    /// The outputs added so far, including the ones added by an output policy
+    pub fn outputs(&self) -> &[TxOut] {
+        &self.outputs
+    }
+
     /// Add an input in a form of a txo pointer to the current state.
     ///
     /// Note that for calculation purpose, we need to know the
@@ -280,6 +300,7 @@ impl TxBuilder {
 
 /// Transaction finalized
 #[derive(Clone)]
+#[cfg_attr(feature = "generic-serialization", derive(Serialize, Deserialize))]
 pub struct TxFinalized {
     tx: Tx,
     witnesses: TxWitness,
@@ -307,6 +328,16 @@ impl TxFinalized {
         Ok(())
     }
 
+    /// Add the witness of the redeem key (ADA voucher) associated with the next input.
+    pub fn add_redeem_witness(
+        &mut self,
+        protocol_magic: ProtocolMagic,
+        key: &redeem::PrivateKey,
+    ) -> Result<()> {
+        let witness = TxInWitness::new_redeem_pk(protocol_magic, key, &self.tx.id());
+        self.add_witness(witness)
+    }
+
     pub fn make_txaux(self) -> Result<TxAux> {
         if self.witnesses.len() != self.tx.inputs.len() {
             return Err(Error::TxSignaturesMismatch);
@@ -320,6 +351,85 @@ impl TxFinalized {
     }
 }
 
+// the builder is serialized as the array of its inputs, the array of the
+// values of the inputs and the array of its outputs, to save and restore
+// a transaction in progress
+impl cbor_event::se::Serialize for TxBuilder {
+    fn serialize<'se, W: Write>(
+        &self,
+        serializer: &'se mut Serializer<W>,
+    ) -> cbor_event::Result<&'se mut Serializer<W>> {
+        serializer.write_array(cbor_event::Len::Len(3))?;
+        cbor_event::se::serialize_indefinite_array(self.inputs.iter().map(|i| &i.0), serializer)?;
+        cbor_event::se::serialize_indefinite_array(self.inputs.iter().map(|i| &i.1), serializer)?;
+        cbor_event::se::serialize_indefinite_array(self.outputs.iter(), serializer)
+    }
+}
+impl cbor_event::de::Deserialize for TxBuilder {
+    fn deserialize<R: BufRead>(raw: &mut Deserializer<R>) -> cbor_event::Result<Self> {
+        raw.tuple(3, "TxBuilder")?;
+        let inputs: Vec<TxoPointer> = cbor_event::de::Deserialize::deserialize(raw)?;
+        let values: Vec<Coin> = cbor_event::de::Deserialize::deserialize(raw)?;
+        let outputs = cbor_event::de::Deserialize::deserialize(raw)?;
+        if inputs.len() != values.len() {
+            return Err(cbor_event::Error::CustomError(format!(
+                "Invalid TxBuilder: {} inputs but {} values",
+                inputs.len(),
+                values.len()
+            )));
+        }
+        Ok(TxBuilder {
+            inputs: inputs.into_iter().zip(values).collect(),
+            outputs,
+        })
+    }
+}
+
+impl cbor_event::se::Serialize for TxFinalized {
+    fn serialize<'se, W: Write>(
+        &self,
+        serializer: &'se mut Serializer<W>,
+    ) -> cbor_event::Result<&'se mut Serializer<W>> {
+        serializer
+            .write_array(cbor_event::Len::Len(2))?
+            .serialize(&self.tx)?
+            .serialize(&self.witnesses)
+    }
+}
+impl cbor_event::de::Deserialize for TxFinalized {
+    fn deserialize<R: BufRead>(raw: &mut Deserializer<R>) -> cbor_event::Result<Self> {
+        raw.tuple(2, "TxFinalized")?;
+        let tx: Tx = cbor_event::de::Deserialize::deserialize(raw)?;
+        let witnesses: TxWitness = cbor_event::de::Deserialize::deserialize(raw)?;
+        if witnesses.len() > tx.inputs.len() {
+            return Err(cbor_event::Error::CustomError(format!(
+                "Invalid TxFinalized: {} witnesses for {} inputs",
+                witnesses.len(),
+                tx.inputs.len()
+            )));
+        }
+        Ok(TxFinalized { tx, witnesses })
+    }
+}
+
+/// Build the signed transaction redeeming the whole value of a redeem
+/// address (ADA voucher) UTxO to the given address, once the fee is paid.
+pub fn redeem<F: FeeAlgorithm>(
+    f: &F,
+    protocol_magic: ProtocolMagic,
+    key: &redeem::PrivateKey,
+    input: &TxoPointer,
+    value: Coin,
+    to: ExtendedAddr,
+) -> Result<TxAux> {
+    let mut builder = TxBuilder::new();
+    builder.add_input(input, value);
+    builder.add_output_policy(f, &OutputPolicy::One(to))?;
+    let mut finalized = TxFinalized::new(builder.make_tx()?);
+    finalized.add_redeem_witness(protocol_magic, key)?;
+    finalized.make_txaux()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -450,4 +560,48 @@ mod tests {
             assert!(build_finalize(builder).is_ok())
         }
     }
+
+    #[test]
+    fn txbuild_redeem() {
+        let protocol_magic = ProtocolMagic::default();
+        let key = redeem::PrivateKey::normalize_bytes([5; redeem::PRIVATEKEY_SIZE]);
+        let voucher = ExtendedAddr::new_redeem(key.public(), protocol_magic.into());
+        let (input, value) = fake_txopointer_val(1_000_000u32.into());
+        let alg = LinearFee::default();
+
+        let txaux = redeem(
+            &alg,
+            protocol_magic,
+            &key,
+            &input,
+            value,
+            decode_addr(RADDRS[2]),
+        )
+        .unwrap();
+        assert_eq!(txaux.tx.outputs.len(), 1);
+        assert!(txaux.witness[0].verify(protocol_magic, &voucher, &txaux.tx));
+        let fee = (value - txaux.tx.outputs[0].value).unwrap();
+        assert!(fee >= alg.calculate_for_txaux(&txaux).unwrap().to_coin());
+    }
+
+    #[test]
+    fn txbuild_cbor_roundtrip() {
+        let inputs = vec![fake_txopointer_val(300000u32.into())];
+        let outputs = vec![TxOut::new(decode_addr(RADDRS[1]), 8000u32.into())];
+        let builder = build_input_outputs(&inputs[..], &outputs[..]);
+
+        let bytes = cbor!(&builder).unwrap();
+        let mut raw = Deserializer::from(std::io::Cursor::new(bytes));
+        let decoded: TxBuilder = cbor_event::de::Deserialize::deserialize(&mut raw).unwrap();
+        assert_eq!(decoded.inputs(), builder.inputs());
+        assert_eq!(decoded.outputs(), builder.outputs());
+
+        let mut finalized = TxFinalized::new(builder.make_tx().unwrap());
+        finalized.add_witness(TxInWitness::fake()).unwrap();
+        let bytes = cbor!(&finalized).unwrap();
+        let mut raw = Deserializer::from(std::io::Cursor::new(bytes));
+        let decoded: TxFinalized = cbor_event::de::Deserialize::deserialize(&mut raw).unwrap();
+        assert_eq!(decoded.tx, finalized.tx);
+        assert_eq!(decoded.witnesses, finalized.witnesses);
+    }
 }
@@ -0,0 +1,255 @@
+//! Human readable amounts: ADA and Lovelace
+//!
+//! A `Coin` holds Lovelace, 1 ADA being 1 000 000 Lovelace. Amounts shown
+//! to or typed by the users are in ADA with up to 6 decimals. The rules
+//! are the same whatever the locale of the host:
+//!
+//! * the decimal separator is `.`, there is no thousands separator;
+//! * the amount may be followed by the unit, `ADA` or `lovelace`,
+//!   separated by one space;
+//! * a negative amount, an amount with more than 6 decimals or above
+//!   `MAX_COIN` is rejected, nothing is rounded.
+//!
+//! # Example
+//!
+//! ```
+//! use cardano::coin::Coin;
+//! use cardano::units::{self, Ada};
+//!
+//! let coin = units::parse_ada("12.345678 ADA").unwrap();
+//! assert_eq!(coin, Coin::new(12_345_678).unwrap());
+//! assert_eq!(units::parse_ada("12.5").unwrap(), Coin::new(12_500_000).unwrap());
+//! assert_eq!(format!("{}", Ada(coin)), "12.345678 ADA");
+//! ```
+
+use coin::{self, Coin};
+use std::{error, fmt, result, str};
+
+/// number of Lovelace in 1 ADA
+pub const LOVELACE_PER_ADA: u64 = 1_000_000;
+
+/// maximum number of decimals of an amount in ADA
+pub const ADA_DECIMALS: usize = 6;
+
+pub const ADA_UNIT: &'static str = "ADA";
+pub const LOVELACE_UNIT: &'static str = "lovelace";
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Error {
+    /// there is no digit to parse
+    Empty,
+    /// the character is not allowed in an amount
+    InvalidCharacter(char),
+    /// the amount has more decimals than the unit allows
+    TooManyDecimals(usize),
+    /// the amount is followed by an unknown unit
+    UnknownUnit(String),
+    /// the amount is not a valid coin value
+    CoinError(coin::Error),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Empty => write!(f, "No amount to parse"),
+            Error::InvalidCharacter(c) => write!(f, "Invalid character {:?} in the amount", c),
+            Error::TooManyDecimals(n) => write!(
+                f,
+                "Amount has {} decimals, the maximum is {}",
+                n, ADA_DECIMALS
+            ),
+            Error::UnknownUnit(unit) => write!(f, "Unknown unit {:?}", unit),
+            Error::CoinError(_) => write!(f, "Amount is not a valid coin value"),
+        }
+    }
+}
+impl error::Error for Error {
+    fn cause(&self) -> Option<&error::Error> {
+        match self {
+            Error::CoinError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+impl From<coin::Error> for Error {
+    fn from(e: coin::Error) -> Self {
+        Error::CoinError(e)
+    }
+}
+
+pub type Result<T> = result::Result<T, Error>;
+
+/// the value of the digits, checking they are all ASCII digits
+fn parse_digits(digits: &str) -> Result<u64> {
+    let mut value = 0u64;
+    for c in digits.chars() {
+        let digit = c.to_digit(10).ok_or(Error::InvalidCharacter(c))?;
+        value = value
+            .checked_mul(10)
+            .and_then(|value| value.checked_add(digit as u64))
+            .ok_or(Error::CoinError(coin::Error::OutOfBound(value)))?;
+    }
+    Ok(value)
+}
+
+/// split the amount from its unit, if any
+fn split_unit(s: &str) -> (&str, Option<&str>) {
+    let s = s.trim();
+    match s.find(' ') {
+        Some(index) => (&s[..index], Some(&s[index + 1..])),
+        None => (s, None),
+    }
+}
+
+/// convert whole ADA to Lovelace
+pub fn ada_to_lovelace(ada: u64) -> Result<Coin> {
+    let lovelace = ada
+        .checked_mul(LOVELACE_PER_ADA)
+        .ok_or(Error::CoinError(coin::Error::OutOfBound(ada)))?;
+    Ok(Coin::new(lovelace)?)
+}
+
+/// split the coin in whole ADA and the remaining Lovelace
+pub fn lovelace_to_ada(coin: Coin) -> (u64, u64) {
+    let lovelace = u64::from(coin);
+    (lovelace / LOVELACE_PER_ADA, lovelace % LOVELACE_PER_ADA)
+}
+
+/// parse an amount in ADA, e.g. `12.345678 ADA`, `0.5` or `3`
+pub fn parse_ada(s: &str) -> Result<Coin> {
+    let (amount, unit) = split_unit(s);
+    match unit {
+        None | Some(ADA_UNIT) => {}
+        Some(unit) => return Err(Error::UnknownUnit(unit.to_owned())),
+    }
+    let (integral, decimals) = match amount.find('.') {
+        Some(index) => (&amount[..index], &amount[index + 1..]),
+        None => (amount, ""),
+    };
+    if integral.is_empty() && decimals.is_empty() {
+        return Err(Error::Empty);
+    }
+    if decimals.len() > ADA_DECIMALS {
+        return Err(Error::TooManyDecimals(decimals.len()));
+    }
+    let ada = ada_to_lovelace(parse_digits(integral)?)?;
+    let fraction = parse_digits(decimals)? * 10u64.pow((ADA_DECIMALS - decimals.len()) as u32);
+    Ok((ada + Coin::new(fraction)?)?)
+}
+
+/// parse an amount in Lovelace, e.g. `12345678 lovelace` or `42`
+pub fn parse_lovelace(s: &str) -> Result<Coin> {
+    let (amount, unit) = split_unit(s);
+    match unit {
+        None | Some(LOVELACE_UNIT) => {}
+        Some(unit) => return Err(Error::UnknownUnit(unit.to_owned())),
+    }
+    if amount.is_empty() {
+        return Err(Error::Empty);
+    }
+    Ok(Coin::new(parse_digits(amount)?)?)
+}
+
+/// format the coin in ADA, with all its decimals: `12.345678 ADA`
+pub fn format_ada(coin: Coin) -> String {
+    format!("{}", Ada(coin))
+}
+
+/// format the coin in Lovelace: `12345678 lovelace`
+pub fn format_lovelace(coin: Coin) -> String {
+    format!("{} {}", u64::from(coin), LOVELACE_UNIT)
+}
+
+/// a coin displayed and parsed in ADA
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Ada(pub Coin);
+impl fmt::Display for Ada {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (ada, lovelace) = lovelace_to_ada(self.0);
+        write!(
+            f,
+            "{}.{:0width$} {}",
+            ada,
+            lovelace,
+            ADA_UNIT,
+            width = ADA_DECIMALS
+        )
+    }
+}
+impl str::FromStr for Ada {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        parse_ada(s).map(Ada)
+    }
+}
+impl From<Coin> for Ada {
+    fn from(coin: Coin) -> Self {
+        Ada(coin)
+    }
+}
+impl From<Ada> for Coin {
+    fn from(ada: Ada) -> Self {
+        ada.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use coin::MAX_COIN;
+
+    fn coin(v: u64) -> Coin {
+        Coin::new(v).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(parse_ada("12.345678 ADA"), Ok(coin(12_345_678)));
+        assert_eq!(parse_ada("12.345678"), Ok(coin(12_345_678)));
+        assert_eq!(parse_ada(" 12 "), Ok(coin(12_000_000)));
+        assert_eq!(parse_ada("0.000001"), Ok(coin(1)));
+        assert_eq!(parse_ada(".5"), Ok(coin(500_000)));
+        assert_eq!(parse_ada("5."), Ok(coin(5_000_000)));
+        assert_eq!(parse_ada("45000000000 ADA"), Ok(coin(MAX_COIN)));
+        assert_eq!(parse_lovelace("42 lovelace"), Ok(coin(42)));
+        assert_eq!(parse_lovelace("42"), Ok(coin(42)));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(parse_ada(""), Err(Error::Empty));
+        assert_eq!(parse_ada("."), Err(Error::Empty));
+        assert_eq!(parse_ada("1,5"), Err(Error::InvalidCharacter(',')));
+        assert_eq!(parse_ada("1.000,5"), Err(Error::InvalidCharacter(',')));
+        assert_eq!(parse_ada("-1"), Err(Error::InvalidCharacter('-')));
+        assert_eq!(parse_ada("1.2.3"), Err(Error::InvalidCharacter('.')));
+        assert_eq!(parse_ada("0.0000001"), Err(Error::TooManyDecimals(7)));
+        assert_eq!(
+            parse_ada("1 lovelace"),
+            Err(Error::UnknownUnit("lovelace".to_owned()))
+        );
+        assert_eq!(
+            parse_lovelace("1 ADA"),
+            Err(Error::UnknownUnit("ADA".to_owned()))
+        );
+        assert!(parse_ada("45000000000.000001").is_err());
+        assert!(parse_ada("99999999999999999999").is_err());
+        assert!(parse_lovelace("45000000000000001").is_err());
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!(format_ada(coin(12_345_678)), "12.345678 ADA");
+        assert_eq!(format_ada(coin(1)), "0.000001 ADA");
+        assert_eq!(format_ada(Coin::zero()), "0.000000 ADA");
+        assert_eq!(format_lovelace(coin(42)), "42 lovelace");
+        assert_eq!(lovelace_to_ada(coin(12_345_678)), (12, 345_678));
+        assert_eq!(ada_to_lovelace(3), Ok(coin(3_000_000)));
+    }
+
+    quickcheck! {
+        fn ada_roundtrip(value: u64) -> bool {
+            let coin = coin(value % (MAX_COIN + 1));
+            parse_ada(&format_ada(coin)) == Ok(coin)
+        }
+    }
+}
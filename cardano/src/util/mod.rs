@@ -2,8 +2,9 @@ pub mod base58;
 pub mod bits;
 pub mod diff_maps;
 pub mod hex;
-pub mod securemem;
 pub mod try_from_slice;
 
+pub use ed25519_bip32::securemem;
+
 #[cfg(test)]
 pub mod arbitrary;
@@ -167,6 +167,10 @@ impl<K> Account<K> {
             derivation_scheme,
         }
     }
+
+    pub fn derivation_scheme(&self) -> DerivationScheme {
+        self.derivation_scheme
+    }
 }
 impl Account<XPrv> {
     pub fn public(&self) -> Account<XPub> {
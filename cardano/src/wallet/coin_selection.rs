@@ -0,0 +1,129 @@
+//! coin selection: the choice of the UTxOs to spend for a payment
+//!
+//! The strategies are described by the [`SelectionPolicy`] of the wallet
+//! schemes and run the algorithms of the `input_selection` module.
+//!
+//! [`SelectionPolicy`]: ../scheme/enum.SelectionPolicy.html
+
+use fee::FeeAlgorithm;
+use input_selection::{self, InputSelectionAlgorithm, InputSelectionResult};
+use tx::TxOut;
+use txutils::{Input, OutputPolicy};
+
+use super::scheme::SelectionPolicy;
+
+/// select, from the given inputs, the ones to spend to pay the given outputs
+///
+/// The result holds the selected inputs, the change that will be paid
+/// to the `output_policy` and the fee of the resulting transaction once
+/// signed.
+pub trait CoinSelection {
+    fn select<Addressing, F>(
+        &self,
+        fee_algorithm: &F,
+        inputs: Vec<Input<Addressing>>,
+        outputs: Vec<TxOut>,
+        output_policy: &OutputPolicy,
+    ) -> input_selection::Result<InputSelectionResult<Addressing>>
+    where
+        Addressing: Clone,
+        F: FeeAlgorithm;
+}
+
+impl CoinSelection for SelectionPolicy {
+    fn select<Addressing, F>(
+        &self,
+        fee_algorithm: &F,
+        inputs: Vec<Input<Addressing>>,
+        outputs: Vec<TxOut>,
+        output_policy: &OutputPolicy,
+    ) -> input_selection::Result<InputSelectionResult<Addressing>>
+    where
+        Addressing: Clone,
+        F: FeeAlgorithm,
+    {
+        match self {
+            SelectionPolicy::FirstMatchFirst => input_selection::HeadFirst::from(inputs).compute(
+                fee_algorithm,
+                outputs,
+                output_policy,
+            ),
+            SelectionPolicy::LargestFirst => input_selection::LargestFirst::from(inputs).compute(
+                fee_algorithm,
+                outputs,
+                output_policy,
+            ),
+            SelectionPolicy::Blackjack(dust) => input_selection::Blackjack::new(*dust, inputs)
+                .compute(fee_algorithm, outputs, output_policy),
+            SelectionPolicy::RandomImprove => input_selection::RandomImprove::from(inputs).compute(
+                fee_algorithm,
+                outputs,
+                output_policy,
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use address::ExtendedAddr;
+    use coin::Coin;
+    use config::NetworkMagic;
+    use fee::LinearFee;
+    use hdwallet::{XPub, XPUB_SIZE};
+    use tx::{TxId, TxoPointer};
+
+    fn address(byte: u8) -> ExtendedAddr {
+        ExtendedAddr::new_simple(XPub::from_bytes([byte; XPUB_SIZE]), NetworkMagic::NoMagic)
+    }
+
+    fn input(index: u32, value: u64) -> Input<u32> {
+        let ptr = TxoPointer::new(TxId::new(&[0]), index);
+        Input::new(
+            ptr,
+            TxOut::new(address(1), Coin::new(value).unwrap()),
+            index,
+        )
+    }
+
+    #[test]
+    fn policies_select_enough_inputs() {
+        let inputs: Vec<_> = (0..10)
+            .map(|i| input(i, (i as u64 + 1) * 1_000_000))
+            .collect();
+        let outputs = vec![TxOut::new(address(2), Coin::new(4_500_000).unwrap())];
+        let policy = OutputPolicy::One(address(3));
+        let fee_algorithm = LinearFee::default();
+
+        for selection_policy in [
+            SelectionPolicy::LargestFirst,
+            SelectionPolicy::RandomImprove,
+        ]
+        .iter()
+        {
+            let result = selection_policy
+                .select(&fee_algorithm, inputs.clone(), outputs.clone(), &policy)
+                .unwrap();
+
+            let selected: u64 = result
+                .selected_inputs
+                .iter()
+                .map(|input| u64::from(input.value.value))
+                .sum();
+            let change = result.estimated_change.map(u64::from).unwrap_or(0);
+            let fee = u64::from(result.estimated_fees.to_coin());
+            assert_eq!(selected, 4_500_000 + change + fee);
+        }
+
+        let largest_first = SelectionPolicy::LargestFirst
+            .select(&fee_algorithm, inputs.clone(), outputs.clone(), &policy)
+            .unwrap();
+        let addressing: Vec<_> = largest_first
+            .selected_inputs
+            .iter()
+            .map(|i| i.addressing)
+            .collect();
+        assert_eq!(addressing, vec![9]);
+    }
+}
@@ -1,4 +1,9 @@
 pub mod bip44;
+pub mod coin_selection;
 pub mod keygen;
+pub mod offline;
 pub mod rindex;
 pub mod scheme;
+pub mod state;
+pub mod vectors;
+pub mod watch;
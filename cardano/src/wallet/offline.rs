@@ -0,0 +1,324 @@
+//! offline signing: an unsigned transaction with what is needed to sign it
+//!
+//! The online wallet (e.g. a [`WatchOnlyWallet`](../watch/struct.WatchOnlyWallet.html))
+//! builds the transaction and exports an `UnsignedTx`: the transaction, the
+//! outputs it spends and the derivation path of the key of each of them. The
+//! offline machine, holding the root private key but not the UTxOs, can show
+//! what is spent and sign it. The witnesses are then applied back to the
+//! `UnsignedTx` to get the signed transaction ready to be sent to the network.
+//!
+
+use cbor_event::{self, de::Deserializer, se::Serializer};
+use coin::{self, Coin};
+use config::ProtocolMagic;
+use hdpayload::Path;
+use hdwallet::{DerivationScheme, XPrv};
+use tx::{Tx, TxAux, TxId, TxInWitness, TxOut};
+use txbuild::{self, TxFinalized};
+use txutils::Input;
+
+use std::io::{BufRead, Write};
+use std::{error, fmt, result};
+
+#[derive(Debug)]
+pub enum Error {
+    /// the number of inputs of the transaction and the number of spent
+    /// outputs given
+    InputsMismatch(usize, usize),
+    /// the spent output given for the input at this index is not the one
+    /// the transaction refers to
+    InputPointerMismatch(usize),
+    /// the number of inputs of the transaction and the number of witnesses
+    /// given
+    WitnessesMismatch(usize, usize),
+    /// the witness of the input at this index does not match the address of
+    /// the spent output or does not sign the transaction
+    InvalidWitness(usize),
+    TxBuildError(txbuild::Error),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InputsMismatch(expected, got) => write!(
+                f,
+                "transaction has {} inputs but {} spent outputs are given",
+                expected, got
+            ),
+            Error::InputPointerMismatch(index) => write!(
+                f,
+                "spent output {} is not the one of the transaction's input",
+                index
+            ),
+            Error::WitnessesMismatch(expected, got) => write!(
+                f,
+                "transaction has {} inputs but {} witnesses are given",
+                expected, got
+            ),
+            Error::InvalidWitness(index) => write!(f, "invalid witness for the input {}", index),
+            Error::TxBuildError(_) => write!(f, "cannot finalize the transaction"),
+        }
+    }
+}
+impl error::Error for Error {
+    fn cause(&self) -> Option<&error::Error> {
+        match self {
+            Error::TxBuildError(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+impl From<txbuild::Error> for Error {
+    fn from(e: txbuild::Error) -> Self {
+        Error::TxBuildError(e)
+    }
+}
+
+pub type Result<T> = result::Result<T, Error>;
+
+/// the output spent by an input of the transaction and the derivation path,
+/// from the root key, of the key to sign it with
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "generic-serialization", derive(Serialize, Deserialize))]
+pub struct SignerInput {
+    pub value: TxOut,
+    pub path: Path,
+}
+impl SignerInput {
+    pub fn new(value: TxOut, path: Path) -> Self {
+        SignerInput { value, path }
+    }
+}
+impl cbor_event::se::Serialize for SignerInput {
+    fn serialize<'se, W: Write>(
+        &self,
+        serializer: &'se mut Serializer<W>,
+    ) -> cbor_event::Result<&'se mut Serializer<W>> {
+        serializer
+            .write_array(cbor_event::Len::Len(2))?
+            .serialize(&self.value)?
+            .serialize(&self.path)
+    }
+}
+impl cbor_event::de::Deserialize for SignerInput {
+    fn deserialize<R: BufRead>(reader: &mut Deserializer<R>) -> cbor_event::Result<Self> {
+        reader.tuple(2, "SignerInput")?;
+        let value = cbor_event::de::Deserialize::deserialize(reader)?;
+        let path = cbor_event::de::Deserialize::deserialize(reader)?;
+        Ok(SignerInput::new(value, path))
+    }
+}
+
+/// a transaction to be signed offline, with the spent output and the
+/// derivation path of each of its inputs, in the order of the inputs
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "generic-serialization", derive(Serialize, Deserialize))]
+pub struct UnsignedTx {
+    protocol_magic: ProtocolMagic,
+    tx: Tx,
+    inputs: Vec<SignerInput>,
+}
+impl UnsignedTx {
+    pub fn new(protocol_magic: ProtocolMagic, tx: Tx, inputs: Vec<SignerInput>) -> Result<Self> {
+        if tx.inputs.len() != inputs.len() {
+            return Err(Error::InputsMismatch(tx.inputs.len(), inputs.len()));
+        }
+        Ok(UnsignedTx {
+            protocol_magic,
+            tx,
+            inputs,
+        })
+    }
+
+    /// create the unsigned transaction from the wallet's inputs, in the
+    /// order of the transaction's inputs, `to_path` giving the derivation
+    /// path of their addressing (e.g. `bip44::Addressing::to_path`)
+    pub fn from_inputs<Addressing, F>(
+        protocol_magic: ProtocolMagic,
+        tx: Tx,
+        inputs: &[Input<Addressing>],
+        to_path: F,
+    ) -> Result<Self>
+    where
+        F: Fn(&Addressing) -> Path,
+    {
+        if tx.inputs.len() != inputs.len() {
+            return Err(Error::InputsMismatch(tx.inputs.len(), inputs.len()));
+        }
+        if let Some(index) = tx
+            .inputs
+            .iter()
+            .zip(inputs.iter())
+            .position(|(ptr, input)| ptr != &input.ptr)
+        {
+            return Err(Error::InputPointerMismatch(index));
+        }
+        let inputs = inputs
+            .iter()
+            .map(|input| SignerInput::new(input.value.clone(), to_path(&input.addressing)))
+            .collect();
+        UnsignedTx::new(protocol_magic, tx, inputs)
+    }
+
+    pub fn protocol_magic(&self) -> ProtocolMagic {
+        self.protocol_magic
+    }
+
+    pub fn tx(&self) -> &Tx {
+        &self.tx
+    }
+
+    pub fn id(&self) -> TxId {
+        self.tx.id()
+    }
+
+    pub fn inputs(&self) -> &[SignerInput] {
+        &self.inputs
+    }
+
+    /// the fee paid by the transaction: the spent outputs minus the outputs
+    pub fn fee(&self) -> coin::Result<Coin> {
+        let inputs = coin::sum_coins(self.inputs.iter().map(|input| input.value.value))?;
+        let outputs = coin::sum_coins(self.tx.outputs.iter().map(|output| output.value))?;
+        inputs - outputs
+    }
+
+    /// sign every input with the key derived from the root key along its
+    /// derivation path
+    pub fn sign(&self, root_key: &XPrv, derivation_scheme: DerivationScheme) -> Vec<TxInWitness> {
+        let txid = self.id();
+        self.inputs
+            .iter()
+            .map(|input| {
+                let key = input.path.iter().fold(root_key.clone(), |key, index| {
+                    key.derive(derivation_scheme, *index)
+                });
+                TxInWitness::new_extended_pk(self.protocol_magic, &key, &txid)
+            })
+            .collect()
+    }
+
+    /// apply the witnesses, produced offline and in the order of the inputs,
+    /// to get the signed transaction
+    ///
+    /// Every witness is checked against the address of the spent output and
+    /// the transaction.
+    pub fn apply_witnesses(self, witnesses: Vec<TxInWitness>) -> Result<TxAux> {
+        if witnesses.len() != self.inputs.len() {
+            return Err(Error::WitnessesMismatch(self.inputs.len(), witnesses.len()));
+        }
+        for (index, (input, witness)) in self.inputs.iter().zip(witnesses.iter()).enumerate() {
+            if !witness.verify(self.protocol_magic, &input.value.address, &self.tx) {
+                return Err(Error::InvalidWitness(index));
+            }
+        }
+        let mut finalized = TxFinalized::new(self.tx);
+        for witness in witnesses {
+            finalized.add_witness(witness)?;
+        }
+        Ok(finalized.make_txaux()?)
+    }
+}
+impl cbor_event::se::Serialize for UnsignedTx {
+    fn serialize<'se, W: Write>(
+        &self,
+        serializer: &'se mut Serializer<W>,
+    ) -> cbor_event::Result<&'se mut Serializer<W>> {
+        let serializer = serializer
+            .write_array(cbor_event::Len::Len(3))?
+            .serialize(&self.protocol_magic)?
+            .serialize(&self.tx)?;
+        cbor_event::se::serialize_fixed_array(self.inputs.iter(), serializer)
+    }
+}
+impl cbor_event::de::Deserialize for UnsignedTx {
+    fn deserialize<R: BufRead>(reader: &mut Deserializer<R>) -> cbor_event::Result<Self> {
+        reader.tuple(3, "UnsignedTx")?;
+        let protocol_magic = cbor_event::de::Deserialize::deserialize(reader)?;
+        let tx = cbor_event::de::Deserialize::deserialize(reader)?;
+        let inputs = cbor_event::de::Deserialize::deserialize(reader)?;
+        UnsignedTx::new(protocol_magic, tx, inputs)
+            .map_err(|e| cbor_event::Error::CustomError(format!("{}", e)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use bip::bip44::{BIP44_COIN_TYPE, BIP44_PURPOSE};
+    use config::NetworkMagic;
+    use hdwallet::XPRV_SIZE;
+    use std::io::Cursor;
+    use tx::TxoPointer;
+    use wallet::bip44::{self, AccountLevel, AddrType};
+    use wallet::scheme::Account;
+
+    fn root_key() -> XPrv {
+        XPrv::normalize_bytes([42; XPRV_SIZE])
+    }
+
+    fn unsigned_tx() -> UnsignedTx {
+        let root = root_key();
+        let scheme = DerivationScheme::V2;
+        let account = bip44::Account::new(
+            AccountLevel::from(
+                root.derive(scheme, BIP44_PURPOSE)
+                    .derive(scheme, BIP44_COIN_TYPE)
+                    .derive(scheme, 0x80000000),
+            ),
+            scheme,
+        );
+        let inputs: Vec<Input<bip44::Addressing>> = (0..2)
+            .map(|index| {
+                let addressing = bip44::Addressing::new(0, AddrType::External, index).unwrap();
+                let address = account
+                    .generate_addresses([(AddrType::External, index)].iter(), NetworkMagic::NoMagic)
+                    .remove(0);
+                Input::new(
+                    TxoPointer::new(TxId::new(&[index as u8]), 0),
+                    TxOut::new(address, Coin::new(10).unwrap()),
+                    addressing,
+                )
+            })
+            .collect();
+        let tx = Tx::new_with(
+            inputs.iter().map(|input| input.ptr.clone()).collect(),
+            vec![TxOut::new(
+                inputs[0].value.address.clone(),
+                Coin::new(15).unwrap(),
+            )],
+        );
+        UnsignedTx::from_inputs(ProtocolMagic::default(), tx, &inputs, |addressing| {
+            addressing.to_path()
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn cbor_roundtrip() {
+        let unsigned = unsigned_tx();
+        let bytes = cbor!(&unsigned).unwrap();
+        let decoded: UnsignedTx = Deserializer::from(Cursor::new(bytes))
+            .deserialize_complete()
+            .unwrap();
+        assert_eq!(decoded, unsigned);
+        assert_eq!(decoded.fee().unwrap(), Coin::new(5).unwrap());
+    }
+
+    #[test]
+    fn sign_offline() {
+        let unsigned = unsigned_tx();
+        let witnesses = unsigned.sign(&root_key(), DerivationScheme::V2);
+        let txaux = unsigned.clone().apply_witnesses(witnesses.clone()).unwrap();
+        assert_eq!(&txaux.tx, unsigned.tx());
+
+        let swapped = vec![witnesses[1].clone(), witnesses[0].clone()];
+        match unsigned.clone().apply_witnesses(swapped) {
+            Err(Error::InvalidWitness(0)) => {}
+            result => panic!("unexpected result {:?}", result),
+        }
+        match unsigned.apply_witnesses(vec![witnesses[0].clone()]) {
+            Err(Error::WitnessesMismatch(2, 1)) => {}
+            result => panic!("unexpected result {:?}", result),
+        }
+    }
+}
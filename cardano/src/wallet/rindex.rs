@@ -24,6 +24,12 @@ impl Addressing {
     pub fn new(account: u32, index: u32) -> Self {
         Addressing(account, index)
     }
+    pub fn account(&self) -> u32 {
+        self.0
+    }
+    pub fn index(&self) -> u32 {
+        self.1
+    }
 }
 impl ::std::fmt::Display for Addressing {
     fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
@@ -76,6 +82,18 @@ impl Wallet {
         Ok(Wallet::from_root_key(derivation_scheme, root_key))
     }
 
+    /// Compatibility with daedalus wallets, from the entropy of their
+    /// mnemonics (for example the one unscrambled from a paper wallet)
+    ///
+    /// See `from_daedalus_mnemonics` for the details of the process.
+    pub fn from_daedalus_entropy(
+        derivation_scheme: DerivationScheme,
+        entropy: &bip39::Entropy,
+    ) -> Result<Self> {
+        let root_key = RootKey::from_daedalus_entropy(derivation_scheme, entropy)?;
+        Ok(Wallet::from_root_key(derivation_scheme, root_key))
+    }
+
     /// test that the given address belongs to the wallet.
     ///
     /// This only possible because addresses from this wallet contain
@@ -360,7 +378,12 @@ impl RootKey {
     {
         let mnemonics = bip39::Mnemonics::from_string(dic, mnemonics_phrase)?;
         let entropy = bip39::Entropy::from_mnemonics(&mnemonics)?;
-
+        RootKey::from_daedalus_entropy(derivation_scheme, &entropy)
+    }
+    pub fn from_daedalus_entropy(
+        derivation_scheme: DerivationScheme,
+        entropy: &bip39::Entropy,
+    ) -> Result<Self> {
         let entropy_bytes = cbor_event::Value::Bytes(Vec::from(entropy.as_ref()));
         let entropy_cbor = cbor!(&entropy_bytes)?;
         let seed: Vec<u8> = {
@@ -6,11 +6,13 @@ use address::ExtendedAddr;
 use coin::Coin;
 use config::{NetworkMagic, ProtocolMagic};
 use fee::{self, FeeAlgorithm};
-use input_selection::{self, InputSelectionAlgorithm};
+use input_selection;
 use tx::{self, TxId, TxInWitness, TxOut};
 use txbuild::{self, TxBuilder, TxFinalized};
 use txutils::{Input, OutputPolicy};
 
+use super::coin_selection::CoinSelection;
+
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 #[cfg_attr(feature = "generic-serialization", derive(Serialize, Deserialize))]
 pub enum SelectionPolicy {
@@ -25,6 +27,10 @@ pub enum SelectionPolicy {
     /// the value in this setting represents the accepted dust threshold
     /// to lose or ignore in fees.
     Blackjack(Coin),
+
+    /// select random inputs to cover every output, then improve the
+    /// selection so the change is about the size of the payments
+    RandomImprove,
 }
 impl Default for SelectionPolicy {
     fn default() -> Self {
@@ -89,23 +95,9 @@ pub trait Wallet {
     {
         let fee_alg = fee::LinearFee::default();
 
-        let selection_result = match selection_policy {
-            SelectionPolicy::FirstMatchFirst => {
-                let inputs: Vec<Input<Self::Addressing>> = inputs.cloned().collect();
-                let mut alg = input_selection::HeadFirst::from(inputs);
-                alg.compute(&fee_alg, outputs.clone(), output_policy)?
-            }
-            SelectionPolicy::LargestFirst => {
-                let inputs: Vec<Input<Self::Addressing>> = inputs.cloned().collect();
-                let mut alg = input_selection::LargestFirst::from(inputs);
-                alg.compute(&fee_alg, outputs.clone(), output_policy)?
-            }
-            SelectionPolicy::Blackjack(dust) => {
-                let inputs: Vec<Input<Self::Addressing>> = inputs.cloned().collect();
-                let mut alg = input_selection::Blackjack::new(dust, inputs);
-                alg.compute(&fee_alg, outputs.clone(), output_policy)?
-            }
-        };
+        let inputs: Vec<Input<Self::Addressing>> = inputs.cloned().collect();
+        let selection_result =
+            selection_policy.select(&fee_alg, inputs, outputs.clone(), output_policy)?;
 
         let mut txbuilder = TxBuilder::new();
         for input in selection_result.selected_inputs.iter() {
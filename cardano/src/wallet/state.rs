@@ -0,0 +1,322 @@
+//! wallet state following the blockchain: the UTxOs of the wallet's
+//! addresses, with checkpoints to recover from the rollbacks of the chain
+//!
+//! A checkpoint (a snapshot of the state) is kept every `checkpoint_interval`
+//! blocks. When the chain reorganizes, `rollback_to` restores the most
+//! recent checkpoint which is an ancestor of the fork point, and the wallet
+//! only needs to apply again the blocks following this checkpoint instead of
+//! rescanning the chain from the genesis.
+//!
+
+use address::ExtendedAddr;
+use block::{Block, BlockDate, HeaderHash};
+use tx::{Tx, TxoPointer};
+use txutils::Input;
+
+use std::collections::{BTreeMap, VecDeque};
+use std::{error, fmt, result};
+
+/// the UTxOs of the wallet, with the wallet's addressing of their address
+pub type Utxos<Addressing> = BTreeMap<TxoPointer, Input<Addressing>>;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// the block does not follow the tip of the state: the expected parent
+    /// (the tip) and the parent of the block
+    InvalidParent(HeaderHash, HeaderHash),
+    /// the block is not one of the blocks the state can roll back to
+    UnknownBlock(HeaderHash),
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidParent(expected, got) => write!(
+                f,
+                "block does not follow the wallet's tip {}, its parent is {}",
+                expected, got
+            ),
+            Error::UnknownBlock(hash) => write!(
+                f,
+                "cannot roll back to the block {}, it is unknown or older than the checkpoints",
+                hash
+            ),
+        }
+    }
+}
+impl error::Error for Error {}
+
+pub type Result<T> = result::Result<T, Error>;
+
+/// the state of the wallet as of a given block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checkpoint<Addressing> {
+    pub block: HeaderHash,
+    /// `None` before the first block of the chain
+    pub date: Option<BlockDate>,
+    pub chain_length: u64,
+    pub utxos: Utxos<Addressing>,
+}
+
+#[derive(Debug, Clone)]
+pub struct State<Addressing> {
+    checkpoint_interval: u64,
+    max_checkpoints: usize,
+    tip: Checkpoint<Addressing>,
+    /// from the oldest to the most recent
+    checkpoints: VecDeque<Checkpoint<Addressing>>,
+    /// the chain length and the hash of the blocks applied after the
+    /// oldest checkpoint
+    blocks: VecDeque<(u64, HeaderHash)>,
+}
+
+impl<Addressing: Clone> State<Addressing> {
+    /// the state at the beginning of the chain (the `genesis_prev` hash of
+    /// the genesis data) with the wallet's UTxOs of the genesis data
+    ///
+    /// A checkpoint is kept every `checkpoint_interval` blocks (typically
+    /// the security parameter `k`) and at most `max_checkpoints` of them
+    /// are kept.
+    pub fn new(
+        genesis_prev: HeaderHash,
+        utxos: Utxos<Addressing>,
+        checkpoint_interval: u64,
+        max_checkpoints: usize,
+    ) -> Self {
+        assert!(checkpoint_interval > 0);
+        assert!(max_checkpoints > 0);
+        let tip = Checkpoint {
+            block: genesis_prev,
+            date: None,
+            chain_length: 0,
+            utxos,
+        };
+        let mut checkpoints = VecDeque::new();
+        checkpoints.push_back(tip.clone());
+        State {
+            checkpoint_interval,
+            max_checkpoints,
+            tip,
+            checkpoints,
+            blocks: VecDeque::new(),
+        }
+    }
+
+    /// the current state of the wallet
+    pub fn tip(&self) -> &Checkpoint<Addressing> {
+        &self.tip
+    }
+
+    pub fn utxos(&self) -> &Utxos<Addressing> {
+        &self.tip.utxos
+    }
+
+    /// the checkpoints the state can be restored to, from the oldest to
+    /// the most recent
+    pub fn checkpoints(&self) -> impl Iterator<Item = &Checkpoint<Addressing>> {
+        self.checkpoints.iter()
+    }
+
+    /// apply the block following the tip
+    ///
+    /// `lookup` returns the wallet's addressing of the addresses belonging
+    /// to the wallet, the outputs to the other addresses are ignored.
+    pub fn apply_block<F>(&mut self, block: &Block, lookup: F) -> Result<()>
+    where
+        F: Fn(&ExtendedAddr) -> Option<Addressing>,
+    {
+        let header = block.header();
+        let txs = match block {
+            Block::BoundaryBlock(_) => Vec::new(),
+            Block::MainBlock(blk) => blk.body.tx.iter().map(|txaux| &txaux.tx).collect(),
+        };
+        self.apply(
+            header.compute_hash(),
+            header.previous_header(),
+            header.blockdate(),
+            &txs,
+            lookup,
+        )
+    }
+
+    pub(crate) fn apply<F>(
+        &mut self,
+        hash: HeaderHash,
+        parent: HeaderHash,
+        date: BlockDate,
+        txs: &[&Tx],
+        lookup: F,
+    ) -> Result<()>
+    where
+        F: Fn(&ExtendedAddr) -> Option<Addressing>,
+    {
+        if parent != self.tip.block {
+            return Err(Error::InvalidParent(self.tip.block.clone(), parent));
+        }
+
+        for tx in txs {
+            for input in tx.inputs.iter() {
+                self.tip.utxos.remove(input);
+            }
+            let id = tx.id();
+            for (index, output) in tx.outputs.iter().enumerate() {
+                if let Some(addressing) = lookup(&output.address) {
+                    let ptr = TxoPointer::new(id, index as u32);
+                    self.tip
+                        .utxos
+                        .insert(ptr.clone(), Input::new(ptr, output.clone(), addressing));
+                }
+            }
+        }
+        self.tip.block = hash.clone();
+        self.tip.date = Some(date);
+        self.tip.chain_length += 1;
+        self.blocks.push_back((self.tip.chain_length, hash));
+
+        if self.tip.chain_length % self.checkpoint_interval == 0 {
+            self.checkpoints.push_back(self.tip.clone());
+            if self.checkpoints.len() > self.max_checkpoints {
+                self.checkpoints.pop_front();
+                let oldest = self.checkpoints[0].chain_length;
+                while self
+                    .blocks
+                    .front()
+                    .map_or(false, |(length, _)| *length <= oldest)
+                {
+                    self.blocks.pop_front();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// roll back the state to the given block, typically the fork point of
+    /// a reorganization of the chain
+    ///
+    /// The state is restored to the most recent checkpoint which is not
+    /// after the block, which is returned: the blocks following it must be
+    /// applied again.
+    pub fn rollback_to(&mut self, hash: &HeaderHash) -> Result<&Checkpoint<Addressing>> {
+        let chain_length = self
+            .blocks
+            .iter()
+            .find(|(_, block)| block == hash)
+            .map(|(length, _)| *length)
+            .or_else(|| {
+                self.checkpoints
+                    .iter()
+                    .find(|checkpoint| &checkpoint.block == hash)
+                    .map(|checkpoint| checkpoint.chain_length)
+            })
+            .ok_or_else(|| Error::UnknownBlock(hash.clone()))?;
+
+        while self.checkpoints.len() > 1
+            && self.checkpoints.back().unwrap().chain_length > chain_length
+        {
+            self.checkpoints.pop_back();
+        }
+        let checkpoint = self.checkpoints.back().unwrap().clone();
+        while self
+            .blocks
+            .back()
+            .map_or(false, |(length, _)| *length > checkpoint.chain_length)
+        {
+            self.blocks.pop_back();
+        }
+        self.tip = checkpoint;
+        Ok(&self.tip)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use block::EpochSlotId;
+    use coin::Coin;
+    use config::NetworkMagic;
+    use hdwallet::{XPub, XPUB_SIZE};
+    use tx::{TxId, TxOut};
+
+    fn address(byte: u8) -> ExtendedAddr {
+        ExtendedAddr::new_simple(XPub::from_bytes([byte; XPUB_SIZE]), NetworkMagic::NoMagic)
+    }
+
+    fn lookup(address: &ExtendedAddr) -> Option<u8> {
+        (1..3).find(|byte| &self::address(*byte) == address)
+    }
+
+    fn hash(n: u64) -> HeaderHash {
+        HeaderHash::new(&n.to_be_bytes())
+    }
+
+    fn date(n: u64) -> BlockDate {
+        BlockDate::Normal(EpochSlotId {
+            epoch: 0,
+            slotid: n as u16,
+        })
+    }
+
+    /// apply the blocks `from..to`, the block `n` paying 1 coin to one of
+    /// the wallet's addresses and to an other address
+    fn apply_blocks(state: &mut State<u8>, from: u64, to: u64) {
+        for n in from..to {
+            let tx = Tx::new_with(
+                vec![TxoPointer::new(TxId::new(&[0]), n as u32)],
+                vec![
+                    TxOut::new(address(1 + (n % 2) as u8), Coin::unit()),
+                    TxOut::new(address(3), Coin::unit()),
+                ],
+            );
+            state
+                .apply(hash(n), hash(n - 1), date(n), &[&tx], lookup)
+                .unwrap();
+        }
+    }
+
+    #[test]
+    fn utxos_of_the_wallet() {
+        let mut state = State::new(hash(0), Utxos::new(), 10, 3);
+        apply_blocks(&mut state, 1, 6);
+        assert_eq!(state.tip().chain_length, 5);
+        assert_eq!(state.utxos().len(), 5);
+        assert!(state
+            .utxos()
+            .values()
+            .all(|input| lookup(&input.value.address) == Some(input.addressing)));
+    }
+
+    #[test]
+    fn invalid_parent() {
+        let mut state = State::new(hash(0), Utxos::new(), 10, 3);
+        assert_eq!(
+            state.apply(hash(2), hash(1), date(2), &[], lookup),
+            Err(Error::InvalidParent(hash(0), hash(1)))
+        );
+    }
+
+    #[test]
+    fn rollback_to_checkpoint() {
+        let mut state = State::new(hash(0), Utxos::new(), 4, 3);
+        apply_blocks(&mut state, 1, 11);
+        let checkpoints: Vec<_> = state.checkpoints().map(|c| c.chain_length).collect();
+        assert_eq!(checkpoints, vec![0, 4, 8]);
+
+        // the block 6 is after the checkpoint 4
+        let expected = state.checkpoints[1].clone();
+        assert_eq!(state.rollback_to(&hash(6)).unwrap(), &expected);
+        assert_eq!(state.tip().block, hash(4));
+        assert_eq!(state.utxos().len(), 4);
+
+        // the blocks 5 to 10 are not known anymore, the chain goes on from 4
+        assert_eq!(
+            state.rollback_to(&hash(6)),
+            Err(Error::UnknownBlock(hash(6)))
+        );
+        apply_blocks(&mut state, 5, 13);
+        let checkpoints: Vec<_> = state.checkpoints().map(|c| c.chain_length).collect();
+        assert_eq!(checkpoints, vec![4, 8, 12]);
+        assert_eq!(
+            state.rollback_to(&hash(2)),
+            Err(Error::UnknownBlock(hash(2)))
+        );
+    }
+}
@@ -0,0 +1,366 @@
+//! Deterministic test vectors of the wallets' keys and addresses
+//!
+//! From a mnemonic phrase, a vector gives the root key of the wallet, the
+//! keys derived from it and their addresses, for the random index wallets
+//! (Daedalus) and the BIP44 wallets, with both derivation schemes.
+//!
+//! The vectors are emitted in JSON so other implementations (JavaScript,
+//! Java...) can check they derive the same keys and addresses:
+//!
+//! ```text
+//! cargo run -p cardano --example test-vectors > test-vectors/vectors/cardano_wallets.json
+//! ```
+
+use address::ExtendedAddr;
+use bip::bip39::{self, dictionary};
+use bip::bip44::{BIP44_COIN_TYPE, BIP44_PURPOSE};
+use config::NetworkMagic;
+use hdwallet::{DerivationIndex, DerivationScheme, XPrv, XPub, XPRV_SIZE};
+use std::fmt::Write;
+
+use super::{keygen, rindex};
+
+const HARDENED: DerivationIndex = 0x8000_0000;
+
+/// the kind of wallet the keys and addresses are derived for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WalletKind {
+    /// the root key is generated from the entropy of the mnemonics only,
+    /// the derivation path (account, index) is encrypted in the addresses
+    RandomIndex,
+    /// the root key is generated from the entropy of the mnemonics and
+    /// the password, the addresses do not hold the derivation path
+    Bip44,
+}
+impl WalletKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WalletKind::RandomIndex => "random-index",
+            WalletKind::Bip44 => "bip44",
+        }
+    }
+}
+
+/// the keys and addresses of a wallet, derived from its mnemonics
+pub struct WalletVector {
+    pub name: String,
+    pub kind: WalletKind,
+    pub derivation_scheme: DerivationScheme,
+    /// english mnemonics
+    pub mnemonics: String,
+    /// only used by the BIP44 wallets
+    pub password: String,
+    pub root_key: XPrv,
+    pub addresses: Vec<AddressVector>,
+}
+
+/// a key derived from the root key of the wallet and its address
+pub struct AddressVector {
+    /// the derivation path from the root key
+    pub path: Vec<DerivationIndex>,
+    pub private_key: XPrv,
+    pub public_key: XPub,
+    pub address: ExtendedAddr,
+}
+
+impl WalletVector {
+    /// derive the keys and the mainnet addresses at the given paths
+    ///
+    /// # panics
+    ///
+    /// the paths of the random index wallets are expected to be of 2
+    /// indices (account and index), this function panics otherwise.
+    pub fn generate(
+        name: &str,
+        kind: WalletKind,
+        derivation_scheme: DerivationScheme,
+        mnemonics: &str,
+        password: &str,
+        paths: &[Vec<DerivationIndex>],
+    ) -> bip39::Result<Self> {
+        let mnemonics_phrase = bip39::Mnemonics::from_string(&dictionary::ENGLISH, mnemonics)?;
+        let entropy = bip39::Entropy::from_mnemonics(&mnemonics_phrase)?;
+
+        let (root_key, addresses) = match kind {
+            WalletKind::RandomIndex => {
+                let root_key = rindex::RootKey::from_daedalus_entropy(derivation_scheme, &entropy)
+                    .expect("serialize the entropy in cbor");
+                let generator = root_key.address_generator();
+                let addresses = paths
+                    .iter()
+                    .map(|path| {
+                        assert!(path.len() == 2, "expecting an account and an index");
+                        let addressing = rindex::Addressing::new(path[0], path[1]);
+                        let private_key = generator.key(&addressing);
+                        AddressVector {
+                            path: path.clone(),
+                            public_key: private_key.public(),
+                            private_key,
+                            address: generator.address(&addressing, NetworkMagic::NoMagic),
+                        }
+                    })
+                    .collect();
+                (root_key.into_xprv(), addresses)
+            }
+            WalletKind::Bip44 => {
+                let mut seed = [0u8; XPRV_SIZE];
+                keygen::generate_seed(&entropy, password.as_bytes(), &mut seed);
+                let root_key = XPrv::normalize_bytes(seed);
+                let addresses = paths
+                    .iter()
+                    .map(|path| {
+                        let private_key = path.iter().fold(root_key.clone(), |key, index| {
+                            key.derive(derivation_scheme, *index)
+                        });
+                        let public_key = private_key.public();
+                        AddressVector {
+                            path: path.clone(),
+                            address: ExtendedAddr::new_simple(
+                                public_key.clone(),
+                                NetworkMagic::NoMagic,
+                            ),
+                            public_key,
+                            private_key,
+                        }
+                    })
+                    .collect();
+                (root_key, addresses)
+            }
+        };
+
+        Ok(WalletVector {
+            name: name.to_owned(),
+            kind,
+            derivation_scheme,
+            mnemonics: mnemonics.to_owned(),
+            password: password.to_owned(),
+            root_key,
+            addresses,
+        })
+    }
+}
+
+/// the mnemonics of the vectors and the password of their BIP44 wallets
+const MNEMONICS: [(&str, &str); 3] = [
+    (
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+        "",
+    ),
+    (
+        "edge club wrap where juice nephew whip entry cover bullet cause jeans",
+        "password",
+    ),
+    (
+        "letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic avoid letter advice cage absurd amount doctor acoustic bless",
+        "TREZOR",
+    ),
+];
+
+fn random_index_paths() -> Vec<Vec<DerivationIndex>> {
+    vec![
+        vec![HARDENED, HARDENED],
+        vec![HARDENED, HARDENED | 1],
+        vec![HARDENED | 1, HARDENED],
+    ]
+}
+
+fn bip44_paths() -> Vec<Vec<DerivationIndex>> {
+    let account = |account: u32, change: u32, index: u32| {
+        vec![
+            BIP44_PURPOSE,
+            BIP44_COIN_TYPE,
+            HARDENED | account,
+            change,
+            index,
+        ]
+    };
+    vec![
+        account(0, 0, 0),
+        account(0, 0, 1),
+        account(0, 1, 0),
+        account(1, 0, 0),
+    ]
+}
+
+fn scheme_str(derivation_scheme: DerivationScheme) -> &'static str {
+    match derivation_scheme {
+        DerivationScheme::V1 => "v1",
+        DerivationScheme::V2 => "v2",
+    }
+}
+
+/// the test vectors: every mnemonics with both kinds of wallets and both
+/// derivation schemes
+pub fn vectors() -> Vec<WalletVector> {
+    let mut vectors = Vec::new();
+    for kind in &[WalletKind::RandomIndex, WalletKind::Bip44] {
+        for derivation_scheme in &[DerivationScheme::V1, DerivationScheme::V2] {
+            for (i, (mnemonics, password)) in MNEMONICS.iter().enumerate() {
+                let (password, paths) = match kind {
+                    WalletKind::RandomIndex => ("", random_index_paths()),
+                    WalletKind::Bip44 => (*password, bip44_paths()),
+                };
+                let name = format!("{}-{}-{}", kind.as_str(), scheme_str(*derivation_scheme), i);
+                let vector = WalletVector::generate(
+                    &name,
+                    *kind,
+                    *derivation_scheme,
+                    mnemonics,
+                    password,
+                    &paths,
+                )
+                .expect("valid english mnemonics");
+                vectors.push(vector);
+            }
+        }
+    }
+    vectors
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => write!(out, "\\u{:04x}", c as u32).unwrap(),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// emit the vectors in JSON, the keys are hex encoded and the addresses
+/// are in base58
+pub fn to_json(vectors: &[WalletVector]) -> String {
+    let mut out = String::new();
+    out.push_str("[\n");
+    for (i, vector) in vectors.iter().enumerate() {
+        out.push_str("  {\n");
+        writeln!(out, "    \"name\": {},", json_string(&vector.name)).unwrap();
+        writeln!(out, "    \"wallet\": \"{}\",", vector.kind.as_str()).unwrap();
+        writeln!(
+            out,
+            "    \"derivation_scheme\": \"{}\",",
+            scheme_str(vector.derivation_scheme)
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "    \"mnemonics\": {},",
+            json_string(&vector.mnemonics)
+        )
+        .unwrap();
+        writeln!(out, "    \"password\": {},", json_string(&vector.password)).unwrap();
+        writeln!(out, "    \"root_key\": \"{}\",", vector.root_key).unwrap();
+        out.push_str("    \"addresses\": [\n");
+        for (j, address) in vector.addresses.iter().enumerate() {
+            let path: Vec<String> = address.path.iter().map(|i| i.to_string()).collect();
+            out.push_str("      {\n");
+            writeln!(out, "        \"path\": [{}],", path.join(", ")).unwrap();
+            writeln!(out, "        \"private_key\": \"{}\",", address.private_key).unwrap();
+            writeln!(out, "        \"public_key\": \"{}\",", address.public_key).unwrap();
+            writeln!(out, "        \"base58\": \"{}\"", address.address).unwrap();
+            out.push_str("      }");
+            out.push_str(if j + 1 < vector.addresses.len() {
+                ",\n"
+            } else {
+                "\n"
+            });
+        }
+        out.push_str("    ]\n");
+        out.push_str("  }");
+        out.push_str(if i + 1 < vectors.len() { ",\n" } else { "\n" });
+    }
+    out.push_str("]\n");
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn wallet_kind(wallet: &str) -> WalletKind {
+        match wallet {
+            "random-index" => WalletKind::RandomIndex,
+            "bip44" => WalletKind::Bip44,
+            _ => panic!("unknown wallet: {}", wallet),
+        }
+    }
+
+    fn derivation_scheme(scheme: &str) -> DerivationScheme {
+        match scheme {
+            "v1" => DerivationScheme::V1,
+            "v2" => DerivationScheme::V2,
+            _ => panic!("unknown derivation scheme: {}", scheme),
+        }
+    }
+
+    #[test]
+    fn wallet_vectors() {
+        for vector in ::test_vectors::cardano_wallets() {
+            let paths: Vec<_> = vector.addresses.iter().map(|a| a.path.clone()).collect();
+            let generated = WalletVector::generate(
+                &vector.name,
+                wallet_kind(&vector.wallet),
+                derivation_scheme(&vector.derivation_scheme),
+                &vector.mnemonics,
+                &vector.password,
+                &paths,
+            )
+            .unwrap();
+
+            assert_eq!(
+                generated.root_key.as_ref(),
+                &vector.root_key[..],
+                "root key of {}",
+                vector.name
+            );
+            assert_eq!(generated.addresses.len(), vector.addresses.len());
+            for (address, expected) in generated.addresses.iter().zip(vector.addresses.iter()) {
+                assert_eq!(
+                    address.private_key.as_ref(),
+                    &expected.private_key[..],
+                    "private key of {} at {:?}",
+                    vector.name,
+                    expected.path
+                );
+                assert_eq!(
+                    address.public_key.as_ref(),
+                    &expected.public_key[..],
+                    "public key of {} at {:?}",
+                    vector.name,
+                    expected.path
+                );
+                assert_eq!(
+                    address.address.to_string(),
+                    expected.base58,
+                    "address of {} at {:?}",
+                    vector.name,
+                    expected.path
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn emitted_vectors_are_the_checked_in_ones() {
+        let emitted: Vec<::test_vectors::CardanoWallet> =
+            ::serde_json::from_str(&to_json(&vectors())).unwrap();
+        let checked_in = ::test_vectors::cardano_wallets();
+
+        assert_eq!(emitted.len(), checked_in.len());
+        for (emitted, checked_in) in emitted.iter().zip(checked_in.iter()) {
+            assert_eq!(emitted.name, checked_in.name);
+            assert_eq!(emitted.mnemonics, checked_in.mnemonics);
+            assert_eq!(emitted.password, checked_in.password);
+            assert_eq!(emitted.root_key, checked_in.root_key);
+            let addresses = |vector: &::test_vectors::CardanoWallet| -> Vec<String> {
+                vector.addresses.iter().map(|a| a.base58.clone()).collect()
+            };
+            assert_eq!(addresses(emitted), addresses(checked_in));
+        }
+    }
+}
@@ -0,0 +1,310 @@
+//! watch-only wallet: tracks the balance and the transactions of a BIP44
+//! account from its extended public key only
+//!
+//! The addresses of the external and internal chains are derived with soft
+//! derivation from the account public key, `gap_limit` addresses ahead of
+//! the last used one. No private key is ever held: the transactions are to
+//! be signed elsewhere (e.g. on an air-gapped device) with the account
+//! private key.
+//!
+
+use address::{Addr, ExtendedAddr};
+use bip::bip44::BIP44_SOFT_UPPER_BOUND;
+use block::{Block, BlockDate, HeaderHash};
+use coin::{self, Coin};
+use config::NetworkMagic;
+use hdwallet::{DerivationScheme, XPub};
+use tx::{Tx, TxId};
+
+use std::collections::BTreeMap;
+
+use super::bip44::{self, AccountLevel, AddrType};
+use super::scheme::Account as _;
+use super::state::{self, Checkpoint, State, Utxos};
+
+/// the number of unused addresses derived after the last used one, on
+/// each chain, as recommended by BIP44
+pub const DEFAULT_GAP_LIMIT: u32 = 20;
+
+/// the addressing of the account's addresses: the chain and the index
+pub type Addressing = (AddrType, u32);
+
+pub struct WatchOnlyWallet {
+    account: bip44::Account<XPub>,
+    network_magic: NetworkMagic,
+    gap_limit: u32,
+    addresses: BTreeMap<Addr, Addressing>,
+    /// the number of derived addresses of the external and internal chains
+    derived: [u32; 2],
+    /// the number of used addresses of the external and internal chains
+    used: [u32; 2],
+    state: State<Addressing>,
+    /// the chain length of the block including the transaction
+    transactions: Vec<(u64, TxId)>,
+}
+
+fn chain_index(addr_type: AddrType) -> usize {
+    match addr_type {
+        AddrType::External => 0,
+        AddrType::Internal => 1,
+    }
+}
+
+impl WatchOnlyWallet {
+    /// create the watch-only wallet of the account public key (the key at
+    /// the derivation path `m/44'/1815'/account'`)
+    ///
+    /// The `state` gives the beginning of the chain to follow and how often
+    /// it is checkpointed.
+    pub fn new(
+        account_key: XPub,
+        derivation_scheme: DerivationScheme,
+        network_magic: NetworkMagic,
+        gap_limit: u32,
+        state: State<Addressing>,
+    ) -> Self {
+        let account = bip44::Account::new(AccountLevel::from(account_key), derivation_scheme);
+        WatchOnlyWallet::from_account(account, network_magic, gap_limit, state)
+    }
+
+    /// create the watch-only wallet of the public account
+    pub fn from_account(
+        account: bip44::Account<XPub>,
+        network_magic: NetworkMagic,
+        gap_limit: u32,
+        state: State<Addressing>,
+    ) -> Self {
+        assert!(gap_limit > 0);
+        let mut wallet = WatchOnlyWallet {
+            account,
+            network_magic,
+            gap_limit,
+            addresses: BTreeMap::new(),
+            derived: [0, 0],
+            used: [0, 0],
+            state,
+            transactions: Vec::new(),
+        };
+        wallet.derive_addresses();
+        wallet
+    }
+
+    /// derive the addresses up to `gap_limit` addresses after the last used
+    /// one, on both chains
+    fn derive_addresses(&mut self) {
+        for addr_type in [AddrType::External, AddrType::Internal].iter() {
+            let chain = chain_index(*addr_type);
+            let target = (self.used[chain] + self.gap_limit).min(BIP44_SOFT_UPPER_BOUND);
+            let addressings: Vec<Addressing> = (self.derived[chain]..target)
+                .map(|index| (*addr_type, index))
+                .collect();
+            let addresses = self
+                .account
+                .generate_addresses(addressings.iter(), self.network_magic);
+            for (address, addressing) in addresses.into_iter().zip(addressings) {
+                self.addresses.insert(Addr::from(address), addressing);
+            }
+            self.derived[chain] = self.derived[chain].max(target);
+        }
+    }
+
+    /// the addressing of the address if it belongs to the account (among
+    /// the derived addresses)
+    pub fn lookup(&self, address: &ExtendedAddr) -> Option<Addressing> {
+        self.addresses.get(&Addr::from(address.clone())).cloned()
+    }
+
+    /// the address of the given chain following the last used one
+    pub fn next_unused_address(&self, addr_type: AddrType) -> ExtendedAddr {
+        let addressing = (addr_type, self.used[chain_index(addr_type)]);
+        self.account
+            .generate_addresses([addressing].iter(), self.network_magic)
+            .remove(0)
+    }
+
+    pub fn utxos(&self) -> &Utxos<Addressing> {
+        self.state.utxos()
+    }
+
+    pub fn balance(&self) -> coin::Result<Coin> {
+        coin::sum_coins(self.utxos().values().map(|input| input.value.value))
+    }
+
+    /// the transactions paying to or spending from the account, in the
+    /// order of the chain
+    pub fn transactions(&self) -> impl Iterator<Item = &TxId> {
+        self.transactions.iter().map(|(_, txid)| txid)
+    }
+
+    pub fn tip(&self) -> &Checkpoint<Addressing> {
+        self.state.tip()
+    }
+
+    /// apply the block following the tip
+    pub fn apply_block(&mut self, block: &Block) -> state::Result<()> {
+        let header = block.header();
+        let txs = match block {
+            Block::BoundaryBlock(_) => Vec::new(),
+            Block::MainBlock(blk) => blk.body.tx.iter().map(|txaux| &txaux.tx).collect(),
+        };
+        self.apply(
+            header.compute_hash(),
+            header.previous_header(),
+            header.blockdate(),
+            &txs,
+        )
+    }
+
+    fn apply(
+        &mut self,
+        hash: HeaderHash,
+        parent: HeaderHash,
+        date: BlockDate,
+        txs: &[&Tx],
+    ) -> state::Result<()> {
+        let chain_length = self.state.tip().chain_length + 1;
+        let mut related = Vec::new();
+        for tx in txs {
+            let mut is_related = tx
+                .inputs
+                .iter()
+                .any(|input| self.state.utxos().contains_key(input));
+            for output in tx.outputs.iter() {
+                if let Some((addr_type, index)) = self.lookup(&output.address) {
+                    let used = &mut self.used[chain_index(addr_type)];
+                    *used = (*used).max(index + 1);
+                    is_related = true;
+                }
+            }
+            if is_related {
+                related.push((chain_length, tx.id()));
+            }
+        }
+
+        {
+            let addresses = &self.addresses;
+            self.state.apply(hash, parent, date, txs, |address| {
+                addresses.get(&Addr::from(address.clone())).cloned()
+            })?;
+        }
+        self.transactions.extend(related);
+        self.derive_addresses();
+        Ok(())
+    }
+
+    /// roll back to the given block, see
+    /// [`State::rollback_to`](../state/struct.State.html#method.rollback_to):
+    /// the blocks following the returned checkpoint must be applied again
+    pub fn rollback_to(&mut self, hash: &HeaderHash) -> state::Result<&Checkpoint<Addressing>> {
+        let chain_length = self.state.rollback_to(hash)?.chain_length;
+        self.transactions
+            .retain(|(length, _)| *length <= chain_length);
+        Ok(self.state.tip())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use block::EpochSlotId;
+    use hdwallet::{XPrv, XPRV_SIZE};
+    use tx::{TxOut, TxoPointer};
+    use wallet::scheme::Account;
+
+    fn account() -> bip44::Account<XPrv> {
+        let key = XPrv::normalize_bytes([42; XPRV_SIZE]);
+        bip44::Account::new(AccountLevel::from(key), DerivationScheme::V2)
+    }
+
+    fn wallet() -> WatchOnlyWallet {
+        let state = State::new(hash(0), Utxos::new(), 10, 2);
+        let public = account().public();
+        WatchOnlyWallet::new(
+            **public,
+            DerivationScheme::V2,
+            NetworkMagic::NoMagic,
+            5,
+            state,
+        )
+    }
+
+    fn address(addr_type: AddrType, index: u32) -> ExtendedAddr {
+        account()
+            .generate_addresses([(addr_type, index)].iter(), NetworkMagic::NoMagic)
+            .remove(0)
+    }
+
+    fn hash(n: u64) -> HeaderHash {
+        HeaderHash::new(&n.to_be_bytes())
+    }
+
+    fn date(n: u64) -> BlockDate {
+        BlockDate::Normal(EpochSlotId {
+            epoch: 0,
+            slotid: n as u16,
+        })
+    }
+
+    fn payment(inputs: Vec<TxoPointer>, to: ExtendedAddr, value: u64) -> Tx {
+        Tx::new_with(inputs, vec![TxOut::new(to, Coin::new(value).unwrap())])
+    }
+
+    #[test]
+    fn same_addresses_as_the_private_account() {
+        let wallet = wallet();
+        for addr_type in [AddrType::External, AddrType::Internal].iter() {
+            for index in 0..5 {
+                assert_eq!(
+                    wallet.lookup(&address(*addr_type, index)),
+                    Some((*addr_type, index))
+                );
+            }
+            assert_eq!(wallet.lookup(&address(*addr_type, 5)), None);
+        }
+        assert_eq!(
+            wallet.next_unused_address(AddrType::External),
+            address(AddrType::External, 0)
+        );
+    }
+
+    #[test]
+    fn track_balance_and_transactions() {
+        let mut wallet = wallet();
+        let other = ExtendedAddr::new_simple(
+            XPrv::normalize_bytes([1; XPRV_SIZE]).public(),
+            NetworkMagic::NoMagic,
+        );
+
+        let tx1 = payment(
+            vec![TxoPointer::new(TxId::new(&[0]), 0)],
+            address(AddrType::External, 4),
+            10,
+        );
+        wallet.apply(hash(1), hash(0), date(1), &[&tx1]).unwrap();
+        assert_eq!(wallet.balance().unwrap(), Coin::new(10).unwrap());
+        // the gap is moved after the used address
+        assert_eq!(
+            wallet.lookup(&address(AddrType::External, 9)),
+            Some((AddrType::External, 9))
+        );
+        assert_eq!(
+            wallet.next_unused_address(AddrType::External),
+            address(AddrType::External, 5)
+        );
+
+        let unrelated = payment(vec![TxoPointer::new(TxId::new(&[1]), 0)], other.clone(), 3);
+        let tx2 = payment(vec![TxoPointer::new(tx1.id(), 0)], other, 10);
+        wallet
+            .apply(hash(2), hash(1), date(2), &[&unrelated, &tx2])
+            .unwrap();
+        assert_eq!(wallet.balance().unwrap(), Coin::zero());
+        assert_eq!(
+            wallet.transactions().cloned().collect::<Vec<_>>(),
+            vec![tx1.id(), tx2.id()]
+        );
+
+        assert_eq!(wallet.rollback_to(&hash(1)).unwrap().block, hash(0));
+        assert_eq!(wallet.balance().unwrap(), Coin::zero());
+        assert_eq!(wallet.transactions().count(), 0);
+    }
+}
@@ -4,11 +4,13 @@
 //! * First byte contains the discrimination information (1 bit) and the kind of address (7 bits)
 //! * Remaining bytes contains a kind specific encoding describe after.
 //!
-//! 3 kinds of address are currently supported:
+//! 5 kinds of address are currently supported:
 //! * Single: Just a (spending) public key using the ED25519 algorithm
 //! * Group: Same as single, but with a added (staking/group) public key
 //!   using the ED25519 algorithm.
 //! * Account: A stake public key using the ED25519 algorithm
+//! * Multisig: The identifier of a multisignature declaration
+//! * Script: The hash of a script
 //!
 //! Single key:
 //!     DISCRIMINATION_BIT || SINGLE_KIND_TYPE (7 bits) || SPENDING_KEY
@@ -19,8 +21,21 @@
 //! Account key:
 //!     DISCRIMINATION_BIT || ACCOUNT_KIND_TYPE (7 bits) || STAKE_KEY
 //!
+//! Multisig key:
+//!     DISCRIMINATION_BIT || MULTISIG_KIND_TYPE (7 bits) || MULTISIG_IDENTIFIER
+//!
+//! Script key:
+//!     DISCRIMINATION_BIT || SCRIPT_KIND_TYPE (7 bits) || SCRIPT_HASH
+//!
 //! Address human format is bech32 encoded
 //!
+//! Without the `std` feature, the crate is `no_std` and only needs an
+//! allocator: the addresses can be encoded to and decoded from bytes, but
+//! not from their human format.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
 
 #[cfg(test)]
 #[macro_use]
@@ -29,12 +44,18 @@ extern crate quickcheck;
 #[macro_use]
 extern crate cfg_if;
 
+use alloc::{string::String, vec::Vec};
+#[cfg(feature = "std")]
 use bech32::{Bech32, FromBase32, ToBase32};
+use core::fmt;
+#[cfg(feature = "std")]
 use std::string::ToString;
 
 use chain_crypto::{Ed25519Extended, PublicKey, PublicKeyError};
 
+#[cfg(feature = "std")]
 use chain_core::mempack::{ReadBuf, ReadError, Readable};
+#[cfg(feature = "std")]
 use chain_core::property::{self, Serialize as PropertySerialize};
 
 cfg_if! {
@@ -59,19 +80,31 @@ pub enum Discrimination {
 /// * Single address : just a single ed25519 spending public key
 /// * Group address : an ed25519 spending public key followed by a group public key used for staking
 /// * Account address : an ed25519 stake public key
+/// * Multisig address : the 32 bytes identifier of a multisignature declaration
+/// * Script address : the 32 bytes hash of a script
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Kind {
     Single(PublicKey<Ed25519Extended>),
     Group(PublicKey<Ed25519Extended>, PublicKey<Ed25519Extended>),
     Account(PublicKey<Ed25519Extended>),
+    Multisig(MultisigId),
+    Script(ScriptHash),
 }
 
+/// Identifier of a multisignature declaration
+pub type MultisigId = [u8; 32];
+
+/// Hash of a script
+pub type ScriptHash = [u8; 32];
+
 /// Kind Type of an address
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum KindType {
     Single,
     Group,
     Account,
+    Multisig,
+    Script,
 }
 
 /// Size of a Single address
@@ -83,11 +116,19 @@ pub const ADDR_SIZE_GROUP: usize = 65;
 /// Size of an Account address
 pub const ADDR_SIZE_ACCOUNT: usize = 33;
 
+/// Size of a Multisig address
+pub const ADDR_SIZE_MULTISIG: usize = 33;
+
+/// Size of a Script address
+pub const ADDR_SIZE_SCRIPT: usize = 33;
+
 const ADDR_KIND_LOW_SENTINEL: u8 = 0x2; /* anything under or equal to this is invalid */
 pub const ADDR_KIND_SINGLE: u8 = 0x3;
 pub const ADDR_KIND_GROUP: u8 = 0x4;
 pub const ADDR_KIND_ACCOUNT: u8 = 0x5;
-const ADDR_KIND_SENTINEL: u8 = 0x6; /* anything above or equal to this is invalid */
+pub const ADDR_KIND_MULTISIG: u8 = 0x6;
+pub const ADDR_KIND_SCRIPT: u8 = 0x7;
+const ADDR_KIND_SENTINEL: u8 = 0x8; /* anything above or equal to this is invalid */
 
 impl KindType {
     pub fn to_value(&self) -> u8 {
@@ -95,6 +136,8 @@ impl KindType {
             KindType::Single => ADDR_KIND_SINGLE,
             KindType::Group => ADDR_KIND_GROUP,
             KindType::Account => ADDR_KIND_ACCOUNT,
+            KindType::Multisig => ADDR_KIND_MULTISIG,
+            KindType::Script => ADDR_KIND_SCRIPT,
         }
     }
 }
@@ -113,28 +156,47 @@ impl Address {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum Error {
     EmptyAddress,
-    InvalidKind,
+    /// the kind value is not one of the known kinds
+    InvalidKind(u8),
+    /// the public key of the address is invalid
     InvalidAddress,
-    InvalidInternalEncoding,
-    InvalidPrefix,
-    MismatchPrefix,
+    /// the size of the address does not match its kind
+    InvalidSize(KindType, usize),
+    #[cfg(feature = "std")]
+    InvalidInternalEncoding(bech32::Error),
+    /// the prefix is not one of the prefixes of the network
+    InvalidPrefix(String),
+    /// the prefix is the one of the given discrimination, but the address
+    /// has the other discrimination
+    MismatchPrefix(Discrimination, Discrimination),
 }
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Error::EmptyAddress => write!(f, "empty address"),
-            Error::InvalidKind => write!(f, "invalid kind"),
+            Error::InvalidKind(kind) => write!(f, "invalid kind {:#x}", kind),
             Error::InvalidAddress => write!(f, "invalid address"),
-            Error::InvalidInternalEncoding => write!(f, "invalid internal encoding"),
-            Error::InvalidPrefix => write!(f, "invalid prefix"),
-            Error::MismatchPrefix => write!(f, "mismatch prefix"),
+            Error::InvalidSize(kind, size) => {
+                write!(f, "invalid size {} for a {:?} address", size, kind)
+            }
+            #[cfg(feature = "std")]
+            Error::InvalidInternalEncoding(err) => {
+                write!(f, "invalid internal encoding: {}", err)
+            }
+            Error::InvalidPrefix(prefix) => write!(f, "invalid prefix `{}'", prefix),
+            Error::MismatchPrefix(prefix, address) => write!(
+                f,
+                "mismatch prefix: {} prefix on a {} address",
+                prefix, address
+            ),
         }
     }
 }
+#[cfg(feature = "std")]
 impl std::error::Error for Error {}
 
 impl From<PublicKeyError> for Error {
@@ -143,9 +205,10 @@ impl From<PublicKeyError> for Error {
     }
 }
 
+#[cfg(feature = "std")]
 impl From<bech32::Error> for Error {
-    fn from(_: bech32::Error) -> Error {
-        Error::InvalidInternalEncoding
+    fn from(err: bech32::Error) -> Error {
+        Error::InvalidInternalEncoding(err)
     }
 }
 
@@ -170,6 +233,16 @@ impl Address {
                 let stake_key = PublicKey::from_binary(&bytes[1..])?;
                 Kind::Account(stake_key)
             }
+            ADDR_KIND_MULTISIG => {
+                let mut identifier = [0u8; 32];
+                identifier.copy_from_slice(&bytes[1..]);
+                Kind::Multisig(identifier)
+            }
+            ADDR_KIND_SCRIPT => {
+                let mut hash = [0u8; 32];
+                hash.copy_from_slice(&bytes[1..]);
+                Kind::Script(hash)
+            }
             _ => unreachable!(),
         };
         Ok(Address(discr, kind))
@@ -181,6 +254,8 @@ impl Address {
             Kind::Single(_) => ADDR_SIZE_SINGLE,
             Kind::Group(_, _) => ADDR_SIZE_GROUP,
             Kind::Account(_) => ADDR_SIZE_ACCOUNT,
+            Kind::Multisig(_) => ADDR_SIZE_MULTISIG,
+            Kind::Script(_) => ADDR_SIZE_SCRIPT,
         }
     }
 
@@ -190,6 +265,8 @@ impl Address {
             Kind::Single(_) => KindType::Single,
             Kind::Group(_, _) => KindType::Group,
             Kind::Account(_) => KindType::Account,
+            Kind::Multisig(_) => KindType::Multisig,
+            Kind::Script(_) => KindType::Script,
         }
     }
 
@@ -199,14 +276,29 @@ impl Address {
 
     /// Serialize an address into bytes
     pub fn to_bytes(&self) -> Vec<u8> {
-        self.serialize_as_vec()
-            .expect("expect in memory allocation to always work")
+        let mut bytes = Vec::with_capacity(self.to_size());
+        bytes.push(match self.0 {
+            Discrimination::Production => self.to_kind_value(),
+            Discrimination::Test => self.to_kind_value() | 0b1000_0000,
+        });
+        match &self.1 {
+            Kind::Single(spend) => bytes.extend_from_slice(spend.as_ref()),
+            Kind::Group(spend, group) => {
+                bytes.extend_from_slice(spend.as_ref());
+                bytes.extend_from_slice(group.as_ref());
+            }
+            Kind::Account(stake_key) => bytes.extend_from_slice(stake_key.as_ref()),
+            Kind::Multisig(identifier) => bytes.extend_from_slice(&identifier[..]),
+            Kind::Script(hash) => bytes.extend_from_slice(&hash[..]),
+        };
+        bytes
     }
 
     /// create a base32 encoding of the byte serialization
     ///
     /// This is not the official normal human representation
     /// for the address, but is used for debug / other.
+    #[cfg(feature = "std")]
     pub fn base32(&self) -> String {
         let v = ToBase32::to_base32(&self.to_bytes());
         let alphabet = b"abcdefghijklmnopqrstuvwxyz234567";
@@ -222,6 +314,8 @@ impl Address {
             Kind::Single(ref pk) => Some(pk),
             Kind::Group(ref pk, _) => Some(pk),
             Kind::Account(ref pk) => Some(pk),
+            Kind::Multisig(_) => None,
+            Kind::Script(_) => None,
         }
     }
 }
@@ -244,90 +338,184 @@ fn is_valid_data(bytes: &[u8]) -> Result<(Discrimination, KindType), Error> {
     }
     let kind_type = get_kind_value(bytes[0]);
     if kind_type <= ADDR_KIND_LOW_SENTINEL || kind_type >= ADDR_KIND_SENTINEL {
-        return Err(Error::InvalidKind);
+        return Err(Error::InvalidKind(kind_type));
     }
     let kty = match kind_type {
         ADDR_KIND_SINGLE => {
             if bytes.len() != ADDR_SIZE_SINGLE {
-                return Err(Error::InvalidAddress);
+                return Err(Error::InvalidSize(KindType::Single, bytes.len()));
             }
             KindType::Single
         }
         ADDR_KIND_GROUP => {
             if bytes.len() != ADDR_SIZE_GROUP {
-                return Err(Error::InvalidAddress);
+                return Err(Error::InvalidSize(KindType::Group, bytes.len()));
             }
             KindType::Group
         }
         ADDR_KIND_ACCOUNT => {
             if bytes.len() != ADDR_SIZE_ACCOUNT {
-                return Err(Error::InvalidAddress);
+                return Err(Error::InvalidSize(KindType::Account, bytes.len()));
             }
             KindType::Account
         }
-        _ => return Err(Error::InvalidKind),
+        ADDR_KIND_MULTISIG => {
+            if bytes.len() != ADDR_SIZE_MULTISIG {
+                return Err(Error::InvalidSize(KindType::Multisig, bytes.len()));
+            }
+            KindType::Multisig
+        }
+        ADDR_KIND_SCRIPT => {
+            if bytes.len() != ADDR_SIZE_SCRIPT {
+                return Err(Error::InvalidSize(KindType::Script, bytes.len()));
+            }
+            KindType::Script
+        }
+        _ => return Err(Error::InvalidKind(kind_type)),
     };
     Ok((get_discrimination_value(bytes[0]), kty))
 }
 
+#[cfg(feature = "std")]
+/// The bech32 human readable prefixes of the addresses of a network,
+/// one per discrimination
+///
+/// The default prefixes are the ones given at build time with the
+/// `PRODUCTION_ADDRESS_PREFIX` and `TEST_ADDRESS_PREFIX` environment
+/// variables, `ca` and `ta` if unset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressPrefixes {
+    production: String,
+    test: String,
+}
+
+#[cfg(feature = "std")]
+impl AddressPrefixes {
+    /// the prefixes must be distinct valid lower case bech32 prefixes
+    pub fn new<P: Into<String>, T: Into<String>>(production: P, test: T) -> Result<Self, Error> {
+        let (production, test) = (production.into(), test.into());
+        for prefix in &[&production, &test] {
+            if !is_valid_prefix(prefix) {
+                return Err(Error::InvalidPrefix(prefix.to_string()));
+            }
+        }
+        if production == test {
+            return Err(Error::InvalidPrefix(test));
+        }
+        Ok(AddressPrefixes { production, test })
+    }
+
+    pub fn prefix(&self, discrimination: Discrimination) -> &str {
+        match discrimination {
+            Discrimination::Production => &self.production,
+            Discrimination::Test => &self.test,
+        }
+    }
+
+    /// the discrimination of the addresses using the given prefix
+    pub fn discrimination(&self, prefix: &str) -> Option<Discrimination> {
+        if prefix == self.production {
+            Some(Discrimination::Production)
+        } else if prefix == self.test {
+            Some(Discrimination::Test)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for AddressPrefixes {
+    fn default() -> Self {
+        AddressPrefixes::new(
+            env!("PRODUCTION_ADDRESS_PREFIX"),
+            env!("TEST_ADDRESS_PREFIX"),
+        )
+        .expect("the address prefixes given at build time are invalid")
+    }
+}
+
+#[cfg(feature = "std")]
+fn is_valid_prefix(prefix: &str) -> bool {
+    !prefix.is_empty()
+        && prefix.len() <= 83
+        && prefix
+            .bytes()
+            .all(|c| c >= 33 && c <= 126 && !c.is_ascii_uppercase())
+}
+
+#[cfg(feature = "std")]
 /// A valid address in a human readable format
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AddressReadable(String);
 
+#[cfg(feature = "std")]
 impl AddressReadable {
-    const PRODUCTION_ADDRESS_PREFIX: &'static str = env!("PRODUCTION_ADDRESS_PREFIX");
-    const TEST_ADDRESS_PREFIX: &'static str = env!("TEST_ADDRESS_PREFIX");
-
     pub fn as_string(&self) -> &str {
         &self.0
     }
 
-    /// Validate from a String to create a valid AddressReadable
+    /// Validate from a String to create a valid AddressReadable, using
+    /// the default prefixes
     pub fn from_string(s: &str) -> Result<Self, Error> {
+        Self::from_string_with_prefixes(&AddressPrefixes::default(), s)
+    }
+
+    /// Validate from a String to create a valid AddressReadable
+    ///
+    /// The prefix must be one of the given prefixes, and the address must
+    /// have the discrimination of this prefix.
+    pub fn from_string_with_prefixes(prefixes: &AddressPrefixes, s: &str) -> Result<Self, Error> {
         use std::str::FromStr;
         let r = Bech32::from_str(s)?;
-        let expected_discrimination = if r.hrp() == Self::PRODUCTION_ADDRESS_PREFIX {
-            Discrimination::Production
-        } else if r.hrp() == Self::TEST_ADDRESS_PREFIX {
-            Discrimination::Test
-        } else {
-            return Err(Error::InvalidPrefix);
-        };
+        let expected_discrimination = prefixes
+            .discrimination(r.hrp())
+            .ok_or_else(|| Error::InvalidPrefix(r.hrp().to_string()))?;
         let dat = Vec::from_base32(r.data())?;
-        let (discrimination, _) = is_valid_data(&dat[..])?;
-        if discrimination != expected_discrimination {
-            return Err(Error::MismatchPrefix);
+        let address = Address::from_bytes(&dat[..])?;
+        if address.discrimination() != expected_discrimination {
+            return Err(Error::MismatchPrefix(
+                expected_discrimination,
+                address.discrimination(),
+            ));
         }
         Ok(AddressReadable(s.to_string()))
     }
 
-    /// Create a new AddressReadable from an encoded address
+    /// Create a new AddressReadable from an encoded address, using the
+    /// default prefixes
     pub fn from_address(addr: &Address) -> Self {
+        Self::from_address_with_prefixes(&AddressPrefixes::default(), addr)
+    }
+
+    /// Create a new AddressReadable from an encoded address, with the
+    /// prefix of its discrimination
+    pub fn from_address_with_prefixes(prefixes: &AddressPrefixes, addr: &Address) -> Self {
         let v = ToBase32::to_base32(&addr.to_bytes());
-        let prefix = match addr.0 {
-            Discrimination::Production => Self::PRODUCTION_ADDRESS_PREFIX.to_string(),
-            Discrimination::Test => Self::TEST_ADDRESS_PREFIX.to_string(),
-        };
-        let r = Bech32::new(prefix, v).unwrap();
+        let prefix = prefixes.prefix(addr.discrimination()).to_string();
+        let r = Bech32::new(prefix, v).expect("the prefixes are valid bech32 prefixes");
         AddressReadable(r.to_string())
     }
 
     /// Convert a valid AddressReadable to an decoded address
     pub fn to_address(&self) -> Address {
         use std::str::FromStr;
-        // the data has been verified ahead of time, so all unwrap are safe
+        // the data, including the public keys, has been verified ahead of
+        // time, so all unwrap are safe
         let r = Bech32::from_str(&self.0).unwrap();
         let dat = Vec::from_base32(r.data()).unwrap();
         Address::from_bytes(&dat[..]).unwrap()
     }
 }
 
+#[cfg(feature = "std")]
 impl ToString for AddressReadable {
     fn to_string(&self) -> String {
         self.0.clone()
     }
 }
 
+#[cfg(feature = "std")]
 impl std::str::FromStr for AddressReadable {
     type Err = Error;
     fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
@@ -335,37 +523,19 @@ impl std::str::FromStr for AddressReadable {
     }
 }
 
+#[cfg(feature = "std")]
 impl PropertySerialize for Address {
     type Error = std::io::Error;
 
-    fn serialize<W: std::io::Write>(&self, writer: W) -> Result<(), Self::Error> {
-        use chain_core::packer::*;
-        use std::io::Write;
-        let mut codec = Codec::from(writer);
-
-        let first_byte = match self.0 {
-            Discrimination::Production => self.to_kind_value(),
-            Discrimination::Test => self.to_kind_value() | 0b1000_0000,
-        };
-        codec.put_u8(first_byte)?;
-        match &self.1 {
-            Kind::Single(spend) => codec.write_all(spend.as_ref())?,
-            Kind::Group(spend, group) => {
-                codec.write_all(spend.as_ref())?;
-                codec.write_all(group.as_ref())?;
-            }
-            Kind::Account(stake_key) => codec.write_all(stake_key.as_ref())?,
-        };
-
-        Ok(())
+    fn serialize<W: std::io::Write>(&self, mut writer: W) -> Result<(), Self::Error> {
+        writer.write_all(&self.to_bytes())
     }
 
     fn serialize_as_vec(&self) -> Result<Vec<u8>, Self::Error> {
-        let mut data = Vec::with_capacity(self.to_size());
-        self.serialize(&mut data)?;
-        Ok(data)
+        Ok(self.to_bytes())
     }
 }
+#[cfg(feature = "std")]
 impl property::Deserialize for Address {
     type Error = std::io::Error;
 
@@ -408,12 +578,28 @@ impl property::Deserialize for Address {
                 })?;
                 Kind::Account(stake_key)
             }
-            _ => unreachable!(),
+            ADDR_KIND_MULTISIG => {
+                let mut identifier = [0u8; 32];
+                codec.read_exact(&mut identifier)?;
+                Kind::Multisig(identifier)
+            }
+            ADDR_KIND_SCRIPT => {
+                let mut hash = [0u8; 32];
+                codec.read_exact(&mut hash)?;
+                Kind::Script(hash)
+            }
+            kind => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    Error::InvalidKind(kind),
+                ))
+            }
         };
         Ok(Address(discr, kind))
     }
 }
 
+#[cfg(feature = "std")]
 fn chain_crypto_err(e: chain_crypto::PublicKeyError) -> ReadError {
     match e {
         PublicKeyError::SizeInvalid => {
@@ -425,6 +611,7 @@ fn chain_crypto_err(e: chain_crypto::PublicKeyError) -> ReadError {
     }
 }
 
+#[cfg(feature = "std")]
 impl Readable for Address {
     fn read<'a>(buf: &mut ReadBuf<'a>) -> Result<Self, ReadError> {
         let byte = buf.get_u8()?;
@@ -447,6 +634,8 @@ impl Readable for Address {
                 let stake_key = PublicKey::from_binary(&bytes[..]).map_err(chain_crypto_err)?;
                 Kind::Account(stake_key)
             }
+            ADDR_KIND_MULTISIG => Kind::Multisig(<[u8; 32]>::read(buf)?),
+            ADDR_KIND_SCRIPT => Kind::Script(<[u8; 32]>::read(buf)?),
             n => return Err(ReadError::UnknownTag(n as u32)),
         };
         Ok(Address(discr, kind))
@@ -457,8 +646,8 @@ impl Readable for Address {
 /// from a string
 #[derive(Debug)]
 pub struct ParseDiscriminationError(String);
-impl std::fmt::Display for ParseDiscriminationError {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl fmt::Display for ParseDiscriminationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
             "Invalid Address Discrimination `{}'. Expected `production' or `test'.",
@@ -466,23 +655,24 @@ impl std::fmt::Display for ParseDiscriminationError {
         )
     }
 }
+#[cfg(feature = "std")]
 impl std::error::Error for ParseDiscriminationError {}
 
-impl std::fmt::Display for Discrimination {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl fmt::Display for Discrimination {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Discrimination::Production => write!(f, "production"),
             Discrimination::Test => write!(f, "test"),
         }
     }
 }
-impl std::str::FromStr for Discrimination {
+impl core::str::FromStr for Discrimination {
     type Err = ParseDiscriminationError;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
             "production" => Ok(Discrimination::Production),
             "test" => Ok(Discrimination::Test),
-            _ => Err(ParseDiscriminationError(s.to_owned())),
+            _ => Err(ParseDiscriminationError(String::from(s))),
         }
     }
 }
@@ -604,4 +794,91 @@ mod test {
             );
         }
     }
+
+    #[test]
+    fn custom_prefixes() {
+        let prefixes = AddressPrefixes::new("addr", "addr_test").unwrap();
+        let addresses = [
+            Address(Discrimination::Test, Kind::Script([7; 32])),
+            Address(Discrimination::Production, Kind::Multisig([8; 32])),
+        ];
+        for address in &addresses {
+            let readable = AddressReadable::from_address_with_prefixes(&prefixes, address);
+            let prefix = prefixes.prefix(address.discrimination());
+            assert!(readable.as_string().starts_with(&format!("{}1", prefix)));
+            let parsed =
+                AddressReadable::from_string_with_prefixes(&prefixes, readable.as_string())
+                    .unwrap();
+            assert_eq!(&parsed.to_address(), address);
+            assert_eq!(
+                AddressReadable::from_string(readable.as_string()),
+                Err(Error::InvalidPrefix(prefix.to_string()))
+            );
+        }
+
+        assert!(AddressPrefixes::new("addr", "addr").is_err());
+        assert!(AddressPrefixes::new("", "ta").is_err());
+        assert!(AddressPrefixes::new("Ca", "ta").is_err());
+    }
+
+    #[test]
+    fn strict_parsing() {
+        let address = Address(Discrimination::Test, Kind::Multisig([1; 32]));
+        let data = ToBase32::to_base32(&address.to_bytes());
+        let mixed = Bech32::new("ca".to_string(), data).unwrap().to_string();
+        assert_eq!(
+            AddressReadable::from_string(&mixed),
+            Err(Error::MismatchPrefix(
+                Discrimination::Production,
+                Discrimination::Test
+            ))
+        );
+
+        let data = ToBase32::to_base32(&[ADDR_KIND_SINGLE, 1, 2, 3][..]);
+        let short = Bech32::new("ca".to_string(), data).unwrap().to_string();
+        assert_eq!(
+            AddressReadable::from_string(&short),
+            Err(Error::InvalidSize(KindType::Single, 4))
+        );
+
+        let data = ToBase32::to_base32(&[0x42u8; 33][..]);
+        let unknown = Bech32::new("ca".to_string(), data).unwrap().to_string();
+        assert_eq!(
+            AddressReadable::from_string(&unknown),
+            Err(Error::InvalidKind(0x42))
+        );
+    }
+
+    #[test]
+    fn golden_addresses() {
+        for vector in test_vectors::chain_addresses() {
+            let addr = Address::from_bytes(&vector.bytes)
+                .unwrap_or_else(|e| panic!("{}: {:?}", vector.name, e));
+            let discrimination = match vector.discrimination.as_str() {
+                "production" => Discrimination::Production,
+                "test" => Discrimination::Test,
+                d => panic!("{}: unknown discrimination {}", vector.name, d),
+            };
+            let kind = match vector.kind.as_str() {
+                "single" => KindType::Single,
+                "group" => KindType::Group,
+                "account" => KindType::Account,
+                "multisig" => KindType::Multisig,
+                "script" => KindType::Script,
+                k => panic!("{}: unknown kind {}", vector.name, k),
+            };
+            assert_eq!(addr.discrimination(), discrimination, "{}", vector.name);
+            assert_eq!(addr.to_kind_type(), kind, "{}", vector.name);
+            assert_eq!(addr.to_bytes(), vector.bytes, "{}", vector.name);
+            assert_eq!(addr.base32(), vector.base32, "{}", vector.name);
+            assert_eq!(
+                AddressReadable::from_address(&addr).as_string(),
+                vector.bech32,
+                "{}",
+                vector.name
+            );
+            let readable = AddressReadable::from_string(&vector.bech32).unwrap();
+            assert_eq!(readable.to_address(), addr, "{}", vector.name);
+        }
+    }
 }
@@ -13,15 +13,25 @@ impl Arbitrary for Discrimination {
 
 impl Arbitrary for KindType {
     fn arbitrary<G: Gen>(g: &mut G) -> Self {
-        match u8::arbitrary(g) % 3 {
+        match u8::arbitrary(g) % 5 {
             0 => KindType::Single,
             1 => KindType::Group,
             2 => KindType::Account,
+            3 => KindType::Multisig,
+            4 => KindType::Script,
             _ => unreachable!(),
         }
     }
 }
 
+fn arbitrary_32_bytes<G: Gen>(g: &mut G) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    for byte in bytes.iter_mut() {
+        *byte = Arbitrary::arbitrary(g);
+    }
+    bytes
+}
+
 impl Arbitrary for AddressReadable {
     fn arbitrary<G: Gen>(g: &mut G) -> Self {
         AddressReadable::from_address(&Arbitrary::arbitrary(g))
@@ -34,6 +44,8 @@ impl Arbitrary for Address {
             KindType::Single => Kind::Single(Arbitrary::arbitrary(g)),
             KindType::Group => Kind::Group(Arbitrary::arbitrary(g), Arbitrary::arbitrary(g)),
             KindType::Account => Kind::Account(Arbitrary::arbitrary(g)),
+            KindType::Multisig => Kind::Multisig(arbitrary_32_bytes(g)),
+            KindType::Script => Kind::Script(arbitrary_32_bytes(g)),
         };
         Address(discrimination, kind)
     }
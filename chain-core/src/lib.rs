@@ -10,5 +10,6 @@ cfg_if! {
 }
 
 pub mod mempack;
+pub mod metrics;
 pub mod packer;
 pub mod property;
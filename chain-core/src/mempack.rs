@@ -89,7 +89,6 @@ impl<'a> ReadBuf<'a> {
         if left >= expected {
             Ok(())
         } else {
-            dbg!(self.data);
             Err(ReadError::NotEnoughBytes(left, expected))
         }
     }
@@ -118,6 +117,15 @@ impl<'a> ReadBuf<'a> {
 
     /// Return a slice of the next bytes from the buffer
     pub fn get_slice(&mut self, sz: usize) -> Result<&[u8], ReadError> {
+        self.get_slice_ref(sz)
+    }
+
+    /// Return a slice of the next bytes from the buffer, borrowed from
+    /// the underlying data instead of the `ReadBuf`.
+    ///
+    /// The slice can outlive the `ReadBuf` and be stored in (or decoded
+    /// from) without copying the bytes first.
+    pub fn get_slice_ref(&mut self, sz: usize) -> Result<&'a [u8], ReadError> {
         self.assure_size(sz)?;
         let s = &self.data[self.offset..self.offset + sz];
         self.offset += sz;
@@ -248,3 +256,33 @@ pub fn read_from_raw<T: Readable>(raw: &[u8]) -> Result<T, std::io::Error> {
         },
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn slice_ref_outlives_the_buffer() {
+        let data = [1, 2, 3, 4, 5];
+        let slice = {
+            let mut buf = ReadBuf::from(&data);
+            buf.skip_bytes(1).unwrap();
+            buf.get_slice_ref(3).unwrap()
+        };
+        assert_eq!(slice, &data[1..4]);
+    }
+
+    #[test]
+    fn slice_ref_past_the_end() {
+        let data = [1, 2, 3, 4, 5];
+        let mut buf = ReadBuf::from(&data);
+        assert_eq!(buf.get_slice_ref(6), Err(ReadError::NotEnoughBytes(5, 6)));
+        assert_eq!(buf.get_slice_ref(4), Ok(&data[..4]));
+        assert_eq!(buf.get_slice_ref(2), Err(ReadError::NotEnoughBytes(1, 2)));
+        // a failed read does not consume the buffer
+        assert_eq!(buf.get_slice_ref(1), Ok(&data[4..]));
+        assert_eq!(buf.get_slice_ref(0), Ok(&[][..]));
+        assert_eq!(buf.get_slice_ref(1), Err(ReadError::NotEnoughBytes(0, 1)));
+        assert!(buf.is_end());
+    }
+}
@@ -0,0 +1,180 @@
+//! Instrumentation hooks.
+//!
+//! The block import, the storage and the ledger report what they do
+//! through the `Metrics` trait: counters only going up, gauges set to the
+//! current value of a quantity and histograms of observed values (e.g.
+//! durations in seconds). The default implementation of every hook does
+//! nothing, so `NoMetrics` costs nothing to the applications not
+//! monitoring anything.
+//!
+//! With the `prometheus-exporter` feature, `prometheus::PrometheusMetrics`
+//! collects the values and renders them in the Prometheus text format.
+
+/// names of the metrics reported by the crates of the workspace
+pub mod names {
+    /// blocks received from the network and verified
+    pub const SYNC_BLOCKS_IMPORTED: &str = "sync_blocks_imported_total";
+    /// time taken to verify a block received from the network, in seconds
+    pub const SYNC_BLOCK_VERIFY_SECONDS: &str = "sync_block_verify_seconds";
+    /// length of the local chain
+    pub const SYNC_CHAIN_LENGTH: &str = "sync_chain_length";
+    /// number of unspent transaction outputs of the local chain
+    pub const SYNC_UTXO_COUNT: &str = "sync_utxo_count";
+
+    /// blocks written to the storage as loose blobs
+    pub const STORAGE_BLOBS_WRITTEN: &str = "storage_blobs_written_total";
+    /// bytes written to the storage as loose blobs
+    pub const STORAGE_BLOB_BYTES_WRITTEN: &str = "storage_blob_bytes_written_total";
+    /// epochs packed by the storage
+    pub const STORAGE_EPOCHS_PACKED: &str = "storage_epochs_packed_total";
+
+    /// blocks applied to the ledger
+    pub const LEDGER_BLOCKS_APPLIED: &str = "ledger_blocks_applied_total";
+    /// messages applied to the ledger
+    pub const LEDGER_MESSAGES_APPLIED: &str = "ledger_messages_applied_total";
+    /// blocks rejected by the ledger
+    pub const LEDGER_BLOCKS_REJECTED: &str = "ledger_blocks_rejected_total";
+    /// time taken to apply a block to the ledger, in seconds
+    pub const LEDGER_APPLY_BLOCK_SECONDS: &str = "ledger_apply_block_seconds";
+}
+
+/// receiver of the measures
+///
+/// The implementations are shared between the threads of the application
+/// and have to synchronize their state themselves.
+pub trait Metrics: Send + Sync {
+    /// add `increment` to the counter
+    fn counter(&self, _name: &'static str, _increment: u64) {}
+
+    /// set the gauge to `value`
+    fn gauge(&self, _name: &'static str, _value: i64) {}
+
+    /// add an observation to the histogram
+    fn histogram(&self, _name: &'static str, _value: f64) {}
+}
+
+/// metrics discarding all the measures
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoMetrics;
+
+impl Metrics for NoMetrics {}
+
+/// the duration as seconds, the unit of the histograms of durations
+pub fn seconds(duration: std::time::Duration) -> f64 {
+    duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1_000_000_000.0
+}
+
+#[cfg(feature = "prometheus-exporter")]
+pub mod prometheus {
+    //! collection of the measures in memory and rendering in the
+    //! Prometheus text exposition format, to be served on the `/metrics`
+    //! end point of the application
+
+    use super::Metrics;
+    use std::collections::BTreeMap;
+    use std::fmt::Write;
+    use std::sync::Mutex;
+
+    /// upper bounds of the buckets of the histograms
+    const BUCKETS: [f64; 11] = [
+        0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+    ];
+
+    struct Histogram {
+        buckets: [u64; 11],
+        count: u64,
+        sum: f64,
+    }
+
+    #[derive(Default)]
+    struct Values {
+        counters: BTreeMap<&'static str, u64>,
+        gauges: BTreeMap<&'static str, i64>,
+        histograms: BTreeMap<&'static str, Histogram>,
+    }
+
+    #[derive(Default)]
+    pub struct PrometheusMetrics {
+        values: Mutex<Values>,
+    }
+
+    impl PrometheusMetrics {
+        pub fn new() -> Self {
+            PrometheusMetrics::default()
+        }
+
+        /// the current values, in the Prometheus text format
+        pub fn render(&self) -> String {
+            let values = self.values.lock().unwrap();
+            let mut out = String::new();
+            for (name, value) in values.counters.iter() {
+                writeln!(out, "# TYPE {} counter\n{} {}", name, name, value).unwrap();
+            }
+            for (name, value) in values.gauges.iter() {
+                writeln!(out, "# TYPE {} gauge\n{} {}", name, name, value).unwrap();
+            }
+            for (name, histogram) in values.histograms.iter() {
+                writeln!(out, "# TYPE {} histogram", name).unwrap();
+                let mut cumulated = 0;
+                for (bound, count) in BUCKETS.iter().zip(histogram.buckets.iter()) {
+                    cumulated += count;
+                    writeln!(out, "{}_bucket{{le=\"{}\"}} {}", name, bound, cumulated).unwrap();
+                }
+                writeln!(out, "{}_bucket{{le=\"+Inf\"}} {}", name, histogram.count).unwrap();
+                writeln!(out, "{}_sum {}", name, histogram.sum).unwrap();
+                writeln!(out, "{}_count {}", name, histogram.count).unwrap();
+            }
+            out
+        }
+    }
+
+    impl Metrics for PrometheusMetrics {
+        fn counter(&self, name: &'static str, increment: u64) {
+            let mut values = self.values.lock().unwrap();
+            *values.counters.entry(name).or_insert(0) += increment;
+        }
+
+        fn gauge(&self, name: &'static str, value: i64) {
+            let mut values = self.values.lock().unwrap();
+            values.gauges.insert(name, value);
+        }
+
+        fn histogram(&self, name: &'static str, value: f64) {
+            let mut values = self.values.lock().unwrap();
+            let histogram = values.histograms.entry(name).or_insert(Histogram {
+                buckets: [0; 11],
+                count: 0,
+                sum: 0.0,
+            });
+            if let Some(index) = BUCKETS.iter().position(|bound| value <= *bound) {
+                histogram.buckets[index] += 1;
+            }
+            histogram.count += 1;
+            histogram.sum += value;
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn render() {
+            let metrics = PrometheusMetrics::new();
+            metrics.counter("blocks_total", 2);
+            metrics.counter("blocks_total", 3);
+            metrics.gauge("utxos", 42);
+            metrics.histogram("apply_seconds", 0.003);
+            metrics.histogram("apply_seconds", 10.0);
+
+            let text = metrics.render();
+            assert!(text.contains("# TYPE blocks_total counter\nblocks_total 5\n"));
+            assert!(text.contains("# TYPE utxos gauge\nutxos 42\n"));
+            assert!(text.contains("apply_seconds_bucket{le=\"0.0025\"} 0\n"));
+            assert!(text.contains("apply_seconds_bucket{le=\"0.005\"} 1\n"));
+            assert!(text.contains("apply_seconds_bucket{le=\"2.5\"} 1\n"));
+            assert!(text.contains("apply_seconds_bucket{le=\"+Inf\"} 2\n"));
+            assert!(text.contains("apply_seconds_count 2\n"));
+        }
+    }
+}
@@ -30,6 +30,7 @@
 //! is selected to write a block in the chain.
 //!
 
+use crate::packer::Codec;
 use std::{fmt::Debug, hash::Hash};
 
 /// Trait identifying the block identifier type.
@@ -309,6 +310,131 @@ pub trait Deserialize: Sized {
     fn deserialize<R: std::io::BufRead>(reader: R) -> Result<Self, Self::Error>;
 }
 
+/// Version of the serialization format of an object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FormatVersion(pub u16);
+
+impl std::fmt::Display for FormatVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "v{}", self.0)
+    }
+}
+
+/// The serialization format version is not supported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UnsupportedFormatVersion(pub FormatVersion);
+
+impl std::fmt::Display for UnsupportedFormatVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "unsupported serialization format version {}", self.0)
+    }
+}
+
+impl std::error::Error for UnsupportedFormatVersion {}
+
+impl From<UnsupportedFormatVersion> for std::io::Error {
+    fn from(e: UnsupportedFormatVersion) -> Self {
+        std::io::Error::new(std::io::ErrorKind::InvalidData, e)
+    }
+}
+
+/// Define the versions of the serialization format of an object.
+///
+/// The format of an object can evolve (e.g. adding the certificates to the
+/// transactions) while the objects written with the previous versions of
+/// the format (e.g. the old blocks) remain readable.
+pub trait Versioned {
+    /// the supported versions, in increasing order. The last one is the
+    /// current version, the one used by `Serialize::serialize`.
+    const SUPPORTED_VERSIONS: &'static [FormatVersion];
+
+    fn current_version() -> FormatVersion {
+        *Self::SUPPORTED_VERSIONS
+            .last()
+            .expect("at least one supported version")
+    }
+
+    fn supports_version(version: FormatVersion) -> bool {
+        Self::SUPPORTED_VERSIONS.contains(&version)
+    }
+}
+
+/// Select the version to use between two parties: the most recent
+/// version supported by both of them.
+pub fn negotiate_version(
+    ours: &[FormatVersion],
+    theirs: &[FormatVersion],
+) -> Option<FormatVersion> {
+    ours.iter()
+        .filter(|version| theirs.contains(version))
+        .max()
+        .cloned()
+}
+
+/// Define that an object can be written with any of its supported
+/// serialization format versions.
+pub trait VersionedSerialize: Serialize + Versioned {
+    /// Serialize the object in the given version of the format.
+    ///
+    /// The default implementation only supports the current version.
+    fn serialize_with_version<W: std::io::Write>(
+        &self,
+        version: FormatVersion,
+        writer: W,
+    ) -> Result<(), Self::Error> {
+        if version == Self::current_version() {
+            self.serialize(writer)
+        } else {
+            Err(std::io::Error::from(UnsupportedFormatVersion(version)).into())
+        }
+    }
+
+    /// Write the versioned envelope of the object: the version of the
+    /// format (big endian `u16`) followed by the object serialized in
+    /// this version.
+    fn serialize_versioned<W: std::io::Write>(
+        &self,
+        version: FormatVersion,
+        writer: W,
+    ) -> Result<(), Self::Error> {
+        if !Self::supports_version(version) {
+            return Err(std::io::Error::from(UnsupportedFormatVersion(version)).into());
+        }
+        let mut codec = Codec::from(writer);
+        codec.put_u16(version.0)?;
+        self.serialize_with_version(version, codec.into_inner())
+    }
+}
+
+/// Define that an object can be read from any of its supported
+/// serialization format versions.
+pub trait VersionedDeserialize: Deserialize + Versioned {
+    /// Deserialize the object written in the given version of the format.
+    ///
+    /// The default implementation only supports the current version.
+    fn deserialize_with_version<R: std::io::BufRead>(
+        version: FormatVersion,
+        reader: R,
+    ) -> Result<Self, Self::Error> {
+        if version == Self::current_version() {
+            Self::deserialize(reader)
+        } else {
+            Err(std::io::Error::from(UnsupportedFormatVersion(version)).into())
+        }
+    }
+
+    /// Read the versioned envelope written by
+    /// `VersionedSerialize::serialize_versioned`.
+    fn deserialize_versioned<R: std::io::BufRead>(reader: R) -> Result<Self, Self::Error> {
+        let mut codec = Codec::from(reader);
+        let version = FormatVersion(codec.get_u16()?);
+        if !Self::supports_version(version) {
+            return Err(std::io::Error::from(UnsupportedFormatVersion(version)).into());
+        }
+        Self::deserialize_with_version(version, codec.into_inner())
+    }
+}
+
 /// Defines the way to parse the object from a UTF-8 string.
 ///
 /// This is like the standard `FromStr` trait, except that it imposes
@@ -364,6 +490,56 @@ pub mod testing {
         TestResult::from_bool(decoded_t == t)
     }
 
+    /// read the object from the given bytes, which must be consumed
+    /// entirely, `None` if they are malformed. Meant to be called by the
+    /// fuzzing targets: reading arbitrary bytes must never panic.
+    pub fn fuzz_readable<T: Readable>(data: &[u8]) -> Option<T> {
+        let mut buf = ReadBuf::from(data);
+        let t = T::read(&mut buf).ok()?;
+        buf.expect_end().ok()?;
+        Some(t)
+    }
+
+    /// test that reading any truncation of the serialized object does not
+    /// panic, and that the whole serialized object is read back
+    pub fn readable_truncations<T>(t: T) -> TestResult
+    where
+        T: Arbitrary + Serialize + Readable,
+    {
+        let vec = match t.serialize_as_vec() {
+            Err(error) => return TestResult::error(format!("serialization: {}", error)),
+            Ok(v) => v,
+        };
+        for len in 0..vec.len() {
+            let _ = fuzz_readable::<T>(&vec[..len]);
+        }
+        TestResult::from_bool(fuzz_readable::<T>(&vec).is_some())
+    }
+
+    /// test that any arbitrary given object can be written in the versioned
+    /// envelope of every supported version and read back into itself
+    pub fn versioned_serialization_bijection<T>(t: T) -> TestResult
+    where
+        T: Arbitrary + VersionedSerialize + VersionedDeserialize + Eq,
+    {
+        for version in <T as Versioned>::SUPPORTED_VERSIONS {
+            let mut vec = Vec::new();
+            if let Err(error) = t.serialize_versioned(*version, &mut vec) {
+                return TestResult::error(format!("serialization ({}): {}", version, error));
+            }
+            let decoded_t = match T::deserialize_versioned(&vec[..]) {
+                Err(error) => {
+                    return TestResult::error(format!("deserialization ({}): {}", version, error))
+                }
+                Ok(v) => v,
+            };
+            if decoded_t != t {
+                return TestResult::failed();
+            }
+        }
+        TestResult::passed()
+    }
+
     /// test that any arbitrary given object can serialize and deserialize
     /// back into itself (i.e. it is a bijection,  or a one to one match
     /// between the serialized bytes and the object)
@@ -384,3 +560,147 @@ pub mod testing {
     }
 
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::packer::Codec;
+    use std::io::{self, BufRead, Write};
+
+    /// the version 1 of the format has no flags, they are all cleared when
+    /// reading it
+    #[derive(Debug, PartialEq, Eq)]
+    struct Record {
+        value: u32,
+        flags: u8,
+    }
+
+    const V1: FormatVersion = FormatVersion(1);
+    const V2: FormatVersion = FormatVersion(2);
+
+    impl Serialize for Record {
+        type Error = io::Error;
+
+        fn serialize<W: Write>(&self, writer: W) -> Result<(), Self::Error> {
+            let mut codec = Codec::from(writer);
+            codec.put_u32(self.value)?;
+            codec.put_u8(self.flags)
+        }
+    }
+
+    impl Deserialize for Record {
+        type Error = io::Error;
+
+        fn deserialize<R: BufRead>(reader: R) -> Result<Self, Self::Error> {
+            let mut codec = Codec::from(reader);
+            let value = codec.get_u32()?;
+            let flags = codec.get_u8()?;
+            Ok(Record { value, flags })
+        }
+    }
+
+    impl Versioned for Record {
+        const SUPPORTED_VERSIONS: &'static [FormatVersion] = &[V1, V2];
+    }
+
+    impl VersionedSerialize for Record {
+        fn serialize_with_version<W: Write>(
+            &self,
+            version: FormatVersion,
+            writer: W,
+        ) -> Result<(), Self::Error> {
+            match version {
+                V1 => Codec::from(writer).put_u32(self.value),
+                _ => self.serialize(writer),
+            }
+        }
+    }
+
+    impl VersionedDeserialize for Record {
+        fn deserialize_with_version<R: BufRead>(
+            version: FormatVersion,
+            reader: R,
+        ) -> Result<Self, Self::Error> {
+            match version {
+                V1 => Ok(Record {
+                    value: Codec::from(reader).get_u32()?,
+                    flags: 0,
+                }),
+                _ => Self::deserialize(reader),
+            }
+        }
+    }
+
+    const RECORD: Record = Record {
+        value: 0x0102_0304,
+        flags: 0x05,
+    };
+
+    fn versioned(version: FormatVersion) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        RECORD.serialize_versioned(version, &mut bytes).unwrap();
+        bytes
+    }
+
+    fn assert_unsupported<T: std::fmt::Debug>(result: Result<T, io::Error>, version: u16) {
+        let error = result.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(
+            error
+                .get_ref()
+                .and_then(|e| e.downcast_ref::<UnsupportedFormatVersion>()),
+            Some(&UnsupportedFormatVersion(FormatVersion(version)))
+        );
+    }
+
+    #[test]
+    fn current_version() {
+        assert_eq!(Record::current_version(), V2);
+        assert!(Record::supports_version(V1));
+        assert!(!Record::supports_version(FormatVersion(3)));
+    }
+
+    #[test]
+    fn versioned_round_trip() {
+        let v1 = versioned(V1);
+        assert_eq!(v1, [0x00, 0x01, 0x01, 0x02, 0x03, 0x04]);
+        assert_eq!(
+            Record::deserialize_versioned(&v1[..]).unwrap(),
+            Record {
+                value: RECORD.value,
+                flags: 0,
+            }
+        );
+
+        let v2 = versioned(V2);
+        assert_eq!(v2, [0x00, 0x02, 0x01, 0x02, 0x03, 0x04, 0x05]);
+        assert_eq!(Record::deserialize_versioned(&v2[..]).unwrap(), RECORD);
+        // the current version is the plain serialization in the envelope
+        assert_eq!(&v2[2..], &RECORD.serialize_as_vec().unwrap()[..]);
+    }
+
+    #[test]
+    fn unknown_version_rejected() {
+        let mut bytes = versioned(V2);
+        bytes[1] = 3;
+        assert_unsupported(Record::deserialize_versioned(&bytes[..]), 3);
+        bytes[0] = 1;
+        bytes[1] = 0;
+        assert_unsupported(Record::deserialize_versioned(&bytes[..]), 256);
+
+        assert_unsupported(RECORD.serialize_versioned(FormatVersion(3), Vec::new()), 3);
+        assert_unsupported(RECORD.serialize_versioned(FormatVersion(0), Vec::new()), 0);
+    }
+
+    #[test]
+    fn negotiation() {
+        let ours = Record::SUPPORTED_VERSIONS;
+        assert_eq!(negotiate_version(ours, &[V1]), Some(V1));
+        assert_eq!(
+            negotiate_version(ours, &[V1, V2, FormatVersion(3)]),
+            Some(V2)
+        );
+        assert_eq!(negotiate_version(ours, &[FormatVersion(3)]), None);
+        assert_eq!(negotiate_version(ours, &[]), None);
+    }
+}
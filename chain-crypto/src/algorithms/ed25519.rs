@@ -1,7 +1,14 @@
 use crate::key::{AsymmetricKey, PublicKeyError, SecretKeyError};
+use crate::securemem::{self, SecureMem};
 use crate::sign::{SignatureError, SigningAlgorithm, Verification, VerificationAlgorithm};
+use alloc::vec::Vec;
 use cryptoxide::ed25519;
-use rand::{CryptoRng, RngCore};
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::{IsIdentity, VartimeMultiscalarMul};
+use rand::{CryptoRng, Rng, RngCore};
+use sha2::{Digest, Sha512};
 
 use ed25519_bip32::XPub;
 
@@ -9,7 +16,7 @@ use ed25519_bip32::XPub;
 pub struct Ed25519;
 
 #[derive(Clone)]
-pub struct Priv([u8; ed25519::SEED_LENGTH]);
+pub struct Priv(SecureMem);
 
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Pub(pub(crate) [u8; ed25519::PUBLIC_KEY_LENGTH]);
@@ -27,7 +34,7 @@ impl Pub {
 
 impl AsRef<[u8]> for Priv {
     fn as_ref(&self) -> &[u8] {
-        &self.0[..]
+        self.0.as_ref()
     }
 }
 
@@ -54,13 +61,14 @@ impl AsymmetricKey for Ed25519 {
     const PUBLIC_KEY_SIZE: usize = ed25519::PUBLIC_KEY_LENGTH;
 
     fn generate<T: RngCore + CryptoRng>(mut rng: T) -> Self::Secret {
-        let mut priv_bytes = [0u8; ed25519::SEED_LENGTH];
-        rng.fill_bytes(&mut priv_bytes);
+        let mut priv_bytes = SecureMem::new(ed25519::SEED_LENGTH);
+        rng.fill_bytes(priv_bytes.as_mut());
         Priv(priv_bytes)
     }
 
     fn compute_public(key: &Self::Secret) -> Self::Public {
-        let (_, pk) = ed25519::keypair(&key.0);
+        let (mut sk, pk) = ed25519::keypair(key.0.as_ref());
+        securemem::zero(&mut sk);
         Pub(pk)
     }
 
@@ -68,9 +76,7 @@ impl AsymmetricKey for Ed25519 {
         if data.len() != ed25519::SEED_LENGTH {
             return Err(SecretKeyError::SizeInvalid);
         }
-        let mut buf = [0; ed25519::SEED_LENGTH];
-        buf[0..ed25519::SEED_LENGTH].clone_from_slice(data);
-        Ok(Priv(buf))
+        Ok(Priv(SecureMem::from_slice(data)))
     }
     fn public_from_binary(data: &[u8]) -> Result<Self::Public, PublicKeyError> {
         if data.len() != ed25519::PUBLIC_KEY_LENGTH {
@@ -104,12 +110,98 @@ impl VerificationAlgorithm for Ed25519 {
     ) -> Verification {
         ed25519::verify(msg, &pubkey.0, signature.as_ref()).into()
     }
+
+    fn verify_batch(batch: &[(&Self::Public, &Self::Signature, &[u8])]) -> Verification {
+        verify_batch(batch)
+    }
+}
+
+/// below this number of signatures, the batch is verified one signature at
+/// a time
+const BATCH_MIN_SIZE: usize = 4;
+
+fn verify_one_by_one(batch: &[(&Pub, &Sig, &[u8])]) -> Verification {
+    batch
+        .iter()
+        .all(|(pubkey, signature, msg)| ed25519::verify(msg, &pubkey.0, &signature.0))
+        .into()
+}
+
+/// verify the (public key, signature, message) of the batch at once
+#[cfg(feature = "std")]
+pub(crate) fn verify_batch(batch: &[(&Pub, &Sig, &[u8])]) -> Verification {
+    if batch.len() < BATCH_MIN_SIZE {
+        return verify_one_by_one(batch);
+    }
+    verify_random_combination(batch, &mut rand::thread_rng())
+}
+
+/// without `std` there is no source of randomness for the coefficients of
+/// the combination, the signatures are verified one by one
+#[cfg(not(feature = "std"))]
+pub(crate) fn verify_batch(batch: &[(&Pub, &Sig, &[u8])]) -> Verification {
+    verify_one_by_one(batch)
+}
+
+/// With random 128 bits `z_i`, the batch is valid if
+/// `sum(z_i * s_i) * B = sum(z_i * R_i) + sum(z_i * H(R_i | A_i | M_i) * A_i)`,
+/// which costs about half of verifying the signatures one by one.
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+fn verify_random_combination<R: Rng + CryptoRng>(
+    batch: &[(&Pub, &Sig, &[u8])],
+    rng: &mut R,
+) -> Verification {
+    let mut scalars = Vec::with_capacity(2 * batch.len() + 1);
+    let mut points = Vec::with_capacity(2 * batch.len() + 1);
+    let mut base_scalar = Scalar::zero();
+    for (pubkey, signature, msg) in batch {
+        let (r_bytes, s_bytes) = signature.0.split_at(32);
+        // same checks of `s` as the single signature verification
+        if s_bytes[31] & 0b1110_0000 != 0 {
+            return Verification::Failed;
+        }
+        let mut s = [0; 32];
+        s.copy_from_slice(s_bytes);
+        let s = Scalar::from_bits(s);
+        let a = match CompressedEdwardsY::from_slice(&pubkey.0).decompress() {
+            Some(a) => a,
+            None => return Verification::Failed,
+        };
+        let r = match CompressedEdwardsY::from_slice(r_bytes).decompress() {
+            Some(r) => r,
+            None => return Verification::Failed,
+        };
+
+        let mut h = Sha512::new();
+        h.input(r_bytes);
+        h.input(&pubkey.0);
+        h.input(msg);
+        let k = Scalar::from_hash(h);
+
+        let mut z = [0; 32];
+        rng.fill(&mut z[..16]);
+        let z = Scalar::from_bytes_mod_order(z);
+
+        base_scalar -= z * s;
+        scalars.push(z);
+        points.push(r);
+        scalars.push(z * k);
+        points.push(a);
+    }
+    scalars.push(base_scalar);
+    points.push(ED25519_BASEPOINT_POINT);
+
+    EdwardsPoint::vartime_multiscalar_mul(scalars, points)
+        .is_identity()
+        .into()
 }
 
 impl SigningAlgorithm for Ed25519 {
     fn sign(key: &Self::Secret, msg: &[u8]) -> Sig {
-        let (sk, _) = ed25519::keypair(&key.0);
-        Sig(ed25519::signature(msg, &sk))
+        let (mut sk, _) = ed25519::keypair(key.0.as_ref());
+        let signature = ed25519::signature(msg, &sk);
+        securemem::zero(&mut sk);
+        Sig(signature)
     }
 }
 
@@ -117,8 +209,10 @@ impl SigningAlgorithm for Ed25519 {
 mod test {
     use super::*;
 
-    use crate::key::{KeyPair, PublicKey};
-    use crate::sign::test::{keypair_signing_ko, keypair_signing_ok};
+    use crate::bech32::Bech32;
+    use crate::key::{KeyPair, PublicKey, SecretKey};
+    use crate::sign::test::{batch_signing_ok, keypair_signing_ko, keypair_signing_ok};
+    use crate::sign::Signature;
 
     quickcheck! {
         fn sign_ok(input: (KeyPair<Ed25519>, Vec<u8>)) -> bool {
@@ -127,5 +221,37 @@ mod test {
         fn sign_ko(input: (KeyPair<Ed25519>, PublicKey<Ed25519>, Vec<u8>)) -> bool {
             keypair_signing_ko(input)
         }
+        fn batch_sign_ok(input: (Vec<(KeyPair<Ed25519>, Vec<u8>)>, usize)) -> bool {
+            batch_signing_ok(input)
+        }
+    }
+
+    #[test]
+    fn golden_vectors() {
+        for vector in test_vectors::ed25519() {
+            let sk = SecretKey::<Ed25519>::from_binary(&vector.secret_key).unwrap();
+            let pk = sk.to_public();
+            assert_eq!(pk.as_ref(), &vector.public_key[..], "{}", vector.name);
+            assert_eq!(
+                pk.to_bech32_str(),
+                vector.public_key_bech32,
+                "{}",
+                vector.name
+            );
+            assert_eq!(
+                PublicKey::<Ed25519>::try_from_bech32_str(&vector.public_key_bech32).unwrap(),
+                pk,
+                "{}",
+                vector.name
+            );
+
+            let signature = Signature::generate(&sk, &vector.message);
+            assert_eq!(signature.as_ref(), &vector.signature[..], "{}", vector.name);
+            let signature = Signature::<Vec<u8>, Ed25519>::from_binary(&vector.signature).unwrap();
+            match signature.verify(&pk, &vector.message) {
+                Verification::Success => (),
+                Verification::Failed => panic!("{}: signature verification failed", vector.name),
+            }
+        }
     }
 }
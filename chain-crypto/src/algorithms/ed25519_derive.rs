@@ -1,13 +1,65 @@
-use crate::key::{AsymmetricKey, PublicKeyError, SecretKeyError};
+use crate::key::{AsymmetricKey, PublicKey, PublicKeyError, SecretKey, SecretKeyError};
 use crate::sign::{SignatureError, SigningAlgorithm, Verification, VerificationAlgorithm};
 
+use super::ed25519 as ei;
+use super::ed25519_extended::{Ed25519Extended, ExtendedPriv};
+
 use ed25519_bip32 as i;
-use ed25519_bip32::{XPrv, XPub, XPRV_SIZE, XPUB_SIZE};
+use ed25519_bip32::{DerivationScheme, XPrv, XPub, XPRV_SIZE, XPUB_SIZE};
 use rand::{CryptoRng, RngCore};
 
+pub use ed25519_bip32::{DerivationError, DerivationIndex};
+
 /// Ed25519 BIP32 Signature algorithm
 pub struct Ed25519Bip32;
 
+/// first hardened index: the children from this index can only be
+/// derived from the secret key
+pub const HARDENED_INDEX: DerivationIndex = 0x8000_0000;
+
+/// children are derived with the V2 scheme of BIP32-Ed25519
+const SCHEME: DerivationScheme = DerivationScheme::V2;
+
+impl SecretKey<Ed25519Bip32> {
+    /// derive the child key at the given index, hardened or soft
+    pub fn derive(&self, index: DerivationIndex) -> Self {
+        SecretKey(self.0.derive(SCHEME, index))
+    }
+
+    /// derive the descendant key following the given path of indices
+    pub fn derive_path(&self, path: &[DerivationIndex]) -> Self {
+        path.iter()
+            .fold(self.clone(), |key, index| key.derive(*index))
+    }
+
+    /// the extended Ed25519 secret key, without the chain code
+    pub fn to_extended(&self) -> SecretKey<Ed25519Extended> {
+        SecretKey(ExtendedPriv::from_xprv(&self.0))
+    }
+}
+
+impl PublicKey<Ed25519Bip32> {
+    /// derive the child public key at the given soft index, so the
+    /// public keys of an account can be derived from its public key
+    /// alone. Fails for the hardened indices
+    pub fn derive(&self, index: DerivationIndex) -> Result<Self, DerivationError> {
+        Ok(PublicKey(self.0.derive(SCHEME, index)?))
+    }
+
+    /// derive the descendant public key following the given path of
+    /// soft indices
+    pub fn derive_path(&self, path: &[DerivationIndex]) -> Result<Self, DerivationError> {
+        path.iter()
+            .try_fold(self.clone(), |key, index| key.derive(*index))
+    }
+
+    /// the Ed25519 public key, without the chain code, as used in the
+    /// addresses
+    pub fn to_extended(&self) -> PublicKey<Ed25519Extended> {
+        PublicKey(ei::Pub::from_xpub(&self.0))
+    }
+}
+
 impl From<i::PrivateKeyError> for SecretKeyError {
     fn from(v: i::PrivateKeyError) -> Self {
         match v {
@@ -96,9 +148,106 @@ impl SigningAlgorithm for Ed25519Bip32 {
 mod test {
     use super::*;
 
+    use crate::hex;
     use crate::key::{KeyPair, PublicKey};
     use crate::sign::test::{keypair_signing_ko, keypair_signing_ok};
 
+    /// the root key of the derivation test vectors of `ed25519-bip32`
+    const ROOT_XPRV: &str = "f8a29231ee38d6c5bf715d5bac21c750577aa3798b22d79d65bf97d6fadea15adcd1ee1abdf78bd4be64731a12deb94d3671784112eb6f364b871851fd1c9a247384db9ad6003bbd08b3b1ddc0d07a597293ff85e961bf252b331262eddfad0d";
+
+    /// (index, child secret key, child public key) of `ROOT_XPRV`
+    const CHILDREN: [(DerivationIndex, &str, &str); 5] = [
+        (
+            0,
+            "e86a12ba078cdbdf044b488624a50b9f681086c5e7c005222c6fb69e02dfa15a28630505d5878465269ecf096b7ec855780e6e4aed06852676e8ced5bd66d1dad6324d15fe0641021a711f3ef93865b2e41c3cef61b155d57a988156074ce2a8",
+            "10abaae2cf8f9c2d0cee0a880c0c3f6fcaaae9a1edff667fc567a117f6359c20d6324d15fe0641021a711f3ef93865b2e41c3cef61b155d57a988156074ce2a8",
+        ),
+        (
+            1,
+            "182cc96f3080b896ad0c8298879f1db02662890bae97bbb0aaaa64c4ffdea15a130b8cf2a43aa05cbfb1b72a50da56a02a5e958c27f9f9f5d83f238bbb87f612c4b7efaf0e908758d0a825719cd42449fceeff81c3c4fbd3914f3223ea7dc3ef",
+            "355c0ed105a9054a59a4ec45ae9624b62c172f5347f16f9afa8abfb01b30e4ecc4b7efaf0e908758d0a825719cd42449fceeff81c3c4fbd3914f3223ea7dc3ef",
+        ),
+        (
+            HARDENED_INDEX - 1,
+            "787d78a3e0fde98fd11fca52dee92e35340ce90ead9dcedf3fa7259701dfa15ac11ab78341bf884c3a20e1bb36afb6121242a975152207a7be3e6bc4f74da79af4dde3a6c13c7594c84045c4795de2649138e186382ac1ac88092aabe7da93c2",
+            "60d14f4da1b4a9ec4ed63a0f86aa207725d033805454c98b28222effb883db02f4dde3a6c13c7594c84045c4795de2649138e186382ac1ac88092aabe7da93c2",
+        ),
+        (
+            HARDENED_INDEX,
+            "60d399da83ef80d8d4f8d223239efdc2b8fef387e1b5219137ffb4e8fbdea15adc9366b7d003af37c11396de9a83734e30e05e851efa32745c9cd7b42712c890608763770eddf77248ab652984b21b849760d1da74a6f5bd633ce41adceef07a",
+            "9c99845ae0a60881decf7874b94ad02e7540628b588f8b4305d668cc53986978608763770eddf77248ab652984b21b849760d1da74a6f5bd633ce41adceef07a",
+        ),
+        (
+            0xffff_ffff,
+            "000cd6e44160c7b41248e570b75c70410ccbf351fc6149182708c6b6ffdea15ac44330195d10f40f3656b29593d9eaa3ebd75745333bc0962d48a830c40d9c0db2f0bde130358890bd87461af29cea5791067d2f5eb50c205b0c449de0d5a041",
+            "bb9903a8b4425106b2753c12b1df00b99f78bda351c3331082b3f8f1c6b19345b2f0bde130358890bd87461af29cea5791067d2f5eb50c205b0c449de0d5a041",
+        ),
+    ];
+
+    fn root() -> SecretKey<Ed25519Bip32> {
+        SecretKey::from_binary(&hex::decode(ROOT_XPRV).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn derivation_known_answers() {
+        let root = root();
+        for (index, xprv, xpub) in CHILDREN.iter() {
+            let child = root.derive(*index);
+            assert_eq!(
+                hex::encode(child.0.as_ref()),
+                *xprv,
+                "secret key {:#x}",
+                index
+            );
+            assert_eq!(
+                hex::encode(child.to_public()),
+                *xpub,
+                "public key {:#x}",
+                index
+            );
+
+            let public = root.to_public().derive(*index);
+            if *index < HARDENED_INDEX {
+                assert_eq!(
+                    hex::encode(public.unwrap()),
+                    *xpub,
+                    "public derivation {:#x}",
+                    index
+                );
+            } else {
+                assert_eq!(public, Err(DerivationError::ExpectedSoftDerivation));
+            }
+        }
+    }
+
+    #[test]
+    fn derivation_matches_ed25519_bip32() {
+        let root = root();
+        let path = [
+            HARDENED_INDEX | 44,
+            HARDENED_INDEX | 1815,
+            HARDENED_INDEX,
+            0,
+            42,
+        ];
+        let expected = path
+            .iter()
+            .fold(root.0.clone(), |xprv, index| xprv.derive(SCHEME, *index));
+        let child = root.derive_path(&path);
+        assert_eq!(child.0.as_ref(), expected.as_ref());
+
+        let account = root.derive_path(&path[..3]).to_public();
+        let expected = path[3..]
+            .iter()
+            .try_fold(account.0.clone(), |xpub, index| xpub.derive(SCHEME, *index))
+            .unwrap();
+        assert_eq!(
+            account.derive_path(&path[3..]).unwrap().as_ref(),
+            expected.as_ref()
+        );
+        assert_eq!(child.to_public(), account.derive_path(&path[3..]).unwrap());
+    }
+
     quickcheck! {
         fn sign_ok(input: (KeyPair<Ed25519Bip32>, Vec<u8>)) -> bool {
             keypair_signing_ok(input)
@@ -106,5 +255,22 @@ mod test {
         fn sign_ko(input: (KeyPair<Ed25519Bip32>, PublicKey<Ed25519Bip32>, Vec<u8>)) -> bool {
             keypair_signing_ko(input)
         }
+
+        fn soft_derivation_commutes(input: (KeyPair<Ed25519Bip32>, Vec<DerivationIndex>)) -> bool {
+            let (keypair, path) = input;
+            let path: Vec<_> = path.iter().take(8).map(|index| index % HARDENED_INDEX).collect();
+            let secret = keypair.private_key().derive_path(&path);
+            let public = keypair.public_key().derive_path(&path).unwrap();
+            secret.to_public() == public
+                && secret.to_extended().to_public() == public.to_extended()
+        }
+
+        fn hardened_derivation_needs_secret(input: (KeyPair<Ed25519Bip32>, DerivationIndex)) -> bool {
+            let (keypair, index) = input;
+            let index = index | HARDENED_INDEX;
+            let child = keypair.private_key().derive(index);
+            keypair.public_key().derive(index) == Err(DerivationError::ExpectedSoftDerivation)
+                && child.to_public() != keypair.public_key().derive(index % HARDENED_INDEX).unwrap()
+        }
     }
 }
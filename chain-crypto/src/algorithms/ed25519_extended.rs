@@ -1,4 +1,5 @@
 use crate::key::{AsymmetricKey, PublicKeyError, SecretKeyError};
+use crate::securemem::{self, SecureMem};
 use crate::sign::{SignatureError, SigningAlgorithm, Verification, VerificationAlgorithm};
 
 use super::ed25519 as ei;
@@ -12,19 +13,19 @@ use ed25519_bip32::{XPrv, XPRV_SIZE};
 pub struct Ed25519Extended;
 
 #[derive(Clone)]
-pub struct ExtendedPriv([u8; ed25519::PRIVATE_KEY_LENGTH]);
+pub struct ExtendedPriv(SecureMem);
 
 impl AsRef<[u8]> for ExtendedPriv {
     fn as_ref(&self) -> &[u8] {
-        &self.0[..]
+        self.0.as_ref()
     }
 }
 
 impl ExtendedPriv {
     pub fn from_xprv(xprv: &XPrv) -> Self {
-        let mut buf = [0; ed25519::PRIVATE_KEY_LENGTH];
-        xprv.get_extended(&mut buf);
-        ExtendedPriv(buf)
+        ExtendedPriv(SecureMem::from_slice(
+            &xprv.as_ref()[0..ed25519::PRIVATE_KEY_LENGTH],
+        ))
     }
 }
 
@@ -42,14 +43,12 @@ impl AsymmetricKey for Ed25519Extended {
         let mut priv_bytes = [0u8; XPRV_SIZE];
         rng.fill_bytes(&mut priv_bytes);
         let xprv = XPrv::normalize_bytes(priv_bytes);
-
-        let mut out = [0u8; ed25519::PRIVATE_KEY_LENGTH];
-        xprv.get_extended(&mut out);
-        ExtendedPriv(out)
+        securemem::zero(&mut priv_bytes);
+        ExtendedPriv::from_xprv(&xprv)
     }
 
     fn compute_public(key: &Self::Secret) -> Self::Public {
-        let pk = ed25519::to_public(&key.0);
+        let pk = ed25519::to_public(key.0.as_ref());
         ei::Pub(pk)
     }
 
@@ -57,10 +56,8 @@ impl AsymmetricKey for Ed25519Extended {
         if data.len() != ed25519::PRIVATE_KEY_LENGTH {
             return Err(SecretKeyError::SizeInvalid);
         }
-        let mut buf = [0; ed25519::PRIVATE_KEY_LENGTH];
-        buf[0..ed25519::PRIVATE_KEY_LENGTH].clone_from_slice(data);
         // TODO structure check
-        Ok(ExtendedPriv(buf))
+        Ok(ExtendedPriv(SecureMem::from_slice(data)))
     }
     fn public_from_binary(data: &[u8]) -> Result<Self::Public, PublicKeyError> {
         if data.len() != ed25519::PUBLIC_KEY_LENGTH {
@@ -94,11 +91,15 @@ impl VerificationAlgorithm for Ed25519Extended {
     ) -> Verification {
         ed25519::verify(msg, &pubkey.0, signature.as_ref()).into()
     }
+
+    fn verify_batch(batch: &[(&Self::Public, &Self::Signature, &[u8])]) -> Verification {
+        ei::verify_batch(batch)
+    }
 }
 
 impl SigningAlgorithm for Ed25519Extended {
     fn sign(key: &Self::Secret, msg: &[u8]) -> ei::Sig {
-        ei::Sig(ed25519::signature_extended(msg, &key.0))
+        ei::Sig(ed25519::signature_extended(msg, key.0.as_ref()))
     }
 }
 
@@ -107,7 +108,9 @@ mod test {
     use super::*;
 
     use crate::key::{KeyPair, PublicKey};
-    use crate::sign::test::{keypair_signing_ko, keypair_signing_ok};
+    use crate::sign::test::{
+        batch_signing_ok, keypair_signing_ko, keypair_signing_ok, signer_signing_ok,
+    };
 
     quickcheck! {
         fn sign_ok(input: (KeyPair<Ed25519Extended>, Vec<u8>)) -> bool {
@@ -116,5 +119,11 @@ mod test {
         fn sign_ko(input: (KeyPair<Ed25519Extended>, PublicKey<Ed25519Extended>, Vec<u8>)) -> bool {
             keypair_signing_ko(input)
         }
+        fn batch_sign_ok(input: (Vec<(KeyPair<Ed25519Extended>, Vec<u8>)>, usize)) -> bool {
+            batch_signing_ok(input)
+        }
+        fn signer_sign_ok(input: (KeyPair<Ed25519Extended>, Vec<u8>)) -> bool {
+            signer_signing_ok(input)
+        }
     }
 }
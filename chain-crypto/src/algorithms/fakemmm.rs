@@ -1,5 +1,6 @@
 use crate::kes::KeyEvolvingSignatureAlgorithm;
 use crate::key::{AsymmetricKey, PublicKeyError, SecretKeyError};
+use crate::securemem::{self, SecureMem};
 use crate::sign::{SignatureError, Verification, VerificationAlgorithm};
 use cryptoxide::ed25519;
 use rand::{CryptoRng, RngCore};
@@ -8,7 +9,7 @@ use rand::{CryptoRng, RngCore};
 pub struct FakeMMM;
 
 #[derive(Clone)]
-pub struct Priv([u8; ed25519::SEED_LENGTH]);
+pub struct Priv(SecureMem);
 
 #[derive(Clone, PartialEq, Eq, Hash)]
 pub struct Pub([u8; ed25519::PUBLIC_KEY_LENGTH]);
@@ -18,7 +19,7 @@ pub struct Sig([u8; ed25519::SIGNATURE_LENGTH]);
 
 impl AsRef<[u8]> for Priv {
     fn as_ref(&self) -> &[u8] {
-        &self.0[..]
+        self.0.as_ref()
     }
 }
 
@@ -45,13 +46,14 @@ impl AsymmetricKey for FakeMMM {
     const PUBLIC_KEY_SIZE: usize = ed25519::PUBLIC_KEY_LENGTH;
 
     fn generate<T: RngCore + CryptoRng>(mut rng: T) -> Priv {
-        let mut priv_bytes = [0u8; ed25519::SEED_LENGTH];
-        rng.fill_bytes(&mut priv_bytes);
+        let mut priv_bytes = SecureMem::new(ed25519::SEED_LENGTH);
+        rng.fill_bytes(priv_bytes.as_mut());
         Priv(priv_bytes)
     }
 
     fn compute_public(key: &Priv) -> Pub {
-        let (_, pk) = ed25519::keypair(&key.0);
+        let (mut sk, pk) = ed25519::keypair(key.0.as_ref());
+        securemem::zero(&mut sk);
         Pub(pk)
     }
 
@@ -59,9 +61,7 @@ impl AsymmetricKey for FakeMMM {
         if data.len() != ed25519::SEED_LENGTH {
             return Err(SecretKeyError::SizeInvalid);
         }
-        let mut buf = [0; ed25519::SEED_LENGTH];
-        buf[0..ed25519::SEED_LENGTH].clone_from_slice(data);
-        Ok(Priv(buf))
+        Ok(Priv(SecureMem::from_slice(data)))
     }
     fn public_from_binary(data: &[u8]) -> Result<Pub, PublicKeyError> {
         if data.len() != ed25519::PUBLIC_KEY_LENGTH {
@@ -97,9 +97,22 @@ impl VerificationAlgorithm for FakeMMM {
     }
 }
 
+/// the key never evolves
 impl KeyEvolvingSignatureAlgorithm for FakeMMM {
+    const PERIODS: usize = 1;
+
+    fn get_period(_key: &Self::Secret) -> usize {
+        0
+    }
+
+    fn update(_key: &mut Self::Secret) -> bool {
+        false
+    }
+
     fn sign_update(key: &mut Self::Secret, msg: &[u8]) -> Sig {
-        let (sk, _) = ed25519::keypair(&key.0);
-        Sig(ed25519::signature(msg, &sk))
+        let (mut sk, _) = ed25519::keypair(key.0.as_ref());
+        let signature = ed25519::signature(msg, &sk);
+        securemem::zero(&mut sk);
+        Sig(signature)
     }
 }
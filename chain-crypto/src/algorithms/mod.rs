@@ -2,10 +2,12 @@ mod ed25519;
 mod ed25519_derive;
 mod ed25519_extended;
 mod fakemmm;
+mod sumed25519;
 pub mod vrf;
 
 pub use ed25519::Ed25519;
-pub use ed25519_derive::Ed25519Bip32;
+pub use ed25519_derive::{DerivationError, DerivationIndex, Ed25519Bip32, HARDENED_INDEX};
 pub use ed25519_extended::Ed25519Extended;
 pub use fakemmm::FakeMMM;
+pub use sumed25519::SumEd25519_12;
 pub use vrf::Curve25519_2HashDH;
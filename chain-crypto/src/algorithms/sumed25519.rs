@@ -0,0 +1,427 @@
+//! Key evolving signatures with the sum composition of the MMM paper
+//! (Malkin, Micciancio, Miner: "Efficient generic forward-secure
+//! signatures with an unbounded number of time periods"), over Ed25519.
+//!
+//! A key of depth `d` signs for `2^d` periods. It is a binary tree of
+//! Ed25519 keys: the public key of a node is the hash of the public keys
+//! of its two children, and the public key of the scheme is the one of
+//! the root. The secret key only holds the Ed25519 seed of the leaf of
+//! the current period, the seeds of the right subtrees not used yet and
+//! the public keys along the path; evolving the key erases the seeds of
+//! the current period, so a compromised key cannot sign for the past.
+//!
+//! A signature holds the period, the Ed25519 signature by the leaf, the
+//! leaf public key and the public keys of the siblings along the path,
+//! from which the verifier recomputes the root public key.
+
+use crate::hash::Blake2b256;
+use crate::kes::KeyEvolvingSignatureAlgorithm;
+use crate::key::{AsymmetricKey, PublicKeyError, SecretKeyError};
+use crate::securemem::{self, SecureMem};
+use crate::sign::{SignatureError, Verification, VerificationAlgorithm};
+use alloc::{vec, vec::Vec};
+use cryptoxide::ed25519;
+use rand::{CryptoRng, RngCore};
+
+const PERIOD_SIZE: usize = 4;
+const SEED_SIZE: usize = ed25519::SEED_LENGTH;
+const PUBLIC_SIZE: usize = ed25519::PUBLIC_KEY_LENGTH;
+const ED25519_SIGNATURE_SIZE: usize = ed25519::SIGNATURE_LENGTH;
+
+/// depth of the tree of `SumEd25519_12`: its keys sign for 4096 periods
+pub const DEPTH: usize = 12;
+
+const fn secret_size(depth: usize) -> usize {
+    PERIOD_SIZE + SEED_SIZE + PUBLIC_SIZE + depth * (SEED_SIZE + 2 * PUBLIC_SIZE)
+}
+
+const fn signature_size(depth: usize) -> usize {
+    PERIOD_SIZE + ED25519_SIGNATURE_SIZE + PUBLIC_SIZE + depth * PUBLIC_SIZE
+}
+
+/// Sum composition of depth 12 over Ed25519
+pub struct SumEd25519_12;
+
+/// Secret key: the period, the seed and public key of the current leaf,
+/// then for each level of the tree from the root the seed of the right
+/// subtree (zeroed once used) and the public keys of the two children
+#[derive(Clone)]
+pub struct Priv(SecureMem);
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct Pub([u8; PUBLIC_SIZE]);
+
+/// Signature: the period, the Ed25519 signature, the leaf public key and
+/// the public keys of the siblings of the path, from the root
+#[derive(Clone)]
+pub struct Sig(Vec<u8>);
+
+impl AsRef<[u8]> for Priv {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+impl AsRef<[u8]> for Pub {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl AsRef<[u8]> for Sig {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl Sig {
+    /// the period the signature was made for
+    pub fn period(&self) -> u32 {
+        read_period(&self.0)
+    }
+}
+
+fn read_period(bytes: &[u8]) -> u32 {
+    let mut period = [0u8; PERIOD_SIZE];
+    period.copy_from_slice(&bytes[0..PERIOD_SIZE]);
+    u32::from_be_bytes(period)
+}
+
+const LEAF_SEED: usize = PERIOD_SIZE;
+const LEAF_PUBLIC: usize = LEAF_SEED + SEED_SIZE;
+const SEEDS: usize = LEAF_PUBLIC + PUBLIC_SIZE;
+
+fn seed_offset(level: usize) -> usize {
+    SEEDS + level * SEED_SIZE
+}
+
+fn pair_offset(depth: usize, level: usize) -> usize {
+    SEEDS + depth * SEED_SIZE + level * 2 * PUBLIC_SIZE
+}
+
+fn split_seed(seed: &[u8]) -> ([u8; SEED_SIZE], [u8; SEED_SIZE]) {
+    let mut buf = [0u8; SEED_SIZE + 1];
+    buf[1..].copy_from_slice(seed);
+    buf[0] = 1;
+    let left = *Blake2b256::new(&buf).as_hash_bytes();
+    buf[0] = 2;
+    let right = *Blake2b256::new(&buf).as_hash_bytes();
+    securemem::zero(&mut buf);
+    (left, right)
+}
+
+fn hash_pair(left: &[u8], right: &[u8]) -> [u8; PUBLIC_SIZE] {
+    let mut buf = [0u8; 2 * PUBLIC_SIZE];
+    buf[..PUBLIC_SIZE].copy_from_slice(left);
+    buf[PUBLIC_SIZE..].copy_from_slice(right);
+    *Blake2b256::new(&buf).as_hash_bytes()
+}
+
+/// public key of the subtree of the given depth generated by `seed`
+fn subtree_public(depth: usize, seed: &[u8]) -> [u8; PUBLIC_SIZE] {
+    if depth == 0 {
+        return ed25519::keypair(seed).1;
+    }
+    let (left, right) = split_seed(seed);
+    hash_pair(
+        &subtree_public(depth - 1, &left),
+        &subtree_public(depth - 1, &right),
+    )
+}
+
+/// generate in the key the leftmost path of the subtree rooted at
+/// `level`, and return the public key of the subtree
+fn generate_path(key: &mut [u8], depth: usize, level: usize, seed: &[u8]) -> [u8; PUBLIC_SIZE] {
+    if level == depth {
+        let public = ed25519::keypair(seed).1;
+        key[LEAF_SEED..LEAF_PUBLIC].copy_from_slice(seed);
+        key[LEAF_PUBLIC..SEEDS].copy_from_slice(&public);
+        return public;
+    }
+    let (mut left, mut right) = split_seed(seed);
+    let left_public = generate_path(key, depth, level + 1, &left);
+    let right_public = subtree_public(depth - level - 1, &right);
+
+    key[seed_offset(level)..seed_offset(level + 1)].copy_from_slice(&right);
+    securemem::zero(&mut left);
+    securemem::zero(&mut right);
+    let pair = pair_offset(depth, level);
+    key[pair..pair + PUBLIC_SIZE].copy_from_slice(&left_public);
+    key[pair + PUBLIC_SIZE..pair + 2 * PUBLIC_SIZE].copy_from_slice(&right_public);
+    hash_pair(&left_public, &right_public)
+}
+
+/// which child of the node at `level` the path of `period` goes through
+fn is_right(depth: usize, level: usize, period: u32) -> bool {
+    (period >> (depth - 1 - level)) & 1 == 1
+}
+
+fn keygen(depth: usize, seed: &[u8]) -> Vec<u8> {
+    let mut key = vec![0u8; secret_size(depth)];
+    generate_path(&mut key, depth, 0, seed);
+    key
+}
+
+fn compute_public(depth: usize, key: &[u8]) -> [u8; PUBLIC_SIZE] {
+    if depth == 0 {
+        let mut public = [0u8; PUBLIC_SIZE];
+        public.copy_from_slice(&key[LEAF_PUBLIC..SEEDS]);
+        return public;
+    }
+    let pair = pair_offset(depth, 0);
+    hash_pair(
+        &key[pair..pair + PUBLIC_SIZE],
+        &key[pair + PUBLIC_SIZE..pair + 2 * PUBLIC_SIZE],
+    )
+}
+
+/// evolve the key to the next period, return false if the key is
+/// already at its last period
+fn update(depth: usize, key: &mut [u8]) -> bool {
+    let next = read_period(key) as u64 + 1;
+    if next >= 1 << depth {
+        return false;
+    }
+    // the path of the next period leaves the one of the current period
+    // at the deepest node where it goes right instead of left
+    let height = next.trailing_zeros() as usize;
+    let level = depth - 1 - height;
+
+    let mut seed = [0u8; SEED_SIZE];
+    seed.copy_from_slice(&key[seed_offset(level)..seed_offset(level + 1)]);
+    securemem::zero(&mut key[seed_offset(level)..seed_offset(level + 1)]);
+    generate_path(key, depth, level + 1, &seed);
+    securemem::zero(&mut seed);
+    key[0..PERIOD_SIZE].copy_from_slice(&(next as u32).to_be_bytes());
+    true
+}
+
+fn sign(depth: usize, key: &[u8], msg: &[u8]) -> Vec<u8> {
+    let period = read_period(key);
+    let (mut leaf_secret, _) = ed25519::keypair(&key[LEAF_SEED..LEAF_PUBLIC]);
+
+    let mut sig = Vec::with_capacity(signature_size(depth));
+    sig.extend_from_slice(&key[0..PERIOD_SIZE]);
+    sig.extend_from_slice(&ed25519::signature(msg, &leaf_secret));
+    securemem::zero(&mut leaf_secret);
+    sig.extend_from_slice(&key[LEAF_PUBLIC..SEEDS]);
+    for level in 0..depth {
+        let pair = pair_offset(depth, level);
+        let sibling = if is_right(depth, level, period) {
+            pair
+        } else {
+            pair + PUBLIC_SIZE
+        };
+        sig.extend_from_slice(&key[sibling..sibling + PUBLIC_SIZE]);
+    }
+    sig
+}
+
+fn verify(depth: usize, public: &[u8], sig: &[u8], msg: &[u8]) -> Verification {
+    if sig.len() != signature_size(depth) {
+        return Verification::Failed;
+    }
+    let period = read_period(sig);
+    if period as u64 >= 1 << depth {
+        return Verification::Failed;
+    }
+    let leaf_public = PERIOD_SIZE + ED25519_SIGNATURE_SIZE;
+    let siblings = leaf_public + PUBLIC_SIZE;
+    if !ed25519::verify(
+        msg,
+        &sig[leaf_public..siblings],
+        &sig[PERIOD_SIZE..leaf_public],
+    ) {
+        return Verification::Failed;
+    }
+
+    let mut node = [0u8; PUBLIC_SIZE];
+    node.copy_from_slice(&sig[leaf_public..siblings]);
+    for level in (0..depth).rev() {
+        let sibling = &sig[siblings + level * PUBLIC_SIZE..siblings + (level + 1) * PUBLIC_SIZE];
+        node = if is_right(depth, level, period) {
+            hash_pair(sibling, &node)
+        } else {
+            hash_pair(&node, sibling)
+        };
+    }
+    (&node[..] == public).into()
+}
+
+impl AsymmetricKey for SumEd25519_12 {
+    type Secret = Priv;
+    type Public = Pub;
+
+    const SECRET_BECH32_HRP: &'static str = "kes12_sk";
+    const PUBLIC_BECH32_HRP: &'static str = "kes12_pk";
+
+    const SECRET_KEY_SIZE: usize = secret_size(DEPTH);
+    const PUBLIC_KEY_SIZE: usize = PUBLIC_SIZE;
+
+    fn generate<T: RngCore + CryptoRng>(mut rng: T) -> Priv {
+        let mut seed = [0u8; SEED_SIZE];
+        rng.fill_bytes(&mut seed);
+        let mut key = keygen(DEPTH, &seed);
+        let secret = Priv(SecureMem::from_slice(&key));
+        securemem::zero(&mut key);
+        securemem::zero(&mut seed);
+        secret
+    }
+
+    fn compute_public(key: &Priv) -> Pub {
+        Pub(compute_public(DEPTH, key.0.as_ref()))
+    }
+
+    fn secret_from_binary(data: &[u8]) -> Result<Priv, SecretKeyError> {
+        if data.len() != Self::SECRET_KEY_SIZE {
+            return Err(SecretKeyError::SizeInvalid);
+        }
+        if read_period(data) as u64 >= 1 << DEPTH {
+            return Err(SecretKeyError::StructureInvalid);
+        }
+        Ok(Priv(SecureMem::from_slice(data)))
+    }
+    fn public_from_binary(data: &[u8]) -> Result<Pub, PublicKeyError> {
+        if data.len() != PUBLIC_SIZE {
+            return Err(PublicKeyError::SizeInvalid);
+        }
+        let mut buf = [0; PUBLIC_SIZE];
+        buf.copy_from_slice(data);
+        Ok(Pub(buf))
+    }
+}
+
+impl VerificationAlgorithm for SumEd25519_12 {
+    type Signature = Sig;
+
+    const SIGNATURE_SIZE: usize = signature_size(DEPTH);
+    const SIGNATURE_BECH32_HRP: &'static str = "kes12_sig";
+
+    fn signature_from_bytes(data: &[u8]) -> Result<Self::Signature, SignatureError> {
+        if data.len() != Self::SIGNATURE_SIZE {
+            return Err(SignatureError::SizeInvalid);
+        }
+        Ok(Sig(data.to_vec()))
+    }
+
+    fn verify_bytes(
+        pubkey: &Self::Public,
+        signature: &Self::Signature,
+        msg: &[u8],
+    ) -> Verification {
+        verify(DEPTH, &pubkey.0, &signature.0, msg)
+    }
+}
+
+impl KeyEvolvingSignatureAlgorithm for SumEd25519_12 {
+    const PERIODS: usize = 1 << DEPTH;
+
+    fn get_period(key: &Priv) -> usize {
+        read_period(key.0.as_ref()) as usize
+    }
+
+    fn update(key: &mut Priv) -> bool {
+        update(DEPTH, key.0.as_mut())
+    }
+
+    fn sign_update(key: &mut Priv, msg: &[u8]) -> Sig {
+        let sig = Sig(sign(DEPTH, key.0.as_ref(), msg));
+        update(DEPTH, key.0.as_mut());
+        sig
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // the keys of depth 12 take too long to generate in debug builds,
+    // the scheme is tested on smaller trees
+    fn check_all_periods(depth: usize) {
+        let seed = [7u8; SEED_SIZE];
+        let mut key = keygen(depth, &seed);
+        let public = compute_public(depth, &key);
+        assert_eq!(public, subtree_public(depth, &seed));
+
+        let mut signatures = Vec::new();
+        for period in 0..1u32 << depth {
+            assert_eq!(read_period(&key), period);
+            // the public key does not change as the key evolves
+            assert_eq!(compute_public(depth, &key), public);
+            let msg = period.to_be_bytes();
+            let sig = sign(depth, &key, &msg);
+            assert_eq!(sig.len(), signature_size(depth));
+            assert_eq!(verify(depth, &public, &sig, &msg), Verification::Success);
+            assert_eq!(
+                verify(depth, &public, &sig, b"another message"),
+                Verification::Failed
+            );
+            signatures.push(sig);
+            assert_eq!(update(depth, &mut key), period + 1 < 1 << depth);
+        }
+        assert_eq!(read_period(&key), (1 << depth) - 1);
+
+        // signatures of past periods remain valid, but not if they claim
+        // another period
+        for (period, sig) in signatures.iter_mut().enumerate() {
+            let msg = (period as u32).to_be_bytes();
+            assert_eq!(verify(depth, &public, sig, &msg), Verification::Success);
+            if depth > 0 {
+                sig[0..PERIOD_SIZE].copy_from_slice(&(period as u32 ^ 1).to_be_bytes());
+                assert_eq!(verify(depth, &public, sig, &msg), Verification::Failed);
+            }
+        }
+    }
+
+    #[test]
+    fn sign_verify_all_periods() {
+        for depth in 0..5 {
+            check_all_periods(depth)
+        }
+    }
+
+    #[test]
+    fn erase_past_seeds() {
+        let depth = 3;
+        let mut key = keygen(depth, &[1u8; SEED_SIZE]);
+        let leaf0 = key[LEAF_SEED..LEAF_PUBLIC].to_vec();
+        assert!(update(depth, &mut key));
+        assert!(update(depth, &mut key));
+        assert!(update(depth, &mut key));
+        assert!(update(depth, &mut key));
+        // at period 4, only the right subtree of the root is left
+        assert_ne!(&key[LEAF_SEED..LEAF_PUBLIC], &leaf0[..]);
+        assert!(key[seed_offset(0)..seed_offset(1)].iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn invalid_signatures() {
+        let depth = 2;
+        let key = keygen(depth, &[3u8; SEED_SIZE]);
+        let public = compute_public(depth, &key);
+        let other_public = subtree_public(depth, &[4u8; SEED_SIZE]);
+        let sig = sign(depth, &key, b"msg");
+
+        assert_eq!(
+            verify(depth, &other_public, &sig, b"msg"),
+            Verification::Failed
+        );
+        assert_eq!(
+            verify(depth, &public, &sig[1..], b"msg"),
+            Verification::Failed
+        );
+        let mut tampered = sig.clone();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert_eq!(
+            verify(depth, &public, &tampered, b"msg"),
+            Verification::Failed
+        );
+        let mut out_of_range = sig.clone();
+        out_of_range[0..PERIOD_SIZE].copy_from_slice(&4u32.to_be_bytes());
+        assert_eq!(
+            verify(depth, &public, &out_of_range, b"msg"),
+            Verification::Failed
+        );
+    }
+}
@@ -3,6 +3,8 @@
 //! defined in the Ouroboros Praos paper
 
 use crate::hash::Blake2b256;
+use alloc::vec::Vec;
+use core::hash::{Hash, Hasher};
 use curve25519_dalek::constants::RISTRETTO_BASEPOINT_POINT;
 use curve25519_dalek::ristretto::CompressedRistretto;
 use curve25519_dalek::ristretto::RistrettoPoint;
@@ -10,7 +12,6 @@ pub use curve25519_dalek::scalar::Scalar;
 use rand::{CryptoRng, Rng};
 use sha2::Digest;
 use sha2::Sha512;
-use std::hash::{Hash, Hasher};
 
 use super::dleq;
 use crate::key::PublicKeyError;
@@ -30,6 +31,15 @@ impl AsRef<[u8]> for SecretKey {
     }
 }
 
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        // volatile so the write is not optimised out, the scalar is not
+        // read afterward
+        unsafe { core::ptr::write_volatile(&mut self.secret, Scalar::zero()) };
+        core::sync::atomic::compiler_fence(core::sync::atomic::Ordering::SeqCst);
+    }
+}
+
 /// VRF Public Key
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PublicKey(Point, CompressedRistretto);
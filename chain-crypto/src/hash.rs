@@ -1,16 +1,21 @@
 //! module to provide some handy interfaces atop the hashes so we have
 //! the common interfaces for the project to work with.
 
-use std::hash::{Hash, Hasher};
-use std::str::FromStr;
-use std::{error, fmt, result};
+use core::hash::{Hash, Hasher};
+use core::str::FromStr;
+use core::{fmt, result};
+#[cfg(feature = "std")]
+use std::{error, io};
 
 use cryptoxide::blake2b::Blake2b;
 use cryptoxide::digest::Digest;
 use cryptoxide::sha3::Sha3;
 
+#[cfg(feature = "std")]
 use crate::bech32::{self, Bech32};
 use crate::hex;
+#[cfg(feature = "std")]
+use alloc::string::String;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Error {
@@ -29,6 +34,7 @@ impl fmt::Display for Error {
         }
     }
 }
+#[cfg(feature = "std")]
 impl error::Error for Error {
     fn source(&self) -> Option<&(dyn error::Error + 'static)> {
         match self {
@@ -118,6 +124,7 @@ macro_rules! define_hash_object {
                 f.write_str(")")
             }
         }
+        #[cfg(feature = "std")]
         impl Bech32 for $hash_ty {
             const BECH32_HRP: &'static str = $bech32_hrp;
 
@@ -161,6 +168,65 @@ impl Sha3_256 {
     }
 }
 
+/// defines an incremental hasher (init/update/finalize) of a hash object
+///
+/// With the `std` feature, the hasher implements `std::io::Write`, so
+/// serializable values can be hashed without materializing their bytes,
+/// and readers can be hashed with `std::io::copy`.
+macro_rules! define_hasher {
+    ($hasher_ty:ident, $hash_ty:ident, $state_ty:ty, $init:expr) => {
+        pub struct $hasher_ty($state_ty);
+        impl $hasher_ty {
+            pub fn new() -> Self {
+                $hasher_ty($init)
+            }
+
+            pub fn update(&mut self, bytes: &[u8]) {
+                self.0.input(bytes)
+            }
+
+            pub fn finalize(mut self) -> $hash_ty {
+                let mut out = [0; $hash_ty::HASH_SIZE];
+                self.0.result(&mut out);
+                $hash_ty::from(out)
+            }
+        }
+        impl Default for $hasher_ty {
+            fn default() -> Self {
+                $hasher_ty::new()
+            }
+        }
+        #[cfg(feature = "std")]
+        impl io::Write for $hasher_ty {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.update(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        #[cfg(feature = "std")]
+        impl $hash_ty {
+            /// hash the content of the reader, until its end
+            pub fn hash_reader<R: io::Read>(mut reader: R) -> io::Result<Self> {
+                let mut hasher = $hasher_ty::new();
+                io::copy(&mut reader, &mut hasher)?;
+                Ok(hasher.finalize())
+            }
+        }
+    };
+}
+
+define_hasher!(
+    Blake2b256Hasher,
+    Blake2b256,
+    Blake2b,
+    Blake2b::new(Blake2b256::HASH_SIZE)
+);
+define_hasher!(Sha3_256Hasher, Sha3_256, Sha3, Sha3::sha3_256());
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -173,4 +239,27 @@ mod test {
             "Blake2b224(0x317512db8239e1f9c2549b04e8071f965983c938d3e649cec78532c7)",
         );
     }
+
+    #[test]
+    fn incremental_hashing() {
+        let bytes: Vec<u8> = (0..=255).cycle().take(1000).collect();
+
+        let mut blake2b = Blake2b256Hasher::new();
+        let mut sha3 = Sha3_256Hasher::new();
+        for chunk in bytes.chunks(7) {
+            blake2b.update(chunk);
+            sha3.update(chunk);
+        }
+        assert_eq!(blake2b.finalize(), Blake2b256::new(&bytes));
+        assert_eq!(sha3.finalize(), Sha3_256::new(&bytes));
+
+        assert_eq!(
+            Blake2b256::hash_reader(&bytes[..]).unwrap(),
+            Blake2b256::new(&bytes)
+        );
+        assert_eq!(
+            Sha3_256::hash_reader(&bytes[..]).unwrap(),
+            Sha3_256::new(&bytes)
+        );
+    }
 }
@@ -1,6 +1,7 @@
 //! simple implementation of hexadecimal encoding and decoding
 
-use std::{error, fmt};
+use alloc::{string::String, vec::Vec};
+use core::fmt;
 
 const ALPHABET: &'static [u8] = b"0123456789abcdef";
 
@@ -36,7 +37,8 @@ impl fmt::Display for DecodeError {
     }
 }
 
-impl error::Error for DecodeError {}
+#[cfg(feature = "std")]
+impl std::error::Error for DecodeError {}
 
 pub fn decode<S: AsRef<[u8]>>(input: S) -> Result<Vec<u8>, DecodeError> {
     decode_bytes(input.as_ref())
@@ -1,5 +1,28 @@
+use crate::key::SecretKey;
 use crate::sign::VerificationAlgorithm;
 
 pub trait KeyEvolvingSignatureAlgorithm: VerificationAlgorithm {
+    /// number of periods a key can sign for
+    const PERIODS: usize;
+
+    /// the period the key currently signs for
+    fn get_period(key: &Self::Secret) -> usize;
+
+    /// evolve the key to the next period, erasing the secret of the
+    /// current one. Return false, leaving the key as is, when the key is
+    /// at its last period
+    fn update(key: &mut Self::Secret) -> bool;
+
+    /// sign with the key of the current period, then evolve the key
     fn sign_update(key: &mut Self::Secret, msg: &[u8]) -> Self::Signature;
 }
+
+impl<A: KeyEvolvingSignatureAlgorithm> SecretKey<A> {
+    pub fn period(&self) -> usize {
+        A::get_period(&self.0)
+    }
+
+    pub fn update(&mut self) -> bool {
+        A::update(&mut self.0)
+    }
+}
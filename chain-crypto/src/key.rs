@@ -1,8 +1,11 @@
+#[cfg(feature = "std")]
 use crate::bech32::{self, Bech32};
 use crate::hex;
+#[cfg(feature = "std")]
+use alloc::string::String;
+use core::fmt;
+use core::hash::Hash;
 use rand::{CryptoRng, RngCore};
-use std::fmt;
-use std::hash::Hash;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum SecretKeyError {
@@ -51,13 +54,13 @@ impl<A: AsymmetricKey> KeyPair<A> {
         (self.0, self.1)
     }
 }
-impl<A: AsymmetricKey> std::fmt::Debug for KeyPair<A> {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl<A: AsymmetricKey> fmt::Debug for KeyPair<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "KeyPair(<secret key>, {:?})", self.public_key())
     }
 }
-impl<A: AsymmetricKey> std::fmt::Display for KeyPair<A> {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+impl<A: AsymmetricKey> fmt::Display for KeyPair<A> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "KeyPair(<secret key>, {})", self.public_key())
     }
 }
@@ -88,7 +91,9 @@ impl fmt::Display for PublicKeyError {
         }
     }
 }
+#[cfg(feature = "std")]
 impl std::error::Error for SecretKeyError {}
+#[cfg(feature = "std")]
 impl std::error::Error for PublicKeyError {}
 
 impl<A: AsymmetricKey> AsRef<[u8]> for PublicKey<A> {
@@ -138,22 +143,22 @@ impl<A: AsymmetricKey> Clone for KeyPair<A> {
     }
 }
 
-impl<A: AsymmetricKey> std::cmp::PartialEq<Self> for PublicKey<A> {
+impl<A: AsymmetricKey> core::cmp::PartialEq<Self> for PublicKey<A> {
     fn eq(&self, other: &Self) -> bool {
         self.0.as_ref().eq(other.0.as_ref())
     }
 }
 
-impl<A: AsymmetricKey> std::cmp::Eq for PublicKey<A> {}
+impl<A: AsymmetricKey> core::cmp::Eq for PublicKey<A> {}
 
-impl<A: AsymmetricKey> std::cmp::PartialOrd<Self> for PublicKey<A> {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+impl<A: AsymmetricKey> core::cmp::PartialOrd<Self> for PublicKey<A> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
         self.0.as_ref().partial_cmp(other.0.as_ref())
     }
 }
 
-impl<A: AsymmetricKey> std::cmp::Ord for PublicKey<A> {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+impl<A: AsymmetricKey> core::cmp::Ord for PublicKey<A> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
         self.0.as_ref().cmp(other.0.as_ref())
     }
 }
@@ -161,12 +166,13 @@ impl<A: AsymmetricKey> std::cmp::Ord for PublicKey<A> {
 impl<A: AsymmetricKey> Hash for PublicKey<A> {
     fn hash<H>(&self, state: &mut H)
     where
-        H: std::hash::Hasher,
+        H: core::hash::Hasher,
     {
         self.0.as_ref().hash(state)
     }
 }
 
+#[cfg(feature = "std")]
 impl<A: AsymmetricKey> Bech32 for PublicKey<A> {
     const BECH32_HRP: &'static str = A::PUBLIC_BECH32_HRP;
 
@@ -180,6 +186,7 @@ impl<A: AsymmetricKey> Bech32 for PublicKey<A> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<A: AsymmetricKey> Bech32 for SecretKey<A> {
     const BECH32_HRP: &'static str = A::SECRET_BECH32_HRP;
 
@@ -1,4 +1,7 @@
 #![cfg_attr(feature = "with-bench", feature(test))]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
 
 #[macro_use]
 extern crate cfg_if;
@@ -20,21 +23,26 @@ cfg_if! {
 }
 
 pub mod algorithms;
+#[cfg(feature = "std")]
 pub mod bech32;
 pub mod hash;
 mod hex;
 mod kes;
 mod key;
+pub mod musig;
 mod sign;
+mod signer;
 mod vrf;
 
+pub use ed25519_bip32::securemem;
 pub use kes::KeyEvolvingSignatureAlgorithm;
 pub use key::{AsymmetricKey, KeyPair, PublicKey, PublicKeyError, SecretKey, SecretKeyError};
 pub use sign::{Signature, SignatureError, SigningAlgorithm, Verification, VerificationAlgorithm};
+pub use signer::{AsymmetricSigner, SignerError};
 pub use vrf::{
-    vrf_evaluate_and_proove, vrf_verified_get_output, vrf_verify, VRFVerification,
-    VerifiableRandomFunction,
+    vrf_evaluate_and_proove, vrf_output_below_threshold, vrf_output_to_ratio,
+    vrf_verified_get_output, vrf_verify, VRFVerification, VerifiableRandomFunction,
 };
 
 pub use algorithms::*;
-pub use hash::{Blake2b224, Blake2b256, Sha3_256};
+pub use hash::{Blake2b224, Blake2b256, Blake2b256Hasher, Sha3_256, Sha3_256Hasher};
@@ -0,0 +1,450 @@
+//! MuSig: Schnorr multi-signatures of Ed25519 extended keys
+//!
+//! `n` signers aggregate their public keys into a single public key and
+//! jointly produce a single signature, which is a regular Ed25519
+//! signature of the message under the aggregated public key:
+//!
+//! 1. every signer computes the `AggregatePublicKey` of the same ordered
+//!    list of public keys;
+//! 2. every signer generates a nonce and sends the commitment of its
+//!    `PublicNonce`, then, once all the commitments are received, the
+//!    `PublicNonce` itself;
+//! 3. every signer checks the nonces against their commitments, computes
+//!    the `AggregateNonce` and sends its `PartialSignature`;
+//! 4. the partial signatures are summed into the final signature.
+//!
+//! With `X_i` the public keys and `L` the list of them, the aggregated key
+//! is `X = sum(a_i * X_i)` with `a_i = H(L | X_i)`, which prevents a signer
+//! from choosing its key so as to cancel the others.
+
+use crate::algorithms::Ed25519Extended;
+use crate::hash::Blake2b256;
+use crate::key::{PublicKey, SecretKey};
+use crate::sign::{Signature, Verification};
+use alloc::vec::Vec;
+use core::fmt;
+use curve25519_dalek::constants::ED25519_BASEPOINT_POINT;
+use curve25519_dalek::edwards::{CompressedEdwardsY, EdwardsPoint};
+use curve25519_dalek::scalar::Scalar;
+use curve25519_dalek::traits::Identity;
+use rand::{CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+const COEFFICIENT_TAG: &[u8] = b"musig coefficient";
+
+pub const NONCE_SIZE: usize = 32;
+pub const PARTIAL_SIGNATURE_SIZE: usize = 32;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// no public key to aggregate
+    NoPublicKeys,
+    /// the public key at this index is not a valid point
+    InvalidPublicKey(usize),
+    /// there is no public key at this index
+    IndexOutOfBound(usize),
+    /// the secret key does not match the public key at this index
+    SecretKeyMismatch(usize),
+    /// the nonce at this index is not a valid point
+    InvalidNonce(usize),
+    /// the number of nonces and the number of aggregated public keys
+    NoncesMismatch(usize, usize),
+    /// the number of partial signatures and the number of aggregated
+    /// public keys
+    SignaturesMismatch(usize, usize),
+    /// the data is not of the expected size
+    SizeInvalid,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::NoPublicKeys => write!(f, "No public key to aggregate"),
+            Error::InvalidPublicKey(index) => write!(f, "Invalid public key {}", index),
+            Error::IndexOutOfBound(index) => write!(f, "No public key at index {}", index),
+            Error::SecretKeyMismatch(index) => {
+                write!(f, "Secret key does not match the public key {}", index)
+            }
+            Error::InvalidNonce(index) => write!(f, "Invalid nonce {}", index),
+            Error::NoncesMismatch(expected, got) => {
+                write!(f, "Expected {} nonces, got {}", expected, got)
+            }
+            Error::SignaturesMismatch(expected, got) => {
+                write!(f, "Expected {} partial signatures, got {}", expected, got)
+            }
+            Error::SizeInvalid => write!(f, "Invalid size"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+fn decompress(bytes: &[u8]) -> Option<EdwardsPoint> {
+    if bytes.len() != 32 {
+        return None;
+    }
+    CompressedEdwardsY::from_slice(bytes).decompress()
+}
+
+/// the public keys of the signers, in the order they are aggregated, and
+/// their aggregation
+#[derive(Clone)]
+pub struct AggregatePublicKey {
+    keys: Vec<PublicKey<Ed25519Extended>>,
+    coefficients: Vec<Scalar>,
+    public_key: PublicKey<Ed25519Extended>,
+}
+
+impl AggregatePublicKey {
+    pub fn new(keys: &[PublicKey<Ed25519Extended>]) -> Result<Self, Error> {
+        if keys.is_empty() {
+            return Err(Error::NoPublicKeys);
+        }
+        let mut list = Sha512::new();
+        list.input(COEFFICIENT_TAG);
+        for key in keys {
+            list.input(key.as_ref());
+        }
+
+        let mut coefficients = Vec::with_capacity(keys.len());
+        let mut aggregated = EdwardsPoint::identity();
+        for (index, key) in keys.iter().enumerate() {
+            let point = decompress(key.as_ref()).ok_or(Error::InvalidPublicKey(index))?;
+            let mut h = list.clone();
+            h.input(key.as_ref());
+            let coefficient = Scalar::from_hash(h);
+            aggregated += coefficient * point;
+            coefficients.push(coefficient);
+        }
+        let public_key = PublicKey::from_binary(aggregated.compress().as_bytes())
+            .map_err(|_| Error::SizeInvalid)?;
+        Ok(AggregatePublicKey {
+            keys: keys.to_vec(),
+            coefficients,
+            public_key,
+        })
+    }
+
+    /// the public key verifying the aggregated signatures
+    pub fn public_key(&self) -> &PublicKey<Ed25519Extended> {
+        &self.public_key
+    }
+
+    pub fn keys(&self) -> &[PublicKey<Ed25519Extended>] {
+        &self.keys
+    }
+
+    fn challenge(&self, nonce: &AggregateNonce, message: &[u8]) -> Scalar {
+        let mut h = Sha512::new();
+        h.input(nonce.0.compress().as_bytes());
+        h.input(self.public_key.as_ref());
+        h.input(message);
+        Scalar::from_hash(h)
+    }
+}
+
+/// the secret part of a signer's nonce, consumed by the signing so it
+/// cannot be used twice
+pub struct SecretNonce(Scalar);
+
+/// the public part of a signer's nonce, `R_i = r_i * B`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicNonce([u8; NONCE_SIZE]);
+
+/// the commitment to a `PublicNonce`, exchanged before the nonces
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonceCommitment(Blake2b256);
+
+impl SecretNonce {
+    pub fn generate<R: RngCore + CryptoRng>(mut rng: R) -> (SecretNonce, PublicNonce) {
+        let mut bytes = [0u8; 64];
+        rng.fill_bytes(&mut bytes);
+        let r = Scalar::from_bytes_mod_order_wide(&bytes);
+        let public = (r * ED25519_BASEPOINT_POINT).compress();
+        (SecretNonce(r), PublicNonce(public.to_bytes()))
+    }
+}
+
+impl PublicNonce {
+    pub fn from_binary(data: &[u8]) -> Result<Self, Error> {
+        if data.len() != NONCE_SIZE {
+            return Err(Error::SizeInvalid);
+        }
+        let mut buf = [0; NONCE_SIZE];
+        buf.copy_from_slice(data);
+        Ok(PublicNonce(buf))
+    }
+
+    pub fn commitment(&self) -> NonceCommitment {
+        NonceCommitment(Blake2b256::new(&self.0))
+    }
+}
+
+impl AsRef<[u8]> for PublicNonce {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+impl NonceCommitment {
+    /// check the nonce is the one committed to
+    pub fn verify(&self, nonce: &PublicNonce) -> bool {
+        self.0 == Blake2b256::new(&nonce.0)
+    }
+}
+
+impl From<Blake2b256> for NonceCommitment {
+    fn from(hash: Blake2b256) -> Self {
+        NonceCommitment(hash)
+    }
+}
+
+impl AsRef<[u8]> for NonceCommitment {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_ref()
+    }
+}
+
+/// the sum of the public nonces of all the signers, `R = sum(R_i)`
+#[derive(Clone)]
+pub struct AggregateNonce(EdwardsPoint);
+
+impl AggregateNonce {
+    /// aggregate the nonces, in the order of the aggregated public keys
+    pub fn new(key: &AggregatePublicKey, nonces: &[PublicNonce]) -> Result<Self, Error> {
+        if nonces.len() != key.keys.len() {
+            return Err(Error::NoncesMismatch(key.keys.len(), nonces.len()));
+        }
+        let mut aggregated = EdwardsPoint::identity();
+        for (index, nonce) in nonces.iter().enumerate() {
+            aggregated += decompress(&nonce.0).ok_or(Error::InvalidNonce(index))?;
+        }
+        Ok(AggregateNonce(aggregated))
+    }
+}
+
+/// the signature share of a signer, `s_i = r_i + c * a_i * x_i`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialSignature(Scalar);
+
+impl PartialSignature {
+    /// sign the message as the signer at the given index of the
+    /// aggregated public keys
+    pub fn generate<T: AsRef<[u8]>>(
+        key: &AggregatePublicKey,
+        index: usize,
+        secret_key: &SecretKey<Ed25519Extended>,
+        nonce: SecretNonce,
+        aggregate_nonce: &AggregateNonce,
+        message: &T,
+    ) -> Result<Self, Error> {
+        let public_key = key.keys.get(index).ok_or(Error::IndexOutOfBound(index))?;
+        if &secret_key.to_public() != public_key {
+            return Err(Error::SecretKeyMismatch(index));
+        }
+        let mut x = [0; 32];
+        x.copy_from_slice(&secret_key.0.as_ref()[0..32]);
+        let x = Scalar::from_bytes_mod_order(x);
+        let c = key.challenge(aggregate_nonce, message.as_ref());
+        Ok(PartialSignature(nonce.0 + c * key.coefficients[index] * x))
+    }
+
+    /// verify the partial signature of the signer at the given index,
+    /// `s_i * B = R_i + c * a_i * X_i`, to find out which signer
+    /// misbehaved when the aggregated signature is invalid
+    pub fn verify<T: AsRef<[u8]>>(
+        &self,
+        key: &AggregatePublicKey,
+        index: usize,
+        nonce: &PublicNonce,
+        aggregate_nonce: &AggregateNonce,
+        message: &T,
+    ) -> Verification {
+        let public_key = match key.keys.get(index).and_then(|k| decompress(k.as_ref())) {
+            Some(public_key) => public_key,
+            None => return Verification::Failed,
+        };
+        let r = match decompress(&nonce.0) {
+            Some(r) => r,
+            None => return Verification::Failed,
+        };
+        let c = key.challenge(aggregate_nonce, message.as_ref());
+        (self.0 * ED25519_BASEPOINT_POINT == r + c * key.coefficients[index] * public_key).into()
+    }
+
+    pub fn from_binary(data: &[u8]) -> Result<Self, Error> {
+        if data.len() != PARTIAL_SIGNATURE_SIZE {
+            return Err(Error::SizeInvalid);
+        }
+        let mut buf = [0; PARTIAL_SIGNATURE_SIZE];
+        buf.copy_from_slice(data);
+        Scalar::from_canonical_bytes(buf)
+            .map(PartialSignature)
+            .ok_or(Error::SizeInvalid)
+    }
+}
+
+impl AsRef<[u8]> for PartialSignature {
+    fn as_ref(&self) -> &[u8] {
+        self.0.as_bytes()
+    }
+}
+
+/// sum the partial signatures, in the order of the aggregated public keys,
+/// into the signature of the message under the aggregated public key
+pub fn aggregate_signatures<T>(
+    key: &AggregatePublicKey,
+    aggregate_nonce: &AggregateNonce,
+    signatures: &[PartialSignature],
+) -> Result<Signature<T, Ed25519Extended>, Error> {
+    if signatures.len() != key.keys.len() {
+        return Err(Error::SignaturesMismatch(key.keys.len(), signatures.len()));
+    }
+    let s = signatures
+        .iter()
+        .fold(Scalar::zero(), |sum, signature| sum + signature.0);
+    let mut bytes = [0; 64];
+    bytes[0..32].copy_from_slice(aggregate_nonce.0.compress().as_bytes());
+    bytes[32..64].copy_from_slice(s.as_bytes());
+    Signature::from_binary(&bytes).map_err(|_| Error::SizeInvalid)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::testing::arbitrary_secret_key;
+    use quickcheck::{Arbitrary, Gen, TestResult};
+
+    #[derive(Clone)]
+    struct Signers(Vec<SecretKey<Ed25519Extended>>);
+
+    impl fmt::Debug for Signers {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "Signers({})", self.0.len())
+        }
+    }
+
+    impl Arbitrary for Signers {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            let n = 1 + usize::arbitrary(g) % 8;
+            Signers((0..n).map(|_| arbitrary_secret_key(g)).collect())
+        }
+    }
+
+    fn sign(
+        signers: &[SecretKey<Ed25519Extended>],
+        message: &[u8],
+    ) -> (
+        AggregatePublicKey,
+        Vec<PublicNonce>,
+        AggregateNonce,
+        Vec<PartialSignature>,
+    ) {
+        let keys: Vec<_> = signers.iter().map(|k| k.to_public()).collect();
+        let key = AggregatePublicKey::new(&keys).unwrap();
+        let (secrets, nonces): (Vec<_>, Vec<_>) = signers
+            .iter()
+            .map(|_| SecretNonce::generate(rand::thread_rng()))
+            .unzip();
+        let aggregate_nonce = AggregateNonce::new(&key, &nonces).unwrap();
+        let partials = signers
+            .iter()
+            .zip(secrets.into_iter())
+            .enumerate()
+            .map(|(index, (secret_key, nonce))| {
+                PartialSignature::generate(
+                    &key,
+                    index,
+                    secret_key,
+                    nonce,
+                    &aggregate_nonce,
+                    &message,
+                )
+                .unwrap()
+            })
+            .collect();
+        (key, nonces, aggregate_nonce, partials)
+    }
+
+    quickcheck! {
+        fn aggregated_signature_verifies(signers: Signers, message: Vec<u8>) -> TestResult {
+            let (key, nonces, aggregate_nonce, partials) = sign(&signers.0, &message);
+            for (index, partial) in partials.iter().enumerate() {
+                if partial.verify(&key, index, &nonces[index], &aggregate_nonce, &message)
+                    != Verification::Success
+                {
+                    return TestResult::failed();
+                }
+            }
+            let signature: Signature<Vec<u8>, Ed25519Extended> =
+                aggregate_signatures(&key, &aggregate_nonce, &partials).unwrap();
+            TestResult::from_bool(
+                signature.verify(key.public_key(), &message) == Verification::Success,
+            )
+        }
+    }
+
+    #[test]
+    fn invalid_aggregations() {
+        let mut g = quickcheck::StdGen::new(rand::thread_rng(), 10);
+        let signers = Signers::arbitrary(&mut g).0;
+        let message = b"message".to_vec();
+        let (key, nonces, aggregate_nonce, mut partials) = sign(&signers, &message);
+
+        // the signature is not valid for another message or another
+        // ordering of the keys
+        let signature: Signature<Vec<u8>, Ed25519Extended> =
+            aggregate_signatures(&key, &aggregate_nonce, &partials).unwrap();
+        assert_eq!(
+            signature.verify(key.public_key(), &b"other".to_vec()),
+            Verification::Failed
+        );
+        let mut keys = key.keys().to_vec();
+        keys.push(arbitrary_secret_key(&mut g).to_public());
+        let other = AggregatePublicKey::new(&keys).unwrap();
+        assert_eq!(
+            signature.verify(other.public_key(), &message),
+            Verification::Failed
+        );
+
+        // a missing or wrong partial signature is detected
+        assert_eq!(
+            aggregate_signatures::<Vec<u8>>(&key, &aggregate_nonce, &partials[1..]).err(),
+            Some(Error::SignaturesMismatch(
+                partials.len(),
+                partials.len() - 1
+            ))
+        );
+        partials[0] = PartialSignature(partials[0].0 + Scalar::one());
+        assert_eq!(
+            partials[0].verify(&key, 0, &nonces[0], &aggregate_nonce, &message),
+            Verification::Failed
+        );
+        let signature: Signature<Vec<u8>, Ed25519Extended> =
+            aggregate_signatures(&key, &aggregate_nonce, &partials).unwrap();
+        assert_eq!(
+            signature.verify(key.public_key(), &message),
+            Verification::Failed
+        );
+
+        // a signer cannot sign for another one
+        let (secret, _) = SecretNonce::generate(rand::thread_rng());
+        let intruder = arbitrary_secret_key(&mut g);
+        assert_eq!(
+            PartialSignature::generate(&key, 0, &intruder, secret, &aggregate_nonce, &message)
+                .err(),
+            Some(Error::SecretKeyMismatch(0))
+        );
+    }
+
+    #[test]
+    fn nonce_commitment() {
+        let (_, nonce) = SecretNonce::generate(rand::thread_rng());
+        let (_, other) = SecretNonce::generate(rand::thread_rng());
+        let commitment = nonce.commitment();
+        assert!(commitment.verify(&nonce));
+        assert!(!commitment.verify(&other));
+        assert_eq!(PublicNonce::from_binary(nonce.as_ref()), Ok(nonce));
+    }
+}
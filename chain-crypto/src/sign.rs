@@ -1,7 +1,12 @@
+#[cfg(feature = "std")]
 use crate::bech32::{self, Bech32};
+use crate::signer::{AsymmetricSigner, SignerError};
 use crate::{hex, kes, key};
-use std::fmt;
-use std::marker::PhantomData;
+#[cfg(feature = "std")]
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::marker::PhantomData;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Verification {
@@ -19,6 +24,28 @@ impl From<bool> for Verification {
     }
 }
 
+impl Verification {
+    /// verify the signatures of the given objects at once, which is faster
+    /// than verifying them one by one for the algorithms supporting it
+    /// (e.g. Ed25519)
+    ///
+    /// Succeeds only if every signature is valid, it does not tell which
+    /// signature is invalid.
+    pub fn batch_verify<A, T>(batch: &[(key::PublicKey<A>, T, Signature<T, A>)]) -> Self
+    where
+        A: VerificationAlgorithm,
+        T: AsRef<[u8]>,
+    {
+        let batch: Vec<_> = batch
+            .iter()
+            .map(|(publickey, object, signature)| {
+                (&publickey.0, &signature.signdata, object.as_ref())
+            })
+            .collect();
+        A::verify_batch(&batch)
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum SignatureError {
     SizeInvalid,
@@ -34,6 +61,17 @@ pub trait VerificationAlgorithm: key::AsymmetricKey {
     fn verify_bytes(pubkey: &Self::Public, signature: &Self::Signature, msg: &[u8])
         -> Verification;
 
+    /// verify all the signatures of the batch, succeeding only if every
+    /// signature is valid. By default they are verified one by one.
+    fn verify_batch(batch: &[(&Self::Public, &Self::Signature, &[u8])]) -> Verification {
+        batch
+            .iter()
+            .all(|(pubkey, signature, msg)| {
+                Self::verify_bytes(pubkey, signature, msg) == Verification::Success
+            })
+            .into()
+    }
+
     fn signature_from_bytes(data: &[u8]) -> Result<Self::Signature, SignatureError>;
 }
 
@@ -65,6 +103,7 @@ impl fmt::Display for SignatureError {
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for SignatureError {}
 
 impl<A: VerificationAlgorithm, T> Signature<T, A> {
@@ -97,11 +136,25 @@ impl<A: SigningAlgorithm, T: AsRef<[u8]>> Signature<T, A> {
     }
 }
 
+impl<A: VerificationAlgorithm, T: AsRef<[u8]>> Signature<T, A> {
+    /// sign the object with the given signer, which may not hold the
+    /// secret key in memory
+    pub fn generate_with<S>(signer: &S, object: &T) -> Result<Signature<T, A>, SignerError>
+    where
+        S: AsymmetricSigner<A> + ?Sized,
+    {
+        Ok(Signature {
+            signdata: signer.sign_bytes(object.as_ref())?,
+            phantom: PhantomData,
+        })
+    }
+}
+
 impl<A: kes::KeyEvolvingSignatureAlgorithm, T> Signature<T, A> {
     pub fn generate_update(key: &mut key::SecretKey<A>, msg: &[u8]) -> Self {
         Signature {
             signdata: A::sign_update(&mut key.0, msg),
-            phantom: std::marker::PhantomData,
+            phantom: PhantomData,
         }
     }
 }
@@ -110,7 +163,7 @@ impl<T, A: VerificationAlgorithm> Clone for Signature<T, A> {
     fn clone(&self) -> Self {
         Signature {
             signdata: self.signdata.clone(),
-            phantom: std::marker::PhantomData,
+            phantom: PhantomData,
         }
     }
 }
@@ -121,6 +174,7 @@ impl<T, A: VerificationAlgorithm> AsRef<[u8]> for Signature<T, A> {
     }
 }
 
+#[cfg(feature = "std")]
 impl<T, A: VerificationAlgorithm> Bech32 for Signature<T, A> {
     const BECH32_HRP: &'static str = A::SIGNATURE_BECH32_HRP;
 
@@ -163,4 +217,43 @@ pub(crate) mod test {
         let signature = Signature::generate(&sk, &data);
         signature.verify(&pk_random, &data) == Verification::Failed
     }
+
+    pub(crate) fn signer_signing_ok<A: AsymmetricKey + SigningAlgorithm>(
+        input: (KeyPair<A>, Vec<u8>),
+    ) -> bool {
+        let (sk, pk) = input.0.into_keys();
+        let data = input.1;
+
+        if AsymmetricSigner::public_key(&sk) != Ok(pk.clone()) {
+            return false;
+        }
+        let signature = Signature::generate_with(&sk, &data).unwrap();
+        signature.as_ref() == Signature::generate(&sk, &data).as_ref()
+            && signature.verify(&pk, &data) == Verification::Success
+    }
+
+    pub(crate) fn batch_signing_ok<A: AsymmetricKey + SigningAlgorithm>(
+        input: (Vec<(KeyPair<A>, Vec<u8>)>, usize),
+    ) -> bool {
+        let mut batch: Vec<_> = input
+            .0
+            .into_iter()
+            .map(|(keypair, data)| {
+                let (sk, pk) = keypair.into_keys();
+                let signature = Signature::generate(&sk, &data);
+                (pk, data, signature)
+            })
+            .collect();
+        if Verification::batch_verify(&batch) == Verification::Failed {
+            return false;
+        }
+        if batch.is_empty() {
+            return true;
+        }
+
+        // one altered message fails the whole batch
+        let index = input.1 % batch.len();
+        batch[index].1.push(0);
+        Verification::batch_verify(&batch) == Verification::Failed
+    }
 }
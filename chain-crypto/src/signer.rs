@@ -0,0 +1,66 @@
+use crate::key::{PublicKey, SecretKey};
+use crate::sign::{SignatureError, SigningAlgorithm, VerificationAlgorithm};
+use alloc::string::String;
+use core::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignerError {
+    /// the signing was refused, e.g. by the user on the device
+    Rejected,
+    /// the device or the process holding the key could not be reached
+    Unavailable(String),
+    /// the signature returned by the device is not a valid signature
+    SignatureInvalid(SignatureError),
+}
+
+/// Something able to sign with the secret key of the algorithm `A`.
+///
+/// The secret key does not need to be in memory: the signing can be
+/// delegated to a hardware wallet (Ledger, Trezor) or to an HSM daemon,
+/// only the public key and the signatures leave the device.
+pub trait AsymmetricSigner<A: VerificationAlgorithm> {
+    /// the public key associated to the secret key used for signing
+    fn public_key(&self) -> Result<PublicKey<A>, SignerError>;
+
+    /// sign the given message with the secret key
+    fn sign_bytes(&self, msg: &[u8]) -> Result<A::Signature, SignerError>;
+}
+
+/// the in memory secret key signs directly, without failing
+impl<A: SigningAlgorithm> AsymmetricSigner<A> for SecretKey<A> {
+    fn public_key(&self) -> Result<PublicKey<A>, SignerError> {
+        Ok(self.to_public())
+    }
+
+    fn sign_bytes(&self, msg: &[u8]) -> Result<A::Signature, SignerError> {
+        Ok(A::sign(&self.0, msg))
+    }
+}
+
+impl From<SignatureError> for SignerError {
+    fn from(e: SignatureError) -> Self {
+        SignerError::SignatureInvalid(e)
+    }
+}
+
+impl fmt::Display for SignerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SignerError::Rejected => write!(f, "Signing rejected"),
+            SignerError::Unavailable(reason) => write!(f, "Signer unavailable: {}", reason),
+            SignerError::SignatureInvalid(e) => {
+                write!(f, "Signer returned an invalid signature: {}", e)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SignerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SignerError::SignatureInvalid(e) => Some(e),
+            _ => None,
+        }
+    }
+}
@@ -52,3 +52,59 @@ pub fn vrf_verified_get_output<VRF: VerifiableRandomFunction>(
 ) -> <VRF as VerifiableRandomFunction>::RandomOutput {
     VRF::strip_verification_output(vr)
 }
+
+/// Interpret a VRF output (e.g. the hash of a `RandomOutput` for a given
+/// input) as a number in the range [0, 1), by reading its first 8 bytes
+/// as a big endian fraction
+pub fn vrf_output_to_ratio(output: &[u8]) -> f64 {
+    assert!(output.len() >= 8);
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&output[0..8]);
+    (u64::from_be_bytes(bytes) as f64) / 18446744073709551616.0
+}
+
+/// Check a VRF output is below the given threshold: for a uniformly
+/// distributed output, this happens with a probability of `threshold`
+pub fn vrf_output_below_threshold(output: &[u8], threshold: f64) -> bool {
+    vrf_output_to_ratio(output) < threshold
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algorithms::Curve25519_2HashDH;
+    use rand::rngs::OsRng;
+
+    #[test]
+    fn ratio_bounds() {
+        assert_eq!(vrf_output_to_ratio(&[0; 32]), 0.0);
+        assert_eq!(vrf_output_to_ratio(&[0x80, 0, 0, 0, 0, 0, 0, 0]), 0.5);
+        assert!(vrf_output_to_ratio(&[0xff; 32]) < 1.0);
+
+        assert!(!vrf_output_below_threshold(&[0; 32], 0.0));
+        assert!(vrf_output_below_threshold(&[0xff; 32], 1.0));
+        assert!(vrf_output_below_threshold(&[0x7f; 8], 0.5));
+        assert!(!vrf_output_below_threshold(&[0x80; 8], 0.5));
+    }
+
+    #[test]
+    fn evaluate_verify_threshold() {
+        let mut csprng = OsRng::new().unwrap();
+        let sk = key::SecretKey::<Curve25519_2HashDH>::generate(&mut csprng);
+        let pk = sk.to_public();
+
+        let mut below = 0;
+        for i in 0u32..200 {
+            let input = &i.to_be_bytes()[..];
+            let vr = vrf_evaluate_and_proove(&sk, input, &mut csprng);
+            assert_eq!(vrf_verify(&pk, input, &vr), VRFVerification::Success);
+            let output =
+                vrf_verified_get_output::<Curve25519_2HashDH>(&vr).to_output(input, b"test");
+            if vrf_output_below_threshold(output.as_ref(), 0.25) {
+                below += 1;
+            }
+        }
+        // 50 expected, the bounds are more than 5 standard deviations away
+        assert!(below > 15 && below < 85, "{} outputs below 0.25", below);
+    }
+}
@@ -59,10 +59,10 @@ impl From<Identifier> for PublicKey<AccountAlg> {
 /// Account Secret Key
 pub type Secret = key::AccountSecretKey;
 
-#[derive(Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct State {
-    counter: SpendingCounter,
-    value: Value,
+    pub(crate) counter: SpendingCounter,
+    pub(crate) value: Value,
 }
 
 impl State {
@@ -110,6 +110,12 @@ impl State {
     pub fn get_value(&self) -> Value {
         self.value
     }
+
+    /// the counter the witness of the next spending from the account
+    /// has to sign
+    pub fn get_counter(&self) -> SpendingCounter {
+        self.counter
+    }
 }
 
 /// Spending counter associated to an account.
@@ -119,7 +125,7 @@ impl State {
 /// needs to be used in the spending phase to make
 /// sure we have non-replayability of a transaction.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct SpendingCounter(u32);
+pub struct SpendingCounter(pub(crate) u32);
 
 impl SpendingCounter {
     pub fn zero() -> Self {
@@ -151,7 +157,7 @@ pub struct SpendingWitness {
 
 /// The public ledger of all accounts associated with their current state
 #[derive(Clone)]
-pub struct Ledger(Hamt<DefaultHasher, Identifier, State>);
+pub struct Ledger(pub(crate) Hamt<DefaultHasher, Identifier, State>);
 
 impl Ledger {
     /// Create a new empty account ledger
@@ -218,6 +224,16 @@ impl Ledger {
             .map_err(|e| e.into())
     }
 
+    /// Get the state of the account
+    pub fn get_state(&self, account: &Identifier) -> Result<&State, LedgerError> {
+        self.0.lookup(account).ok_or(LedgerError::NonExistent)
+    }
+
+    /// Iterate over the accounts and their state
+    pub fn iter(&self) -> impl Iterator<Item = (&Identifier, &State)> {
+        self.0.iter()
+    }
+
     pub fn get_total_value(&self) -> Result<Value, ValueError> {
         let values = self.0.iter().map(|(_, state)| state.get_value());
         Value::sum(values)
@@ -0,0 +1,302 @@
+//! genesis block (block0) builder
+//!
+//! The block0 is made of the initial message, carrying the configuration
+//! parameters of the blockchain, followed by the transactions distributing
+//! the initial funds and by the initial certificates. The builder collects
+//! them, checks they are consistent with each other and with the ledger,
+//! and makes the block.
+
+use crate::block::{Block, BlockBuilder, ConsensusVersion};
+use crate::certificate::{Certificate, CertificateContent};
+use crate::config::ConfigParam;
+use crate::ledger::{self, Ledger};
+use crate::message::{InitialEnts, Message};
+use crate::transaction::{AuthenticatedTransaction, NoExtra, Output, Transaction};
+use crate::value::Value;
+use chain_addr::Address;
+use chain_core::property::Serialize;
+use std::fmt;
+
+/// maximum number of outputs of an initial transaction, the index of an
+/// output being a `u8`
+const MAX_FUNDS_PER_TRANSACTION: usize = 255;
+
+/// the block0 is not consistent
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block0Error {
+    /// there is no `ConsensusVersion` parameter
+    NoConsensusVersion,
+    /// there are several `ConsensusVersion` parameters with different values
+    ConflictingConsensusVersions(ConsensusVersion, ConsensusVersion),
+    /// the consensus is BFT but there is no BFT leader
+    NoBftLeader,
+    /// the consensus is Genesis Praos but there is no stake pool registered
+    NoStakePool,
+    /// the ledger cannot be initialized with the block0
+    Ledger(ledger::Error),
+}
+
+impl fmt::Display for Block0Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Block0Error::NoConsensusVersion => write!(f, "the consensus version is not set"),
+            Block0Error::ConflictingConsensusVersions(first, second) => write!(
+                f,
+                "the consensus version is set to both {} and {}",
+                first, second
+            ),
+            Block0Error::NoBftLeader => write!(f, "the BFT consensus requires a BFT leader"),
+            Block0Error::NoStakePool => write!(
+                f,
+                "the Genesis Praos consensus requires a stake pool registration"
+            ),
+            Block0Error::Ledger(error) => write!(f, "invalid block0 contents: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Block0Error {}
+
+impl From<ledger::Error> for Block0Error {
+    fn from(error: ledger::Error) -> Self {
+        Block0Error::Ledger(error)
+    }
+}
+
+/// builder of the genesis block
+///
+/// ```ignore
+/// let block0 = Block0Builder::new()
+///     .config_param(ConfigParam::Discrimination(Discrimination::Test))
+///     .config_param(ConfigParam::ConsensusVersion(ConsensusVersion::Bft))
+///     .config_param(ConfigParam::AddBftLeader(leader_id))
+///     .initial_fund(address, Value(1000))
+///     .build()?;
+/// ```
+#[derive(Debug, Clone)]
+pub struct Block0Builder {
+    params: Vec<ConfigParam>,
+    funds: Vec<Output<Address>>,
+    certificates: Vec<Certificate>,
+}
+
+impl Block0Builder {
+    pub fn new() -> Self {
+        Block0Builder {
+            params: Vec::new(),
+            funds: Vec::new(),
+            certificates: Vec::new(),
+        }
+    }
+
+    /// add a configuration parameter to the initial message
+    pub fn config_param(&mut self, param: ConfigParam) -> &mut Self {
+        self.params.push(param);
+        self
+    }
+
+    /// add multiple configuration parameters to the initial message
+    pub fn config_params<I>(&mut self, params: I) -> &mut Self
+    where
+        I: IntoIterator<Item = ConfigParam>,
+    {
+        self.params.extend(params);
+        self
+    }
+
+    /// give the initial value to the address: a UTxO, or the initial
+    /// balance of the account for an account address
+    pub fn initial_fund(&mut self, address: Address, value: Value) -> &mut Self {
+        self.funds.push(Output { address, value });
+        self
+    }
+
+    /// give multiple initial funds
+    pub fn initial_funds<I>(&mut self, funds: I) -> &mut Self
+    where
+        I: IntoIterator<Item = Output<Address>>,
+    {
+        self.funds.extend(funds);
+        self
+    }
+
+    /// add an initial certificate, e.g. the registration of a stake pool
+    pub fn certificate(&mut self, certificate: Certificate) -> &mut Self {
+        self.certificates.push(certificate);
+        self
+    }
+
+    fn check_consensus(&self) -> Result<(), Block0Error> {
+        let mut consensus = None;
+        for param in self.params.iter() {
+            if let ConfigParam::ConsensusVersion(version) = param {
+                match consensus {
+                    Some(previous) if previous != *version => {
+                        return Err(Block0Error::ConflictingConsensusVersions(
+                            previous, *version,
+                        ));
+                    }
+                    _ => consensus = Some(*version),
+                }
+            }
+        }
+        match consensus.ok_or(Block0Error::NoConsensusVersion)? {
+            ConsensusVersion::Bft => {
+                let has_leader = self.params.iter().any(|param| match param {
+                    ConfigParam::AddBftLeader(_) => true,
+                    _ => false,
+                });
+                if !has_leader {
+                    return Err(Block0Error::NoBftLeader);
+                }
+            }
+            ConsensusVersion::GenesisPraos => {
+                let has_stake_pool = self.certificates.iter().any(|cert| match cert.content {
+                    CertificateContent::StakePoolRegistration(_) => true,
+                    _ => false,
+                });
+                if !has_stake_pool {
+                    return Err(Block0Error::NoStakePool);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// the messages of the block0, in order: the initial message, the
+    /// transactions of the initial funds and the certificates
+    pub fn messages(&self) -> Result<Vec<Message>, Block0Error> {
+        self.check_consensus()?;
+
+        let mut initial = InitialEnts::new();
+        for param in self.params.iter() {
+            initial.push(param.clone());
+        }
+        let mut messages = vec![Message::Initial(initial)];
+        messages.extend(self.funds.chunks(MAX_FUNDS_PER_TRANSACTION).map(|outputs| {
+            Message::Transaction(AuthenticatedTransaction {
+                transaction: Transaction {
+                    inputs: vec![],
+                    outputs: outputs.to_vec(),
+                    extra: NoExtra,
+                    metadata: None,
+                },
+                witnesses: vec![],
+            })
+        }));
+        messages.extend(self.certificates.iter().map(|certificate| {
+            Message::Certificate(AuthenticatedTransaction {
+                transaction: Transaction {
+                    inputs: vec![],
+                    outputs: vec![],
+                    extra: certificate.clone(),
+                    metadata: None,
+                },
+                witnesses: vec![],
+            })
+        }));
+        Ok(messages)
+    }
+
+    /// make the genesis block, checking the ledger can be initialized
+    /// with it
+    pub fn build(&self) -> Result<Block, Block0Error> {
+        let mut builder = BlockBuilder::new();
+        builder.messages(self.messages()?);
+        let block = builder.make_genesis_block();
+        Ledger::new(block.header.hash(), block.contents.iter())?;
+        Ok(block)
+    }
+
+    /// make the serialized genesis block
+    pub fn build_bytes(&self) -> Result<Vec<u8>, Block0Error> {
+        let block = self.build()?;
+        Ok(block
+            .serialize_as_vec()
+            .expect("in memory serialization of the block0"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::SpendingSecretKey;
+    use crate::leadership::bft::LeaderId;
+    use chain_addr::{Discrimination, Kind};
+    use chain_core::property::Deserialize;
+    use quickcheck::{Arbitrary, StdGen};
+
+    fn key() -> SpendingSecretKey {
+        let mut g = StdGen::new(rand::thread_rng(), 10);
+        SpendingSecretKey::arbitrary(&mut g)
+    }
+
+    fn bft_builder() -> Block0Builder {
+        let mut builder = Block0Builder::new();
+        builder
+            .config_param(ConfigParam::Discrimination(Discrimination::Test))
+            .config_param(ConfigParam::ConsensusVersion(ConsensusVersion::Bft))
+            .config_param(ConfigParam::AddBftLeader(LeaderId::from(key().to_public())));
+        builder
+    }
+
+    #[test]
+    fn build_bft_block0() {
+        let address = Address(Discrimination::Test, Kind::Single(key().to_public()));
+        let mut builder = bft_builder();
+        builder.initial_funds((0..300).map(|i| Output {
+            address: address.clone(),
+            value: Value(i + 1),
+        }));
+        let bytes = builder.build_bytes().unwrap();
+        let block = Block::deserialize(bytes.as_slice()).unwrap();
+        // the initial message and the funds split in 2 transactions
+        assert_eq!(block.contents.iter().count(), 3);
+        let ledger = Ledger::new(block.header.hash(), block.contents.iter()).unwrap();
+        assert_eq!(ledger.utxos().count(), 300);
+    }
+
+    #[test]
+    fn consensus_is_required() {
+        let mut builder = Block0Builder::new();
+        builder.config_param(ConfigParam::Discrimination(Discrimination::Test));
+        assert_eq!(
+            builder.build().unwrap_err(),
+            Block0Error::NoConsensusVersion
+        );
+
+        builder.config_param(ConfigParam::ConsensusVersion(ConsensusVersion::Bft));
+        assert_eq!(builder.build().unwrap_err(), Block0Error::NoBftLeader);
+
+        builder.config_param(ConfigParam::ConsensusVersion(
+            ConsensusVersion::GenesisPraos,
+        ));
+        assert_eq!(
+            builder.build().unwrap_err(),
+            Block0Error::ConflictingConsensusVersions(
+                ConsensusVersion::Bft,
+                ConsensusVersion::GenesisPraos
+            )
+        );
+    }
+
+    #[test]
+    fn genesis_praos_requires_a_stake_pool() {
+        let mut builder = Block0Builder::new();
+        builder.config_param(ConfigParam::ConsensusVersion(
+            ConsensusVersion::GenesisPraos,
+        ));
+        assert_eq!(builder.build().unwrap_err(), Block0Error::NoStakePool);
+    }
+
+    #[test]
+    fn funds_are_checked_by_the_ledger() {
+        let address = Address(Discrimination::Production, Kind::Single(key().to_public()));
+        let mut builder = bft_builder();
+        builder.initial_fund(address, Value(42));
+        assert_eq!(
+            builder.build().unwrap_err(),
+            Block0Error::Ledger(ledger::Error::InvalidDiscrimination)
+        );
+    }
+}
@@ -2,8 +2,8 @@
 //!
 
 use crate::block::{
-    BftProof, Block, BlockContentHash, BlockContents, BlockDate, BlockId, BlockVersion,
-    ChainLength, Common, GenesisPraosProof, Header, KESSignature, Message, Proof,
+    BftProof, Block, BlockContentHash, BlockContents, BlockDate, BlockId, BlockTransactionsRoot,
+    BlockVersion, ChainLength, Common, GenesisPraosProof, Header, KESSignature, Message, Proof,
 };
 use crate::key::{make_signature, make_signature_update};
 use crate::leadership;
@@ -37,6 +37,7 @@ impl BlockBuilder {
             common: Common {
                 block_content_size: 0,
                 block_content_hash: BlockContentHash::zero(),
+                block_transactions_root: BlockTransactionsRoot::zero(),
                 any_block_version: BlockVersion::Genesis.into(),
                 block_parent_hash: BlockId::zero(),
                 block_date: BlockDate::first(),
@@ -103,6 +104,7 @@ impl BlockBuilder {
         let (content_hash, content_size) = self.contents.compute_hash_size();
         self.common.block_content_hash = content_hash;
         self.common.block_content_size = content_size as u32;
+        self.common.block_transactions_root = self.contents.compute_transactions_root();
         self.common.any_block_version = block_version.into();
         self
     }
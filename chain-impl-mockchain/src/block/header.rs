@@ -1,5 +1,6 @@
 use crate::block::{
     headerraw::HeaderRaw,
+    merkle::{self, BlockTransactionsRoot, InclusionProof},
     version::{AnyBlockVersion, BlockVersion},
 };
 use crate::date::BlockDate;
@@ -8,6 +9,7 @@ use crate::key::{
 };
 use crate::leadership::bft;
 use crate::stake::StakePoolId;
+use crate::transaction::TransactionId;
 use chain_core::{
     mempack::{read_from_raw, ReadBuf, ReadError, Readable},
     property,
@@ -27,6 +29,7 @@ pub struct Common {
     pub block_date: BlockDate,
     pub block_content_size: BlockContentSize,
     pub block_content_hash: BlockContentHash,
+    pub block_transactions_root: BlockTransactionsRoot,
     pub block_parent_hash: BlockId,
     pub chain_length: ChainLength,
 }
@@ -111,6 +114,18 @@ impl Header {
         &self.common.block_content_hash
     }
 
+    /// the root of the Merkle tree of the transactions of the block
+    #[inline]
+    pub fn block_transactions_root(&self) -> &BlockTransactionsRoot {
+        &self.common.block_transactions_root
+    }
+
+    /// check that the transaction is part of the contents of the block,
+    /// see `Block::proof_of_inclusion`
+    pub fn verify_inclusion_proof(&self, id: &TransactionId, proof: &InclusionProof) -> bool {
+        merkle::verify_inclusion_proof(&self.common.block_transactions_root, id, proof)
+    }
+
     #[inline]
     pub fn block_parent_hash(&self) -> &BlockId {
         &self.common.block_parent_hash
@@ -129,10 +144,10 @@ impl Header {
     /// of the serialized header (except the first 2bytes: the size)
     #[inline]
     pub fn hash(&self) -> HeaderHash {
-        // TODO: this is not the optimal way to compute the hash
         use chain_core::property::Serialize;
-        let bytes = self.serialize_as_vec().unwrap();
-        HeaderHash::hash_bytes(&bytes[..])
+        let mut hasher = chain_crypto::Blake2b256Hasher::new();
+        self.serialize(&mut hasher).unwrap();
+        HeaderHash::from(hasher.finalize())
     }
 
     pub fn proof(&self) -> &Proof {
@@ -161,6 +176,7 @@ impl property::Serialize for Common {
         codec.put_u32(self.block_date.slot_id)?;
         codec.put_u32(self.chain_length.0)?;
         codec.write_all(self.block_content_hash.as_ref())?;
+        codec.write_all(self.block_transactions_root.as_ref())?;
         codec.write_all(self.block_parent_hash.as_ref())?;
 
         Ok(())
@@ -202,6 +218,7 @@ impl Readable for Common {
         let slot_id = buf.get_u32()?;
         let chain_length = buf.get_u32().map(ChainLength)?;
         let block_content_hash = Hash::read(buf)?;
+        let block_transactions_root = Hash::read(buf)?;
         let block_parent_hash = Hash::read(buf)?;
 
         let block_date = BlockDate { epoch, slot_id };
@@ -211,6 +228,7 @@ impl Readable for Common {
             block_date,
             chain_length,
             block_content_hash,
+            block_transactions_root,
             block_parent_hash,
         })
     }
@@ -233,16 +251,13 @@ impl Readable for Header {
             }
             AnyBlockVersion::Supported(BlockVersion::KesVrfproof) => {
                 let node_id = StakePoolId::read(buf)?;
-                dbg!(&node_id);
                 let vrf_proof = {
                     let bytes = <[u8;<Curve25519_2HashDH as VerifiableRandomFunction>::VERIFIED_RANDOM_SIZE]>::read(buf)?;
 
                     <Curve25519_2HashDH as VerifiableRandomFunction>::VerifiedRandomOutput::from_bytes_unverified(&bytes)
                         .ok_or(ReadError::StructureInvalid("VRF Proof".to_string()))
                 }?;
-                dbg!(&vrf_proof);
                 let kes_proof = deserialize_signature(buf).map(KESSignature)?;
-                dbg!(&kes_proof);
 
                 Proof::GenesisPraos(GenesisPraosProof {
                     node_id: node_id,
@@ -302,6 +317,15 @@ mod test {
         fn header_serialization_bijection(b: Header) -> TestResult {
             property::testing::serialization_bijection_r(b)
         }
+
+        fn header_readable_truncations(b: Header) -> TestResult {
+            property::testing::readable_truncations(b)
+        }
+
+        fn header_hash_of_serialized_bytes(b: Header) -> bool {
+            use chain_core::property::Serialize;
+            b.hash() == HeaderHash::hash_bytes(&b.serialize_as_vec().unwrap())
+        }
     }
 
     impl Arbitrary for AnyBlockVersion {
@@ -323,6 +347,7 @@ mod test {
                 block_date: Arbitrary::arbitrary(g),
                 block_content_size: Arbitrary::arbitrary(g),
                 block_content_hash: Arbitrary::arbitrary(g),
+                block_transactions_root: Arbitrary::arbitrary(g),
                 block_parent_hash: Arbitrary::arbitrary(g),
                 chain_length: ChainLength(Arbitrary::arbitrary(g)),
             }
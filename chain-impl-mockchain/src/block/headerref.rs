@@ -0,0 +1,193 @@
+use crate::block::{
+    header::{BlockContentHash, BlockContentSize, BlockId, ChainLength, Header, HeaderHash},
+    merkle::BlockTransactionsRoot,
+    version::{AnyBlockVersion, BlockVersion},
+};
+use crate::date::BlockDate;
+use crate::key::Hash;
+use chain_core::mempack::{ReadBuf, ReadError, Readable};
+use chain_crypto::{
+    AsymmetricKey, Curve25519_2HashDH, Ed25519Extended, FakeMMM, VerifiableRandomFunction,
+    VerificationAlgorithm,
+};
+
+const HASH_SIZE: usize = 32;
+
+const OFFSET_VERSION: usize = 0;
+const OFFSET_CONTENT_SIZE: usize = OFFSET_VERSION + 2;
+const OFFSET_DATE_EPOCH: usize = OFFSET_CONTENT_SIZE + 4;
+const OFFSET_DATE_SLOT_ID: usize = OFFSET_DATE_EPOCH + 4;
+const OFFSET_CHAIN_LENGTH: usize = OFFSET_DATE_SLOT_ID + 4;
+const OFFSET_CONTENT_HASH: usize = OFFSET_CHAIN_LENGTH + 4;
+const OFFSET_TRANSACTIONS_ROOT: usize = OFFSET_CONTENT_HASH + HASH_SIZE;
+const OFFSET_PARENT_HASH: usize = OFFSET_TRANSACTIONS_ROOT + HASH_SIZE;
+
+const COMMON_SIZE: usize = OFFSET_PARENT_HASH + HASH_SIZE;
+
+const BFT_PROOF_SIZE: usize = <Ed25519Extended as AsymmetricKey>::PUBLIC_KEY_SIZE
+    + <Ed25519Extended as VerificationAlgorithm>::SIGNATURE_SIZE;
+
+const GENESIS_PRAOS_PROOF_SIZE: usize = HASH_SIZE
+    + <Curve25519_2HashDH as VerifiableRandomFunction>::VERIFIED_RANDOM_SIZE
+    + <FakeMMM as VerificationAlgorithm>::SIGNATURE_SIZE;
+
+/// Block header borrowed from its serialized bytes
+///
+/// The fields are read from the bytes when accessed instead of being
+/// decoded in a `Header`, so the headers can be checked (chain length,
+/// date, parent, hash) without copying them or decoding the keys and
+/// signatures of their proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeaderRef<'a>(&'a [u8]);
+
+impl<'a> HeaderRef<'a> {
+    /// borrow the serialized header (without the size), only its
+    /// structure is checked
+    pub fn from_slice(slice: &'a [u8]) -> Result<Self, ReadError> {
+        let mut buf = ReadBuf::from(slice);
+        let version = AnyBlockVersion::from(buf.get_u16()?);
+        buf.skip_bytes(COMMON_SIZE - OFFSET_CONTENT_SIZE)?;
+        match version {
+            AnyBlockVersion::Supported(BlockVersion::Genesis) => {}
+            AnyBlockVersion::Supported(BlockVersion::Ed25519Signed) => {
+                buf.skip_bytes(BFT_PROOF_SIZE)?
+            }
+            AnyBlockVersion::Supported(BlockVersion::KesVrfproof) => {
+                buf.skip_bytes(GENESIS_PRAOS_PROOF_SIZE)?
+            }
+            AnyBlockVersion::Unsupported(version) => {
+                return Err(ReadError::UnknownTag(version as u32));
+            }
+        }
+        buf.expect_end()?;
+        Ok(HeaderRef(slice))
+    }
+
+    /// borrow the next size prefixed header of the buffer
+    pub fn read(buf: &mut ReadBuf<'a>) -> Result<Self, ReadError> {
+        let size = buf.get_u16()? as usize;
+        HeaderRef::from_slice(buf.get_slice_ref(size)?)
+    }
+
+    fn u32_at(&self, offset: usize) -> u32 {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(&self.0[offset..offset + 4]);
+        u32::from_be_bytes(bytes)
+    }
+
+    fn hash_at(&self, offset: usize) -> Hash {
+        let mut bytes = [0; HASH_SIZE];
+        bytes.copy_from_slice(&self.0[offset..offset + HASH_SIZE]);
+        Hash::from_bytes(bytes)
+    }
+
+    pub fn block_version(&self) -> AnyBlockVersion {
+        AnyBlockVersion::from(u16::from_be_bytes([
+            self.0[OFFSET_VERSION],
+            self.0[OFFSET_VERSION + 1],
+        ]))
+    }
+
+    pub fn block_content_size(&self) -> BlockContentSize {
+        self.u32_at(OFFSET_CONTENT_SIZE)
+    }
+
+    pub fn block_date(&self) -> BlockDate {
+        BlockDate {
+            epoch: self.u32_at(OFFSET_DATE_EPOCH),
+            slot_id: self.u32_at(OFFSET_DATE_SLOT_ID),
+        }
+    }
+
+    pub fn chain_length(&self) -> ChainLength {
+        ChainLength(self.u32_at(OFFSET_CHAIN_LENGTH))
+    }
+
+    pub fn block_content_hash(&self) -> BlockContentHash {
+        self.hash_at(OFFSET_CONTENT_HASH)
+    }
+
+    pub fn block_transactions_root(&self) -> BlockTransactionsRoot {
+        self.hash_at(OFFSET_TRANSACTIONS_ROOT)
+    }
+
+    pub fn block_parent_hash(&self) -> BlockId {
+        self.hash_at(OFFSET_PARENT_HASH)
+    }
+
+    /// the hash of the header, computed on the borrowed bytes: same as
+    /// `Header::hash` without serializing the header again
+    pub fn hash(&self) -> HeaderHash {
+        HeaderHash::hash_bytes(self.0)
+    }
+
+    /// decode the whole header, including its proof
+    pub fn to_header(&self) -> Result<Header, ReadError> {
+        let mut buf = ReadBuf::from(self.0);
+        let header = Header::read(&mut buf)?;
+        buf.expect_end()?;
+        Ok(header)
+    }
+}
+
+impl<'a> AsRef<[u8]> for HeaderRef<'a> {
+    fn as_ref(&self) -> &[u8] {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use chain_core::property::Serialize;
+    use quickcheck::TestResult;
+
+    quickcheck! {
+        fn header_ref_reads_the_header(header: Header) -> TestResult {
+            let bytes = header.serialize_as_vec().unwrap();
+            let header_ref = HeaderRef::from_slice(&bytes).unwrap();
+            TestResult::from_bool(
+                header_ref.block_version() == header.block_version()
+                    && header_ref.block_content_size() == header.common.block_content_size
+                    && header_ref.block_date() == *header.block_date()
+                    && header_ref.chain_length() == header.chain_length()
+                    && header_ref.block_content_hash() == *header.block_content_hash()
+                    && header_ref.block_transactions_root() == *header.block_transactions_root()
+                    && header_ref.block_parent_hash() == *header.block_parent_hash()
+                    && header_ref.hash() == header.hash()
+                    && header_ref.to_header().unwrap() == header,
+            )
+        }
+
+        fn header_ref_rejects_bad_sizes(header: Header) -> TestResult {
+            let mut bytes = header.serialize_as_vec().unwrap();
+            let truncated = HeaderRef::from_slice(&bytes[..bytes.len() - 1]);
+            bytes.push(0);
+            let extended = HeaderRef::from_slice(&bytes);
+            TestResult::from_bool(
+                truncated.is_err() && extended == Err(ReadError::UnconsumedData(1)),
+            )
+        }
+    }
+
+    #[test]
+    fn header_ref_read_size_prefixed() {
+        let mut bytes = vec![0, COMMON_SIZE as u8];
+        bytes.extend_from_slice(&[0; COMMON_SIZE]);
+        bytes[2 + OFFSET_CHAIN_LENGTH + 3] = 42;
+        bytes.push(0xff);
+
+        let mut buf = ReadBuf::from(&bytes);
+        let header_ref = HeaderRef::read(&mut buf).unwrap();
+        assert_eq!(header_ref.block_version(), BlockVersion::Genesis.into());
+        assert_eq!(header_ref.chain_length(), ChainLength(42));
+        assert_eq!(header_ref.as_ref(), &bytes[2..2 + COMMON_SIZE]);
+        assert_eq!(buf.get_u8(), Ok(0xff));
+
+        let mut buf = ReadBuf::from(&bytes[..COMMON_SIZE]);
+        assert_eq!(
+            HeaderRef::read(&mut buf),
+            Err(ReadError::NotEnoughBytes(COMMON_SIZE - 2, COMMON_SIZE))
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! Merkle tree of the transactions of a block.
+//!
+//! The header of a block commits to the identifiers of the transactions of
+//! its contents (the transactions, the certificates and the multisig
+//! declarations, in the order of the contents) with the root of a Merkle
+//! tree. A wallet only tracking the headers can verify that a transaction
+//! is part of a block from the identifier of the transaction and an
+//! `InclusionProof`, without downloading the contents.
+//!
+//! The leaves are hashed as `H(0x00 | id)` and the nodes as
+//! `H(0x01 | left | right)`, so a node cannot be passed for a leaf. A node
+//! without sibling is promoted as is to the next level. The root of a block
+//! without transactions is the zero hash.
+
+use super::BlockContents;
+use crate::key::Hash;
+use crate::message::Message;
+use crate::transaction::TransactionId;
+use chain_core::mempack::{ReadBuf, ReadError, Readable};
+use chain_core::property;
+use chain_crypto::Blake2b256Hasher;
+
+pub type BlockTransactionsRoot = Hash;
+
+const LEAF_TAG: u8 = 0;
+const NODE_TAG: u8 = 1;
+
+fn hash_leaf(id: &TransactionId) -> Hash {
+    let mut hasher = Blake2b256Hasher::new();
+    hasher.update(&[LEAF_TAG]);
+    hasher.update(id.as_ref());
+    Hash::from(hasher.finalize())
+}
+
+fn hash_node(left: &Hash, right: &Hash) -> Hash {
+    let mut hasher = Blake2b256Hasher::new();
+    hasher.update(&[NODE_TAG]);
+    hasher.update(left.as_ref());
+    hasher.update(right.as_ref());
+    Hash::from(hasher.finalize())
+}
+
+/// a sibling on the path from a leaf to the root
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofStep {
+    /// the sibling is on the left of the node of the path
+    Left(Hash),
+    /// the sibling is on the right of the node of the path
+    Right(Hash),
+}
+
+/// proof that a transaction is part of the contents of a block, see
+/// `Block::proof_of_inclusion`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InclusionProof {
+    /// the siblings from the leaf of the transaction up to the root
+    pub steps: Vec<ProofStep>,
+}
+
+impl InclusionProof {
+    /// the root of the tree the proof leads to from the given transaction
+    pub fn root(&self, id: &TransactionId) -> BlockTransactionsRoot {
+        self.steps
+            .iter()
+            .fold(hash_leaf(id), |node, step| match step {
+                ProofStep::Left(sibling) => hash_node(sibling, &node),
+                ProofStep::Right(sibling) => hash_node(&node, sibling),
+            })
+    }
+}
+
+/// check that the transaction is part of the block whose header holds the
+/// given root
+pub fn verify_inclusion_proof(
+    root: &BlockTransactionsRoot,
+    id: &TransactionId,
+    proof: &InclusionProof,
+) -> bool {
+    &proof.root(id) == root
+}
+
+/// the levels of the tree, from the leaves to the root
+fn levels(leaves: Vec<Hash>) -> Vec<Vec<Hash>> {
+    let mut levels = vec![leaves];
+    while levels.last().unwrap().len() > 1 {
+        let next = levels
+            .last()
+            .unwrap()
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => hash_node(left, right),
+                [single] => *single,
+                _ => unreachable!(),
+            })
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+impl BlockContents {
+    /// the identifiers of the transactions committed to by the
+    /// transactions root of the header, in the order of the contents
+    pub fn transaction_ids<'a>(&'a self) -> impl Iterator<Item = TransactionId> + 'a {
+        self.iter().filter_map(|message| match message {
+            Message::Transaction(tx) => Some(tx.transaction.hash()),
+            Message::Certificate(tx) => Some(tx.transaction.hash()),
+            Message::MultisigDeclaration(tx) => Some(tx.transaction.hash()),
+            _ => None,
+        })
+    }
+
+    fn leaves(&self) -> Vec<Hash> {
+        self.transaction_ids().map(|id| hash_leaf(&id)).collect()
+    }
+
+    /// the root of the Merkle tree of the transactions of the contents
+    pub fn compute_transactions_root(&self) -> BlockTransactionsRoot {
+        use chain_core::property::BlockId;
+        match levels(self.leaves()).last().unwrap().first() {
+            None => Hash::zero(),
+            Some(root) => *root,
+        }
+    }
+
+    /// the proof that the transaction of the given identifier is part of
+    /// the contents, `None` if it is not
+    pub fn proof_of_inclusion(&self, id: &TransactionId) -> Option<InclusionProof> {
+        let mut index = self.transaction_ids().position(|tx_id| &tx_id == id)?;
+        let levels = levels(self.leaves());
+        let mut steps = Vec::with_capacity(levels.len());
+        for level in &levels[..levels.len() - 1] {
+            let sibling = index ^ 1;
+            if sibling < level.len() {
+                steps.push(if sibling < index {
+                    ProofStep::Left(level[sibling])
+                } else {
+                    ProofStep::Right(level[sibling])
+                });
+            }
+            index /= 2;
+        }
+        Some(InclusionProof { steps })
+    }
+}
+
+impl property::Serialize for InclusionProof {
+    type Error = std::io::Error;
+    fn serialize<W: std::io::Write>(&self, writer: W) -> Result<(), Self::Error> {
+        use chain_core::packer::*;
+        use std::io::Write;
+        // a tree of at most 2^64 leaves never needs that many steps, only
+        // a hand-built proof does
+        if self.steps.len() > u8::max_value() as usize {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("inclusion proof of {} steps", self.steps.len()),
+            ));
+        }
+        let mut codec = Codec::from(writer);
+        codec.put_u8(self.steps.len() as u8)?;
+        for step in &self.steps {
+            let (side, sibling) = match step {
+                ProofStep::Left(sibling) => (0, sibling),
+                ProofStep::Right(sibling) => (1, sibling),
+            };
+            codec.put_u8(side)?;
+            codec.write_all(sibling.as_ref())?;
+        }
+        Ok(())
+    }
+}
+
+impl Readable for InclusionProof {
+    fn read<'a>(buf: &mut ReadBuf<'a>) -> Result<Self, ReadError> {
+        let len = buf.get_u8()? as usize;
+        let mut steps = Vec::with_capacity(len);
+        for _ in 0..len {
+            let side = buf.get_u8()?;
+            let sibling = Hash::read(buf)?;
+            steps.push(match side {
+                0 => ProofStep::Left(sibling),
+                1 => ProofStep::Right(sibling),
+                _ => return Err(ReadError::UnknownTag(side as u32)),
+            });
+        }
+        Ok(InclusionProof { steps })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::message::Message;
+    use crate::transaction::{AuthenticatedTransaction, Metadata, NoExtra, Transaction};
+    use chain_core::property::Serialize;
+
+    fn transaction(metadata: u8) -> Message {
+        Message::Transaction(AuthenticatedTransaction {
+            transaction: Transaction {
+                inputs: vec![],
+                outputs: vec![],
+                extra: NoExtra,
+                metadata: Some(Metadata::new(vec![metadata]).unwrap()),
+            },
+            witnesses: vec![],
+        })
+    }
+
+    #[test]
+    pub fn inclusion_proofs() {
+        for count in 0..8 {
+            let contents = BlockContents::new((0..count).map(transaction).collect());
+            let root = contents.compute_transactions_root();
+            let ids: Vec<_> = contents.transaction_ids().collect();
+            assert_eq!(ids.len(), count as usize);
+            for id in &ids {
+                let proof = contents.proof_of_inclusion(id).unwrap();
+                assert!(verify_inclusion_proof(&root, id, &proof));
+
+                let bytes = proof.serialize_as_vec().unwrap();
+                let decoded = InclusionProof::read(&mut ReadBuf::from(&bytes)).unwrap();
+                assert_eq!(decoded, proof);
+
+                // the proof of a transaction does not hold for another one
+                for other in ids.iter().filter(|other| *other != id) {
+                    assert!(!verify_inclusion_proof(&root, other, &proof));
+                }
+            }
+        }
+
+        let contents = BlockContents::new(vec![transaction(1)]);
+        let other = BlockContents::new(vec![transaction(2)]);
+        let other_id = other.transaction_ids().next().unwrap();
+        assert!(contents.proof_of_inclusion(&other_id).is_none());
+    }
+
+    #[test]
+    pub fn too_deep_proof() {
+        let step = ProofStep::Left(Hash::from_bytes([0; 32]));
+        let proof = InclusionProof {
+            steps: vec![step; 255],
+        };
+        assert!(proof.serialize_as_vec().is_ok());
+        let proof = InclusionProof {
+            steps: vec![step; 256],
+        };
+        assert!(proof.serialize_as_vec().is_err());
+    }
+}
@@ -1,17 +1,23 @@
 //! Representation of the block in the mockchain.
 use crate::key::Hash;
-use crate::message::{Message, MessageRaw};
-use chain_core::mempack::read_from_raw;
+use crate::message::Message;
+use crate::transaction::{Metadata, TransactionId};
+use chain_core::mempack::{read_from_raw, ReadBuf, Readable};
 use chain_core::property::{self, Serialize};
+use chain_crypto::Blake2b256Hasher;
 
+mod block0;
 mod builder;
 //mod cstruct;
 mod header;
 mod headerraw;
+mod headerref;
+mod merkle;
 mod version;
 
 pub use self::version::{AnyBlockVersion, BlockVersion, ConsensusVersion};
 
+pub use self::block0::{Block0Builder, Block0Error};
 pub use self::builder::BlockBuilder;
 
 pub use self::header::{
@@ -19,6 +25,8 @@ pub use self::header::{
     GenesisPraosProof, Header, HeaderHash, KESSignature, Proof,
 };
 pub use self::headerraw::HeaderRaw;
+pub use self::headerref::HeaderRef;
+pub use self::merkle::{verify_inclusion_proof, BlockTransactionsRoot, InclusionProof, ProofStep};
 pub use self::version::*;
 
 pub use crate::date::{BlockDate, BlockDateParseError, Epoch, SlotId};
@@ -59,14 +67,36 @@ impl BlockContents {
         self.0.iter()
     }
     pub fn compute_hash_size(&self) -> (BlockContentHash, usize) {
-        let mut bytes = Vec::with_capacity(4096);
+        let mut hasher = Blake2b256Hasher::new();
+        let mut size = 0;
 
         for message in self.iter() {
-            message.to_raw().serialize(&mut bytes).unwrap();
+            let raw = message.to_raw();
+            raw.serialize(&mut hasher).unwrap();
+            size += raw.size_bytes_plus_size();
         }
 
-        let hash = Hash::hash_bytes(&bytes);
-        (hash, bytes.len())
+        (Hash::from(hasher.finalize()), size)
+    }
+
+    /// the metadata attached to the transaction of the given identifier,
+    /// if the transaction is part of these contents and carries any
+    pub fn transaction_metadata(&self, id: &TransactionId) -> Option<&Metadata> {
+        self.iter()
+            .filter_map(|message| match message {
+                Message::Transaction(tx) if &tx.transaction.hash() == id => {
+                    Some(&tx.transaction.metadata)
+                }
+                Message::Certificate(tx) if &tx.transaction.hash() == id => {
+                    Some(&tx.transaction.metadata)
+                }
+                Message::MultisigDeclaration(tx) if &tx.transaction.hash() == id => {
+                    Some(&tx.transaction.metadata)
+                }
+                _ => None,
+            })
+            .next()
+            .and_then(Option::as_ref)
     }
 }
 
@@ -76,6 +106,15 @@ impl Block {
 
         &content_hash == self.header.block_content_hash()
             && content_size == self.header.common.block_content_size as usize
+            && &self.contents.compute_transactions_root() == self.header.block_transactions_root()
+    }
+
+    /// the proof that the transaction of the given identifier is part of
+    /// the block, to check against the header with
+    /// `Header::verify_inclusion_proof`. `None` if the transaction is not
+    /// part of the block.
+    pub fn proof_of_inclusion(&self, id: &TransactionId) -> Option<InclusionProof> {
+        self.contents.proof_of_inclusion(id)
     }
 }
 
@@ -133,23 +172,25 @@ impl property::Deserialize for Block {
     type Error = std::io::Error;
 
     fn deserialize<R: std::io::BufRead>(mut reader: R) -> Result<Self, Self::Error> {
+        use std::io::Read;
+
         let header_raw = HeaderRaw::deserialize(&mut reader)?;
         let header = read_from_raw::<Header>(header_raw.as_ref())?;
 
-        let mut serialized_content_size = header.common.block_content_size;
-        let mut contents = BlockContents(Vec::with_capacity(4));
-
-        while serialized_content_size > 0 {
-            let message_raw = MessageRaw::deserialize(&mut reader)?;
-            let message_size = message_raw.size_bytes_plus_size();
-
-            // return error here if message serialize sized is bigger than remaining size
+        // read the contents in one buffer and decode the messages in place
+        let content_size = header.common.block_content_size as u64;
+        let mut contents_raw = Vec::new();
+        reader.take(content_size).read_to_end(&mut contents_raw)?;
+        if (contents_raw.len() as u64) < content_size {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
+        }
 
-            let message = Message::from_raw(&message_raw)
+        let mut buf = ReadBuf::from(&contents_raw);
+        let mut contents = BlockContents(Vec::with_capacity(4));
+        while !buf.is_end() {
+            let message = Message::read(&mut buf)
                 .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
             contents.0.push(message);
-
-            serialized_content_size -= message_size as u32;
         }
 
         Ok(Block {
@@ -159,6 +200,14 @@ impl property::Deserialize for Block {
     }
 }
 
+impl property::Versioned for Block {
+    const SUPPORTED_VERSIONS: &'static [property::FormatVersion] = &[property::FormatVersion(1)];
+}
+
+impl property::VersionedSerialize for Block {}
+
+impl property::VersionedDeserialize for Block {}
+
 impl property::HasMessages for Block {
     type Message = Message;
     fn messages<'a>(&'a self) -> Box<Iterator<Item = &Message> + 'a> {
@@ -198,6 +247,10 @@ mod test {
         fn block_serialization_bijection(b: Block) -> TestResult {
             property::testing::serialization_bijection(b)
         }
+
+        fn block_versioned_serialization_bijection(b: Block) -> TestResult {
+            property::testing::versioned_serialization_bijection(b)
+        }
     }
 
     impl Arbitrary for HeaderRaw {
@@ -228,6 +281,7 @@ mod test {
             let mut header = Header::arbitrary(g);
             header.common.block_content_size = size as u32;
             header.common.block_content_hash = hash;
+            header.common.block_transactions_root = content.compute_transactions_root();
             Block {
                 header: header,
                 contents: content,
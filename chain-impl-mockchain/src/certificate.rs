@@ -1,12 +1,18 @@
-use crate::key::SpendingSecretKey;
+//! Certificates carried by the transactions, as their extra.
+//!
+//! A certificate is a payload, identified by a tag of the registered
+//! payload-type tag space, and the signatures of the stake keys owning
+//! the payload. The payload is serialized with its size, so the format of
+//! the certificates does not change when a new kind of payload is
+//! registered.
+
+use crate::key::{deserialize_signature, make_signature, verify_signature, SpendingSecretKey};
 use crate::stake::{StakeKeyId, StakePoolId, StakePoolInfo};
 use chain_core::mempack::{read_vec, ReadBuf, ReadError, Readable};
 use chain_core::property;
 use chain_crypto::{Ed25519Extended, SecretKey, Verification};
-use num_derive::FromPrimitive;
-use num_traits::FromPrimitive;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SignatureRaw(Vec<u8>);
 
 impl property::Serialize for SignatureRaw {
@@ -28,85 +34,109 @@ impl Readable for SignatureRaw {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct Certificate {
-    pub content: CertificateContent,
-    pub signatures: Vec<SignatureRaw>,
-}
+/// Tag of the type of the payload of a certificate.
+///
+/// The tag space is registered by family of certificates, a new kind of
+/// certificate taking the next free tag of its family:
+///
+/// * `0x01..=0x0f`: stake keys and delegation;
+/// * `0x10..=0x1f`: stake pool operations;
+/// * `0x20..=0x2f`: update proposals and votes (reserved, they are still
+///   carried by their own messages).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct CertificateTag(u8);
 
-impl Certificate {
-    pub fn sign(&mut self, secret_key: &SpendingSecretKey) -> () {
-        match &self.content {
-            CertificateContent::StakeKeyRegistration(v) => {
-                let signature = v.make_certificate(secret_key);
-                self.signatures.push(signature);
-            }
-            CertificateContent::StakeKeyDeregistration(v) => {
-                let signature = v.make_certificate(secret_key);
-                self.signatures.push(signature);
-            }
-            CertificateContent::StakeDelegation(v) => {
-                let signature = v.make_certificate(secret_key);
-                self.signatures.push(signature);
-            }
-            CertificateContent::StakePoolRegistration(v) => {
-                let signature = v.make_certificate(secret_key);
-                self.signatures.push(signature);
-            }
-            CertificateContent::StakePoolRetirement(v) => {
-                let signature = v.make_certificate(secret_key);
-                self.signatures.push(signature);
-            }
-        }
+impl CertificateTag {
+    pub const fn new(tag: u8) -> Self {
+        CertificateTag(tag)
     }
 
-    pub fn verify(&self) -> Verification {
-        match &self.content {
-            CertificateContent::StakeKeyRegistration(v) => verify_certificate(v, &self.signatures),
-            CertificateContent::StakeKeyDeregistration(v) => {
-                verify_certificate(v, &self.signatures)
-            }
-            CertificateContent::StakeDelegation(v) => verify_certificate(v, &self.signatures),
-            CertificateContent::StakePoolRegistration(v) => verify_certificate(v, &self.signatures),
-            CertificateContent::StakePoolRetirement(v) => verify_certificate(v, &self.signatures),
-        }
+    pub fn as_u8(self) -> u8 {
+        self.0
     }
 }
 
-/// Keep an information how to extract public keys from
-/// the certificate.
-trait HasStakeKeyIds {
-    fn public_keys<'a>(&'a self) -> Box<ExactSizeIterator<Item = &StakeKeyId> + 'a>;
-}
-
-fn verify_certificate<C>(certificate: &C, raw_signatures: &[SignatureRaw]) -> Verification
-where
-    C: HasStakeKeyIds + property::Serialize,
+/// A type of payload of a certificate, registered with its tag.
+///
+/// The witness rules are shared by all the certificates: the payload is
+/// signed by each of its signers, in order.
+pub trait CertificateVariant:
+    Clone + property::Serialize<Error = std::io::Error> + Readable
 {
-    use crate::key::{deserialize_signature, verify_signature};
-    let signatures = raw_signatures.iter();
-    let owners = certificate.public_keys();
-    if owners.len() > signatures.len() {
-        return Verification::Failed;
-    }
-    owners
-        .zip(signatures)
-        .fold(Verification::Success, |_, (owner, signature)| {
+    const TAG: CertificateTag;
+
+    /// the stake keys which have to sign the payload
+    fn signers<'a>(&'a self) -> Box<dyn ExactSizeIterator<Item = &'a StakeKeyId> + 'a>;
+
+    /// sign the payload with the secret key of one of its signers
+    fn make_certificate(&self, secret_key: &SecretKey<Ed25519Extended>) -> SignatureRaw {
+        SignatureRaw(make_signature(secret_key, self).as_ref().to_vec())
+    }
+
+    /// verify the payload is signed by all its signers
+    fn verify_signatures(&self, raw_signatures: &[SignatureRaw]) -> Verification {
+        let owners = self.signers();
+        if owners.len() > raw_signatures.len() {
+            return Verification::Failed;
+        }
+        let all_signed = owners.zip(raw_signatures).all(|(owner, signature)| {
             let mut reader = ReadBuf::from(&signature.0);
             match deserialize_signature(&mut reader) {
                 Ok(signature) => {
-                    if verify_signature(&signature, &owner.0, &certificate) == Verification::Failed
-                    {
-                        return Verification::Failed;
-                    }
+                    verify_signature(&signature, &owner.0, self) == Verification::Success
                 }
-                Err(_) => return Verification::Failed,
+                Err(_) => false,
             }
+        });
+        if all_signed {
             Verification::Success
-        })
+        } else {
+            Verification::Failed
+        }
+    }
+
+    fn to_payload(&self) -> Result<Vec<u8>, std::io::Error> {
+        self.serialize_as_vec()
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self, ReadError> {
+        let mut buf = ReadBuf::from(payload);
+        let variant = Self::read(&mut buf)?;
+        buf.expect_end()?;
+        Ok(variant)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Certificate {
+    pub content: CertificateContent,
+    pub signatures: Vec<SignatureRaw>,
 }
 
-#[derive(Debug, Clone)]
+impl Certificate {
+    pub fn sign(&mut self, secret_key: &SpendingSecretKey) -> () {
+        let signature = match &self.content {
+            CertificateContent::StakeKeyRegistration(v) => v.make_certificate(secret_key),
+            CertificateContent::StakeKeyDeregistration(v) => v.make_certificate(secret_key),
+            CertificateContent::StakeDelegation(v) => v.make_certificate(secret_key),
+            CertificateContent::StakePoolRegistration(v) => v.make_certificate(secret_key),
+            CertificateContent::StakePoolRetirement(v) => v.make_certificate(secret_key),
+        };
+        self.signatures.push(signature);
+    }
+
+    pub fn verify(&self) -> Verification {
+        match &self.content {
+            CertificateContent::StakeKeyRegistration(v) => v.verify_signatures(&self.signatures),
+            CertificateContent::StakeKeyDeregistration(v) => v.verify_signatures(&self.signatures),
+            CertificateContent::StakeDelegation(v) => v.verify_signatures(&self.signatures),
+            CertificateContent::StakePoolRegistration(v) => v.verify_signatures(&self.signatures),
+            CertificateContent::StakePoolRetirement(v) => v.verify_signatures(&self.signatures),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum CertificateContent {
     StakeKeyRegistration(StakeKeyRegistration),
     StakeKeyDeregistration(StakeKeyDeregistration),
@@ -115,42 +145,67 @@ pub enum CertificateContent {
     StakePoolRetirement(StakePoolRetirement),
 }
 
-#[derive(FromPrimitive)]
-enum CertificateTag {
-    StakeKeyRegistration = 1,
-    StakeKeyDeregistration = 2,
-    StakeDelegation = 3,
-    StakePoolRegistration = 4,
-    StakePoolRetirement = 5,
+impl CertificateContent {
+    pub fn tag(&self) -> CertificateTag {
+        match self {
+            CertificateContent::StakeKeyRegistration(_) => StakeKeyRegistration::TAG,
+            CertificateContent::StakeKeyDeregistration(_) => StakeKeyDeregistration::TAG,
+            CertificateContent::StakeDelegation(_) => StakeDelegation::TAG,
+            CertificateContent::StakePoolRegistration(_) => StakePoolInfo::TAG,
+            CertificateContent::StakePoolRetirement(_) => StakePoolRetirement::TAG,
+        }
+    }
+
+    fn to_payload(&self) -> Result<Vec<u8>, std::io::Error> {
+        match self {
+            CertificateContent::StakeKeyRegistration(v) => v.to_payload(),
+            CertificateContent::StakeKeyDeregistration(v) => v.to_payload(),
+            CertificateContent::StakeDelegation(v) => v.to_payload(),
+            CertificateContent::StakePoolRegistration(v) => v.to_payload(),
+            CertificateContent::StakePoolRetirement(v) => v.to_payload(),
+        }
+    }
+
+    fn from_payload(tag: CertificateTag, payload: &[u8]) -> Result<Self, ReadError> {
+        match tag {
+            StakeKeyRegistration::TAG => StakeKeyRegistration::from_payload(payload)
+                .map(CertificateContent::StakeKeyRegistration),
+            StakeKeyDeregistration::TAG => StakeKeyDeregistration::from_payload(payload)
+                .map(CertificateContent::StakeKeyDeregistration),
+            StakeDelegation::TAG => {
+                StakeDelegation::from_payload(payload).map(CertificateContent::StakeDelegation)
+            }
+            StakePoolInfo::TAG => {
+                StakePoolInfo::from_payload(payload).map(CertificateContent::StakePoolRegistration)
+            }
+            StakePoolRetirement::TAG => StakePoolRetirement::from_payload(payload)
+                .map(CertificateContent::StakePoolRetirement),
+            CertificateTag(tag) => Err(ReadError::UnknownTag(tag as u32)),
+        }
+    }
 }
 
 impl property::Serialize for Certificate {
     type Error = std::io::Error;
     fn serialize<W: std::io::Write>(&self, writer: W) -> Result<(), Self::Error> {
         use chain_core::packer::*;
+        let payload = self.content.to_payload()?;
+        if payload.len() > u16::max_value() as usize {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "certificate payload too big",
+            ));
+        }
+        if self.signatures.len() > u8::max_value() as usize {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "too many certificate signatures",
+            ));
+        }
         let mut codec = Codec::from(writer);
-        match &self.content {
-            CertificateContent::StakeKeyRegistration(s) => {
-                codec.put_u8(CertificateTag::StakeKeyRegistration as u8)?;
-                s.serialize(&mut codec)
-            }
-            CertificateContent::StakeKeyDeregistration(s) => {
-                codec.put_u8(CertificateTag::StakeKeyDeregistration as u8)?;
-                s.serialize(&mut codec)
-            }
-            CertificateContent::StakeDelegation(s) => {
-                codec.put_u8(CertificateTag::StakeDelegation as u8)?;
-                s.serialize(&mut codec)
-            }
-            CertificateContent::StakePoolRegistration(s) => {
-                codec.put_u8(CertificateTag::StakePoolRegistration as u8)?;
-                s.serialize(&mut codec)
-            }
-            CertificateContent::StakePoolRetirement(s) => {
-                codec.put_u8(CertificateTag::StakePoolRetirement as u8)?;
-                s.serialize(&mut codec)
-            }
-        }?;
+        codec.put_u8(self.content.tag().as_u8())?;
+        codec.put_u16(payload.len() as u16)?;
+        codec.write_all(&payload)?;
         codec.put_u8(self.signatures.len() as u8)?;
         for sig in &self.signatures {
             sig.serialize(&mut codec)?;
@@ -161,28 +216,12 @@ impl property::Serialize for Certificate {
 
 impl Readable for Certificate {
     fn read<'a>(buf: &mut ReadBuf<'a>) -> Result<Self, ReadError> {
-        let tag = buf.get_u8()?;
-        let content = match CertificateTag::from_u8(tag) {
-            Some(CertificateTag::StakeKeyRegistration) => {
-                CertificateContent::StakeKeyRegistration(StakeKeyRegistration::read(buf)?)
-            }
-            Some(CertificateTag::StakeKeyDeregistration) => {
-                CertificateContent::StakeKeyDeregistration(StakeKeyDeregistration::read(buf)?)
-            }
-            Some(CertificateTag::StakePoolRegistration) => {
-                CertificateContent::StakePoolRegistration(StakePoolInfo::read(buf)?)
-            }
-            Some(CertificateTag::StakePoolRetirement) => {
-                CertificateContent::StakePoolRetirement(StakePoolRetirement::read(buf)?)
-            }
-            Some(CertificateTag::StakeDelegation) => {
-                CertificateContent::StakeDelegation(StakeDelegation::read(buf)?)
-            }
-
-            None => panic!("not a certificate"),
-        };
+        let tag = CertificateTag(buf.get_u8()?);
+        let payload_len = buf.get_u16()?;
+        let payload = buf.get_slice(payload_len as usize)?;
+        let content = CertificateContent::from_payload(tag, payload)?;
         let len = buf.get_u8()?;
-        let signatures = chain_core::mempack::read_vec(buf, len as usize)?;
+        let signatures = read_vec(buf, len as usize)?;
         Ok(Certificate {
             content,
             signatures,
@@ -195,15 +234,10 @@ pub struct StakeKeyRegistration {
     pub stake_key_id: StakeKeyId,
 }
 
-impl StakeKeyRegistration {
-    pub fn make_certificate(&self, stake_private_key: &SecretKey<Ed25519Extended>) -> SignatureRaw {
-        use crate::key::make_signature;
-        SignatureRaw(make_signature(stake_private_key, &self).as_ref().to_vec())
-    }
-}
+impl CertificateVariant for StakeKeyRegistration {
+    const TAG: CertificateTag = CertificateTag::new(0x01);
 
-impl HasStakeKeyIds for StakeKeyRegistration {
-    fn public_keys<'a>(&'a self) -> Box<ExactSizeIterator<Item = &StakeKeyId> + 'a> {
+    fn signers<'a>(&'a self) -> Box<dyn ExactSizeIterator<Item = &'a StakeKeyId> + 'a> {
         Box::new(std::iter::once(&self.stake_key_id))
     }
 }
@@ -231,15 +265,10 @@ pub struct StakeKeyDeregistration {
     pub stake_key_id: StakeKeyId,
 }
 
-impl StakeKeyDeregistration {
-    pub fn make_certificate(&self, stake_private_key: &SecretKey<Ed25519Extended>) -> SignatureRaw {
-        use crate::key::make_signature;
-        SignatureRaw(make_signature(stake_private_key, &self).as_ref().to_vec())
-    }
-}
+impl CertificateVariant for StakeKeyDeregistration {
+    const TAG: CertificateTag = CertificateTag::new(0x02);
 
-impl HasStakeKeyIds for StakeKeyDeregistration {
-    fn public_keys<'a>(&'a self) -> Box<ExactSizeIterator<Item = &StakeKeyId> + 'a> {
+    fn signers<'a>(&'a self) -> Box<dyn ExactSizeIterator<Item = &'a StakeKeyId> + 'a> {
         Box::new(std::iter::once(&self.stake_key_id))
     }
 }
@@ -268,17 +297,10 @@ pub struct StakeDelegation {
     pub pool_id: StakePoolId,
 }
 
-impl StakeDelegation {
-    pub fn make_certificate(&self, stake_private_key: &SecretKey<Ed25519Extended>) -> SignatureRaw {
-        // FIXME: "It must be signed by sks_source, and that key must
-        // be included in the witness." - why?
-        use crate::key::make_signature;
-        SignatureRaw(make_signature(stake_private_key, &self).as_ref().to_vec())
-    }
-}
+impl CertificateVariant for StakeDelegation {
+    const TAG: CertificateTag = CertificateTag::new(0x03);
 
-impl HasStakeKeyIds for StakeDelegation {
-    fn public_keys<'a>(&'a self) -> Box<ExactSizeIterator<Item = &StakeKeyId> + 'a> {
+    fn signers<'a>(&'a self) -> Box<dyn ExactSizeIterator<Item = &'a StakeKeyId> + 'a> {
         Box::new(std::iter::once(&self.stake_key_id))
     }
 }
@@ -303,17 +325,11 @@ impl Readable for StakeDelegation {
     }
 }
 
-impl StakePoolInfo {
-    /// Create a certificate for this stake pool registration, signed
-    /// by the pool's staking key and the owners.
-    pub fn make_certificate(&self, pool_private_key: &SecretKey<Ed25519Extended>) -> SignatureRaw {
-        use crate::key::make_signature;
-        SignatureRaw(make_signature(pool_private_key, &self).as_ref().to_vec())
-    }
-}
+/// the registration of a stake pool, signed by its owners
+impl CertificateVariant for StakePoolInfo {
+    const TAG: CertificateTag = CertificateTag::new(0x10);
 
-impl HasStakeKeyIds for StakePoolInfo {
-    fn public_keys<'a>(&'a self) -> Box<ExactSizeIterator<Item = &StakeKeyId> + 'a> {
+    fn signers<'a>(&'a self) -> Box<dyn ExactSizeIterator<Item = &'a StakeKeyId> + 'a> {
         Box::new(self.owners.iter())
     }
 }
@@ -325,17 +341,11 @@ pub struct StakePoolRetirement {
     pub pool_info: StakePoolInfo,
 }
 
-impl StakePoolRetirement {
-    /// Create a certificate for this stake pool retirement, signed
-    /// by the pool's staking key.
-    pub fn make_certificate(&self, pool_private_key: &SecretKey<Ed25519Extended>) -> SignatureRaw {
-        use crate::key::make_signature;
-        SignatureRaw(make_signature(pool_private_key, &self).as_ref().to_vec())
-    }
-}
+/// the retirement of a stake pool, signed by its owners
+impl CertificateVariant for StakePoolRetirement {
+    const TAG: CertificateTag = CertificateTag::new(0x11);
 
-impl HasStakeKeyIds for StakePoolRetirement {
-    fn public_keys<'a>(&'a self) -> Box<ExactSizeIterator<Item = &StakeKeyId> + 'a> {
+    fn signers<'a>(&'a self) -> Box<dyn ExactSizeIterator<Item = &'a StakeKeyId> + 'a> {
         Box::new(self.pool_info.owners.iter())
     }
 }
@@ -365,7 +375,7 @@ mod test {
     use super::*;
     use crate::leadership::genesis::GenesisPraosLeader;
     use chain_crypto::SecretKey;
-    use quickcheck::{Arbitrary, Gen};
+    use quickcheck::{Arbitrary, Gen, TestResult};
 
     impl Arbitrary for Certificate {
         fn arbitrary<G: Gen>(g: &mut G) -> Self {
@@ -442,4 +452,62 @@ mod test {
             }
         }
     }
+
+    quickcheck! {
+        fn certificate_serialization_bijection(certificate: Certificate) -> TestResult {
+            property::testing::serialization_bijection_r(certificate)
+        }
+
+        fn certificate_readable_truncations(certificate: Certificate) -> TestResult {
+            property::testing::readable_truncations(certificate)
+        }
+    }
+
+    #[test]
+    fn unknown_certificate_tag() {
+        // a (reserved) update proposal tag, with an empty payload
+        let bytes = [0x20, 0x00, 0x00, 0x00];
+        match chain_core::mempack::read_from_raw::<Certificate>(&bytes) {
+            Err(error) => assert_eq!(error.kind(), std::io::ErrorKind::InvalidData),
+            Ok(certificate) => panic!("unexpected certificate {:?}", certificate),
+        }
+    }
+
+    #[test]
+    fn too_many_certificate_signatures() {
+        let mut g = quickcheck::StdGen::new(rand::thread_rng(), 10);
+        let mut certificate = Certificate {
+            content: CertificateContent::StakeDelegation(Arbitrary::arbitrary(&mut g)),
+            signatures: vec![SignatureRaw(vec![]); 255],
+        };
+        assert!(property::Serialize::serialize_as_vec(&certificate).is_ok());
+        certificate.signatures.push(SignatureRaw(vec![]));
+        assert!(property::Serialize::serialize_as_vec(&certificate).is_err());
+    }
+
+    #[test]
+    fn certificate_signed_by_its_signers() {
+        let mut g = quickcheck::StdGen::new(rand::thread_rng(), 10);
+        let stake_key: SpendingSecretKey = Arbitrary::arbitrary(&mut g);
+        let other_key: SpendingSecretKey = Arbitrary::arbitrary(&mut g);
+        let content = CertificateContent::StakeDelegation(StakeDelegation {
+            stake_key_id: StakeKeyId::from(&stake_key),
+            pool_id: Arbitrary::arbitrary(&mut g),
+        });
+
+        let mut certificate = Certificate {
+            content: content.clone(),
+            signatures: vec![],
+        };
+        assert_eq!(certificate.verify(), Verification::Failed);
+        certificate.sign(&stake_key);
+        assert_eq!(certificate.verify(), Verification::Success);
+
+        let mut certificate = Certificate {
+            content,
+            signatures: vec![],
+        };
+        certificate.sign(&other_key);
+        assert_eq!(certificate.verify(), Verification::Failed);
+    }
 }
@@ -1,8 +1,11 @@
 use crate::block::ConsensusVersion;
+use crate::fee::LinearFee;
+use crate::leadership::bft::LeaderId;
 use chain_addr::Discrimination;
 use chain_core::mempack::{ReadBuf, ReadError, Readable};
 use chain_core::packer::Codec;
 use chain_core::property;
+use chain_crypto::{bech32::Bech32 as _, PublicKey};
 use num_traits::FromPrimitive;
 use std::fmt::{self, Display, Formatter};
 use std::io::{self, Write};
@@ -12,6 +15,63 @@ use std::str::FromStr;
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub struct Block0Date(pub u64);
 
+/// Number of blocks after which a block is considered stable: the
+/// blockchain cannot switch to a fork starting deeper than this
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct EpochStabilityDepth(pub u32);
+
+/// Maximum size, in bytes, of the contents of a block
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct MaxBlockSize(pub u32);
+
+/// Duration of a slot, in seconds
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct SlotDuration(pub u8);
+
+/// Percentage of the slots of an epoch reserved to the BFT leaders
+/// (the `d` parameter of Genesis Praos, times 100)
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct BootstrapKeySlotsPercentage(pub u8);
+
+/// Allow the creation of accounts without publishing a certificate
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct AllowAccountCreation(pub bool);
+
+/// Percentage of the fees of an epoch going to the treasury
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct TreasuryCut(pub u8);
+
+/// Percentage of the rewards of a stake pool going to its owners, the
+/// rest being shared between the delegators
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct PoolOwnersCut(pub u8);
+
+/// Probability that a slot has a leader (the `f` parameter of Genesis
+/// Praos, the active slots coefficient), in thousandths
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct GenesisPraosParamF(pub u16);
+
+/// Maximum size, in bytes, of the metadata attached to a transaction
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct MaxMetadataSize(pub u16);
+
+/// Maximum number of inputs of a transaction
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct MaxTransactionInputs(pub u8);
+
+/// Maximum number of outputs of a transaction
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct MaxTransactionOutputs(pub u8);
+
+/// Maximum number of signatures carried by the witnesses of a
+/// transaction, each signature of a multisig witness counting as one
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct MaxTransactionWitnesses(pub u8);
+
+/// Maximum size, in bytes, of a transaction and its witnesses
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct MaxTransactionSize(pub u32);
+
 /// Possible errors
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Error {
@@ -45,11 +105,48 @@ pub enum ConfigParam {
     Block0Date(Block0Date),
     Discrimination(Discrimination),
     ConsensusVersion(ConsensusVersion),
+    LinearFee(LinearFee),
+    EpochStabilityDepth(EpochStabilityDepth),
+    MaxBlockSize(MaxBlockSize),
+    SlotDuration(SlotDuration),
+    BootstrapKeySlotsPercentage(BootstrapKeySlotsPercentage),
+    AddBftLeader(LeaderId),
+    AllowAccountCreation(AllowAccountCreation),
+    TreasuryCut(TreasuryCut),
+    PoolOwnersCut(PoolOwnersCut),
+    GenesisPraosParamF(GenesisPraosParamF),
+    MaxMetadataSize(MaxMetadataSize),
+    MaxTransactionInputs(MaxTransactionInputs),
+    MaxTransactionOutputs(MaxTransactionOutputs),
+    MaxTransactionWitnesses(MaxTransactionWitnesses),
+    MaxTransactionSize(MaxTransactionSize),
+    /// a parameter of a tag unknown to this version of the software, only
+    /// read in `ReadMode::Lenient` and serialized back as it was read
+    Unknown {
+        tag: u16,
+        payload: Vec<u8>,
+    },
+}
+
+/// How the tags unknown to this version of the software are read
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ReadMode {
+    /// an unknown tag is an error
+    Strict,
+    /// an unknown tag is kept as `ConfigParam::Unknown`, so the parameters
+    /// added by newer versions can still be parsed and relayed
+    Lenient,
 }
 
 impl Readable for ConfigParam {
     fn read<'a>(buf: &mut ReadBuf<'a>) -> Result<Self, ReadError> {
-        let taglen = TagLen(buf.get_u16()?);
+        ConfigParam::read_with_mode(buf, ReadMode::Strict)
+    }
+}
+
+impl ConfigParam {
+    pub fn read_with_mode<'a>(buf: &mut ReadBuf<'a>, mode: ReadMode) -> Result<Self, ReadError> {
+        let taglen = TagLen::read(buf)?;
         let bytes = buf.get_slice(taglen.get_len())?;
         match taglen.get_tag() {
             Block0Date::TAG => Block0Date::from_payload(bytes).map(ConfigParam::Block0Date),
@@ -59,6 +156,43 @@ impl Readable for ConfigParam {
             ConsensusVersion::TAG => {
                 ConsensusVersion::from_payload(bytes).map(ConfigParam::ConsensusVersion)
             }
+            LinearFee::TAG => LinearFee::from_payload(bytes).map(ConfigParam::LinearFee),
+            EpochStabilityDepth::TAG => {
+                EpochStabilityDepth::from_payload(bytes).map(ConfigParam::EpochStabilityDepth)
+            }
+            MaxBlockSize::TAG => MaxBlockSize::from_payload(bytes).map(ConfigParam::MaxBlockSize),
+            SlotDuration::TAG => SlotDuration::from_payload(bytes).map(ConfigParam::SlotDuration),
+            BootstrapKeySlotsPercentage::TAG => BootstrapKeySlotsPercentage::from_payload(bytes)
+                .map(ConfigParam::BootstrapKeySlotsPercentage),
+            LeaderId::TAG => LeaderId::from_payload(bytes).map(ConfigParam::AddBftLeader),
+            AllowAccountCreation::TAG => {
+                AllowAccountCreation::from_payload(bytes).map(ConfigParam::AllowAccountCreation)
+            }
+            TreasuryCut::TAG => TreasuryCut::from_payload(bytes).map(ConfigParam::TreasuryCut),
+            PoolOwnersCut::TAG => {
+                PoolOwnersCut::from_payload(bytes).map(ConfigParam::PoolOwnersCut)
+            }
+            GenesisPraosParamF::TAG => {
+                GenesisPraosParamF::from_payload(bytes).map(ConfigParam::GenesisPraosParamF)
+            }
+            MaxMetadataSize::TAG => {
+                MaxMetadataSize::from_payload(bytes).map(ConfigParam::MaxMetadataSize)
+            }
+            MaxTransactionInputs::TAG => {
+                MaxTransactionInputs::from_payload(bytes).map(ConfigParam::MaxTransactionInputs)
+            }
+            MaxTransactionOutputs::TAG => {
+                MaxTransactionOutputs::from_payload(bytes).map(ConfigParam::MaxTransactionOutputs)
+            }
+            MaxTransactionWitnesses::TAG => MaxTransactionWitnesses::from_payload(bytes)
+                .map(ConfigParam::MaxTransactionWitnesses),
+            MaxTransactionSize::TAG => {
+                MaxTransactionSize::from_payload(bytes).map(ConfigParam::MaxTransactionSize)
+            }
+            Tag(tag) if mode == ReadMode::Lenient => Ok(ConfigParam::Unknown {
+                tag,
+                payload: bytes.to_vec(),
+            }),
             _ => Err(Error::InvalidTag),
         }
         .map_err(Into::into)
@@ -73,6 +207,32 @@ impl property::Serialize for ConfigParam {
             ConfigParam::Block0Date(data) => (Block0Date::TAG, data.to_payload()),
             ConfigParam::Discrimination(data) => (Discrimination::TAG, data.to_payload()),
             ConfigParam::ConsensusVersion(data) => (ConsensusVersion::TAG, data.to_payload()),
+            ConfigParam::LinearFee(data) => (LinearFee::TAG, data.to_payload()),
+            ConfigParam::EpochStabilityDepth(data) => (EpochStabilityDepth::TAG, data.to_payload()),
+            ConfigParam::MaxBlockSize(data) => (MaxBlockSize::TAG, data.to_payload()),
+            ConfigParam::SlotDuration(data) => (SlotDuration::TAG, data.to_payload()),
+            ConfigParam::BootstrapKeySlotsPercentage(data) => {
+                (BootstrapKeySlotsPercentage::TAG, data.to_payload())
+            }
+            ConfigParam::AddBftLeader(data) => (LeaderId::TAG, data.to_payload()),
+            ConfigParam::AllowAccountCreation(data) => {
+                (AllowAccountCreation::TAG, data.to_payload())
+            }
+            ConfigParam::TreasuryCut(data) => (TreasuryCut::TAG, data.to_payload()),
+            ConfigParam::PoolOwnersCut(data) => (PoolOwnersCut::TAG, data.to_payload()),
+            ConfigParam::GenesisPraosParamF(data) => (GenesisPraosParamF::TAG, data.to_payload()),
+            ConfigParam::MaxMetadataSize(data) => (MaxMetadataSize::TAG, data.to_payload()),
+            ConfigParam::MaxTransactionInputs(data) => {
+                (MaxTransactionInputs::TAG, data.to_payload())
+            }
+            ConfigParam::MaxTransactionOutputs(data) => {
+                (MaxTransactionOutputs::TAG, data.to_payload())
+            }
+            ConfigParam::MaxTransactionWitnesses(data) => {
+                (MaxTransactionWitnesses::TAG, data.to_payload())
+            }
+            ConfigParam::MaxTransactionSize(data) => (MaxTransactionSize::TAG, data.to_payload()),
+            ConfigParam::Unknown { tag, payload } => (Tag::new(*tag), payload.clone()),
         };
         let taglen = TagLen::new(tag, bytes.len()).ok_or_else(|| {
             io::Error::new(
@@ -81,7 +241,7 @@ impl property::Serialize for ConfigParam {
             )
         })?;
         let mut codec = Codec::from(writer);
-        codec.put_u16(taglen.0)?;
+        taglen.write(&mut codec)?;
         codec.write_all(&bytes)
     }
 }
@@ -89,7 +249,9 @@ impl property::Serialize for ConfigParam {
 #[cfg(feature = "generic-serialization")]
 mod serde_impl {
     use super::*;
-    use serde::{de::Error as _, Deserialize, Deserializer, Serialize, Serializer};
+    use serde::{
+        de::Error as _, ser::Error as _, Deserialize, Deserializer, Serialize, Serializer,
+    };
 
     impl<'de> Deserialize<'de> for ConfigParam {
         fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
@@ -102,6 +264,44 @@ mod serde_impl {
                 ConsensusVersion::NAME => {
                     ConsensusVersion::from_cfg_str(&value).map(ConfigParam::ConsensusVersion)
                 }
+                LinearFee::NAME => LinearFee::from_cfg_str(&value).map(ConfigParam::LinearFee),
+                EpochStabilityDepth::NAME => {
+                    EpochStabilityDepth::from_cfg_str(&value).map(ConfigParam::EpochStabilityDepth)
+                }
+                MaxBlockSize::NAME => {
+                    MaxBlockSize::from_cfg_str(&value).map(ConfigParam::MaxBlockSize)
+                }
+                SlotDuration::NAME => {
+                    SlotDuration::from_cfg_str(&value).map(ConfigParam::SlotDuration)
+                }
+                BootstrapKeySlotsPercentage::NAME => {
+                    BootstrapKeySlotsPercentage::from_cfg_str(&value)
+                        .map(ConfigParam::BootstrapKeySlotsPercentage)
+                }
+                LeaderId::NAME => LeaderId::from_cfg_str(&value).map(ConfigParam::AddBftLeader),
+                AllowAccountCreation::NAME => AllowAccountCreation::from_cfg_str(&value)
+                    .map(ConfigParam::AllowAccountCreation),
+                TreasuryCut::NAME => {
+                    TreasuryCut::from_cfg_str(&value).map(ConfigParam::TreasuryCut)
+                }
+                PoolOwnersCut::NAME => {
+                    PoolOwnersCut::from_cfg_str(&value).map(ConfigParam::PoolOwnersCut)
+                }
+                GenesisPraosParamF::NAME => {
+                    GenesisPraosParamF::from_cfg_str(&value).map(ConfigParam::GenesisPraosParamF)
+                }
+                MaxMetadataSize::NAME => {
+                    MaxMetadataSize::from_cfg_str(&value).map(ConfigParam::MaxMetadataSize)
+                }
+                MaxTransactionInputs::NAME => MaxTransactionInputs::from_cfg_str(&value)
+                    .map(ConfigParam::MaxTransactionInputs),
+                MaxTransactionOutputs::NAME => MaxTransactionOutputs::from_cfg_str(&value)
+                    .map(ConfigParam::MaxTransactionOutputs),
+                MaxTransactionWitnesses::NAME => MaxTransactionWitnesses::from_cfg_str(&value)
+                    .map(ConfigParam::MaxTransactionWitnesses),
+                MaxTransactionSize::NAME => {
+                    MaxTransactionSize::from_cfg_str(&value).map(ConfigParam::MaxTransactionSize)
+                }
                 _ => Err(Error::InvalidTag),
             }
             .map_err(D::Error::custom)
@@ -116,12 +316,233 @@ mod serde_impl {
                 ConfigParam::ConsensusVersion(data) => {
                     (ConsensusVersion::NAME, data.to_cfg_string())
                 }
+                ConfigParam::LinearFee(data) => (LinearFee::NAME, data.to_cfg_string()),
+                ConfigParam::EpochStabilityDepth(data) => {
+                    (EpochStabilityDepth::NAME, data.to_cfg_string())
+                }
+                ConfigParam::MaxBlockSize(data) => (MaxBlockSize::NAME, data.to_cfg_string()),
+                ConfigParam::SlotDuration(data) => (SlotDuration::NAME, data.to_cfg_string()),
+                ConfigParam::BootstrapKeySlotsPercentage(data) => {
+                    (BootstrapKeySlotsPercentage::NAME, data.to_cfg_string())
+                }
+                ConfigParam::AddBftLeader(data) => (LeaderId::NAME, data.to_cfg_string()),
+                ConfigParam::AllowAccountCreation(data) => {
+                    (AllowAccountCreation::NAME, data.to_cfg_string())
+                }
+                ConfigParam::TreasuryCut(data) => (TreasuryCut::NAME, data.to_cfg_string()),
+                ConfigParam::PoolOwnersCut(data) => (PoolOwnersCut::NAME, data.to_cfg_string()),
+                ConfigParam::GenesisPraosParamF(data) => {
+                    (GenesisPraosParamF::NAME, data.to_cfg_string())
+                }
+                ConfigParam::MaxMetadataSize(data) => (MaxMetadataSize::NAME, data.to_cfg_string()),
+                ConfigParam::MaxTransactionInputs(data) => {
+                    (MaxTransactionInputs::NAME, data.to_cfg_string())
+                }
+                ConfigParam::MaxTransactionOutputs(data) => {
+                    (MaxTransactionOutputs::NAME, data.to_cfg_string())
+                }
+                ConfigParam::MaxTransactionWitnesses(data) => {
+                    (MaxTransactionWitnesses::NAME, data.to_cfg_string())
+                }
+                ConfigParam::MaxTransactionSize(data) => {
+                    (MaxTransactionSize::NAME, data.to_cfg_string())
+                }
+                ConfigParam::Unknown { tag, .. } => {
+                    return Err(S::Error::custom(format!(
+                        "config parameter of unknown tag {} has no name",
+                        tag
+                    )))
+                }
             }
             .serialize(serializer)
         }
     }
 }
 
+impl ConfigParam {
+    /// the name of the parameter in the configuration files
+    pub fn name(&self) -> &'static str {
+        match self {
+            ConfigParam::Block0Date(_) => Block0Date::NAME,
+            ConfigParam::Discrimination(_) => Discrimination::NAME,
+            ConfigParam::ConsensusVersion(_) => ConsensusVersion::NAME,
+            ConfigParam::LinearFee(_) => LinearFee::NAME,
+            ConfigParam::EpochStabilityDepth(_) => EpochStabilityDepth::NAME,
+            ConfigParam::MaxBlockSize(_) => MaxBlockSize::NAME,
+            ConfigParam::SlotDuration(_) => SlotDuration::NAME,
+            ConfigParam::BootstrapKeySlotsPercentage(_) => BootstrapKeySlotsPercentage::NAME,
+            ConfigParam::AddBftLeader(_) => LeaderId::NAME,
+            ConfigParam::AllowAccountCreation(_) => AllowAccountCreation::NAME,
+            ConfigParam::TreasuryCut(_) => TreasuryCut::NAME,
+            ConfigParam::PoolOwnersCut(_) => PoolOwnersCut::NAME,
+            ConfigParam::GenesisPraosParamF(_) => GenesisPraosParamF::NAME,
+            ConfigParam::MaxMetadataSize(_) => MaxMetadataSize::NAME,
+            ConfigParam::MaxTransactionInputs(_) => MaxTransactionInputs::NAME,
+            ConfigParam::MaxTransactionOutputs(_) => MaxTransactionOutputs::NAME,
+            ConfigParam::MaxTransactionWitnesses(_) => MaxTransactionWitnesses::NAME,
+            ConfigParam::MaxTransactionSize(_) => MaxTransactionSize::NAME,
+            ConfigParam::Unknown { .. } => "unknown",
+        }
+    }
+}
+
+/// A rule of the configuration parameters broken by a `ConfigParams`
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ConfigParamsViolation {
+    /// the parameter of the given name is set more than once
+    Duplicated(&'static str),
+    /// the BFT leader is added more than once
+    DuplicatedBftLeader(LeaderId),
+    /// the duration of the slots is zero
+    ZeroSlotDuration,
+    /// the parameter of the given name only applies to Genesis Praos, but
+    /// the consensus is another one
+    GenesisPraosParamWithoutGenesisPraos(&'static str),
+    /// the parameter of the given tag is unknown to this version of the
+    /// software, so its effect cannot be applied
+    Unknown(u16),
+}
+
+/// All the violations of the rules found in a collection of parameters
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ConfigParamsError(pub Vec<ConfigParamsViolation>);
+
+impl Display for ConfigParamsViolation {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ConfigParamsViolation::Duplicated(name) => {
+                write!(f, "config parameter '{}' is set more than once", name)
+            }
+            ConfigParamsViolation::DuplicatedBftLeader(leader) => write!(
+                f,
+                "BFT leader '{}' is added more than once",
+                leader.to_cfg_string()
+            ),
+            ConfigParamsViolation::ZeroSlotDuration => {
+                write!(f, "config parameter '{}' is zero", SlotDuration::NAME)
+            }
+            ConfigParamsViolation::GenesisPraosParamWithoutGenesisPraos(name) => write!(
+                f,
+                "config parameter '{}' is only valid with the genesis praos consensus",
+                name
+            ),
+            ConfigParamsViolation::Unknown(tag) => {
+                write!(f, "config parameter of tag {} is unknown", tag)
+            }
+        }
+    }
+}
+
+impl Display for ConfigParamsError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "Invalid config parameters")?;
+        for (i, violation) in self.0.iter().enumerate() {
+            write!(f, "{} {}", if i == 0 { ":" } else { "," }, violation)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigParamsError {}
+
+/// Collection of configuration parameters, each one set at most once
+///
+/// Only `AddBftLeader` may be given several times, with different leaders.
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct ConfigParams(Vec<ConfigParam>);
+
+impl ConfigParams {
+    pub fn new() -> Self {
+        ConfigParams(Vec::new())
+    }
+
+    /// collect the parameters, reporting all the duplicated parameters and
+    /// the inconsistencies between them (see `validate`)
+    pub fn from_params<'a, I>(params: I) -> Result<Self, ConfigParamsError>
+    where
+        I: IntoIterator<Item = &'a ConfigParam>,
+    {
+        let mut config_params = ConfigParams::new();
+        let mut violations = Vec::new();
+        for param in params {
+            if let Err(violation) = config_params.push(param.clone()) {
+                violations.push(violation);
+            }
+        }
+        if let Err(ConfigParamsError(inconsistencies)) = config_params.validate() {
+            violations.extend(inconsistencies);
+        }
+        if violations.is_empty() {
+            Ok(config_params)
+        } else {
+            Err(ConfigParamsError(violations))
+        }
+    }
+
+    /// add the parameter, unless it is already set
+    pub fn push(&mut self, param: ConfigParam) -> Result<(), ConfigParamsViolation> {
+        match &param {
+            ConfigParam::AddBftLeader(leader) => {
+                if self.iter().any(|p| p == &param) {
+                    return Err(ConfigParamsViolation::DuplicatedBftLeader(leader.clone()));
+                }
+            }
+            // reported by `validate`, whether duplicated or not
+            ConfigParam::Unknown { .. } => {}
+            _ => {
+                if self.iter().any(|p| p.name() == param.name()) {
+                    return Err(ConfigParamsViolation::Duplicated(param.name()));
+                }
+            }
+        }
+        self.0.push(param);
+        Ok(())
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<ConfigParam> {
+        self.0.iter()
+    }
+
+    pub fn consensus_version(&self) -> Option<ConsensusVersion> {
+        self.iter()
+            .filter_map(|param| match param {
+                ConfigParam::ConsensusVersion(version) => Some(*version),
+                _ => None,
+            })
+            .next()
+    }
+
+    /// check the consistency between the parameters: the slots have a
+    /// duration, the parameters of Genesis Praos are only given with
+    /// the Genesis Praos consensus and all the parameters are known
+    pub fn validate(&self) -> Result<(), ConfigParamsError> {
+        let genesis_praos = self.consensus_version() == Some(ConsensusVersion::GenesisPraos);
+        let violations: Vec<_> = self
+            .iter()
+            .filter_map(|param| match param {
+                ConfigParam::SlotDuration(SlotDuration(0)) => {
+                    Some(ConfigParamsViolation::ZeroSlotDuration)
+                }
+                ConfigParam::BootstrapKeySlotsPercentage(_)
+                | ConfigParam::GenesisPraosParamF(_)
+                    if !genesis_praos =>
+                {
+                    Some(ConfigParamsViolation::GenesisPraosParamWithoutGenesisPraos(
+                        param.name(),
+                    ))
+                }
+                ConfigParam::Unknown { tag, .. } => Some(ConfigParamsViolation::Unknown(*tag)),
+                _ => None,
+            })
+            .collect();
+        if violations.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigParamsError(violations))
+        }
+    }
+}
+
 trait ConfigParamVariant: Clone + Eq + PartialEq {
     const TAG: Tag;
     const NAME: &'static str;
@@ -232,6 +653,398 @@ impl ConfigParamVariant for ConsensusVersion {
     }
 }
 
+impl ConfigParamVariant for LinearFee {
+    const TAG: Tag = Tag::new(4);
+    const NAME: &'static str = "linear-fee";
+
+    fn to_payload(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(24);
+        out.extend_from_slice(&self.constant.to_be_bytes());
+        out.extend_from_slice(&self.coefficient.to_be_bytes());
+        out.extend_from_slice(&self.certificate.to_be_bytes());
+        out
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        if payload.len() != 24 {
+            return Err(Error::SizeInvalid);
+        };
+        let mut bytes = [0u8; 8];
+        let mut values = payload.chunks(8).map(|chunk| {
+            bytes.copy_from_slice(chunk);
+            u64::from_be_bytes(bytes)
+        });
+        let constant = values.next().unwrap();
+        let coefficient = values.next().unwrap();
+        let certificate = values.next().unwrap();
+        Ok(LinearFee::new(constant, coefficient, certificate))
+    }
+
+    /// `constant=<u64>,coefficient=<u64>,certificate=<u64>`
+    fn to_cfg_string(&self) -> String {
+        format!(
+            "constant={},coefficient={},certificate={}",
+            self.constant, self.coefficient, self.certificate
+        )
+    }
+
+    fn from_cfg_str(s: &str) -> Result<Self, Error> {
+        let fields: Vec<&str> = s.split(',').map(str::trim).collect();
+        if fields.len() != 3 {
+            return Err(Error::UnknownString(s.to_string()));
+        }
+        let value = |idx: usize, name: &str| {
+            let field = fields[idx];
+            if field.starts_with(name) && field[name.len()..].starts_with('=') {
+                u64::from_str(&field[name.len() + 1..])
+                    .map_err(|_| Error::UnknownString(s.to_string()))
+            } else {
+                Err(Error::UnknownString(s.to_string()))
+            }
+        };
+        Ok(LinearFee::new(
+            value(0, "constant")?,
+            value(1, "coefficient")?,
+            value(2, "certificate")?,
+        ))
+    }
+}
+
+fn u32_from_payload(payload: &[u8]) -> Result<u32, Error> {
+    if payload.len() != 4 {
+        return Err(Error::SizeInvalid);
+    };
+    let mut bytes = [0u8; 4];
+    bytes.copy_from_slice(payload);
+    Ok(u32::from_be_bytes(bytes))
+}
+
+fn u32_from_cfg_str(s: &str) -> Result<u32, Error> {
+    u32::from_str(s).map_err(|_| Error::UnknownString(s.to_string()))
+}
+
+impl ConfigParamVariant for EpochStabilityDepth {
+    const TAG: Tag = Tag::new(5);
+    const NAME: &'static str = "epoch-stability-depth";
+
+    fn to_payload(&self) -> Vec<u8> {
+        self.0.to_be_bytes().to_vec()
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        u32_from_payload(payload).map(EpochStabilityDepth)
+    }
+
+    fn to_cfg_string(&self) -> String {
+        format!("{}", self.0)
+    }
+
+    fn from_cfg_str(s: &str) -> Result<Self, Error> {
+        u32_from_cfg_str(s).map(EpochStabilityDepth)
+    }
+}
+
+impl ConfigParamVariant for MaxBlockSize {
+    const TAG: Tag = Tag::new(6);
+    const NAME: &'static str = "max-block-size";
+
+    fn to_payload(&self) -> Vec<u8> {
+        self.0.to_be_bytes().to_vec()
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        u32_from_payload(payload).map(MaxBlockSize)
+    }
+
+    fn to_cfg_string(&self) -> String {
+        format!("{}", self.0)
+    }
+
+    fn from_cfg_str(s: &str) -> Result<Self, Error> {
+        u32_from_cfg_str(s).map(MaxBlockSize)
+    }
+}
+
+fn u8_from_payload(payload: &[u8]) -> Result<u8, Error> {
+    if payload.len() != 1 {
+        return Err(Error::SizeInvalid);
+    };
+    Ok(payload[0])
+}
+
+fn u8_from_cfg_str(s: &str) -> Result<u8, Error> {
+    u8::from_str(s).map_err(|_| Error::UnknownString(s.to_string()))
+}
+
+impl ConfigParamVariant for SlotDuration {
+    const TAG: Tag = Tag::new(7);
+    const NAME: &'static str = "slot-duration";
+
+    fn to_payload(&self) -> Vec<u8> {
+        vec![self.0]
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        u8_from_payload(payload).map(SlotDuration)
+    }
+
+    fn to_cfg_string(&self) -> String {
+        format!("{}", self.0)
+    }
+
+    fn from_cfg_str(s: &str) -> Result<Self, Error> {
+        u8_from_cfg_str(s).map(SlotDuration)
+    }
+}
+
+impl ConfigParamVariant for BootstrapKeySlotsPercentage {
+    const TAG: Tag = Tag::new(8);
+    const NAME: &'static str = "bootstrap-key-slots-percentage";
+
+    fn to_payload(&self) -> Vec<u8> {
+        vec![self.0]
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        percentage_from_payload(payload).map(BootstrapKeySlotsPercentage)
+    }
+
+    fn to_cfg_string(&self) -> String {
+        format!("{}", self.0)
+    }
+
+    fn from_cfg_str(s: &str) -> Result<Self, Error> {
+        percentage_from_cfg_str(s).map(BootstrapKeySlotsPercentage)
+    }
+}
+
+impl ConfigParamVariant for LeaderId {
+    const TAG: Tag = Tag::new(9);
+    const NAME: &'static str = "add-bft-leader";
+
+    fn to_payload(&self) -> Vec<u8> {
+        self.as_ref().to_vec()
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        PublicKey::from_binary(payload)
+            .map(LeaderId::from)
+            .map_err(|_| Error::SizeInvalid)
+    }
+
+    fn to_cfg_string(&self) -> String {
+        self.as_public_key().to_bech32_str()
+    }
+
+    fn from_cfg_str(s: &str) -> Result<Self, Error> {
+        PublicKey::try_from_bech32_str(s)
+            .map(LeaderId::from)
+            .map_err(|_| Error::UnknownString(s.to_string()))
+    }
+}
+
+impl ConfigParamVariant for AllowAccountCreation {
+    const TAG: Tag = Tag::new(10);
+    const NAME: &'static str = "allow-account-creation";
+
+    fn to_payload(&self) -> Vec<u8> {
+        vec![if self.0 { 1 } else { 0 }]
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        match u8_from_payload(payload)? {
+            0 => Ok(AllowAccountCreation(false)),
+            1 => Ok(AllowAccountCreation(true)),
+            _ => Err(Error::StructureInvalid),
+        }
+    }
+
+    fn to_cfg_string(&self) -> String {
+        format!("{}", self.0)
+    }
+
+    fn from_cfg_str(s: &str) -> Result<Self, Error> {
+        bool::from_str(s)
+            .map(AllowAccountCreation)
+            .map_err(|_| Error::UnknownString(s.to_string()))
+    }
+}
+
+fn percentage_from_payload(payload: &[u8]) -> Result<u8, Error> {
+    match u8_from_payload(payload)? {
+        percentage if percentage <= 100 => Ok(percentage),
+        _ => Err(Error::StructureInvalid),
+    }
+}
+
+fn percentage_from_cfg_str(s: &str) -> Result<u8, Error> {
+    match u8_from_cfg_str(s)? {
+        percentage if percentage <= 100 => Ok(percentage),
+        _ => Err(Error::UnknownString(s.to_string())),
+    }
+}
+
+impl ConfigParamVariant for TreasuryCut {
+    const TAG: Tag = Tag::new(11);
+    const NAME: &'static str = "treasury-cut";
+
+    fn to_payload(&self) -> Vec<u8> {
+        vec![self.0]
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        percentage_from_payload(payload).map(TreasuryCut)
+    }
+
+    fn to_cfg_string(&self) -> String {
+        format!("{}", self.0)
+    }
+
+    fn from_cfg_str(s: &str) -> Result<Self, Error> {
+        percentage_from_cfg_str(s).map(TreasuryCut)
+    }
+}
+
+impl ConfigParamVariant for PoolOwnersCut {
+    const TAG: Tag = Tag::new(12);
+    const NAME: &'static str = "pool-owners-cut";
+
+    fn to_payload(&self) -> Vec<u8> {
+        vec![self.0]
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        percentage_from_payload(payload).map(PoolOwnersCut)
+    }
+
+    fn to_cfg_string(&self) -> String {
+        format!("{}", self.0)
+    }
+
+    fn from_cfg_str(s: &str) -> Result<Self, Error> {
+        percentage_from_cfg_str(s).map(PoolOwnersCut)
+    }
+}
+
+impl ConfigParamVariant for GenesisPraosParamF {
+    const TAG: Tag = Tag::new(13);
+    const NAME: &'static str = "genesis-praos-param-f";
+
+    fn to_payload(&self) -> Vec<u8> {
+        self.0.to_be_bytes().to_vec()
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        if payload.len() != 2 {
+            return Err(Error::SizeInvalid);
+        };
+        match u16::from_be_bytes([payload[0], payload[1]]) {
+            f @ 1..=1000 => Ok(GenesisPraosParamF(f)),
+            _ => Err(Error::StructureInvalid),
+        }
+    }
+
+    fn to_cfg_string(&self) -> String {
+        format!("{}", self.0)
+    }
+
+    fn from_cfg_str(s: &str) -> Result<Self, Error> {
+        match u16::from_str(s) {
+            Ok(f @ 1..=1000) => Ok(GenesisPraosParamF(f)),
+            _ => Err(Error::UnknownString(s.to_string())),
+        }
+    }
+}
+
+impl ConfigParamVariant for MaxMetadataSize {
+    const TAG: Tag = Tag::new(14);
+    const NAME: &'static str = "max-metadata-size";
+
+    fn to_payload(&self) -> Vec<u8> {
+        self.0.to_be_bytes().to_vec()
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        if payload.len() != 2 {
+            return Err(Error::SizeInvalid);
+        };
+        let size = u16::from_be_bytes([payload[0], payload[1]]);
+        Ok(MaxMetadataSize(size))
+    }
+
+    fn to_cfg_string(&self) -> String {
+        format!("{}", self.0)
+    }
+
+    fn from_cfg_str(s: &str) -> Result<Self, Error> {
+        u16::from_str(s)
+            .map(MaxMetadataSize)
+            .map_err(|_| Error::UnknownString(s.to_string()))
+    }
+}
+
+macro_rules! define_u8_limit {
+    ($name:ident, $tag:expr, $cfg_name:expr) => {
+        impl ConfigParamVariant for $name {
+            const TAG: Tag = Tag::new($tag);
+            const NAME: &'static str = $cfg_name;
+
+            fn to_payload(&self) -> Vec<u8> {
+                vec![self.0]
+            }
+
+            fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+                if payload.len() != 1 {
+                    return Err(Error::SizeInvalid);
+                };
+                Ok($name(payload[0]))
+            }
+
+            fn to_cfg_string(&self) -> String {
+                format!("{}", self.0)
+            }
+
+            fn from_cfg_str(s: &str) -> Result<Self, Error> {
+                u8::from_str(s)
+                    .map($name)
+                    .map_err(|_| Error::UnknownString(s.to_string()))
+            }
+        }
+    };
+}
+
+define_u8_limit!(MaxTransactionInputs, 15, "max-transaction-inputs");
+define_u8_limit!(MaxTransactionOutputs, 16, "max-transaction-outputs");
+define_u8_limit!(MaxTransactionWitnesses, 17, "max-transaction-witnesses");
+
+impl ConfigParamVariant for MaxTransactionSize {
+    const TAG: Tag = Tag::new(18);
+    const NAME: &'static str = "max-transaction-size";
+
+    fn to_payload(&self) -> Vec<u8> {
+        self.0.to_be_bytes().to_vec()
+    }
+
+    fn from_payload(payload: &[u8]) -> Result<Self, Error> {
+        if payload.len() != 4 {
+            return Err(Error::SizeInvalid);
+        };
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(payload);
+        Ok(MaxTransactionSize(u32::from_be_bytes(bytes)))
+    }
+
+    fn to_cfg_string(&self) -> String {
+        format!("{}", self.0)
+    }
+
+    fn from_cfg_str(s: &str) -> Result<Self, Error> {
+        u32::from_str(s)
+            .map(MaxTransactionSize)
+            .map_err(|_| Error::UnknownString(s.to_string()))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 struct Tag(u16);
 
@@ -242,43 +1055,323 @@ impl Tag {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
-struct TagLen(u16);
+/// The tag and the length of the payload of a parameter, serialized as a
+/// `u16` with the tag in the 10 high bits and the length in the 6 low bits.
+/// The payloads of `EXTENDED_LEN` bytes or more, and only them, have
+/// `EXTENDED_LEN` in the 6 low bits, the actual length following as a `u32`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TagLen {
+    tag: Tag,
+    len: usize,
+}
 
-const MAXIMUM_LEN: usize = 64;
+const EXTENDED_LEN: usize = 0b11_1111;
 
 impl TagLen {
     pub fn new(tag: Tag, len: usize) -> Option<Self> {
-        if len < MAXIMUM_LEN {
-            Some(TagLen(tag.0 << 6 | len as u16))
+        if len <= u32::max_value() as usize {
+            Some(TagLen { tag, len })
         } else {
             None
         }
     }
 
     pub fn get_tag(self) -> Tag {
-        Tag::new(self.0 >> 6)
+        self.tag
     }
 
     pub fn get_len(self) -> usize {
-        (self.0 & 0b11_1111) as usize
+        self.len
+    }
+
+    fn is_extended(self) -> bool {
+        self.len >= EXTENDED_LEN
+    }
+
+    fn read<'a>(buf: &mut ReadBuf<'a>) -> Result<Self, ReadError> {
+        let taglen = buf.get_u16()?;
+        let tag = Tag::new(taglen >> 6);
+        let len = match (taglen & 0b11_1111) as usize {
+            EXTENDED_LEN => match buf.get_u32()? as usize {
+                // the short form is the only encoding of the short lengths
+                len if len < EXTENDED_LEN => {
+                    return Err(ReadError::StructureInvalid(format!(
+                        "non canonical extended length {} of config param tag {}",
+                        len, tag.0
+                    )))
+                }
+                len => len,
+            },
+            len => len,
+        };
+        Ok(TagLen { tag, len })
+    }
+
+    fn write<W: Write>(self, codec: &mut Codec<W>) -> io::Result<()> {
+        if self.is_extended() {
+            codec.put_u16(self.tag.0 << 6 | EXTENDED_LEN as u16)?;
+            codec.put_u32(self.len as u32)
+        } else {
+            codec.put_u16(self.tag.0 << 6 | self.len as u16)
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use chain_core::{mempack::read_from_raw, property::Serialize as _};
     use quickcheck::{Arbitrary, Gen, TestResult};
 
+    #[test]
+    fn golden_config_params() {
+        for vector in test_vectors::config_params() {
+            let expected = match vector.name.as_str() {
+                Block0Date::NAME => {
+                    Block0Date::from_cfg_str(&vector.value).map(ConfigParam::Block0Date)
+                }
+                Discrimination::NAME => {
+                    Discrimination::from_cfg_str(&vector.value).map(ConfigParam::Discrimination)
+                }
+                ConsensusVersion::NAME => {
+                    ConsensusVersion::from_cfg_str(&vector.value).map(ConfigParam::ConsensusVersion)
+                }
+                LinearFee::NAME => {
+                    LinearFee::from_cfg_str(&vector.value).map(ConfigParam::LinearFee)
+                }
+                EpochStabilityDepth::NAME => EpochStabilityDepth::from_cfg_str(&vector.value)
+                    .map(ConfigParam::EpochStabilityDepth),
+                MaxBlockSize::NAME => {
+                    MaxBlockSize::from_cfg_str(&vector.value).map(ConfigParam::MaxBlockSize)
+                }
+                SlotDuration::NAME => {
+                    SlotDuration::from_cfg_str(&vector.value).map(ConfigParam::SlotDuration)
+                }
+                BootstrapKeySlotsPercentage::NAME => {
+                    BootstrapKeySlotsPercentage::from_cfg_str(&vector.value)
+                        .map(ConfigParam::BootstrapKeySlotsPercentage)
+                }
+                LeaderId::NAME => {
+                    LeaderId::from_cfg_str(&vector.value).map(ConfigParam::AddBftLeader)
+                }
+                AllowAccountCreation::NAME => AllowAccountCreation::from_cfg_str(&vector.value)
+                    .map(ConfigParam::AllowAccountCreation),
+                TreasuryCut::NAME => {
+                    TreasuryCut::from_cfg_str(&vector.value).map(ConfigParam::TreasuryCut)
+                }
+                PoolOwnersCut::NAME => {
+                    PoolOwnersCut::from_cfg_str(&vector.value).map(ConfigParam::PoolOwnersCut)
+                }
+                GenesisPraosParamF::NAME => GenesisPraosParamF::from_cfg_str(&vector.value)
+                    .map(ConfigParam::GenesisPraosParamF),
+                name => panic!("unknown config parameter {}", name),
+            }
+            .unwrap();
+            let param = read_from_raw::<ConfigParam>(&vector.bytes).unwrap();
+            assert_eq!(param, expected, "{} {}", vector.name, vector.value);
+            assert_eq!(
+                param.serialize_as_vec().unwrap(),
+                vector.bytes,
+                "{} {}",
+                vector.name,
+                vector.value
+            );
+        }
+    }
+
+    #[test]
+    fn config_params_violations() {
+        let leader = LeaderId::from(
+            crate::leadership::bft::SigningKey::generate(&mut rand::thread_rng()).to_public(),
+        );
+        let params = vec![
+            ConfigParam::ConsensusVersion(ConsensusVersion::Bft),
+            ConfigParam::SlotDuration(SlotDuration(0)),
+            ConfigParam::GenesisPraosParamF(GenesisPraosParamF(100)),
+            ConfigParam::Discrimination(Discrimination::Test),
+            ConfigParam::Discrimination(Discrimination::Production),
+        ];
+        assert_eq!(
+            ConfigParams::from_params(&params),
+            Err(ConfigParamsError(vec![
+                ConfigParamsViolation::Duplicated(Discrimination::NAME),
+                ConfigParamsViolation::ZeroSlotDuration,
+                ConfigParamsViolation::GenesisPraosParamWithoutGenesisPraos(
+                    GenesisPraosParamF::NAME
+                ),
+            ]))
+        );
+
+        let mut config_params = ConfigParams::from_params(&params[..1]).unwrap();
+        config_params
+            .push(ConfigParam::AddBftLeader(leader.clone()))
+            .unwrap();
+        assert_eq!(
+            config_params.push(ConfigParam::AddBftLeader(leader.clone())),
+            Err(ConfigParamsViolation::DuplicatedBftLeader(leader))
+        );
+        assert_eq!(
+            config_params.consensus_version(),
+            Some(ConsensusVersion::Bft)
+        );
+    }
+
+    #[test]
+    fn unknown_tag_lenient_read() {
+        // tag 1000, payload of 3 bytes
+        let bytes = [(1000u16 << 6 | 3).to_be_bytes().to_vec(), vec![1, 2, 3]].concat();
+        assert!(read_from_raw::<ConfigParam>(&bytes).is_err());
+
+        let param =
+            ConfigParam::read_with_mode(&mut ReadBuf::from(&bytes), ReadMode::Lenient).unwrap();
+        assert_eq!(
+            param,
+            ConfigParam::Unknown {
+                tag: 1000,
+                payload: vec![1, 2, 3],
+            }
+        );
+        assert_eq!(param.serialize_as_vec().unwrap(), bytes);
+        assert_eq!(
+            ConfigParams::from_params(&[param]),
+            Err(ConfigParamsError(vec![ConfigParamsViolation::Unknown(
+                1000
+            )]))
+        );
+
+        // the known tags are still checked
+        let bytes = [
+            (Discrimination::TAG.0 << 6 | 1).to_be_bytes().to_vec(),
+            vec![9],
+        ]
+        .concat();
+        assert!(
+            ConfigParam::read_with_mode(&mut ReadBuf::from(&bytes), ReadMode::Lenient).is_err()
+        );
+    }
+
+    #[test]
+    fn extended_payload_length() {
+        for len in &[0usize, 1, 62, 63, 64, 300, 70_000] {
+            let param = ConfigParam::Unknown {
+                tag: 1000,
+                payload: vec![0xab; *len],
+            };
+            let bytes = param.serialize_as_vec().unwrap();
+            if *len < EXTENDED_LEN {
+                assert_eq!(bytes[..2], (1000u16 << 6 | *len as u16).to_be_bytes());
+                assert_eq!(bytes.len(), 2 + len);
+            } else {
+                assert_eq!(bytes[..2], (1000u16 << 6 | 0b11_1111).to_be_bytes());
+                assert_eq!(bytes[2..6], (*len as u32).to_be_bytes());
+                assert_eq!(bytes.len(), 6 + len);
+            }
+            let decoded =
+                ConfigParam::read_with_mode(&mut ReadBuf::from(&bytes), ReadMode::Lenient).unwrap();
+            assert_eq!(decoded, param);
+        }
+
+        // the extended length is truncated
+        let bytes = [(1000u16 << 6 | 0b11_1111).to_be_bytes(), [0, 0]].concat();
+        assert!(
+            ConfigParam::read_with_mode(&mut ReadBuf::from(&bytes), ReadMode::Lenient).is_err()
+        );
+    }
+
+    #[test]
+    fn percentage_out_of_range() {
+        assert_eq!(
+            TreasuryCut::from_payload(&[101]),
+            Err(Error::StructureInvalid)
+        );
+        assert!(PoolOwnersCut::from_cfg_str("101").is_err());
+        assert_eq!(PoolOwnersCut::from_cfg_str("100"), Ok(PoolOwnersCut(100)));
+    }
+
+    #[test]
+    fn param_f_out_of_range() {
+        assert_eq!(
+            GenesisPraosParamF::from_payload(&[0, 0]),
+            Err(Error::StructureInvalid)
+        );
+        assert_eq!(
+            GenesisPraosParamF::from_payload(&[0x03, 0xe9]),
+            Err(Error::StructureInvalid)
+        );
+        assert!(GenesisPraosParamF::from_cfg_str("0").is_err());
+        assert_eq!(
+            GenesisPraosParamF::from_cfg_str("1000"),
+            Ok(GenesisPraosParamF(1000))
+        );
+    }
+
+    #[test]
+    fn linear_fee_cfg_string() {
+        let fee = LinearFee::new(10, 2, 100);
+        let s = fee.to_cfg_string();
+        assert_eq!(s, "constant=10,coefficient=2,certificate=100");
+        assert_eq!(LinearFee::from_cfg_str(&s), Ok(fee));
+        assert!(LinearFee::from_cfg_str("constant=10,coefficient=2").is_err());
+        assert!(LinearFee::from_cfg_str("coefficient=2,constant=10,certificate=100").is_err());
+        assert!(LinearFee::from_cfg_str("constant=10,coefficient=2,certificate=100,x=1").is_err());
+    }
+
     quickcheck! {
-        fn tag_len_computation_correct(tag: Tag, len: usize) -> TestResult {
-            let len = len % MAXIMUM_LEN;
+        fn config_param_serialize_readable(param: ConfigParam) -> TestResult {
+            let bytes = param.serialize_as_vec().unwrap();
+            let decoded = read_from_raw::<ConfigParam>(&bytes).unwrap();
+            TestResult::from_bool(param == decoded)
+        }
+
+        fn config_param_readable_truncations(param: ConfigParam) -> TestResult {
+            chain_core::property::testing::readable_truncations(param)
+        }
+
+        fn linear_fee_cfg_string_roundtrip(fee: LinearFee) -> TestResult {
+            let decoded = LinearFee::from_cfg_str(&fee.to_cfg_string()).unwrap();
+            TestResult::from_bool(fee == decoded)
+        }
+
+        fn tag_len_computation_correct(tag: Tag, len: u32, short: bool) -> TestResult {
+            let len = if short {
+                len as usize % EXTENDED_LEN
+            } else {
+                len as usize
+            };
             let tag_len = TagLen::new(tag, len).unwrap();
 
             assert_eq!(tag, tag_len.get_tag(), "Invalid tag");
             assert_eq!(len, tag_len.get_len(), "Invalid len");
-            TestResult::passed()
+
+            let mut codec = Codec::from(Vec::new());
+            tag_len.write(&mut codec).unwrap();
+            let bytes = codec.into_inner();
+            let expected_size = if len < EXTENDED_LEN { 2 } else { 6 };
+            assert_eq!(bytes.len(), expected_size, "Invalid size");
+            let decoded = TagLen::read(&mut ReadBuf::from(&bytes)).unwrap();
+            TestResult::from_bool(decoded == tag_len)
+        }
+    }
+
+    #[test]
+    fn tag_len_non_canonical_extended_len() {
+        let tag = Tag::new(3);
+        let extended = |len: u32| {
+            let mut codec = Codec::from(Vec::new());
+            codec.put_u16(tag.0 << 6 | EXTENDED_LEN as u16).unwrap();
+            codec.put_u32(len).unwrap();
+            codec.into_inner()
+        };
+
+        for len in &[0, 1, EXTENDED_LEN as u32 - 1] {
+            match TagLen::read(&mut ReadBuf::from(&extended(*len))) {
+                Err(ReadError::StructureInvalid(_)) => {}
+                result => panic!("non canonical length {} accepted: {:?}", len, result),
+            }
         }
+
+        let decoded = TagLen::read(&mut ReadBuf::from(&extended(EXTENDED_LEN as u32))).unwrap();
+        assert_eq!(decoded, TagLen::new(tag, EXTENDED_LEN).unwrap());
     }
 
     impl Arbitrary for Tag {
@@ -293,12 +1386,115 @@ mod test {
         }
     }
 
+    impl Arbitrary for EpochStabilityDepth {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            EpochStabilityDepth(Arbitrary::arbitrary(g))
+        }
+    }
+
+    impl Arbitrary for MaxBlockSize {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            MaxBlockSize(Arbitrary::arbitrary(g))
+        }
+    }
+
+    impl Arbitrary for SlotDuration {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            SlotDuration(Arbitrary::arbitrary(g))
+        }
+    }
+
+    impl Arbitrary for BootstrapKeySlotsPercentage {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            BootstrapKeySlotsPercentage(u8::arbitrary(g) % 101)
+        }
+    }
+
+    impl Arbitrary for AllowAccountCreation {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            AllowAccountCreation(Arbitrary::arbitrary(g))
+        }
+    }
+
+    impl Arbitrary for TreasuryCut {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            TreasuryCut(u8::arbitrary(g) % 101)
+        }
+    }
+
+    impl Arbitrary for PoolOwnersCut {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            PoolOwnersCut(u8::arbitrary(g) % 101)
+        }
+    }
+
+    impl Arbitrary for GenesisPraosParamF {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            GenesisPraosParamF(1 + u16::arbitrary(g) % 1000)
+        }
+    }
+
+    impl Arbitrary for MaxMetadataSize {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            MaxMetadataSize(Arbitrary::arbitrary(g))
+        }
+    }
+
+    impl Arbitrary for MaxTransactionInputs {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            MaxTransactionInputs(Arbitrary::arbitrary(g))
+        }
+    }
+
+    impl Arbitrary for MaxTransactionOutputs {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            MaxTransactionOutputs(Arbitrary::arbitrary(g))
+        }
+    }
+
+    impl Arbitrary for MaxTransactionWitnesses {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            MaxTransactionWitnesses(Arbitrary::arbitrary(g))
+        }
+    }
+
+    impl Arbitrary for MaxTransactionSize {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            MaxTransactionSize(Arbitrary::arbitrary(g))
+        }
+    }
+
+    impl Arbitrary for LinearFee {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            LinearFee::new(
+                Arbitrary::arbitrary(g),
+                Arbitrary::arbitrary(g),
+                Arbitrary::arbitrary(g),
+            )
+        }
+    }
+
     impl Arbitrary for ConfigParam {
         fn arbitrary<G: Gen>(g: &mut G) -> Self {
-            match u8::arbitrary(g) % 3 {
+            match u8::arbitrary(g) % 18 {
                 0 => ConfigParam::Block0Date(Arbitrary::arbitrary(g)),
                 1 => ConfigParam::Discrimination(Arbitrary::arbitrary(g)),
                 2 => ConfigParam::ConsensusVersion(Arbitrary::arbitrary(g)),
+                3 => ConfigParam::LinearFee(Arbitrary::arbitrary(g)),
+                4 => ConfigParam::EpochStabilityDepth(Arbitrary::arbitrary(g)),
+                5 => ConfigParam::MaxBlockSize(Arbitrary::arbitrary(g)),
+                6 => ConfigParam::SlotDuration(Arbitrary::arbitrary(g)),
+                7 => ConfigParam::BootstrapKeySlotsPercentage(Arbitrary::arbitrary(g)),
+                8 => ConfigParam::AddBftLeader(Arbitrary::arbitrary(g)),
+                9 => ConfigParam::AllowAccountCreation(Arbitrary::arbitrary(g)),
+                10 => ConfigParam::TreasuryCut(Arbitrary::arbitrary(g)),
+                11 => ConfigParam::PoolOwnersCut(Arbitrary::arbitrary(g)),
+                12 => ConfigParam::GenesisPraosParamF(Arbitrary::arbitrary(g)),
+                13 => ConfigParam::MaxMetadataSize(Arbitrary::arbitrary(g)),
+                14 => ConfigParam::MaxTransactionInputs(Arbitrary::arbitrary(g)),
+                15 => ConfigParam::MaxTransactionOutputs(Arbitrary::arbitrary(g)),
+                16 => ConfigParam::MaxTransactionWitnesses(Arbitrary::arbitrary(g)),
+                17 => ConfigParam::MaxTransactionSize(Arbitrary::arbitrary(g)),
                 _ => unreachable!(),
             }
         }
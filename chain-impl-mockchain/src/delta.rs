@@ -0,0 +1,323 @@
+//! Differences between two states of the ledger.
+//!
+//! A `LedgerDelta` lists the UTxOs created and spent and the accounts
+//! changed between two states, e.g. the states before and after a block.
+//! Light clients and explorers can follow the chain by subscribing to the
+//! deltas instead of re-deriving them from the raw blocks.
+//!
+//! The serialization follows the conventions of the `snapshot` module: the
+//! integers are big endian, the collections are prefixed by their number of
+//! elements as a `u32`.
+
+use crate::account::{self, SpendingCounter};
+use crate::block::ChainLength;
+use crate::key::{deserialize_public_key, serialize_public_key};
+use crate::ledger::Ledger;
+use crate::snapshot::{get_len, put_len, read_old_address, write_old_address};
+use crate::transaction::{Output, TransactionId, TransactionIndex};
+use crate::value::Value;
+use crate::{legacy, utxo};
+use chain_addr::Address;
+use chain_core::mempack::{ReadBuf, ReadError, Readable};
+use chain_core::packer::Codec;
+use chain_core::property;
+use chain_crypto::PublicKey;
+use std::io::Write;
+
+/// an unspent output created or spent between the two states
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UtxoChange<A> {
+    pub transaction_id: TransactionId,
+    pub output_index: TransactionIndex,
+    pub output: Output<A>,
+}
+
+/// an account whose state differs between the two states
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccountChange {
+    pub identifier: account::Identifier,
+    /// the state of the account in the old ledger, `None` if it did not exist
+    pub old: Option<account::State>,
+    /// the state of the account in the new ledger, `None` if it was removed
+    pub new: Option<account::State>,
+}
+
+/// the changes from one state of the ledger to another, see `Ledger::diff`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LedgerDelta {
+    pub old_chain_length: ChainLength,
+    pub new_chain_length: ChainLength,
+    pub created_utxos: Vec<UtxoChange<Address>>,
+    pub spent_utxos: Vec<UtxoChange<Address>>,
+    /// the legacy UTxOs are only created by block0, so they are only spent
+    pub spent_old_utxos: Vec<UtxoChange<legacy::OldAddress>>,
+    pub accounts: Vec<AccountChange>,
+}
+
+impl LedgerDelta {
+    /// no UTxO nor account changed
+    pub fn is_empty(&self) -> bool {
+        self.created_utxos.is_empty()
+            && self.spent_utxos.is_empty()
+            && self.spent_old_utxos.is_empty()
+            && self.accounts.is_empty()
+    }
+}
+
+/// the entries of `from` which are not in `to`
+fn missing_utxos<A: Clone>(from: &utxo::Ledger<A>, to: &utxo::Ledger<A>) -> Vec<UtxoChange<A>> {
+    from.iter()
+        .filter(|entry| to.get(&entry.transaction_id, &entry.output_index).is_none())
+        .map(|entry| UtxoChange {
+            transaction_id: entry.transaction_id,
+            output_index: entry.output_index,
+            output: entry.output.clone(),
+        })
+        .collect()
+}
+
+impl Ledger {
+    /// the UTxOs created and spent and the accounts changed from the `old`
+    /// state of the ledger to the `new` one.
+    ///
+    /// The ledgers are compared entry by entry, the cost is proportional to
+    /// the number of UTxOs and accounts of the two states.
+    pub fn diff(old: &Ledger, new: &Ledger) -> LedgerDelta {
+        let mut accounts: Vec<AccountChange> = new
+            .accounts
+            .iter()
+            .filter_map(|(identifier, state)| {
+                let old_state = old.accounts.get_state(identifier).ok();
+                if old_state == Some(state) {
+                    None
+                } else {
+                    Some(AccountChange {
+                        identifier: identifier.clone(),
+                        old: old_state.cloned(),
+                        new: Some(state.clone()),
+                    })
+                }
+            })
+            .collect();
+        accounts.extend(
+            old.accounts
+                .iter()
+                .filter(|(identifier, _)| new.accounts.get_state(identifier).is_err())
+                .map(|(identifier, state)| AccountChange {
+                    identifier: identifier.clone(),
+                    old: Some(state.clone()),
+                    new: None,
+                }),
+        );
+
+        LedgerDelta {
+            old_chain_length: old.chain_length,
+            new_chain_length: new.chain_length,
+            created_utxos: missing_utxos(&new.utxos, &old.utxos),
+            spent_utxos: missing_utxos(&old.utxos, &new.utxos),
+            spent_old_utxos: missing_utxos(&old.oldutxos, &new.oldutxos),
+            accounts,
+        }
+    }
+}
+
+fn write_utxo_changes<W, A, F>(
+    codec: &mut Codec<W>,
+    changes: &[UtxoChange<A>],
+    write_address: F,
+) -> Result<(), std::io::Error>
+where
+    W: Write,
+    F: Fn(&mut Codec<W>, &A) -> Result<(), std::io::Error>,
+{
+    use chain_core::property::Serialize;
+    put_len(codec, changes.len())?;
+    for change in changes {
+        change.transaction_id.serialize(&mut *codec)?;
+        codec.put_u8(change.output_index)?;
+        write_address(codec, &change.output.address)?;
+        change.output.value.serialize(&mut *codec)?;
+    }
+    Ok(())
+}
+
+fn read_utxo_changes<'a, A, F>(
+    buf: &mut ReadBuf<'a>,
+    read_address: F,
+) -> Result<Vec<UtxoChange<A>>, ReadError>
+where
+    F: Fn(&mut ReadBuf<'a>) -> Result<A, ReadError>,
+{
+    let len = get_len(buf)?;
+    let mut changes = Vec::with_capacity(len);
+    for _ in 0..len {
+        let transaction_id = TransactionId::read(buf)?;
+        let output_index = buf.get_u8()?;
+        let address = read_address(buf)?;
+        let value = Value::read(buf)?;
+        changes.push(UtxoChange {
+            transaction_id,
+            output_index,
+            output: Output { address, value },
+        });
+    }
+    Ok(changes)
+}
+
+fn write_account_state<W: Write>(
+    codec: &mut Codec<W>,
+    state: &Option<account::State>,
+) -> Result<(), std::io::Error> {
+    use chain_core::property::Serialize;
+    match state {
+        None => codec.put_u8(0),
+        Some(state) => {
+            codec.put_u8(1)?;
+            codec.put_u32(state.counter.0)?;
+            state.value.serialize(&mut *codec)
+        }
+    }
+}
+
+fn read_account_state<'a>(buf: &mut ReadBuf<'a>) -> Result<Option<account::State>, ReadError> {
+    match buf.get_u8()? {
+        0 => Ok(None),
+        1 => {
+            let counter = SpendingCounter(buf.get_u32()?);
+            let value = Value::read(buf)?;
+            Ok(Some(account::State { counter, value }))
+        }
+        tag => Err(ReadError::UnknownTag(tag as u32)),
+    }
+}
+
+impl property::Serialize for LedgerDelta {
+    type Error = std::io::Error;
+    fn serialize<W: std::io::Write>(&self, writer: W) -> Result<(), Self::Error> {
+        use chain_core::property::Serialize;
+        let mut codec = Codec::from(writer);
+        codec.put_u32(self.old_chain_length.0)?;
+        codec.put_u32(self.new_chain_length.0)?;
+        write_utxo_changes(&mut codec, &self.created_utxos, |codec, address| {
+            address.serialize(codec)
+        })?;
+        write_utxo_changes(&mut codec, &self.spent_utxos, |codec, address| {
+            address.serialize(codec)
+        })?;
+        write_utxo_changes(&mut codec, &self.spent_old_utxos, write_old_address)?;
+        put_len(&mut codec, self.accounts.len())?;
+        for change in &self.accounts {
+            let public_key: PublicKey<account::AccountAlg> = change.identifier.clone().into();
+            serialize_public_key(&public_key, &mut codec)?;
+            write_account_state(&mut codec, &change.old)?;
+            write_account_state(&mut codec, &change.new)?;
+        }
+        Ok(())
+    }
+}
+
+impl Readable for LedgerDelta {
+    fn read<'a>(buf: &mut ReadBuf<'a>) -> Result<Self, ReadError> {
+        let old_chain_length = ChainLength(buf.get_u32()?);
+        let new_chain_length = ChainLength(buf.get_u32()?);
+        let created_utxos = read_utxo_changes(buf, Address::read)?;
+        let spent_utxos = read_utxo_changes(buf, Address::read)?;
+        let spent_old_utxos = read_utxo_changes(buf, read_old_address)?;
+        let len = get_len(buf)?;
+        let mut accounts = Vec::with_capacity(len);
+        for _ in 0..len {
+            let identifier = deserialize_public_key::<account::AccountAlg>(buf)?.into();
+            let old = read_account_state(buf)?;
+            let new = read_account_state(buf)?;
+            accounts.push(AccountChange {
+                identifier,
+                old,
+                new,
+            });
+        }
+        Ok(LedgerDelta {
+            old_chain_length,
+            new_chain_length,
+            created_utxos,
+            spent_utxos,
+            spent_old_utxos,
+            accounts,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ledger::test::{make_key, output, test_initial_ents, test_ledger, test_rng};
+    use crate::transaction::{AuthenticatedTransaction, Input, NoExtra, Transaction, Witness};
+    use chain_addr::Discrimination;
+    use chain_core::property::Serialize;
+
+    #[test]
+    pub fn diff_of_a_transaction() {
+        let discrimination = Discrimination::Test;
+        let mut rng = test_rng();
+        let (sk1, _pk1, user1_address) = make_key(&mut rng, &discrimination);
+        let (_sk2, _pk2, user2_address) = make_key(&mut rng, &discrimination);
+        let (old, utxos) = test_ledger(test_initial_ents(), vec![output(&user1_address, 100)]);
+        let tx0_id = utxos[0].transaction_id;
+        assert!(Ledger::diff(&old, &old).is_empty());
+
+        let tx = Transaction {
+            inputs: vec![Input::from_utxo(utxos[0])],
+            outputs: vec![Output {
+                address: user2_address.clone(),
+                value: Value(100),
+            }],
+            extra: NoExtra,
+            metadata: None,
+        };
+        let tx1_id = tx.hash();
+        let witness = Witness::new_utxo(&tx1_id, &sk1).unwrap();
+        let signed_tx = AuthenticatedTransaction {
+            transaction: tx,
+            witnesses: vec![witness],
+        };
+        let mut new = old
+            .clone()
+            .apply_transaction(&signed_tx, &old.get_ledger_parameters())
+            .unwrap();
+        let (_account_sk, account_pk, _) = make_key(&mut rng, &discrimination);
+        let account = account::Identifier::from(account_pk);
+        new.accounts = new.accounts.add_account(&account, Value(5)).unwrap();
+
+        let delta = Ledger::diff(&old, &new);
+        assert_eq!(delta.spent_utxos.len(), 1);
+        assert_eq!(delta.spent_utxos[0].transaction_id, tx0_id);
+        assert_eq!(
+            delta.created_utxos,
+            vec![UtxoChange {
+                transaction_id: tx1_id,
+                output_index: 0,
+                output: Output {
+                    address: user2_address,
+                    value: Value(100),
+                },
+            }]
+        );
+        assert_eq!(
+            delta.accounts,
+            vec![AccountChange {
+                identifier: account.clone(),
+                old: None,
+                new: Some(account::State::new(Value(5))),
+            }]
+        );
+
+        // the reverse delta swaps the created and the spent outputs
+        let reverse = Ledger::diff(&new, &old);
+        assert_eq!(reverse.created_utxos, delta.spent_utxos);
+        assert_eq!(reverse.spent_utxos, delta.created_utxos);
+        assert_eq!(reverse.accounts[0].new, None);
+
+        let bytes = delta.serialize_as_vec().unwrap();
+        let decoded = LedgerDelta::read(&mut ReadBuf::from(&bytes)).unwrap();
+        assert_eq!(decoded, delta);
+    }
+}
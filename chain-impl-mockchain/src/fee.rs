@@ -1,3 +1,5 @@
+use crate::certificate::Certificate;
+use crate::multisig;
 use crate::transaction as tx;
 use crate::value::Value;
 use chain_addr::Address;
@@ -21,25 +23,95 @@ impl LinearFee {
     }
 }
 
+/// The certificates carried by the extra of a transaction, each one
+/// charged the `certificate` coefficient of the linear fee.
+pub trait CertificateCount {
+    fn certificate_count(&self) -> u64;
+}
+
+impl CertificateCount for tx::NoExtra {
+    fn certificate_count(&self) -> u64 {
+        0
+    }
+}
+
+impl CertificateCount for Certificate {
+    fn certificate_count(&self) -> u64 {
+        1
+    }
+}
+
+impl CertificateCount for multisig::Declaration {
+    fn certificate_count(&self) -> u64 {
+        0
+    }
+}
+
 pub trait FeeAlgorithm {
-    fn calculate_for<Extra>(&self, tx: &tx::Transaction<Address, Extra>) -> Option<Value>;
+    fn calculate_for<Extra: CertificateCount>(
+        &self,
+        tx: &tx::Transaction<Address, Extra>,
+    ) -> Option<Value>;
 }
 
 impl<'a, FA: FeeAlgorithm> FeeAlgorithm for &'a FA {
-    fn calculate_for<Extra>(&self, tx: &tx::Transaction<Address, Extra>) -> Option<Value> {
+    fn calculate_for<Extra: CertificateCount>(
+        &self,
+        tx: &tx::Transaction<Address, Extra>,
+    ) -> Option<Value> {
         (*self).calculate_for(tx)
     }
 }
 
 impl FeeAlgorithm for LinearFee {
-    fn calculate_for<Extra>(&self, tx: &tx::Transaction<Address, Extra>) -> Option<Value> {
+    fn calculate_for<Extra: CertificateCount>(
+        &self,
+        tx: &tx::Transaction<Address, Extra>,
+    ) -> Option<Value> {
         let msz = (tx.inputs.len() as u64).checked_add(tx.outputs.len() as u64)?;
-        // FIXME for now we don't consider extra as payload, however in the near future
-        // we need a trait related to the Extra that will give the fee valuation of the certificate
-        let fee = self
-            .coefficient
-            .checked_mul(msz)?
-            .checked_add(self.constant)?;
-        Some(Value(fee))
+        let certificates = Value(self.certificate)
+            .checked_mul(tx.extra.certificate_count())
+            .ok()?;
+        Value(self.coefficient)
+            .checked_mul(msz)
+            .and_then(|fee| fee + Value(self.constant))
+            .and_then(|fee| fee + certificates)
+            .ok()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::certificate::{CertificateContent, StakeDelegation};
+    use crate::transaction::{Input, NoExtra, Output, UtxoPointer};
+    use quickcheck::Arbitrary;
+
+    #[test]
+    fn linear_fee_charges_certificates() {
+        let mut g = quickcheck::StdGen::new(rand::thread_rng(), 10);
+        let fee = LinearFee::new(10, 2, 100);
+        let input = Input::from_utxo(UtxoPointer::arbitrary(&mut g));
+        let output = Output {
+            address: Address::arbitrary(&mut g),
+            value: Value(1),
+        };
+        let transaction = tx::Transaction {
+            inputs: vec![input],
+            outputs: vec![output.clone(), output],
+            extra: NoExtra,
+            metadata: None,
+        };
+        assert_eq!(fee.calculate_for(&transaction), Some(Value(16)));
+
+        let certificate = Certificate {
+            content: CertificateContent::StakeDelegation(StakeDelegation::arbitrary(&mut g)),
+            signatures: vec![],
+        };
+        let transaction = transaction.replace_extra(certificate);
+        assert_eq!(fee.calculate_for(&transaction), Some(Value(116)));
+
+        let fee = LinearFee::new(10, 2, u64::max_value());
+        assert_eq!(fee.calculate_for(&transaction), None);
     }
 }
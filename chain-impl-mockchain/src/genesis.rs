@@ -0,0 +1,254 @@
+//! genesis file: the human readable description of the block0
+//!
+//! The description is YAML (or JSON, YAML being a superset of JSON):
+//!
+//! ```yaml
+//! blockchain_configuration:
+//!   - [ block0-date, "1550822014" ]
+//!   - [ discrimination, test ]
+//!   - [ block0-consensus, bft ]
+//!   - [ linear-fee, "constant=10,coefficient=2,certificate=1" ]
+//! consensus_leader_ids:
+//!   - ed25519_pk1...
+//! initial_funds:
+//!   - address: ta1s...
+//!     value: 10000
+//! ```
+//!
+//! The configuration parameters use the names and the string values of the
+//! `generic-serialization` implementation of `ConfigParam`; the consensus
+//! leaders are added as `add-bft-leader` parameters. For instance the
+//! linear fee above reads as:
+//!
+//! ```
+//! use chain_impl_mockchain::{config::ConfigParam, fee::LinearFee};
+//!
+//! let param: ConfigParam =
+//!     serde_yaml::from_str(r#"[ linear-fee, "constant=10,coefficient=2,certificate=1" ]"#)
+//!         .unwrap();
+//! assert_eq!(param, ConfigParam::LinearFee(LinearFee::new(10, 2, 1)));
+//! ```
+
+use crate::block::{Block0Builder, Block0Error};
+use crate::config::ConfigParam;
+use crate::leadership::bft::LeaderId;
+use crate::value::Value;
+use chain_addr::{Address, AddressReadable};
+use chain_crypto::{bech32::Bech32 as _, PublicKey};
+use serde::{de::Error as _, Deserialize, Deserializer};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+#[derive(serde_derive::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Genesis {
+    blockchain_configuration: Vec<ConfigParam>,
+    #[serde(default)]
+    consensus_leader_ids: Vec<Leader>,
+    #[serde(default)]
+    initial_funds: Vec<InitialFund>,
+}
+
+#[derive(serde_derive::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InitialFund {
+    #[serde(deserialize_with = "deserialize_address")]
+    address: Address,
+    value: u64,
+}
+
+/// a consensus leader, as its bech32 public key
+struct Leader(LeaderId);
+
+impl<'de> Deserialize<'de> for Leader {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        PublicKey::try_from_bech32_str(&s)
+            .map(|public_key| Leader(LeaderId::from(public_key)))
+            .map_err(|err| D::Error::custom(format!("invalid leader id '{}': {}", s, err)))
+    }
+}
+
+fn deserialize_address<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Address, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    AddressReadable::from_string(&s)
+        .map(|address| address.to_address())
+        .map_err(|err| D::Error::custom(format!("invalid address '{}': {}", s, err)))
+}
+
+/// error while loading a genesis file
+#[derive(Debug)]
+pub enum Error {
+    /// the file cannot be read
+    Io(io::Error),
+    /// the file is not a valid genesis description
+    Syntax(serde_yaml::Error),
+    /// the description is not a consistent block0
+    Block0(Block0Error),
+}
+
+impl Error {
+    /// the line and the column, starting at 1, of the invalid field when
+    /// the error is a syntax error
+    pub fn location(&self) -> Option<(usize, usize)> {
+        match self {
+            Error::Syntax(error) => error
+                .location()
+                .map(|location| (location.line(), location.column())),
+            Error::Io(_) | Error::Block0(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(error) => write!(f, "cannot read the genesis file: {}", error),
+            Error::Syntax(error) => write!(f, "invalid genesis file: {}", error),
+            Error::Block0(error) => write!(f, "invalid genesis block: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(error) => Some(error),
+            Error::Syntax(error) => Some(error),
+            Error::Block0(error) => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<serde_yaml::Error> for Error {
+    fn from(error: serde_yaml::Error) -> Self {
+        Error::Syntax(error)
+    }
+}
+
+impl From<Block0Error> for Error {
+    fn from(error: Block0Error) -> Self {
+        Error::Block0(error)
+    }
+}
+
+/// load the YAML or JSON genesis file into a block0 builder, checking
+/// the block0 is valid
+pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Block0Builder, Error> {
+    from_reader(File::open(path)?)
+}
+
+/// load the YAML or JSON genesis description from the reader
+pub fn from_reader<R: Read>(reader: R) -> Result<Block0Builder, Error> {
+    let genesis: Genesis = serde_yaml::from_reader(reader)?;
+    into_builder(genesis)
+}
+
+/// load the YAML or JSON genesis description from the string
+pub fn from_str(genesis: &str) -> Result<Block0Builder, Error> {
+    let genesis: Genesis = serde_yaml::from_str(genesis)?;
+    into_builder(genesis)
+}
+
+fn into_builder(genesis: Genesis) -> Result<Block0Builder, Error> {
+    let mut builder = Block0Builder::new();
+    builder
+        .config_params(genesis.blockchain_configuration)
+        .config_params(
+            genesis
+                .consensus_leader_ids
+                .into_iter()
+                .map(|Leader(leader_id)| ConfigParam::AddBftLeader(leader_id)),
+        );
+    for fund in genesis.initial_funds {
+        builder.initial_fund(fund.address, Value(fund.value));
+    }
+    builder.build()?;
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key::SpendingSecretKey;
+    use crate::ledger::Ledger;
+    use chain_addr::{Discrimination, Kind};
+    use quickcheck::{Arbitrary, StdGen};
+
+    fn key() -> SpendingSecretKey {
+        let mut g = StdGen::new(rand::thread_rng(), 10);
+        SpendingSecretKey::arbitrary(&mut g)
+    }
+
+    fn genesis(leader: &str, address: &str) -> String {
+        format!(
+            r#"
+blockchain_configuration:
+  - [ block0-date, "1550822014" ]
+  - [ discrimination, test ]
+  - [ block0-consensus, bft ]
+consensus_leader_ids:
+  - {}
+initial_funds:
+  - address: {}
+    value: 10000
+"#,
+            leader, address
+        )
+    }
+
+    fn valid_genesis() -> String {
+        let leader = key().to_public().to_bech32_str();
+        let address = Address(Discrimination::Test, Kind::Single(key().to_public()));
+        genesis(&leader, AddressReadable::from_address(&address).as_string())
+    }
+
+    #[test]
+    fn load_genesis() {
+        let block0 = from_str(&valid_genesis()).unwrap().build().unwrap();
+        let ledger = Ledger::new(block0.header.hash(), block0.contents.iter()).unwrap();
+        assert_eq!(ledger.utxos().count(), 1);
+    }
+
+    #[test]
+    fn load_json_genesis() {
+        let leader = key().to_public().to_bech32_str();
+        let json = format!(
+            r#"{{ "blockchain_configuration": [ [ "block0-consensus", "bft" ] ],
+                 "consensus_leader_ids": [ "{}" ] }}"#,
+            leader
+        );
+        assert!(from_str(&json).is_ok());
+    }
+
+    #[test]
+    fn invalid_address_is_located() {
+        let leader = key().to_public().to_bech32_str();
+        let error = from_str(&genesis(&leader, "ta1notanaddress"))
+            .err()
+            .unwrap();
+        match error {
+            Error::Syntax(_) => assert_eq!(error.location().map(|(line, _)| line), Some(9)),
+            error => panic!("unexpected error {}", error),
+        }
+    }
+
+    #[test]
+    fn inconsistent_genesis_is_rejected() {
+        let error = from_str("blockchain_configuration: [ [ discrimination, test ] ]")
+            .err()
+            .unwrap();
+        match error {
+            Error::Block0(Block0Error::NoConsensusVersion) => {}
+            error => panic!("unexpected error {}", error),
+        }
+    }
+}
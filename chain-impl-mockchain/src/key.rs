@@ -1,7 +1,7 @@
 //! Module provides cryptographic utilities and types related to
 //! the user keys.
 //!
-use chain_core::mempack::{read_mut_slice, ReadBuf, ReadError, Readable};
+use chain_core::mempack::{ReadBuf, ReadError, Readable};
 use chain_core::property;
 use chain_crypto as crypto;
 use chain_crypto::{
@@ -60,9 +60,8 @@ pub fn deserialize_public_key<'a, A>(
 where
     A: AsymmetricKey,
 {
-    let mut bytes = vec![0u8; A::PUBLIC_KEY_SIZE];
-    read_mut_slice(buf, &mut bytes[..])?;
-    crypto::PublicKey::from_binary(&bytes).map_err(chain_crypto_pub_err)
+    let bytes = buf.get_slice_ref(A::PUBLIC_KEY_SIZE)?;
+    crypto::PublicKey::from_binary(bytes).map_err(chain_crypto_pub_err)
 }
 #[inline]
 pub fn deserialize_signature<'a, A, T>(
@@ -71,9 +70,8 @@ pub fn deserialize_signature<'a, A, T>(
 where
     A: VerificationAlgorithm,
 {
-    let mut bytes = vec![0u8; A::SIGNATURE_SIZE];
-    read_mut_slice(buf, &mut bytes[..])?;
-    crypto::Signature::from_binary(&bytes).map_err(chain_crypto_sig_err)
+    let bytes = buf.get_slice_ref(A::SIGNATURE_SIZE)?;
+    crypto::Signature::from_binary(bytes).map_err(chain_crypto_sig_err)
 }
 
 pub fn make_signature<T, A>(
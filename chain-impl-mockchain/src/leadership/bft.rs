@@ -1,12 +1,12 @@
 use crate::block::{BlockDate, Header, Proof};
-use crate::key::{deserialize_public_key, serialize_public_key};
+use crate::key::{deserialize_public_key, serialize_public_key, verify_signature};
 use crate::{
     leadership::{Error, ErrorKind, Verification},
     ledger::Ledger,
 };
 use chain_core::mempack::{ReadBuf, ReadError, Readable};
 use chain_core::property;
-use chain_crypto::{Ed25519Extended, PublicKey, SecretKey};
+use chain_crypto::{Ed25519Extended, PublicKey, SecretKey, Verification as SigningVerification};
 use std::sync::Arc;
 
 /// cryptographic signature algorithm used for the BFT leadership
@@ -53,11 +53,25 @@ impl BftLeaderSelection {
     }
 
     pub(crate) fn verify(&self, block_header: &Header) -> Verification {
+        self.verify_round(block_header.block_date().slot_id as u64, block_header)
+    }
+
+    /// verify the block was signed by the leader of the given round of the
+    /// round robin (the slot of the block in pure BFT, the index of the
+    /// BFT slot of the block in Genesis Praos)
+    pub(crate) fn verify_round(&self, round: u64, block_header: &Header) -> Verification {
         match &block_header.proof() {
-            Proof::Bft(bft_proof) => match self.get_leader_at(*block_header.block_date()) {
+            Proof::Bft(bft_proof) => match self.get_leader_of_round(round) {
                 Ok(leader_at) => {
                     if bft_proof.leader_id != leader_at {
                         Verification::Failure(Error::new(ErrorKind::InvalidLeader))
+                    } else if verify_signature(
+                        &bft_proof.signature.0,
+                        &bft_proof.leader_id.0,
+                        &block_header.common,
+                    ) == SigningVerification::Failed
+                    {
+                        Verification::Failure(Error::new(ErrorKind::InvalidLeaderSignature))
                     } else {
                         Verification::Success
                     }
@@ -70,7 +84,12 @@ impl BftLeaderSelection {
 
     #[inline]
     pub(crate) fn get_leader_at(&self, date: BlockDate) -> Result<LeaderId, Error> {
-        let BftRoundRobinIndex(ofs) = self.offset(date.slot_id as u64);
+        self.get_leader_of_round(date.slot_id as u64)
+    }
+
+    #[inline]
+    pub(crate) fn get_leader_of_round(&self, round: u64) -> Result<LeaderId, Error> {
+        let BftRoundRobinIndex(ofs) = self.offset(round);
         Ok(self.leaders[ofs as usize].clone())
     }
 }
@@ -4,7 +4,10 @@ use crate::{
     block::{BlockDate, Header, Proof},
     date::Epoch,
     key::verify_signature,
-    leadership::{Error, ErrorKind, Verification},
+    leadership::{
+        bft::{BftLeaderSelection, LeaderId},
+        Error, ErrorKind, Verification,
+    },
     ledger::Ledger,
     stake::{self, StakeDistribution, StakePoolId},
     value::Value,
@@ -24,20 +27,65 @@ pub struct GenesisLeaderSelection {
     epoch_nonce: vrfeval::Nonce,
     nodes: stake::PoolTable,
     distribution: StakeDistribution,
+    param_f: vrfeval::ParamF,
+    /// the BFT leaders of the slots reserved to them during the
+    /// transition from BFT to Genesis Praos
+    bft_leaders: Option<BftLeaderSelection>,
+    /// percentage of the slots of the epoch reserved to the BFT leaders
+    /// (the `d` parameter)
+    bootstrap_key_slots_percentage: u8,
     // the epoch this leader selection is valid for
     epoch: Epoch,
 }
 
 impl GenesisLeaderSelection {
-    pub fn new(epoch: Epoch, ledger: &Ledger) -> Self {
-        let stake_distribution = ledger.get_stake_distribution();
+    pub fn new(epoch: Epoch, ledger: &Ledger) -> Result<Self, Error> {
+        let stake_distribution = ledger.get_stake_distribution().clone();
+        let param_f = vrfeval::ParamF::from_per_mille(ledger.settings.genesis_praos_param_f)
+            .map_err(|err| Error::new_(ErrorKind::Failure, Box::new(err)))?;
 
-        GenesisLeaderSelection {
+        Ok(GenesisLeaderSelection {
             epoch_nonce: vrfeval::Nonce::zero(),
             nodes: ledger.delegation.stake_pools.clone(),
             distribution: stake_distribution,
+            param_f,
+            bft_leaders: BftLeaderSelection::new(ledger),
+            bootstrap_key_slots_percentage: ledger.settings.bootstrap_key_slots_percentage,
             epoch: epoch,
-        }
+        })
+    }
+
+    /// the epoch this leader selection is valid for
+    pub fn epoch(&self) -> Epoch {
+        self.epoch
+    }
+
+    /// whether the slot is reserved to the BFT leaders: the
+    /// `bootstrap_key_slots_percentage` percents of the slots of the epoch
+    /// are evenly spread among them
+    pub fn is_bft_slot(&self, date: BlockDate) -> bool {
+        let d = self.bootstrap_key_slots_percentage as u64;
+        let slot_id = date.slot_id as u64;
+        slot_id * d / 100 != (slot_id + 1) * d / 100
+    }
+
+    /// the BFT leader of the slot reserved to the BFT leaders: they take
+    /// turns over the reserved slots of the epoch
+    pub(crate) fn get_bft_leader_at(&self, date: BlockDate) -> Result<LeaderId, Error> {
+        self.bft_leaders()?
+            .get_leader_of_round(self.bft_round(date))
+    }
+
+    fn bft_leaders(&self) -> Result<&BftLeaderSelection, Error> {
+        self.bft_leaders
+            .as_ref()
+            .ok_or_else(|| Error::new(ErrorKind::NoLeaderForThisSlot))
+    }
+
+    /// the number of slots of the epoch reserved to the BFT leaders before
+    /// the given one
+    fn bft_round(&self, date: BlockDate) -> u64 {
+        date.slot_id as u64 * self.bootstrap_key_slots_percentage as u64 / 100
     }
 
     pub fn leader(
@@ -47,8 +95,11 @@ impl GenesisLeaderSelection {
         date: BlockDate,
     ) -> Result<Option<Witness>, Error> {
         if date.epoch != self.epoch {
-            // TODO: add more error details: invalid Date
-            return Err(Error::new(ErrorKind::Failure));
+            return Err(Error::new(ErrorKind::EpochMismatch(self.epoch, date.epoch)));
+        }
+
+        if self.is_bft_slot(date) {
+            return Ok(None);
         }
 
         let stake_snapshot = &self.distribution;
@@ -68,18 +119,32 @@ impl GenesisLeaderSelection {
                     stake: stake,
                     total: total_stake,
                 };
-                match vrfeval::evaluate(percent_stake, vrf_key, &self.epoch_nonce, date.slot_id) {
-                    None => Ok(None),
-                    Some(vrfout) => Ok(Some(vrfout)),
-                }
+                vrfeval::evaluate(
+                    self.param_f,
+                    percent_stake,
+                    vrf_key,
+                    &self.epoch_nonce,
+                    date.slot_id,
+                )
+                .map_err(|err| Error::new_(ErrorKind::Failure, Box::new(err)))
             }
         }
     }
 
     pub(crate) fn verify(&self, block_header: &Header) -> Verification {
         if block_header.block_date().epoch != self.epoch {
-            // TODO: add more error details: invalid Date
-            return Verification::Failure(Error::new(ErrorKind::Failure));
+            return Verification::Failure(Error::new(ErrorKind::EpochMismatch(
+                self.epoch,
+                block_header.block_date().epoch,
+            )));
+        }
+
+        if self.is_bft_slot(*block_header.block_date()) {
+            return match self.bft_leaders() {
+                Ok(bft_leaders) => bft_leaders
+                    .verify_round(self.bft_round(*block_header.block_date()), block_header),
+                Err(error) => Verification::Failure(error),
+            };
         }
 
         let stake_snapshot = &self.distribution;
@@ -100,13 +165,17 @@ impl GenesisLeaderSelection {
                             total: total_stake,
                         };
 
-                        let _ = vrfeval::verify(
+                        let eligible = vrfeval::verify(
+                            self.param_f,
                             percent_stake,
                             &pool_info.initial_key.vrf_public_key,
                             &self.epoch_nonce,
                             block_header.block_date().slot_id,
                             &genesis_praos_proof.vrf_proof,
                         );
+                        if eligible.is_none() {
+                            return Verification::Failure(Error::new(ErrorKind::InvalidLeader));
+                        }
 
                         let valid = verify_signature(
                             &genesis_praos_proof.kes_proof.0,
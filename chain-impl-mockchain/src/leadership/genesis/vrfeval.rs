@@ -3,10 +3,11 @@
 use crate::date::SlotId;
 use crate::value::Value;
 use chain_crypto::{
-    vrf_evaluate_and_proove, vrf_verified_get_output, vrf_verify, Curve25519_2HashDH, PublicKey,
-    SecretKey, VRFVerification, VerifiableRandomFunction,
+    vrf_evaluate_and_proove, vrf_output_below_threshold, vrf_verified_get_output, vrf_verify,
+    Curve25519_2HashDH, PublicKey, SecretKey, VRFVerification, VerifiableRandomFunction,
 };
 use rand::rngs::OsRng;
+use std::fmt;
 
 /// Nonce gathered per block
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -18,44 +19,55 @@ impl Nonce {
     }
 }
 
-/// number between 0.0 and 1.0 that is used to calculate to
-pub struct F(f64);
+/// The active slots coefficient: probability, between 0.0 and 1.0, that
+/// a slot has a leader
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ParamF(f64);
 
-impl F {
-    // TODO: error handling and replace by TryFrom once more stable
-    pub fn create(v: f64) -> F {
-        assert!(v > 0.0 && v <= 1.0);
-        F(v)
+impl ParamF {
+    pub fn create(v: f64) -> Result<ParamF, InvalidParamF> {
+        if v > 0.0 && v <= 1.0 {
+            Ok(ParamF(v))
+        } else {
+            Err(InvalidParamF(v))
+        }
+    }
+
+    /// from the `f` of the settings, in thousandths
+    pub fn from_per_mille(v: u16) -> Result<ParamF, InvalidParamF> {
+        ParamF::create(v as f64 / 1000.0)
+    }
+}
+
+/// the active slots coefficient is not in `]0.0, 1.0]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InvalidParamF(pub f64);
+
+impl fmt::Display for InvalidParamF {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid active slots coefficient {}, expected a value in ]0.0, 1.0]",
+            self.0
+        )
     }
 }
 
+impl std::error::Error for InvalidParamF {}
+
 /// Threshold between 0.0 and 1.0
 #[derive(Clone, Copy, PartialEq, PartialOrd)]
 pub struct Threshold(f64);
 
-impl Threshold {
-    pub fn from_u256(v: &[u8]) -> Self {
-        assert_eq!(v.len(), 32);
-        // TODO, only consider the highest part
-        let v64 = (v[0] as u64) << 56
-            | (v[1] as u64) << 48
-            | (v[2] as u64) << 40
-            | (v[3] as u64) << 32
-            | (v[4] as u64) << 24
-            | (v[5] as u64) << 16
-            | (v[6] as u64) << 8
-            | (v[7] as u64);
-        Threshold((v64 as f64) / 18446744073709551616.0)
-    }
-}
-
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct PercentStake {
     pub stake: Value,
     pub total: Value,
 }
 
-pub fn phi(f: F, rs: PercentStake) -> Threshold {
+/// probability for a stakeholder with the given relative stake to be
+/// leader of a slot
+pub fn phi(f: ParamF, rs: PercentStake) -> Threshold {
     assert!(rs.stake <= rs.total);
     let t = (rs.stake.0 as f64) / (rs.total.0 as f64);
     Threshold(1.0 - (1.0 - f.0).powf(t))
@@ -80,22 +92,23 @@ pub type WitnessOutput = <Curve25519_2HashDH as VerifiableRandomFunction>::Rando
 
 /// Evaluate if the threshold is above for a given input for the key and the associated stake
 ///
-/// On threshold success, the witness is returned, otherwise None is returned
+/// On threshold success, the witness is returned, otherwise None is returned.
+/// Fails if the randomness of the proof cannot be gathered.
 pub fn evaluate(
+    f: ParamF,
     my_stake: PercentStake,
     key: &SecretKey<Curve25519_2HashDH>,
     nonce: &Nonce,
     slotid: SlotId,
-) -> Option<Witness> {
+) -> Result<Option<Witness>, rand::Error> {
     let input = Input::create(nonce, slotid);
-    let csprng = OsRng::new().unwrap();
+    let csprng = OsRng::new()?;
     let vr = vrf_evaluate_and_proove(key, &input.0, csprng);
     let r = vrf_verified_get_output::<Curve25519_2HashDH>(&vr);
-    let t = get_threshold(&input, &r);
-    if above_stake_threshold(t, my_stake) {
-        Some(vr)
+    if below_stake_threshold(f, &input, &r, my_stake) {
+        Ok(Some(vr))
     } else {
-        None
+        Ok(None)
     }
 }
 
@@ -104,6 +117,7 @@ pub fn evaluate(
 ///
 /// On success, the nonce is returned, otherwise None is returned
 pub fn verify(
+    f: ParamF,
     key_stake: PercentStake,
     key: &PublicKey<Curve25519_2HashDH>,
     nonce: &Nonce,
@@ -113,8 +127,7 @@ pub fn verify(
     let input = Input::create(nonce, slotid);
     if vrf_verify(key, &input.0, witness) == VRFVerification::Success {
         let r = vrf_verified_get_output::<Curve25519_2HashDH>(witness);
-        let t = get_threshold(&input, &r);
-        if above_stake_threshold(t, key_stake) {
+        if below_stake_threshold(f, &input, &r, key_stake) {
             Some(get_nonce(&input, &r))
         } else {
             None
@@ -124,23 +137,85 @@ pub fn verify(
     }
 }
 
-fn above_stake_threshold(threshold: Threshold, stake: PercentStake) -> bool {
-    // TODO F is hardcoded here
-    threshold >= phi(F::create(0.5), stake)
+/// the output of the VRF is uniformly distributed, so it is below
+/// `phi(f, stake)` with the probability to be leader of the slot
+fn below_stake_threshold(
+    f: ParamF,
+    input: &Input,
+    os: &WitnessOutput,
+    stake: PercentStake,
+) -> bool {
+    let out = os.to_output(&input.0, DOMAIN_THRESHOLD);
+    vrf_output_below_threshold(out.as_ref(), phi(f, stake).0)
 }
 
 const DOMAIN_NONCE: &'static [u8] = b"NONCE";
 const DOMAIN_THRESHOLD: &'static [u8] = b"TEST";
 
-fn get_threshold(input: &Input, os: &WitnessOutput) -> Threshold {
-    let out = os.to_output(&input.0, DOMAIN_THRESHOLD);
-    // read as big endian 64 bits values from left to right.
-    Threshold::from_u256(out.as_ref())
-}
-
 fn get_nonce(input: &Input, os: &WitnessOutput) -> Nonce {
     let mut nonce = [0u8; 32];
     let out = os.to_output(&input.0, DOMAIN_NONCE);
     nonce.copy_from_slice(out.as_ref());
     Nonce(nonce)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn stake(stake: u64, total: u64) -> PercentStake {
+        PercentStake {
+            stake: Value(stake),
+            total: Value(total),
+        }
+    }
+
+    #[test]
+    fn phi_bounds() {
+        let f = ParamF::from_per_mille(200).unwrap();
+        assert!(phi(f, stake(0, 100)).0 == 0.0);
+        assert!((phi(f, stake(100, 100)).0 - 0.2).abs() < 1e-9);
+        // independent aggregation: splitting the stake does not change the
+        // probability that one of the parts is leader
+        let half = 1.0 - phi(f, stake(50, 100)).0;
+        assert!((1.0 - half * half - 0.2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn param_f_bounds() {
+        assert_eq!(ParamF::from_per_mille(0), Err(InvalidParamF(0.0)));
+        assert_eq!(ParamF::from_per_mille(1001), Err(InvalidParamF(1.001)));
+        assert!(ParamF::create(-0.5).is_err());
+        assert!(ParamF::create(std::f64::NAN).is_err());
+        assert_eq!(ParamF::from_per_mille(1000), Ok(ParamF(1.0)));
+    }
+
+    #[test]
+    fn leader_frequency_follows_f() {
+        let mut csprng = OsRng::new().unwrap();
+        let key = SecretKey::<Curve25519_2HashDH>::generate(&mut csprng);
+        let public = key.to_public();
+        let nonce = Nonce::zero();
+
+        for (f, slots) in &[(1000, 50), (250, 200)] {
+            let f = ParamF::from_per_mille(*f).unwrap();
+            let mut elected = 0;
+            for slotid in 0..*slots {
+                if let Some(witness) = evaluate(f, stake(10, 10), &key, &nonce, slotid).unwrap() {
+                    assert!(verify(f, stake(10, 10), &public, &nonce, slotid, &witness).is_some());
+                    elected += 1;
+                }
+                assert!(evaluate(f, stake(0, 10), &key, &nonce, slotid)
+                    .unwrap()
+                    .is_none());
+            }
+            if f == ParamF::create(1.0).unwrap() {
+                assert_eq!(elected, *slots);
+            } else {
+                // 50 expected, the bounds are more than 5 standard
+                // deviations away
+                assert!(elected > 15 && elected < 85, "elected {} times", elected);
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::{
-    block::{AnyBlockVersion, BlockDate, BlockVersion, ConsensusVersion, Header},
+    block::{BlockDate, BlockVersion, ConsensusVersion, Header},
     date::Epoch,
     ledger::Ledger,
     stake::StakePoolId,
@@ -20,6 +20,8 @@ pub enum ErrorKind {
     InvalidLeaderSignature,
     InvalidBlockMessage,
     InvalidStateUpdate,
+    /// the leader selection is for the first epoch, not the second one
+    EpochMismatch(Epoch, Epoch),
 }
 
 #[derive(Debug)]
@@ -74,9 +76,19 @@ pub struct Leadership {
     inner: LeadershipConsensus,
 }
 
+/// whether the BFT leader is the given leader of the slot
+fn is_bft_leader(bft_leader: &BftLeader, bft_leader_id: bft::LeaderId) -> LeaderOutput {
+    if bft_leader_id == bft_leader.sig_key.to_public().into() {
+        LeaderOutput::Bft(bft_leader_id)
+    } else {
+        LeaderOutput::None
+    }
+}
+
 impl LeadershipConsensus {
     #[inline]
-    fn verify_version(&self, block_version: AnyBlockVersion) -> Verification {
+    fn verify_version(&self, block_header: &Header) -> Verification {
+        let block_version = block_header.block_version();
         match self {
             LeadershipConsensus::None(_) if block_version == BlockVersion::Genesis => {
                 Verification::Success
@@ -84,6 +96,20 @@ impl LeadershipConsensus {
             LeadershipConsensus::Bft(_) if block_version == BlockVersion::Ed25519Signed => {
                 Verification::Success
             }
+            // the blocks of the slots reserved to the BFT leaders are
+            // signed by them
+            LeadershipConsensus::GenesisPraos(genesis_praos)
+                if genesis_praos.is_bft_slot(*block_header.block_date()) =>
+            {
+                if block_version == BlockVersion::Ed25519Signed {
+                    Verification::Success
+                } else {
+                    Verification::Failure(Error::new(ErrorKind::IncompatibleBlockVersion))
+                }
+            }
+            LeadershipConsensus::GenesisPraos(_) if block_version == BlockVersion::KesVrfproof => {
+                Verification::Success
+            }
             _ => Verification::Failure(Error::new(ErrorKind::IncompatibleBlockVersion)),
         }
     }
@@ -102,16 +128,18 @@ impl LeadershipConsensus {
         match self {
             LeadershipConsensus::None(_none) => Ok(LeaderOutput::None),
             LeadershipConsensus::Bft(bft) => match leader.bft_leader {
-                Some(ref bft_leader) => {
-                    let bft_leader_id = bft.get_leader_at(date)?;
-                    if bft_leader_id == bft_leader.sig_key.to_public().into() {
-                        Ok(LeaderOutput::Bft(bft_leader_id))
-                    } else {
-                        Ok(LeaderOutput::None)
-                    }
-                }
+                Some(ref bft_leader) => Ok(is_bft_leader(bft_leader, bft.get_leader_at(date)?)),
                 None => Ok(LeaderOutput::None),
             },
+            LeadershipConsensus::GenesisPraos(genesis_praos) if genesis_praos.is_bft_slot(date) => {
+                match leader.bft_leader {
+                    Some(ref bft_leader) => Ok(is_bft_leader(
+                        bft_leader,
+                        genesis_praos.get_bft_leader_at(date)?,
+                    )),
+                    None => Ok(LeaderOutput::None),
+                }
+            }
             LeadershipConsensus::GenesisPraos(genesis_praos) => match leader.genesis_leader {
                 None => Ok(LeaderOutput::None),
                 Some(ref gen_leader) => {
@@ -126,22 +154,23 @@ impl LeadershipConsensus {
 }
 
 impl Leadership {
-    pub fn new(epoch: Epoch, ledger: &Ledger) -> Self {
+    pub fn new(epoch: Epoch, ledger: &Ledger) -> Result<Self, Error> {
         let inner = match ledger.settings.consensus_version {
-            ConsensusVersion::Bft => {
-                LeadershipConsensus::Bft(bft::BftLeaderSelection::new(ledger).unwrap())
-            }
+            ConsensusVersion::Bft => LeadershipConsensus::Bft(
+                bft::BftLeaderSelection::new(ledger)
+                    .ok_or_else(|| Error::new(ErrorKind::NoLeaderForThisSlot))?,
+            ),
             ConsensusVersion::GenesisPraos => LeadershipConsensus::GenesisPraos(
-                genesis::GenesisLeaderSelection::new(epoch, ledger),
+                genesis::GenesisLeaderSelection::new(epoch, ledger)?,
             ),
         };
-        Leadership { inner }
+        Ok(Leadership { inner })
     }
 
     /// Verify whether this header has been produced by a leader that fits with the leadership
     ///
     pub fn verify(&self, block_header: &Header) -> Verification {
-        try_check!(self.inner.verify_version(block_header.block_version()));
+        try_check!(self.inner.verify_version(block_header));
 
         try_check!(self.inner.verify_leader(block_header));
         Verification::Success
@@ -156,6 +185,43 @@ impl Leadership {
     ) -> Result<LeaderOutput, Error> {
         self.inner.is_leader(leader, date)
     }
+
+    /// List the slots of the epoch for which the given leader is eligible
+    /// to create a block, with the output proving the leadership of each.
+    ///
+    /// With BFT the leaders follow a round robin, so the schedule is only
+    /// the slots of the leader in the round. With Genesis Praos the
+    /// eligibility of the pool for each slot is evaluated with its VRF key
+    /// against the stake distribution of the epoch, except for the slots
+    /// reserved to the BFT leaders during the transition era.
+    ///
+    /// `slots_per_epoch` is the number of slots of the epoch, as given by
+    /// the time era it belongs to.
+    pub fn schedule(
+        &self,
+        epoch: Epoch,
+        slots_per_epoch: u32,
+        leader: &Leader,
+    ) -> Result<Vec<(BlockDate, LeaderOutput)>, Error> {
+        if let LeadershipConsensus::GenesisPraos(genesis_praos) = &self.inner {
+            if genesis_praos.epoch() != epoch {
+                return Err(Error::new(ErrorKind::EpochMismatch(
+                    genesis_praos.epoch(),
+                    epoch,
+                )));
+            }
+        }
+
+        let mut slots = Vec::new();
+        for slot_id in 0..slots_per_epoch {
+            let date = BlockDate { epoch, slot_id };
+            match self.inner.is_leader(leader, date)? {
+                LeaderOutput::None => {}
+                output => slots.push((date, output)),
+            }
+        }
+        Ok(slots)
+    }
 }
 
 impl Verification {
@@ -210,6 +276,11 @@ impl std::fmt::Display for ErrorKind {
             ErrorKind::InvalidLeaderSignature => write!(f, "Block signature is invalid"),
             ErrorKind::InvalidBlockMessage => write!(f, "Invalid block message"),
             ErrorKind::InvalidStateUpdate => write!(f, "Invalid State Update"),
+            ErrorKind::EpochMismatch(expected, epoch) => write!(
+                f,
+                "The leader selection is for epoch {}, not epoch {}",
+                expected, epoch
+            ),
         }
     }
 }
@@ -227,3 +298,93 @@ impl std::error::Error for Error {
         self.cause.as_ref().map(std::ops::Deref::deref)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::{BootstrapKeySlotsPercentage, ConfigParam};
+    use crate::ledger::test::{make_leader, test_block0_hash, test_initial_ents, test_rng};
+    use crate::message::{initial, Message};
+
+    fn bft_leader(sig_key: bft::SigningKey) -> Leader {
+        Leader {
+            bft_leader: Some(BftLeader { sig_key }),
+            genesis_leader: None,
+        }
+    }
+
+    #[test]
+    pub fn bft_schedule() {
+        let mut rng = test_rng();
+        let (leader0_key, leader0_id) = make_leader(&mut rng);
+        let (leader1_key, leader1_id) = make_leader(&mut rng);
+
+        let mut ie = test_initial_ents();
+        ie.push(ConfigParam::AddBftLeader(leader0_id.clone()));
+        ie.push(ConfigParam::AddBftLeader(leader1_id.clone()));
+        let ledger = Ledger::new(test_block0_hash(), &[Message::Initial(ie)]).unwrap();
+
+        let leadership = Leadership::new(1, &ledger).unwrap();
+        for (index, (key, id)) in vec![(leader0_key, leader0_id), (leader1_key, leader1_id)]
+            .into_iter()
+            .enumerate()
+        {
+            let schedule = leadership.schedule(1, 50, &bft_leader(key)).unwrap();
+            assert_eq!(schedule.len(), 25);
+            for (date, output) in schedule {
+                assert_eq!(date.epoch, 1);
+                assert_eq!(date.slot_id as usize % 2, index);
+                match output {
+                    LeaderOutput::Bft(leader_id) => assert_eq!(leader_id, id),
+                    _ => panic!("expected a BFT leader output"),
+                }
+            }
+        }
+
+        let not_a_leader = Leader {
+            bft_leader: None,
+            genesis_leader: None,
+        };
+        assert!(leadership
+            .schedule(1, 50, &not_a_leader)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    pub fn genesis_praos_bft_slots() {
+        let mut rng = test_rng();
+        let (leader0_key, leader0_id) = make_leader(&mut rng);
+        let (leader1_key, leader1_id) = make_leader(&mut rng);
+
+        let mut ie = initial::InitialEnts::new();
+        ie.push(ConfigParam::ConsensusVersion(
+            ConsensusVersion::GenesisPraos,
+        ));
+        ie.push(ConfigParam::BootstrapKeySlotsPercentage(
+            BootstrapKeySlotsPercentage(20),
+        ));
+        ie.push(ConfigParam::AddBftLeader(leader0_id.clone()));
+        ie.push(ConfigParam::AddBftLeader(leader1_id.clone()));
+        let ledger = Ledger::new(test_block0_hash(), &[Message::Initial(ie)]).unwrap();
+
+        // the BFT leaders take turns over the fifth of the slots reserved
+        // to them
+        let leadership = Leadership::new(0, &ledger).unwrap();
+        let leader0 = bft_leader(leader0_key);
+        let schedule0 = leadership.schedule(0, 200, &leader0).unwrap();
+        let schedule1 = leadership
+            .schedule(0, 200, &bft_leader(leader1_key))
+            .unwrap();
+        assert_eq!(schedule0.len(), 20);
+        assert_eq!(schedule1.len(), 20);
+        for (date, _) in schedule0.iter().chain(schedule1.iter()) {
+            assert_eq!(date.slot_id % 5, 4);
+        }
+        let slots0: Vec<_> = schedule0.iter().map(|(date, _)| date.slot_id).collect();
+        assert_eq!(&slots0[..3], &[4, 14, 24]);
+
+        let err = leadership.schedule(1, 200, &leader0).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::EpochMismatch(0, 1));
+    }
+}
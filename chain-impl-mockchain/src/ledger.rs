@@ -1,17 +1,26 @@
 //! Mockchain ledger. Ledger exists in order to update the
 //! current state and verify transactions.
 
-use crate::block::{ChainLength, ConsensusVersion, HeaderHash};
-use crate::config::{self, ConfigParam};
-use crate::fee::LinearFee;
+use crate::block::{
+    BlockContentHash, BlockContents, BlockDate, BlockTransactionsRoot, ChainLength,
+    ConsensusVersion, Header, HeaderHash,
+};
+use crate::config::{self, ConfigParam, ConfigParams};
+use crate::fee::{CertificateCount, FeeAlgorithm, LinearFee};
+use crate::leadership::{self, Leadership};
 use crate::message::Message;
+use crate::rewards::Rewards;
 use crate::stake::{DelegationError, DelegationState, StakeDistribution};
 use crate::transaction::*;
+use crate::update::{self, SignedProposalCertificate, SignedVoteCertificate, UpdateState};
 use crate::value::*;
-use crate::{account, certificate, legacy, setting, stake, utxo};
+use crate::verification::WitnessBatch;
+use crate::{account, certificate, legacy, multisig, setting, stake, utxo};
 use chain_addr::{Address, Discrimination, Kind};
-use chain_core::property::{self, ChainLength as _};
+use chain_core::metrics::{names, seconds, Metrics, NoMetrics};
+use chain_core::property::{self, ChainLength as _, Message as _};
 use std::sync::Arc;
+use std::time::Instant;
 
 // static parameters, effectively this is constant in the parameter of the blockchain
 #[derive(Clone)]
@@ -52,15 +61,22 @@ pub struct Ledger {
     pub(crate) utxos: utxo::Ledger<Address>,
     pub(crate) oldutxos: utxo::Ledger<legacy::OldAddress>,
     pub(crate) accounts: account::Ledger,
+    pub(crate) multisig: multisig::Ledger,
     pub(crate) settings: setting::Settings,
     pub(crate) delegation: DelegationState,
+    pub(crate) stake_distribution: Arc<StakeDistribution>,
+    pub(crate) rewards: Rewards,
+    pub(crate) updates: UpdateState,
     pub(crate) static_params: Arc<LedgerStaticParameters>,
     pub(crate) chain_length: ChainLength,
+    pub(crate) date: BlockDate,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Error {
     Config(config::Error),
+    Setting(setting::Error),
+    Update(update::Error),
     NotEnoughSignatures(usize, usize),
     UtxoValueNotMatching(Value, Value),
     UtxoError(utxo::Error),
@@ -68,6 +84,7 @@ pub enum Error {
     OldUtxoInvalidSignature(UtxoPointer, Output<legacy::OldAddress>, Witness),
     OldUtxoInvalidPublicKey(UtxoPointer, Output<legacy::OldAddress>, Witness),
     AccountInvalidSignature(account::Identifier, Witness),
+    Multisig(multisig::Error),
     TransactionHasNoInput,
     Block0OnlyMessageReceived,
     Block0TransactionHasInput,
@@ -75,9 +92,13 @@ pub enum Error {
     Block0TransactionHasWitnesses,
     Block0InitialMessageMissing,
     Block0InitialMessageNoConsensus,
+    Block0InvalidConfig(config::ConfigParamsError),
+    Block0UpdateNotAllowed,
     Block0UtxoTotalValueTooBig,
     UtxoInputsTotal(ValueError),
     UtxoOutputsTotal(ValueError),
+    FeeCalculationError,
+    Rewards(ValueError),
     Account(account::LedgerError),
     NotBalanced(Value, Value),
     ZeroOutput(Output<Address>),
@@ -85,8 +106,33 @@ pub enum Error {
     InvalidDiscrimination,
     ExpectingAccountWitness,
     ExpectingUtxoWitness,
+    UtxoOutputWithoutKey(UtxoPointer, Output<Address>),
     ExpectingInitialMessage,
+    ScriptOutputNotSupported(Output<Address>),
     CertificateInvalidSignature,
+    MaxBlockSizeExceeded(usize, u32),
+    ForkTooDeep(ChainLength, ChainLength),
+    BlockContentHashMismatch(BlockContentHash, BlockContentHash),
+    BlockContentSizeMismatch(u32, u32),
+    BlockTransactionsRootMismatch(BlockTransactionsRoot, BlockTransactionsRoot),
+    MetadataTooBig(usize, u16),
+    TooManyInputs(usize, u8),
+    TooManyOutputs(usize, u8),
+    TooManyWitnessSignatures(usize, u8),
+    TransactionTooBig(usize, u32),
+}
+
+/// Error of the verification of a block header, see `Ledger::verify_header`
+#[derive(Debug)]
+pub enum HeaderError {
+    /// the header does not refer to the expected parent
+    WrongParent(HeaderHash, HeaderHash),
+    /// the chain length of the header does not follow the one of its parent
+    WrongChainLength(ChainLength, ChainLength),
+    /// the date of the header is not after the date of its parent
+    NonIncreasingDate(BlockDate, BlockDate),
+    /// the leader of the block or its proof is invalid
+    Leadership(leadership::Error),
 }
 
 impl From<utxo::Error> for Error {
@@ -101,6 +147,12 @@ impl From<account::LedgerError> for Error {
     }
 }
 
+impl From<multisig::Error> for Error {
+    fn from(e: multisig::Error) -> Self {
+        Error::Multisig(e)
+    }
+}
+
 impl From<DelegationError> for Error {
     fn from(e: DelegationError) -> Self {
         Error::Delegation(e)
@@ -113,16 +165,33 @@ impl From<config::Error> for Error {
     }
 }
 
+impl From<setting::Error> for Error {
+    fn from(e: setting::Error) -> Self {
+        Error::Setting(e)
+    }
+}
+
+impl From<update::Error> for Error {
+    fn from(e: update::Error) -> Self {
+        Error::Update(e)
+    }
+}
+
 impl Ledger {
     fn empty(static_parameters: LedgerStaticParameters) -> Self {
         Ledger {
             utxos: utxo::Ledger::new(),
             oldutxos: utxo::Ledger::new(),
             accounts: account::Ledger::new(),
+            multisig: multisig::Ledger::new(),
             settings: setting::Settings::new(),
             delegation: DelegationState::new(),
+            stake_distribution: Arc::new(StakeDistribution::empty()),
+            rewards: Rewards::new(),
+            updates: UpdateState::new(),
             static_params: Arc::new(static_parameters),
             chain_length: ChainLength(0),
+            date: BlockDate::first(),
         }
     }
 
@@ -131,16 +200,17 @@ impl Ledger {
         I: IntoIterator<Item = &'a Message>,
     {
         let mut content_iter = contents.into_iter();
-        let mut ledger_params = LedgerParameters {
-            fees: LinearFee::new(0, 0, 0),
-            allow_account_creation: false,
-        };
+
+        let mut bft_leaders = Vec::new();
+        let mut settings_changes = Vec::new();
 
         let static_parameters = match content_iter.next() {
             Some(Message::Initial(ref ents)) => {
+                let config_params =
+                    ConfigParams::from_params(ents.iter()).map_err(Error::Block0InvalidConfig)?;
                 let mut params = LedgerStaticParameters::default();
                 let mut consensus = None;
-                for config in ents.iter() {
+                for config in config_params.iter() {
                     match config {
                         ConfigParam::Block0Date(block0_start_time) => {
                             params.block0_start_time = *block0_start_time
@@ -149,6 +219,22 @@ impl Ledger {
                             params.discrimination = *discrimination
                         }
                         ConfigParam::ConsensusVersion(version) => consensus = Some(*version),
+                        ConfigParam::AddBftLeader(leader_id) => bft_leaders.push(leader_id.clone()),
+                        ConfigParam::LinearFee(_)
+                        | ConfigParam::EpochStabilityDepth(_)
+                        | ConfigParam::MaxBlockSize(_)
+                        | ConfigParam::SlotDuration(_)
+                        | ConfigParam::BootstrapKeySlotsPercentage(_)
+                        | ConfigParam::AllowAccountCreation(_)
+                        | ConfigParam::TreasuryCut(_)
+                        | ConfigParam::PoolOwnersCut(_)
+                        | ConfigParam::GenesisPraosParamF(_)
+                        | ConfigParam::MaxMetadataSize(_)
+                        | ConfigParam::MaxTransactionInputs(_)
+                        | ConfigParam::MaxTransactionOutputs(_)
+                        | ConfigParam::MaxTransactionWitnesses(_)
+                        | ConfigParam::MaxTransactionSize(_)
+                        | ConfigParam::Unknown { .. } => settings_changes.push(config.clone()),
                     }
                 }
                 params.block0_consensus =
@@ -161,6 +247,9 @@ impl Ledger {
         }?;
 
         let mut ledger = Self::empty(static_parameters);
+        ledger.settings = ledger.settings.apply(&settings_changes)?;
+        ledger.settings.bft_leaders = Arc::new(bft_leaders);
+        let ledger_params = ledger.get_ledger_parameters();
 
         for content in content_iter {
             match content {
@@ -181,17 +270,17 @@ impl Ledger {
                     let (new_utxos, new_accounts) = internal_apply_transaction_output(
                         ledger.utxos,
                         ledger.accounts,
+                        &ledger.multisig,
                         &ledger.static_params,
                         &ledger_params,
                         &transaction_id,
                         &authenticated_tx.transaction.outputs,
                     )?;
                     ledger.utxos = new_utxos;
-                    ledger.accounts = new_accounts;;
+                    ledger.accounts = new_accounts;
                 }
-                Message::Update(update_proposal) => {
-                    ledger = ledger.apply_update(&update_proposal)?;
-                    ledger_params = ledger.get_ledger_parameters();
+                Message::UpdateProposal(_) | Message::UpdateVote(_) => {
+                    return Err(Error::Block0UpdateNotAllowed);
                 }
                 Message::Certificate(authenticated_cert_tx) => {
                     if authenticated_cert_tx.transaction.inputs.len() != 0 {
@@ -207,51 +296,281 @@ impl Ledger {
                         .delegation
                         .apply(&authenticated_cert_tx.transaction.extra)?;
                 }
+                Message::MultisigDeclaration(authenticated_decl_tx) => {
+                    if authenticated_decl_tx.transaction.inputs.len() != 0 {
+                        return Err(Error::Block0TransactionHasInput);
+                    }
+                    if authenticated_decl_tx.witnesses.len() != 0 {
+                        return Err(Error::Block0TransactionHasWitnesses);
+                    }
+                    if authenticated_decl_tx.transaction.outputs.len() != 0 {
+                        return Err(Error::Block0TransactionHasOutput);
+                    }
+                    ledger.multisig = ledger
+                        .multisig
+                        .add_declaration(&authenticated_decl_tx.transaction.extra)?;
+                }
             }
         }
 
         ledger.validate_utxo_total_value()?;
+        ledger.stake_distribution = Arc::new(ledger.compute_stake_distribution());
         Ok(ledger)
     }
 
+    /// verify the header of a block whose parent is `parent_id`, the block
+    /// of this state: the parent hash, the chain length, the date and the
+    /// leadership proof (the BFT signature, or the VRF proof and the KES
+    /// signature for Genesis Praos).
+    ///
+    /// This does not need the contents of the block, which are applied
+    /// with `apply_body` once the header is verified.
+    pub fn verify_header(
+        &self,
+        parent_id: &HeaderHash,
+        header: &Header,
+    ) -> Result<(), HeaderError> {
+        if header.block_parent_hash() != parent_id {
+            return Err(HeaderError::WrongParent(
+                parent_id.clone(),
+                header.block_parent_hash().clone(),
+            ));
+        }
+        let chain_length = self.chain_length.next();
+        if header.chain_length() != chain_length {
+            return Err(HeaderError::WrongChainLength(
+                chain_length,
+                header.chain_length(),
+            ));
+        }
+        if header.block_date() <= &self.date {
+            return Err(HeaderError::NonIncreasingDate(
+                self.date,
+                *header.block_date(),
+            ));
+        }
+        self.verify_leadership(header)
+    }
+
+    /// verify the leadership proof of the header against the leaders and
+    /// the stake distribution of this state, without checking how the
+    /// header links to its parent
+    pub fn verify_leadership(&self, header: &Header) -> Result<(), HeaderError> {
+        if self.settings.consensus_version == ConsensusVersion::Bft
+            && self.settings.bft_leaders.is_empty()
+        {
+            return Err(HeaderError::Leadership(leadership::Error::new(
+                leadership::ErrorKind::NoLeaderForThisSlot,
+            )));
+        }
+        Leadership::new(header.block_date().epoch, self)
+            .and_then(|leadership| leadership.verify(header).into_error())
+            .map_err(HeaderError::Leadership)
+    }
+
+    /// apply the contents of a block whose header was verified with
+    /// `verify_header`, once they match the hash, the size and the
+    /// transactions root of the header
+    pub fn apply_body<'a>(
+        &'a self,
+        ledger_params: &LedgerParameters,
+        header: &Header,
+        contents: &'a BlockContents,
+    ) -> Result<Self, Error> {
+        let (content_hash, content_size) = contents.compute_hash_size();
+        if &content_hash != header.block_content_hash() {
+            return Err(Error::BlockContentHashMismatch(
+                header.block_content_hash().clone(),
+                content_hash,
+            ));
+        }
+        if content_size != header.common.block_content_size as usize {
+            return Err(Error::BlockContentSizeMismatch(
+                header.common.block_content_size,
+                content_size as u32,
+            ));
+        }
+        let transactions_root = contents.compute_transactions_root();
+        if &transactions_root != header.block_transactions_root() {
+            return Err(Error::BlockTransactionsRootMismatch(
+                header.block_transactions_root().clone(),
+                transactions_root,
+            ));
+        }
+        self.apply_block(ledger_params, *header.block_date(), contents.iter())
+    }
+
     /// Try to apply messages to a State, and return the new State if succesful
+    ///
+    /// If the block is the first of a new epoch, the fees of the previous
+    /// epoch are distributed as rewards, the stake distribution snapshot
+    /// is recomputed from the state at the end of the previous epoch, then
+    /// the accepted update proposals are applied and the given
+    /// `ledger_params` are replaced by the ones of the updated settings.
+    ///
+    /// The witnesses of all the transactions of the block are verified
+    /// together once the messages are applied, see `WitnessBatch`.
     pub fn apply_block<'a, I>(
         &'a self,
         ledger_params: &LedgerParameters,
+        date: BlockDate,
+        contents: I,
+    ) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = &'a Message>,
+    {
+        self.apply_block_with_metrics(ledger_params, date, contents, &NoMetrics)
+    }
+
+    /// apply the block like `apply_block`, reporting the number of blocks
+    /// and messages applied and the time taken to the metrics
+    pub fn apply_block_with_metrics<'a, I>(
+        &'a self,
+        ledger_params: &LedgerParameters,
+        date: BlockDate,
+        contents: I,
+        metrics: &dyn Metrics,
+    ) -> Result<Self, Error>
+    where
+        I: IntoIterator<Item = &'a Message>,
+    {
+        let start = Instant::now();
+        let mut messages = 0;
+        let result = self.apply_messages(ledger_params, date, contents, &mut messages);
+        match result {
+            Ok(_) => {
+                metrics.counter(names::LEDGER_BLOCKS_APPLIED, 1);
+                metrics.counter(names::LEDGER_MESSAGES_APPLIED, messages);
+                metrics.histogram(names::LEDGER_APPLY_BLOCK_SECONDS, seconds(start.elapsed()));
+            }
+            Err(_) => metrics.counter(names::LEDGER_BLOCKS_REJECTED, 1),
+        }
+        result
+    }
+
+    fn apply_messages<'a, I>(
+        &'a self,
+        ledger_params: &LedgerParameters,
+        date: BlockDate,
         contents: I,
+        messages: &mut u64,
     ) -> Result<Self, Error>
     where
         I: IntoIterator<Item = &'a Message>,
     {
         let mut new_ledger = self.clone();
+        let mut ledger_params = ledger_params.clone();
 
         new_ledger.chain_length = self.chain_length.next();
 
+        if date.epoch > self.date.epoch {
+            new_ledger.rewards = self
+                .rewards
+                .distribute(
+                    &self.stake_distribution,
+                    &self.delegation.stake_pools,
+                    self.settings.treasury_cut,
+                    self.settings.pool_owners_cut,
+                )
+                .map_err(Error::Rewards)?;
+            new_ledger.stake_distribution = Arc::new(self.compute_stake_distribution());
+        }
+        let (updates, settings) =
+            new_ledger
+                .updates
+                .process_proposals(new_ledger.settings, self.date, date)?;
+        new_ledger.updates = updates;
+        new_ledger.settings = settings;
+        new_ledger.date = date;
+        if new_ledger.settings != self.settings {
+            ledger_params = new_ledger.get_ledger_parameters();
+        }
+
+        let max_block_size = new_ledger.settings.max_block_size;
+        let mut block_size = 0;
+        let mut witnesses = WitnessBatch::new();
+
         for content in contents {
+            *messages += 1;
+            block_size += content.to_raw().size_bytes_plus_size();
+            if block_size > max_block_size as usize {
+                return Err(Error::MaxBlockSizeExceeded(block_size, max_block_size));
+            }
             match content {
                 Message::Initial(_) => return Err(Error::Block0OnlyMessageReceived),
                 Message::OldUtxoDeclaration(_) => return Err(Error::Block0OnlyMessageReceived),
                 Message::Transaction(authenticated_tx) => {
-                    new_ledger = new_ledger.apply_transaction(&authenticated_tx, &ledger_params)?;
+                    new_ledger = new_ledger.apply_transaction_deferred(
+                        &authenticated_tx,
+                        &ledger_params,
+                        &mut witnesses,
+                    )?;
+                }
+                Message::UpdateProposal(proposal) => {
+                    new_ledger = new_ledger.apply_update_proposal(content.id(), &proposal)?;
                 }
-                Message::Update(update_proposal) => {
-                    new_ledger = new_ledger.apply_update(&update_proposal)?;
+                Message::UpdateVote(vote) => {
+                    new_ledger = new_ledger.apply_update_vote(&vote)?;
                 }
                 Message::Certificate(authenticated_cert_tx) => {
-                    new_ledger =
-                        new_ledger.apply_certificate(authenticated_cert_tx, &ledger_params)?;
+                    new_ledger = new_ledger.apply_certificate_deferred(
+                        authenticated_cert_tx,
+                        &ledger_params,
+                        &mut witnesses,
+                    )?;
+                }
+                Message::MultisigDeclaration(authenticated_decl_tx) => {
+                    new_ledger = new_ledger.apply_multisig_declaration_deferred(
+                        authenticated_decl_tx,
+                        &ledger_params,
+                        &mut witnesses,
+                    )?;
                 }
             }
         }
+        witnesses.verify()?;
         Ok(new_ledger)
     }
 
-    pub fn apply_transaction<Extra: property::Serialize>(
+    /// check a block whose parent is this state can still be applied when
+    /// the longest known chain is of the given length: the blocks deeper
+    /// than the epoch stability depth are final and cannot be forked from
+    pub fn check_fork_depth(&self, longest_chain: ChainLength) -> Result<(), Error> {
+        let depth = longest_chain.0.saturating_sub(self.chain_length.0);
+        if depth as usize > self.settings.epoch_stability_depth {
+            Err(Error::ForkTooDeep(self.chain_length, longest_chain))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn apply_transaction<Extra: property::Serialize + CertificateCount>(
+        self,
+        signed_tx: &AuthenticatedTransaction<Address, Extra>,
+        dyn_params: &LedgerParameters,
+    ) -> Result<Self, Error> {
+        let mut witnesses = WitnessBatch::new();
+        let ledger = self.apply_transaction_deferred(signed_tx, dyn_params, &mut witnesses)?;
+        witnesses.verify()?;
+        Ok(ledger)
+    }
+
+    /// apply the transaction, pushing the verification of its witnesses
+    /// in the given batch instead of verifying them. The returned state
+    /// is only valid once the batch is verified, so the deferred
+    /// functions are private to the ledger.
+    fn apply_transaction_deferred<Extra: property::Serialize + CertificateCount>(
         mut self,
         signed_tx: &AuthenticatedTransaction<Address, Extra>,
         dyn_params: &LedgerParameters,
+        witnesses: &mut WitnessBatch,
     ) -> Result<Self, Error> {
+        self.check_transaction_limits(signed_tx)?;
         let transaction_id = signed_tx.transaction.hash();
+        let fee = dyn_params
+            .fees
+            .calculate_for(&signed_tx.transaction)
+            .ok_or(Error::FeeCalculationError)?;
         self = internal_apply_transaction(
             self,
             dyn_params,
@@ -259,31 +578,147 @@ impl Ledger {
             &signed_tx.transaction.inputs[..],
             &signed_tx.transaction.outputs[..],
             &signed_tx.witnesses[..],
+            fee,
+            witnesses,
         )?;
+        self.rewards = self.rewards.add_fees(fee).map_err(Error::Rewards)?;
+        Ok(self)
+    }
+
+    /// check the transaction is within the limits of the settings, before
+    /// anything else is verified
+    fn check_transaction_limits<Extra: property::Serialize>(
+        &self,
+        signed_tx: &AuthenticatedTransaction<Address, Extra>,
+    ) -> Result<(), Error> {
+        let settings = &self.settings;
+        let transaction = &signed_tx.transaction;
+        if transaction.inputs.len() > settings.max_transaction_inputs as usize {
+            return Err(Error::TooManyInputs(
+                transaction.inputs.len(),
+                settings.max_transaction_inputs,
+            ));
+        }
+        if transaction.outputs.len() > settings.max_transaction_outputs as usize {
+            return Err(Error::TooManyOutputs(
+                transaction.outputs.len(),
+                settings.max_transaction_outputs,
+            ));
+        }
+        let signatures = signed_tx
+            .witnesses
+            .iter()
+            .map(|witness| match witness {
+                Witness::Multisig(signatures) => signatures.len(),
+                _ => 1,
+            })
+            .sum::<usize>();
+        if signatures > settings.max_transaction_witnesses as usize {
+            return Err(Error::TooManyWitnessSignatures(
+                signatures,
+                settings.max_transaction_witnesses,
+            ));
+        }
+        if let Some(metadata) = &transaction.metadata {
+            if metadata.size() > settings.max_metadata_size as usize {
+                return Err(Error::MetadataTooBig(
+                    metadata.size(),
+                    settings.max_metadata_size,
+                ));
+            }
+        }
+        // serializing in memory only fails if the extra does, in which case
+        // the transaction is rejected as well
+        let size = property::Serialize::serialize_as_vec(signed_tx)
+            .map(|bytes| bytes.len())
+            .unwrap_or(usize::max_value());
+        if size > settings.max_transaction_size as usize {
+            return Err(Error::TransactionTooBig(
+                size,
+                settings.max_transaction_size,
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn apply_update_proposal(
+        mut self,
+        proposal_id: update::ProposalId,
+        proposal: &SignedProposalCertificate,
+    ) -> Result<Self, Error> {
+        self.updates =
+            self.updates
+                .apply_proposal(proposal_id, proposal, &self.settings, self.date)?;
         Ok(self)
     }
 
-    pub fn apply_update(mut self, update: &setting::UpdateProposal) -> Result<Self, Error> {
-        self.settings = self.settings.apply(update);
+    pub fn apply_update_vote(mut self, vote: &SignedVoteCertificate) -> Result<Self, Error> {
+        self.updates = self.updates.apply_vote(vote, &self.settings)?;
         Ok(self)
     }
 
     pub fn apply_certificate(
+        self,
+        auth_cert: &AuthenticatedTransaction<Address, certificate::Certificate>,
+        dyn_params: &LedgerParameters,
+    ) -> Result<Self, Error> {
+        let mut witnesses = WitnessBatch::new();
+        let ledger = self.apply_certificate_deferred(auth_cert, dyn_params, &mut witnesses)?;
+        witnesses.verify()?;
+        Ok(ledger)
+    }
+
+    fn apply_certificate_deferred(
         mut self,
         auth_cert: &AuthenticatedTransaction<Address, certificate::Certificate>,
         dyn_params: &LedgerParameters,
+        witnesses: &mut WitnessBatch,
     ) -> Result<Self, Error> {
         let verified = auth_cert.transaction.extra.verify();
         if verified == chain_crypto::Verification::Failed {
             return Err(Error::CertificateInvalidSignature);
         };
-        self = self.apply_transaction(auth_cert, dyn_params)?;
+        self = self.apply_transaction_deferred(auth_cert, dyn_params, witnesses)?;
         self.delegation = self.delegation.apply(&auth_cert.transaction.extra)?;
         Ok(self)
     }
 
-    pub fn get_stake_distribution(&self) -> StakeDistribution {
-        stake::get_distribution(&self.delegation, &self.utxos)
+    /// register the multisig declaration carried by the transaction; the
+    /// declaration has to be registered before any output can be sent to
+    /// its identifier, the outputs of the declaring transaction included
+    pub fn apply_multisig_declaration(
+        self,
+        auth_decl: &AuthenticatedTransaction<Address, multisig::Declaration>,
+        dyn_params: &LedgerParameters,
+    ) -> Result<Self, Error> {
+        let mut witnesses = WitnessBatch::new();
+        let ledger =
+            self.apply_multisig_declaration_deferred(auth_decl, dyn_params, &mut witnesses)?;
+        witnesses.verify()?;
+        Ok(ledger)
+    }
+
+    fn apply_multisig_declaration_deferred(
+        mut self,
+        auth_decl: &AuthenticatedTransaction<Address, multisig::Declaration>,
+        dyn_params: &LedgerParameters,
+        witnesses: &mut WitnessBatch,
+    ) -> Result<Self, Error> {
+        self.multisig = self
+            .multisig
+            .add_declaration(&auth_decl.transaction.extra)?;
+        self.apply_transaction_deferred(auth_decl, dyn_params, witnesses)
+    }
+
+    /// the stake distribution snapshot taken at the beginning of the
+    /// current epoch, used for the leader selection of this epoch
+    pub fn get_stake_distribution(&self) -> &StakeDistribution {
+        &self.stake_distribution
+    }
+
+    /// compute the stake distribution of the current state of the ledger
+    pub fn compute_stake_distribution(&self) -> StakeDistribution {
+        stake::get_distribution(&self.delegation, &self.accounts, &self.utxos)
     }
 
     /// access the ledger static parameters
@@ -307,10 +742,48 @@ impl Ledger {
         self.utxos.iter()
     }
 
+    /// the balances and spending counters of the accounts
+    pub fn accounts(&self) -> &account::Ledger {
+        &self.accounts
+    }
+
+    /// the unspent outputs owned by the given address
+    pub fn utxos_by_address<'a>(
+        &'a self,
+        address: &'a Address,
+    ) -> impl Iterator<Item = utxo::Entry<'a, Address>> {
+        self.utxos
+            .iter()
+            .filter(move |entry| &entry.output.address == address)
+    }
+
+    /// the total value of the unspent outputs owned by the given address
+    pub fn address_total_value(&self, address: &Address) -> Result<Value, ValueError> {
+        Value::sum(
+            self.utxos_by_address(address)
+                .map(|entry| entry.output.value),
+        )
+    }
+
+    /// look up the unspent output at the given index of the transaction
+    pub fn get_utxo<'a>(
+        &'a self,
+        transaction_id: &TransactionId,
+        index: TransactionIndex,
+    ) -> Option<utxo::Entry<'a, Address>> {
+        self.utxos.get(transaction_id, &index)
+    }
+
     pub fn chain_length(&self) -> ChainLength {
         self.chain_length
     }
 
+    /// fees collected since the beginning of the epoch, the rewards and
+    /// the treasury
+    pub fn rewards(&self) -> &Rewards {
+        &self.rewards
+    }
+
     fn validate_utxo_total_value(&self) -> Result<(), Error> {
         let old_utxo_values = self.oldutxos.iter().map(|entry| entry.output.value);
         let new_utxo_values = self.utxos.iter().map(|entry| entry.output.value);
@@ -352,11 +825,9 @@ fn internal_apply_transaction(
     inputs: &[Input],
     outputs: &[Output<Address>],
     witnesses: &[Witness],
+    fee: Value,
+    batch: &mut WitnessBatch,
 ) -> Result<Ledger, Error> {
-    assert!(inputs.len() < 255);
-    assert!(outputs.len() < 255);
-    assert!(witnesses.len() < 255);
-
     if inputs.len() == 0 {
         return Err(Error::TransactionHasNoInput);
     }
@@ -368,11 +839,11 @@ fn internal_apply_transaction(
     }
 
     // 2. validate inputs of transaction by gathering what we know of it,
-    // then verifying the associated witness
+    // then deferring the verification of the associated witness
     for (input, witness) in inputs.iter().zip(witnesses.iter()) {
         match input.to_enum() {
             InputEnum::UtxoInput(utxo) => {
-                ledger = input_utxo_verify(ledger, transaction_id, &utxo, witness)?
+                ledger = input_utxo_verify(ledger, transaction_id, &utxo, witness, batch)?
             }
             InputEnum::AccountInput(account_id, value) => {
                 ledger.accounts = input_account_verify(
@@ -381,17 +852,17 @@ fn internal_apply_transaction(
                     &account_id,
                     value,
                     witness,
+                    batch,
                 )?
             }
         }
     }
 
-    // 3. verify that transaction sum is zero.
-    // TODO: with fees this will change
+    // 3. verify that the inputs pay for the outputs and the fee
     let total_input =
         Value::sum(inputs.iter().map(|i| i.value)).map_err(|e| Error::UtxoInputsTotal(e))?;
-    let total_output =
-        Value::sum(inputs.iter().map(|i| i.value)).map_err(|e| Error::UtxoOutputsTotal(e))?;
+    let total_output = Value::sum(outputs.iter().map(|o| o.value).chain(Some(fee)))
+        .map_err(|e| Error::UtxoOutputsTotal(e))?;
     if total_input != total_output {
         return Err(Error::NotBalanced(total_input, total_output));
     }
@@ -400,6 +871,7 @@ fn internal_apply_transaction(
     let (new_utxos, new_accounts) = internal_apply_transaction_output(
         ledger.utxos,
         ledger.accounts,
+        &ledger.multisig,
         &ledger.static_params,
         dyn_params,
         transaction_id,
@@ -414,6 +886,7 @@ fn internal_apply_transaction(
 fn internal_apply_transaction_output(
     mut utxos: utxo::Ledger<Address>,
     mut accounts: account::Ledger,
+    multisig: &multisig::Ledger,
     static_params: &LedgerStaticParameters,
     dyn_params: &LedgerParameters,
     transaction_id: &TransactionId,
@@ -433,6 +906,14 @@ fn internal_apply_transaction_output(
             Kind::Single(_) | Kind::Group(_, _) => {
                 new_utxos.push((index as u8, output.clone()));
             }
+            Kind::Multisig(identifier) => {
+                multisig.get_declaration(&(*identifier).into())?;
+                new_utxos.push((index as u8, output.clone()));
+            }
+            Kind::Script(_) => {
+                // no witness can spend a script output yet
+                return Err(Error::ScriptOutputNotSupported(output.clone()));
+            }
             Kind::Account(identifier) => {
                 // don't have a way to make a newtype ref from the ref so .clone()
                 let account = identifier.clone().into();
@@ -458,6 +939,7 @@ fn input_utxo_verify(
     transaction_id: &TransactionId,
     utxo: &UtxoPointer,
     witness: &Witness,
+    batch: &mut WitnessBatch,
 ) -> Result<Ledger, Error> {
     match witness {
         Witness::Account(_) => return Err(Error::ExpectingUtxoWitness),
@@ -482,14 +964,11 @@ fn input_utxo_verify(
                 ));
             };
 
-            let verified = signature.verify(&xpub, &transaction_id);
-            if verified == chain_crypto::Verification::Failed {
-                return Err(Error::OldUtxoInvalidSignature(
-                    utxo.clone(),
-                    associated_output.clone(),
-                    witness.clone(),
-                ));
-            };
+            let error =
+                Error::OldUtxoInvalidSignature(utxo.clone(), associated_output, witness.clone());
+            let (xpub, signature, transaction_id) =
+                (xpub.clone(), signature.clone(), *transaction_id);
+            batch.push(error, move || signature.verify(&xpub, &transaction_id));
 
             Ok(ledger)
         }
@@ -505,17 +984,65 @@ fn input_utxo_verify(
                 ));
             }
 
-            let verified = signature.verify(
-                &associated_output.address.public_key().unwrap(),
-                &transaction_id,
-            );
-            if verified == chain_crypto::Verification::Failed {
-                return Err(Error::UtxoInvalidSignature(
-                    utxo.clone(),
-                    associated_output.clone(),
-                    witness.clone(),
+            let public_key = match associated_output.address.public_key() {
+                Some(public_key) => public_key.clone(),
+                None => return Err(Error::UtxoOutputWithoutKey(utxo.clone(), associated_output)),
+            };
+            let error =
+                Error::UtxoInvalidSignature(utxo.clone(), associated_output, witness.clone());
+            batch.push_spending(error, public_key, transaction_id, signature.clone());
+            Ok(ledger)
+        }
+        Witness::Multisig(signatures) => {
+            let (new_utxos, associated_output) = ledger
+                .utxos
+                .remove(&utxo.transaction_id, utxo.output_index)?;
+            ledger.utxos = new_utxos;
+            if utxo.value != associated_output.value {
+                return Err(Error::UtxoValueNotMatching(
+                    utxo.value,
+                    associated_output.value,
                 ));
+            }
+
+            let identifier = match associated_output.address.kind() {
+                Kind::Multisig(identifier) => multisig::Identifier::from(*identifier),
+                _ => return Err(Error::ExpectingUtxoWitness),
             };
+            let declaration = ledger.multisig.get_declaration(&identifier)?;
+            declaration.check_signers(signatures)?;
+            for (index, signature) in signatures {
+                let error = Error::Multisig(multisig::Error::InvalidSignature(*index));
+                let owner = declaration.owners[*index as usize].clone();
+                batch.push_spending(error, owner, transaction_id, signature.clone());
+            }
+            Ok(ledger)
+        }
+        Witness::MultisigAggregated(signers, signature) => {
+            let (new_utxos, associated_output) = ledger
+                .utxos
+                .remove(&utxo.transaction_id, utxo.output_index)?;
+            ledger.utxos = new_utxos;
+            if utxo.value != associated_output.value {
+                return Err(Error::UtxoValueNotMatching(
+                    utxo.value,
+                    associated_output.value,
+                ));
+            }
+
+            let identifier = match associated_output.address.kind() {
+                Kind::Multisig(identifier) => multisig::Identifier::from(*identifier),
+                _ => return Err(Error::ExpectingUtxoWitness),
+            };
+            let declaration = ledger.multisig.get_declaration(&identifier)?;
+            let key = declaration.aggregate_key(signers)?;
+            let error = Error::Multisig(multisig::Error::InvalidAggregatedSignature);
+            batch.push_spending(
+                error,
+                key.public_key().clone(),
+                transaction_id,
+                signature.clone(),
+            );
             Ok(ledger)
         }
     }
@@ -527,6 +1054,7 @@ fn input_account_verify(
     account: &account::Identifier,
     value: Value,
     witness: &Witness,
+    batch: &mut WitnessBatch,
 ) -> Result<account::Ledger, Error> {
     // .remove_value() check if there's enough value and if not, returns a Err.
     let (new_ledger, spending_counter) = ledger.remove_value(account, value)?;
@@ -535,15 +1063,12 @@ fn input_account_verify(
     match witness {
         Witness::OldUtxo(_, _) => return Err(Error::ExpectingAccountWitness),
         Witness::Utxo(_) => return Err(Error::ExpectingAccountWitness),
+        Witness::Multisig(_) => return Err(Error::ExpectingAccountWitness),
+        Witness::MultisigAggregated(_, _) => return Err(Error::ExpectingAccountWitness),
         Witness::Account(sig) => {
             let tidsc = TransactionIdSpendingCounter::new(transaction_id, &spending_counter);
-            let verified = sig.verify(&account.clone().into(), &tidsc);
-            if verified == chain_crypto::Verification::Failed {
-                return Err(Error::AccountInvalidSignature(
-                    account.clone(),
-                    witness.clone(),
-                ));
-            };
+            let error = Error::AccountInvalidSignature(account.clone(), witness.clone());
+            batch.push_spending(error, account.clone().into(), &tidsc, sig.clone());
             Ok(ledger)
         }
     }
@@ -556,13 +1081,112 @@ impl std::fmt::Display for Error {
 }
 impl std::error::Error for Error {}
 
+impl std::fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            HeaderError::WrongParent(expected, actual) => write!(
+                f,
+                "Block header refers to the parent {} instead of {}",
+                actual, expected
+            ),
+            HeaderError::WrongChainLength(expected, actual) => write!(
+                f,
+                "Block header has the chain length {} instead of {}",
+                actual, expected
+            ),
+            HeaderError::NonIncreasingDate(parent, date) => write!(
+                f,
+                "Block header date {} is not after its parent date {}",
+                date, parent
+            ),
+            HeaderError::Leadership(error) => write!(f, "Invalid block leader: {}", error),
+        }
+    }
+}
+impl std::error::Error for HeaderError {}
+
 #[cfg(test)]
 pub mod test {
     use super::*;
+    use crate::block::BlockBuilder;
     use crate::key::{SpendingPublicKey, SpendingSecretKey};
+    use crate::leadership::bft;
     use crate::message::initial;
+    use crate::update::{ProposalCertificate, VoteCertificate};
     use chain_addr::{Address, Discrimination, Kind};
+    use chain_core::property::Message as _;
     use rand::{CryptoRng, RngCore};
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    /// A random generator with a fixed seed, so that a failing test
+    /// reproduces with the same keys
+    pub fn test_rng() -> ChaChaRng {
+        ChaChaRng::from_seed([42; 32])
+    }
+
+    /// A quickcheck generator with a fixed seed, independent of `test_rng`
+    pub fn test_gen() -> quickcheck::StdGen<ChaChaRng> {
+        quickcheck::StdGen::new(ChaChaRng::from_seed([43; 32]), 10)
+    }
+
+    pub fn test_block0_hash() -> HeaderHash {
+        HeaderHash::hash_bytes(&[1, 2, 3])
+    }
+
+    /// The parameters of a BFT test ledger with the test discrimination,
+    /// the tests pushing their own parameters on top
+    pub fn test_initial_ents() -> initial::InitialEnts {
+        let mut ie = initial::InitialEnts::new();
+        ie.push(ConfigParam::Discrimination(Discrimination::Test));
+        ie.push(ConfigParam::ConsensusVersion(ConsensusVersion::Bft));
+        ie
+    }
+
+    /// The block0 transaction creating the given outputs, with the
+    /// pointers to them
+    pub fn block0_funds(outputs: Vec<Output<Address>>) -> (Message, Vec<UtxoPointer>) {
+        let transaction = Transaction {
+            inputs: vec![],
+            outputs,
+            extra: NoExtra,
+            metadata: None,
+        };
+        let transaction_id = transaction.hash();
+        let utxos = transaction
+            .outputs
+            .iter()
+            .enumerate()
+            .map(|(output_index, output)| UtxoPointer {
+                transaction_id,
+                output_index: output_index as u8,
+                value: output.value,
+            })
+            .collect();
+        let message = Message::Transaction(AuthenticatedTransaction {
+            transaction,
+            witnesses: vec![],
+        });
+        (message, utxos)
+    }
+
+    /// The ledger of the block0 made of the initial parameters and of the
+    /// transaction creating the given outputs, with the pointers to them
+    pub fn test_ledger(
+        ie: initial::InitialEnts,
+        outputs: Vec<Output<Address>>,
+    ) -> (Ledger, Vec<UtxoPointer>) {
+        let (funds, utxos) = block0_funds(outputs);
+        let ledger = Ledger::new(test_block0_hash(), &[Message::Initial(ie), funds]).unwrap();
+        (ledger, utxos)
+    }
+
+    pub fn output(address: &Address, value: u64) -> Output<Address> {
+        Output {
+            address: address.clone(),
+            value: Value(value),
+        }
+    }
 
     pub fn make_key<R: RngCore + CryptoRng>(
         rng: &mut R,
@@ -598,40 +1222,14 @@ pub mod test {
 
     #[test]
     pub fn utxo() -> () {
-        let block0_hash = HeaderHash::hash_bytes(&[1, 2, 3]);
         let discrimination = Discrimination::Test;
-        let mut ie = initial::InitialEnts::new();
-        ie.push(ConfigParam::Discrimination(Discrimination::Test));
-        ie.push(ConfigParam::ConsensusVersion(ConsensusVersion::Bft));
-
-        let mut rng = rand::thread_rng();
+        let mut rng = test_rng();
         let (sk1, _pk1, user1_address) = make_key(&mut rng, &discrimination);
         let (_sk2, _pk2, user2_address) = make_key(&mut rng, &discrimination);
         let value = Value(42000);
 
-        let output0 = Output {
-            address: user1_address.clone(),
-            value: value,
-        };
-
-        let first_trans = AuthenticatedTransaction {
-            transaction: Transaction {
-                inputs: vec![],
-                outputs: vec![output0],
-                extra: NoExtra,
-            },
-            witnesses: vec![],
-        };
-        let tx0_id = first_trans.transaction.hash();
-
-        let utxo0 = UtxoPointer {
-            transaction_id: tx0_id,
-            output_index: 0,
-            value: value,
-        };
-
-        let messages = [Message::Initial(ie), Message::Transaction(first_trans)];
-        let ledger = Ledger::new(block0_hash, &messages).unwrap();
+        let (ledger, utxos) = test_ledger(test_initial_ents(), vec![output(&user1_address, 42000)]);
+        let utxo0 = utxos[0];
         let dyn_params = ledger.get_ledger_parameters();
 
         {
@@ -643,6 +1241,7 @@ pub mod test {
                     value: Value(1),
                 }],
                 extra: NoExtra,
+                metadata: None,
             };
             let signed_tx = AuthenticatedTransaction {
                 transaction: tx,
@@ -658,12 +1257,13 @@ pub mod test {
                 inputs: vec![Input::from_utxo(utxo0)],
                 outputs: vec![Output {
                     address: user2_address.clone(),
-                    value: Value(1),
+                    value: value,
                 }],
                 extra: NoExtra,
+                metadata: None,
             };
             let txid = tx.hash();
-            let w1 = Witness::new_utxo(&txid, &sk1);
+            let w1 = Witness::new_utxo(&txid, &sk1).unwrap();
             let signed_tx = AuthenticatedTransaction {
                 transaction: tx,
                 witnesses: vec![w1],
@@ -672,4 +1272,793 @@ pub mod test {
             assert!(r.is_ok())
         }
     }
+
+    #[test]
+    pub fn utxo_spent_with_external_signer() {
+        use crate::transaction::witness::test::DeviceSigner;
+
+        let discrimination = Discrimination::Test;
+        let mut rng = test_rng();
+        let (sk1, _pk1, user1_address) = make_key(&mut rng, &discrimination);
+        let (_sk2, _pk2, user2_address) = make_key(&mut rng, &discrimination);
+        let value = Value(42000);
+
+        let (ledger, utxos) = test_ledger(test_initial_ents(), vec![output(&user1_address, 42000)]);
+        let dyn_params = ledger.get_ledger_parameters();
+
+        let tx = Transaction {
+            inputs: vec![Input::from_utxo(utxos[0])],
+            outputs: vec![Output {
+                address: user2_address,
+                value: value,
+            }],
+            extra: NoExtra,
+            metadata: None,
+        };
+        let txid = tx.hash();
+
+        // the device holding the key refuses to sign: no witness
+        let rejected = Witness::new_utxo(&txid, &DeviceSigner::rejecting(sk1.clone()));
+        assert_eq!(rejected.unwrap_err(), chain_crypto::SignerError::Rejected);
+
+        // the signature returned by the device is verified by the ledger
+        // like the one of the in memory key
+        let witness = Witness::new_utxo(&txid, &DeviceSigner::new(sk1.clone())).unwrap();
+        assert_eq!(witness, Witness::new_utxo(&txid, &sk1).unwrap());
+        let signed_tx = AuthenticatedTransaction {
+            transaction: tx,
+            witnesses: vec![witness],
+        };
+        let ledger = ledger.apply_transaction(&signed_tx, &dyn_params).unwrap();
+        assert!(ledger.utxos().find(|o| o.output.value == value).is_some());
+    }
+
+    #[test]
+    pub fn utxo_queries() {
+        let discrimination = Discrimination::Test;
+        let mut rng = test_rng();
+        let (_sk1, _pk1, user1_address) = make_key(&mut rng, &discrimination);
+        let (_sk2, _pk2, user2_address) = make_key(&mut rng, &discrimination);
+        let (_sk3, _pk3, user3_address) = make_key(&mut rng, &discrimination);
+        let (ledger, utxos) = test_ledger(
+            test_initial_ents(),
+            vec![
+                output(&user1_address, 10),
+                output(&user2_address, 20),
+                output(&user1_address, 30),
+            ],
+        );
+        let tx0_id = utxos[0].transaction_id;
+
+        let mut indexes: Vec<_> = ledger
+            .utxos_by_address(&user1_address)
+            .map(|entry| entry.output_index)
+            .collect();
+        indexes.sort();
+        assert_eq!(indexes, vec![0, 2]);
+        assert_eq!(ledger.utxos_by_address(&user3_address).count(), 0);
+
+        assert_eq!(ledger.address_total_value(&user1_address), Ok(Value(40)));
+        assert_eq!(ledger.address_total_value(&user2_address), Ok(Value(20)));
+        assert_eq!(
+            ledger.address_total_value(&user3_address),
+            Ok(Value::zero())
+        );
+
+        let entry = ledger.get_utxo(&tx0_id, 1).unwrap();
+        assert_eq!(entry.output, &output(&user2_address, 20));
+        assert!(ledger.get_utxo(&tx0_id, 3).is_none());
+    }
+
+    #[test]
+    pub fn fees_collected_and_distributed() {
+        let discrimination = Discrimination::Test;
+        let mut ie = test_initial_ents();
+        ie.push(ConfigParam::LinearFee(LinearFee::new(10, 0, 0)));
+
+        let mut rng = test_rng();
+        let (sk1, _pk1, user1_address) = make_key(&mut rng, &discrimination);
+        let (_sk2, _pk2, user2_address) = make_key(&mut rng, &discrimination);
+        let (ledger, utxos) = test_ledger(ie, vec![output(&user1_address, 100)]);
+        let utxo0 = utxos[0];
+
+        let spend = |output_value| {
+            let tx = Transaction {
+                inputs: vec![Input::from_utxo(utxo0)],
+                outputs: vec![Output {
+                    address: user2_address.clone(),
+                    value: output_value,
+                }],
+                extra: NoExtra,
+                metadata: None,
+            };
+            let witness = Witness::new_utxo(&tx.hash(), &sk1).unwrap();
+            Message::Transaction(AuthenticatedTransaction {
+                transaction: tx,
+                witnesses: vec![witness],
+            })
+        };
+
+        let date = BlockDate::first().next();
+        assert_err!(
+            Error::NotBalanced(Value(100), Value(110)),
+            ledger.apply_block(&ledger.get_ledger_parameters(), date, &[spend(Value(100))])
+        );
+        let ledger = ledger
+            .apply_block(&ledger.get_ledger_parameters(), date, &[spend(Value(90))])
+            .unwrap();
+        assert_eq!(ledger.rewards().epoch_fees(), Value(10));
+        assert_eq!(ledger.rewards().treasury(), Value::zero());
+
+        // nothing is staked, the fees go to the treasury
+        let ledger = ledger
+            .apply_block(&ledger.get_ledger_parameters(), date.next_epoch(), &[])
+            .unwrap();
+        assert_eq!(ledger.rewards().epoch_fees(), Value::zero());
+        assert_eq!(ledger.rewards().treasury(), Value(10));
+    }
+
+    #[test]
+    pub fn block0_linear_fees() {
+        let block0_hash = test_block0_hash();
+        let mut ie = test_initial_ents();
+
+        let messages = [Message::Initial(ie.clone())];
+        let ledger = Ledger::new(block0_hash, &messages).unwrap();
+        assert_eq!(ledger.get_ledger_parameters().fees, LinearFee::new(0, 0, 0));
+
+        let fees = LinearFee::new(10, 2, 100);
+        ie.push(ConfigParam::LinearFee(fees));
+        let messages = [Message::Initial(ie)];
+        let ledger = Ledger::new(block0_hash, &messages).unwrap();
+        assert_eq!(ledger.get_ledger_parameters().fees, fees);
+        assert_eq!(ledger.settings.linear_fees(), fees);
+    }
+
+    #[test]
+    pub fn transaction_metadata_size_limit() {
+        let discrimination = Discrimination::Test;
+        let mut ie = test_initial_ents();
+        ie.push(ConfigParam::MaxMetadataSize(config::MaxMetadataSize(8)));
+
+        let mut rng = test_rng();
+        let (sk1, _pk1, user1_address) = make_key(&mut rng, &discrimination);
+        let (_sk2, _pk2, user2_address) = make_key(&mut rng, &discrimination);
+        let (ledger, utxos) = test_ledger(ie, vec![output(&user1_address, 100)]);
+        let utxo0 = utxos[0];
+
+        // a CBOR byte string of the given length
+        let spend = |len: u8| {
+            let mut bytes = vec![0x40 | len];
+            bytes.extend(std::iter::repeat(0xaa).take(len as usize));
+            let tx = Transaction {
+                inputs: vec![Input::from_utxo(utxo0)],
+                outputs: vec![Output {
+                    address: user2_address.clone(),
+                    value: Value(100),
+                }],
+                extra: NoExtra,
+                metadata: Some(Metadata::new(bytes).unwrap()),
+            };
+            let witness = Witness::new_utxo(&tx.hash(), &sk1).unwrap();
+            AuthenticatedTransaction {
+                transaction: tx,
+                witnesses: vec![witness],
+            }
+        };
+
+        let date = BlockDate::first().next();
+        assert_err!(
+            Error::MetadataTooBig(11, 8),
+            ledger.apply_block(
+                &ledger.get_ledger_parameters(),
+                date,
+                &[Message::Transaction(spend(10))]
+            )
+        );
+        let tx = spend(7);
+        let id = tx.transaction.hash();
+        let message = Message::Transaction(tx);
+        let ledger = ledger
+            .apply_block(&ledger.get_ledger_parameters(), date, &[message.clone()])
+            .unwrap();
+        assert_eq!(ledger.utxos().count(), 1);
+
+        let contents = crate::block::BlockContents::new(vec![message]);
+        assert_eq!(contents.transaction_metadata(&id).unwrap().size(), 8);
+    }
+
+    #[test]
+    pub fn transaction_limits() {
+        let discrimination = Discrimination::Test;
+        let mut ie = test_initial_ents();
+        ie.push(ConfigParam::MaxTransactionInputs(
+            config::MaxTransactionInputs(1),
+        ));
+        ie.push(ConfigParam::MaxTransactionOutputs(
+            config::MaxTransactionOutputs(2),
+        ));
+
+        let mut rng = test_rng();
+        let (sk1, _pk1, user1_address) = make_key(&mut rng, &discrimination);
+        let (_sk2, _pk2, user2_address) = make_key(&mut rng, &discrimination);
+        let (ledger, utxos) = test_ledger(
+            ie,
+            vec![output(&user1_address, 1020), output(&user1_address, 1020)],
+        );
+        let dyn_params = ledger.get_ledger_parameters();
+
+        let spend = |inputs: &[UtxoPointer], outputs: usize| {
+            let total = 1020 * inputs.len() as u64;
+            let tx = Transaction {
+                inputs: inputs.iter().map(|utxo| Input::from_utxo(*utxo)).collect(),
+                outputs: (0..outputs)
+                    .map(|_| Output {
+                        address: user2_address.clone(),
+                        value: Value(total / outputs as u64),
+                    })
+                    .collect(),
+                extra: NoExtra,
+                metadata: None,
+            };
+            let witnesses = inputs
+                .iter()
+                .map(|_| Witness::new_utxo(&tx.hash(), &sk1).unwrap())
+                .collect();
+            AuthenticatedTransaction {
+                transaction: tx,
+                witnesses,
+            }
+        };
+
+        assert_err!(
+            Error::TooManyInputs(2, 1),
+            ledger
+                .clone()
+                .apply_transaction(&spend(&utxos, 1), &dyn_params)
+        );
+        assert_err!(
+            Error::TooManyOutputs(3, 2),
+            ledger
+                .clone()
+                .apply_transaction(&spend(&utxos[..1], 3), &dyn_params)
+        );
+
+        let tx = spend(&utxos[..1], 2);
+        let size = property::Serialize::serialize_as_vec(&tx).unwrap().len();
+        let mut settings = ledger.settings.clone();
+        settings.max_transaction_witnesses = 0;
+        let limited = Ledger {
+            settings,
+            ..ledger.clone()
+        };
+        assert_err!(
+            Error::TooManyWitnessSignatures(1, 0),
+            limited.apply_transaction(&tx, &dyn_params)
+        );
+        let mut settings = ledger.settings.clone();
+        settings.max_transaction_size = size as u32 - 1;
+        let limited = Ledger {
+            settings,
+            ..ledger.clone()
+        };
+        assert_err!(
+            Error::TransactionTooBig(size, size as u32 - 1),
+            limited.apply_transaction(&tx, &dyn_params)
+        );
+        // the limits are checked before the transaction is applied, so a
+        // transaction at the widest limits is accepted
+        let mut settings = ledger.settings.clone();
+        settings.max_transaction_outputs = u8::max_value();
+        let widest = Ledger {
+            settings,
+            ..ledger.clone()
+        };
+        let outputs = u8::max_value() as usize;
+        let widest = widest
+            .apply_transaction(&spend(&utxos[..1], outputs), &dyn_params)
+            .unwrap();
+        assert_eq!(widest.utxos().count(), outputs + 1);
+
+        let ledger = ledger.apply_transaction(&tx, &dyn_params).unwrap();
+        assert_eq!(ledger.utxos().count(), 3);
+    }
+
+    pub fn make_leader<R: RngCore + CryptoRng>(rng: &mut R) -> (bft::SigningKey, bft::LeaderId) {
+        let sk = bft::SigningKey::generate(rng);
+        let leader_id = bft::LeaderId::from(sk.to_public());
+        (sk, leader_id)
+    }
+
+    #[test]
+    pub fn block_max_size() {
+        let mut rng = test_rng();
+        let (leader_key, leader_id) = make_leader(&mut rng);
+
+        let update = Message::UpdateProposal(
+            ProposalCertificate {
+                changes: vec![],
+                proposer_id: leader_id.clone(),
+            }
+            .sign(&leader_key),
+        );
+        let size = update.to_raw().size_bytes_plus_size();
+
+        let mut ie = test_initial_ents();
+        ie.push(ConfigParam::AddBftLeader(leader_id));
+        ie.push(ConfigParam::MaxBlockSize(config::MaxBlockSize(size as u32)));
+
+        let messages = [Message::Initial(ie)];
+        let ledger = Ledger::new(test_block0_hash(), &messages).unwrap();
+        let dyn_params = ledger.get_ledger_parameters();
+        let date = BlockDate::first().next();
+
+        assert!(ledger
+            .apply_block(&dyn_params, date, &[update.clone()])
+            .is_ok());
+        assert_err!(
+            Error::MaxBlockSizeExceeded(size * 2, size as u32),
+            ledger.apply_block(&dyn_params, date, &[update.clone(), update])
+        );
+    }
+
+    #[test]
+    pub fn block_witnesses_verified() {
+        let discrimination = Discrimination::Test;
+        let mut rng = test_rng();
+        let (sk1, _pk1, user1_address) = make_key(&mut rng, &discrimination);
+        let (sk2, _pk2, user2_address) = make_key(&mut rng, &discrimination);
+        let value = Value(1000);
+        let output = output(&user1_address, 1000);
+
+        let (ledger, utxos) =
+            test_ledger(test_initial_ents(), vec![output.clone(), output.clone()]);
+        let dyn_params = ledger.get_ledger_parameters();
+        let date = BlockDate::first().next();
+
+        let spend = |output_index: usize, sk: &SpendingSecretKey| {
+            let utxo = utxos[output_index];
+            let tx = Transaction {
+                inputs: vec![Input::from_utxo(utxo)],
+                outputs: vec![Output {
+                    address: user2_address.clone(),
+                    value,
+                }],
+                extra: NoExtra,
+                metadata: None,
+            };
+            let witness = Witness::new_utxo(&tx.hash(), sk).unwrap();
+            let signed_tx = AuthenticatedTransaction {
+                transaction: tx,
+                witnesses: vec![witness.clone()],
+            };
+            (utxo, witness, Message::Transaction(signed_tx))
+        };
+
+        let (_, _, tx0) = spend(0, &sk1);
+        let (_, _, tx1) = spend(1, &sk1);
+        let (utxo1, invalid_witness, invalid_tx1) = spend(1, &sk2);
+
+        assert!(ledger
+            .apply_block(&dyn_params, date, &[tx0.clone(), tx1])
+            .is_ok());
+        assert_err!(
+            Error::UtxoInvalidSignature(utxo1, output, invalid_witness),
+            ledger.apply_block(&dyn_params, date, &[tx0, invalid_tx1])
+        );
+    }
+
+    #[test]
+    pub fn update_applied_at_epoch_boundary() {
+        let block0_hash = test_block0_hash();
+        let mut rng = test_rng();
+        let (leader0_key, leader0_id) = make_leader(&mut rng);
+        let (leader1_key, leader1_id) = make_leader(&mut rng);
+
+        let mut ie = test_initial_ents();
+        ie.push(ConfigParam::AddBftLeader(leader0_id.clone()));
+        ie.push(ConfigParam::AddBftLeader(leader1_id.clone()));
+
+        let fees = LinearFee::new(10, 2, 100);
+        let proposal = Message::UpdateProposal(
+            ProposalCertificate {
+                changes: vec![ConfigParam::LinearFee(fees)],
+                proposer_id: leader0_id,
+            }
+            .sign(&leader0_key),
+        );
+        let vote = Message::UpdateVote(
+            VoteCertificate {
+                proposal_id: proposal.id(),
+                voter_id: leader1_id,
+            }
+            .sign(&leader1_key),
+        );
+
+        assert_err!(
+            Error::Block0UpdateNotAllowed,
+            Ledger::new(
+                block0_hash,
+                &[Message::Initial(ie.clone()), proposal.clone()]
+            )
+        );
+
+        let ledger = Ledger::new(block0_hash, &[Message::Initial(ie)]).unwrap();
+        let date = BlockDate::first().next();
+        let ledger = ledger
+            .apply_block(&ledger.get_ledger_parameters(), date, &[proposal])
+            .unwrap();
+        let date = date.next();
+        let ledger = ledger
+            .apply_block(&ledger.get_ledger_parameters(), date, &[vote])
+            .unwrap();
+        assert_eq!(ledger.get_ledger_parameters().fees, LinearFee::new(0, 0, 0));
+
+        let date = date.next_epoch();
+        let ledger = ledger
+            .apply_block(&ledger.get_ledger_parameters(), date, &[])
+            .unwrap();
+        assert_eq!(ledger.get_ledger_parameters().fees, fees);
+        assert!(ledger.updates.proposals.is_empty());
+    }
+
+    fn block0_certificate(content: certificate::CertificateContent) -> Message {
+        Message::Certificate(AuthenticatedTransaction {
+            transaction: Transaction {
+                inputs: vec![],
+                outputs: vec![],
+                extra: certificate::Certificate {
+                    content,
+                    signatures: vec![],
+                },
+                metadata: None,
+            },
+            witnesses: vec![],
+        })
+    }
+
+    #[test]
+    pub fn stake_distribution_epoch_boundary() {
+        use crate::certificate::{CertificateContent, StakeDelegation, StakeKeyRegistration};
+        use crate::stake::{StakeKeyId, StakePoolInfo};
+        use quickcheck::Arbitrary;
+
+        let discrimination = Discrimination::Test;
+        let mut rng = test_rng();
+        let mut g = test_gen();
+        let (_sk, pk, _address) = make_key(&mut rng, &discrimination);
+        let (_stake_sk, stake_pk, _) = make_key(&mut rng, &discrimination);
+        let stake_key_id = StakeKeyId::from(stake_pk.clone());
+        let pool_info = StakePoolInfo::arbitrary(&mut g);
+        let pool_id = pool_info.to_id();
+        let value = Value(100);
+
+        let (funds, _) = block0_funds(vec![Output {
+            address: Address(discrimination, Kind::Group(pk, stake_pk)),
+            value,
+        }]);
+        let messages = [
+            Message::Initial(test_initial_ents()),
+            funds,
+            block0_certificate(CertificateContent::StakePoolRegistration(pool_info)),
+            block0_certificate(CertificateContent::StakeKeyRegistration(
+                StakeKeyRegistration {
+                    stake_key_id: stake_key_id.clone(),
+                },
+            )),
+            block0_certificate(CertificateContent::StakeDelegation(StakeDelegation {
+                stake_key_id,
+                pool_id: pool_id.clone(),
+            })),
+        ];
+        let mut ledger = Ledger::new(test_block0_hash(), &messages).unwrap();
+        assert_eq!(
+            ledger.get_stake_distribution().get_stake_for(&pool_id),
+            Some(value)
+        );
+        assert_eq!(
+            ledger.get_stake_distribution(),
+            &ledger.compute_stake_distribution()
+        );
+
+        // the stake delegated to a retired pool is not accounted for anymore,
+        // but only from the next epoch
+        ledger.delegation = ledger.delegation.deregister_stake_pool(&pool_id).unwrap();
+        assert_eq!(
+            ledger.compute_stake_distribution().eligible_stake_pools(),
+            0
+        );
+
+        let date = BlockDate::first().next();
+        let ledger = ledger
+            .apply_block(&ledger.get_ledger_parameters(), date, &[])
+            .unwrap();
+        assert_eq!(
+            ledger.get_stake_distribution().get_stake_for(&pool_id),
+            Some(value)
+        );
+
+        let date = date.next_epoch();
+        let ledger = ledger
+            .apply_block(&ledger.get_ledger_parameters(), date, &[])
+            .unwrap();
+        assert_eq!(ledger.get_stake_distribution().eligible_stake_pools(), 0);
+    }
+
+    #[test]
+    pub fn account_spending_and_stake() {
+        use crate::certificate::{CertificateContent, StakeDelegation, StakeKeyRegistration};
+        use crate::config::AllowAccountCreation;
+        use crate::stake::{StakeKeyId, StakePoolInfo};
+        use quickcheck::Arbitrary;
+
+        let discrimination = Discrimination::Test;
+        let mut rng = test_rng();
+        let mut g = test_gen();
+        let (account_sk, account_pk, _) = make_key(&mut rng, &discrimination);
+        let (_user_sk, _user_pk, user_address) = make_key(&mut rng, &discrimination);
+        let account_id = account::Identifier::from(account_pk.clone());
+        let pool_info = StakePoolInfo::arbitrary(&mut g);
+        let pool_id = pool_info.to_id();
+
+        let mut ie = test_initial_ents();
+        ie.push(ConfigParam::AllowAccountCreation(AllowAccountCreation(
+            true,
+        )));
+        let (funds, _) = block0_funds(vec![Output {
+            address: Address(discrimination, Kind::Account(account_pk.clone())),
+            value: Value(100),
+        }]);
+        let messages = [
+            Message::Initial(ie),
+            funds,
+            block0_certificate(CertificateContent::StakePoolRegistration(pool_info)),
+            block0_certificate(CertificateContent::StakeKeyRegistration(
+                StakeKeyRegistration {
+                    stake_key_id: StakeKeyId::from(account_pk.clone()),
+                },
+            )),
+            block0_certificate(CertificateContent::StakeDelegation(StakeDelegation {
+                stake_key_id: StakeKeyId::from(account_pk),
+                pool_id: pool_id.clone(),
+            })),
+        ];
+        let ledger = Ledger::new(test_block0_hash(), &messages).unwrap();
+        let state = ledger.accounts().get_state(&account_id).unwrap();
+        assert_eq!(state.get_value(), Value(100));
+        assert_eq!(state.get_counter(), account::SpendingCounter::zero());
+        // the balance of the account is the stake of its key
+        assert_eq!(
+            ledger.get_stake_distribution().get_stake_for(&pool_id),
+            Some(Value(100))
+        );
+
+        let tx = Transaction {
+            inputs: vec![Input::from_account(account_id.clone(), Value(30))],
+            outputs: vec![Output {
+                address: user_address,
+                value: Value(30),
+            }],
+            extra: NoExtra,
+            metadata: None,
+        };
+        let witness =
+            Witness::new_account(&tx.hash(), &account::SpendingCounter::zero(), &account_sk)
+                .unwrap();
+        let signed_tx = AuthenticatedTransaction {
+            transaction: tx,
+            witnesses: vec![witness.clone()],
+        };
+        let dyn_params = ledger.get_ledger_parameters();
+        let ledger = ledger.apply_transaction(&signed_tx, &dyn_params).unwrap();
+        let state = ledger.accounts().get_state(&account_id).unwrap();
+        assert_eq!(state.get_value(), Value(70));
+        assert_eq!(state.get_counter(), account::SpendingCounter::from(1));
+        assert_eq!(
+            ledger.compute_stake_distribution().get_stake_for(&pool_id),
+            Some(Value(70))
+        );
+
+        // the witness signs the counter, so the transaction cannot be
+        // replayed
+        assert_err!(
+            Error::AccountInvalidSignature(account_id, witness),
+            ledger.apply_transaction(&signed_tx, &dyn_params)
+        );
+    }
+
+    #[test]
+    pub fn multisig_declaration_and_spending() {
+        let discrimination = Discrimination::Test;
+        let mut rng = test_rng();
+        let (sk1, _pk1, user1_address) = make_key(&mut rng, &discrimination);
+        let (_sk2, _pk2, user2_address) = make_key(&mut rng, &discrimination);
+        let owners: Vec<_> = (0..3)
+            .map(|_| make_key(&mut rng, &discrimination))
+            .collect();
+        let declaration =
+            multisig::Declaration::new(2, owners.iter().map(|(_, pk, _)| pk.clone()).collect())
+                .unwrap();
+        let multisig_address = Address(discrimination, declaration.to_identifier().to_kind());
+        let value = Value(1000);
+
+        let (ledger, utxos) = test_ledger(test_initial_ents(), vec![output(&user1_address, 1000)]);
+        let dyn_params = ledger.get_ledger_parameters();
+
+        // outputs cannot be sent to an undeclared multisig
+        let tx = Transaction {
+            inputs: vec![Input::from_utxo(utxos[0])],
+            outputs: vec![Output {
+                address: multisig_address.clone(),
+                value,
+            }],
+            extra: NoExtra,
+            metadata: None,
+        };
+        let signed_tx = AuthenticatedTransaction {
+            witnesses: vec![Witness::new_utxo(&tx.hash(), &sk1).unwrap()],
+            transaction: tx,
+        };
+        assert_err!(
+            Error::Multisig(multisig::Error::NonExistent(declaration.to_identifier())),
+            ledger.clone().apply_transaction(&signed_tx, &dyn_params)
+        );
+
+        // the declaring transaction can fund the multisig
+        let tx = signed_tx.transaction.replace_extra(declaration.clone());
+        let decl_tx = AuthenticatedTransaction {
+            witnesses: vec![Witness::new_utxo(&tx.hash(), &sk1).unwrap()],
+            transaction: tx,
+        };
+        let ledger = ledger
+            .apply_multisig_declaration(&decl_tx, &dyn_params)
+            .unwrap();
+        let utxo1 = UtxoPointer {
+            transaction_id: decl_tx.transaction.hash(),
+            output_index: 0,
+            value,
+        };
+
+        let tx = Transaction {
+            inputs: vec![Input::from_utxo(utxo1)],
+            outputs: vec![Output {
+                address: user2_address.clone(),
+                value,
+            }],
+            extra: NoExtra,
+            metadata: None,
+        };
+        let txid = tx.hash();
+        let spend = |signers: &[multisig::Index]| AuthenticatedTransaction {
+            transaction: tx.clone(),
+            witnesses: vec![Witness::new_multisig(
+                &txid,
+                &signers
+                    .iter()
+                    .map(|i| (*i, &owners[*i as usize].0))
+                    .collect::<Vec<_>>(),
+            )
+            .unwrap()],
+        };
+        assert_err!(
+            Error::Multisig(multisig::Error::ThresholdNotMet(1, 2)),
+            ledger.clone().apply_transaction(&spend(&[1]), &dyn_params)
+        );
+        assert_err!(
+            Error::UtxoOutputWithoutKey(
+                utxo1,
+                Output {
+                    address: multisig_address.clone(),
+                    value,
+                }
+            ),
+            ledger.clone().apply_transaction(
+                &AuthenticatedTransaction {
+                    transaction: tx.clone(),
+                    witnesses: vec![Witness::new_utxo(&txid, &owners[0].0).unwrap()],
+                },
+                &dyn_params
+            )
+        );
+        let keys: Vec<_> = owners.iter().map(|(sk, _, _)| sk.clone()).collect();
+        let spend_aggregated = |signers: &[multisig::Index]| AuthenticatedTransaction {
+            transaction: tx.clone(),
+            witnesses: vec![Witness::MultisigAggregated(
+                signers.to_vec(),
+                multisig::test::aggregated_signature(&declaration, &keys, &[0, 1], &txid),
+            )],
+        };
+        assert_err!(
+            Error::Multisig(multisig::Error::InvalidAggregatedSignature),
+            ledger
+                .clone()
+                .apply_transaction(&spend_aggregated(&[1, 0]), &dyn_params)
+        );
+        ledger
+            .clone()
+            .apply_transaction(&spend_aggregated(&[0, 1]), &dyn_params)
+            .unwrap();
+        let ledger = ledger
+            .apply_transaction(&spend(&[2, 0]), &dyn_params)
+            .unwrap();
+        assert_eq!(ledger.utxos().count(), 1);
+    }
+
+    #[test]
+    pub fn fork_depth() {
+        let mut ie = test_initial_ents();
+        ie.push(ConfigParam::EpochStabilityDepth(
+            config::EpochStabilityDepth(2),
+        ));
+
+        let messages = [Message::Initial(ie)];
+        let ledger = Ledger::new(test_block0_hash(), &messages).unwrap();
+        assert_eq!(ledger.settings.epoch_stability_depth, 2);
+
+        assert!(ledger.check_fork_depth(ChainLength(2)).is_ok());
+        assert_err!(
+            Error::ForkTooDeep(ChainLength(0), ChainLength(3)),
+            ledger.check_fork_depth(ChainLength(3))
+        );
+    }
+
+    #[test]
+    pub fn header_verified_before_body() {
+        let block0_hash = test_block0_hash();
+        let mut rng = test_rng();
+        let (leader_key, leader_id) = make_leader(&mut rng);
+        let (other_key, _) = make_leader(&mut rng);
+
+        let mut ie = test_initial_ents();
+        ie.push(ConfigParam::AddBftLeader(leader_id.clone()));
+        let ledger = Ledger::new(block0_hash, &[Message::Initial(ie)]).unwrap();
+
+        let update = Message::UpdateProposal(
+            ProposalCertificate {
+                changes: vec![],
+                proposer_id: leader_id,
+            }
+            .sign(&leader_key),
+        );
+        let make_block = |chain_length, key: &bft::SigningKey| {
+            let mut block = BlockBuilder::new();
+            block
+                .parent(block0_hash)
+                .chain_length(chain_length)
+                .date(BlockDate::first().next())
+                .message(update.clone());
+            block.make_bft_block(key)
+        };
+
+        let block = make_block(ChainLength(1), &leader_key);
+        assert!(ledger.verify_header(&block0_hash, &block.header).is_ok());
+        match ledger.verify_header(&HeaderHash::hash_bytes(&[4]), &block.header) {
+            Err(HeaderError::WrongParent(_, parent)) => assert_eq!(parent, block0_hash),
+            r => panic!("unexpected verification result {:?}", r),
+        }
+        match ledger.verify_header(
+            &block0_hash,
+            &make_block(ChainLength(2), &leader_key).header,
+        ) {
+            Err(HeaderError::WrongChainLength(expected, _)) => assert_eq!(expected, ChainLength(1)),
+            r => panic!("unexpected verification result {:?}", r),
+        }
+        match ledger.verify_header(&block0_hash, &make_block(ChainLength(1), &other_key).header) {
+            Err(HeaderError::Leadership(_)) => {}
+            r => panic!("unexpected verification result {:?}", r),
+        }
+
+        let dyn_params = ledger.get_ledger_parameters();
+        assert!(ledger
+            .apply_body(&dyn_params, &block.header, &block.contents)
+            .is_ok());
+        let empty = BlockContents::new(vec![]);
+        assert_err!(
+            Error::BlockContentHashMismatch(
+                block.header.block_content_hash().clone(),
+                empty.compute_hash_size().0
+            ),
+            ledger.apply_body(&dyn_params, &block.header, &empty)
+        );
+    }
 }
@@ -50,7 +50,8 @@ impl Readable for UtxoDeclaration {
         for _ in 0..nb_entries {
             let value = Value::read(buf)?;
             let addr_size = buf.get_u16()? as usize;
-            let addr = OldAddress::try_from_slice(buf.get_slice(addr_size)?).unwrap();
+            let addr = OldAddress::try_from_slice(buf.get_slice(addr_size)?)
+                .map_err(|e| ReadError::StructureInvalid(e.to_string()))?;
             addrs.push((addr, value))
         }
 
@@ -85,7 +86,23 @@ mod test {
     use cardano::config::NetworkMagic;
     use cardano::hdwallet::XPub;
     use cardano::hdwallet::XPUB_SIZE;
-    use quickcheck::{Arbitrary, Gen};
+    use quickcheck::{Arbitrary, Gen, TestResult};
+
+    quickcheck! {
+        fn utxo_declaration_readable_truncations(declaration: UtxoDeclaration) -> TestResult {
+            property::testing::readable_truncations(declaration)
+        }
+    }
+
+    #[test]
+    fn malformed_old_address() {
+        // one entry of value 1, with a 2 bytes address which is not valid CBOR
+        let bytes = [0x01, 0, 0, 0, 0, 0, 0, 0, 1, 0x00, 0x02, 0xff, 0xff];
+        match chain_core::mempack::read_from_raw::<UtxoDeclaration>(&bytes) {
+            Err(error) => assert_eq!(error.kind(), std::io::ErrorKind::InvalidData),
+            Ok(declaration) => panic!("unexpected declaration {:?}", declaration),
+        }
+    }
 
     impl Arbitrary for UtxoDeclaration {
         fn arbitrary<G: Gen>(g: &mut G) -> Self {
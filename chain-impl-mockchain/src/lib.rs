@@ -1,4 +1,4 @@
-#[cfg(test)]
+#[cfg(any(test, feature = "property-test-api"))]
 #[macro_use]
 extern crate quickcheck;
 #[macro_use(custom_error)]
@@ -9,22 +9,34 @@ pub mod block;
 pub mod certificate;
 pub mod config;
 mod date;
+pub mod delta;
 pub mod legacy;
 pub mod message;
 // #[cfg(test)]
 // pub mod environment;
 pub mod error;
 pub mod fee;
+#[cfg(feature = "generic-serialization")]
+pub mod genesis;
 pub mod key;
 pub mod leadership;
 pub mod ledger;
+pub mod light;
+pub mod mempool;
+pub mod multisig;
 pub mod multiverse;
+pub mod rewards;
 pub mod setting;
+pub mod snapshot;
 pub mod stake;
+#[cfg(any(test, feature = "property-test-api"))]
+pub mod testing;
 pub mod transaction;
 pub mod txbuilder;
+pub mod update;
 pub mod utxo;
 pub mod value;
+pub mod verification;
 
 #[cfg(test)]
 mod tests {}
@@ -0,0 +1,267 @@
+//! Header-only light client.
+//!
+//! The light client follows the chain with the headers only: it checks how
+//! each header links to its parent (parent hash, chain length and date) and
+//! its leadership proof against the leaders and the stake distribution set
+//! by the configuration of block0, and keeps the longest chain of valid
+//! headers. The contents of the blocks are never downloaded: a transaction
+//! is known to be part of the chain from the transactions root of the
+//! header of its block and an `InclusionProof` given by a full node.
+//!
+//! The leadership is not updated by the blocks following block0, the
+//! light client is thus meant for the chains whose leaders (BFT) or stake
+//! distribution (Genesis Praos) are set by block0.
+
+use crate::block::{Block, ChainLength, Header, HeaderHash, InclusionProof};
+use crate::ledger::{self, HeaderError, Ledger};
+use crate::transaction::TransactionId;
+use chain_core::property::ChainLength as _;
+use std::collections::HashMap;
+
+#[derive(Debug)]
+pub enum Error {
+    /// the parent of the header is not known
+    MissingParent(HeaderHash),
+    /// the header does not follow its parent or has an invalid leader
+    InvalidHeader(HeaderError),
+    /// the block is not part of the best chain
+    NotInBestChain(HeaderHash),
+    /// the transaction is not part of the block
+    InvalidInclusionProof(HeaderHash, TransactionId),
+}
+
+impl From<HeaderError> for Error {
+    fn from(e: HeaderError) -> Self {
+        Error::InvalidHeader(e)
+    }
+}
+
+pub struct LightClient {
+    /// the state of block0, giving the leadership of the chain
+    block0_ledger: Ledger,
+    block0_id: HeaderHash,
+    /// the valid headers, block0 excepted
+    headers: HashMap<HeaderHash, Header>,
+    tip: HeaderHash,
+}
+
+impl LightClient {
+    /// start from block0, the only block whose contents are needed
+    pub fn new(block0: &Block) -> Result<Self, ledger::Error> {
+        let block0_id = block0.header.hash();
+        let block0_ledger = Ledger::new(block0_id, block0.contents.iter())?;
+        Ok(LightClient {
+            block0_ledger,
+            block0_id,
+            headers: HashMap::new(),
+            tip: block0_id,
+        })
+    }
+
+    pub fn block0_id(&self) -> &HeaderHash {
+        &self.block0_id
+    }
+
+    /// the identifier of the tip of the best chain
+    pub fn tip(&self) -> &HeaderHash {
+        &self.tip
+    }
+
+    /// the header of the tip of the best chain, `None` as long as the best
+    /// chain is block0 only
+    pub fn tip_header(&self) -> Option<&Header> {
+        self.headers.get(&self.tip)
+    }
+
+    /// the valid header of the given identifier, block0 excepted
+    pub fn get_header(&self, id: &HeaderHash) -> Option<&Header> {
+        self.headers.get(id)
+    }
+
+    /// verify the header and add it to the known headers. Returns whether
+    /// the header is the new tip of the best chain, the longest one.
+    ///
+    /// The headers are to be added from the parents to the children.
+    pub fn add_header(&mut self, header: Header) -> Result<bool, Error> {
+        let id = header.hash();
+        if id == self.block0_id || self.headers.contains_key(&id) {
+            return Ok(false);
+        }
+
+        let parent_id = *header.block_parent_hash();
+        let (parent_chain_length, parent_date) = if parent_id == self.block0_id {
+            (self.block0_ledger.chain_length(), self.block0_ledger.date)
+        } else {
+            match self.headers.get(&parent_id) {
+                Some(parent) => (parent.chain_length(), *parent.block_date()),
+                None => return Err(Error::MissingParent(parent_id)),
+            }
+        };
+        let chain_length = parent_chain_length.next();
+        if header.chain_length() != chain_length {
+            return Err(HeaderError::WrongChainLength(chain_length, header.chain_length()).into());
+        }
+        if header.block_date() <= &parent_date {
+            return Err(HeaderError::NonIncreasingDate(parent_date, *header.block_date()).into());
+        }
+        self.block0_ledger.verify_leadership(&header)?;
+
+        let is_tip = header.chain_length() > self.tip_chain_length();
+        self.headers.insert(id, header);
+        if is_tip {
+            self.tip = id;
+        }
+        Ok(is_tip)
+    }
+
+    fn tip_chain_length(&self) -> ChainLength {
+        match self.tip_header() {
+            Some(header) => header.chain_length(),
+            None => self.block0_ledger.chain_length(),
+        }
+    }
+
+    /// whether the block of the given identifier is on the best chain
+    pub fn is_in_best_chain(&self, id: &HeaderHash) -> bool {
+        if id == &self.block0_id {
+            return true;
+        }
+        let chain_length = match self.headers.get(id) {
+            Some(header) => header.chain_length(),
+            None => return false,
+        };
+        let mut current = &self.tip;
+        while let Some(header) = self.headers.get(current) {
+            if header.chain_length() <= chain_length {
+                return current == id;
+            }
+            current = header.block_parent_hash();
+        }
+        false
+    }
+
+    /// verify that the transaction is part of the block of the given
+    /// identifier, on the best chain
+    pub fn verify_inclusion(
+        &self,
+        block_id: &HeaderHash,
+        transaction_id: &TransactionId,
+        proof: &InclusionProof,
+    ) -> Result<(), Error> {
+        if !self.is_in_best_chain(block_id) {
+            return Err(Error::NotInBestChain(*block_id));
+        }
+        let verified = match self.headers.get(block_id) {
+            Some(header) => header.verify_inclusion_proof(transaction_id, proof),
+            // the transactions of block0 are not verified with a proof
+            None => false,
+        };
+        if verified {
+            Ok(())
+        } else {
+            Err(Error::InvalidInclusionProof(*block_id, *transaction_id))
+        }
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::MissingParent(parent) => write!(f, "Unknown parent block {}", parent),
+            Error::InvalidHeader(error) => write!(f, "Invalid block header: {}", error),
+            Error::NotInBestChain(block) => write!(f, "Block {} is not in the best chain", block),
+            Error::InvalidInclusionProof(block, transaction) => write!(
+                f,
+                "Transaction {} is not proven to be part of the block {}",
+                transaction, block
+            ),
+        }
+    }
+}
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::block::{BlockBuilder, BlockDate};
+    use crate::config::ConfigParam;
+    use crate::leadership::bft;
+    use crate::ledger::test::{
+        block0_funds, make_key, make_leader, output, test_initial_ents, test_rng,
+    };
+    use crate::message::Message;
+    use chain_addr::Discrimination;
+
+    fn make_block(
+        parent: HeaderHash,
+        chain_length: u32,
+        date: BlockDate,
+        messages: Vec<Message>,
+        key: &bft::SigningKey,
+    ) -> Block {
+        let mut block = BlockBuilder::new();
+        block
+            .parent(parent)
+            .chain_length(ChainLength(chain_length))
+            .date(date)
+            .messages(messages);
+        block.make_bft_block(key)
+    }
+
+    #[test]
+    pub fn follow_the_best_chain() {
+        let mut rng = test_rng();
+        let (leader_key, leader_id) = make_leader(&mut rng);
+        let (other_key, _) = make_leader(&mut rng);
+        let (_, _, address) = make_key(&mut rng, &Discrimination::Test);
+
+        let mut ie = test_initial_ents();
+        ie.push(ConfigParam::AddBftLeader(leader_id));
+        let mut block0 = BlockBuilder::new();
+        block0.message(Message::Initial(ie));
+        let block0 = block0.make_genesis_block();
+        let mut client = LightClient::new(&block0).unwrap();
+        let block0_id = block0.header.hash();
+
+        let (tx, utxos) = block0_funds(vec![output(&address, 1)]);
+        let tx_id = utxos[0].transaction_id;
+
+        let date1 = BlockDate::first().next();
+        let block1 = make_block(block0_id, 1, date1, vec![tx], &leader_key);
+        let block2 = make_block(block1.header.hash(), 2, date1.next(), vec![], &leader_key);
+        let fork1 = make_block(block0_id, 1, date1.next(), vec![], &leader_key);
+
+        match client.add_header(block2.header.clone()) {
+            Err(Error::MissingParent(parent)) => assert_eq!(parent, block1.header.hash()),
+            r => panic!("unexpected result {:?}", r),
+        }
+        match client.add_header(make_block(block0_id, 1, date1, vec![], &other_key).header) {
+            Err(Error::InvalidHeader(HeaderError::Leadership(_))) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+        match client.add_header(make_block(block0_id, 2, date1, vec![], &leader_key).header) {
+            Err(Error::InvalidHeader(HeaderError::WrongChainLength(..))) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+
+        assert!(client.add_header(block1.header.clone()).unwrap());
+        assert!(!client.add_header(fork1.header.clone()).unwrap());
+        assert!(client.add_header(block2.header.clone()).unwrap());
+        assert_eq!(client.tip(), &block2.header.hash());
+        assert!(client.is_in_best_chain(&block1.header.hash()));
+        assert!(!client.is_in_best_chain(&fork1.header.hash()));
+
+        let proof = block1.proof_of_inclusion(&tx_id).unwrap();
+        assert!(client
+            .verify_inclusion(&block1.header.hash(), &tx_id, &proof)
+            .is_ok());
+        match client.verify_inclusion(&fork1.header.hash(), &tx_id, &proof) {
+            Err(Error::NotInBestChain(_)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+        match client.verify_inclusion(&block2.header.hash(), &tx_id, &proof) {
+            Err(Error::InvalidInclusionProof(..)) => {}
+            r => panic!("unexpected result {:?}", r),
+        }
+    }
+}
@@ -0,0 +1,349 @@
+//! Pool of the transactions waiting to be included in a block.
+//!
+//! The transactions are validated against the current ledger when they
+//! enter the pool. Two transactions of the pool never spend the same UTxO
+//! nor the same account: a transaction conflicting with transactions of the
+//! pool replaces them only if its fee rate (the fee per byte of the
+//! message) is higher than theirs, otherwise it is rejected.
+//!
+//! The leaders select the transactions of their block by decreasing fee
+//! rate with `select_for_block`.
+
+use crate::account;
+use crate::fee::{CertificateCount, FeeAlgorithm};
+use crate::ledger::{self, Ledger, LedgerParameters};
+use crate::message::{Message, MessageId};
+use crate::transaction::{AuthenticatedTransaction, InputEnum, TransactionId, TransactionIndex};
+use crate::value::Value;
+use chain_addr::Address;
+use chain_core::property::Message as _;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+/// what a transaction spends: two transactions spending the same thing
+/// cannot be both in the pool
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Spending {
+    Utxo(TransactionId, TransactionIndex),
+    Account(account::Identifier),
+}
+
+struct Entry {
+    message: Message,
+    fee: Value,
+    size: usize,
+    spendings: Vec<Spending>,
+    /// arrival order, to order the transactions of the same fee rate
+    sequence: u64,
+}
+
+impl Entry {
+    /// compare the fee rates of the entries, without rounding
+    fn cmp_fee_rate(&self, other: &Self) -> Ordering {
+        let rate = self.fee.0 as u128 * other.size as u128;
+        let other_rate = other.fee.0 as u128 * self.size as u128;
+        rate.cmp(&other_rate)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// only the messages paying fees (transactions, certificates and
+    /// multisig declarations) are pooled
+    NotATransaction,
+    AlreadyInPool(MessageId),
+    /// the transaction cannot be applied to the current ledger
+    Invalid(ledger::Error),
+    /// the transaction conflicts with a transaction of the pool with a
+    /// higher or equal fee rate
+    FeeTooLowToReplace(MessageId),
+}
+
+impl From<ledger::Error> for Error {
+    fn from(e: ledger::Error) -> Self {
+        Error::Invalid(e)
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::NotATransaction => write!(f, "Only fee paying messages can be pooled"),
+            Error::AlreadyInPool(id) => write!(f, "Message {} is already in the pool", id),
+            Error::Invalid(error) => write!(f, "Invalid transaction: {}", error),
+            Error::FeeTooLowToReplace(id) => write!(
+                f,
+                "Transaction conflicts with the transaction {} which has a higher fee rate",
+                id
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The pool of the transactions valid against a ledger
+pub struct Pool {
+    ledger: Ledger,
+    entries: HashMap<MessageId, Entry>,
+    spent_by: HashMap<Spending, MessageId>,
+    next_sequence: u64,
+}
+
+fn fee_and_spendings<Extra: CertificateCount>(
+    params: &LedgerParameters,
+    tx: &AuthenticatedTransaction<Address, Extra>,
+) -> Result<(Value, Vec<Spending>), Error> {
+    let fee = params
+        .fees
+        .calculate_for(&tx.transaction)
+        .ok_or(ledger::Error::FeeCalculationError)?;
+    let spendings = tx
+        .transaction
+        .inputs
+        .iter()
+        .map(|input| match input.to_enum() {
+            InputEnum::UtxoInput(pointer) => {
+                Spending::Utxo(pointer.transaction_id, pointer.output_index)
+            }
+            InputEnum::AccountInput(identifier, _) => Spending::Account(identifier),
+        })
+        .collect();
+    Ok((fee, spendings))
+}
+
+/// apply the fee paying message to the ledger
+fn apply(ledger: Ledger, params: &LedgerParameters, message: &Message) -> Result<Ledger, Error> {
+    match message {
+        Message::Transaction(tx) => ledger.apply_transaction(tx, params),
+        Message::Certificate(tx) => ledger.apply_certificate(tx, params),
+        Message::MultisigDeclaration(tx) => ledger.apply_multisig_declaration(tx, params),
+        _ => return Err(Error::NotATransaction),
+    }
+    .map_err(Error::Invalid)
+}
+
+impl Pool {
+    pub fn new(ledger: Ledger) -> Self {
+        Pool {
+            ledger,
+            entries: HashMap::new(),
+            spent_by: HashMap::new(),
+            next_sequence: 0,
+        }
+    }
+
+    /// Return the number of transactions in the pool
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains(&self, id: &MessageId) -> bool {
+        self.entries.contains_key(id)
+    }
+
+    /// Add the transaction to the pool if it is valid against the ledger
+    /// of the pool, evicting the transactions it conflicts with if its fee
+    /// rate is higher. Return the ids of the evicted transactions.
+    pub fn insert(&mut self, message: Message) -> Result<Vec<MessageId>, Error> {
+        let id = message.id();
+        if self.contains(&id) {
+            return Err(Error::AlreadyInPool(id));
+        }
+
+        let params = self.ledger.get_ledger_parameters();
+        let (fee, spendings) = match &message {
+            Message::Transaction(tx) => fee_and_spendings(&params, tx)?,
+            Message::Certificate(tx) => fee_and_spendings(&params, tx)?,
+            Message::MultisigDeclaration(tx) => fee_and_spendings(&params, tx)?,
+            _ => return Err(Error::NotATransaction),
+        };
+        apply(self.ledger.clone(), &params, &message)?;
+
+        let entry = Entry {
+            size: message.to_raw().size_bytes_plus_size(),
+            message,
+            fee,
+            spendings,
+            sequence: self.next_sequence,
+        };
+
+        let mut conflicts: Vec<MessageId> = entry
+            .spendings
+            .iter()
+            .filter_map(|spending| self.spent_by.get(spending).cloned())
+            .collect();
+        conflicts.sort();
+        conflicts.dedup();
+        for conflict in conflicts.iter() {
+            if entry.cmp_fee_rate(&self.entries[conflict]) != Ordering::Greater {
+                return Err(Error::FeeTooLowToReplace(*conflict));
+            }
+        }
+        for conflict in conflicts.iter() {
+            self.remove(conflict);
+        }
+
+        for spending in entry.spendings.iter() {
+            self.spent_by.insert(spending.clone(), id);
+        }
+        self.entries.insert(id, entry);
+        self.next_sequence += 1;
+        Ok(conflicts)
+    }
+
+    /// Remove the transaction from the pool
+    pub fn remove(&mut self, id: &MessageId) -> Option<Message> {
+        let entry = self.entries.remove(id)?;
+        for spending in entry.spendings.iter() {
+            self.spent_by.remove(spending);
+        }
+        Some(entry.message)
+    }
+
+    /// Replace the ledger of the pool, after a block is applied or the tip
+    /// changed, and remove the transactions which are not valid anymore
+    /// (the ones included in the block among them). Return the ids of the
+    /// removed transactions.
+    pub fn set_ledger(&mut self, ledger: Ledger) -> Vec<MessageId> {
+        self.ledger = ledger;
+        let params = self.ledger.get_ledger_parameters();
+        let invalid: Vec<MessageId> = self
+            .entries
+            .iter()
+            .filter(|(_, entry)| apply(self.ledger.clone(), &params, &entry.message).is_err())
+            .map(|(id, _)| *id)
+            .collect();
+        for id in invalid.iter() {
+            self.remove(id);
+        }
+        invalid
+    }
+
+    /// Select the transactions of the next block, by decreasing fee rate,
+    /// so they can all be applied to the ledger of the pool and the size of
+    /// the messages does not exceed `max_size` bytes (nor the maximum block
+    /// size of the ledger settings).
+    pub fn select_for_block(&self, max_size: usize) -> Vec<Message> {
+        let max_size = std::cmp::min(max_size, self.ledger.settings.max_block_size as usize);
+        let mut entries: Vec<&Entry> = self.entries.values().collect();
+        entries.sort_by(|a, b| b.cmp_fee_rate(a).then_with(|| a.sequence.cmp(&b.sequence)));
+
+        let params = self.ledger.get_ledger_parameters();
+        let mut ledger = self.ledger.clone();
+        let mut size = 0;
+        let mut selected = Vec::new();
+        for entry in entries {
+            if size + entry.size > max_size {
+                continue;
+            }
+            if let Ok(new_ledger) = apply(ledger.clone(), &params, &entry.message) {
+                ledger = new_ledger;
+                size += entry.size;
+                selected.push(entry.message.clone());
+            }
+        }
+        selected
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config::ConfigParam;
+    use crate::fee::LinearFee;
+    use crate::ledger::test::{make_key, output, test_initial_ents, test_ledger, test_rng};
+    use crate::message::initial;
+    use crate::transaction::{Input, NoExtra, Transaction, Witness};
+    use chain_addr::Discrimination;
+
+    #[test]
+    pub fn fee_ordering_and_replacement() {
+        let discrimination = Discrimination::Test;
+        let mut ie = test_initial_ents();
+        // the fee rate of a transaction increases with its number of outputs
+        ie.push(ConfigParam::LinearFee(LinearFee::new(0, 10, 0)));
+
+        let mut rng = test_rng();
+        let (sk1, _pk1, user1_address) = make_key(&mut rng, &discrimination);
+        let (_sk2, _pk2, user2_address) = make_key(&mut rng, &discrimination);
+        let (ledger, utxos) = test_ledger(
+            ie,
+            vec![output(&user1_address, 100), output(&user1_address, 100)],
+        );
+
+        let spend = |output_index: usize, values: &[u64]| {
+            let tx = Transaction {
+                inputs: vec![Input::from_utxo(utxos[output_index])],
+                outputs: values
+                    .iter()
+                    .map(|value| output(&user2_address, *value))
+                    .collect(),
+                extra: NoExtra,
+                metadata: None,
+            };
+            let witness = Witness::new_utxo(&tx.hash(), &sk1).unwrap();
+            Message::Transaction(AuthenticatedTransaction {
+                transaction: tx,
+                witnesses: vec![witness],
+            })
+        };
+        let ids = |messages: Vec<Message>| messages.iter().map(|m| m.id()).collect::<Vec<_>>();
+
+        let mut pool = Pool::new(ledger.clone());
+        let low_fee = spend(0, &[80]);
+        let high_fee = spend(1, &[35, 35]);
+        assert_eq!(pool.insert(low_fee.clone()), Ok(vec![]));
+        assert_eq!(pool.insert(high_fee.clone()), Ok(vec![]));
+        assert_eq!(
+            pool.insert(low_fee.clone()),
+            Err(Error::AlreadyInPool(low_fee.id()))
+        );
+        assert_eq!(
+            pool.insert(spend(0, &[100])),
+            Err(Error::Invalid(ledger::Error::NotBalanced(
+                Value(100),
+                Value(120)
+            )))
+        );
+        assert_eq!(
+            pool.insert(Message::Initial(initial::InitialEnts::new())),
+            Err(Error::NotATransaction)
+        );
+
+        // the transactions are selected by decreasing fee rate, up to the
+        // size limit
+        assert_eq!(
+            ids(pool.select_for_block(usize::max_value())),
+            vec![high_fee.id(), low_fee.id()]
+        );
+        let size = high_fee.to_raw().size_bytes_plus_size();
+        assert_eq!(ids(pool.select_for_block(size)), vec![high_fee.id()]);
+        assert_eq!(ids(pool.select_for_block(size - 1)), vec![low_fee.id()]);
+
+        // a double spend only replaces transactions of lower fee rate
+        assert_eq!(
+            pool.insert(spend(1, &[80])),
+            Err(Error::FeeTooLowToReplace(high_fee.id()))
+        );
+        let replacement = spend(0, &[30, 40]);
+        assert_eq!(pool.insert(replacement.clone()), Ok(vec![low_fee.id()]));
+        assert_eq!(pool.len(), 2);
+        assert!(!pool.contains(&low_fee.id()));
+
+        // the transactions included in a block leave the pool
+        let ledger = match &high_fee {
+            Message::Transaction(tx) => ledger
+                .apply_transaction(tx, &ledger.get_ledger_parameters())
+                .unwrap(),
+            _ => unreachable!(),
+        };
+        assert_eq!(pool.set_ledger(ledger), vec![high_fee.id()]);
+        assert!(pool.contains(&replacement.id()));
+        assert_eq!(pool.len(), 1);
+    }
+}
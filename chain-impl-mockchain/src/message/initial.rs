@@ -1,4 +1,4 @@
-use crate::config::ConfigParam;
+use crate::config::{ConfigParam, ReadMode};
 use chain_core::mempack::{ReadBuf, ReadError, Readable};
 use chain_core::property;
 
@@ -36,9 +36,15 @@ impl property::Serialize for InitialEnts {
 
 impl Readable for InitialEnts {
     fn read<'a>(buf: &mut ReadBuf<'a>) -> Result<Self, ReadError> {
+        InitialEnts::read_with_mode(buf, ReadMode::Strict)
+    }
+}
+
+impl InitialEnts {
+    pub fn read_with_mode<'a>(buf: &mut ReadBuf<'a>, mode: ReadMode) -> Result<Self, ReadError> {
         let mut configs = vec![];
         while !buf.is_end() {
-            configs.push(ConfigParam::read(buf)?);
+            configs.push(ConfigParam::read_with_mode(buf, mode)?);
         }
         Ok(InitialEnts(configs))
     }
@@ -1,6 +1,7 @@
 pub mod initial;
 mod raw;
 
+use crate::config::ReadMode;
 use crate::legacy;
 use chain_addr::Address;
 use chain_core::mempack::{ReadBuf, ReadError, Readable};
@@ -12,8 +13,9 @@ pub use initial::InitialEnts;
 pub use raw::{MessageId, MessageRaw};
 
 use crate::{
-    certificate, setting,
+    certificate, multisig,
     transaction::{AuthenticatedTransaction, NoExtra},
+    update::{SignedProposalCertificate, SignedVoteCertificate},
 };
 
 /// All possible messages recordable in the content
@@ -23,7 +25,9 @@ pub enum Message {
     OldUtxoDeclaration(legacy::UtxoDeclaration),
     Transaction(AuthenticatedTransaction<Address, NoExtra>),
     Certificate(AuthenticatedTransaction<Address, certificate::Certificate>),
-    Update(setting::UpdateProposal),
+    UpdateProposal(SignedProposalCertificate),
+    UpdateVote(SignedVoteCertificate),
+    MultisigDeclaration(AuthenticatedTransaction<Address, multisig::Declaration>),
 }
 
 /// Tag enumeration of all known message
@@ -33,7 +37,9 @@ pub(super) enum MessageTag {
     OldUtxoDeclaration = 1,
     Transaction = 2,
     Certificate = 3,
-    Update = 4,
+    UpdateProposal = 4,
+    UpdateVote = 5,
+    MultisigDeclaration = 6,
 }
 
 impl Message {
@@ -44,7 +50,9 @@ impl Message {
             Message::OldUtxoDeclaration(_) => MessageTag::OldUtxoDeclaration,
             Message::Transaction(_) => MessageTag::Transaction,
             Message::Certificate(_) => MessageTag::Certificate,
-            Message::Update(_) => MessageTag::Update,
+            Message::UpdateProposal(_) => MessageTag::UpdateProposal,
+            Message::UpdateVote(_) => MessageTag::UpdateVote,
+            Message::MultisigDeclaration(_) => MessageTag::MultisigDeclaration,
         }
     }
 
@@ -60,16 +68,33 @@ impl Message {
             Message::OldUtxoDeclaration(s) => s.serialize(&mut codec).unwrap(),
             Message::Transaction(signed) => signed.serialize(&mut codec).unwrap(),
             Message::Certificate(signed) => signed.serialize(&mut codec).unwrap(),
-            Message::Update(proposal) => proposal.serialize(&mut codec).unwrap(),
+            Message::UpdateProposal(proposal) => proposal.serialize(&mut codec).unwrap(),
+            Message::UpdateVote(vote) => vote.serialize(&mut codec).unwrap(),
+            Message::MultisigDeclaration(signed) => signed.serialize(&mut codec).unwrap(),
         }
         MessageRaw(codec.into_inner())
     }
 
     pub fn from_raw(raw: &MessageRaw) -> Result<Self, ReadError> {
-        let buf = &mut ReadBuf::from(raw.as_ref());
+        Message::from_slice(raw.as_ref())
+    }
+
+    /// Decode a message from its serialized representation (without the
+    /// size)
+    pub fn from_slice(slice: &[u8]) -> Result<Self, ReadError> {
+        Message::from_slice_with_mode(slice, ReadMode::Strict)
+    }
+
+    /// Decode a message from its serialized representation (without the
+    /// size), the config parameters of unknown tags being accepted in
+    /// `ReadMode::Lenient`, e.g. to relay the messages of newer versions
+    pub fn from_slice_with_mode(slice: &[u8], mode: ReadMode) -> Result<Self, ReadError> {
+        let buf = &mut ReadBuf::from(slice);
         let tag = buf.get_u8()?;
         match MessageTag::from_u8(tag) {
-            Some(MessageTag::Initial) => InitialEnts::read(buf).map(Message::Initial),
+            Some(MessageTag::Initial) => {
+                InitialEnts::read_with_mode(buf, mode).map(Message::Initial)
+            }
             Some(MessageTag::OldUtxoDeclaration) => {
                 legacy::UtxoDeclaration::read(buf).map(Message::OldUtxoDeclaration)
             }
@@ -79,12 +104,29 @@ impl Message {
             Some(MessageTag::Certificate) => {
                 AuthenticatedTransaction::read(buf).map(Message::Certificate)
             }
-            Some(MessageTag::Update) => setting::UpdateProposal::read(buf).map(Message::Update),
+            Some(MessageTag::UpdateProposal) => {
+                SignedProposalCertificate::read_with_mode(buf, mode).map(Message::UpdateProposal)
+            }
+            Some(MessageTag::UpdateVote) => {
+                SignedVoteCertificate::read(buf).map(Message::UpdateVote)
+            }
+            Some(MessageTag::MultisigDeclaration) => {
+                AuthenticatedTransaction::read(buf).map(Message::MultisigDeclaration)
+            }
             None => Err(ReadError::UnknownTag(tag as u32)),
         }
     }
 }
 
+/// Read a size prefixed message, decoding it in place from the buffer
+/// instead of copying it in a `MessageRaw` first
+impl Readable for Message {
+    fn read<'a>(buf: &mut ReadBuf<'a>) -> Result<Self, ReadError> {
+        let size = buf.get_u16()? as usize;
+        Message::from_slice(buf.get_slice_ref(size)?)
+    }
+}
+
 impl property::Serialize for Message {
     type Error = std::io::Error;
     fn serialize<W: std::io::Write>(&self, writer: W) -> Result<(), Self::Error> {
@@ -117,12 +159,14 @@ mod test {
 
     impl Arbitrary for Message {
         fn arbitrary<G: Gen>(g: &mut G) -> Self {
-            match g.next_u32() % 5 {
+            match g.next_u32() % 7 {
                 0 => Message::Initial(Arbitrary::arbitrary(g)),
                 1 => Message::OldUtxoDeclaration(Arbitrary::arbitrary(g)),
                 2 => Message::Transaction(Arbitrary::arbitrary(g)),
                 3 => Message::Certificate(Arbitrary::arbitrary(g)),
-                _ => Message::Update(Arbitrary::arbitrary(g)),
+                4 => Message::UpdateProposal(Arbitrary::arbitrary(g)),
+                5 => Message::UpdateVote(Arbitrary::arbitrary(g)),
+                _ => Message::MultisigDeclaration(Arbitrary::arbitrary(g)),
             }
         }
     }
@@ -0,0 +1,539 @@
+//! Multisignature accounts.
+//!
+//! A multisignature `Declaration` registers a set of owner public keys and
+//! a threshold `m` (m-of-n). Once declared, outputs can be sent to the
+//! `Kind::Multisig` address of its `Identifier`; spending such an output
+//! requires a `Witness::Multisig` carrying the signatures of at least
+//! `threshold` distinct owners.
+//!
+//! Alternatively the owners can jointly produce a single MuSig signature
+//! (see `chain_crypto::musig`) under the aggregation of their keys, in which
+//! case the `Witness::MultisigAggregated` only carries the indices of the
+//! signers and that signature, whatever the number of signers.
+
+use crate::key::{deserialize_public_key, serialize_public_key, SpendingPublicKey};
+use crate::key::{deserialize_signature, serialize_signature, SpendingSignature};
+use crate::transaction::TransactionId;
+use chain_addr::Kind;
+use chain_core::mempack::{ReadBuf, ReadError, Readable};
+use chain_core::property;
+use chain_crypto::musig::AggregatePublicKey;
+use chain_crypto::{Blake2b256, Verification};
+use imhamt::{Hamt, InsertError};
+use std::collections::hash_map::DefaultHasher;
+
+/// Maximum number of owners of a multisignature declaration
+pub const MAX_OWNERS: usize = 32;
+
+/// Index of an owner in the declaration
+pub type Index = u8;
+
+/// Signature of the transaction by the owner at the given index
+pub type IndexedSignature = (Index, SpendingSignature<TransactionId>);
+
+/// Multisignature identifier: the hash of the declaration, also used as
+/// the content of the `Kind::Multisig` addresses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Identifier([u8; 32]);
+
+impl Identifier {
+    pub fn to_kind(&self) -> Kind {
+        Kind::Multisig(self.0)
+    }
+}
+
+impl From<[u8; 32]> for Identifier {
+    fn from(bytes: [u8; 32]) -> Self {
+        Identifier(bytes)
+    }
+}
+
+impl AsRef<[u8]> for Identifier {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Declaration {
+    pub threshold: u8,
+    pub owners: Vec<SpendingPublicKey>,
+}
+
+impl Declaration {
+    /// create a valid m-of-n declaration
+    pub fn new(threshold: u8, owners: Vec<SpendingPublicKey>) -> Result<Self, Error> {
+        let declaration = Declaration { threshold, owners };
+        declaration.is_valid()?;
+        Ok(declaration)
+    }
+
+    pub fn to_identifier(&self) -> Identifier {
+        use chain_core::property::Serialize;
+        let bytes = self.serialize_as_vec().unwrap();
+        Identifier(*Blake2b256::new(&bytes).as_hash_bytes())
+    }
+
+    /// check the declaration is a valid m-of-n declaration
+    pub fn is_valid(&self) -> Result<(), Error> {
+        if self.owners.len() > MAX_OWNERS {
+            return Err(Error::TooManyOwners(self.owners.len()));
+        }
+        if self.threshold == 0 || self.threshold as usize > self.owners.len() {
+            return Err(Error::InvalidThreshold(self.threshold, self.owners.len()));
+        }
+        for (i, owner) in self.owners.iter().enumerate() {
+            if self.owners[..i].contains(owner) {
+                return Err(Error::DuplicatedOwner(i as Index));
+            }
+        }
+        Ok(())
+    }
+
+    /// check the signers are distinct owners of the declaration and
+    /// reach its threshold, without verifying the signatures
+    pub fn check_signers(&self, signatures: &[IndexedSignature]) -> Result<(), Error> {
+        self.check_indices(signatures.iter().map(|(index, _)| *index))
+    }
+
+    fn check_indices<I>(&self, indices: I) -> Result<(), Error>
+    where
+        I: Iterator<Item = Index>,
+    {
+        let mut signers = Vec::with_capacity(self.owners.len());
+        for index in indices {
+            if index as usize >= self.owners.len() {
+                return Err(Error::IndexOutOfBound(index));
+            }
+            if signers.contains(&index) {
+                return Err(Error::DuplicatedSignature(index));
+            }
+            signers.push(index);
+        }
+        if signers.len() < self.threshold as usize {
+            return Err(Error::ThresholdNotMet(signers.len(), self.threshold));
+        }
+        Ok(())
+    }
+
+    /// aggregate the keys of the owners at the given indices, in that
+    /// order, checking they are distinct owners reaching the threshold.
+    ///
+    /// The signers run the MuSig protocol with this key, and the
+    /// aggregated signature verifies with its public key.
+    pub fn aggregate_key(&self, signers: &[Index]) -> Result<AggregatePublicKey, Error> {
+        self.check_indices(signers.iter().cloned())?;
+        let keys: Vec<_> = signers
+            .iter()
+            .map(|index| self.owners[*index as usize].clone())
+            .collect();
+        AggregatePublicKey::new(&keys).map_err(|e| match e {
+            chain_crypto::musig::Error::InvalidPublicKey(i) => Error::InvalidOwnerKey(signers[i]),
+            _ => Error::InvalidAggregatedSignature,
+        })
+    }
+
+    /// verify the aggregated signature of the transaction by the owners at
+    /// the given indices
+    pub fn verify_aggregated(
+        &self,
+        transaction_id: &TransactionId,
+        signers: &[Index],
+        signature: &SpendingSignature<TransactionId>,
+    ) -> Result<(), Error> {
+        let key = self.aggregate_key(signers)?;
+        if signature.verify(key.public_key(), transaction_id) == Verification::Failed {
+            return Err(Error::InvalidAggregatedSignature);
+        }
+        Ok(())
+    }
+
+    /// verify the signatures of the transaction reach the threshold of
+    /// distinct owners
+    pub fn verify(
+        &self,
+        transaction_id: &TransactionId,
+        signatures: &[IndexedSignature],
+    ) -> Result<(), Error> {
+        self.check_signers(signatures)?;
+        for (index, signature) in signatures {
+            let owner = &self.owners[*index as usize];
+            if signature.verify(owner, transaction_id) == Verification::Failed {
+                return Err(Error::InvalidSignature(*index));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl property::Serialize for Declaration {
+    type Error = std::io::Error;
+    fn serialize<W: std::io::Write>(&self, writer: W) -> Result<(), Self::Error> {
+        use chain_core::packer::*;
+        let mut codec = Codec::from(writer);
+        codec.put_u8(self.threshold)?;
+        put_count(&mut codec, self.owners.len(), "owners")?;
+        for owner in &self.owners {
+            serialize_public_key(owner, &mut codec)?;
+        }
+        Ok(())
+    }
+}
+
+impl Readable for Declaration {
+    fn read<'a>(buf: &mut ReadBuf<'a>) -> Result<Self, ReadError> {
+        let threshold = buf.get_u8()?;
+        let len = buf.get_u8()? as usize;
+        let mut owners = Vec::with_capacity(len);
+        for _ in 0..len {
+            owners.push(deserialize_public_key(buf)?);
+        }
+        Ok(Declaration { threshold, owners })
+    }
+}
+
+/// write the number of elements of a list on one byte, refusing to
+/// truncate it
+fn put_count<W: std::io::Write>(
+    codec: &mut chain_core::packer::Codec<W>,
+    count: usize,
+    what: &str,
+) -> Result<(), std::io::Error> {
+    if count > u8::max_value() as usize {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("too many multisig {}: {}", what, count),
+        ));
+    }
+    codec.put_u8(count as u8)
+}
+
+pub(crate) fn serialize_signatures<W: std::io::Write>(
+    signatures: &[IndexedSignature],
+    writer: W,
+) -> Result<(), std::io::Error> {
+    use chain_core::packer::*;
+    let mut codec = Codec::from(writer);
+    put_count(&mut codec, signatures.len(), "signatures")?;
+    for (index, signature) in signatures {
+        codec.put_u8(*index)?;
+        serialize_signature(signature, &mut codec)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn deserialize_signatures<'a>(
+    buf: &mut ReadBuf<'a>,
+) -> Result<Vec<IndexedSignature>, ReadError> {
+    let len = buf.get_u8()? as usize;
+    let mut signatures = Vec::with_capacity(len);
+    for _ in 0..len {
+        let index = buf.get_u8()?;
+        signatures.push((index, deserialize_signature(buf)?));
+    }
+    Ok(signatures)
+}
+
+pub(crate) fn serialize_aggregated<W: std::io::Write>(
+    signers: &[Index],
+    signature: &SpendingSignature<TransactionId>,
+    writer: W,
+) -> Result<(), std::io::Error> {
+    use chain_core::packer::*;
+    let mut codec = Codec::from(writer);
+    put_count(&mut codec, signers.len(), "signers")?;
+    for index in signers {
+        codec.put_u8(*index)?;
+    }
+    serialize_signature(signature, &mut codec)
+}
+
+pub(crate) fn deserialize_aggregated<'a>(
+    buf: &mut ReadBuf<'a>,
+) -> Result<(Vec<Index>, SpendingSignature<TransactionId>), ReadError> {
+    let len = buf.get_u8()? as usize;
+    let mut signers = Vec::with_capacity(len);
+    for _ in 0..len {
+        signers.push(buf.get_u8()?);
+    }
+    Ok((signers, deserialize_signature(buf)?))
+}
+
+/// The registered multisignature declarations
+#[derive(Clone)]
+pub struct Ledger(pub(crate) Hamt<DefaultHasher, Identifier, Declaration>);
+
+impl Ledger {
+    pub fn new() -> Self {
+        Ledger(Hamt::new())
+    }
+
+    /// register a new valid declaration
+    pub fn add_declaration(&self, declaration: &Declaration) -> Result<Self, Error> {
+        declaration.is_valid()?;
+        let identifier = declaration.to_identifier();
+        self.0
+            .insert(identifier, declaration.clone())
+            .map(Ledger)
+            .map_err(|_: InsertError| Error::AlreadyExists(identifier))
+    }
+
+    pub fn get_declaration(&self, identifier: &Identifier) -> Result<&Declaration, Error> {
+        self.0
+            .lookup(identifier)
+            .ok_or(Error::NonExistent(*identifier))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    TooManyOwners(usize),
+    InvalidThreshold(u8, usize),
+    DuplicatedOwner(Index),
+    AlreadyExists(Identifier),
+    NonExistent(Identifier),
+    IndexOutOfBound(Index),
+    DuplicatedSignature(Index),
+    InvalidSignature(Index),
+    ThresholdNotMet(usize, u8),
+    InvalidOwnerKey(Index),
+    InvalidAggregatedSignature,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::TooManyOwners(n) => write!(
+                f,
+                "Multisig declaration has {} owners, the maximum is {}",
+                n, MAX_OWNERS
+            ),
+            Error::InvalidThreshold(threshold, n) => write!(
+                f,
+                "Multisig threshold {} is invalid for {} owners",
+                threshold, n
+            ),
+            Error::DuplicatedOwner(index) => {
+                write!(f, "Multisig owner {} is declared more than once", index)
+            }
+            Error::AlreadyExists(identifier) => {
+                write!(f, "Multisig {:?} is already declared", identifier)
+            }
+            Error::NonExistent(identifier) => {
+                write!(f, "Multisig {:?} is not declared", identifier)
+            }
+            Error::IndexOutOfBound(index) => write!(f, "Multisig owner {} does not exist", index),
+            Error::DuplicatedSignature(index) => {
+                write!(f, "Multisig owner {} signed more than once", index)
+            }
+            Error::InvalidSignature(index) => {
+                write!(f, "Invalid signature of multisig owner {}", index)
+            }
+            Error::ThresholdNotMet(signers, threshold) => write!(
+                f,
+                "Multisig signed by {} owners, the threshold is {}",
+                signers, threshold
+            ),
+            Error::InvalidOwnerKey(index) => {
+                write!(f, "Key of multisig owner {} cannot be aggregated", index)
+            }
+            Error::InvalidAggregatedSignature => {
+                write!(f, "Invalid aggregated signature of the multisig owners")
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::key::SpendingSecretKey;
+    use quickcheck::{Arbitrary, Gen, TestResult};
+
+    pub fn arbitrary_secret_key<G: Gen>(g: &mut G) -> SpendingSecretKey {
+        use rand_chacha::ChaChaRng;
+        use rand_core::SeedableRng;
+        let mut seed = [0; 32];
+        for byte in seed.iter_mut() {
+            *byte = Arbitrary::arbitrary(g);
+        }
+        SpendingSecretKey::generate(&mut ChaChaRng::from_seed(seed))
+    }
+
+    impl Arbitrary for Declaration {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            let len = 1 + u8::arbitrary(g) % MAX_OWNERS as u8;
+            Declaration {
+                threshold: 1 + u8::arbitrary(g) % len,
+                owners: (0..len)
+                    .map(|_| arbitrary_secret_key(g).to_public())
+                    .collect(),
+            }
+        }
+    }
+
+    quickcheck! {
+        fn declaration_serialization_bijection(declaration: Declaration) -> TestResult {
+            let bytes = property::Serialize::serialize_as_vec(&declaration).unwrap();
+            let decoded = chain_core::mempack::read_from_raw::<Declaration>(&bytes).unwrap();
+            TestResult::from_bool(decoded == declaration)
+        }
+    }
+
+    #[test]
+    fn verify_threshold() {
+        let mut g = quickcheck::StdGen::new(rand::thread_rng(), 10);
+        let keys: Vec<SpendingSecretKey> = (0..3).map(|_| arbitrary_secret_key(&mut g)).collect();
+        let declaration =
+            Declaration::new(2, keys.iter().map(|k| k.to_public()).collect()).unwrap();
+
+        let txid = TransactionId::arbitrary(&mut g);
+        let sign = |index: Index| {
+            (
+                index,
+                SpendingSignature::generate(&keys[index as usize], &txid),
+            )
+        };
+
+        assert_eq!(declaration.verify(&txid, &[sign(0), sign(2)]), Ok(()));
+        assert_eq!(
+            declaration.verify(&txid, &[sign(1)]),
+            Err(Error::ThresholdNotMet(1, 2))
+        );
+        assert_eq!(
+            declaration.verify(&txid, &[sign(1), sign(1)]),
+            Err(Error::DuplicatedSignature(1))
+        );
+        assert_eq!(
+            declaration.verify(&txid, &[sign(0), (1, sign(2).1)]),
+            Err(Error::InvalidSignature(1))
+        );
+        assert_eq!(
+            declaration.verify(&txid, &[sign(0), (3, sign(2).1)]),
+            Err(Error::IndexOutOfBound(3))
+        );
+    }
+
+    /// run the MuSig protocol between the owners at the given indices
+    pub fn aggregated_signature(
+        declaration: &Declaration,
+        keys: &[SpendingSecretKey],
+        signers: &[Index],
+        transaction_id: &TransactionId,
+    ) -> SpendingSignature<TransactionId> {
+        use chain_crypto::musig::{self, AggregateNonce, PartialSignature, SecretNonce};
+        let key = declaration.aggregate_key(signers).unwrap();
+        let (secrets, nonces): (Vec<_>, Vec<_>) = signers
+            .iter()
+            .map(|_| SecretNonce::generate(rand::thread_rng()))
+            .unzip();
+        let aggregate_nonce = AggregateNonce::new(&key, &nonces).unwrap();
+        let partials: Vec<_> = signers
+            .iter()
+            .zip(secrets.into_iter())
+            .enumerate()
+            .map(|(i, (index, nonce))| {
+                PartialSignature::generate(
+                    &key,
+                    i,
+                    &keys[*index as usize],
+                    nonce,
+                    &aggregate_nonce,
+                    transaction_id,
+                )
+                .unwrap()
+            })
+            .collect();
+        musig::aggregate_signatures(&key, &aggregate_nonce, &partials).unwrap()
+    }
+
+    #[test]
+    fn verify_aggregated() {
+        let mut g = quickcheck::StdGen::new(rand::thread_rng(), 10);
+        let keys: Vec<SpendingSecretKey> = (0..3).map(|_| arbitrary_secret_key(&mut g)).collect();
+        let declaration =
+            Declaration::new(2, keys.iter().map(|k| k.to_public()).collect()).unwrap();
+        let txid = TransactionId::arbitrary(&mut g);
+
+        let signature = aggregated_signature(&declaration, &keys, &[2, 0], &txid);
+        assert_eq!(
+            declaration.verify_aggregated(&txid, &[2, 0], &signature),
+            Ok(())
+        );
+        assert_eq!(
+            declaration.verify_aggregated(&txid, &[0, 2], &signature),
+            Err(Error::InvalidAggregatedSignature)
+        );
+        assert_eq!(
+            declaration.verify_aggregated(&txid, &[2, 0, 1], &signature),
+            Err(Error::InvalidAggregatedSignature)
+        );
+        assert_eq!(
+            declaration.verify_aggregated(&txid, &[2], &signature),
+            Err(Error::ThresholdNotMet(1, 2))
+        );
+        assert_eq!(
+            declaration.verify_aggregated(&txid, &[2, 2], &signature),
+            Err(Error::DuplicatedSignature(2))
+        );
+    }
+
+    #[test]
+    fn invalid_declarations() {
+        let mut g = quickcheck::StdGen::new(rand::thread_rng(), 10);
+        let owner = arbitrary_secret_key(&mut g).to_public();
+        let declaration = Declaration {
+            threshold: 0,
+            owners: vec![owner.clone()],
+        };
+        assert_eq!(declaration.is_valid(), Err(Error::InvalidThreshold(0, 1)));
+        let declaration = Declaration {
+            threshold: 2,
+            owners: vec![owner.clone()],
+        };
+        assert_eq!(declaration.is_valid(), Err(Error::InvalidThreshold(2, 1)));
+        let declaration = Declaration {
+            threshold: 1,
+            owners: vec![owner.clone(), owner.clone()],
+        };
+        assert_eq!(declaration.is_valid(), Err(Error::DuplicatedOwner(1)));
+        let owners: Vec<_> = (0..=MAX_OWNERS)
+            .map(|_| arbitrary_secret_key(&mut g).to_public())
+            .collect();
+        assert_eq!(
+            Declaration::new(1, owners),
+            Err(Error::TooManyOwners(MAX_OWNERS + 1))
+        );
+
+        let ledger = Ledger::new();
+        let declaration = Declaration::arbitrary(&mut g);
+        let ledger = ledger.add_declaration(&declaration).unwrap();
+        assert!(ledger.add_declaration(&declaration).is_err());
+        assert_eq!(
+            ledger.get_declaration(&declaration.to_identifier()),
+            Ok(&declaration)
+        );
+    }
+
+    #[test]
+    fn counts_not_truncated() {
+        let mut g = quickcheck::StdGen::new(rand::thread_rng(), 10);
+        let owner = arbitrary_secret_key(&mut g).to_public();
+        let declaration = Declaration {
+            threshold: 1,
+            owners: vec![owner; 256],
+        };
+        assert!(property::Serialize::serialize_as_vec(&declaration).is_err());
+
+        let key = arbitrary_secret_key(&mut g);
+        let txid = TransactionId::arbitrary(&mut g);
+        let signature = SpendingSignature::generate(&key, &txid);
+        let signatures: Vec<_> = (0..256).map(|i| (i as Index, signature.clone())).collect();
+        assert!(serialize_signatures(&signatures, Vec::new()).is_err());
+        assert!(serialize_signatures(&signatures[..255], Vec::new()).is_ok());
+        let signers: Vec<_> = (0..256).map(|i| i as Index).collect();
+        assert!(serialize_aggregated(&signers, &signature, Vec::new()).is_err());
+    }
+}
@@ -3,12 +3,15 @@
 //! This is a multi temporal store, where the timeline is accessible by HeaderHash
 //! and multiple timelines are possible.
 //!
-//! For now this only track block at the headerhash level, and doesn't order them
-//! temporaly, leaving no way to do garbage collection
-
-use crate::block::ChainLength;
-use crate::ledger::Ledger;
-use chain_core::property::{BlockId as _, HasMessages as _};
+//! The blocks added with `add_block` are linked to their parent, so the
+//! multiverse selects the tip among the branches it knows: the longest
+//! chain for BFT, the densest chain after the fork point for Genesis Praos.
+//! Blocks forking deeper than the epoch stability depth are rejected and the
+//! states of the branches which can no longer be selected are collected.
+
+use crate::block::{Block, BlockDate, ChainLength, ConsensusVersion};
+use crate::ledger::{self, Ledger};
+use chain_core::property::{Block as _, BlockId as _, HasMessages as _};
 use chain_storage::store::BlockStore;
 use std::collections::{hash_map::Entry, BTreeMap, HashMap, HashSet};
 use std::sync::{Arc, RwLock};
@@ -35,9 +38,65 @@ type BlockId = crate::key::Hash;
 pub struct Multiverse<State> {
     states_by_hash: HashMap<BlockId, State>,
     states_by_chain_length: BTreeMap<ChainLength, HashSet<BlockId>>, // FIXME: use multimap?
+    /// the blocks added with `add_block`, kept after their state is
+    /// collected so the branches can still be compared
+    blocks: HashMap<BlockId, BlockInfo>,
+    /// the selected tip, pinned in memory
+    tip: Option<GCRoot>,
     roots: Arc<RwLock<Roots>>,
+    /// the number of slots of an epoch, to count the slots between the
+    /// dates of the blocks
+    slots_per_epoch: u32,
+}
+
+struct BlockInfo {
+    parent: BlockId,
+    chain_length: ChainLength,
+    date: BlockDate,
+}
+
+/// How the selected tip of the multiverse changed when a block was added
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TipUpdate {
+    /// the block is on a branch which is not selected
+    Unchanged,
+    /// the block extends the selected chain and is the new tip
+    Extended,
+    /// the block is the tip of a branch selected over the previous tip:
+    /// the blocks of the previous branch after the fork point are rolled
+    /// back, listed from the previous tip
+    Switched {
+        fork_point: BlockId,
+        rolled_back: Vec<BlockId>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// the state of the parent of the block is not in the multiverse
+    MissingParent(BlockId),
+    Ledger(ledger::Error),
+}
+
+impl From<ledger::Error> for Error {
+    fn from(e: ledger::Error) -> Self {
+        Error::Ledger(e)
+    }
 }
 
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::MissingParent(parent) => {
+                write!(f, "The state of the parent block {} is unknown", parent)
+            }
+            Error::Ledger(error) => write!(f, "Invalid block: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
 /// Keep all states that are this close to the longest chain.
 const SUFFIX_TO_KEEP: u32 = 50;
 
@@ -89,13 +148,18 @@ impl Drop for GCRoot {
 }
 
 impl<State> Multiverse<State> {
-    pub fn new() -> Self {
+    /// an empty multiverse for a chain of `slots_per_epoch` slots per
+    /// epoch, as configured by its time era
+    pub fn new(slots_per_epoch: u32) -> Self {
         Multiverse {
             states_by_hash: HashMap::new(),
             states_by_chain_length: BTreeMap::new(),
+            blocks: HashMap::new(),
+            tip: None,
             roots: Arc::new(RwLock::new(Roots {
                 roots: HashMap::new(),
             })),
+            slots_per_epoch,
         }
     }
     fn make_root(&mut self, k: BlockId) -> GCRoot {
@@ -112,6 +176,11 @@ impl<State> Multiverse<State> {
         self.get(&*root).unwrap()
     }
 
+    /// The selected tip, if blocks were added with `add_block`
+    pub fn tip(&self) -> Option<&BlockId> {
+        self.tip.as_ref().map(|root| &root.hash)
+    }
+
     /// Return the number of states stored in memory.
     pub fn nr_states(&self) -> usize {
         self.states_by_hash.len()
@@ -136,6 +205,144 @@ impl Multiverse<Ledger> {
         self.insert(st.chain_length(), k, st)
     }
 
+    /// Apply the block to the state of its parent and add the new state to
+    /// the multiverse, then select the tip between the block and the
+    /// current tip. Return a GCRoot object that pins the new state into
+    /// memory and how the tip changed.
+    ///
+    /// A block whose parent is deeper than the epoch stability depth from
+    /// the tip is rejected, see `Ledger::check_fork_depth`.
+    pub fn add_block(&mut self, block: &Block) -> Result<(GCRoot, TipUpdate), Error> {
+        let parent_id = block.parent_id();
+        let block_id = block.id();
+
+        let state = {
+            let parent = self
+                .get(&parent_id)
+                .ok_or_else(|| Error::MissingParent(parent_id.clone()))?;
+            if let Some(tip) = &self.tip {
+                parent.check_fork_depth(self.get_from_root(tip).chain_length())?;
+            }
+            parent.apply_block(
+                &parent.get_ledger_parameters(),
+                block.date(),
+                block.messages(),
+            )?
+        };
+
+        self.blocks.insert(
+            block_id.clone(),
+            BlockInfo {
+                parent: parent_id,
+                chain_length: state.chain_length(),
+                date: block.date(),
+            },
+        );
+        let root = self.add(block_id.clone(), state);
+        let update = self.select_tip(block_id);
+        Ok((root, update))
+    }
+
+    fn chain_length_of(&self, k: &BlockId) -> Option<ChainLength> {
+        self.blocks
+            .get(k)
+            .map(|info| info.chain_length)
+            .or_else(|| self.get(k).map(|state| state.chain_length()))
+    }
+
+    fn date_of(&self, k: &BlockId) -> Option<BlockDate> {
+        self.blocks
+            .get(k)
+            .map(|info| info.date)
+            .or_else(|| self.get(k).map(|state| state.date))
+    }
+
+    /// the common ancestor of the two blocks and the blocks of each branch
+    /// after it, from the given blocks. `None` if the branches do not meet
+    /// in the known blocks.
+    fn fork(&self, a: &BlockId, b: &BlockId) -> Option<(BlockId, Vec<BlockId>, Vec<BlockId>)> {
+        let mut a = a.clone();
+        let mut b = b.clone();
+        let mut branch_a = Vec::new();
+        let mut branch_b = Vec::new();
+        while a != b {
+            let length_a = self.chain_length_of(&a)?;
+            let length_b = self.chain_length_of(&b)?;
+            if length_a >= length_b {
+                let parent = self.blocks.get(&a)?.parent.clone();
+                branch_a.push(std::mem::replace(&mut a, parent));
+            }
+            if length_b >= length_a {
+                let parent = self.blocks.get(&b)?.parent.clone();
+                branch_b.push(std::mem::replace(&mut b, parent));
+            }
+        }
+        Some((a, branch_a, branch_b))
+    }
+
+    /// the number of blocks of the branch in the first slots after the
+    /// fork point: a window of slots where the branches are expected to
+    /// have `epoch_stability_depth` blocks, given the active slots
+    /// coefficient `f`
+    fn density(&self, fork_point: &BlockId, branch: &[BlockId]) -> usize {
+        let slots_per_epoch = self.slots_per_epoch as u64;
+        let slot = |date: BlockDate| date.epoch as u64 * slots_per_epoch + date.slot_id as u64;
+        let state = self.get(fork_point);
+        let (depth, f) = state.map_or((0, 1), |state| {
+            (
+                state.settings.epoch_stability_depth as u64,
+                std::cmp::max(state.settings.genesis_praos_param_f, 1) as u64,
+            )
+        });
+        let start = self.date_of(fork_point).map_or(0, slot);
+        let end = start + depth * 1000 / f;
+        branch
+            .iter()
+            .filter_map(|k| self.date_of(k))
+            .filter(|date| slot(*date) <= end)
+            .count()
+    }
+
+    /// select the tip between the current tip and the given block
+    fn select_tip(&mut self, candidate: BlockId) -> TipUpdate {
+        let tip = match self.tip().cloned() {
+            None => {
+                self.tip = Some(self.make_root(candidate));
+                return TipUpdate::Extended;
+            }
+            Some(tip) => tip,
+        };
+        if self.blocks.get(&candidate).map(|info| &info.parent) == Some(&tip) {
+            self.tip = Some(self.make_root(candidate));
+            return TipUpdate::Extended;
+        }
+
+        let (fork_point, tip_branch, candidate_branch) = match self.fork(&tip, &candidate) {
+            None => return TipUpdate::Unchanged,
+            Some(fork) => fork,
+        };
+        let tip_length = self.chain_length_of(&tip);
+        let candidate_length = self.chain_length_of(&candidate);
+        let better = match self.get(&tip).map(|state| state.consensus_version()) {
+            Some(ConsensusVersion::GenesisPraos) => {
+                let tip_density = self.density(&fork_point, &tip_branch);
+                let candidate_density = self.density(&fork_point, &candidate_branch);
+                candidate_density > tip_density
+                    || (candidate_density == tip_density && candidate_length > tip_length)
+            }
+            _ => candidate_length > tip_length,
+        };
+        if !better {
+            return TipUpdate::Unchanged;
+        }
+
+        self.tip = Some(self.make_root(candidate));
+        TipUpdate::Switched {
+            fork_point,
+            rolled_back: tip_branch,
+        }
+    }
+
     fn delete(&mut self, k: &BlockId) {
         //println!("deleting state {:?}", k);
         let st = self.states_by_hash.remove(&k).unwrap();
@@ -196,6 +403,51 @@ impl Multiverse<Ledger> {
         for k in garbage {
             self.delete(&k);
         }
+
+        self.gc_dead_branches();
+    }
+
+    /// Drop the blocks deeper than the epoch stability depth from the
+    /// selected tip, with the states of the ones which are not ancestors of
+    /// the tip: no block can be added on top of them anymore.
+    fn gc_dead_branches(&mut self) {
+        let (tip, tip_length, depth) = match &self.tip {
+            None => return,
+            Some(tip) => {
+                let state = self.get_from_root(tip);
+                (
+                    tip.hash.clone(),
+                    state.chain_length(),
+                    state.settings.epoch_stability_depth as u32,
+                )
+            }
+        };
+        let too_deep = |length: ChainLength| length.0.saturating_add(depth) < tip_length.0;
+
+        let mut ancestors = HashSet::new();
+        let mut current = tip;
+        while let Some(info) = self.blocks.get(&current) {
+            ancestors.insert(current.clone());
+            current = info.parent.clone();
+        }
+
+        let dead: Vec<_> = self
+            .blocks
+            .iter()
+            .filter(|(_, info)| too_deep(info.chain_length))
+            .map(|(k, _)| k.clone())
+            .collect();
+        let roots = self.roots.clone();
+        let roots = roots.read().unwrap();
+        for k in dead {
+            self.blocks.remove(&k);
+            if !ancestors.contains(&k)
+                && !roots.roots.contains_key(&k)
+                && self.states_by_hash.contains_key(&k)
+            {
+                self.delete(&k);
+            }
+        }
     }
 
     /// Get the chain state at block 'k' from memory if present;
@@ -229,7 +481,7 @@ impl Multiverse<Ledger> {
             }
 
             let cur_block_info = store.get_block_info(&cur_hash).unwrap();
-            blocks_to_apply.push(k.clone());
+            blocks_to_apply.push(cur_hash.clone());
             cur_hash = cur_block_info.parent_id();
         };
 
@@ -243,7 +495,11 @@ impl Multiverse<Ledger> {
         for hash in blocks_to_apply.iter().rev() {
             let block = store.get_block(&hash).unwrap().0;
             state = state
-                .apply_block(&state.get_ledger_parameters(), block.messages())
+                .apply_block(
+                    &state.get_ledger_parameters(),
+                    block.date(),
+                    block.messages(),
+                )
                 .unwrap();
             // FIXME: add the intermediate states to memory?
         }
@@ -255,12 +511,13 @@ impl Multiverse<Ledger> {
 #[cfg(test)]
 mod test {
 
-    use super::Multiverse;
-    use crate::block::{Block, BlockBuilder, ConsensusVersion};
-    use crate::config::ConfigParam;
-    use crate::ledger::Ledger;
+    use super::{Error, Multiverse, TipUpdate};
+    use crate::block::{Block, BlockBuilder, BlockDate, ChainLength, ConsensusVersion, HeaderHash};
+    use crate::config::{self, ConfigParam};
+    use crate::ledger::{self, Ledger};
     use crate::message::{InitialEnts, Message};
     use chain_core::property::{Block as _, ChainLength as _, HasMessages as _};
+    use chain_crypto::{Ed25519Extended, SecretKey};
     use chain_storage::store::BlockStore;
     use quickcheck::{Arbitrary, StdGen};
 
@@ -269,13 +526,17 @@ mod test {
             assert_eq!(state.chain_length().0 + 1, block.chain_length().0);
         }
         state
-            .apply_block(&state.get_ledger_parameters(), block.messages())
+            .apply_block(
+                &state.get_ledger_parameters(),
+                block.date(),
+                block.messages(),
+            )
             .unwrap()
     }
 
     #[test]
     pub fn multiverse() {
-        let mut multiverse = Multiverse::new();
+        let mut multiverse = Multiverse::new(100);
 
         let mut g = StdGen::new(rand::thread_rng(), 10);
         let leader_key = Arbitrary::arbitrary(&mut g);
@@ -344,4 +605,122 @@ mod test {
         assert_eq!(before, after + 2);
     }
 
+    fn add_block(
+        multiverse: &mut Multiverse<Ledger>,
+        leader_key: &SecretKey<Ed25519Extended>,
+        parent: HeaderHash,
+        slot_id: u32,
+    ) -> Result<(HeaderHash, TipUpdate), Error> {
+        let date = BlockDate { epoch: 0, slot_id };
+        add_block_at(multiverse, leader_key, parent, date)
+    }
+
+    fn add_block_at(
+        multiverse: &mut Multiverse<Ledger>,
+        leader_key: &SecretKey<Ed25519Extended>,
+        parent: HeaderHash,
+        date: BlockDate,
+    ) -> Result<(HeaderHash, TipUpdate), Error> {
+        let chain_length = multiverse.get(&parent).unwrap().chain_length().next();
+        let mut block = BlockBuilder::new();
+        block.chain_length(chain_length);
+        block.parent(parent);
+        block.date(date);
+        let block = block.make_bft_block(leader_key);
+        multiverse
+            .add_block(&block)
+            .map(|(_, update)| (block.id(), update))
+    }
+
+    #[test]
+    pub fn chain_selection() {
+        let mut multiverse = Multiverse::new(100);
+
+        let mut g = StdGen::new(rand::thread_rng(), 10);
+        let leader_key = Arbitrary::arbitrary(&mut g);
+
+        let mut genesis_block = BlockBuilder::new();
+        let mut ents = InitialEnts::new();
+        ents.push(ConfigParam::ConsensusVersion(ConsensusVersion::Bft));
+        ents.push(ConfigParam::EpochStabilityDepth(
+            config::EpochStabilityDepth(2),
+        ));
+        genesis_block.message(Message::Initial(ents));
+        let genesis_block = genesis_block.make_genesis_block();
+        let genesis_state = Ledger::new(genesis_block.id(), genesis_block.messages()).unwrap();
+        let _genesis_root = multiverse.add(genesis_block.id(), genesis_state);
+
+        let (a1, update) = add_block(&mut multiverse, &leader_key, genesis_block.id(), 1).unwrap();
+        assert_eq!(update, TipUpdate::Extended);
+        let (a2, update) = add_block(&mut multiverse, &leader_key, a1, 2).unwrap();
+        assert_eq!(update, TipUpdate::Extended);
+        let (a3, update) = add_block(&mut multiverse, &leader_key, a2, 3).unwrap();
+        assert_eq!(update, TipUpdate::Extended);
+
+        // a branch of the same length does not replace the tip
+        let (b2, update) = add_block(&mut multiverse, &leader_key, a1, 12).unwrap();
+        assert_eq!(update, TipUpdate::Unchanged);
+        let (b3, update) = add_block(&mut multiverse, &leader_key, b2, 13).unwrap();
+        assert_eq!(update, TipUpdate::Unchanged);
+
+        // a longer branch does, rolling back the blocks after the fork
+        let (b4, update) = add_block(&mut multiverse, &leader_key, b3, 14).unwrap();
+        assert_eq!(
+            update,
+            TipUpdate::Switched {
+                fork_point: a1,
+                rolled_back: vec![a3, a2],
+            }
+        );
+        assert_eq!(multiverse.tip(), Some(&b4));
+
+        // blocks cannot fork deeper than the stability depth
+        assert_eq!(
+            add_block(&mut multiverse, &leader_key, a1, 22).map(|(_, update)| update),
+            Err(Error::Ledger(ledger::Error::ForkTooDeep(
+                ChainLength(1),
+                ChainLength(4)
+            )))
+        );
+        let (b5, _) = add_block(&mut multiverse, &leader_key, b4, 15).unwrap();
+        let (b6, _) = add_block(&mut multiverse, &leader_key, b5, 16).unwrap();
+        assert_eq!(multiverse.tip(), Some(&b6));
+
+        // the states of the dead branch are collected, not the ones of the
+        // selected chain
+        multiverse.gc();
+        assert!(multiverse.get(&a2).is_none());
+        assert!(multiverse.get(&a3).is_none());
+        assert!(multiverse.get(&b3).is_some());
+        assert!(multiverse.get(&b6).is_some());
+    }
+
+    #[test]
+    pub fn density_across_epochs() {
+        let mut multiverse = Multiverse::new(10);
+
+        let mut g = StdGen::new(rand::thread_rng(), 10);
+        let leader_key = Arbitrary::arbitrary(&mut g);
+
+        let mut genesis_block = BlockBuilder::new();
+        let mut ents = InitialEnts::new();
+        ents.push(ConfigParam::ConsensusVersion(ConsensusVersion::Bft));
+        ents.push(ConfigParam::EpochStabilityDepth(
+            config::EpochStabilityDepth(2),
+        ));
+        genesis_block.message(Message::Initial(ents));
+        let genesis_block = genesis_block.make_genesis_block();
+        let genesis_state = Ledger::new(genesis_block.id(), genesis_block.messages()).unwrap();
+        let _genesis_root = multiverse.add(genesis_block.id(), genesis_state);
+
+        // with f = 0.5 and a stability depth of 2, the window is the 4
+        // slots following the fork point, here slots 8 to 12
+        let date = |epoch, slot_id| BlockDate { epoch, slot_id };
+        let (a1, _) =
+            add_block_at(&mut multiverse, &leader_key, genesis_block.id(), date(0, 8)).unwrap();
+        let (b1, _) = add_block_at(&mut multiverse, &leader_key, a1, date(1, 0)).unwrap();
+        let (b2, _) = add_block_at(&mut multiverse, &leader_key, b1, date(1, 2)).unwrap();
+        let (b3, _) = add_block_at(&mut multiverse, &leader_key, b2, date(1, 3)).unwrap();
+        assert_eq!(multiverse.density(&a1, &[b3, b2, b1]), 2);
+    }
 }
@@ -0,0 +1,185 @@
+//! Rewards and treasury accounting.
+//!
+//! The fees paid by the transactions of an epoch are collected in a pot.
+//! At the epoch transition, the `treasury_cut` percentage of the pot goes
+//! to the treasury and the remainder is shared between the stake pools
+//! proportionally to their stake. The owners of a pool get the
+//! `pool_owners_cut` percentage of the pool's share, the delegators the
+//! rest proportionally to their stake. Rounding leftovers go to the
+//! treasury.
+
+use crate::stake::{PoolTable, StakeDistribution, StakeKeyId};
+use crate::value::{Value, ValueError};
+use imhamt::{Hamt, UpdateError};
+use std::collections::hash_map::DefaultHasher;
+
+/// Rewards accumulated by each stake key
+pub type RewardTable = Hamt<DefaultHasher, StakeKeyId, Value>;
+
+#[derive(Clone)]
+pub struct Rewards {
+    /// fees collected since the beginning of the current epoch
+    pub(crate) epoch_fees: Value,
+    pub(crate) treasury: Value,
+    pub(crate) rewards: RewardTable,
+}
+
+impl Rewards {
+    pub fn new() -> Self {
+        Rewards {
+            epoch_fees: Value::zero(),
+            treasury: Value::zero(),
+            rewards: Hamt::new(),
+        }
+    }
+
+    pub fn epoch_fees(&self) -> Value {
+        self.epoch_fees
+    }
+
+    pub fn treasury(&self) -> Value {
+        self.treasury
+    }
+
+    /// total of the rewards received by the given stake key
+    pub fn get_reward(&self, stake_key_id: &StakeKeyId) -> Value {
+        self.rewards
+            .lookup(stake_key_id)
+            .cloned()
+            .unwrap_or_else(Value::zero)
+    }
+
+    pub fn add_fees(&self, fees: Value) -> Result<Self, ValueError> {
+        Ok(Rewards {
+            epoch_fees: (self.epoch_fees + fees)?,
+            treasury: self.treasury,
+            rewards: self.rewards.clone(),
+        })
+    }
+
+    /// Distribute the fees of the epoch according to the given stake
+    /// distribution, and start a new epoch with an empty pot.
+    pub fn distribute(
+        &self,
+        distribution: &StakeDistribution,
+        stake_pools: &PoolTable,
+        treasury_cut: u8,
+        pool_owners_cut: u8,
+    ) -> Result<Self, ValueError> {
+        let fees = self.epoch_fees;
+        let to_pools = (fees - fees.percentage(treasury_cut)?)?;
+        let total_stake = distribution.total_stake();
+
+        let mut rewards = self.rewards.clone();
+        let mut distributed = Value::zero();
+        if total_stake > Value::zero() {
+            for (pool_id, pool) in distribution.0.iter() {
+                if pool.total_stake == Value::zero() {
+                    continue;
+                }
+                let pool_reward = to_pools.scale(pool.total_stake.0, total_stake.0)?;
+
+                let owners = stake_pools
+                    .lookup(pool_id)
+                    .map_or(&[][..], |pool_info| &pool_info.owners[..]);
+                let owner_reward = if owners.is_empty() {
+                    Value::zero()
+                } else {
+                    pool_reward
+                        .percentage(pool_owners_cut)?
+                        .split_in(owners.len() as u32)?
+                        .parts
+                };
+                for owner in owners {
+                    rewards = add_reward(&rewards, owner, owner_reward)?;
+                    distributed = (distributed + owner_reward)?;
+                }
+
+                let members_reward =
+                    (pool_reward - owner_reward.checked_mul(owners.len() as u64)?)?;
+                for (member, stake) in pool.member_stake.iter() {
+                    let member_reward = members_reward.scale(stake.0, pool.total_stake.0)?;
+                    rewards = add_reward(&rewards, member, member_reward)?;
+                    distributed = (distributed + member_reward)?;
+                }
+            }
+        }
+
+        Ok(Rewards {
+            epoch_fees: Value::zero(),
+            treasury: (self.treasury + (fees - distributed)?)?,
+            rewards,
+        })
+    }
+}
+
+fn add_reward(
+    rewards: &RewardTable,
+    stake_key_id: &StakeKeyId,
+    reward: Value,
+) -> Result<RewardTable, ValueError> {
+    if reward == Value::zero() {
+        return Ok(rewards.clone());
+    }
+    match rewards.update(stake_key_id, |v| (*v + reward).map(Some)) {
+        Ok(rewards) => Ok(rewards),
+        Err(UpdateError::ValueCallbackError(e)) => Err(e),
+        Err(UpdateError::KeyNotFound) => Ok(rewards
+            .insert(stake_key_id.clone(), reward)
+            .expect("stake key not already in the reward table")),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::stake::{PoolStakeDistribution, StakePoolInfo};
+    use quickcheck::{Arbitrary, StdGen};
+    use std::collections::HashMap;
+
+    #[test]
+    fn distribute_fees() {
+        let mut g = StdGen::new(rand::thread_rng(), 10);
+        let mut pool_info = StakePoolInfo::arbitrary(&mut g);
+        let owner = StakeKeyId::arbitrary(&mut g);
+        pool_info.owners = vec![owner.clone()];
+        let pool_id = pool_info.to_id();
+        let stake_pools = PoolTable::new().insert(pool_id.clone(), pool_info).unwrap();
+
+        let member1 = StakeKeyId::arbitrary(&mut g);
+        let member2 = StakeKeyId::arbitrary(&mut g);
+        let mut member_stake = HashMap::new();
+        member_stake.insert(member1.clone(), Value(300));
+        member_stake.insert(member2.clone(), Value(100));
+        let mut pools = HashMap::new();
+        pools.insert(
+            pool_id,
+            PoolStakeDistribution {
+                total_stake: Value(400),
+                member_stake,
+            },
+        );
+        let distribution = StakeDistribution(pools);
+
+        let rewards = Rewards::new().add_fees(Value(1000)).unwrap();
+        assert_eq!(rewards.epoch_fees(), Value(1000));
+
+        let rewards = rewards
+            .distribute(&distribution, &stake_pools, 10, 20)
+            .unwrap();
+        assert_eq!(rewards.epoch_fees(), Value::zero());
+        assert_eq!(rewards.get_reward(&owner), Value(180));
+        assert_eq!(rewards.get_reward(&member1), Value(540));
+        assert_eq!(rewards.get_reward(&member2), Value(180));
+        assert_eq!(rewards.treasury(), Value(100));
+
+        // without any stake, all the fees go to the treasury
+        let rewards = rewards
+            .add_fees(Value(10))
+            .unwrap()
+            .distribute(&StakeDistribution::empty(), &stake_pools, 10, 20)
+            .unwrap();
+        assert_eq!(rewards.treasury(), Value(110));
+        assert_eq!(rewards.get_reward(&member1), Value(540));
+    }
+}
@@ -1,164 +1,10 @@
 //! define the Blockchain settings
 //!
 
+use crate::config::{self, ConfigParam};
 use crate::{block::ConsensusVersion, fee::LinearFee, key::Hash, leadership::bft};
-use chain_core::mempack::{read_vec, ReadBuf, ReadError, Readable};
-use chain_core::property;
 use std::sync::Arc;
 
-use num_derive::FromPrimitive;
-use num_traits::FromPrimitive;
-
-// FIXME: sign UpdateProposals, add voting, execute updates at an
-// epoch boundary.
-
-#[derive(Clone, Debug, PartialEq, Eq)]
-pub struct UpdateProposal {
-    pub max_number_of_transactions_per_block: Option<u32>,
-    pub bootstrap_key_slots_percentage: Option<u8>,
-    pub consensus_version: Option<ConsensusVersion>,
-    pub bft_leaders: Option<Vec<bft::LeaderId>>,
-    /// update to trigger allowing the creation of accounts without
-    /// publishing a certificate
-    pub allow_account_creation: Option<bool>,
-    /// update the LinearFee settings
-    pub linear_fees: Option<LinearFee>,
-    /// setting the slot duration (in seconds, max value is 255sec -- 4min)
-    pub slot_duration: Option<u8>,
-    /// Todo
-    pub epoch_stability_depth: Option<u32>,
-}
-
-impl UpdateProposal {
-    pub fn new() -> Self {
-        UpdateProposal {
-            max_number_of_transactions_per_block: None,
-            bootstrap_key_slots_percentage: None,
-            consensus_version: None,
-            bft_leaders: None,
-            allow_account_creation: None,
-            linear_fees: None,
-            slot_duration: None,
-            epoch_stability_depth: None,
-        }
-    }
-}
-
-#[derive(FromPrimitive)]
-enum UpdateTag {
-    End = 0,
-    MaxNumberOfTransactionsPerBlock = 1,
-    BootstrapKeySlotsPercentage = 2,
-    ConsensusVersion = 3,
-    BftLeaders = 4,
-    AllowAccountCreation = 5,
-    LinearFee = 6,
-    SlotDuration = 7,
-    EpochStabilityDepth = 8,
-}
-
-impl property::Serialize for UpdateProposal {
-    type Error = std::io::Error;
-    fn serialize<W: std::io::Write>(&self, writer: W) -> Result<(), Self::Error> {
-        use chain_core::packer::*;
-        let mut codec = Codec::from(writer);
-        if let Some(max_number_of_transactions_per_block) =
-            self.max_number_of_transactions_per_block
-        {
-            codec.put_u16(UpdateTag::MaxNumberOfTransactionsPerBlock as u16)?;
-            codec.put_u32(max_number_of_transactions_per_block)?;
-        }
-        if let Some(bootstrap_key_slots_percentage) = self.bootstrap_key_slots_percentage {
-            codec.put_u16(UpdateTag::BootstrapKeySlotsPercentage as u16)?;
-            codec.put_u8(bootstrap_key_slots_percentage)?;
-        }
-        if let Some(consensus_version) = self.consensus_version {
-            codec.put_u16(UpdateTag::ConsensusVersion as u16)?;
-            codec.put_u16(consensus_version as u16)?;
-        }
-        if let Some(leaders) = &self.bft_leaders {
-            codec.put_u16(UpdateTag::BftLeaders as u16)?;
-            codec.put_u8(leaders.len() as u8)?;
-            for leader in leaders.iter() {
-                leader.serialize(&mut codec)?;
-            }
-        }
-        if let Some(allow_account_creation) = &self.allow_account_creation {
-            codec.put_u16(UpdateTag::AllowAccountCreation as u16)?;
-            codec.put_u8(if *allow_account_creation { 1 } else { 0 })?;
-        }
-        if let Some(linear_fees) = &self.linear_fees {
-            codec.put_u16(UpdateTag::LinearFee as u16)?;
-            codec.put_u64(linear_fees.constant)?;
-            codec.put_u64(linear_fees.coefficient)?;
-            codec.put_u64(linear_fees.certificate)?;
-        }
-        if let Some(slot_duration) = self.slot_duration {
-            codec.put_u16(UpdateTag::SlotDuration as u16)?;
-            codec.put_u8(slot_duration)?;
-        }
-        if let Some(epoch_stability_depth) = self.epoch_stability_depth {
-            codec.put_u16(UpdateTag::EpochStabilityDepth as u16)?;
-            codec.put_u32(epoch_stability_depth)?;
-        }
-        codec.put_u16(UpdateTag::End as u16)?;
-        Ok(())
-    }
-}
-
-impl Readable for UpdateProposal {
-    fn read<'a>(buf: &mut ReadBuf<'a>) -> Result<Self, ReadError> {
-        let mut update = UpdateProposal::new();
-        loop {
-            let tag = buf.get_u16()?;
-            match UpdateTag::from_u16(tag) {
-                Some(UpdateTag::End) => {
-                    return Ok(update);
-                }
-                Some(UpdateTag::MaxNumberOfTransactionsPerBlock) => {
-                    update.max_number_of_transactions_per_block = Some(buf.get_u32()?);
-                }
-                Some(UpdateTag::BootstrapKeySlotsPercentage) => {
-                    update.bootstrap_key_slots_percentage = Some(buf.get_u8()?);
-                }
-                Some(UpdateTag::ConsensusVersion) => {
-                    let version_u16 = buf.get_u16()?;
-                    let version = ConsensusVersion::from_u16(version_u16).ok_or_else(|| {
-                        ReadError::StructureInvalid(format!(
-                            "Unrecognized consensus version {}",
-                            version_u16
-                        ))
-                    })?;
-                    update.consensus_version = Some(version);
-                }
-                Some(UpdateTag::BftLeaders) => {
-                    let len = buf.get_u8()? as usize;
-                    let leaders = read_vec(buf, len)?;
-                    update.bft_leaders = Some(leaders);
-                }
-                Some(UpdateTag::AllowAccountCreation) => {
-                    let boolean = buf.get_u8()? != 0;
-                    update.allow_account_creation = Some(boolean);
-                }
-                Some(UpdateTag::LinearFee) => {
-                    update.linear_fees = Some(LinearFee {
-                        constant: buf.get_u64()?,
-                        coefficient: buf.get_u64()?,
-                        certificate: buf.get_u64()?,
-                    });
-                }
-                Some(UpdateTag::SlotDuration) => {
-                    update.slot_duration = Some(buf.get_u8()?);
-                }
-                Some(UpdateTag::EpochStabilityDepth) => {
-                    update.epoch_stability_depth = Some(buf.get_u32()?);
-                }
-                None => panic!("Unrecognized update tag {}.", tag),
-            }
-        }
-    }
-}
-
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Settings {
     pub max_number_of_transactions_per_block: u32,
@@ -170,6 +16,28 @@ pub struct Settings {
     pub linear_fees: Arc<LinearFee>,
     pub slot_duration: u8,
     pub epoch_stability_depth: usize,
+    /// maximum size, in bytes, of the contents of a block
+    pub max_block_size: u32,
+    /// number of epochs an update proposal remains open for votes
+    pub proposal_expiration: u32,
+    /// percentage of the fees of an epoch going to the treasury
+    pub treasury_cut: u8,
+    /// percentage of a stake pool's rewards going to its owners
+    pub pool_owners_cut: u8,
+    /// probability that a slot has a leader in Genesis Praos, in
+    /// thousandths
+    pub genesis_praos_param_f: u16,
+    /// maximum size, in bytes, of the metadata of a transaction
+    pub max_metadata_size: u16,
+    /// maximum number of inputs of a transaction
+    pub max_transaction_inputs: u8,
+    /// maximum number of outputs of a transaction
+    pub max_transaction_outputs: u8,
+    /// maximum number of signatures carried by the witnesses of a
+    /// transaction
+    pub max_transaction_witnesses: u8,
+    /// maximum size, in bytes, of a transaction and its witnesses
+    pub max_transaction_size: u32,
 }
 
 pub const SLOTS_PERCENTAGE_RANGE: u8 = 100;
@@ -185,6 +53,16 @@ impl Settings {
             linear_fees: Arc::new(LinearFee::new(0, 0, 0)),
             slot_duration: 10,         // 10 sec
             epoch_stability_depth: 10, // num of block
+            max_block_size: u32::max_value(),
+            proposal_expiration: 100, // num of epochs
+            treasury_cut: 0,
+            pool_owners_cut: 0,
+            genesis_praos_param_f: 500,
+            max_metadata_size: 128,
+            max_transaction_inputs: u8::max_value(),
+            max_transaction_outputs: u8::max_value(),
+            max_transaction_witnesses: u8::max_value(),
+            max_transaction_size: u32::max_value(),
         }
     }
 
@@ -196,42 +74,77 @@ impl Settings {
         *self.linear_fees
     }
 
-    pub fn apply(&self, update: &UpdateProposal) -> Self {
+    /// apply the given changes to a copy of the settings. The parameters
+    /// that are fixed by block0 cannot be changed afterward.
+    pub fn apply(&self, changes: &[ConfigParam]) -> Result<Self, Error> {
         let mut new_state = self.clone();
-        if let Some(max_number_of_transactions_per_block) =
-            update.max_number_of_transactions_per_block
-        {
-            new_state.max_number_of_transactions_per_block = max_number_of_transactions_per_block;
-        }
-        if let Some(bootstrap_key_slots_percentage) = update.bootstrap_key_slots_percentage {
-            new_state.bootstrap_key_slots_percentage = bootstrap_key_slots_percentage;
-        }
-        if let Some(consensus_version) = update.consensus_version {
-            new_state.consensus_version = consensus_version;
-        }
-        if let Some(ref leaders) = update.bft_leaders {
-            new_state.bft_leaders = Arc::new(leaders.clone());
-        }
-        if let Some(allow_account_creation) = update.allow_account_creation {
-            new_state.allow_account_creation = allow_account_creation;
-        }
-        if let Some(linear_fees) = update.linear_fees {
-            new_state.linear_fees = Arc::new(linear_fees);
-        }
-        if let Some(slot_duration) = update.slot_duration {
-            new_state.slot_duration = slot_duration;
-        }
-        if let Some(epoch_stability_depth) = update.epoch_stability_depth {
-            new_state.epoch_stability_depth = epoch_stability_depth as usize;
+        for change in changes {
+            match change {
+                ConfigParam::Block0Date(_)
+                | ConfigParam::Discrimination(_)
+                | ConfigParam::ConsensusVersion(_)
+                | ConfigParam::AddBftLeader(_) => {
+                    return Err(Error::ReadOnlySetting(change.clone()));
+                }
+                ConfigParam::LinearFee(linear_fees) => {
+                    new_state.linear_fees = Arc::new(*linear_fees);
+                }
+                ConfigParam::EpochStabilityDepth(config::EpochStabilityDepth(depth)) => {
+                    new_state.epoch_stability_depth = *depth as usize;
+                }
+                ConfigParam::MaxBlockSize(config::MaxBlockSize(size)) => {
+                    new_state.max_block_size = *size;
+                }
+                ConfigParam::SlotDuration(config::SlotDuration(duration)) => {
+                    new_state.slot_duration = *duration;
+                }
+                ConfigParam::BootstrapKeySlotsPercentage(config::BootstrapKeySlotsPercentage(
+                    percentage,
+                )) => {
+                    new_state.bootstrap_key_slots_percentage = *percentage;
+                }
+                ConfigParam::AllowAccountCreation(config::AllowAccountCreation(allow)) => {
+                    new_state.allow_account_creation = *allow;
+                }
+                ConfigParam::TreasuryCut(config::TreasuryCut(percentage)) => {
+                    new_state.treasury_cut = *percentage;
+                }
+                ConfigParam::PoolOwnersCut(config::PoolOwnersCut(percentage)) => {
+                    new_state.pool_owners_cut = *percentage;
+                }
+                ConfigParam::GenesisPraosParamF(config::GenesisPraosParamF(f)) => {
+                    new_state.genesis_praos_param_f = *f;
+                }
+                ConfigParam::MaxMetadataSize(config::MaxMetadataSize(size)) => {
+                    new_state.max_metadata_size = *size;
+                }
+                ConfigParam::MaxTransactionInputs(config::MaxTransactionInputs(max)) => {
+                    new_state.max_transaction_inputs = *max;
+                }
+                ConfigParam::MaxTransactionOutputs(config::MaxTransactionOutputs(max)) => {
+                    new_state.max_transaction_outputs = *max;
+                }
+                ConfigParam::MaxTransactionWitnesses(config::MaxTransactionWitnesses(max)) => {
+                    new_state.max_transaction_witnesses = *max;
+                }
+                ConfigParam::MaxTransactionSize(config::MaxTransactionSize(size)) => {
+                    new_state.max_transaction_size = *size;
+                }
+                ConfigParam::Unknown { tag, .. } => {
+                    return Err(Error::UnknownSetting(*tag));
+                }
+            }
         }
-        new_state
+        Ok(new_state)
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Error {
     InvalidCurrentBlockId(Hash, Hash),
     UpdateIsInvalid,
+    ReadOnlySetting(ConfigParam),
+    UnknownSetting(u16),
 }
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
@@ -239,32 +152,14 @@ impl std::fmt::Display for Error {
             Error::InvalidCurrentBlockId(current_one, update_one) => {
                 write!(f, "Cannot apply Setting Update. Update needs to be applied to from block {:?} but received {:?}", update_one, current_one)
             }
-            Error::UpdateIsInvalid => write!(
-                f,
-                "Update does not apply to current state"
-            ),
-        }
-    }
-}
-impl std::error::Error for Error {}
-
-#[cfg(test)]
-mod test {
-    use super::*;
-    use quickcheck::{Arbitrary, Gen};
-
-    impl Arbitrary for UpdateProposal {
-        fn arbitrary<G: Gen>(g: &mut G) -> Self {
-            UpdateProposal {
-                max_number_of_transactions_per_block: Arbitrary::arbitrary(g),
-                bootstrap_key_slots_percentage: Arbitrary::arbitrary(g),
-                consensus_version: Arbitrary::arbitrary(g),
-                bft_leaders: None,
-                allow_account_creation: None,
-                linear_fees: None,
-                slot_duration: Arbitrary::arbitrary(g),
-                epoch_stability_depth: Arbitrary::arbitrary(g),
+            Error::UpdateIsInvalid => write!(f, "Update does not apply to current state"),
+            Error::ReadOnlySetting(param) => {
+                write!(f, "Setting {:?} can only be set in block0", param)
+            }
+            Error::UnknownSetting(tag) => {
+                write!(f, "Setting of tag {} is unknown to this version", tag)
             }
         }
     }
 }
+impl std::error::Error for Error {}
@@ -0,0 +1,725 @@
+//! Ledger snapshots.
+//!
+//! A snapshot is the full state of a `Ledger` in a compact binary format:
+//! the static parameters, the settings, the UTxOs (new and old), the
+//! accounts, the multisig declarations, the delegation state, the stake
+//! distribution of the epoch, the rewards and the update proposals, pending
+//! and closed.
+//! A node restarting from a recent snapshot only needs to apply the blocks
+//! following it instead of replaying the chain from block0.
+//!
+//! All the integers are big endian, the collections are prefixed by their
+//! number of elements as a `u32`.
+
+use crate::account::{self, SpendingCounter};
+use crate::block::{BlockDate, ChainLength, ConsensusVersion, HeaderHash};
+use crate::config::ConfigParam;
+use crate::fee::LinearFee;
+use crate::key::{deserialize_public_key, serialize_public_key};
+use crate::leadership::bft::LeaderId;
+use crate::ledger::{Ledger, LedgerStaticParameters};
+use crate::multisig::{self, Declaration};
+use crate::rewards::Rewards;
+use crate::setting::Settings;
+use crate::stake::{
+    DelegationState, PoolStakeDistribution, StakeDistribution, StakeKeyId, StakeKeyInfo,
+    StakePoolId, StakePoolInfo,
+};
+use crate::transaction::{Output, TransactionId, TransactionIndex};
+use crate::update::{ProposalCertificate, ProposalId, ProposalState, UpdateState};
+use crate::value::Value;
+use crate::{legacy, utxo};
+use chain_addr::Address;
+use chain_core::mempack::{ReadBuf, ReadError, Readable};
+use chain_core::packer::Codec;
+use chain_core::property::Serialize;
+use chain_crypto::PublicKey;
+use num_traits::FromPrimitive;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::sync::Arc;
+
+const SNAPSHOT_MAGIC: u32 = 0x4c534e50; // = LSNP
+const SNAPSHOT_VERSION: u16 = 4;
+
+impl Ledger {
+    /// write the full state of the ledger, see the `snapshot` module
+    pub fn serialize_snapshot<W: Write>(&self, writer: W) -> Result<(), std::io::Error> {
+        let mut codec = Codec::from(writer);
+        codec.put_u32(SNAPSHOT_MAGIC)?;
+        codec.put_u16(SNAPSHOT_VERSION)?;
+
+        write_static_params(&mut codec, &self.static_params)?;
+        codec.put_u32(self.chain_length.0)?;
+        write_date(&mut codec, &self.date)?;
+        write_settings(&mut codec, &self.settings)?;
+
+        write_utxos(&mut codec, &self.utxos, |codec, address| {
+            address.serialize(codec)
+        })?;
+        write_utxos(&mut codec, &self.oldutxos, write_old_address)?;
+        write_accounts(&mut codec, &self.accounts)?;
+        write_multisig(&mut codec, &self.multisig)?;
+        write_delegation(&mut codec, &self.delegation)?;
+        write_stake_distribution(&mut codec, &self.stake_distribution)?;
+        write_rewards(&mut codec, &self.rewards)?;
+        write_updates(&mut codec, &self.updates)?;
+        Ok(())
+    }
+
+    /// restore a ledger from a snapshot written by `serialize_snapshot`
+    pub fn restore_snapshot(bytes: &[u8]) -> Result<Self, ReadError> {
+        let mut buf = ReadBuf::from(bytes);
+        let magic = buf.get_u32()?;
+        if magic != SNAPSHOT_MAGIC {
+            return Err(ReadError::StructureInvalid(format!(
+                "not a ledger snapshot (magic {:#x})",
+                magic
+            )));
+        }
+        let version = buf.get_u16()?;
+        if version != SNAPSHOT_VERSION {
+            return Err(ReadError::StructureInvalid(format!(
+                "unsupported ledger snapshot version {}",
+                version
+            )));
+        }
+
+        let static_params = read_static_params(&mut buf)?;
+        let chain_length = ChainLength(buf.get_u32()?);
+        let date = read_date(&mut buf)?;
+        let settings = read_settings(&mut buf)?;
+        let utxos = read_utxos(&mut buf, Address::read)?;
+        let oldutxos = read_utxos(&mut buf, read_old_address)?;
+        let accounts = read_accounts(&mut buf)?;
+        let multisig = read_multisig(&mut buf)?;
+        let delegation = read_delegation(&mut buf)?;
+        let stake_distribution = read_stake_distribution(&mut buf)?;
+        let rewards = read_rewards(&mut buf)?;
+        let updates = read_updates(&mut buf)?;
+        buf.expect_end()?;
+
+        Ok(Ledger {
+            utxos,
+            oldutxos,
+            accounts,
+            multisig,
+            settings,
+            delegation,
+            stake_distribution: Arc::new(stake_distribution),
+            rewards,
+            updates,
+            static_params: Arc::new(static_params),
+            chain_length,
+            date,
+        })
+    }
+}
+
+pub(crate) fn put_len<W: Write>(codec: &mut Codec<W>, len: usize) -> Result<(), std::io::Error> {
+    if len > u32::max_value() as usize {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "too many elements in the snapshot",
+        ));
+    }
+    codec.put_u32(len as u32)
+}
+
+pub(crate) fn get_len<'a>(buf: &mut ReadBuf<'a>) -> Result<usize, ReadError> {
+    buf.get_u32().map(|len| len as usize)
+}
+
+pub(crate) fn write_old_address<W: Write>(
+    codec: &mut Codec<W>,
+    address: &legacy::OldAddress,
+) -> Result<(), std::io::Error> {
+    let bytes = address.as_ref();
+    codec.put_u16(bytes.len() as u16)?;
+    codec.write_all(bytes)
+}
+
+pub(crate) fn read_old_address<'a>(buf: &mut ReadBuf<'a>) -> Result<legacy::OldAddress, ReadError> {
+    use cardano::util::try_from_slice::TryFromSlice;
+    let len = buf.get_u16()? as usize;
+    legacy::OldAddress::try_from_slice(buf.get_slice(len)?)
+        .map_err(|e| ReadError::StructureInvalid(format!("old address: {:?}", e)))
+}
+
+fn write_date<W: Write>(codec: &mut Codec<W>, date: &BlockDate) -> Result<(), std::io::Error> {
+    codec.put_u32(date.epoch)?;
+    codec.put_u32(date.slot_id)
+}
+
+fn read_date<'a>(buf: &mut ReadBuf<'a>) -> Result<BlockDate, ReadError> {
+    let epoch = buf.get_u32()?;
+    let slot_id = buf.get_u32()?;
+    Ok(BlockDate { epoch, slot_id })
+}
+
+fn read_consensus_version<'a>(buf: &mut ReadBuf<'a>) -> Result<ConsensusVersion, ReadError> {
+    let version = buf.get_u16()?;
+    ConsensusVersion::from_u16(version).ok_or(ReadError::UnknownTag(version as u32))
+}
+
+fn write_static_params<W: Write>(
+    codec: &mut Codec<W>,
+    params: &LedgerStaticParameters,
+) -> Result<(), std::io::Error> {
+    params.block0_initial_hash.serialize(&mut *codec)?;
+    ConfigParam::Block0Date(params.block0_start_time).serialize(&mut *codec)?;
+    ConfigParam::Discrimination(params.discrimination).serialize(&mut *codec)?;
+    codec.put_u16(params.block0_consensus as u16)
+}
+
+fn read_static_params<'a>(buf: &mut ReadBuf<'a>) -> Result<LedgerStaticParameters, ReadError> {
+    let block0_initial_hash = HeaderHash::read(buf)?;
+    let block0_start_time = match ConfigParam::read(buf)? {
+        ConfigParam::Block0Date(date) => date,
+        param => {
+            return Err(ReadError::StructureInvalid(format!(
+                "expected the block0 date, got {:?}",
+                param
+            )));
+        }
+    };
+    let discrimination = match ConfigParam::read(buf)? {
+        ConfigParam::Discrimination(discrimination) => discrimination,
+        param => {
+            return Err(ReadError::StructureInvalid(format!(
+                "expected the discrimination, got {:?}",
+                param
+            )));
+        }
+    };
+    let block0_consensus = read_consensus_version(buf)?;
+    Ok(LedgerStaticParameters {
+        block0_initial_hash,
+        block0_start_time,
+        block0_consensus,
+        discrimination,
+    })
+}
+
+fn write_settings<W: Write>(
+    codec: &mut Codec<W>,
+    settings: &Settings,
+) -> Result<(), std::io::Error> {
+    codec.put_u32(settings.max_number_of_transactions_per_block)?;
+    codec.put_u8(settings.bootstrap_key_slots_percentage)?;
+    codec.put_u16(settings.consensus_version as u16)?;
+    put_len(codec, settings.bft_leaders.len())?;
+    for leader in settings.bft_leaders.iter() {
+        leader.serialize(&mut *codec)?;
+    }
+    codec.put_u8(settings.allow_account_creation as u8)?;
+    codec.put_u64(settings.linear_fees.constant)?;
+    codec.put_u64(settings.linear_fees.coefficient)?;
+    codec.put_u64(settings.linear_fees.certificate)?;
+    codec.put_u8(settings.slot_duration)?;
+    codec.put_u64(settings.epoch_stability_depth as u64)?;
+    codec.put_u32(settings.max_block_size)?;
+    codec.put_u32(settings.proposal_expiration)?;
+    codec.put_u8(settings.treasury_cut)?;
+    codec.put_u8(settings.pool_owners_cut)?;
+    codec.put_u16(settings.genesis_praos_param_f)?;
+    codec.put_u16(settings.max_metadata_size)?;
+    codec.put_u8(settings.max_transaction_inputs)?;
+    codec.put_u8(settings.max_transaction_outputs)?;
+    codec.put_u8(settings.max_transaction_witnesses)?;
+    codec.put_u32(settings.max_transaction_size)
+}
+
+fn read_settings<'a>(buf: &mut ReadBuf<'a>) -> Result<Settings, ReadError> {
+    let max_number_of_transactions_per_block = buf.get_u32()?;
+    let bootstrap_key_slots_percentage = buf.get_u8()?;
+    let consensus_version = read_consensus_version(buf)?;
+    let nb_leaders = get_len(buf)?;
+    let bft_leaders = chain_core::mempack::read_vec::<LeaderId>(buf, nb_leaders)?;
+    let allow_account_creation = buf.get_u8()? != 0;
+    let constant = buf.get_u64()?;
+    let coefficient = buf.get_u64()?;
+    let certificate = buf.get_u64()?;
+    let slot_duration = buf.get_u8()?;
+    let epoch_stability_depth = buf.get_u64()? as usize;
+    let max_block_size = buf.get_u32()?;
+    let proposal_expiration = buf.get_u32()?;
+    let treasury_cut = buf.get_u8()?;
+    let pool_owners_cut = buf.get_u8()?;
+    let genesis_praos_param_f = buf.get_u16()?;
+    let max_metadata_size = buf.get_u16()?;
+    let max_transaction_inputs = buf.get_u8()?;
+    let max_transaction_outputs = buf.get_u8()?;
+    let max_transaction_witnesses = buf.get_u8()?;
+    let max_transaction_size = buf.get_u32()?;
+    Ok(Settings {
+        max_number_of_transactions_per_block,
+        bootstrap_key_slots_percentage,
+        consensus_version,
+        bft_leaders: Arc::new(bft_leaders),
+        allow_account_creation,
+        linear_fees: Arc::new(LinearFee::new(constant, coefficient, certificate)),
+        slot_duration,
+        epoch_stability_depth,
+        max_block_size,
+        proposal_expiration,
+        treasury_cut,
+        pool_owners_cut,
+        genesis_praos_param_f,
+        max_metadata_size,
+        max_transaction_inputs,
+        max_transaction_outputs,
+        max_transaction_witnesses,
+        max_transaction_size,
+    })
+}
+
+/// write the unspent outputs grouped by transaction, the iterator of the
+/// UTxO ledger yielding the outputs of a transaction one after the other
+fn write_utxos<W, A, F>(
+    codec: &mut Codec<W>,
+    utxos: &utxo::Ledger<A>,
+    write_address: F,
+) -> Result<(), std::io::Error>
+where
+    W: Write,
+    F: Fn(&mut Codec<W>, &A) -> Result<(), std::io::Error>,
+{
+    let mut transactions: Vec<(TransactionId, Vec<(TransactionIndex, &Output<A>)>)> = Vec::new();
+    for entry in utxos.iter() {
+        match transactions.last_mut() {
+            Some((transaction_id, outputs)) if *transaction_id == entry.transaction_id => {
+                outputs.push((entry.output_index, entry.output))
+            }
+            _ => transactions.push((
+                entry.transaction_id,
+                vec![(entry.output_index, entry.output)],
+            )),
+        }
+    }
+
+    put_len(codec, transactions.len())?;
+    for (transaction_id, outputs) in transactions {
+        transaction_id.serialize(&mut *codec)?;
+        codec.put_u8(outputs.len() as u8)?;
+        for (index, output) in outputs {
+            codec.put_u8(index)?;
+            write_address(codec, &output.address)?;
+            output.value.serialize(&mut *codec)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_utxos<'a, A, F>(
+    buf: &mut ReadBuf<'a>,
+    read_address: F,
+) -> Result<utxo::Ledger<A>, ReadError>
+where
+    A: Clone,
+    F: Fn(&mut ReadBuf<'a>) -> Result<A, ReadError>,
+{
+    let mut utxos = utxo::Ledger::new();
+    for _ in 0..get_len(buf)? {
+        let transaction_id = TransactionId::read(buf)?;
+        let nb_outputs = buf.get_u8()? as usize;
+        let mut outputs = Vec::with_capacity(nb_outputs);
+        for _ in 0..nb_outputs {
+            let index = buf.get_u8()?;
+            let address = read_address(buf)?;
+            let value = Value::read(buf)?;
+            outputs.push((index, Output { address, value }));
+        }
+        utxos = utxos
+            .add(&transaction_id, &outputs)
+            .map_err(|e| ReadError::StructureInvalid(format!("utxo: {:?}", e)))?;
+    }
+    Ok(utxos)
+}
+
+fn write_accounts<W: Write>(
+    codec: &mut Codec<W>,
+    accounts: &account::Ledger,
+) -> Result<(), std::io::Error> {
+    put_len(codec, accounts.0.iter().count())?;
+    for (identifier, state) in accounts.0.iter() {
+        let public_key: PublicKey<account::AccountAlg> = identifier.clone().into();
+        serialize_public_key(&public_key, &mut *codec)?;
+        codec.put_u32(state.counter.0)?;
+        state.value.serialize(&mut *codec)?;
+    }
+    Ok(())
+}
+
+fn read_accounts<'a>(buf: &mut ReadBuf<'a>) -> Result<account::Ledger, ReadError> {
+    let mut accounts = account::Ledger::new();
+    for _ in 0..get_len(buf)? {
+        let identifier: account::Identifier =
+            deserialize_public_key::<account::AccountAlg>(buf)?.into();
+        let counter = SpendingCounter(buf.get_u32()?);
+        let value = Value::read(buf)?;
+        let state = account::State { counter, value };
+        accounts = account::Ledger(
+            accounts
+                .0
+                .insert(identifier, state)
+                .map_err(|_| ReadError::StructureInvalid("duplicated account".to_string()))?,
+        );
+    }
+    Ok(accounts)
+}
+
+fn write_multisig<W: Write>(
+    codec: &mut Codec<W>,
+    multisig: &multisig::Ledger,
+) -> Result<(), std::io::Error> {
+    put_len(codec, multisig.0.iter().count())?;
+    for (_, declaration) in multisig.0.iter() {
+        declaration.serialize(&mut *codec)?;
+    }
+    Ok(())
+}
+
+fn read_multisig<'a>(buf: &mut ReadBuf<'a>) -> Result<multisig::Ledger, ReadError> {
+    let mut multisig = multisig::Ledger::new();
+    for _ in 0..get_len(buf)? {
+        let declaration = Declaration::read(buf)?;
+        multisig = multisig
+            .add_declaration(&declaration)
+            .map_err(|e| ReadError::StructureInvalid(format!("multisig: {:?}", e)))?;
+    }
+    Ok(multisig)
+}
+
+fn write_delegation<W: Write>(
+    codec: &mut Codec<W>,
+    delegation: &DelegationState,
+) -> Result<(), std::io::Error> {
+    put_len(codec, delegation.stake_keys.iter().count())?;
+    for (stake_key_id, info) in delegation.stake_keys.iter() {
+        stake_key_id.serialize(&mut *codec)?;
+        match &info.pool {
+            None => codec.put_u8(0)?,
+            Some(pool_id) => {
+                codec.put_u8(1)?;
+                pool_id.serialize(&mut *codec)?;
+            }
+        }
+    }
+    put_len(codec, delegation.stake_pools.iter().count())?;
+    for (_, info) in delegation.stake_pools.iter() {
+        info.serialize(&mut *codec)?;
+    }
+    Ok(())
+}
+
+fn read_delegation<'a>(buf: &mut ReadBuf<'a>) -> Result<DelegationState, ReadError> {
+    let mut stake_keys = Vec::new();
+    for _ in 0..get_len(buf)? {
+        let stake_key_id = StakeKeyId::read(buf)?;
+        let pool = match buf.get_u8()? {
+            0 => None,
+            1 => Some(StakePoolId::read(buf)?),
+            tag => return Err(ReadError::UnknownTag(tag as u32)),
+        };
+        stake_keys.push((stake_key_id, StakeKeyInfo { pool }));
+    }
+
+    let mut delegation = DelegationState::new();
+    for _ in 0..get_len(buf)? {
+        delegation = delegation
+            .register_stake_pool(StakePoolInfo::read(buf)?)
+            .map_err(|e| ReadError::StructureInvalid(format!("stake pool: {:?}", e)))?;
+    }
+    delegation.stake_keys = stake_keys.into_iter().collect();
+    Ok(delegation)
+}
+
+fn write_stake_distribution<W: Write>(
+    codec: &mut Codec<W>,
+    distribution: &StakeDistribution,
+) -> Result<(), std::io::Error> {
+    put_len(codec, distribution.0.len())?;
+    for (pool_id, pool) in distribution.0.iter() {
+        pool_id.serialize(&mut *codec)?;
+        pool.total_stake.serialize(&mut *codec)?;
+        put_len(codec, pool.member_stake.len())?;
+        for (stake_key_id, value) in pool.member_stake.iter() {
+            stake_key_id.serialize(&mut *codec)?;
+            value.serialize(&mut *codec)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_stake_distribution<'a>(buf: &mut ReadBuf<'a>) -> Result<StakeDistribution, ReadError> {
+    let mut distribution = HashMap::new();
+    for _ in 0..get_len(buf)? {
+        let pool_id = StakePoolId::read(buf)?;
+        let total_stake = Value::read(buf)?;
+        let mut member_stake = HashMap::new();
+        for _ in 0..get_len(buf)? {
+            let stake_key_id = StakeKeyId::read(buf)?;
+            member_stake.insert(stake_key_id, Value::read(buf)?);
+        }
+        distribution.insert(
+            pool_id,
+            PoolStakeDistribution {
+                total_stake,
+                member_stake,
+            },
+        );
+    }
+    Ok(StakeDistribution(distribution))
+}
+
+fn write_rewards<W: Write>(codec: &mut Codec<W>, rewards: &Rewards) -> Result<(), std::io::Error> {
+    rewards.epoch_fees.serialize(&mut *codec)?;
+    rewards.treasury.serialize(&mut *codec)?;
+    put_len(codec, rewards.rewards.iter().count())?;
+    for (stake_key_id, value) in rewards.rewards.iter() {
+        stake_key_id.serialize(&mut *codec)?;
+        value.serialize(&mut *codec)?;
+    }
+    Ok(())
+}
+
+fn read_rewards<'a>(buf: &mut ReadBuf<'a>) -> Result<Rewards, ReadError> {
+    let epoch_fees = Value::read(buf)?;
+    let treasury = Value::read(buf)?;
+    let mut rewards = Vec::new();
+    for _ in 0..get_len(buf)? {
+        let stake_key_id = StakeKeyId::read(buf)?;
+        rewards.push((stake_key_id, Value::read(buf)?));
+    }
+    Ok(Rewards {
+        epoch_fees,
+        treasury,
+        rewards: rewards.into_iter().collect(),
+    })
+}
+
+fn write_updates<W: Write>(
+    codec: &mut Codec<W>,
+    updates: &UpdateState,
+) -> Result<(), std::io::Error> {
+    put_len(codec, updates.proposals.len())?;
+    for (proposal_id, state) in updates.proposals.iter() {
+        proposal_id.serialize(&mut *codec)?;
+        state.proposal.serialize(&mut *codec)?;
+        write_date(codec, &state.proposal_date)?;
+        put_len(codec, state.votes.len())?;
+        for voter in state.votes.iter() {
+            voter.serialize(&mut *codec)?;
+        }
+    }
+    put_len(codec, updates.closed_proposals.len())?;
+    for proposal_id in updates.closed_proposals.iter() {
+        proposal_id.serialize(&mut *codec)?;
+    }
+    Ok(())
+}
+
+fn read_updates<'a>(buf: &mut ReadBuf<'a>) -> Result<UpdateState, ReadError> {
+    let mut proposals = HashMap::new();
+    for _ in 0..get_len(buf)? {
+        let proposal_id = ProposalId::read(buf)?;
+        let proposal = ProposalCertificate::read(buf)?;
+        let proposal_date = read_date(buf)?;
+        let mut votes = HashSet::new();
+        for _ in 0..get_len(buf)? {
+            votes.insert(LeaderId::read(buf)?);
+        }
+        proposals.insert(
+            proposal_id,
+            ProposalState {
+                proposal,
+                proposal_date,
+                votes,
+            },
+        );
+    }
+    let mut closed_proposals = HashSet::new();
+    for _ in 0..get_len(buf)? {
+        closed_proposals.insert(ProposalId::read(buf)?);
+    }
+    Ok(UpdateState {
+        proposals,
+        closed_proposals,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config;
+    use crate::key::Hash;
+    use crate::ledger::test::{
+        make_key, make_leader, output, test_gen, test_initial_ents, test_ledger, test_rng,
+    };
+    use chain_addr::Discrimination;
+    use quickcheck::Arbitrary;
+
+    /// the entries of a table, sorted by key
+    fn sorted<'a, K, V, I>(entries: I) -> Vec<(K, V)>
+    where
+        K: Ord + Clone + 'a,
+        V: Clone + 'a,
+        I: Iterator<Item = (&'a K, &'a V)>,
+    {
+        let mut entries: Vec<_> = entries.map(|(k, v)| (k.clone(), v.clone())).collect();
+        entries.sort_by(|(k1, _), (k2, _)| k1.cmp(k2));
+        entries
+    }
+
+    #[test]
+    pub fn snapshot_roundtrip() {
+        let discrimination = Discrimination::Test;
+        let mut ie = test_initial_ents();
+        ie.push(ConfigParam::LinearFee(LinearFee::new(10, 2, 1)));
+        ie.push(ConfigParam::MaxTransactionInputs(
+            config::MaxTransactionInputs(3),
+        ));
+        ie.push(ConfigParam::MaxTransactionOutputs(
+            config::MaxTransactionOutputs(4),
+        ));
+        ie.push(ConfigParam::MaxTransactionWitnesses(
+            config::MaxTransactionWitnesses(5),
+        ));
+        ie.push(ConfigParam::MaxTransactionSize(config::MaxTransactionSize(
+            4096,
+        )));
+
+        let mut rng = test_rng();
+        let (_sk1, _pk1, user1_address) = make_key(&mut rng, &discrimination);
+        let (_sk2, _pk2, user2_address) = make_key(&mut rng, &discrimination);
+        let (ledger, _) = test_ledger(
+            ie,
+            vec![output(&user1_address, 100), output(&user2_address, 42)],
+        );
+        let date = BlockDate::first().next();
+        let mut ledger = ledger
+            .apply_block(&ledger.get_ledger_parameters(), date, &[])
+            .unwrap();
+
+        // populate the rest of the state directly
+        let mut g = test_gen();
+        let (sk3, pk3, _) = make_key(&mut rng, &discrimination);
+        let (_sk4, pk4, _) = make_key(&mut rng, &discrimination);
+        let account: account::Identifier = pk3.clone().into();
+        ledger.accounts = ledger.accounts.add_account(&account, Value(7)).unwrap();
+        ledger.accounts = ledger.accounts.add_value(&account, Value(3)).unwrap();
+
+        let declaration = Declaration::new(1, vec![pk3, pk4]).unwrap();
+        ledger.multisig = ledger.multisig.add_declaration(&declaration).unwrap();
+
+        let stake_key = StakeKeyId::from(&sk3);
+        let pool = StakePoolInfo::arbitrary(&mut g);
+        let pool_id = pool.to_id();
+        ledger.delegation = ledger
+            .delegation
+            .register_stake_pool(pool)
+            .unwrap()
+            .register_stake_key(stake_key.clone())
+            .unwrap()
+            .delegate_stake(stake_key.clone(), pool_id.clone())
+            .unwrap()
+            .register_stake_key(StakeKeyId::arbitrary(&mut g))
+            .unwrap();
+
+        let mut member_stake = HashMap::new();
+        member_stake.insert(stake_key.clone(), Value(10));
+        let mut distribution = HashMap::new();
+        distribution.insert(
+            pool_id,
+            PoolStakeDistribution {
+                total_stake: Value(10),
+                member_stake,
+            },
+        );
+        ledger.stake_distribution = Arc::new(StakeDistribution(distribution));
+
+        ledger.rewards = ledger.rewards.add_fees(Value(12)).unwrap();
+        ledger.rewards.treasury = Value(5);
+        ledger.rewards.rewards = ledger.rewards.rewards.insert(stake_key, Value(4)).unwrap();
+
+        let (_, leader1) = make_leader(&mut rng);
+        let (_, leader2) = make_leader(&mut rng);
+        let mut votes = HashSet::new();
+        votes.insert(leader1.clone());
+        votes.insert(leader2);
+        ledger.updates.proposals.insert(
+            Hash::hash_bytes(&[1]),
+            ProposalState {
+                proposal: ProposalCertificate {
+                    changes: vec![ConfigParam::LinearFee(LinearFee::new(1, 2, 3))],
+                    proposer_id: leader1,
+                },
+                proposal_date: date,
+                votes,
+            },
+        );
+        ledger
+            .updates
+            .closed_proposals
+            .insert(Hash::hash_bytes(&[2]));
+
+        let mut snapshot = Vec::new();
+        ledger.serialize_snapshot(&mut snapshot).unwrap();
+        let restored = Ledger::restore_snapshot(&snapshot).unwrap();
+
+        assert_eq!(restored.chain_length(), ledger.chain_length());
+        assert_eq!(restored.date, ledger.date);
+        assert_eq!(restored.settings, ledger.settings);
+        assert_eq!(restored.settings.max_transaction_inputs, 3);
+        assert_eq!(restored.settings.max_transaction_outputs, 4);
+        assert_eq!(restored.settings.max_transaction_witnesses, 5);
+        assert_eq!(restored.settings.max_transaction_size, 4096);
+        assert_eq!(
+            restored.get_static_parameters().block0_initial_hash,
+            ledger.get_static_parameters().block0_initial_hash
+        );
+        let mut outputs: Vec<_> = ledger.utxos().map(|entry| entry.output.clone()).collect();
+        let mut restored_outputs: Vec<_> =
+            restored.utxos().map(|entry| entry.output.clone()).collect();
+        outputs.sort_by_key(|output| output.value);
+        restored_outputs.sort_by_key(|output| output.value);
+        assert_eq!(restored_outputs, outputs);
+        assert_eq!(restored.oldutxos.iter().count(), 0);
+
+        assert_eq!(
+            sorted(restored.accounts.0.iter()),
+            sorted(ledger.accounts.0.iter())
+        );
+        assert_eq!(restored.accounts.0.iter().count(), 1);
+        assert_eq!(
+            sorted(restored.multisig.0.iter()),
+            sorted(ledger.multisig.0.iter())
+        );
+        assert_eq!(restored.multisig.0.iter().count(), 1);
+        assert_eq!(
+            sorted(restored.delegation.stake_keys.iter()),
+            sorted(ledger.delegation.stake_keys.iter())
+        );
+        assert_eq!(restored.delegation.stake_keys.iter().count(), 2);
+        assert_eq!(
+            sorted(restored.delegation.stake_pools.iter()),
+            sorted(ledger.delegation.stake_pools.iter())
+        );
+        assert_eq!(restored.delegation.stake_pools.iter().count(), 1);
+        assert_eq!(restored.stake_distribution, ledger.stake_distribution);
+        assert_eq!(restored.rewards.epoch_fees(), ledger.rewards.epoch_fees());
+        assert_eq!(restored.rewards.treasury(), Value(5));
+        assert_eq!(
+            sorted(restored.rewards.rewards.iter()),
+            sorted(ledger.rewards.rewards.iter())
+        );
+        assert_eq!(restored.rewards.rewards.iter().count(), 1);
+        assert_eq!(restored.updates, ledger.updates);
+
+        // truncated or altered snapshots are rejected
+        assert!(Ledger::restore_snapshot(&snapshot[..snapshot.len() - 1]).is_err());
+        snapshot[0] ^= 0xff;
+        assert!(Ledger::restore_snapshot(&snapshot).is_err());
+    }
+}
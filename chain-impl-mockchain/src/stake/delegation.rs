@@ -13,7 +13,7 @@ pub type KeyTable = Hamt<DefaultHasher, StakeKeyId, StakeKeyInfo>;
 /// A structure that keeps track of stake keys and stake pools.
 #[derive(Clone)]
 pub struct DelegationState {
-    pub(super) stake_keys: KeyTable,
+    pub(crate) stake_keys: KeyTable,
     pub(crate) stake_pools: PoolTable,
 }
 
@@ -1,5 +1,6 @@
-use crate::{stake::StakePoolId, utxo, value::Value};
+use crate::{account, stake::StakePoolId, utxo, value::Value};
 use chain_addr::{Address, Kind};
+use chain_crypto::PublicKey;
 use std::collections::HashMap;
 
 use super::delegation::DelegationState;
@@ -63,8 +64,44 @@ impl StakeDistribution {
     }
 }
 
+/// add the value to the stake of the stake key, if it is delegated to a
+/// stake pool which is still registered
+fn assign_stake(
+    dist: &mut HashMap<StakePoolId, PoolStakeDistribution>,
+    dstate: &DelegationState,
+    stake_key: &StakeKeyId,
+    value: Value,
+) {
+    // Do we have a stake key for this spending key?
+    if let Some(stake_key_info) = dstate.stake_keys.lookup(stake_key) {
+        // Is this stake key a member of a stake pool which is
+        // still registered?
+        if let Some(pool_id) = stake_key_info
+            .pool
+            .as_ref()
+            .filter(|pool_id| dstate.stake_pool_exists(pool_id))
+        {
+            let stake_pool_dist =
+                dist.entry(pool_id.clone())
+                    .or_insert_with(|| PoolStakeDistribution {
+                        total_stake: Value(0),
+                        member_stake: HashMap::new(),
+                    });
+            // note: unwrap should be safe, the system should have a total less than overflow
+            stake_pool_dist.total_stake = (stake_pool_dist.total_stake + value).unwrap();
+
+            let member_dist = stake_pool_dist
+                .member_stake
+                .entry(stake_key.clone())
+                .or_insert_with(|| Value::zero());
+            *member_dist = (*member_dist + value).unwrap();
+        }
+    }
+}
+
 pub fn get_distribution(
     dstate: &DelegationState,
+    accounts: &account::Ledger,
     utxos: &utxo::Ledger<Address>,
 ) -> StakeDistribution {
     let mut dist = HashMap::new();
@@ -75,30 +112,17 @@ pub fn get_distribution(
         if let Kind::Group(_spending_key, stake_key) = output.address.kind() {
             // Grmbl.
             let stake_key = stake_key.clone().into();
-
-            // Do we have a stake key for this spending key?
-            if let Some(stake_key_info) = dstate.stake_keys.lookup(&stake_key) {
-                // Is this stake key a member of a stake pool?
-                if let Some(pool_id) = &stake_key_info.pool {
-                    let stake_pool_dist =
-                        dist.entry(pool_id.clone())
-                            .or_insert_with(|| PoolStakeDistribution {
-                                total_stake: Value(0),
-                                member_stake: HashMap::new(),
-                            });
-                    // note: unwrap should be safe, the system should have a total less than overflow
-                    stake_pool_dist.total_stake =
-                        (stake_pool_dist.total_stake + output.value).unwrap();
-
-                    let member_dist = stake_pool_dist
-                        .member_stake
-                        .entry(stake_key.clone())
-                        .or_insert_with(|| Value::zero());
-                    *member_dist = (*member_dist + output.value).unwrap();
-                }
-            }
+            assign_stake(&mut dist, dstate, &stake_key, output.value);
         }
     }
 
+    // the balance of an account is the stake of the account key, which is
+    // delegated as any other stake key
+    for (identifier, state) in accounts.iter() {
+        let public_key: PublicKey<account::AccountAlg> = identifier.clone().into();
+        let stake_key = StakeKeyId::from(public_key);
+        assign_stake(&mut dist, dstate, &stake_key, state.get_value());
+    }
+
     StakeDistribution(dist)
 }
@@ -0,0 +1,404 @@
+//! Property-based simulation of the ledger
+//!
+//! `Chain` is an arbitrary chain: the block0 distributes some value to a
+//! set of keys, then every block is made of valid transactions spending
+//! the unspent outputs left by the previous blocks. The invariant checkers
+//! verify the chain and the ledgers obtained by applying it, so the ledger
+//! can be tested (or fuzzed) with quickcheck:
+//!
+//! ```ignore
+//! quickcheck! {
+//!     fn ledger_invariants(chain: Chain) -> TestResult {
+//!         chain_invariants(&chain)
+//!     }
+//! }
+//! ```
+//!
+//! The fees are null, so the total value of the ledger never changes.
+
+use crate::block::{Block0Builder, BlockDate, ConsensusVersion, HeaderHash};
+use crate::config::ConfigParam;
+use crate::key::SpendingSecretKey;
+use crate::ledger::{self, Ledger};
+use crate::message::Message;
+use crate::transaction::*;
+use crate::value::{Value, ValueError};
+use chain_addr::{Address, Discrimination, Kind};
+use quickcheck::{Arbitrary, Gen, TestResult};
+use std::collections::HashSet;
+use std::fmt;
+
+/// maximum number of keys owning the value of the chain
+const MAX_KEYS: usize = 6;
+/// maximum number of blocks following the block0
+const MAX_BLOCKS: usize = 8;
+/// maximum number of transactions per block
+const MAX_TRANSACTIONS: usize = 4;
+/// maximum number of inputs and outputs per transaction
+const MAX_INPUTS: usize = 3;
+const MAX_OUTPUTS: usize = 3;
+/// maximum value of the outputs of the block0
+const MAX_INITIAL_VALUE: u64 = 1_000_000;
+
+/// an arbitrary chain of valid blocks
+#[derive(Clone)]
+pub struct Chain {
+    pub block0_hash: HeaderHash,
+    pub block0: Vec<Message>,
+    pub blocks: Vec<(BlockDate, Vec<Message>)>,
+}
+
+impl fmt::Debug for Chain {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let transactions: Vec<usize> = self.blocks.iter().map(|(_, b)| b.len()).collect();
+        f.debug_struct("Chain")
+            .field("block0_hash", &self.block0_hash)
+            .field("transactions", &transactions)
+            .finish()
+    }
+}
+
+/// an unspent output of the simulation and the index of the key owning it
+#[derive(Clone)]
+struct Unspent {
+    pointer: UtxoPointer,
+    owner: usize,
+}
+
+fn arbitrary_outputs<G: Gen>(
+    g: &mut G,
+    total: Value,
+    addresses: &[Address],
+) -> Vec<(usize, Output<Address>)> {
+    // every output holds at least 1
+    let count = (1 + usize::arbitrary(g) % MAX_OUTPUTS).min(total.0 as usize);
+    let mut remaining = total.0;
+    (0..count)
+        .map(|i| {
+            let left = (count - i - 1) as u64;
+            let value = if left == 0 {
+                remaining
+            } else {
+                1 + u64::arbitrary(g) % (remaining - left)
+            };
+            remaining -= value;
+            let owner = usize::arbitrary(g) % addresses.len();
+            let output = Output {
+                address: addresses[owner].clone(),
+                value: Value(value),
+            };
+            (owner, output)
+        })
+        .collect()
+}
+
+fn transaction_unspent(
+    transaction: &Transaction<Address, NoExtra>,
+    owners: &[usize],
+) -> Vec<Unspent> {
+    let transaction_id = transaction.hash();
+    transaction
+        .outputs
+        .iter()
+        .zip(owners.iter())
+        .enumerate()
+        .map(|(index, (output, owner))| Unspent {
+            pointer: UtxoPointer::new(transaction_id, index as u8, output.value),
+            owner: *owner,
+        })
+        .collect()
+}
+
+impl Arbitrary for Chain {
+    fn arbitrary<G: Gen>(g: &mut G) -> Self {
+        let discrimination = Discrimination::Test;
+        let keys: Vec<SpendingSecretKey> = (0..1 + usize::arbitrary(g) % MAX_KEYS)
+            .map(|_| SpendingSecretKey::arbitrary(g))
+            .collect();
+        let addresses: Vec<Address> = keys
+            .iter()
+            .map(|key| Address(discrimination, Kind::Single(key.to_public())))
+            .collect();
+
+        let initial_value = Value(1 + u64::arbitrary(g) % MAX_INITIAL_VALUE);
+        let (owners, outputs): (Vec<_>, Vec<_>) = arbitrary_outputs(g, initial_value, &addresses)
+            .into_iter()
+            .unzip();
+        let block0 = Block0Builder::new()
+            .config_param(ConfigParam::Discrimination(discrimination))
+            .config_param(ConfigParam::ConsensusVersion(ConsensusVersion::Bft))
+            .config_param(ConfigParam::AddBftLeader(keys[0].to_public().into()))
+            .initial_funds(outputs)
+            .build()
+            .expect("valid block0");
+        // the initial outputs fit in a single transaction
+        let mut unspent = match block0.contents.iter().nth(1) {
+            Some(Message::Transaction(initial)) => {
+                transaction_unspent(&initial.transaction, &owners)
+            }
+            _ => unreachable!("the block0 distributes the initial value"),
+        };
+
+        let mut date = BlockDate::first();
+        let mut blocks = Vec::new();
+        for _ in 0..usize::arbitrary(g) % (MAX_BLOCKS + 1) {
+            date = date.next();
+            let mut messages = Vec::new();
+            let mut created = Vec::new();
+            for _ in 0..usize::arbitrary(g) % (MAX_TRANSACTIONS + 1) {
+                if unspent.is_empty() {
+                    break;
+                }
+                let spent: Vec<Unspent> = (0..1 + usize::arbitrary(g) % MAX_INPUTS)
+                    .filter_map(|_| {
+                        if unspent.is_empty() {
+                            None
+                        } else {
+                            let index = usize::arbitrary(g) % unspent.len();
+                            Some(unspent.swap_remove(index))
+                        }
+                    })
+                    .collect();
+                let total = Value::sum(spent.iter().map(|u| u.pointer.value))
+                    .expect("the total value fits in a u64");
+                let (owners, outputs): (Vec<_>, Vec<_>) =
+                    arbitrary_outputs(g, total, &addresses).into_iter().unzip();
+                let transaction = Transaction {
+                    inputs: spent.iter().map(|u| Input::from_utxo(u.pointer)).collect(),
+                    outputs,
+                    extra: NoExtra,
+                    metadata: None,
+                };
+                let transaction_id = transaction.hash();
+                let witnesses = spent
+                    .iter()
+                    .map(|u| Witness::new_utxo(&transaction_id, &keys[u.owner]).unwrap())
+                    .collect();
+                // the outputs can only be spent from the next block
+                created.extend(transaction_unspent(&transaction, &owners));
+                messages.push(Message::Transaction(AuthenticatedTransaction {
+                    transaction,
+                    witnesses,
+                }));
+            }
+            unspent.extend(created);
+            blocks.push((date, messages));
+        }
+
+        Chain {
+            block0_hash: block0.header.hash(),
+            block0: block0.contents.iter().cloned().collect(),
+            blocks,
+        }
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        // the prefixes of the chain are valid chains
+        let chain = self.clone();
+        Box::new((0..self.blocks.len()).rev().map(move |len| Chain {
+            block0_hash: chain.block0_hash,
+            block0: chain.block0.clone(),
+            blocks: chain.blocks[..len].to_vec(),
+        }))
+    }
+}
+
+impl Chain {
+    /// the ledger after the block0 and after every block of the chain
+    pub fn ledgers(&self) -> Result<Vec<Ledger>, ledger::Error> {
+        let mut ledgers = vec![Ledger::new(self.block0_hash, &self.block0)?];
+        for (date, messages) in self.blocks.iter() {
+            let ledger = {
+                let previous = ledgers.last().unwrap();
+                previous.apply_block(&previous.get_ledger_parameters(), *date, messages)?
+            };
+            ledgers.push(ledger);
+        }
+        Ok(ledgers)
+    }
+
+    /// all the messages of the chain, from the block0
+    pub fn messages(&self) -> impl Iterator<Item = &Message> {
+        self.block0
+            .iter()
+            .chain(self.blocks.iter().flat_map(|(_, messages)| messages.iter()))
+    }
+}
+
+/// a broken invariant of the ledger or of the chain
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvariantError {
+    /// the total value of the ledger changed from the first value
+    ValueNotPreserved(Value, Value),
+    /// a transaction does not spend the value it receives
+    TransactionNotBalanced(TransactionId, Value, Value),
+    /// the output is spent more than once
+    DoubleSpend(UtxoPointer),
+    /// the spent output was not created before
+    UnknownInput(UtxoPointer),
+    Value(ValueError),
+}
+
+impl fmt::Display for InvariantError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            InvariantError::ValueNotPreserved(before, after) => write!(
+                f,
+                "total value not preserved: {} then {}",
+                before.0, after.0
+            ),
+            InvariantError::TransactionNotBalanced(id, inputs, outputs) => write!(
+                f,
+                "transaction {} not balanced: inputs {}, outputs {}",
+                id, inputs.0, outputs.0
+            ),
+            InvariantError::DoubleSpend(pointer) => write!(
+                f,
+                "output {}.{} spent twice",
+                pointer.transaction_id, pointer.output_index
+            ),
+            InvariantError::UnknownInput(pointer) => write!(
+                f,
+                "output {}.{} spent before being created",
+                pointer.transaction_id, pointer.output_index
+            ),
+            InvariantError::Value(e) => write!(f, "{}", e),
+        }
+    }
+}
+impl std::error::Error for InvariantError {}
+
+impl From<ValueError> for InvariantError {
+    fn from(e: ValueError) -> Self {
+        InvariantError::Value(e)
+    }
+}
+
+/// the total value held by the ledger: its unspent outputs and the
+/// balances of its accounts
+pub fn total_value(ledger: &Ledger) -> Result<Value, ValueError> {
+    let utxos = Value::sum(ledger.utxos().map(|entry| entry.output.value))?;
+    utxos + ledger.accounts().get_total_value()?
+}
+
+/// check the ledgers hold the same total value
+pub fn check_value_preservation(ledgers: &[Ledger]) -> Result<(), InvariantError> {
+    let mut ledgers = ledgers.iter();
+    let first = match ledgers.next() {
+        None => return Ok(()),
+        Some(ledger) => total_value(ledger)?,
+    };
+    for ledger in ledgers {
+        let value = total_value(ledger)?;
+        if value != first {
+            return Err(InvariantError::ValueNotPreserved(first, value));
+        }
+    }
+    Ok(())
+}
+
+/// check the transactions spend exactly the value of their inputs, the
+/// inputs of the block0 transactions being the initial value
+pub fn check_balanced_transactions<'a, I>(messages: I) -> Result<(), InvariantError>
+where
+    I: IntoIterator<Item = &'a Message>,
+{
+    for message in messages {
+        if let Message::Transaction(signed) = message {
+            let transaction = &signed.transaction;
+            if transaction.inputs.is_empty() {
+                continue;
+            }
+            let inputs = Value::sum(transaction.inputs.iter().map(|input| input.value))?;
+            let outputs = Value::sum(transaction.outputs.iter().map(|output| output.value))?;
+            if inputs != outputs {
+                return Err(InvariantError::TransactionNotBalanced(
+                    transaction.hash(),
+                    inputs,
+                    outputs,
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// check every spent output was created before and is spent only once
+pub fn check_no_double_spend<'a, I>(messages: I) -> Result<(), InvariantError>
+where
+    I: IntoIterator<Item = &'a Message>,
+{
+    let mut created = HashSet::new();
+    let mut spent = HashSet::new();
+    for message in messages {
+        if let Message::Transaction(signed) = message {
+            let transaction = &signed.transaction;
+            for input in transaction.inputs.iter() {
+                if let InputEnum::UtxoInput(pointer) = input.to_enum() {
+                    let key = (pointer.transaction_id, pointer.output_index);
+                    if !created.contains(&key) {
+                        return Err(InvariantError::UnknownInput(pointer));
+                    }
+                    if !spent.insert(key) {
+                        return Err(InvariantError::DoubleSpend(pointer));
+                    }
+                }
+            }
+            let transaction_id = transaction.hash();
+            for index in 0..transaction.outputs.len() {
+                created.insert((transaction_id, index as u8));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// apply the chain and check all the invariants
+pub fn chain_invariants(chain: &Chain) -> TestResult {
+    let ledgers = match chain.ledgers() {
+        Err(error) => return TestResult::error(format!("invalid chain: {:?}", error)),
+        Ok(ledgers) => ledgers,
+    };
+    let checks = check_value_preservation(&ledgers)
+        .and_then(|()| check_balanced_transactions(chain.messages()))
+        .and_then(|()| check_no_double_spend(chain.messages()));
+    match checks {
+        Err(error) => TestResult::error(format!("{}", error)),
+        Ok(()) => TestResult::passed(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    quickcheck! {
+        fn chain_is_valid(chain: Chain) -> TestResult {
+            chain_invariants(&chain)
+        }
+
+        fn double_spend_rejected(chain: Chain) -> TestResult {
+            // replay a transaction spending outputs in the following block
+            let replayed = chain
+                .blocks
+                .iter()
+                .enumerate()
+                .filter_map(|(i, (_, messages))| messages.first().map(|m| (i, m.clone())))
+                .next();
+            let (index, message) = match replayed {
+                None => return TestResult::discard(),
+                Some(replayed) => replayed,
+            };
+            let mut chain = chain;
+            chain.blocks.truncate(index + 1);
+            let date = chain.blocks[index].0.next();
+            chain.blocks.push((date, vec![message]));
+
+            match check_no_double_spend(chain.messages()) {
+                Err(InvariantError::DoubleSpend(_)) => {}
+                _ => return TestResult::failed(),
+            }
+            TestResult::from_bool(chain.ledgers().is_err())
+        }
+    }
+}
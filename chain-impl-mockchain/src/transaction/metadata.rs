@@ -0,0 +1,139 @@
+//! Metadata attached to a transaction.
+//!
+//! The metadata is an opaque blob for the ledger, which only checks it is
+//! a well-formed CBOR value of bounded size. It is hashed into the
+//! transaction id, so it is committed to by the witnesses of the
+//! transaction, and it lets the applications anchor data (document hashes,
+//! payment references...) on chain.
+
+use chain_core::mempack::{ReadBuf, ReadError, Readable};
+use chain_core::property;
+use std::fmt;
+
+/// the absolute bound of the size of the metadata, the actual limit is
+/// the `max_metadata_size` of the ledger settings
+pub const METADATA_MAX_SIZE: usize = 0xffff;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetadataError {
+    SizeTooBig(usize),
+    InvalidCbor(String),
+}
+
+impl fmt::Display for MetadataError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MetadataError::SizeTooBig(size) => write!(
+                f,
+                "Metadata of {} bytes is bigger than {} bytes",
+                size, METADATA_MAX_SIZE
+            ),
+            MetadataError::InvalidCbor(error) => write!(f, "Metadata is not CBOR: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for MetadataError {}
+
+/// A CBOR value attached to a transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Metadata(Vec<u8>);
+
+impl Metadata {
+    /// check the bytes are a single CBOR value
+    pub fn new(bytes: Vec<u8>) -> Result<Self, MetadataError> {
+        if bytes.len() > METADATA_MAX_SIZE {
+            return Err(MetadataError::SizeTooBig(bytes.len()));
+        }
+        cbor_event::de::Deserializer::from(std::io::Cursor::new(&bytes))
+            .deserialize_complete::<cbor_event::Value>()
+            .map_err(|error| MetadataError::InvalidCbor(error.to_string()))?;
+        Ok(Metadata(bytes))
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// the size of the CBOR value, in bytes
+    pub fn size(&self) -> usize {
+        self.0.len()
+    }
+}
+
+impl AsRef<[u8]> for Metadata {
+    fn as_ref(&self) -> &[u8] {
+        self.as_bytes()
+    }
+}
+
+impl property::Serialize for Metadata {
+    type Error = std::io::Error;
+    fn serialize<W: std::io::Write>(&self, writer: W) -> Result<(), Self::Error> {
+        use chain_core::packer::*;
+        let mut codec = Codec::from(writer);
+        codec.put_u16(self.0.len() as u16)?;
+        codec.write_all(&self.0)?;
+        Ok(())
+    }
+}
+
+impl property::Deserialize for Metadata {
+    type Error = std::io::Error;
+    fn deserialize<R: std::io::BufRead>(reader: R) -> Result<Self, Self::Error> {
+        use chain_core::packer::*;
+        let mut codec = Codec::from(reader);
+        let size = codec.get_u16()?;
+        let bytes = codec.get_bytes(size as usize)?;
+        Metadata::new(bytes)
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))
+    }
+}
+
+impl Readable for Metadata {
+    fn read<'a>(buf: &mut ReadBuf<'a>) -> Result<Self, ReadError> {
+        let size = buf.get_u16()?;
+        let bytes = buf.get_slice(size as usize)?.to_vec();
+        Metadata::new(bytes).map_err(|error| ReadError::StructureInvalid(error.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use quickcheck::{Arbitrary, Gen, TestResult};
+
+    impl Arbitrary for Metadata {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            // a CBOR byte string
+            let bytes: Vec<u8> = Arbitrary::arbitrary(g);
+            let bytes = bytes.into_iter().take(23).collect::<Vec<_>>();
+            let mut cbor = vec![0x40 | bytes.len() as u8];
+            cbor.extend_from_slice(&bytes);
+            Metadata::new(cbor).unwrap()
+        }
+    }
+
+    quickcheck! {
+        fn metadata_serialization_bijection(metadata: Metadata) -> TestResult {
+            chain_core::property::testing::serialization_bijection_r(metadata)
+        }
+    }
+
+    #[test]
+    fn metadata_is_one_cbor_value() {
+        // the text "hello"
+        let hello = vec![0x65, b'h', b'e', b'l', b'l', b'o'];
+        assert_eq!(Metadata::new(hello.clone()).unwrap().size(), 6);
+
+        let mut trailing = hello.clone();
+        trailing.push(0x00);
+        assert!(Metadata::new(trailing).is_err());
+        assert!(Metadata::new(hello[..3].to_vec()).is_err());
+        assert!(Metadata::new(vec![]).is_err());
+        assert_eq!(
+            Metadata::new(vec![0x00; METADATA_MAX_SIZE + 1]),
+            Err(MetadataError::SizeTooBig(METADATA_MAX_SIZE + 1))
+        );
+    }
+}
@@ -1,13 +1,15 @@
+mod metadata;
 mod transaction;
 mod transfer;
 mod utxo;
-mod witness;
+pub(crate) mod witness;
 
 use chain_addr::Address;
 use chain_core::mempack::{read_vec, ReadBuf, ReadError, Readable};
 use chain_core::property;
 
 // to remove..
+pub use metadata::*;
 pub use transaction::*;
 pub use transfer::*;
 pub use utxo::*;
@@ -64,6 +66,9 @@ mod test {
         fn signed_transaction_encode_decode(transaction: AuthenticatedTransaction<Address, NoExtra>) -> TestResult {
             chain_core::property::testing::serialization_bijection_r(transaction)
         }
+        fn signed_transaction_readable_truncations(transaction: AuthenticatedTransaction<Address, NoExtra>) -> TestResult {
+            chain_core::property::testing::readable_truncations(transaction)
+        }
     }
 
     impl Arbitrary for Value {
@@ -115,6 +120,7 @@ mod test {
                     .take(num_outputs % 8)
                     .collect(),
                 extra: Arbitrary::arbitrary(g),
+                metadata: Arbitrary::arbitrary(g),
             }
         }
     }
@@ -1,3 +1,4 @@
+use super::metadata::Metadata;
 use super::transfer::*;
 use crate::key::Hash;
 use crate::value::{Value, ValueError};
@@ -37,8 +38,13 @@ pub struct Transaction<OutAddress, Extra> {
     pub inputs: Vec<Input>,
     pub outputs: Vec<Output<OutAddress>>,
     pub extra: Extra,
+    /// the optional metadata, hashed into the transaction id
+    pub metadata: Option<Metadata>,
 }
 
+const METADATA_NONE: u8 = 0;
+const METADATA_SOME: u8 = 1;
+
 /// Amount of the balance in the transaction.
 pub enum Balance {
     /// Balance is positive.
@@ -58,11 +64,17 @@ impl<Extra: Readable> Transaction<Address, Extra> {
         let inputs = read_vec(buf, num_inputs)?;
         let outputs = read_vec(buf, num_outputs)?;
         let extra = Extra::read(buf)?;
+        let metadata = match buf.get_u8()? {
+            METADATA_NONE => None,
+            METADATA_SOME => Some(Metadata::read(buf)?),
+            tag => return Err(ReadError::UnknownTag(tag as u32)),
+        };
 
         Ok(Transaction {
             inputs,
             outputs,
             extra,
+            metadata,
         })
     }
 
@@ -94,6 +106,13 @@ impl<Extra: property::Serialize> Transaction<Address, Extra> {
             output.value.serialize(&mut codec)?;
         }
         self.extra.serialize(&mut codec)?;
+        match &self.metadata {
+            None => codec.put_u8(METADATA_NONE)?,
+            Some(metadata) => {
+                codec.put_u8(METADATA_SOME)?;
+                metadata.serialize(&mut codec)?;
+            }
+        }
         Ok(())
     }
 
@@ -143,11 +162,23 @@ impl<Extra: property::Deserialize> Transaction<Address, Extra> {
         }
 
         let extra = Extra::deserialize(&mut codec)?;
+        let metadata = match codec.get_u8()? {
+            METADATA_NONE => None,
+            METADATA_SOME => Some(Metadata::deserialize(&mut codec)?),
+            _ => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "invalid transaction metadata tag",
+                )
+                .into())
+            }
+        };
 
         Ok(Transaction {
             inputs,
             outputs,
             extra,
+            metadata,
         })
     }
 
@@ -195,6 +226,7 @@ impl<A, Extra> Transaction<A, Extra> {
             inputs: self.inputs,
             outputs: self.outputs,
             extra: e2,
+            metadata: self.metadata,
         }
     }
 
@@ -2,11 +2,15 @@ use super::transaction::*;
 use crate::account;
 use crate::key::{
     deserialize_public_key, deserialize_signature, serialize_public_key, serialize_signature,
-    AccountSecretKey, AccountSignature, SpendingPublicKey, SpendingSecretKey, SpendingSignature,
+    AccountSignature, SpendingPublicKey, SpendingSignature,
 };
+use crate::multisig;
 use chain_core::mempack::{ReadBuf, ReadError, Readable};
 use chain_core::property;
-use chain_crypto::{Ed25519Bip32, PublicKey, Signature, Verification};
+use chain_crypto::{
+    AsymmetricSigner, Ed25519Bip32, Ed25519Extended, PublicKey, Signature, SignerError,
+    Verification,
+};
 
 /// Structure that proofs that certain user agrees with
 /// some data. This structure is used to sign `Transaction`
@@ -22,6 +26,10 @@ pub enum Witness {
         PublicKey<Ed25519Bip32>,
         Signature<TransactionId, Ed25519Bip32>,
     ),
+    Multisig(Vec<multisig::IndexedSignature>),
+    /// the owners at the given indices of the declaration and their
+    /// MuSig signature under the aggregation of their keys
+    MultisigAggregated(Vec<multisig::Index>, SpendingSignature<TransactionId>),
 }
 
 impl PartialEq for Witness {
@@ -32,6 +40,16 @@ impl PartialEq for Witness {
             (Witness::OldUtxo(p1, s1), Witness::OldUtxo(p2, s2)) => {
                 s1.as_ref() == s2.as_ref() && p1 == p2
             }
+            (Witness::Multisig(s1), Witness::Multisig(s2)) => {
+                s1.len() == s2.len()
+                    && s1
+                        .iter()
+                        .zip(s2.iter())
+                        .all(|((i1, sig1), (i2, sig2))| i1 == i2 && sig1.as_ref() == sig2.as_ref())
+            }
+            (Witness::MultisigAggregated(i1, s1), Witness::MultisigAggregated(i2, s2)) => {
+                i1 == i2 && s1.as_ref() == s2.as_ref()
+            }
             (_, _) => false,
         }
     }
@@ -61,19 +79,50 @@ impl AsRef<[u8]> for TransactionIdSpendingCounter {
 
 impl Witness {
     /// Creates new `Witness` value.
-    pub fn new_utxo(transaction_id: &TransactionId, secret_key: &SpendingSecretKey) -> Self {
-        Witness::Utxo(SpendingSignature::generate(secret_key, transaction_id))
+    ///
+    /// The signer is either the spending secret key or an external device
+    /// holding it, in which case the signing may fail.
+    pub fn new_utxo<S>(transaction_id: &TransactionId, signer: &S) -> Result<Self, SignerError>
+    where
+        S: AsymmetricSigner<Ed25519Extended> + ?Sized,
+    {
+        Ok(Witness::Utxo(SpendingSignature::generate_with(
+            signer,
+            transaction_id,
+        )?))
     }
 
-    pub fn new_account(
+    pub fn new_account<S>(
         transaction_id: &TransactionId,
         spending_counter: &account::SpendingCounter,
-        secret_key: &AccountSecretKey,
-    ) -> Self {
-        Witness::Account(AccountSignature::generate(
-            secret_key,
+        signer: &S,
+    ) -> Result<Self, SignerError>
+    where
+        S: AsymmetricSigner<Ed25519Extended> + ?Sized,
+    {
+        Ok(Witness::Account(AccountSignature::generate_with(
+            signer,
             &TransactionIdSpendingCounter::new(transaction_id, spending_counter),
-        ))
+        )?))
+    }
+
+    /// Creates a multisig witness signed by the owners at the given indices
+    /// of the declaration.
+    pub fn new_multisig<S>(
+        transaction_id: &TransactionId,
+        signers: &[(multisig::Index, &S)],
+    ) -> Result<Self, SignerError>
+    where
+        S: AsymmetricSigner<Ed25519Extended> + ?Sized,
+    {
+        let signatures = signers
+            .iter()
+            .map(|(index, signer)| {
+                SpendingSignature::generate_with(*signer, transaction_id)
+                    .map(|signature| (*index, signature))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Witness::Multisig(signatures))
     }
 
     /// Verify the given `TransactionId` using the witness.
@@ -86,6 +135,8 @@ impl Witness {
             Witness::OldUtxo(_xpub, _signature) => unimplemented!(),
             Witness::Utxo(signature) => signature.verify(public_key, transaction_id),
             Witness::Account(_) => Verification::Failed,
+            Witness::Multisig(_) => Verification::Failed,
+            Witness::MultisigAggregated(_, _) => Verification::Failed,
         }
     }
 }
@@ -93,6 +144,8 @@ impl Witness {
 const WITNESS_TAG_OLDUTXO: u8 = 0u8;
 const WITNESS_TAG_UTXO: u8 = 1u8;
 const WITNESS_TAG_ACCOUNT: u8 = 2u8;
+const WITNESS_TAG_MULTISIG: u8 = 3u8;
+const WITNESS_TAG_MULTISIG_AGGREGATED: u8 = 4u8;
 
 impl property::Serialize for Witness {
     type Error = std::io::Error;
@@ -116,6 +169,14 @@ impl property::Serialize for Witness {
                 codec.put_u8(WITNESS_TAG_ACCOUNT)?;
                 serialize_signature(sig, codec.into_inner())
             }
+            Witness::Multisig(sigs) => {
+                codec.put_u8(WITNESS_TAG_MULTISIG)?;
+                multisig::serialize_signatures(sigs, codec.into_inner())
+            }
+            Witness::MultisigAggregated(signers, sig) => {
+                codec.put_u8(WITNESS_TAG_MULTISIG_AGGREGATED)?;
+                multisig::serialize_aggregated(signers, sig, codec.into_inner())
+            }
         }
     }
 }
@@ -130,6 +191,9 @@ impl Readable for Witness {
             }
             WITNESS_TAG_UTXO => deserialize_signature(buf).map(Witness::Utxo),
             WITNESS_TAG_ACCOUNT => deserialize_signature(buf).map(Witness::Account),
+            WITNESS_TAG_MULTISIG => multisig::deserialize_signatures(buf).map(Witness::Multisig),
+            WITNESS_TAG_MULTISIG_AGGREGATED => multisig::deserialize_aggregated(buf)
+                .map(|(signers, sig)| Witness::MultisigAggregated(signers, sig)),
             i => Err(ReadError::UnknownTag(i as u32)),
         }
     }
@@ -138,6 +202,8 @@ impl Readable for Witness {
 #[cfg(test)]
 pub mod test {
     use super::*;
+    use crate::key::SpendingSecretKey;
+    use chain_crypto::VerificationAlgorithm;
     use quickcheck::{Arbitrary, Gen};
 
     #[derive(Clone)]
@@ -162,6 +228,44 @@ pub mod test {
         }
     }
 
+    /// a signer standing for an external device: the secret key never
+    /// leaves it, it only returns the public key and the signature bytes
+    pub struct DeviceSigner {
+        key: SpendingSecretKey,
+        rejects: bool,
+    }
+
+    impl DeviceSigner {
+        pub fn new(key: SpendingSecretKey) -> Self {
+            DeviceSigner {
+                key,
+                rejects: false,
+            }
+        }
+
+        /// a device on which the user refuses every signing
+        pub fn rejecting(key: SpendingSecretKey) -> Self {
+            DeviceSigner { key, rejects: true }
+        }
+    }
+
+    impl AsymmetricSigner<Ed25519Extended> for DeviceSigner {
+        fn public_key(&self) -> Result<SpendingPublicKey, SignerError> {
+            Ok(self.key.to_public())
+        }
+
+        fn sign_bytes(
+            &self,
+            msg: &[u8],
+        ) -> Result<<Ed25519Extended as VerificationAlgorithm>::Signature, SignerError> {
+            if self.rejects {
+                return Err(SignerError::Rejected);
+            }
+            let signature = SpendingSignature::generate(&self.key, &msg);
+            Ok(Ed25519Extended::signature_from_bytes(signature.as_ref())?)
+        }
+    }
+
     impl Arbitrary for Witness {
         fn arbitrary<G: Gen>(g: &mut G) -> Self {
             let sk = TransactionSigningKey::arbitrary(g);
@@ -177,8 +281,21 @@ pub mod test {
         /// ```
         fn prop_witness_verifies_own_tx(sk: TransactionSigningKey, tx:TransactionId) -> bool {
             let pk = sk.0.to_public();
-            let witness = Witness::new_utxo(&tx, &sk.0);
+            let witness = Witness::new_utxo(&tx, &sk.0).unwrap();
             witness.verify_utxo(&pk, &tx) == Verification::Success
         }
+
+        fn prop_device_witness_verifies_own_tx(sk: TransactionSigningKey, tx: TransactionId) -> bool {
+            let pk = sk.0.to_public();
+            let witness = Witness::new_utxo(&tx, &DeviceSigner::new(sk.0.clone())).unwrap();
+            witness.verify_utxo(&pk, &tx) == Verification::Success
+                && witness == Witness::new_utxo(&tx, &sk.0).unwrap()
+        }
+
+        fn prop_device_rejection_fails_witness(sk: TransactionSigningKey, tx: TransactionId) -> bool {
+            let device = DeviceSigner::rejecting(sk.0);
+            Witness::new_utxo(&tx, &device).unwrap_err() == SignerError::Rejected
+                && Witness::new_account(&tx, &account::SpendingCounter::zero(), &device).is_err()
+        }
     }
 }
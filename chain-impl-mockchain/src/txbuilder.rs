@@ -1,5 +1,5 @@
 use crate::certificate as cert;
-use crate::fee::FeeAlgorithm;
+use crate::fee::{CertificateCount, FeeAlgorithm};
 use crate::transaction::{self as tx, Balance};
 use crate::value::{Value, ValueError};
 use chain_addr::Address;
@@ -57,6 +57,7 @@ impl TransactionBuilder<Address, tx::NoExtra> {
                 inputs: vec![],
                 outputs: vec![],
                 extra: tx::NoExtra,
+                metadata: None,
             },
         }
     }
@@ -77,7 +78,7 @@ impl<Address, Extra> From<tx::Transaction<Address, Extra>> for TransactionBuilde
     }
 }
 
-impl<Extra: Clone> TransactionBuilder<Address, Extra> {
+impl<Extra: Clone + CertificateCount> TransactionBuilder<Address, Extra> {
     /// Create new transaction builder.
 
     /// Add additional input.
@@ -220,6 +221,8 @@ fn set_witness<Address, Extra>(
     match (transaction.inputs[index].get_type(), &witness) {
         (tx::InputType::Utxo, tx::Witness::OldUtxo(_, _)) => (),
         (tx::InputType::Utxo, tx::Witness::Utxo(_)) => (),
+        (tx::InputType::Utxo, tx::Witness::Multisig(_)) => (),
+        (tx::InputType::Utxo, tx::Witness::MultisigAggregated(_, _)) => (),
         (tx::InputType::Account, tx::Witness::Account(_)) => (),
         (_, _) => return Err(BuildError::WitnessMismatch(index)),
     };
@@ -0,0 +1,584 @@
+//! On-chain update of the blockchain settings.
+//!
+//! A BFT leader publishes a signed `ProposalCertificate` carrying a set of
+//! `ConfigParam` changes and the other BFT leaders vote for it with signed
+//! `VoteCertificate`s. At the next epoch boundary, the proposals voted by
+//! a majority of the BFT leaders are applied to the settings; the ones
+//! still not accepted after `proposal_expiration` epochs are dropped.
+//!
+//! Nothing in a signed proposal or its votes ties it to a date, so the ids
+//! of the accepted and expired proposals are kept: publishing one of them
+//! again, e.g. to revert the settings to older values, is rejected.
+
+use crate::block::BlockDate;
+use crate::config::{ConfigParam, ReadMode};
+use crate::key::{deserialize_signature, make_signature, serialize_signature, verify_signature};
+use crate::leadership::bft::{self, LeaderId};
+use crate::message::MessageId;
+use crate::setting::{self, Settings};
+use chain_core::mempack::{ReadBuf, ReadError, Readable};
+use chain_core::property;
+use chain_crypto::{Signature, Verification};
+use std::collections::{HashMap, HashSet};
+
+/// a proposal is identified by the id of the message publishing it
+pub type ProposalId = MessageId;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProposalCertificate {
+    pub changes: Vec<ConfigParam>,
+    pub proposer_id: LeaderId,
+}
+
+impl ProposalCertificate {
+    pub fn sign(self, secret_key: &bft::SigningKey) -> SignedProposalCertificate {
+        let signature = make_signature(secret_key, &self);
+        SignedProposalCertificate {
+            certificate: self,
+            signature,
+        }
+    }
+}
+
+impl property::Serialize for ProposalCertificate {
+    type Error = std::io::Error;
+    fn serialize<W: std::io::Write>(&self, writer: W) -> Result<(), Self::Error> {
+        use chain_core::packer::*;
+        let mut codec = Codec::from(writer);
+        if self.changes.len() > u8::max_value() as usize {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "too many changes in the update proposal",
+            ));
+        }
+        codec.put_u8(self.changes.len() as u8)?;
+        for change in &self.changes {
+            change.serialize(&mut codec)?;
+        }
+        self.proposer_id.serialize(&mut codec)?;
+        Ok(())
+    }
+}
+
+impl Readable for ProposalCertificate {
+    fn read<'a>(buf: &mut ReadBuf<'a>) -> Result<Self, ReadError> {
+        ProposalCertificate::read_with_mode(buf, ReadMode::Strict)
+    }
+}
+
+impl ProposalCertificate {
+    pub fn read_with_mode<'a>(buf: &mut ReadBuf<'a>, mode: ReadMode) -> Result<Self, ReadError> {
+        let len = buf.get_u8()? as usize;
+        let mut changes = Vec::with_capacity(len);
+        for _ in 0..len {
+            changes.push(ConfigParam::read_with_mode(buf, mode)?);
+        }
+        let proposer_id = LeaderId::read(buf)?;
+        Ok(ProposalCertificate {
+            changes,
+            proposer_id,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SignedProposalCertificate {
+    pub certificate: ProposalCertificate,
+    pub signature: Signature<ProposalCertificate, bft::SIGNING_ALGORITHM>,
+}
+
+impl SignedProposalCertificate {
+    pub fn verify(&self) -> Verification {
+        verify_signature(
+            &self.signature,
+            self.certificate.proposer_id.as_public_key(),
+            &self.certificate,
+        )
+    }
+}
+
+impl property::Serialize for SignedProposalCertificate {
+    type Error = std::io::Error;
+    fn serialize<W: std::io::Write>(&self, mut writer: W) -> Result<(), Self::Error> {
+        self.certificate.serialize(&mut writer)?;
+        serialize_signature(&self.signature, &mut writer)
+    }
+}
+
+impl Readable for SignedProposalCertificate {
+    fn read<'a>(buf: &mut ReadBuf<'a>) -> Result<Self, ReadError> {
+        SignedProposalCertificate::read_with_mode(buf, ReadMode::Strict)
+    }
+}
+
+impl SignedProposalCertificate {
+    pub fn read_with_mode<'a>(buf: &mut ReadBuf<'a>, mode: ReadMode) -> Result<Self, ReadError> {
+        Ok(SignedProposalCertificate {
+            certificate: ProposalCertificate::read_with_mode(buf, mode)?,
+            signature: deserialize_signature(buf)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VoteCertificate {
+    pub proposal_id: ProposalId,
+    pub voter_id: LeaderId,
+}
+
+impl VoteCertificate {
+    pub fn sign(self, secret_key: &bft::SigningKey) -> SignedVoteCertificate {
+        let signature = make_signature(secret_key, &self);
+        SignedVoteCertificate {
+            certificate: self,
+            signature,
+        }
+    }
+}
+
+impl property::Serialize for VoteCertificate {
+    type Error = std::io::Error;
+    fn serialize<W: std::io::Write>(&self, mut writer: W) -> Result<(), Self::Error> {
+        self.proposal_id.serialize(&mut writer)?;
+        self.voter_id.serialize(&mut writer)?;
+        Ok(())
+    }
+}
+
+impl Readable for VoteCertificate {
+    fn read<'a>(buf: &mut ReadBuf<'a>) -> Result<Self, ReadError> {
+        Ok(VoteCertificate {
+            proposal_id: ProposalId::read(buf)?,
+            voter_id: LeaderId::read(buf)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SignedVoteCertificate {
+    pub certificate: VoteCertificate,
+    pub signature: Signature<VoteCertificate, bft::SIGNING_ALGORITHM>,
+}
+
+impl SignedVoteCertificate {
+    pub fn verify(&self) -> Verification {
+        verify_signature(
+            &self.signature,
+            self.certificate.voter_id.as_public_key(),
+            &self.certificate,
+        )
+    }
+}
+
+impl property::Serialize for SignedVoteCertificate {
+    type Error = std::io::Error;
+    fn serialize<W: std::io::Write>(&self, mut writer: W) -> Result<(), Self::Error> {
+        self.certificate.serialize(&mut writer)?;
+        serialize_signature(&self.signature, &mut writer)
+    }
+}
+
+impl Readable for SignedVoteCertificate {
+    fn read<'a>(buf: &mut ReadBuf<'a>) -> Result<Self, ReadError> {
+        Ok(SignedVoteCertificate {
+            certificate: VoteCertificate::read(buf)?,
+            signature: deserialize_signature(buf)?,
+        })
+    }
+}
+
+/// A proposal waiting to be accepted, with the BFT leaders who voted
+/// for it (the proposer included).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProposalState {
+    pub proposal: ProposalCertificate,
+    pub proposal_date: BlockDate,
+    pub votes: HashSet<LeaderId>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateState {
+    pub(crate) proposals: HashMap<ProposalId, ProposalState>,
+    /// the proposals already accepted or expired, which cannot be
+    /// published again
+    pub(crate) closed_proposals: HashSet<ProposalId>,
+}
+
+impl UpdateState {
+    pub fn new() -> Self {
+        UpdateState {
+            proposals: HashMap::new(),
+            closed_proposals: HashSet::new(),
+        }
+    }
+
+    pub fn apply_proposal(
+        mut self,
+        proposal_id: ProposalId,
+        proposal: &SignedProposalCertificate,
+        settings: &Settings,
+        cur_date: BlockDate,
+    ) -> Result<Self, Error> {
+        let proposer_id = &proposal.certificate.proposer_id;
+        if !settings.bft_leaders.contains(proposer_id) {
+            return Err(Error::ProposerNotLeader(proposer_id.clone()));
+        }
+        if proposal.verify() == Verification::Failed {
+            return Err(Error::BadProposalSignature);
+        }
+        if self.proposals.contains_key(&proposal_id) {
+            return Err(Error::DuplicateProposal(proposal_id));
+        }
+        if self.closed_proposals.contains(&proposal_id) {
+            return Err(Error::ClosedProposal(proposal_id));
+        }
+        settings
+            .apply(&proposal.certificate.changes)
+            .map_err(Error::InvalidChanges)?;
+
+        let mut votes = HashSet::new();
+        votes.insert(proposer_id.clone());
+        self.proposals.insert(
+            proposal_id,
+            ProposalState {
+                proposal: proposal.certificate.clone(),
+                proposal_date: cur_date,
+                votes,
+            },
+        );
+        Ok(self)
+    }
+
+    pub fn apply_vote(
+        mut self,
+        vote: &SignedVoteCertificate,
+        settings: &Settings,
+    ) -> Result<Self, Error> {
+        let voter_id = &vote.certificate.voter_id;
+        let proposal_id = vote.certificate.proposal_id;
+        if !settings.bft_leaders.contains(voter_id) {
+            return Err(Error::VoterNotLeader(voter_id.clone()));
+        }
+        if vote.verify() == Verification::Failed {
+            return Err(Error::BadVoteSignature);
+        }
+        let proposal = self
+            .proposals
+            .get_mut(&proposal_id)
+            .ok_or(Error::UnknownProposal(proposal_id))?;
+        if !proposal.votes.insert(voter_id.clone()) {
+            return Err(Error::DuplicateVote(proposal_id, voter_id.clone()));
+        }
+        Ok(self)
+    }
+
+    /// On an epoch transition, apply the accepted proposals (in the
+    /// order they were published) to the settings and drop the expired
+    /// ones. Nothing happens if `new_date` is in the same epoch.
+    pub fn process_proposals(
+        mut self,
+        mut settings: Settings,
+        prev_date: BlockDate,
+        new_date: BlockDate,
+    ) -> Result<(Self, Settings), Error> {
+        if new_date.epoch <= prev_date.epoch {
+            return Ok((self, settings));
+        }
+
+        let mut accepted = Vec::new();
+        let mut expired = Vec::new();
+        for (proposal_id, proposal) in self.proposals.iter() {
+            if proposal.votes.len() * 2 > settings.bft_leaders.len() {
+                accepted.push((proposal.proposal_date, *proposal_id));
+            } else if proposal
+                .proposal_date
+                .epoch
+                .saturating_add(settings.proposal_expiration)
+                < new_date.epoch
+            {
+                expired.push(*proposal_id);
+            }
+        }
+        accepted.sort();
+
+        for (_, proposal_id) in accepted {
+            let proposal = self.proposals.remove(&proposal_id).unwrap();
+            settings = settings
+                .apply(&proposal.proposal.changes)
+                .map_err(Error::InvalidChanges)?;
+            self.closed_proposals.insert(proposal_id);
+        }
+        for proposal_id in expired {
+            self.proposals.remove(&proposal_id);
+            self.closed_proposals.insert(proposal_id);
+        }
+        Ok((self, settings))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    ProposerNotLeader(LeaderId),
+    VoterNotLeader(LeaderId),
+    BadProposalSignature,
+    BadVoteSignature,
+    DuplicateProposal(ProposalId),
+    ClosedProposal(ProposalId),
+    UnknownProposal(ProposalId),
+    DuplicateVote(ProposalId, LeaderId),
+    InvalidChanges(setting::Error),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::ProposerNotLeader(leader_id) => write!(
+                f,
+                "Update proposal published by {:?} which is not a BFT leader",
+                leader_id
+            ),
+            Error::VoterNotLeader(leader_id) => write!(
+                f,
+                "Update vote published by {:?} which is not a BFT leader",
+                leader_id
+            ),
+            Error::BadProposalSignature => write!(f, "Invalid update proposal signature"),
+            Error::BadVoteSignature => write!(f, "Invalid update vote signature"),
+            Error::DuplicateProposal(proposal_id) => {
+                write!(f, "Update proposal {} already published", proposal_id)
+            }
+            Error::ClosedProposal(proposal_id) => write!(
+                f,
+                "Update proposal {} already accepted or expired",
+                proposal_id
+            ),
+            Error::UnknownProposal(proposal_id) => {
+                write!(f, "Vote for unknown update proposal {}", proposal_id)
+            }
+            Error::DuplicateVote(proposal_id, leader_id) => write!(
+                f,
+                "{:?} already voted for update proposal {}",
+                leader_id, proposal_id
+            ),
+            Error::InvalidChanges(error) => write!(f, "Invalid update proposal: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::config;
+    use chain_crypto::SecretKey;
+    use quickcheck::{Arbitrary, Gen, TestResult};
+    use std::sync::Arc;
+
+    fn arbitrary_signing_key<G: Gen>(g: &mut G) -> bft::SigningKey {
+        use rand_chacha::ChaChaRng;
+        use rand_core::SeedableRng;
+        let mut seed = [0; 32];
+        for byte in seed.iter_mut() {
+            *byte = Arbitrary::arbitrary(g);
+        }
+        SecretKey::generate(&mut ChaChaRng::from_seed(seed))
+    }
+
+    impl Arbitrary for SignedProposalCertificate {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            let secret_key = arbitrary_signing_key(g);
+            let len = u8::arbitrary(g) % 8;
+            ProposalCertificate {
+                changes: (0..len).map(|_| Arbitrary::arbitrary(g)).collect(),
+                proposer_id: LeaderId::from(secret_key.to_public()),
+            }
+            .sign(&secret_key)
+        }
+    }
+
+    impl Arbitrary for SignedVoteCertificate {
+        fn arbitrary<G: Gen>(g: &mut G) -> Self {
+            let secret_key = arbitrary_signing_key(g);
+            VoteCertificate {
+                proposal_id: Arbitrary::arbitrary(g),
+                voter_id: LeaderId::from(secret_key.to_public()),
+            }
+            .sign(&secret_key)
+        }
+    }
+
+    quickcheck! {
+        fn signed_proposal_serialization_bijection(proposal: SignedProposalCertificate) -> TestResult {
+            let bytes = property::Serialize::serialize_as_vec(&proposal).unwrap();
+            let decoded = chain_core::mempack::read_from_raw::<SignedProposalCertificate>(&bytes).unwrap();
+            TestResult::from_bool(
+                decoded.certificate == proposal.certificate
+                    && decoded.verify() == Verification::Success,
+            )
+        }
+
+        fn signed_vote_serialization_bijection(vote: SignedVoteCertificate) -> TestResult {
+            let bytes = property::Serialize::serialize_as_vec(&vote).unwrap();
+            let decoded = chain_core::mempack::read_from_raw::<SignedVoteCertificate>(&bytes).unwrap();
+            TestResult::from_bool(
+                decoded.certificate == vote.certificate && decoded.verify() == Verification::Success,
+            )
+        }
+    }
+
+    #[test]
+    fn proposal_accepted_at_epoch_boundary() {
+        let mut g = quickcheck::StdGen::new(rand::thread_rng(), 10);
+        let keys: Vec<bft::SigningKey> = (0..3).map(|_| arbitrary_signing_key(&mut g)).collect();
+        let leaders: Vec<LeaderId> = keys.iter().map(|k| LeaderId::from(k.to_public())).collect();
+        let mut settings = Settings::new();
+        settings.bft_leaders = Arc::new(leaders.clone());
+
+        let proposal_id = ProposalId::arbitrary(&mut g);
+        let proposal = ProposalCertificate {
+            changes: vec![ConfigParam::SlotDuration(config::SlotDuration(20))],
+            proposer_id: leaders[0].clone(),
+        }
+        .sign(&keys[0]);
+        let date = BlockDate::first();
+        let state = UpdateState::new()
+            .apply_proposal(proposal_id, &proposal, &settings, date)
+            .unwrap();
+
+        // a single vote out of three is not enough
+        let (state, new_settings) = state
+            .process_proposals(settings.clone(), date, date.next_epoch())
+            .unwrap();
+        assert_eq!(new_settings, settings);
+
+        let vote = VoteCertificate {
+            proposal_id,
+            voter_id: leaders[1].clone(),
+        }
+        .sign(&keys[1]);
+        let state = state.apply_vote(&vote, &settings).unwrap();
+        assert_eq!(
+            state.clone().apply_vote(&vote, &settings).unwrap_err(),
+            Error::DuplicateVote(proposal_id, leaders[1].clone())
+        );
+
+        // no epoch transition, nothing applied yet
+        let (state, new_settings) = state
+            .process_proposals(settings.clone(), date, date.next())
+            .unwrap();
+        assert_eq!(new_settings, settings);
+
+        let (state, new_settings) = state
+            .process_proposals(settings.clone(), date, date.next_epoch())
+            .unwrap();
+        assert_eq!(new_settings.slot_duration, 20);
+        assert!(state.proposals.is_empty());
+
+        // the accepted proposal and its votes cannot be replayed
+        assert_eq!(
+            state
+                .clone()
+                .apply_proposal(proposal_id, &proposal, &new_settings, date.next_epoch())
+                .unwrap_err(),
+            Error::ClosedProposal(proposal_id)
+        );
+        assert_eq!(
+            state.apply_vote(&vote, &new_settings).unwrap_err(),
+            Error::UnknownProposal(proposal_id)
+        );
+    }
+
+    #[test]
+    fn proposal_expired_cannot_be_replayed() {
+        let mut g = quickcheck::StdGen::new(rand::thread_rng(), 10);
+        let keys: Vec<bft::SigningKey> = (0..3).map(|_| arbitrary_signing_key(&mut g)).collect();
+        let leaders: Vec<LeaderId> = keys.iter().map(|k| LeaderId::from(k.to_public())).collect();
+        let mut settings = Settings::new();
+        settings.bft_leaders = Arc::new(leaders.clone());
+        settings.proposal_expiration = 0;
+
+        let proposal_id = ProposalId::arbitrary(&mut g);
+        let proposal = ProposalCertificate {
+            changes: vec![ConfigParam::SlotDuration(config::SlotDuration(20))],
+            proposer_id: leaders[0].clone(),
+        }
+        .sign(&keys[0]);
+        let date = BlockDate::first();
+        let state = UpdateState::new()
+            .apply_proposal(proposal_id, &proposal, &settings, date)
+            .unwrap();
+        let later = date.next_epoch().next_epoch();
+        let (state, _) = state
+            .process_proposals(settings.clone(), date.next_epoch(), later)
+            .unwrap();
+        assert!(state.proposals.is_empty());
+        assert_eq!(
+            state
+                .apply_proposal(proposal_id, &proposal, &settings, later)
+                .unwrap_err(),
+            Error::ClosedProposal(proposal_id)
+        );
+    }
+
+    #[test]
+    fn proposal_too_many_changes() {
+        let mut g = quickcheck::StdGen::new(rand::thread_rng(), 10);
+        let key = arbitrary_signing_key(&mut g);
+        let proposal = ProposalCertificate {
+            changes: vec![ConfigParam::SlotDuration(config::SlotDuration(20)); 256],
+            proposer_id: LeaderId::from(key.to_public()),
+        };
+        assert!(property::Serialize::serialize_as_vec(&proposal).is_err());
+    }
+
+    #[test]
+    fn proposal_rejected() {
+        let mut g = quickcheck::StdGen::new(rand::thread_rng(), 10);
+        let leader_key = arbitrary_signing_key(&mut g);
+        let other_key = arbitrary_signing_key(&mut g);
+        let leader_id = LeaderId::from(leader_key.to_public());
+        let other_id = LeaderId::from(other_key.to_public());
+        let mut settings = Settings::new();
+        settings.bft_leaders = Arc::new(vec![leader_id.clone()]);
+        let proposal_id = ProposalId::arbitrary(&mut g);
+        let date = BlockDate::first();
+
+        let proposal = ProposalCertificate {
+            changes: vec![],
+            proposer_id: other_id.clone(),
+        }
+        .sign(&other_key);
+        assert_eq!(
+            UpdateState::new()
+                .apply_proposal(proposal_id, &proposal, &settings, date)
+                .unwrap_err(),
+            Error::ProposerNotLeader(other_id)
+        );
+
+        let proposal = ProposalCertificate {
+            changes: vec![],
+            proposer_id: leader_id.clone(),
+        }
+        .sign(&other_key);
+        assert_eq!(
+            UpdateState::new()
+                .apply_proposal(proposal_id, &proposal, &settings, date)
+                .unwrap_err(),
+            Error::BadProposalSignature
+        );
+
+        let change = ConfigParam::Block0Date(config::Block0Date(0));
+        let proposal = ProposalCertificate {
+            changes: vec![change.clone()],
+            proposer_id: leader_id,
+        }
+        .sign(&leader_key);
+        assert_eq!(
+            UpdateState::new()
+                .apply_proposal(proposal_id, &proposal, &settings, date)
+                .unwrap_err(),
+            Error::InvalidChanges(setting::Error::ReadOnlySetting(change))
+        );
+    }
+}
@@ -18,6 +18,69 @@ impl Value {
     {
         values.fold(Ok(Value::zero()), |acc, v| acc? + v)
     }
+
+    pub fn checked_add(self, other: Value) -> Result<Self, ValueError> {
+        self + other
+    }
+
+    pub fn checked_sub(self, other: Value) -> Result<Self, ValueError> {
+        self - other
+    }
+
+    pub fn checked_mul(self, factor: u64) -> Result<Self, ValueError> {
+        self.0
+            .checked_mul(factor)
+            .map(Value)
+            .ok_or(ValueError::MultiplicationOverflow)
+    }
+
+    /// add the values, capping the result to the maximum value
+    pub fn saturating_add(self, other: Value) -> Self {
+        Value(self.0.saturating_add(other.0))
+    }
+
+    /// subtract the values, the result being zero if `other` is greater
+    pub fn saturating_sub(self, other: Value) -> Self {
+        Value(self.0.saturating_sub(other.0))
+    }
+
+    /// `self * part / total`, rounded down, without overflowing in the
+    /// intermediate multiplication
+    pub fn scale(self, part: u64, total: u64) -> Result<Self, ValueError> {
+        if total == 0 {
+            return Err(ValueError::DivisionByZero);
+        }
+        let scaled = self.0 as u128 * part as u128 / total as u128;
+        if scaled > u64::max_value() as u128 {
+            return Err(ValueError::MultiplicationOverflow);
+        }
+        Ok(Value(scaled as u64))
+    }
+
+    /// `percent` percent of the value, rounded down
+    pub fn percentage(self, percent: u8) -> Result<Self, ValueError> {
+        self.scale(percent as u64, 100)
+    }
+
+    /// split the value in `count` equal parts, see `ValueSplit`
+    pub fn split_in(self, count: u32) -> Result<ValueSplit, ValueError> {
+        if count == 0 {
+            return Err(ValueError::DivisionByZero);
+        }
+        let count = count as u64;
+        Ok(ValueSplit {
+            parts: Value(self.0 / count),
+            remaining: Value(self.0 % count),
+        })
+    }
+}
+
+/// Result of splitting a value in equal parts: `count * parts + remaining`
+/// is the split value.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ValueSplit {
+    pub parts: Value,
+    pub remaining: Value,
 }
 
 custom_error! {
@@ -25,6 +88,8 @@ custom_error! {
     pub ValueError
         NegativeAmount = "Value cannot be negative",
         Overflow = "Value overflowed its maximum value",
+        MultiplicationOverflow = "Value overflowed its maximum value when multiplied",
+        DivisionByZero = "Value divided by zero",
 }
 
 impl ops::Add for Value {
@@ -78,3 +143,52 @@ impl property::Serialize for Value {
         codec.put_u64(self.0)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use quickcheck::TestResult;
+
+    quickcheck! {
+        fn split_in_parts(value: u64, count: u32) -> TestResult {
+            if count == 0 {
+                return TestResult::discard();
+            }
+            let split = Value(value).split_in(count).unwrap();
+            let total = (split.parts.checked_mul(count as u64).unwrap() + split.remaining).unwrap();
+            TestResult::from_bool(total == Value(value) && split.remaining.0 < count as u64)
+        }
+
+        fn scale_is_bounded(value: u64, part: u64, total: u64) -> TestResult {
+            if total == 0 || part > total {
+                return TestResult::discard();
+            }
+            TestResult::from_bool(Value(value).scale(part, total).unwrap() <= Value(value))
+        }
+    }
+
+    #[test]
+    fn checked_operations() {
+        let max = Value(u64::max_value());
+        assert_eq!(max.checked_add(Value(1)), Err(ValueError::Overflow));
+        assert_eq!(
+            Value(1).checked_sub(Value(2)),
+            Err(ValueError::NegativeAmount)
+        );
+        assert_eq!(max.checked_mul(2), Err(ValueError::MultiplicationOverflow));
+        assert_eq!(max.saturating_add(Value(1)), max);
+        assert_eq!(Value(1).saturating_sub(Value(2)), Value::zero());
+        assert_eq!(max.scale(2, 1), Err(ValueError::MultiplicationOverflow));
+        assert_eq!(max.scale(3, 4), Ok(Value(u64::max_value() / 4 * 3 + 2)));
+        assert_eq!(Value(1).scale(1, 0), Err(ValueError::DivisionByZero));
+        assert_eq!(Value(199).percentage(50), Ok(Value(99)));
+        assert_eq!(Value(1).split_in(0), Err(ValueError::DivisionByZero));
+        assert_eq!(
+            Value(10).split_in(3),
+            Ok(ValueSplit {
+                parts: Value(3),
+                remaining: Value(1)
+            })
+        );
+    }
+}
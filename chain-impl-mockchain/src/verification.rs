@@ -0,0 +1,262 @@
+//! Batch verification of the witnesses.
+//!
+//! Verifying the signatures is the most expensive part of applying a
+//! block. The ledger does every other check of a transaction (inputs,
+//! values, balance) as it applies it, and pushes the signature checks
+//! in a `WitnessBatch` which is verified once all the messages of the
+//! block are applied. The signatures of the spending keys are verified
+//! together with the Ed25519 batch verification, the other checks one by
+//! one. With the `parallel` feature, the checks are spread on the rayon
+//! thread pool.
+
+use crate::key::{SpendingPublicKey, SpendingSignature};
+use crate::ledger::Error;
+use chain_crypto::Verification;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+type Check = Box<dyn Fn() -> Verification + Send + Sync>;
+
+/// number of spending signatures verified together by a thread
+#[cfg(feature = "parallel")]
+const SPENDING_CHUNK: usize = 64;
+
+/// a check of the batch, indexing its list
+enum Pending {
+    Spending(usize),
+    Check(usize),
+}
+
+/// Signature checks deferred until the end of the block, each with the
+/// error to report if the signature is invalid
+pub struct WitnessBatch {
+    spending: Vec<(SpendingPublicKey, Vec<u8>, SpendingSignature<Vec<u8>>)>,
+    checks: Vec<Check>,
+    /// the pending checks in the order they were pushed
+    errors: Vec<(Pending, Error)>,
+}
+
+impl WitnessBatch {
+    pub fn new() -> Self {
+        WitnessBatch {
+            spending: Vec::new(),
+            checks: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    /// defer the given signature check, `error` is reported if it fails
+    pub fn push<F>(&mut self, error: Error, check: F)
+    where
+        F: Fn() -> Verification + Send + Sync + 'static,
+    {
+        self.errors.push((Pending::Check(self.checks.len()), error));
+        self.checks.push(Box::new(check))
+    }
+
+    /// defer the check of the signature of a spending key, verified with
+    /// the other spending signatures of the batch. `error` is reported if
+    /// it fails.
+    pub fn push_spending<T: AsRef<[u8]>>(
+        &mut self,
+        error: Error,
+        public_key: SpendingPublicKey,
+        message: &T,
+        signature: SpendingSignature<T>,
+    ) {
+        self.errors
+            .push((Pending::Spending(self.spending.len()), error));
+        self.spending
+            .push((public_key, message.as_ref().to_vec(), signature.coerce()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    fn failed(&self, pending: &Pending) -> bool {
+        let verification = match pending {
+            Pending::Spending(index) => {
+                let (public_key, message, signature) = &self.spending[*index];
+                signature.verify(public_key, message)
+            }
+            Pending::Check(index) => self.checks[*index](),
+        };
+        verification == Verification::Failed
+    }
+
+    /// verify all the signatures of the batch. If several are invalid,
+    /// the error of the first one pushed is reported, whether the
+    /// verification is parallel or not.
+    #[cfg(feature = "parallel")]
+    pub fn verify(self) -> Result<(), Error> {
+        let valid = self
+            .spending
+            .par_chunks(SPENDING_CHUNK)
+            .all(|chunk| Verification::batch_verify(chunk) == Verification::Success)
+            && self
+                .checks
+                .par_iter()
+                .all(|check| check() == Verification::Success);
+        if valid {
+            return Ok(());
+        }
+        // find the invalid signature, one by one
+        let failed = self
+            .errors
+            .par_iter()
+            .position_first(|(pending, _)| self.failed(pending));
+        match failed {
+            None => Ok(()),
+            Some(index) => Err(self.into_error(index)),
+        }
+    }
+
+    /// verify all the signatures of the batch. If several are invalid,
+    /// the error of the first one pushed is reported, whether the
+    /// verification is parallel or not.
+    #[cfg(not(feature = "parallel"))]
+    pub fn verify(self) -> Result<(), Error> {
+        let valid = Verification::batch_verify(&self.spending) == Verification::Success
+            && self
+                .checks
+                .iter()
+                .all(|check| check() == Verification::Success);
+        if valid {
+            return Ok(());
+        }
+        // find the invalid signature, one by one
+        let failed = self
+            .errors
+            .iter()
+            .position(|(pending, _)| self.failed(pending));
+        match failed {
+            None => Ok(()),
+            Some(index) => Err(self.into_error(index)),
+        }
+    }
+
+    fn into_error(mut self, index: usize) -> Error {
+        self.errors.swap_remove(index).1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::multisig::test::arbitrary_secret_key;
+    use crate::transaction::TransactionId;
+    use quickcheck::Arbitrary;
+
+    #[test]
+    fn first_failure_reported() {
+        let mut batch = WitnessBatch::new();
+        assert!(batch.is_empty());
+        for i in 0..100 {
+            let verification = if i % 30 == 29 {
+                Verification::Failed
+            } else {
+                Verification::Success
+            };
+            batch.push(Error::NotEnoughSignatures(i, 0), move || verification);
+        }
+        assert_eq!(batch.len(), 100);
+        assert_eq!(batch.verify(), Err(Error::NotEnoughSignatures(29, 0)));
+
+        let mut batch = WitnessBatch::new();
+        batch.push(Error::TransactionHasNoInput, || Verification::Success);
+        assert_eq!(batch.verify(), Ok(()));
+    }
+
+    #[test]
+    fn spending_signatures_batched() {
+        let mut g = quickcheck::StdGen::new(rand::thread_rng(), 10);
+        let keys: Vec<_> = (0..10).map(|_| arbitrary_secret_key(&mut g)).collect();
+        let txid = TransactionId::arbitrary(&mut g);
+        let batch = |invalid: Option<usize>| {
+            let mut batch = WitnessBatch::new();
+            for (i, key) in keys.iter().enumerate() {
+                let signer = if invalid == Some(i) { &keys[0] } else { key };
+                let signature = SpendingSignature::generate(signer, &txid);
+                let error = Error::NotEnoughSignatures(i, 0);
+                batch.push_spending(error, key.to_public(), &txid, signature);
+                batch.push(Error::NotEnoughSignatures(i, 1), || Verification::Success);
+            }
+            batch
+        };
+
+        assert_eq!(batch(None).verify(), Ok(()));
+        assert_eq!(
+            batch(Some(7)).verify(),
+            Err(Error::NotEnoughSignatures(7, 0))
+        );
+    }
+
+    /// a batch of spending signatures over several chunks of the parallel
+    /// verification, with the signatures of the given indices invalid
+    fn spending_batch(signatures: usize, invalid: &[usize]) -> WitnessBatch {
+        let mut g = quickcheck::StdGen::new(rand::thread_rng(), 10);
+        let keys: Vec<_> = (0..2).map(|_| arbitrary_secret_key(&mut g)).collect();
+        let txid = TransactionId::arbitrary(&mut g);
+        let mut batch = WitnessBatch::new();
+        for i in 0..signatures {
+            let signer = if invalid.contains(&i) {
+                &keys[1]
+            } else {
+                &keys[0]
+            };
+            let signature = SpendingSignature::generate(signer, &txid);
+            batch.push_spending(
+                Error::NotEnoughSignatures(i, 0),
+                keys[0].to_public(),
+                &txid,
+                signature,
+            );
+        }
+        batch
+    }
+
+    #[test]
+    fn first_failure_reported_across_chunks() {
+        assert_eq!(spending_batch(200, &[]).verify(), Ok(()));
+        assert_eq!(
+            spending_batch(200, &[199, 150, 70]).verify(),
+            Err(Error::NotEnoughSignatures(70, 0))
+        );
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn parallel_verification() {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap();
+
+        let mut batch = spending_batch(4 * SPENDING_CHUNK + 1, &[4 * SPENDING_CHUNK]);
+        for i in 0..100 {
+            let verification = if i >= 90 {
+                Verification::Failed
+            } else {
+                Verification::Success
+            };
+            batch.push(Error::NotEnoughSignatures(i, 1), move || verification);
+        }
+        // the spending signatures were pushed first: their error is the
+        // one reported, whichever thread finds a failure first
+        assert_eq!(
+            pool.install(|| batch.verify()),
+            Err(Error::NotEnoughSignatures(4 * SPENDING_CHUNK, 0))
+        );
+
+        let mut batch = spending_batch(4 * SPENDING_CHUNK + 1, &[]);
+        for i in 0..100 {
+            batch.push(Error::NotEnoughSignatures(i, 1), || Verification::Success);
+        }
+        assert_eq!(pool.install(|| batch.verify()), Ok(()));
+    }
+}
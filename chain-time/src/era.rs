@@ -36,6 +36,21 @@ impl TimeEra {
         }
     }
 
+    /// The first epoch of this era
+    pub fn epoch_start(&self) -> Epoch {
+        self.epoch_start
+    }
+
+    /// The first slot of this era
+    pub fn slot_start(&self) -> Slot {
+        self.slot_start
+    }
+
+    /// The number of slots of every epoch of this era
+    pub fn slots_per_epoch(&self) -> u32 {
+        self.slots_per_epoch
+    }
+
     /// Try to return the epoch/inner-epoch-slot associated.
     ///
     /// If the slot in parameter is before the beginning of this era, then
@@ -59,7 +74,8 @@ impl TimeEra {
         assert!(pos.epoch >= self.epoch_start);
         assert!(pos.slot.0 < self.slots_per_epoch);
 
-        let slot_offset = (pos.epoch.0 as u64) * (self.slots_per_epoch as u64) + pos.slot.0 as u64;
+        let epoch_offset = (pos.epoch.0 - self.epoch_start.0) as u64;
+        let slot_offset = epoch_offset * (self.slots_per_epoch as u64) + pos.slot.0 as u64;
         Slot(self.slot_start.0 + slot_offset)
     }
 }
@@ -120,5 +136,9 @@ mod test {
                 slot: EpochSlotOffset(2)
             }
         );
+
+        assert_eq!(era.from_era_to_slot(p1), slot1);
+        assert_eq!(era.from_era_to_slot(p2), slot2);
+        assert_eq!(era.from_era_to_slot(p3), slot3);
     }
 }
@@ -1,3 +1,4 @@
 pub mod era;
+pub mod schedule;
 pub mod timeframe;
 pub mod timeline;
@@ -0,0 +1,299 @@
+//! Conversion between the system time, the slots and the epochs of a
+//! blockchain
+//!
+//! ```text
+//!  block0 date                   epoch 2
+//!  ↓                             ↓
+//!  x----x----x----x----x----x----x--x--x--x--x--x--x--x--x  ...
+//!  epoch 0        epoch 1        (change to 2 secs per slot and 8 slots
+//!  (5 secs per slot, 3 slots per epoch)                    per epoch)
+//! ```
+//!
+//! The slot duration and the number of slots per epoch start with the
+//! parameters of the block0, and can change at the beginning of an epoch.
+
+use crate::era::{Epoch, EpochPosition, EpochSlotOffset, TimeEra};
+use crate::timeframe::{Slot, SlotDuration, TimeFrame};
+use crate::timeline::{ClockOffset, Timeline};
+use std::fmt;
+use std::time::SystemTime;
+
+/// The time frame and the era of the epochs sharing the same parameters
+#[derive(Debug, Clone)]
+struct Period {
+    frame: TimeFrame,
+    era: TimeEra,
+}
+
+/// The succession of the periods of a blockchain, from the block0 date
+#[derive(Debug, Clone)]
+pub struct Schedule {
+    /// the period starting at the block0
+    first: Period,
+    /// the following periods, ordered by starting epoch
+    changes: Vec<Period>,
+}
+
+/// Invalid parameters of a period of the schedule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// an epoch has no slot
+    ZeroSlotsPerEpoch,
+    /// the change at the first epoch does not come after the last change,
+    /// at the second epoch
+    ChangeNotInOrder(Epoch, Epoch),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::ZeroSlotsPerEpoch => write!(f, "an epoch needs at least one slot"),
+            Error::ChangeNotInOrder(epoch, last) => write!(
+                f,
+                "change at epoch {} does not come after the last change at epoch {}",
+                epoch.0, last.0
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Schedule {
+    /// Create the schedule of a blockchain starting at the block0 date with
+    /// the given slot duration and number of slots per epoch
+    pub fn new(
+        block0_date: SystemTime,
+        slot_duration: SlotDuration,
+        slots_per_epoch: u32,
+    ) -> Result<Self, Error> {
+        if slots_per_epoch == 0 {
+            return Err(Error::ZeroSlotsPerEpoch);
+        }
+        Ok(Schedule {
+            first: Period {
+                frame: TimeFrame::new(Timeline::new(block0_date), slot_duration),
+                era: TimeEra::new_era(Slot(0), Epoch(0), slots_per_epoch),
+            },
+            changes: Vec::new(),
+        })
+    }
+
+    /// Change the slot duration and the number of slots per epoch from the
+    /// beginning of the given epoch
+    ///
+    /// The changes have to be applied in order: the epoch must come after
+    /// the epoch of the last change.
+    pub fn change_at(
+        &mut self,
+        epoch: Epoch,
+        slot_duration: SlotDuration,
+        slots_per_epoch: u32,
+    ) -> Result<(), Error> {
+        if slots_per_epoch == 0 {
+            return Err(Error::ZeroSlotsPerEpoch);
+        }
+        let period = {
+            let last = self.changes.last().unwrap_or(&self.first);
+            if epoch <= last.era.epoch_start() {
+                return Err(Error::ChangeNotInOrder(epoch, last.era.epoch_start()));
+            }
+            let slot = last.era.from_era_to_slot(EpochPosition {
+                epoch,
+                slot: EpochSlotOffset(0),
+            });
+            Period {
+                frame: last.frame.change_frame(slot, slot_duration),
+                era: TimeEra::new_era(slot, epoch, slots_per_epoch),
+            }
+        };
+        self.changes.push(period);
+        Ok(())
+    }
+
+    fn period_of_slot(&self, slot: Slot) -> &Period {
+        self.changes
+            .iter()
+            .rev()
+            .find(|period| period.era.slot_start() <= slot)
+            .unwrap_or(&self.first)
+    }
+
+    fn period_of_epoch(&self, epoch: Epoch) -> &Period {
+        self.changes
+            .iter()
+            .rev()
+            .find(|period| period.era.epoch_start() <= epoch)
+            .unwrap_or(&self.first)
+    }
+
+    /// The slot duration in use at the given epoch
+    pub fn slot_duration(&self, epoch: Epoch) -> SlotDuration {
+        self.period_of_epoch(epoch).frame.slot_duration()
+    }
+
+    /// The number of slots of the given epoch
+    pub fn slots_per_epoch(&self, epoch: Epoch) -> u32 {
+        self.period_of_epoch(epoch).era.slots_per_epoch()
+    }
+
+    /// Get the slot associated with the given system time
+    ///
+    /// None is returned if the time is before the block0 date.
+    pub fn slot_at(&self, at: &SystemTime) -> Option<Slot> {
+        self.changes
+            .iter()
+            .rev()
+            .chain(std::iter::once(&self.first))
+            .filter_map(|period| period.frame.slot_at(at))
+            .next()
+    }
+
+    /// Get the epoch and the slot in the epoch associated with the given
+    /// system time
+    ///
+    /// None is returned if the time is before the block0 date.
+    pub fn position_at(&self, at: &SystemTime) -> Option<EpochPosition> {
+        self.slot_at(at).and_then(|slot| self.position_of(slot))
+    }
+
+    /// Get the current epoch and slot in the epoch, the local clock being
+    /// corrected with the given offset
+    ///
+    /// None is returned if the corrected time cannot be represented or is
+    /// before the block0 date.
+    pub fn position_now(&self, clock_offset: ClockOffset) -> Option<EpochPosition> {
+        clock_offset
+            .correct(SystemTime::now())
+            .and_then(|now| self.position_at(&now))
+    }
+
+    /// Get the epoch and the slot in the epoch of a flat slot
+    pub fn position_of(&self, slot: Slot) -> Option<EpochPosition> {
+        // the slot is in the era of its own period
+        self.period_of_slot(slot).era.from_slot_to_era(slot)
+    }
+
+    /// Get the flat slot of an epoch position
+    ///
+    /// None is returned if the slot offset is not in the epoch.
+    pub fn slot_of(&self, pos: EpochPosition) -> Option<Slot> {
+        let era = &self.period_of_epoch(pos.epoch).era;
+        if pos.slot.0 < era.slots_per_epoch() {
+            Some(era.from_era_to_slot(pos))
+        } else {
+            None
+        }
+    }
+
+    /// Get the system time at the beginning of a slot
+    pub fn slot_to_systemtime(&self, slot: Slot) -> Option<SystemTime> {
+        // the slot is in the frame of its own period
+        self.period_of_slot(slot).frame.slot_to_systemtime(slot)
+    }
+
+    /// Get the system time at the beginning of an epoch position
+    ///
+    /// None is returned if the slot offset is not in the epoch.
+    pub fn position_to_systemtime(&self, pos: EpochPosition) -> Option<SystemTime> {
+        self.slot_of(pos)
+            .and_then(|slot| self.slot_to_systemtime(slot))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::time::Duration;
+
+    fn position(epoch: u32, slot: u32) -> EpochPosition {
+        EpochPosition {
+            epoch: Epoch(epoch),
+            slot: EpochSlotOffset(slot),
+        }
+    }
+
+    #[test]
+    pub fn it_works() {
+        let block0 = SystemTime::now();
+        let mut schedule = Schedule::new(block0, SlotDuration::from_secs(5), 3).unwrap();
+        schedule
+            .change_at(Epoch(2), SlotDuration::from_secs(2), 8)
+            .unwrap();
+        schedule
+            .change_at(Epoch(4), SlotDuration::from_secs(10), 2)
+            .unwrap();
+
+        let at = |secs| block0 + Duration::from_secs(secs);
+
+        assert_eq!(
+            schedule.position_at(&(block0 - Duration::from_secs(1))),
+            None
+        );
+        assert_eq!(schedule.position_at(&at(0)), Some(position(0, 0)));
+        assert_eq!(schedule.position_at(&at(29)), Some(position(1, 2)));
+
+        // epoch 2 starts at slot 6, 30 seconds after the block0
+        assert_eq!(schedule.slot_at(&at(30)), Some(Slot(6)));
+        assert_eq!(schedule.position_at(&at(30)), Some(position(2, 0)));
+        assert_eq!(schedule.position_at(&at(47)), Some(position(3, 0)));
+
+        // epoch 4 starts at slot 22, 62 seconds after the block0
+        assert_eq!(schedule.slot_of(position(4, 0)), Some(Slot(22)));
+        assert_eq!(schedule.slot_to_systemtime(Slot(22)), Some(at(62)));
+        assert_eq!(schedule.position_at(&at(61)), Some(position(3, 7)));
+        assert_eq!(schedule.position_at(&at(92)), Some(position(5, 1)));
+
+        assert_eq!(schedule.slot_of(position(1, 3)), None);
+        assert_eq!(schedule.slots_per_epoch(Epoch(3)), 8);
+        assert_eq!(schedule.slot_duration(Epoch(1)), SlotDuration::from_secs(5));
+
+        for slot in 0..30 {
+            let pos = schedule.position_of(Slot(slot)).unwrap();
+            assert_eq!(schedule.slot_of(pos), Some(Slot(slot)));
+            let time = schedule.position_to_systemtime(pos).unwrap();
+            assert_eq!(schedule.slot_at(&time), Some(Slot(slot)));
+        }
+    }
+
+    #[test]
+    pub fn clock_offset() {
+        let block0 = SystemTime::now() - Duration::from_secs(100);
+        let schedule = Schedule::new(block0, SlotDuration::from_secs(20), 10).unwrap();
+
+        let ahead = ClockOffset::Ahead(Duration::from_secs(60));
+        assert_eq!(schedule.position_now(ahead), Some(position(0, 2)));
+        let behind = ClockOffset::Behind(Duration::from_secs(120));
+        assert_eq!(schedule.position_now(behind), Some(position(1, 1)));
+
+        let far_ahead = ClockOffset::Ahead(Duration::from_secs(u64::max_value()));
+        assert_eq!(far_ahead.correct(SystemTime::now()), None);
+        assert_eq!(schedule.position_now(far_ahead), None);
+    }
+
+    #[test]
+    pub fn invalid_changes() {
+        let block0 = SystemTime::now();
+        let slot_duration = SlotDuration::from_secs(5);
+        assert_eq!(
+            Schedule::new(block0, slot_duration, 0).unwrap_err(),
+            Error::ZeroSlotsPerEpoch
+        );
+
+        let mut schedule = Schedule::new(block0, slot_duration, 3).unwrap();
+        assert_eq!(
+            schedule.change_at(Epoch(0), slot_duration, 4),
+            Err(Error::ChangeNotInOrder(Epoch(0), Epoch(0)))
+        );
+        assert_eq!(
+            schedule.change_at(Epoch(2), slot_duration, 0),
+            Err(Error::ZeroSlotsPerEpoch)
+        );
+        schedule.change_at(Epoch(2), slot_duration, 4).unwrap();
+        assert_eq!(
+            schedule.change_at(Epoch(2), slot_duration, 5),
+            Err(Error::ChangeNotInOrder(Epoch(2), Epoch(2)))
+        );
+        assert_eq!(schedule.slots_per_epoch(Epoch(2)), 4);
+    }
+}
@@ -84,15 +84,21 @@ impl TimeFrame {
     /// ```
     ///
     pub fn change_frame(&self, slot: Slot, duration_per_slot: SlotDuration) -> Self {
-        let d = Duration::from_secs(slot.0 * self.slot_duration.0);
+        assert!(slot >= self.slot_offset);
+        let d = Duration::from_secs((slot.0 - self.slot_offset.0) * self.slot_duration.0);
         let new_timeline = self.timeline.advance(d);
         TimeFrame {
             timeline: new_timeline,
-            slot_offset: Slot(self.slot_offset.0 + slot.0),
+            slot_offset: slot,
             slot_duration: duration_per_slot,
         }
     }
 
+    /// The duration of the slots of this time frame
+    pub fn slot_duration(&self) -> SlotDuration {
+        self.slot_duration
+    }
+
     /// Given a system time get the slot and associated duration leftover
     pub fn slot_at_precise(&self, at: &SystemTime) -> Option<SlotAndDuration> {
         match self.timeline.differential(at) {
@@ -170,5 +176,13 @@ mod test {
         assert_eq!(tf1.slot_at(&t2), Some(Slot(7)));
 
         assert_eq!(tf0.slot_at(&t2), Some(Slot(4)));
+
+        let f2 = SlotDuration::from_secs(1);
+        let tf2 = tf1.change_frame(Slot(7), f2);
+        assert_eq!(tf2.slot_at(&t2), Some(Slot(7)));
+        assert_eq!(
+            tf2.slot_to_systemtime(Slot(10)),
+            Some(t2 + Duration::from_secs(3))
+        );
     }
 }
@@ -8,6 +8,34 @@ pub struct Timeline(pub(crate) SystemTime);
 #[derive(Debug, Clone)]
 pub struct TimeOffset(pub(crate) Duration);
 
+/// Offset of the local clock to the reference time, e.g. as measured
+/// against a NTP server
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClockOffset {
+    /// the local clock is ahead of the reference time
+    Ahead(Duration),
+    /// the local clock is behind the reference time
+    Behind(Duration),
+}
+
+impl ClockOffset {
+    /// Convert a time of the local clock to the reference time
+    ///
+    /// None is returned if the reference time cannot be represented.
+    pub fn correct(&self, local: SystemTime) -> Option<SystemTime> {
+        match self {
+            ClockOffset::Ahead(d) => local.checked_sub(*d),
+            ClockOffset::Behind(d) => local.checked_add(*d),
+        }
+    }
+}
+
+impl Default for ClockOffset {
+    fn default() -> Self {
+        ClockOffset::Ahead(Duration::from_secs(0))
+    }
+}
+
 impl From<SystemTime> for Timeline {
     fn from(s: SystemTime) -> Self {
         Timeline(s)
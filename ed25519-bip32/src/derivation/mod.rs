@@ -16,6 +16,19 @@ pub enum DerivationError {
     ExpectedSoftDerivation,
 }
 
+impl core::fmt::Display for DerivationError {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            DerivationError::InvalidAddition => write!(f, "Invalid point addition"),
+            DerivationError::ExpectedSoftDerivation => {
+                write!(f, "Hardened derivation needs the private key")
+            }
+        }
+    }
+}
+#[cfg(feature = "std")]
+impl std::error::Error for DerivationError {}
+
 fn add_256bits(x: &[u8], y: &[u8], scheme: DerivationScheme) -> [u8; 32] {
     match scheme {
         DerivationScheme::V1 => v1::add_256bits_v1(x, y),
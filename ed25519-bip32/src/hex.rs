@@ -1,3 +1,5 @@
+use alloc::{string::String, vec::Vec};
+
 const ALPHABET: &'static [u8] = b"0123456789abcdef";
 
 pub fn encode(input: &[u8]) -> String {
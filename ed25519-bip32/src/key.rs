@@ -1,10 +1,10 @@
-use std::fmt;
+use core::fmt;
 
 use cryptoxide::ed25519;
 use cryptoxide::ed25519::signature_extended;
 use cryptoxide::util::fixed_time_eq;
 
-use std::hash::{Hash, Hasher};
+use core::hash::{Hash, Hasher};
 
 use super::derivation::{self, DerivationError, DerivationIndex, DerivationScheme};
 use super::hex;
@@ -1,4 +1,7 @@
 #![cfg_attr(feature = "with-bench", feature(test))]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
 
 #[cfg(test)]
 #[cfg(feature = "with-bench")]
@@ -7,7 +10,7 @@ extern crate test;
 mod derivation;
 mod hex;
 mod key;
-mod securemem;
+pub mod securemem;
 mod signature;
 
 #[cfg(test)]
@@ -17,6 +20,6 @@ mod tests;
 #[cfg(feature = "with-bench")]
 mod bench;
 
-pub use derivation::{DerivationIndex, DerivationScheme};
+pub use derivation::{DerivationError, DerivationIndex, DerivationScheme};
 pub use key::{PrivateKeyError, PublicKeyError, XPrv, XPub, XPRV_SIZE, XPUB_SIZE};
 pub use signature::{Signature, SignatureError, SIGNATURE_SIZE};
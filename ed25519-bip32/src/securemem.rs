@@ -1,9 +1,150 @@
+//! Storage for secret material.
+//!
+//! `SecureMem` keeps its bytes in a heap allocation of its own, so the
+//! secret is not copied around when the key owning it is moved, and
+//! zeroes them when dropped. With the `mlock` feature, the allocation
+//! spans whole pages which are locked in memory so the secret is never
+//! written to the swap.
+
+use alloc::alloc::{alloc_zeroed, dealloc, handle_alloc_error};
+use core::alloc::Layout;
+use core::ptr::NonNull;
+use core::sync::atomic::{self, Ordering};
+
+/// zero the given slice.
+///
+/// The writes are volatile so the compiler cannot optimise them out,
+/// even if the memory is not read afterward.
 pub fn zero(to_zero: &mut [u8]) {
-    // the unsafety of this call is bounded to the existence of the pointer
-    // and the accuracy of the length of the array.
-    //
-    // since to_zero existence is bound to live at least as long as the call
-    // of this function and that we use the length (in bytes) of the given
-    // slice, this call is safe.
-    unsafe { ::std::ptr::write_bytes(to_zero.as_mut_ptr(), 0, to_zero.len()) }
+    for byte in to_zero.iter_mut() {
+        // the pointer comes from a valid mutable reference
+        unsafe { core::ptr::write_volatile(byte, 0) };
+    }
+    atomic::compiler_fence(Ordering::SeqCst);
+}
+
+/// Fixed size buffer of secret bytes, zeroed on drop
+pub struct SecureMem {
+    ptr: NonNull<u8>,
+    len: usize,
+    layout: Layout,
+    locked: bool,
+}
+
+// the buffer is owned and only accessed through references to SecureMem
+unsafe impl Send for SecureMem {}
+unsafe impl Sync for SecureMem {}
+
+impl SecureMem {
+    /// allocate `len` zeroed bytes
+    pub fn new(len: usize) -> Self {
+        let layout = layout_for(len);
+        let ptr = unsafe { alloc_zeroed(layout) };
+        let ptr = NonNull::new(ptr).unwrap_or_else(|| handle_alloc_error(layout));
+        let locked = lock(ptr.as_ptr(), layout.size());
+        SecureMem {
+            ptr,
+            len,
+            layout,
+            locked,
+        }
+    }
+
+    /// allocate a copy of the given bytes
+    pub fn from_slice(bytes: &[u8]) -> Self {
+        let mut mem = SecureMem::new(bytes.len());
+        mem.as_mut().copy_from_slice(bytes);
+        mem
+    }
+
+    /// whether the memory is locked: always false without the `mlock`
+    /// feature, and may be false with it if the lock limit of the
+    /// process is reached
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+}
+
+impl AsRef<[u8]> for SecureMem {
+    fn as_ref(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl AsMut<[u8]> for SecureMem {
+    fn as_mut(&mut self) -> &mut [u8] {
+        unsafe { core::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl Clone for SecureMem {
+    fn clone(&self) -> Self {
+        SecureMem::from_slice(self.as_ref())
+    }
+}
+
+impl Drop for SecureMem {
+    fn drop(&mut self) {
+        zero(self.as_mut());
+        if self.locked {
+            unlock(self.ptr.as_ptr(), self.layout.size());
+        }
+        unsafe { dealloc(self.ptr.as_ptr(), self.layout) }
+    }
+}
+
+#[cfg(all(unix, feature = "mlock"))]
+fn layout_for(len: usize) -> Layout {
+    // locks apply to whole pages and are not counted: the buffer has its
+    // pages of its own so unlocking it does not unlock another buffer
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+    let size = (len.max(1) + page_size - 1) / page_size * page_size;
+    Layout::from_size_align(size, page_size).unwrap()
+}
+
+#[cfg(not(all(unix, feature = "mlock")))]
+fn layout_for(len: usize) -> Layout {
+    Layout::from_size_align(len.max(1), 1).unwrap()
+}
+
+#[cfg(all(unix, feature = "mlock"))]
+fn lock(ptr: *mut u8, size: usize) -> bool {
+    unsafe { libc::mlock(ptr as *const libc::c_void, size) == 0 }
+}
+
+#[cfg(not(all(unix, feature = "mlock")))]
+fn lock(_ptr: *mut u8, _size: usize) -> bool {
+    false
+}
+
+#[cfg(all(unix, feature = "mlock"))]
+fn unlock(ptr: *mut u8, size: usize) {
+    unsafe { libc::munlock(ptr as *const libc::c_void, size) };
+}
+
+#[cfg(not(all(unix, feature = "mlock")))]
+fn unlock(_ptr: *mut u8, _size: usize) {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn zeroed_copy() {
+        let mut mem = SecureMem::new(32);
+        assert_eq!(mem.as_ref(), &[0u8; 32][..]);
+        mem.as_mut()[3] = 42;
+
+        let copy = mem.clone();
+        assert_eq!(copy.as_ref(), mem.as_ref());
+        zero(mem.as_mut());
+        assert_eq!(mem.as_ref(), &[0u8; 32][..]);
+        assert_eq!(copy.as_ref()[3], 42);
+
+        let empty = SecureMem::from_slice(&[]);
+        assert!(empty.as_ref().is_empty());
+        if !cfg!(feature = "mlock") {
+            assert!(!empty.is_locked());
+        }
+    }
 }
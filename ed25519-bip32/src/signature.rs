@@ -1,6 +1,6 @@
 use super::hex;
-use std::fmt;
-use std::marker::PhantomData;
+use core::fmt;
+use core::marker::PhantomData;
 
 use cryptoxide::util::fixed_time_eq;
 
@@ -1,8 +1,11 @@
 extern crate cardano;
 extern crate cardano_storage;
 extern crate cbor_event;
+extern crate chain_core;
+extern crate cryptoxide;
 extern crate protocol;
 extern crate rand;
+extern crate slog;
 extern crate storage_units;
 #[macro_use]
 extern crate log;
@@ -27,4 +30,6 @@ pub mod genesisdata;
 mod mstream;
 pub mod network;
 pub mod sync;
+pub mod txspec;
 pub mod utils;
+pub mod wallet;
@@ -69,6 +69,10 @@ pub struct MStream {
 const TIMEOUT_SECONDS: u64 = 30;
 const TIMEOUT_NANO_SECONDS: u32 = 0;
 
+/// a peer sending nothing for this long is considered stalled, the native
+/// peers announce a new tip at every slot (20 seconds)
+const STALL_TIMEOUT_SECONDS: u64 = 120;
+
 impl MStream {
     pub fn init(dest: &SocketAddr) -> Result<Self> {
         let timeout = Duration::new(TIMEOUT_SECONDS, TIMEOUT_NANO_SECONDS);
@@ -83,6 +87,7 @@ impl MStream {
             }
         };
         stream.set_nodelay(true)?;
+        stream.set_read_timeout(Some(Duration::from_secs(STALL_TIMEOUT_SECONDS)))?;
         //let lock = RwLock::new(5);
         Ok(MStream {
             //lock: lock,
@@ -0,0 +1,197 @@
+//! Parallel download of the epoch packs from hermes end points.
+//!
+//! Workers, a few per end point, download the packs of the epochs and
+//! verify them: every block must decode, be in its epoch and follow the
+//! previous block of the pack. The verified epochs are given back in order,
+//! and the workers never download more than `EPOCHS_AHEAD` epochs past the
+//! next one to give back, which bounds the memory used by the epochs
+//! waiting for their predecessors.
+//!
+//! When an end point fails, its workers stop and the epoch is downloaded
+//! from another one. The download fails once all the end points failed.
+
+use cardano::block::{Block, EpochId, HeaderHash, RawBlock};
+use network::{hermes, Error, Result};
+use std::collections::{BTreeMap, VecDeque};
+use std::ops::Range;
+use std::sync::{mpsc, Arc, Condvar, Mutex};
+use std::thread;
+use storage_units::packfile;
+use tokio_core::reactor::Core;
+
+/// number of epochs downloaded at the same time from an end point
+const DOWNLOADS_PER_END_POINT: usize = 2;
+
+/// maximum number of epochs downloaded, or waiting to be given back,
+/// ahead of the next epoch to give back
+const EPOCHS_AHEAD: u64 = 4;
+
+/// the verified blocks of an epoch, in order
+pub type EpochBlocks = Vec<(HeaderHash, Block, RawBlock)>;
+
+struct Schedule {
+    /// the epochs to download, in order
+    pending: VecDeque<EpochId>,
+    /// the next epoch to give back
+    next: EpochId,
+    /// no more epoch will be given back, the workers have to stop
+    stopped: bool,
+}
+
+struct Shared {
+    schedule: Mutex<Schedule>,
+    changed: Condvar,
+}
+
+impl Shared {
+    /// wait for the next epoch to download, or `None` if the workers have
+    /// to stop
+    fn next_epoch(&self) -> Option<EpochId> {
+        let mut schedule = self.schedule.lock().unwrap();
+        loop {
+            if schedule.stopped {
+                return None;
+            }
+            match schedule.pending.front() {
+                Some(&epoch) if epoch < schedule.next + EPOCHS_AHEAD => {
+                    schedule.pending.pop_front();
+                    return Some(epoch);
+                }
+                _ => schedule = self.changed.wait(schedule).unwrap(),
+            }
+        }
+    }
+
+    /// give the epoch back to the other workers
+    fn reschedule(&self, epoch: EpochId) {
+        let mut schedule = self.schedule.lock().unwrap();
+        let position = schedule
+            .pending
+            .iter()
+            .position(|&pending| pending > epoch)
+            .unwrap_or(schedule.pending.len());
+        schedule.pending.insert(position, epoch);
+        self.changed.notify_all();
+    }
+
+    fn given_back(&self, epoch: EpochId) {
+        let mut schedule = self.schedule.lock().unwrap();
+        schedule.next = epoch + 1;
+        self.changed.notify_all();
+    }
+
+    fn stop(&self) {
+        self.schedule.lock().unwrap().stopped = true;
+        self.changed.notify_all();
+    }
+}
+
+/// stop the workers when the download ends, whether it succeeded or not
+struct StopGuard(Arc<Shared>);
+
+impl Drop for StopGuard {
+    fn drop(&mut self) {
+        self.0.stop()
+    }
+}
+
+/// decode the blocks of the pack and check they are the chain of the
+/// blocks of the epoch
+fn verify_epoch(epoch: EpochId, pack: &[u8]) -> Result<EpochBlocks> {
+    let mut reader = packfile::Reader::init(pack).map_err(|_| Error::InvalidEpochPack(epoch))?;
+    let mut blocks: EpochBlocks = Vec::new();
+    while let Some(data) = reader.next_block()? {
+        let block_raw = RawBlock(data);
+        let block = block_raw.decode()?;
+        let hash = {
+            let hdr = block.header();
+            if hdr.blockdate().get_epochid() != epoch {
+                return Err(Error::InvalidEpochPack(epoch));
+            }
+            if let Some((previous, _, _)) = blocks.last() {
+                if hdr.previous_header() != *previous {
+                    return Err(Error::InvalidEpochPack(epoch));
+                }
+            }
+            hdr.compute_hash()
+        };
+        blocks.push((hash, block, block_raw));
+    }
+    if blocks.is_empty() {
+        return Err(Error::InvalidEpochPack(epoch));
+    }
+    Ok(blocks)
+}
+
+fn worker(url: String, shared: Arc<Shared>, results: mpsc::Sender<(EpochId, Result<EpochBlocks>)>) {
+    let mut core = match Core::new() {
+        Ok(core) => core,
+        Err(err) => {
+            let _ = results.send((0, Err(err.into())));
+            return;
+        }
+    };
+    while let Some(epoch) = shared.next_epoch() {
+        let uri = format!("{}/epoch/{}", url, epoch);
+        info!("querying uri: {}", uri);
+        let result = hermes::download(&mut core, uri).and_then(|pack| verify_epoch(epoch, &pack));
+        if let Err(ref err) = result {
+            warn!("cannot download epoch {} from `{}': {}", epoch, url, err);
+            shared.reschedule(epoch);
+        }
+        let failed = result.is_err();
+        if results.send((epoch, result)).is_err() || failed {
+            return;
+        }
+    }
+}
+
+/// download the epochs of the range from the end points, verify them and
+/// give them in order to `got_epoch`
+pub fn fetch_epochs<F>(urls: Vec<String>, epochs: Range<EpochId>, got_epoch: &mut F) -> Result<()>
+where
+    F: FnMut(EpochId, EpochBlocks) -> (),
+{
+    let shared = Arc::new(Shared {
+        schedule: Mutex::new(Schedule {
+            pending: epochs.clone().collect(),
+            next: epochs.start,
+            stopped: false,
+        }),
+        changed: Condvar::new(),
+    });
+    let _guard = StopGuard(shared.clone());
+
+    let (results_tx, results_rx) = mpsc::channel();
+    for url in urls.iter() {
+        for _ in 0..DOWNLOADS_PER_END_POINT {
+            let url = url.clone();
+            let shared = shared.clone();
+            let results_tx = results_tx.clone();
+            thread::spawn(move || worker(url, shared, results_tx));
+        }
+    }
+    // only the workers hold a sender: the channel is closed once they
+    // have all failed
+    drop(results_tx);
+
+    let mut verified = BTreeMap::new();
+    let mut last_error = None;
+    let mut next = epochs.start;
+    while next < epochs.end {
+        if let Some(blocks) = verified.remove(&next) {
+            got_epoch(next, blocks);
+            next += 1;
+            shared.given_back(next - 1);
+            continue;
+        }
+        match results_rx.recv() {
+            Ok((epoch, Ok(blocks))) => {
+                verified.insert(epoch, blocks);
+            }
+            Ok((_, Err(err))) => last_error = Some(err),
+            Err(_) => return Err(last_error.unwrap_or(Error::ConnectionTimedOut)),
+        }
+    }
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use cardano::block::HeaderHash;
+use cardano::block::{EpochId, HeaderHash};
 use cardano_storage as storage;
 use cbor_event;
 use hyper;
@@ -18,6 +18,8 @@ pub enum Error {
     StorageError(storage::Error),
     BlockError(cardano::block::Error),
     InvalidPeerAddress(String),
+    InvalidEpochPack(EpochId),
+    NoPeers,
 }
 impl From<io::Error> for Error {
     fn from(e: io::Error) -> Self {
@@ -68,6 +70,8 @@ impl fmt::Display for Error {
             Error::StorageError(_) => write!(f, "Storage error"),
             Error::BlockError(_) => write!(f, "Block error"),
             Error::InvalidPeerAddress(addr) => write!(f, "Invalid peer address {}", addr),
+            Error::InvalidEpochPack(epoch) => write!(f, "Invalid pack of epoch {}", epoch),
+            Error::NoPeers => write!(f, "No peer to connect to"),
         }
     }
 }
@@ -85,6 +89,8 @@ impl error::Error for Error {
             Error::StorageError(ref err) => Some(err),
             Error::BlockError(ref err) => Some(err),
             Error::InvalidPeerAddress(_) => None,
+            Error::InvalidEpochPack(_) => None,
+            Error::NoPeers => None,
         }
     }
 }
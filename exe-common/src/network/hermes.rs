@@ -6,14 +6,13 @@ use cardano::{
 use std::io::Write;
 use std::thread;
 use std::time::{Duration, SystemTime};
-use storage_units::packfile;
 
 use futures::{Future, Stream};
 use hyper::Client;
 use tokio_core::reactor::Core;
 
 use network::api::{Api, BlockRef};
-use network::{Error, Result};
+use network::{epoch_fetch, Error, Result};
 
 // Time between get_tip calls. FIXME: make configurable?
 static NETWORK_REFRESH_FREQUENCY: Duration = Duration::from_secs(60 * 10);
@@ -22,6 +21,9 @@ static NETWORK_REFRESH_FREQUENCY: Duration = Duration::from_secs(60 * 10);
 pub struct HermesEndPoint {
     pub url: String,
     pub blockchain: String,
+    /// other end points serving the same blockchain, the epochs are
+    /// downloaded from all of them in parallel
+    pub mirrors: Vec<String>,
     core: Core,
 }
 
@@ -30,15 +32,45 @@ impl HermesEndPoint {
         HermesEndPoint {
             url,
             blockchain,
+            mirrors: Vec::new(),
             core: Core::new().unwrap(),
         }
     }
 
+    /// download the epochs from the mirrors too
+    pub fn with_mirrors(mut self, mirrors: Vec<String>) -> Self {
+        self.mirrors = mirrors;
+        self
+    }
+
     pub fn uri(&mut self, path: &str) -> String {
         format!("{}/{}", self.url, path)
     }
 }
 
+/// download the content at the uri
+pub fn download(core: &mut Core, uri: String) -> Result<Vec<u8>> {
+    let client = Client::new(&core.handle());
+    let mut bytes = vec![];
+    let mut err = None;
+    {
+        let work = client.get(uri.parse().unwrap()).and_then(|res| {
+            if !res.status().is_success() {
+                err = Some(Error::HttpError(uri, res.status().clone()));
+            };
+            res.body().for_each(|chunk| {
+                bytes.append(&mut chunk.to_vec());
+                Ok(())
+            })
+        });
+        core.run(work)?;
+    }
+    if let Some(err) = err {
+        return Err(err);
+    };
+    Ok(bytes)
+}
+
 impl Api for HermesEndPoint {
     fn get_tip(&mut self) -> Result<BlockHeader> {
         let uri = self.uri("tip");
@@ -146,55 +178,35 @@ impl Api for HermesEndPoint {
             }
 
             if inclusive && from.date.is_boundary() && epoch < to.date.get_epochid() {
-                // Fetch a complete epoch.
-
-                let mut tmppack = vec![];
-                let mut err = None;
-
-                {
-                    let uri = self.uri(&format!("epoch/{}", epoch));
-                    info!("querying uri: {}", uri);
-                    let client = Client::new(&self.core.handle());
-                    let work = client.get(uri.parse().unwrap()).and_then(|res| {
-                        if !res.status().is_success() {
-                            err = Some(Error::HttpError(uri, res.status().clone()));
-                        };
-                        res.body().for_each(|chunk| {
-                            tmppack.append(&mut chunk.to_vec());
-                            Ok(())
-                        })
-                    });
-                    let now = SystemTime::now();
-                    self.core.run(work)?;
-                    let time_elapsed = now.elapsed().unwrap();
-                    info!("Downloaded EPOCH in {}sec", time_elapsed.as_secs());
-                }
-
-                if let Some(err) = err {
-                    return Err(err);
+                // Fetch the complete epochs, in parallel from the end point
+                // and its mirrors.
+                let mut urls = vec![self.url.clone()];
+                urls.extend(self.mirrors.iter().cloned());
+
+                let now = SystemTime::now();
+                let mut last = None;
+                epoch_fetch::fetch_epochs(
+                    urls,
+                    epoch..to.date.get_epochid(),
+                    &mut |epoch, blocks| {
+                        for (hash, block, block_raw) in blocks.iter() {
+                            got_block(hash, block, block_raw);
+                        }
+                        info!("imported EPOCH {}", epoch);
+                        last = blocks.into_iter().last();
+                    },
+                )?;
+                let time_elapsed = now.elapsed().unwrap();
+                info!("Downloaded EPOCHS in {}sec", time_elapsed.as_secs());
+
+                let (hash, block, _) = last.expect("fetched epochs are not empty");
+                let hdr = block.header();
+                from = BlockRef {
+                    hash,
+                    parent: hdr.previous_header(),
+                    date: hdr.blockdate(),
                 };
-
-                let mut packfile = packfile::Reader::init(&tmppack[..]).unwrap();
-
-                while let Some(data) = packfile.next_block()? {
-                    let block_raw = block::RawBlock(data);
-                    let block = block_raw.decode()?;
-                    let hdr = block.header();
-
-                    assert!(hdr.blockdate().get_epochid() == epoch);
-                    //assert!(from.date != hdr.get_blockdate() || from.hash == hdr.compute_hash());
-
-                    if from.date <= hdr.blockdate() {
-                        got_block(&hdr.compute_hash(), &block, &block_raw);
-                    }
-
-                    from = BlockRef {
-                        hash: hdr.compute_hash(),
-                        parent: hdr.previous_header(),
-                        date: hdr.blockdate(),
-                    };
-                    inclusive = false;
-                }
+                inclusive = false;
             } else {
                 //assert!(from.date.get_epochid() == to.date.get_epochid());
 
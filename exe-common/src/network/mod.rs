@@ -1,13 +1,16 @@
 pub mod api;
+pub mod epoch_fetch;
 pub mod error;
 pub mod hermes;
 pub mod native;
 pub mod ntt;
 pub mod peer;
+pub mod peer_store;
 pub mod result;
 
 pub use self::api::*;
 pub use self::error::Error;
 pub use self::hermes::HermesEndPoint;
 pub use self::peer::Peer;
+pub use self::peer_store::PeerStore;
 pub use self::result::Result;
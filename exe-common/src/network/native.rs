@@ -50,6 +50,9 @@ impl PeerPool {
                 }
             }
         }
+        if connections.is_empty() {
+            return Err(Error::ConnectionTimedOut);
+        }
         Ok(PeerPool {
             name,
             address,
@@ -163,9 +166,7 @@ impl Api for OpenPeer {
             return Ok(prev_tip);
         }
 
-        let block_headers_raw = GetBlockHeader::tip()
-            .execute(&mut self.0)
-            .expect("to get one header at least");
+        let block_headers_raw = GetBlockHeader::tip().execute(&mut self.0)?;
 
         let block_headers = block_headers_raw.decode()?;
 
@@ -186,9 +187,7 @@ impl Api for OpenPeer {
     }
 
     fn get_block(&mut self, hash: &HeaderHash) -> Result<RawBlock> {
-        let b = GetBlock::only(&hash)
-            .execute(&mut self.0)
-            .expect("to get one block at least");
+        let b = GetBlock::only(&hash).execute(&mut self.0)?;
 
         match b.first() {
             Some(b) => Ok(RawBlock::from_dat(b.as_ref().to_vec())),
@@ -0,0 +1,198 @@
+//! Persistent address book of the peers.
+//!
+//! The store remembers the peers met so far along with how well they
+//! behaved: the latency of the connection to the peer and the number of
+//! failures in a row. The peers are tried from the healthiest one, and the
+//! peers of the network configuration are only added to the store when it
+//! does not know them yet.
+
+use config::net::{NamedPeer, Peer, Peers};
+use serde_yaml;
+use std::{
+    fs::{self, File},
+    io,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use storage_units::utils::tmpfile::TmpFile;
+
+/// latency assumed for the peers never connected to, in milliseconds
+const UNKNOWN_LATENCY: u64 = 1_000;
+
+/// weight of the latest measure in the average latency, in percent
+const LATENCY_WEIGHT: u64 = 25;
+
+/// what is known of a peer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerEntry {
+    pub name: String,
+    pub peer: Peer,
+    /// average latency of the connection to the peer, in milliseconds
+    pub latency: Option<u64>,
+    /// number of failures since the last successful connection
+    pub failures: u32,
+    /// time of the last successful connection, in seconds since the epoch
+    pub last_seen: Option<u64>,
+}
+impl PeerEntry {
+    fn new(name: String, peer: Peer) -> Self {
+        PeerEntry {
+            name,
+            peer,
+            latency: None,
+            failures: 0,
+            last_seen: None,
+        }
+    }
+
+    /// the score of the peer, the lower the better: the peers failing the
+    /// less come first, then the fastest ones
+    pub fn score(&self) -> (u32, u64) {
+        (self.failures, self.latency.unwrap_or(UNKNOWN_LATENCY))
+    }
+
+    pub fn named_peer(&self) -> NamedPeer {
+        NamedPeer::new(self.name.clone(), self.peer.clone())
+    }
+}
+
+/// the address book of the peers, saved in a YAML file
+#[derive(Debug)]
+pub struct PeerStore {
+    path: PathBuf,
+    entries: Vec<PeerEntry>,
+}
+impl PeerStore {
+    /// open the peer store saved at the given path, adding the given peers
+    /// if they are not known yet.
+    ///
+    /// A missing or unreadable store starts empty.
+    pub fn open<P: AsRef<Path>>(path: P, peers: &Peers) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let entries = if path.is_file() {
+            match File::open(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|file| serde_yaml::from_reader(file).map_err(|e| e.to_string()))
+            {
+                Ok(entries) => entries,
+                Err(err) => {
+                    warn!("cannot read the peer store `{}': {}", path.display(), err);
+                    Vec::new()
+                }
+            }
+        } else {
+            Vec::new()
+        };
+        let mut store = PeerStore { path, entries };
+        for peer in peers.iter() {
+            store.add(peer.name().to_owned(), peer.peer().clone());
+        }
+        store
+    }
+
+    /// add the peer to the store, unless a peer with the same address is
+    /// already known
+    pub fn add(&mut self, name: String, peer: Peer) {
+        if !self.entries.iter().any(|entry| entry.peer == peer) {
+            self.entries.push(PeerEntry::new(name, peer));
+        }
+    }
+
+    pub fn entries(&self) -> &[PeerEntry] {
+        &self.entries
+    }
+
+    /// the known peers of the kind (native or http), from the healthiest
+    pub fn candidates(&self, native: bool) -> Vec<NamedPeer> {
+        let mut entries: Vec<_> = self
+            .entries
+            .iter()
+            .filter(|entry| (native && entry.peer.is_native()) || (!native && entry.peer.is_http()))
+            .collect();
+        entries.sort_by_key(|entry| entry.score());
+        entries.iter().map(|entry| entry.named_peer()).collect()
+    }
+
+    /// record a successful connection to the peer, established in `latency`
+    pub fn record_success(&mut self, peer: &Peer, latency: Duration) {
+        let latency = latency.as_secs() * 1_000 + u64::from(latency.subsec_millis());
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .ok();
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.peer == *peer) {
+            entry.latency = Some(match entry.latency {
+                None => latency,
+                Some(average) => {
+                    (average * (100 - LATENCY_WEIGHT) + latency * LATENCY_WEIGHT) / 100
+                }
+            });
+            entry.failures = 0;
+            entry.last_seen = now;
+        }
+    }
+
+    /// record a failure of the peer
+    pub fn record_failure(&mut self, peer: &Peer) {
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.peer == *peer) {
+            entry.failures = entry.failures.saturating_add(1);
+        }
+    }
+
+    /// save the store, replacing the previous file atomically
+    pub fn save(&self) -> io::Result<()> {
+        let dir = match self.path.parent() {
+            Some(dir) => dir.to_path_buf(),
+            None => PathBuf::from("."),
+        };
+        fs::DirBuilder::new().recursive(true).create(&dir)?;
+        let mut file = TmpFile::create(dir)?;
+        serde_yaml::to_writer(&mut file, &self.entries)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        file.render_permanent(&self.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> PeerStore {
+        let mut peers = Peers::new();
+        peers.push("a".to_owned(), Peer::native("a:3000".to_owned()));
+        peers.push("b".to_owned(), Peer::native("b:3000".to_owned()));
+        peers.push("c".to_owned(), Peer::native("c:3000".to_owned()));
+        peers.push("h".to_owned(), Peer::http("http://h".to_owned()));
+        PeerStore::open("/nonexistent/peers.yaml", &peers)
+    }
+
+    fn names(peers: Vec<NamedPeer>) -> Vec<String> {
+        peers.iter().map(|peer| peer.name().to_owned()).collect()
+    }
+
+    #[test]
+    fn healthy_peers_come_first() {
+        let mut store = store();
+        store.record_success(
+            &Peer::native("a:3000".to_owned()),
+            Duration::from_millis(2_000),
+        );
+        store.record_success(
+            &Peer::native("b:3000".to_owned()),
+            Duration::from_millis(100),
+        );
+        store.record_failure(&Peer::native("c:3000".to_owned()));
+        assert_eq!(names(store.candidates(true)), vec!["b", "a", "c"]);
+        assert_eq!(names(store.candidates(false)), vec!["h"]);
+
+        store.record_failure(&Peer::native("b:3000".to_owned()));
+        assert_eq!(names(store.candidates(true)), vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn known_peers_are_not_duplicated() {
+        let mut store = store();
+        store.add("other".to_owned(), Peer::native("a:3000".to_owned()));
+        assert_eq!(store.entries().len(), 4);
+    }
+}
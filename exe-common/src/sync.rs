@@ -6,13 +6,18 @@ use cardano_storage::{
     epoch::{self, epoch_exists},
     pack, tag, types, Error, Storage,
 };
+use chain_core::metrics::{names, seconds};
 use config::net;
-use network::{api::Api, api::BlockRef, Peer, Result};
+use network::{api::Api, api::BlockRef, Error as NetworkError, Peer, PeerStore, Result};
+use slog::{slog_debug, slog_info, slog_o, slog_warn, Logger};
 use std::mem;
 use std::sync::{Arc, RwLock};
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 use storage_units::packfile;
 
+/// number of loose blocks stored between two checkpoints of the tip
+const LOOSE_BLOCKS_CHECKPOINT: usize = 100;
+
 fn duration_print(d: Duration) -> String {
     format!("{}.{:03} seconds", d.as_secs(), d.subsec_millis())
 }
@@ -24,12 +29,64 @@ struct EpochWriterState {
     blobs_to_delete: Vec<HeaderHash>,
 }
 
+/// the import of a block, logged as structured events, one per stage:
+/// `fetch` (waiting for the block from the network), `flush` (packing the
+/// previous epoch, on epoch boundaries only), `verify` (checking the block
+/// against the chain state and applying it to the chain state, which is a
+/// single step for the chain state of the Byron era) and `store` (writing
+/// the block to the epoch pack or as a loose blob).
+///
+/// The events carry the hash and the date of the block and the time
+/// taken by the stage in microseconds.
+struct BlockSpan {
+    logger: Logger,
+    start: Instant,
+    stage_start: Instant,
+}
+
+impl BlockSpan {
+    fn new(logger: &Logger, hash: &HeaderHash, date: &BlockDate, fetch_start: Instant) -> Self {
+        let logger = logger.new(slog_o!(
+            "block" => hash.to_string(),
+            "date" => date.to_string(),
+        ));
+        let now = Instant::now();
+        slog_debug!(logger, "block stage";
+            "stage" => "fetch",
+            "time_us" => micros(now - fetch_start),
+        );
+        BlockSpan {
+            logger,
+            start: fetch_start,
+            stage_start: now,
+        }
+    }
+
+    fn stage(&mut self, stage: &'static str) {
+        let now = Instant::now();
+        slog_debug!(self.logger, "block stage";
+            "stage" => stage,
+            "time_us" => micros(now - self.stage_start),
+        );
+        self.stage_start = now;
+    }
+
+    fn end(self) {
+        slog_debug!(self.logger, "block imported"; "time_us" => micros(self.start.elapsed()));
+    }
+}
+
+fn micros(duration: Duration) -> u64 {
+    duration.as_secs() * 1_000_000 + u64::from(duration.subsec_micros())
+}
+
 fn net_sync_to<A: Api>(
     net: &mut A,
     net_cfg: &net::Config,
     genesis_data: &GenesisData,
     storage: Arc<RwLock<Storage>>,
     tip_header: &BlockHeader,
+    logger: &Logger,
 ) -> Result<()> {
     let tip = BlockRef {
         hash: tip_header.compute_hash(),
@@ -38,11 +95,14 @@ fn net_sync_to<A: Api>(
     };
     let storage_config = storage.read().unwrap().config.clone();
 
-    debug!("Configured genesis   : {}", net_cfg.genesis);
-    debug!("Configured genesis-1 : {}", net_cfg.genesis_prev);
-    info!(
-        "Network TIP is       : {} ({}) <- {}",
-        tip.hash, tip.date, tip.parent
+    slog_debug!(logger, "configured genesis";
+        "genesis" => net_cfg.genesis.to_string(),
+        "genesis_prev" => net_cfg.genesis_prev.to_string(),
+    );
+    slog_info!(logger, "network tip";
+        "block" => tip.hash.to_string(),
+        "date" => tip.date.to_string(),
+        "parent" => tip.parent.to_string(),
     );
 
     // Start fetching at the current HEAD tag, or the genesis block if
@@ -75,7 +135,10 @@ fn net_sync_to<A: Api>(
     // ancestor of tip. In that case we should start from the last
     // stable epoch before our_tip.
 
-    info!("Fetching from        : {} ({})", our_tip.hash, our_tip.date);
+    slog_info!(logger, "fetching from";
+        "block" => our_tip.hash.to_string(),
+        "date" => our_tip.date.to_string(),
+    );
 
     // Determine whether the previous epoch is stable yet. Note: This
     // assumes that k is smaller than the number of blocks in an
@@ -91,7 +154,7 @@ fn net_sync_to<A: Api>(
                 }
             }
         };
-    info!("First unstable epoch : {}", first_unstable_epoch);
+    slog_info!(logger, "first unstable epoch"; "epoch" => first_unstable_epoch);
 
     let mut epoch_writer_state: Option<EpochWriterState> = None;
 
@@ -186,12 +249,15 @@ fn net_sync_to<A: Api>(
         },
     )?;
 
+    let mut fetch_start = Instant::now();
+    let mut loose_blocks = 0;
     net.get_blocks(
         &our_tip,
         our_tip_is_genesis,
         &tip,
         &mut |block_hash, block, block_raw| {
             let date = block.header().blockdate();
+            let mut span = BlockSpan::new(logger, block_hash, &date, fetch_start);
 
             // Flush the previous epoch (if any). FIXME: shouldn't rely on
             // 'date' here since the block hasn't been verified yet.
@@ -216,19 +282,45 @@ fn net_sync_to<A: Api>(
                         &chain_state.last_block.as_ref(),
                     );
                 }
+                span.stage("flush");
             }
 
             // FIXME: propagate errors
+            let verify_start = SystemTime::now();
             chain_state
                 .verify_block(block_hash, block)
                 .expect(&format!("Block {} ({}) failed to verify", block_hash, date));
+            {
+                let storage = storage.read().unwrap();
+                let metrics = storage.metrics();
+                metrics.counter(names::SYNC_BLOCKS_IMPORTED, 1);
+                if let Ok(elapsed) = verify_start.elapsed() {
+                    metrics.histogram(names::SYNC_BLOCK_VERIFY_SECONDS, seconds(elapsed));
+                }
+                metrics.gauge(names::SYNC_CHAIN_LENGTH, chain_state.chain_length as i64);
+                metrics.gauge(names::SYNC_UTXO_COUNT, chain_state.utxos.len() as i64);
+            }
+            span.stage("verify");
 
             if date.get_epochid() >= first_unstable_epoch {
                 // This block is not part of a stable epoch yet and could
                 // be rolled back. Therefore we can't pack this epoch
                 // yet. Instead we write this block to disk separately.
-                let block_hash = types::header_to_blockhash(&block_hash);
-                blob::write(&storage.read().unwrap(), &block_hash, block_raw.as_ref()).unwrap();
+                let storage = storage.read().unwrap();
+                blob::write(
+                    &storage,
+                    &types::header_to_blockhash(&block_hash),
+                    block_raw.as_ref(),
+                )
+                .unwrap();
+
+                // Checkpoint the tip every few stored blocks, so an
+                // interrupted synchronisation resumes from it without
+                // syncing the tag on every block.
+                loose_blocks += 1;
+                if loose_blocks % LOOSE_BLOCKS_CHECKPOINT == 0 {
+                    tag::write_hash(&storage, &tag::HEAD, block_hash);
+                }
             } else {
                 // If this is the epoch genesis block, start writing a new epoch pack.
                 if date.is_boundary() {
@@ -250,6 +342,9 @@ fn net_sync_to<A: Api>(
                     unreachable!();
                 }
             }
+            span.stage("store");
+            span.end();
+            fetch_start = Instant::now();
         },
     )?;
 
@@ -276,18 +371,31 @@ fn net_sync_to<A: Api>(
 /// sleep for some time between polling for new tips; with the native
 /// protocol backend, it will block waiting for the server to send us
 /// new tip announcements.)
+///
+/// The progress is reported to `logger` as structured events; the
+/// import of every block is logged at the debug level, stage by stage
+/// (`fetch`, `flush` on epoch boundaries, `verify` and `store`), with the
+/// hash and the date of the block and the time taken by each stage.
 pub fn net_sync<A: Api>(
     net: &mut A,
     net_cfg: &net::Config,
     genesis_data: &GenesisData,
     storage: Arc<RwLock<Storage>>,
     sync_once: bool,
+    logger: &Logger,
 ) -> Result<()> {
     // recover and print the TIP of the network
     let mut tip_header = net.get_tip()?;
 
     loop {
-        net_sync_to(net, net_cfg, genesis_data, storage.clone(), &tip_header)?;
+        net_sync_to(
+            net,
+            net_cfg,
+            genesis_data,
+            storage.clone(),
+            &tip_header,
+            logger,
+        )?;
 
         if sync_once {
             break;
@@ -299,6 +407,82 @@ pub fn net_sync<A: Api>(
     Ok(())
 }
 
+/// Synchronize the local blockchain like `net_sync`, with the native
+/// peers of the peer store instead of a single one, starting from the
+/// healthiest peer. When the connection to the current peer fails or
+/// stalls, the synchronisation moves on to the next peer and resumes from
+/// the last block stored. The latency and the failures of the peers are
+/// recorded in the store.
+///
+/// Returns the error of the last peer tried once all the peers have
+/// failed in a row without storing any new block.
+pub fn net_sync_peers(
+    blockchain: &str,
+    net_cfg: &net::Config,
+    peer_store: &mut PeerStore,
+    genesis_data: &GenesisData,
+    storage: Arc<RwLock<Storage>>,
+    sync_once: bool,
+    logger: &Logger,
+) -> Result<()> {
+    let peers = peer_store.candidates(true);
+    if peers.is_empty() {
+        return Err(NetworkError::NoPeers);
+    }
+
+    let mut failures = 0;
+    let mut attempts = 0;
+    loop {
+        let peer = &peers[attempts % peers.len()];
+        attempts += 1;
+        let head = tag::read_hash(&storage.read().unwrap(), &tag::HEAD);
+        let now = SystemTime::now();
+        let result = Peer::new(
+            String::from(blockchain),
+            peer.name().to_owned(),
+            peer.peer().clone(),
+            net_cfg.protocol_magic,
+        )
+        .and_then(|mut net| {
+            peer_store.record_success(peer.peer(), now.elapsed().unwrap_or_default());
+            save_peer_store(peer_store);
+            let logger = logger.new(slog_o!("peer" => peer.name().to_owned()));
+            net_sync(
+                &mut net,
+                net_cfg,
+                genesis_data,
+                storage.clone(),
+                sync_once,
+                &logger,
+            )
+        });
+
+        let err = match result {
+            Ok(()) => return Ok(()),
+            Err(err) => err,
+        };
+        slog_warn!(logger, "synchronisation failed";
+            "peer" => peer.name().to_owned(),
+            "error" => err.to_string(),
+        );
+        peer_store.record_failure(peer.peer());
+        save_peer_store(peer_store);
+        if tag::read_hash(&storage.read().unwrap(), &tag::HEAD) != head {
+            failures = 0;
+        }
+        failures += 1;
+        if failures == peers.len() {
+            return Err(err);
+        }
+    }
+}
+
+fn save_peer_store(peer_store: &PeerStore) {
+    if let Err(err) = peer_store.save() {
+        warn!("cannot save the peer store: {}", err);
+    }
+}
+
 // Create an epoch from a complete set of previously fetched blocks on
 // disk.
 fn maybe_create_epoch(
@@ -428,13 +612,27 @@ fn finish_epoch(
 pub fn get_peer(blockchain: &str, cfg: &net::Config, native: bool) -> Peer {
     for peer in cfg.peers.iter() {
         if (native && peer.is_native()) || (!native && peer.is_http()) {
-            return Peer::new(
+            let peer = Peer::new(
                 String::from(blockchain),
                 peer.name().to_owned(),
                 peer.peer().clone(),
                 cfg.protocol_magic,
             )
             .unwrap();
+            return match peer {
+                // the epochs are downloaded from all the http peers
+                Peer::Http(endpoint) => {
+                    let mirrors = cfg
+                        .peers
+                        .iter()
+                        .filter_map(|peer| peer.get_http())
+                        .filter(|url| *url != endpoint.url)
+                        .map(String::from)
+                        .collect();
+                    Peer::Http(endpoint.with_mirrors(mirrors))
+                }
+                peer => peer,
+            };
         }
     }
 
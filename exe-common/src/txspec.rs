@@ -0,0 +1,71 @@
+//! parts of the JSON transaction specification shared by `cardano-cli`
+//! and `cardano-walletd`
+
+use cardano::fee;
+use serde::de::{Deserialize, Deserializer, Error, Unexpected};
+
+/// the linear fee algorithm of a specification:
+/// `{ "constant": 155381, "coefficient": 43.946 }`
+#[derive(Deserialize)]
+pub struct LinearFee {
+    /// constant part of the fee, in Lovelace
+    pub constant: u64,
+    /// cost per byte of the transaction, in Lovelace
+    #[serde(deserialize_with = "deserialize_coefficient")]
+    pub coefficient: f64,
+}
+impl From<LinearFee> for fee::LinearFee {
+    fn from(f: LinearFee) -> Self {
+        let coefficient = (f.coefficient * 1000.0).round() as u64;
+        fee::LinearFee::new(
+            fee::Milli::integral(f.constant),
+            fee::Milli::new(coefficient / 1000, coefficient % 1000),
+        )
+    }
+}
+
+/// the coefficient is converted to thousandths of Lovelace, reject the
+/// ones which cannot be instead of saturating them
+fn deserialize_coefficient<'de, D>(deserializer: D) -> Result<f64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let coefficient = f64::deserialize(deserializer)?;
+    if coefficient >= 0.0 && coefficient * 1000.0 < u64::max_value() as f64 {
+        Ok(coefficient)
+    } else {
+        Err(D::Error::invalid_value(
+            Unexpected::Float(coefficient),
+            &"a non-negative number of Lovelace per byte",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    fn parse(json: &str) -> Result<fee::LinearFee, serde_json::Error> {
+        serde_json::from_str::<LinearFee>(json).map(fee::LinearFee::from)
+    }
+
+    #[test]
+    fn coefficient_in_thousandths() {
+        assert_eq!(
+            parse(r#"{ "constant": 155381, "coefficient": 43.946 }"#).unwrap(),
+            fee::LinearFee::default()
+        );
+        assert_eq!(
+            parse(r#"{ "constant": 0, "coefficient": 0 }"#).unwrap(),
+            fee::LinearFee::new(fee::Milli::integral(0), fee::Milli::integral(0))
+        );
+    }
+
+    #[test]
+    fn invalid_coefficient_rejected() {
+        assert!(parse(r#"{ "constant": 155381, "coefficient": -1.5 }"#).is_err());
+        assert!(parse(r#"{ "constant": 155381, "coefficient": 1e30 }"#).is_err());
+        assert!(parse(r#"{ "constant": -1, "coefficient": 43.946 }"#).is_err());
+    }
+}
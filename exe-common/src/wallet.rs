@@ -0,0 +1,193 @@
+//! on-disk format of the wallets, shared by `cardano-cli` and
+//! `cardano-walletd`
+//!
+//! Every wallet is stored in its own file of the wallet directory: the root
+//! private key of the wallet is encrypted with ChaCha20Poly1305, the
+//! symmetric key being derived from the spending password with
+//! PBKDF2-HMAC-SHA512 and a random salt.
+
+use cardano::{hdwallet, util::hex};
+use cryptoxide::{chacha20poly1305::ChaCha20Poly1305, hmac::Hmac, pbkdf2::pbkdf2, sha2::Sha512};
+use rand::{rngs::OsRng, RngCore};
+use serde_json;
+
+use std::{
+    error, fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+const PBKDF2_ITERATIONS: u32 = 19_162;
+const SALT_SIZE: usize = 32;
+const NONCE_SIZE: usize = 12;
+const KEY_SIZE: usize = 32;
+const TAG_SIZE: usize = 16;
+
+const WALLET_FILE_EXTENSION: &'static str = "wallet";
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Json(serde_json::Error),
+    WalletAlreadyExists(PathBuf),
+    WalletNotFound(PathBuf),
+    InvalidEncoding,
+    CannotDecrypt,
+}
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(err) => write!(f, "I/O error: {}", err),
+            Error::Json(err) => write!(f, "invalid wallet file: {}", err),
+            Error::WalletAlreadyExists(path) => {
+                write!(f, "wallet already exists: {}", path.display())
+            }
+            Error::WalletNotFound(path) => write!(f, "wallet not found: {}", path.display()),
+            Error::InvalidEncoding => write!(f, "invalid wallet file encoding"),
+            Error::CannotDecrypt => write!(f, "cannot decrypt the wallet, invalid password?"),
+        }
+    }
+}
+impl error::Error for Error {}
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+impl From<hex::Error> for Error {
+    fn from(_: hex::Error) -> Self {
+        Error::InvalidEncoding
+    }
+}
+
+/// the content of a wallet file
+#[derive(Serialize, Deserialize)]
+pub struct WalletFile {
+    pub derivation_scheme: hdwallet::DerivationScheme,
+    salt: String,
+    nonce: String,
+    encrypted_root_key: String,
+}
+
+fn symmetric_key(password: &[u8], salt: &[u8]) -> [u8; KEY_SIZE] {
+    let mut key = [0; KEY_SIZE];
+    let mut mac = Hmac::new(Sha512::new(), password);
+    pbkdf2(&mut mac, salt, PBKDF2_ITERATIONS, &mut key);
+    key
+}
+
+impl WalletFile {
+    /// encrypt the given root key with the spending password
+    pub fn new(
+        root_key: &hdwallet::XPrv,
+        derivation_scheme: hdwallet::DerivationScheme,
+        password: &[u8],
+    ) -> Result<Self, Error> {
+        let mut salt = [0; SALT_SIZE];
+        let mut nonce = [0; NONCE_SIZE];
+        let mut rng = OsRng::new().map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        rng.fill_bytes(&mut salt);
+        rng.fill_bytes(&mut nonce);
+
+        let key = symmetric_key(password, &salt);
+        let mut ctx = ChaCha20Poly1305::new(&key, &nonce, &[]);
+        let mut encrypted = vec![0; hdwallet::XPRV_SIZE];
+        let mut tag = [0; TAG_SIZE];
+        ctx.encrypt(root_key.as_ref(), &mut encrypted, &mut tag);
+        encrypted.extend_from_slice(&tag);
+
+        Ok(WalletFile {
+            derivation_scheme,
+            salt: hex::encode(&salt),
+            nonce: hex::encode(&nonce),
+            encrypted_root_key: hex::encode(&encrypted),
+        })
+    }
+
+    /// decrypt the root key with the spending password
+    pub fn decrypt(&self, password: &[u8]) -> Result<hdwallet::XPrv, Error> {
+        let salt = hex::decode(&self.salt)?;
+        let nonce = hex::decode(&self.nonce)?;
+        let encrypted = hex::decode(&self.encrypted_root_key)?;
+        if nonce.len() != NONCE_SIZE || encrypted.len() != hdwallet::XPRV_SIZE + TAG_SIZE {
+            return Err(Error::InvalidEncoding);
+        }
+
+        let key = symmetric_key(password, &salt);
+        let mut ctx = ChaCha20Poly1305::new(&key, &nonce, &[]);
+        let mut bytes = [0; hdwallet::XPRV_SIZE];
+        let (encrypted, tag) = encrypted.split_at(hdwallet::XPRV_SIZE);
+        if !ctx.decrypt(encrypted, &mut bytes, tag) {
+            return Err(Error::CannotDecrypt);
+        }
+        hdwallet::XPrv::from_bytes_verified(bytes).map_err(|_| Error::InvalidEncoding)
+    }
+
+    fn path<P: AsRef<Path>>(dir: P, name: &str) -> PathBuf {
+        dir.as_ref()
+            .join(name)
+            .with_extension(WALLET_FILE_EXTENSION)
+    }
+
+    /// save the wallet in the given directory, fails if a wallet of the
+    /// same name already exists
+    pub fn save<P: AsRef<Path>>(&self, dir: P, name: &str) -> Result<(), Error> {
+        let path = Self::path(&dir, name);
+        fs::create_dir_all(dir)?;
+        let file = match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Err(ref err) if err.kind() == io::ErrorKind::AlreadyExists => {
+                return Err(Error::WalletAlreadyExists(path))
+            }
+            result => result?,
+        };
+        serde_json::to_writer_pretty(file, self)?;
+        Ok(())
+    }
+
+    pub fn load<P: AsRef<Path>>(dir: P, name: &str) -> Result<Self, Error> {
+        let path = Self::path(dir, name);
+        if !path.exists() {
+            return Err(Error::WalletNotFound(path));
+        }
+        let file = fs::File::open(path)?;
+        Ok(serde_json::from_reader(file)?)
+    }
+
+    /// the names of all the wallets of the given directory, sorted
+    pub fn list<P: AsRef<Path>>(dir: P) -> Result<Vec<String>, Error> {
+        let mut names = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(WALLET_FILE_EXTENSION) {
+                continue;
+            }
+            if let Some(name) = path.file_stem().and_then(|n| n.to_str()) {
+                names.push(name.to_owned());
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypt_decrypt() {
+        let root_key = hdwallet::XPrv::normalize_bytes([42; hdwallet::XPRV_SIZE]);
+        let file = WalletFile::new(&root_key, Default::default(), b"password").unwrap();
+
+        assert_eq!(file.decrypt(b"password").unwrap(), root_key);
+        assert!(file.decrypt(b"not the password").is_err());
+    }
+}
@@ -0,0 +1,14 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+
+use cardano::{address::ExtendedAddr, util::base58};
+
+use std::str::FromStr;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = base58::decode_bytes(data);
+    if let Ok(s) = std::str::from_utf8(data) {
+        let _ = ExtendedAddr::from_str(s);
+    }
+});
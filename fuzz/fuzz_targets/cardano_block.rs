@@ -0,0 +1,9 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+
+use cardano::block::RawBlock;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = RawBlock::from_dat(data.to_vec()).decode();
+});
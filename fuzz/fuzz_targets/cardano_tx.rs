@@ -0,0 +1,12 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+
+use cardano::tx::TxAux;
+use cbor_event::de::Deserializer;
+
+use std::io::Cursor;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Deserializer::from(Cursor::new(data)).deserialize_complete::<TxAux>();
+});
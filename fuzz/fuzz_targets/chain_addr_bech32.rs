@@ -0,0 +1,14 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+
+use chain_addr::AddressReadable;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        if let Ok(readable) = AddressReadable::from_string(s) {
+            // `to_address` relies on the validation of `from_string`
+            let _ = readable.to_address();
+        }
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+
+use chain_addr::Address;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(address) = Address::from_bytes(data) {
+        // the binary encoding of the addresses is canonical
+        assert_eq!(address.to_bytes(), data);
+    }
+});
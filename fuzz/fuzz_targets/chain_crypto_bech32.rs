@@ -0,0 +1,14 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+
+use chain_crypto::{bech32::Bech32, Ed25519, Ed25519Extended, PublicKey, SecretKey};
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(s) = std::str::from_utf8(data) {
+        let _ = PublicKey::<Ed25519>::try_from_bech32_str(s);
+        let _ = PublicKey::<Ed25519Extended>::try_from_bech32_str(s);
+        let _ = SecretKey::<Ed25519>::try_from_bech32_str(s);
+        let _ = SecretKey::<Ed25519Extended>::try_from_bech32_str(s);
+    }
+});
@@ -0,0 +1,13 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+
+use chain_core::property::{testing::fuzz_readable, Serialize};
+use chain_impl_mockchain::config::ConfigParam;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(param) = fuzz_readable::<ConfigParam>(data) {
+        // a successfully read parameter is always serializable back
+        param.serialize_as_vec().unwrap();
+    }
+});
@@ -0,0 +1,10 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+
+use chain_core::property::Deserialize;
+use chain_impl_mockchain::block::Block;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = Block::deserialize(data);
+});
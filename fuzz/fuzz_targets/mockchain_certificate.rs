@@ -0,0 +1,12 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+
+use chain_core::property::{testing::fuzz_readable, Serialize};
+use chain_impl_mockchain::certificate::Certificate;
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(certificate) = fuzz_readable::<Certificate>(data) {
+        certificate.serialize_as_vec().unwrap();
+    }
+});
@@ -0,0 +1,11 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+
+use chain_addr::Address;
+use chain_core::property::testing::fuzz_readable;
+use chain_impl_mockchain::transaction::{NoExtra, Transaction};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = fuzz_readable::<Transaction<Address, NoExtra>>(data);
+});
@@ -0,0 +1,10 @@
+#![no_main]
+#[macro_use]
+extern crate libfuzzer_sys;
+
+use chain_core::property::testing::fuzz_readable;
+use chain_impl_mockchain::legacy::UtxoDeclaration;
+
+fuzz_target!(|data: &[u8]| {
+    let _ = fuzz_readable::<UtxoDeclaration>(data);
+});
@@ -0,0 +1,12 @@
+fn main() {
+    // the server speaks the protocol of `network-grpc`, so that the
+    // existing peers can connect to it
+    #[cfg(feature = "grpc")]
+    tonic_build::configure()
+        .build_client(false)
+        .compile(
+            &["../network-grpc/proto/node.proto"],
+            &["../network-grpc/proto/"],
+        )
+        .unwrap_or_else(|e| panic!("cannot compile the protocol: {}", e));
+}
@@ -0,0 +1,403 @@
+//! Block service backed by a `chain-storage` block store.
+
+use crate::subscription::{Broadcast, Subscription};
+
+use chain_core::property::{Block, HasHeader, Header, Serialize};
+use chain_storage::{error::Error as StorageError, store::BlockStore};
+use network_core::{
+    error::{Code, Error},
+    gossip::NodeId,
+    server::{block::BlockService, P2pService},
+};
+
+use futures::{
+    future::{self, FutureResult},
+    prelude::*,
+    stream::{self, IterOk},
+};
+
+use std::{
+    sync::{Arc, RwLock},
+    vec,
+};
+
+/// The tag of the block store pointing to the tip of the chain.
+pub const TIP_TAG: &str = "tip";
+
+type BlockId<S> = <<S as BlockStore>::Block as Block>::Id;
+type BlockHeader<S> = <<S as BlockStore>::Block as HasHeader>::Header;
+
+/// Serves the blocks of a block store.
+///
+/// The tip of the chain is the block of the store's `TIP_TAG` tag.
+/// New blocks should be added with `put_block`, which updates the tip and
+/// announces the new header to the subscribers.
+pub struct StoreBlockService<S, Id>
+where
+    S: BlockStore,
+    S::Block: HasHeader,
+{
+    store: Arc<RwLock<S>>,
+    node_id: Id,
+    announcements: Broadcast<BlockHeader<S>>,
+}
+
+impl<S, Id> Clone for StoreBlockService<S, Id>
+where
+    S: BlockStore,
+    S::Block: HasHeader,
+    Id: Clone,
+{
+    fn clone(&self) -> Self {
+        StoreBlockService {
+            store: self.store.clone(),
+            node_id: self.node_id.clone(),
+            announcements: self.announcements.clone(),
+        }
+    }
+}
+
+fn storage_error(err: StorageError) -> Error {
+    let code = match err {
+        StorageError::BlockNotFound => Code::NotFound,
+        StorageError::CannotIterate => Code::InvalidArgument,
+    };
+    Error::new(code, err)
+}
+
+impl<S, Id> StoreBlockService<S, Id>
+where
+    S: BlockStore,
+    S::Block: HasHeader,
+    BlockHeader<S>: Clone,
+{
+    pub fn new(store: Arc<RwLock<S>>, node_id: Id) -> Self {
+        StoreBlockService {
+            store,
+            node_id,
+            announcements: Broadcast::new(),
+        }
+    }
+
+    /// Adds the block to the store, makes it the tip of the chain and
+    /// announces its header to the subscribers.
+    pub fn put_block(&self, block: &S::Block) -> Result<(), StorageError> {
+        {
+            let mut store = self.store.write().unwrap();
+            store.put_block(block)?;
+            store.put_tag(TIP_TAG, &block.id())?;
+        }
+        self.announcements.send(block.header());
+        Ok(())
+    }
+
+    fn tip_block(&self) -> Result<S::Block, Error> {
+        let store = self.store.read().unwrap();
+        let tip = store
+            .get_tag(TIP_TAG)
+            .map_err(storage_error)?
+            .ok_or_else(|| Error::new(Code::NotFound, "the block store has no tip"))?;
+        let (block, _) = store.get_block(&tip).map_err(storage_error)?;
+        Ok(block)
+    }
+
+    fn blocks(&self, ids: &[BlockId<S>]) -> Result<Vec<S::Block>, Error> {
+        let store = self.store.read().unwrap();
+        ids.iter()
+            .map(|id| store.get_block(id).map(|(block, _)| block))
+            .collect::<Result<_, _>>()
+            .map_err(storage_error)
+    }
+
+    /// The blocks in the range (from, to], from the first of the starting
+    /// points that is an ancestor of `to`.
+    fn range(&self, from: &[BlockId<S>], to: &BlockId<S>) -> Result<Vec<S::Block>, Error> {
+        let store = self.store.read().unwrap();
+        for start in from {
+            // the starting points unknown to this store are skipped like
+            // the ones that are not on the chain of `to`
+            match store.is_ancestor(start, to) {
+                Ok(Some(_)) => {}
+                Ok(None) => continue,
+                Err(StorageError::BlockNotFound)
+                    if store.block_exists(to).map_err(storage_error)? =>
+                {
+                    continue
+                }
+                Err(err) => return Err(storage_error(err)),
+            }
+            return store
+                .iterate_range(start, to)
+                .map_err(storage_error)?
+                .map(|info| {
+                    info.and_then(|info| store.get_block(&info.block_hash))
+                        .map(|(block, _)| block)
+                })
+                .collect::<Result<_, _>>()
+                .map_err(storage_error);
+        }
+        Err(Error::new(
+            Code::InvalidArgument,
+            "none of the starting points is an ancestor of the requested block",
+        ))
+    }
+}
+
+impl<S, Id> P2pService for StoreBlockService<S, Id>
+where
+    S: BlockStore,
+    S::Block: HasHeader,
+    Id: NodeId,
+{
+    type NodeId = Id;
+
+    fn node_id(&self) -> Id {
+        self.node_id.clone()
+    }
+}
+
+impl<S, Id> BlockService for StoreBlockService<S, Id>
+where
+    S: BlockStore,
+    S::Block: HasHeader,
+    <S::Block as Block>::Date: ToString,
+    BlockHeader<S>: Header<Id = BlockId<S>, Date = <S::Block as Block>::Date> + Serialize + Clone,
+    Id: NodeId,
+{
+    type BlockId = BlockId<S>;
+    type BlockDate = <S::Block as Block>::Date;
+    type Block = S::Block;
+    type Header = BlockHeader<S>;
+    type TipFuture = FutureResult<Self::Header, Error>;
+    type PullBlocksStream = IterOk<vec::IntoIter<Self::Block>, Error>;
+    type PullBlocksFuture = FutureResult<Self::PullBlocksStream, Error>;
+    type GetBlocksStream = IterOk<vec::IntoIter<Self::Block>, Error>;
+    type GetBlocksFuture = FutureResult<Self::GetBlocksStream, Error>;
+    type PullHeadersStream = IterOk<vec::IntoIter<Self::Header>, Error>;
+    type PullHeadersFuture = FutureResult<Self::PullHeadersStream, Error>;
+    type GetHeadersStream = IterOk<vec::IntoIter<Self::Header>, Error>;
+    type GetHeadersFuture = FutureResult<Self::GetHeadersStream, Error>;
+    type BlockSubscription = Subscription<Self::Header>;
+    type BlockSubscriptionFuture = FutureResult<Self::BlockSubscription, Error>;
+
+    fn tip(&mut self) -> Self::TipFuture {
+        future::result(self.tip_block().map(|block| block.header()))
+    }
+
+    fn get_blocks(&mut self, ids: &[Self::BlockId]) -> Self::GetBlocksFuture {
+        future::result(self.blocks(ids).map(stream::iter_ok))
+    }
+
+    fn get_headers(&mut self, ids: &[Self::BlockId]) -> Self::GetHeadersFuture {
+        future::result(self.blocks(ids).map(headers))
+    }
+
+    fn pull_blocks_to(
+        &mut self,
+        from: &[Self::BlockId],
+        to: &Self::BlockId,
+    ) -> Self::PullBlocksFuture {
+        future::result(self.range(from, to).map(stream::iter_ok))
+    }
+
+    fn pull_blocks_to_tip(&mut self, from: &[Self::BlockId]) -> Self::PullBlocksFuture {
+        let blocks = self
+            .tip_block()
+            .and_then(|tip| self.range(from, &tip.id()));
+        future::result(blocks.map(stream::iter_ok))
+    }
+
+    fn pull_headers_to(
+        &mut self,
+        from: &[Self::BlockId],
+        to: &Self::BlockId,
+    ) -> Self::PullHeadersFuture {
+        future::result(self.range(from, to).map(headers))
+    }
+
+    fn pull_headers_to_tip(&mut self, from: &[Self::BlockId]) -> Self::PullHeadersFuture {
+        let blocks = self
+            .tip_block()
+            .and_then(|tip| self.range(from, &tip.id()));
+        future::result(blocks.map(headers))
+    }
+
+    fn block_subscription<In>(
+        &mut self,
+        _subscriber: Self::NodeId,
+        _inbound: In,
+    ) -> Self::BlockSubscriptionFuture
+    where
+        In: Stream<Item = Self::Header, Error = Error> + Send + 'static,
+    {
+        // the blocks announced by the peer are not fetched: the store is
+        // only fed by the application through `put_block`
+        future::ok(self.announcements.subscribe())
+    }
+}
+
+fn headers<B: HasHeader>(blocks: Vec<B>) -> IterOk<vec::IntoIter<B::Header>, Error> {
+    stream::iter_ok(
+        blocks
+            .iter()
+            .map(|block| block.header())
+            .collect::<Vec<_>>(),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::StoreBlockService;
+    use chain_core::property::{Block as _, ChainLength as _, Deserialize, Serialize};
+    use chain_crypto::{Ed25519Extended, SecretKey};
+    use chain_impl_mockchain::{
+        block::{Block, BlockBuilder, ConsensusVersion, HeaderHash},
+        config::ConfigParam,
+        message::{InitialEnts, Message},
+    };
+    use chain_storage::{memory::MemoryBlockStore, store::BlockStore};
+    use network_core::{error::Code, gossip, server::block::BlockService};
+
+    use futures::prelude::*;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    use std::{
+        io::{self, BufRead, Write},
+        sync::{Arc, RwLock},
+    };
+
+    #[derive(Clone, Debug)]
+    struct TestNodeId;
+
+    impl Serialize for TestNodeId {
+        type Error = io::Error;
+
+        fn serialize<W: Write>(&self, _: W) -> Result<(), io::Error> {
+            Ok(())
+        }
+    }
+
+    impl Deserialize for TestNodeId {
+        type Error = io::Error;
+
+        fn deserialize<R: BufRead>(_: R) -> Result<Self, io::Error> {
+            Ok(TestNodeId)
+        }
+    }
+
+    impl gossip::NodeId for TestNodeId {}
+
+    type Service = StoreBlockService<MemoryBlockStore<Block>, TestNodeId>;
+
+    /// A store holding the genesis block followed by `len` blocks.
+    /// Returns the service and the ids of the chain, genesis first.
+    fn chain(len: u32) -> (Service, Vec<HeaderHash>) {
+        let leader_key = SecretKey::<Ed25519Extended>::generate(ChaChaRng::from_seed([0; 32]));
+        let store = Arc::new(RwLock::new(MemoryBlockStore::new()));
+        let service = StoreBlockService::new(store, TestNodeId);
+
+        let mut genesis = BlockBuilder::new();
+        let mut ents = InitialEnts::new();
+        ents.push(ConfigParam::ConsensusVersion(ConsensusVersion::Bft));
+        genesis.message(Message::Initial(ents));
+        let genesis = genesis.make_genesis_block();
+        service.put_block(&genesis).unwrap();
+
+        let mut ids = vec![genesis.id()];
+        let mut parent = genesis;
+        for _ in 0..len {
+            let mut block = BlockBuilder::new();
+            block.chain_length(parent.chain_length().next());
+            block.parent(parent.id());
+            let block = block.make_bft_block(&leader_key);
+            service.put_block(&block).unwrap();
+            ids.push(block.id());
+            parent = block;
+        }
+        (service, ids)
+    }
+
+    fn ids(blocks: Vec<Block>) -> Vec<HeaderHash> {
+        blocks.iter().map(|block| block.id()).collect()
+    }
+
+    #[test]
+    fn tip() {
+        let (mut service, chain) = chain(3);
+        let tip = service.tip().wait().unwrap();
+        assert_eq!(tip.hash(), chain[3]);
+
+        let store = Arc::new(RwLock::new(MemoryBlockStore::new()));
+        let mut empty = Service::new(store, TestNodeId);
+        assert_eq!(empty.tip().wait().unwrap_err().code(), Code::NotFound);
+    }
+
+    #[test]
+    fn get_blocks() {
+        let (mut service, chain) = chain(3);
+        let blocks = service
+            .get_blocks(&[chain[2], chain[0]])
+            .wait()
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        assert_eq!(ids(blocks), vec![chain[2], chain[0]]);
+
+        let (_, other) = self::chain(1);
+        let err = service
+            .get_blocks(&[chain[1], other[1]])
+            .wait()
+            .unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+    }
+
+    #[test]
+    fn range() {
+        let (service, chain) = chain(5);
+        assert_eq!(
+            ids(service.range(&[chain[1]], &chain[4]).unwrap()),
+            &chain[2..5]
+        );
+        assert_eq!(ids(service.range(&[chain[4]], &chain[4]).unwrap()), vec![]);
+
+        // the first starting point that is an ancestor is used
+        let blocks = service
+            .range(&[chain[5], chain[3], chain[0]], &chain[4])
+            .unwrap();
+        assert_eq!(ids(blocks), vec![chain[4]]);
+
+        let err = service.range(&[chain[5]], &chain[4]).unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+    }
+
+    #[test]
+    fn range_skips_unknown_checkpoints() {
+        let (service, chain) = chain(3);
+        let (_, other) = self::chain(3);
+
+        let blocks = service.range(&[other[2], chain[1]], &chain[3]).unwrap();
+        assert_eq!(ids(blocks), &chain[2..4]);
+
+        let err = service.range(&[other[2]], &chain[3]).unwrap_err();
+        assert_eq!(err.code(), Code::InvalidArgument);
+
+        // an unknown end of the range is not a checkpoint to skip
+        let err = service.range(&[chain[1]], &other[3]).unwrap_err();
+        assert_eq!(err.code(), Code::NotFound);
+    }
+
+    #[test]
+    fn pull_blocks_to_tip() {
+        let (mut service, chain) = chain(3);
+        let blocks = service
+            .pull_blocks_to_tip(&[chain[1]])
+            .wait()
+            .unwrap()
+            .collect()
+            .wait()
+            .unwrap();
+        assert_eq!(ids(blocks), &chain[2..4]);
+    }
+}
@@ -0,0 +1,112 @@
+//! Content service forwarding the submitted fragments to the application.
+
+use crate::subscription::{Broadcast, Subscription};
+
+use chain_core::property::{Message, Serialize};
+use network_core::{
+    error::{Code, Error},
+    gossip::NodeId,
+    server::{
+        content::{ContentService, ProposeTransactionsResponse},
+        P2pService,
+    },
+};
+
+use futures::{
+    future::{self, FutureResult},
+    prelude::*,
+    stream::IterOk,
+    sync::mpsc,
+};
+
+use std::vec;
+
+/// Receives the fragments (transactions, certificates...) submitted by the
+/// peers and forwards them to the application's sink, typically the
+/// mempool.
+///
+/// The fragments accepted by the application can be relayed to the
+/// subscribed peers with `announce`.
+pub struct FragmentService<M, Id> {
+    sink: mpsc::UnboundedSender<M>,
+    node_id: Id,
+    announcements: Broadcast<M>,
+}
+
+impl<M, Id: Clone> Clone for FragmentService<M, Id> {
+    fn clone(&self) -> Self {
+        FragmentService {
+            sink: self.sink.clone(),
+            node_id: self.node_id.clone(),
+            announcements: self.announcements.clone(),
+        }
+    }
+}
+
+impl<M: Clone, Id> FragmentService<M, Id> {
+    pub fn new(sink: mpsc::UnboundedSender<M>, node_id: Id) -> Self {
+        FragmentService {
+            sink,
+            node_id,
+            announcements: Broadcast::new(),
+        }
+    }
+
+    /// Relays the fragment to the subscribed peers.
+    pub fn announce(&self, fragment: M) {
+        self.announcements.send(fragment)
+    }
+}
+
+impl<M, Id: NodeId> P2pService for FragmentService<M, Id> {
+    type NodeId = Id;
+
+    fn node_id(&self) -> Id {
+        self.node_id.clone()
+    }
+}
+
+impl<M, Id> ContentService for FragmentService<M, Id>
+where
+    M: Message + Serialize + Clone + Send + 'static,
+    Id: NodeId,
+{
+    type Message = M;
+    type MessageId = M::Id;
+    type ProposeTransactionsFuture = FutureResult<ProposeTransactionsResponse<M::Id>, Error>;
+    type GetMessagesStream = IterOk<vec::IntoIter<M>, Error>;
+    type GetMessagesFuture = FutureResult<Self::GetMessagesStream, Error>;
+    type MessageSubscription = Subscription<M>;
+    type MessageSubscriptionFuture = FutureResult<Self::MessageSubscription, Error>;
+
+    fn get_messages(&mut self, _ids: &[M::Id]) -> Self::GetMessagesFuture {
+        future::err(Error::new(
+            Code::Unimplemented,
+            "the fragments are not stored by this node",
+        ))
+    }
+
+    fn propose_transactions(&mut self, _ids: &[M::Id]) -> Self::ProposeTransactionsFuture {
+        future::err(Error::new(
+            Code::Unimplemented,
+            "transaction proposals are not supported by this node",
+        ))
+    }
+
+    fn message_subscription<In>(
+        &mut self,
+        _subscriber: Self::NodeId,
+        inbound: In,
+    ) -> Self::MessageSubscriptionFuture
+    where
+        In: Stream<Item = M, Error = Error> + Send + 'static,
+    {
+        let sink = self.sink.clone();
+        tokio::spawn(
+            inbound
+                .map_err(|_| ())
+                .for_each(move |fragment| sink.unbounded_send(fragment).map_err(|_| ())),
+        );
+        future::ok(self.announcements.subscribe())
+    }
+}
@@ -0,0 +1,62 @@
+//! Placeholder gossip service: this node does not take part in the
+//! peer-to-peer gossip.
+
+use crate::subscription::Subscription;
+
+use network_core::{
+    error::{Code, Error},
+    gossip::{Gossip, Node},
+    server::{gossip::GossipService, P2pService},
+};
+
+use futures::{
+    future::{self, FutureResult},
+    prelude::*,
+};
+
+use std::marker::PhantomData;
+
+pub struct NoGossip<N: Node> {
+    node_id: N::Id,
+    _node: PhantomData<N>,
+}
+
+impl<N: Node> Clone for NoGossip<N> {
+    fn clone(&self) -> Self {
+        NoGossip::new(self.node_id.clone())
+    }
+}
+
+impl<N: Node> NoGossip<N> {
+    pub fn new(node_id: N::Id) -> Self {
+        NoGossip {
+            node_id,
+            _node: PhantomData,
+        }
+    }
+}
+
+impl<N: Node> P2pService for NoGossip<N> {
+    type NodeId = N::Id;
+
+    fn node_id(&self) -> N::Id {
+        self.node_id.clone()
+    }
+}
+
+impl<N: Node> GossipService for NoGossip<N> {
+    type Node = N;
+    type GossipSubscription = Subscription<Gossip<N>>;
+    type GossipSubscriptionFuture = FutureResult<Self::GossipSubscription, Error>;
+
+    fn gossip_subscription<In>(
+        &mut self,
+        _subscriber: N::Id,
+        _inbound: In,
+    ) -> Self::GossipSubscriptionFuture
+    where
+        In: Stream<Item = Gossip<N>, Error = Error> + Send + 'static,
+    {
+        future::err(Error::new(Code::Unimplemented, "gossip is not supported"))
+    }
+}
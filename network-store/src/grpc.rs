@@ -0,0 +1,332 @@
+//! gRPC server of a `StoreNode`, built on `tonic`.
+//!
+//! The server speaks the protocol of `network-grpc` (`node.proto`), so the
+//! peers using its client can connect to it. The node services are futures
+//! 0.1 services spawning their tasks on the `tokio` 0.1 executor: the server
+//! has to run on a `tokio-compat` runtime, which provides both executors.
+//!
+//! ```ignore
+//! let service = network_store::grpc::server(node);
+//! tokio_compat::run_std(async move {
+//!     tonic::transport::Server::builder()
+//!         .add_service(service)
+//!         .serve(addr)
+//!         .await
+//!         .unwrap()
+//! });
+//! ```
+
+mod proto {
+    tonic::include_proto!("iohk.chain.node");
+}
+
+use self::proto::node_server::{Node as NodeRpc, NodeServer};
+use crate::StoreNode;
+
+use chain_core::property::{Block, Deserialize, HasHeader, Header, Message, Serialize};
+use chain_storage::store::BlockStore;
+use network_core::{
+    error::{Code, Error},
+    gossip::{Gossip, Node as GossipNode, NodeId},
+    server::{block::BlockService, content::ContentService, gossip::GossipService, Node},
+};
+
+use futures::Stream as _;
+use futures03::{
+    channel::mpsc,
+    compat::{Compat, Future01CompatExt},
+    prelude::*,
+};
+use tonic::{metadata::MetadataMap, Request, Response, Status, Streaming};
+
+use std::pin::Pin;
+
+/// Name of the binary metadata key passing the node ID of the subscribers,
+/// as in `network-grpc`.
+const NODE_ID_HEADER: &str = "node-id-bin";
+
+type ResponseStream<R> = Pin<Box<dyn Stream<Item = Result<R, Status>> + Send + Sync>>;
+
+type InboundStream<T> = Compat<Pin<Box<dyn Stream<Item = Result<T, Error>> + Send>>>;
+
+/// The gRPC service of the node, to be added to a `tonic` server.
+pub fn server<S, M, N>(node: StoreNode<S, M, N>) -> NodeServer<NodeService<S, M, N>>
+where
+    NodeService<S, M, N>: NodeRpc,
+{
+    NodeServer::new(NodeService { node })
+}
+
+/// Implementation of the gRPC protocol over the services of a `StoreNode`.
+pub struct NodeService<S, M, N>
+where
+    S: BlockStore,
+    S::Block: HasHeader,
+    N: GossipNode,
+{
+    node: StoreNode<S, M, N>,
+}
+
+fn error_into_status(err: Error) -> Status {
+    let code = match err.code() {
+        Code::Canceled => tonic::Code::Cancelled,
+        Code::Unknown => tonic::Code::Unknown,
+        Code::InvalidArgument => tonic::Code::InvalidArgument,
+        Code::NotFound => tonic::Code::NotFound,
+        Code::FailedPrecondition => tonic::Code::FailedPrecondition,
+        Code::Unimplemented => tonic::Code::Unimplemented,
+        Code::Internal => tonic::Code::Internal,
+    };
+    Status::new(code, err.to_string())
+}
+
+fn error_from_status(status: Status) -> Error {
+    let code = match status.code() {
+        tonic::Code::Cancelled => Code::Canceled,
+        tonic::Code::InvalidArgument => Code::InvalidArgument,
+        tonic::Code::NotFound => Code::NotFound,
+        tonic::Code::FailedPrecondition => Code::FailedPrecondition,
+        tonic::Code::Unimplemented => Code::Unimplemented,
+        tonic::Code::Internal => Code::Internal,
+        _ => Code::Unknown,
+    };
+    Error::new(code, status)
+}
+
+fn serialize<T: Serialize>(item: &T) -> Result<Vec<u8>, Status> {
+    item.serialize_as_vec()
+        .map_err(|_| Status::internal("response serialization failed"))
+}
+
+fn deserialize<T: Deserialize>(mut bytes: &[u8]) -> Result<T, Error> {
+    T::deserialize(&mut bytes).map_err(|e| Error::new(Code::InvalidArgument, e))
+}
+
+fn deserialize_ids<T: Deserialize>(ids: &[Vec<u8>]) -> Result<Vec<T>, Status> {
+    ids.iter()
+        .map(|id| deserialize(id))
+        .collect::<Result<_, _>>()
+        .map_err(error_into_status)
+}
+
+fn subscriber<Id: NodeId>(metadata: &MetadataMap) -> Result<Id, Status> {
+    let value = metadata
+        .get_bin(NODE_ID_HEADER)
+        .ok_or_else(|| Status::invalid_argument(format!("missing metadata {}", NODE_ID_HEADER)))?;
+    let bytes = value.to_bytes().map_err(|e| {
+        Status::invalid_argument(format!("invalid metadata value {}: {}", NODE_ID_HEADER, e))
+    })?;
+    deserialize(&bytes).map_err(error_into_status)
+}
+
+fn unsupported(service: &str) -> Status {
+    Status::unimplemented(format!(
+        "the {} service is not supported by this node",
+        service
+    ))
+}
+
+/// The items of the stream of a node service as the messages of a
+/// server-streamed response.
+///
+/// The stream is polled by a task of the `tokio` 0.1 executor, like the
+/// other tasks of the services, until the peer drops the response.
+fn outbound<St, R>(stream: St, message: fn(St::Item) -> Result<R, Status>) -> ResponseStream<R>
+where
+    St: futures::Stream<Error = Error> + Send + 'static,
+    R: Send + 'static,
+{
+    let (sender, receiver) = mpsc::unbounded();
+    let forward = stream
+        .then(move |item| {
+            let message = item.map_err(error_into_status).and_then(message);
+            sender.unbounded_send(message).map_err(|_| ())
+        })
+        .for_each(|()| Ok(()));
+    tokio::spawn(forward);
+    Box::pin(receiver)
+}
+
+fn block(block: impl Serialize) -> Result<proto::Block, Status> {
+    Ok(proto::Block {
+        content: serialize(&block)?,
+    })
+}
+
+fn header(header: impl Serialize) -> Result<proto::Header, Status> {
+    Ok(proto::Header {
+        content: serialize(&header)?,
+    })
+}
+
+fn message(message: impl Serialize) -> Result<proto::Message, Status> {
+    Ok(proto::Message {
+        content: serialize(&message)?,
+    })
+}
+
+fn gossip<N: GossipNode>(gossip: Gossip<N>) -> Result<proto::Gossip, Status> {
+    let nodes = gossip
+        .nodes()
+        .iter()
+        .map(serialize)
+        .collect::<Result<_, _>>()?;
+    Ok(proto::Gossip { nodes })
+}
+
+/// The messages of a client-streamed request as the inbound stream of a
+/// node service subscription.
+fn inbound<T, R>(stream: Streaming<R>, content: fn(R) -> Vec<u8>) -> InboundStream<T>
+where
+    T: Deserialize + Send + 'static,
+    R: Send + 'static,
+{
+    let stream: Pin<Box<dyn Stream<Item = Result<T, Error>> + Send>> =
+        Box::pin(stream.map(move |message| {
+            let message = message.map_err(error_from_status)?;
+            deserialize(&content(message))
+        }));
+    stream.compat()
+}
+
+#[tonic::async_trait]
+impl<S, M, N> NodeRpc for NodeService<S, M, N>
+where
+    S: BlockStore + Send + Sync + 'static,
+    S::Block: HasHeader + Serialize + Send + Sync + 'static,
+    <S::Block as Block>::Id: Send,
+    <S::Block as Block>::Date: ToString,
+    <S::Block as HasHeader>::Header: Header<
+            Id = <S::Block as Block>::Id,
+            Date = <S::Block as Block>::Date,
+        > + Serialize
+        + Deserialize
+        + Clone
+        + Send
+        + Sync
+        + 'static,
+    M: Message + Serialize + Deserialize + Clone + Send + Sync + 'static,
+    M::Id: Send,
+    N: GossipNode + Send + Sync + 'static,
+    N::Id: Send + Sync + 'static,
+{
+    type GetBlocksStream = ResponseStream<proto::Block>;
+    type GetHeadersStream = ResponseStream<proto::Header>;
+    type GetMessagesStream = ResponseStream<proto::Message>;
+    type PullBlocksToTipStream = ResponseStream<proto::Block>;
+    type BlockSubscriptionStream = ResponseStream<proto::Header>;
+    type MessageSubscriptionStream = ResponseStream<proto::Message>;
+    type GossipSubscriptionStream = ResponseStream<proto::Gossip>;
+
+    async fn tip(
+        &self,
+        _request: Request<proto::TipRequest>,
+    ) -> Result<Response<proto::TipResponse>, Status> {
+        let mut node = self.node.clone();
+        let service = node.block_service().ok_or_else(|| unsupported("block"))?;
+        let header = service.tip().compat().await.map_err(error_into_status)?;
+        Ok(Response::new(proto::TipResponse {
+            block_header: serialize(&header)?,
+        }))
+    }
+
+    async fn get_blocks(
+        &self,
+        request: Request<proto::BlockIds>,
+    ) -> Result<Response<Self::GetBlocksStream>, Status> {
+        let ids = deserialize_ids(&request.get_ref().id)?;
+        let mut node = self.node.clone();
+        let service = node.block_service().ok_or_else(|| unsupported("block"))?;
+        let blocks = service.get_blocks(&ids).compat().await;
+        let blocks = blocks.map_err(error_into_status)?;
+        Ok(Response::new(outbound(blocks, block)))
+    }
+
+    async fn get_headers(
+        &self,
+        request: Request<proto::BlockIds>,
+    ) -> Result<Response<Self::GetHeadersStream>, Status> {
+        let ids = deserialize_ids(&request.get_ref().id)?;
+        let mut node = self.node.clone();
+        let service = node.block_service().ok_or_else(|| unsupported("block"))?;
+        let headers = service.get_headers(&ids).compat().await;
+        let headers = headers.map_err(error_into_status)?;
+        Ok(Response::new(outbound(headers, header)))
+    }
+
+    async fn get_messages(
+        &self,
+        request: Request<proto::MessageIds>,
+    ) -> Result<Response<Self::GetMessagesStream>, Status> {
+        let ids = deserialize_ids(&request.get_ref().id)?;
+        let mut node = self.node.clone();
+        let service = node
+            .content_service()
+            .ok_or_else(|| unsupported("content"))?;
+        let messages = service.get_messages(&ids).compat().await;
+        let messages = messages.map_err(error_into_status)?;
+        Ok(Response::new(outbound(messages, message)))
+    }
+
+    async fn pull_blocks_to_tip(
+        &self,
+        request: Request<proto::PullBlocksToTipRequest>,
+    ) -> Result<Response<Self::PullBlocksToTipStream>, Status> {
+        let from = deserialize_ids(&request.get_ref().from)?;
+        let mut node = self.node.clone();
+        let service = node.block_service().ok_or_else(|| unsupported("block"))?;
+        let blocks = service.pull_blocks_to_tip(&from).compat().await;
+        let blocks = blocks.map_err(error_into_status)?;
+        Ok(Response::new(outbound(blocks, block)))
+    }
+
+    async fn block_subscription(
+        &self,
+        request: Request<Streaming<proto::Header>>,
+    ) -> Result<Response<Self::BlockSubscriptionStream>, Status> {
+        let subscriber = subscriber(request.metadata())?;
+        let inbound = inbound(request.into_inner(), |header| header.content);
+        let mut node = self.node.clone();
+        let service = node.block_service().ok_or_else(|| unsupported("block"))?;
+        let subscription = service.block_subscription(subscriber, inbound);
+        let headers = subscription.compat().await.map_err(error_into_status)?;
+        Ok(Response::new(outbound(headers, header)))
+    }
+
+    async fn message_subscription(
+        &self,
+        request: Request<Streaming<proto::Message>>,
+    ) -> Result<Response<Self::MessageSubscriptionStream>, Status> {
+        let subscriber = subscriber(request.metadata())?;
+        let inbound = inbound(request.into_inner(), |message| message.content);
+        let mut node = self.node.clone();
+        let service = node
+            .content_service()
+            .ok_or_else(|| unsupported("content"))?;
+        let subscription = service.message_subscription(subscriber, inbound);
+        let messages = subscription.compat().await.map_err(error_into_status)?;
+        Ok(Response::new(outbound(messages, message)))
+    }
+
+    async fn gossip_subscription(
+        &self,
+        request: Request<Streaming<proto::Gossip>>,
+    ) -> Result<Response<Self::GossipSubscriptionStream>, Status> {
+        let subscriber = subscriber(request.metadata())?;
+        let stream: Pin<Box<dyn Stream<Item = Result<Gossip<N>, Error>> + Send>> =
+            Box::pin(request.into_inner().map(|gossip| {
+                let gossip = gossip.map_err(error_from_status)?;
+                let nodes = gossip
+                    .nodes
+                    .iter()
+                    .map(|node| deserialize(node))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(Gossip::from_nodes(nodes))
+            }));
+        let mut node = self.node.clone();
+        let service = node.gossip_service().ok_or_else(|| unsupported("gossip"))?;
+        let subscription = service.gossip_subscription(subscriber, stream.compat());
+        let subscription = subscription.compat().await.map_err(error_into_status)?;
+        Ok(Response::new(outbound(subscription, gossip)))
+    }
+}
@@ -0,0 +1,132 @@
+//! Network node services serving a `chain-storage` block store.
+//!
+//! `StoreNode` implements the `network_core::server::Node` abstraction
+//! generically over the block type of the store, so any blockchain
+//! implementing the `chain_core::property` traits (the Byron blocks of
+//! `cardano`, the blocks of `chain-impl-mockchain`...) can be served with
+//! the same protocol implementation.
+//!
+//! The node serves block fetching and the tip subscription from the store,
+//! and forwards the fragments submitted by the peers to the application.
+//! It does not take part in the gossip: the gossip subscriptions are
+//! refused.
+//!
+//! With the `grpc` feature, the `grpc` module serves the node with a
+//! `tonic` gRPC server, speaking the protocol of `network-grpc`:
+//!
+//! ```ignore
+//! let node = StoreNode::new(store, node_id, fragment_sink);
+//! let service = network_store::grpc::server(node.clone());
+//! ```
+
+#![warn(clippy::all)]
+
+pub mod block;
+pub mod content;
+pub mod gossip;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+mod subscription;
+
+pub use self::subscription::Subscription;
+
+use chain_core::property::{Block, HasHeader, Message};
+use chain_storage::store::BlockStore;
+use network_core::{gossip::Node as GossipNode, server::Node};
+
+use futures::sync::mpsc;
+
+use std::sync::{Arc, RwLock};
+
+use self::{block::StoreBlockService, content::FragmentService, gossip::NoGossip};
+
+/// A node serving the blocks of the store `S`, accepting the fragments
+/// `M`, identified on the network as a node `N`.
+pub struct StoreNode<S, M, N>
+where
+    S: BlockStore,
+    S::Block: HasHeader,
+    N: GossipNode,
+{
+    blocks: StoreBlockService<S, N::Id>,
+    fragments: FragmentService<M, N::Id>,
+    gossip: NoGossip<N>,
+}
+
+impl<S, M, N> Clone for StoreNode<S, M, N>
+where
+    S: BlockStore,
+    S::Block: HasHeader,
+    N: GossipNode,
+{
+    fn clone(&self) -> Self {
+        StoreNode {
+            blocks: self.blocks.clone(),
+            fragments: self.fragments.clone(),
+            gossip: self.gossip.clone(),
+        }
+    }
+}
+
+impl<S, M, N> StoreNode<S, M, N>
+where
+    S: BlockStore,
+    S::Block: HasHeader,
+    <S::Block as HasHeader>::Header: Clone,
+    M: Clone,
+    N: GossipNode,
+{
+    /// Creates the node, the fragments submitted by the peers are sent to
+    /// `fragment_sink`.
+    pub fn new(
+        store: Arc<RwLock<S>>,
+        node_id: N::Id,
+        fragment_sink: mpsc::UnboundedSender<M>,
+    ) -> Self {
+        StoreNode {
+            blocks: StoreBlockService::new(store, node_id.clone()),
+            fragments: FragmentService::new(fragment_sink, node_id.clone()),
+            gossip: NoGossip::new(node_id),
+        }
+    }
+
+    /// Adds a new block to the store and announces it as the new tip.
+    pub fn put_block(&self, block: &S::Block) -> Result<(), chain_storage::error::Error> {
+        self.blocks.put_block(block)
+    }
+
+    /// Relays an accepted fragment to the subscribed peers.
+    pub fn announce_fragment(&self, fragment: M) {
+        self.fragments.announce(fragment)
+    }
+}
+
+impl<S, M, N> Node for StoreNode<S, M, N>
+where
+    S: BlockStore,
+    S::Block: HasHeader,
+    <S::Block as Block>::Date: ToString,
+    <S::Block as HasHeader>::Header: chain_core::property::Header<
+            Id = <S::Block as Block>::Id,
+            Date = <S::Block as Block>::Date,
+        > + chain_core::property::Serialize
+        + Clone,
+    M: Message + chain_core::property::Serialize + Clone + Send + 'static,
+    N: GossipNode,
+{
+    type BlockService = StoreBlockService<S, N::Id>;
+    type ContentService = FragmentService<M, N::Id>;
+    type GossipService = NoGossip<N>;
+
+    fn block_service(&mut self) -> Option<&mut Self::BlockService> {
+        Some(&mut self.blocks)
+    }
+
+    fn content_service(&mut self) -> Option<&mut Self::ContentService> {
+        Some(&mut self.fragments)
+    }
+
+    fn gossip_service(&mut self) -> Option<&mut Self::GossipService> {
+        Some(&mut self.gossip)
+    }
+}
@@ -0,0 +1,55 @@
+use network_core::error::Error;
+
+use futures::{prelude::*, sync::mpsc};
+
+use std::sync::{Arc, Mutex};
+
+/// Broadcasts items to all the current subscribers.
+pub struct Broadcast<T> {
+    subscribers: Arc<Mutex<Vec<mpsc::UnboundedSender<T>>>>,
+}
+
+impl<T> Clone for Broadcast<T> {
+    fn clone(&self) -> Self {
+        Broadcast {
+            subscribers: self.subscribers.clone(),
+        }
+    }
+}
+
+impl<T: Clone> Broadcast<T> {
+    pub fn new() -> Self {
+        Broadcast {
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Creates a new subscription, receiving the items sent from now on.
+    pub fn subscribe(&self) -> Subscription<T> {
+        let (sender, receiver) = mpsc::unbounded();
+        self.subscribers.lock().unwrap().push(sender);
+        Subscription { inner: receiver }
+    }
+
+    /// Sends the item to all the subscribers, forgetting about the ones
+    /// that have been dropped.
+    pub fn send(&self, item: T) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|sender| sender.unbounded_send(item.clone()).is_ok());
+    }
+}
+
+/// Stream of the items sent to a `Broadcast` since the subscription.
+pub struct Subscription<T> {
+    inner: mpsc::UnboundedReceiver<T>,
+}
+
+impl<T> Stream for Subscription<T> {
+    type Item = T;
+    type Error = Error;
+
+    fn poll(&mut self) -> Poll<Option<T>, Error> {
+        // receiving from an unbounded channel never fails
+        Ok(self.inner.poll().unwrap())
+    }
+}
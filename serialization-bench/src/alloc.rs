@@ -0,0 +1,55 @@
+//! global allocator counting the allocations of the process
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+pub struct CountingAllocator;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        // a reallocation is counted as a new allocation of the new size, it
+        // is what it costs when the allocator can't grow the block in place
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+/// the allocation counters at a given point
+#[derive(Debug, Clone, Copy)]
+pub struct Snapshot {
+    pub allocations: usize,
+    pub bytes: usize,
+}
+
+impl Snapshot {
+    pub fn now() -> Self {
+        Snapshot {
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            bytes: ALLOCATED_BYTES.load(Ordering::Relaxed),
+        }
+    }
+
+    /// the allocations since the given snapshot
+    pub fn since(self, start: Snapshot) -> Self {
+        Snapshot {
+            allocations: self.allocations - start.allocations,
+            bytes: self.bytes - start.bytes,
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! the CBOR path: transactions (`TxAux`) and block bodies (`TxPayload`) of
+//! the `cardano` crate
+
+use cardano::{
+    address::ExtendedAddr,
+    block::normal::TxPayload,
+    coin::Coin,
+    config::ProtocolMagic,
+    hash::Blake2b256,
+    hdwallet::{XPrv, XPRV_SIZE},
+    tx::{Tx, TxAux, TxInWitness, TxOut, TxWitness, TxoPointer},
+};
+use cbor_event::{de::Deserializer, se::Serializer};
+
+use std::io::Cursor;
+
+use crate::workload::{Workload, BLOCK_SIZES, TRANSACTION_SHAPES};
+
+fn encode<T: cbor_event::se::Serialize>(t: &T) -> Vec<u8> {
+    let mut se = Serializer::new_vec();
+    se.serialize(t).expect("in memory serialization");
+    se.finalize()
+}
+
+fn decode<T: cbor_event::de::Deserialize>(bytes: &[u8]) -> T {
+    Deserializer::from(Cursor::new(bytes))
+        .deserialize_complete()
+        .expect("valid encoding")
+}
+
+/// a signed transaction, `seed` makes the keys and ids distinct
+fn txaux(seed: u8, inputs: usize, outputs: usize) -> TxAux {
+    let protocol_magic = ProtocolMagic::default();
+    let key = XPrv::normalize_bytes([seed; XPRV_SIZE]);
+    let address = ExtendedAddr::new_simple(key.public(), protocol_magic.into());
+
+    let mut tx = Tx::new();
+    for index in 0..inputs {
+        let id = Blake2b256::new(&[seed, index as u8]);
+        tx.add_input(TxoPointer::new(id, index as u32));
+    }
+    for value in 0..outputs {
+        let value = Coin::new(1_000_000 + value as u64).unwrap();
+        tx.add_output(TxOut::new(address.clone(), value));
+    }
+    let txid = tx.id();
+    let witnesses: Vec<_> = (0..inputs)
+        .map(|_| TxInWitness::new_extended_pk(protocol_magic, &key, &txid))
+        .collect();
+    TxAux::new(tx, TxWitness::from(witnesses))
+}
+
+pub fn workloads() -> Vec<Workload> {
+    let mut workloads = Vec::new();
+    for &(inputs, outputs) in TRANSACTION_SHAPES {
+        let txaux = txaux(0, inputs, outputs);
+        workloads.push(Workload {
+            name: format!("cbor/tx/{}-{}", inputs, outputs),
+            encoded: encode(&txaux),
+            encode: Box::new(move || encode(&txaux)),
+            decode: Box::new(|bytes| drop(decode::<TxAux>(bytes))),
+        });
+    }
+    for &size in BLOCK_SIZES {
+        let payload = TxPayload::new((0..size).map(|i| txaux(i as u8, 1, 2)).collect());
+        workloads.push(Workload {
+            name: format!("cbor/block/{}", size),
+            encoded: encode(&payload),
+            encode: Box::new(move || encode(&payload)),
+            decode: Box::new(|bytes| drop(decode::<TxPayload>(bytes))),
+        });
+    }
+    workloads
+}
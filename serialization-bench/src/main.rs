@@ -0,0 +1,80 @@
+//! Serialization benchmark of the CBOR path (`cardano`) and the mempack path
+//! (`chain-core`, `chain-impl-mockchain`) on transaction and block workloads
+//!
+//! For every workload the encoding and the decoding are run for a fixed
+//! duration, reporting the time per operation, the throughput in encoded
+//! bytes and the number and size of the allocations per operation.
+//!
+//! ```text
+//! cargo run --release -p serialization-bench -- [--duration <secs>] [FILTER]
+//! ```
+
+use structopt::StructOpt;
+
+use std::time::Duration;
+
+mod alloc;
+mod cardano;
+mod measure;
+mod mockchain;
+mod workload;
+
+use self::measure::Measure;
+
+#[global_allocator]
+static ALLOCATOR: alloc::CountingAllocator = alloc::CountingAllocator;
+
+#[derive(StructOpt)]
+#[structopt(name = "serialization-bench", rename_all = "kebab-case")]
+struct Args {
+    /// duration of the measure of every operation, in seconds
+    #[structopt(long = "duration", default_value = "1")]
+    duration: u64,
+
+    /// only run the workloads whose name contains the filter
+    /// (e.g. `block`, `cbor/tx`)
+    #[structopt(name = "FILTER")]
+    filter: Option<String>,
+}
+
+fn report(name: &str, operation: &str, measure: &Measure) {
+    println!(
+        "{:<22} {:<7} {:>9} {:>12} ns/op {:>10.2} MiB/s {:>10.1} allocs/op {:>12.0} B/op",
+        name,
+        operation,
+        measure.bytes,
+        measure.nanos_per_iter(),
+        measure.throughput(),
+        measure.allocations_per_iter(),
+        measure.allocated_bytes_per_iter(),
+    );
+}
+
+fn main() {
+    let args = Args::from_args();
+    let duration = Duration::from_secs(args.duration);
+
+    println!(
+        "{:<22} {:<7} {:>9} {:>18} {:>16} {:>20} {:>17}",
+        "workload", "op", "size", "time", "throughput", "allocations", "allocated"
+    );
+    let workloads = cardano::workloads()
+        .into_iter()
+        .chain(mockchain::workloads())
+        .filter(|w| match &args.filter {
+            None => true,
+            Some(filter) => w.name.contains(filter.as_str()),
+        });
+    for workload in workloads {
+        let size = workload.encoded.len();
+
+        let encode = &workload.encode;
+        let measure = measure::run(duration, size, || encode());
+        report(&workload.name, "encode", &measure);
+
+        let encoded = &workload.encoded;
+        let decode = &workload.decode;
+        let measure = measure::run(duration, size, || decode(encoded));
+        report(&workload.name, "decode", &measure);
+    }
+}
@@ -0,0 +1,73 @@
+//! running a workload and collecting its throughput and allocations
+
+use std::time::{Duration, Instant};
+
+use crate::alloc::Snapshot;
+
+/// the result of a run of a workload
+pub struct Measure {
+    pub iterations: u64,
+    pub elapsed: Duration,
+    /// size of the encoded data processed by one iteration
+    pub bytes: usize,
+    pub allocations: Snapshot,
+}
+
+impl Measure {
+    pub fn nanos_per_iter(&self) -> u64 {
+        let nanos = self.elapsed.as_secs() * 1_000_000_000 + u64::from(self.elapsed.subsec_nanos());
+        nanos / self.iterations
+    }
+
+    /// throughput in MiB of encoded data per second
+    pub fn throughput(&self) -> f64 {
+        let secs = self.elapsed.as_secs() as f64 + f64::from(self.elapsed.subsec_nanos()) / 1e9;
+        (self.bytes as f64 * self.iterations as f64) / secs / (1024.0 * 1024.0)
+    }
+
+    pub fn allocations_per_iter(&self) -> f64 {
+        self.allocations.allocations as f64 / self.iterations as f64
+    }
+
+    pub fn allocated_bytes_per_iter(&self) -> f64 {
+        self.allocations.bytes as f64 / self.iterations as f64
+    }
+}
+
+/// run `f` repeatedly for at least `duration`, `bytes` being the size of
+/// the encoded data processed by every call
+pub fn run<F, R>(duration: Duration, bytes: usize, mut f: F) -> Measure
+where
+    F: FnMut() -> R,
+{
+    // warm up, and an estimate of the number of iterations per batch so the
+    // clock is not read at every iteration
+    let start = Instant::now();
+    let mut batch = 1;
+    while start.elapsed() < duration / 10 {
+        for _ in 0..batch {
+            drop(f());
+        }
+        batch *= 2;
+    }
+
+    let mut iterations = 0;
+    let allocations = Snapshot::now();
+    let start = Instant::now();
+    loop {
+        for _ in 0..batch {
+            drop(f());
+        }
+        iterations += batch;
+        if start.elapsed() >= duration {
+            break;
+        }
+    }
+    let elapsed = start.elapsed();
+    Measure {
+        iterations,
+        elapsed,
+        bytes,
+        allocations: Snapshot::now().since(allocations),
+    }
+}
@@ -0,0 +1,81 @@
+//! the mempack path: transactions and blocks of `chain-impl-mockchain`
+
+use chain_addr::{Address, Discrimination, Kind};
+use chain_core::property::{Deserialize, Serialize};
+use chain_crypto::{Ed25519Extended, SecretKey};
+use chain_impl_mockchain::{
+    block::{Block, BlockBuilder},
+    key::Hash,
+    message::Message,
+    transaction::{
+        AuthenticatedTransaction, Input, NoExtra, Output, Transaction, UtxoPointer, Witness,
+    },
+    value::Value,
+};
+use rand_chacha::ChaChaRng;
+use rand_core::SeedableRng;
+
+use crate::workload::{Workload, BLOCK_SIZES, TRANSACTION_SHAPES};
+
+/// a signed transaction, `seed` makes the keys and ids distinct
+fn transaction(
+    seed: u8,
+    inputs: usize,
+    outputs: usize,
+) -> AuthenticatedTransaction<Address, NoExtra> {
+    let sk = SecretKey::<Ed25519Extended>::generate(ChaChaRng::from_seed([seed; 32]));
+    let address = Address(Discrimination::Test, Kind::Single(sk.to_public()));
+
+    let transaction = Transaction {
+        inputs: (0..inputs)
+            .map(|index| {
+                let id = Hash::hash_bytes(&[seed, index as u8]);
+                Input::from_utxo(UtxoPointer::new(id, index as u8, Value(1_000_000)))
+            })
+            .collect(),
+        outputs: (0..outputs)
+            .map(|value| Output {
+                address: address.clone(),
+                value: Value(1_000_000 + value as u64),
+            })
+            .collect(),
+        extra: NoExtra,
+        metadata: None,
+    };
+    let txid = transaction.hash();
+    AuthenticatedTransaction {
+        transaction,
+        witnesses: (0..inputs)
+            .map(|_| Witness::new_utxo(&txid, &sk).unwrap())
+            .collect(),
+    }
+}
+
+fn encode<T: Serialize>(t: &T) -> Vec<u8> {
+    t.serialize_as_vec().expect("in memory serialization")
+}
+
+pub fn workloads() -> Vec<Workload> {
+    let mut workloads = Vec::new();
+    for &(inputs, outputs) in TRANSACTION_SHAPES {
+        let message = Message::Transaction(transaction(0, inputs, outputs));
+        workloads.push(Workload {
+            name: format!("mempack/tx/{}-{}", inputs, outputs),
+            encoded: encode(&message),
+            encode: Box::new(move || encode(&message)),
+            decode: Box::new(|bytes| drop(Message::deserialize(bytes).expect("valid encoding"))),
+        });
+    }
+    for &size in BLOCK_SIZES {
+        let mut builder = BlockBuilder::new();
+        builder.messages((0..size).map(|i| Message::Transaction(transaction(i as u8, 1, 2))));
+        let block = builder.make_genesis_block();
+        workloads.push(Workload {
+            name: format!("mempack/block/{}", size),
+            encoded: encode(&block),
+            encode: Box::new(move || encode(&block)),
+            decode: Box::new(|bytes| drop(Block::deserialize(bytes).expect("valid encoding"))),
+        });
+    }
+    workloads
+}
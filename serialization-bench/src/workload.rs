@@ -0,0 +1,18 @@
+//! the workloads: a value of a given shape, its encoding and the functions
+//! encoding and decoding it
+
+/// a value to encode and decode
+pub struct Workload {
+    pub name: String,
+    /// the encoded value, input of the decoding benchmark
+    pub encoded: Vec<u8>,
+    pub encode: Box<dyn Fn() -> Vec<u8>>,
+    pub decode: Box<dyn Fn(&[u8])>,
+}
+
+/// the shapes of the transactions of the workloads: (inputs, outputs)
+pub const TRANSACTION_SHAPES: &[(usize, usize)] = &[(1, 1), (1, 2), (10, 10)];
+
+/// the number of transactions of the blocks of the workloads, the
+/// transactions having 1 input and 2 outputs, the most common shape
+pub const BLOCK_SIZES: &[usize] = &[10, 100, 1000];
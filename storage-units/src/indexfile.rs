@@ -138,7 +138,13 @@ impl Index {
     }
 
     pub fn write_to_tmpfile(&self, tmpfile: &mut TmpFile) -> Result<Lookup> {
-        magic::write_header(tmpfile, FILE_TYPE, VERSION)?;
+        self.write(tmpfile)
+    }
+
+    /// write the index file, e.g. in memory to compare it with the one
+    /// on disk
+    pub fn write<W: Write>(&self, writer: &mut W) -> Result<Lookup> {
+        magic::write_header(writer, FILE_TYPE, VERSION)?;
 
         let mut hdr_buf = [0u8; HEADER_SIZE];
 
@@ -174,14 +180,14 @@ impl Index {
             }
             Fanout(fanout_incr)
         };
-        tmpfile.write_all(&hdr_buf)?;
+        writer.write_all(&hdr_buf)?;
 
         let mut bloom: Vec<u8> = repeat(0).take(bloom_size as usize).collect();
         for hash in self.hashes.iter() {
             bloom::set(&mut bloom[..], hash);
         }
 
-        tmpfile.write_all(&bloom[..])?;
+        writer.write_all(&bloom[..])?;
 
         let mut sorted = Vec::with_capacity(entries);
         for i in 0..entries {
@@ -190,13 +196,13 @@ impl Index {
         sorted.sort_by(|a, b| a.0.cmp(&b.0));
 
         for &(hash, _) in sorted.iter() {
-            tmpfile.write_all(&hash[..])?;
+            writer.write_all(&hash[..])?;
         }
 
         for &(_, ofs) in sorted.iter() {
             let mut buf = [0u8; OFF_SIZE];
             write_offset(&mut buf, ofs);
-            tmpfile.write_all(&buf[..])?;
+            writer.write_all(&buf[..])?;
         }
         Ok(Lookup {
             params: params,
@@ -23,6 +23,9 @@ use utils::tmpfile::TmpFile;
 const FILE_TYPE: magic::FileType = 0x5041434b; // = PACK
 const VERSION: magic::Version = 1;
 
+// don't potentially consume all memory when reading a corrupt file
+const MAX_BLOCK_SIZE: u32 = 20000000;
+
 /// A Stream Reader that also computes the hash of the sum of all data read
 pub struct Reader<R> {
     reader: R,
@@ -82,8 +85,12 @@ pub fn read_next_block<R: Read>(mut file: R) -> io::Result<Vec<u8>> {
     let mut sz_buf = [0u8; SIZE_SIZE];
     file.read_exact(&mut sz_buf)?;
     let sz = read_size(&sz_buf);
-    // don't potentially consume all memory when reading a corrupt file
-    assert!(sz < 20000000, "read block of size: {}", sz);
+    if sz >= MAX_BLOCK_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("read block of size: {}", sz),
+        ));
+    }
     let mut v: Vec<u8> = repeat(0).take(sz as usize).collect();
     file.read_exact(v.as_mut_slice())?;
     if (v.len() % 4) != 0 {
@@ -132,6 +139,72 @@ impl<R: Read> Reader<R> {
     }
 }
 
+// the data of a block of a pack, streamed from the pack file and hashed
+// as they are read
+struct BlockReader<'a, R: 'a> {
+    reader: io::Take<&'a mut R>,
+    hash_context: &'a mut blake2b::Blake2b,
+}
+
+impl<'a, R: Read> Read for BlockReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.reader.read(buf)?;
+        self.hash_context.input(&buf[..len]);
+        Ok(len)
+    }
+}
+
+impl<R: Read> Reader<R> {
+    /// Gives the next data block to `f` as a stream, instead of loading
+    /// the whole block in memory. The data not read by `f` are skipped.
+    /// If the source is at EOF, `None` is returned.
+    ///
+    /// # Errors
+    /// I/O errors are returned in an `Err` value, the errors of `f` are
+    /// part of its result.
+    pub fn next_block_with<F, T>(&mut self, f: F) -> io::Result<Option<T>>
+    where
+        F: FnOnce(&mut dyn Read) -> T,
+    {
+        let mut sz_buf = [0u8; SIZE_SIZE];
+        match self.reader.read_exact(&mut sz_buf) {
+            Err(ref err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            r => r?,
+        }
+        let sz = read_size(&sz_buf);
+        if sz >= MAX_BLOCK_SIZE {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("read block of size: {}", sz),
+            ));
+        }
+        let mut block = BlockReader {
+            reader: (&mut self.reader).take(sz as u64),
+            hash_context: &mut self.hash_context,
+        };
+        let result = f(&mut block);
+        io::copy(&mut block, &mut io::sink())?;
+        if block.reader.limit() > 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "truncated block in the pack",
+            ));
+        }
+        if (sz % 4) != 0 {
+            let to_align = 4 - (sz as usize % 4);
+            let mut align = [0u8; 4];
+            self.reader.read_exact(&mut align[0..to_align])?;
+        }
+        self.pos = self
+            .pos
+            .checked_add(4)
+            .unwrap()
+            .checked_add(offset_align4(sz as u64))
+            .unwrap();
+        Ok(Some(result))
+    }
+}
+
 impl<S: Read + Seek> Seeker<S> {
     /// Return the next data chunk if it exists
     /// on file. On EOF, None is returned.
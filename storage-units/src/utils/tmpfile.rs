@@ -3,7 +3,7 @@ use std::fs;
 use std::fs::OpenOptions;
 use std::io;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub struct TmpFile {
     file: fs::File,
@@ -32,6 +32,14 @@ impl TmpFile {
             })
     }
 
+    /// flush the content of the file to the disk
+    ///
+    /// To call before `render_permanent` when the file must not be found
+    /// empty or truncated after a crash.
+    pub fn sync(&self) -> io::Result<()> {
+        self.file.sync_all()
+    }
+
     pub fn render_permanent(&self, path: &PathBuf) -> io::Result<()> {
         // NOTE: we need to consider what is being written, in a case of a tag we want rename
         // to error out correctly in every cases rename fail, however in a case of a hash, since the hash is suppose
@@ -63,6 +71,19 @@ impl io::Write for TmpFile {
     }
 }
 
+/// flush the entries of the directory to the disk, so a file renamed into
+/// it (see `TmpFile::render_permanent`) or removed from it is still so
+/// after a crash
+///
+/// The directories cannot be opened as files on Windows, nothing is done.
+pub fn sync_dir<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    if cfg!(unix) {
+        fs::File::open(path)?.sync_all()
+    } else {
+        Ok(())
+    }
+}
+
 // write the content buf atomically to the path.
 //
 // if an issue arise until the data is written, then
@@ -196,10 +196,9 @@ pub struct ChainStateFile {
 pub fn decode_chain_state_file<R: Read>(file: &mut R) -> Result<ChainStateFile> {
     magic::check_header(file, FILE_TYPE, VERSION, VERSION)?;
 
-    let mut data = vec![];
-    file.read_to_end(&mut data)?;
-
-    let mut raw = de::Deserializer::from(::std::io::Cursor::new(&data));
+    // decode the utxo delta directly from the file instead of loading
+    // the whole (possibly multi-megabyte) content in memory first
+    let mut raw = de::Deserializer::from(::std::io::BufReader::new(file));
 
     raw.tuple(NR_FIELDS, "chain state delta file")?;
     let parent = raw.deserialize()?;
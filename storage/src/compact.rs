@@ -0,0 +1,312 @@
+//! storage maintenance: repack the loose blocks into epoch packs
+//!
+//! A node keeps every block it receives as a loose file (a blob) until its
+//! epoch is packed. When the packing is interrupted or skipped, the loose
+//! files accumulate; `compact` packs the epochs which are deep enough in the
+//! chain to be immutable and removes the loose files which are superseded
+//! by a pack.
+
+use cardano::block::{BlockDate, EpochId, HeaderHash};
+use cardano::config::GenesisData;
+
+use super::{
+    blob, chain_state, epoch, header_to_blockhash, pack, tag, BlockLocation, Result, Storage,
+};
+
+/// compaction parameters
+///
+/// `depth` is the number of blocks, from the `HEAD` of the storage, which
+/// are kept loose whatever their epoch, as they may still be rolled back.
+pub struct CompactParameters {
+    pub depth: usize,
+}
+impl Default for CompactParameters {
+    fn default() -> Self {
+        // the security parameter `k` of the mainnet: blocks deeper than
+        // this cannot be rolled back
+        CompactParameters { depth: 2160 }
+    }
+}
+
+/// what a compaction did to the storage
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CompactReport {
+    /// the epochs packed from loose blocks, in order
+    pub packed_epochs: Vec<EpochId>,
+    /// the number of loose block files removed
+    pub removed_blobs: usize,
+}
+
+/// pack the complete epochs of loose blocks older than `params.depth` from
+/// the `HEAD` of the storage and remove the loose blocks superseded by a pack
+///
+/// Epochs are packed in order and only if the previous epoch is packed, so
+/// the storage stays valid if the compaction is interrupted. The pack and
+/// its index are written in temporary files and renamed in place, the lookup
+/// of the pack is only registered once its index is written.
+pub fn compact(
+    storage: &mut Storage,
+    genesis_data: &GenesisData,
+    params: &CompactParameters,
+) -> Result<CompactReport> {
+    let mut report = CompactReport::default();
+
+    if let Some(tip) = tag::read_hash(storage, &tag::HEAD) {
+        let blocks = loose_chain(storage, &tip)?;
+        let stable = &blocks[..blocks.len().saturating_sub(params.depth)];
+
+        // only the epochs followed by a stable block are complete
+        let mut start = 0;
+        while start < stable.len() {
+            let epoch_id = stable[start].1.get_epochid();
+            let end = start
+                + stable[start..]
+                    .iter()
+                    .take_while(|(_, date)| date.get_epochid() == epoch_id)
+                    .count();
+            if end == stable.len() || !stable[start].1.is_boundary() {
+                break;
+            }
+            if epoch_id > 0 && !epoch::epoch_exists(&storage.config, epoch_id - 1)? {
+                warn!(
+                    "cannot pack epoch {}: the previous epoch is not packed",
+                    epoch_id
+                );
+                break;
+            }
+
+            report.removed_blobs +=
+                pack_epoch(storage, genesis_data, epoch_id, &stable[start..end])?;
+            report.packed_epochs.push(epoch_id);
+            start = end;
+        }
+    }
+
+    report.removed_blobs += remove_packed_blobs(storage)?;
+    Ok(report)
+}
+
+/// the loose blocks of the chain ending at `tip` which are not in a packed
+/// epoch yet, from the oldest to the tip
+fn loose_chain(storage: &Storage, tip: &HeaderHash) -> Result<Vec<(HeaderHash, BlockDate)>> {
+    let mut blocks = Vec::new();
+    let mut current = tip.clone();
+    loop {
+        let hash = header_to_blockhash(&current);
+        if !blob::exist(storage, &hash) {
+            break;
+        }
+        let block = blob::read(storage, &hash)?.decode()?;
+        let header = block.header();
+        let date = header.blockdate();
+        if epoch::epoch_exists(&storage.config, date.get_epochid())? {
+            break;
+        }
+        blocks.push((current, date));
+        current = header.previous_header();
+    }
+    blocks.reverse();
+    Ok(blocks)
+}
+
+/// write the pack of the blocks of the epoch (from its boundary block to
+/// its last block), with its chain state, then remove the loose blocks
+fn pack_epoch(
+    storage: &mut Storage,
+    genesis_data: &GenesisData,
+    epoch_id: EpochId,
+    blocks: &[(HeaderHash, BlockDate)],
+) -> Result<usize> {
+    info!("Packing epoch {} ({} loose blocks)", epoch_id, blocks.len());
+
+    let mut writer = pack::packwriter_init(&storage.config)?;
+    for (hash, _) in blocks {
+        let block_hash = header_to_blockhash(hash);
+        let blob = blob::read_raw(storage, &block_hash)?;
+        writer.append(&block_hash, &blob[..])?;
+    }
+    let (packhash, index) = pack::packwriter_finalize(&storage.config, writer);
+    let (lookup, tmpfile) = pack::create_index(storage, &index);
+    tmpfile.render_permanent(&storage.config.get_index_filepath(&packhash))?;
+    storage.add_lookup(packhash, lookup);
+
+    let last_block = &blocks[blocks.len() - 1].0;
+    let chain_state = chain_state::restore_chain_state(storage, genesis_data, last_block)?;
+    epoch::epoch_create(
+        storage,
+        &packhash,
+        epoch_id,
+        Some((&chain_state, genesis_data)),
+    );
+
+    for (hash, _) in blocks {
+        blob::remove(storage, &header_to_blockhash(hash));
+    }
+    Ok(blocks.len())
+}
+
+/// remove the loose blocks which are also in a pack
+fn remove_packed_blobs(storage: &Storage) -> Result<usize> {
+    let mut removed = 0;
+    for block_hash in storage.config.list_blob(None) {
+        if let BlockLocation::Packed(_, _) = storage.block_location(&block_hash)? {
+            debug!("removing packed blob {:?}", block_hash);
+            blob::remove(storage, &block_hash);
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cardano::block::{Block, HeaderHash};
+    use cardano::config::GenesisData;
+    use test::{chain, epoch_dates, genesis_data, raw, write_blocks, TempStorage};
+    use {blob, chain_state, epoch, header_to_blockhash, Storage};
+
+    /// the number of the blocks which are loose in the storage
+    fn loose(storage: &Storage, blocks: &[(HeaderHash, Block)]) -> usize {
+        blocks
+            .iter()
+            .filter(|(hash, _)| blob::exist(storage, &header_to_blockhash(hash)))
+            .count()
+    }
+
+    fn compact_at(
+        storage: &mut Storage,
+        genesis_data: &GenesisData,
+        depth: usize,
+    ) -> CompactReport {
+        compact(storage, genesis_data, &CompactParameters { depth }).unwrap()
+    }
+
+    #[test]
+    fn compact_stable_epochs() {
+        let mut storage = TempStorage::new();
+        let genesis_data = genesis_data();
+        // 4 epochs of a boundary block and 3 main blocks
+        let blocks = chain(&genesis_data.genesis_prev, &epoch_dates(0, 4, 3));
+        write_blocks(&storage, &blocks);
+
+        assert_eq!(
+            compact_at(&mut storage, &genesis_data, 2),
+            CompactReport {
+                packed_epochs: vec![0, 1, 2],
+                removed_blobs: 12,
+            }
+        );
+        for epoch_id in 0..3 {
+            assert!(epoch::epoch_exists(&storage.config, epoch_id).unwrap());
+        }
+        assert!(!epoch::epoch_exists(&storage.config, 3).unwrap());
+        assert_eq!(loose(&storage, &blocks[12..]), 4);
+        assert_eq!(loose(&storage, &blocks), 4);
+
+        // the packed blocks are still found, and the chain state of the tip
+        // is restored from the chain state of the last packed epoch
+        for (hash, _) in blocks.iter() {
+            assert!(storage.block_exists(&header_to_blockhash(hash)).unwrap());
+        }
+        let tip = &blocks[blocks.len() - 1].0;
+        let chain_state = chain_state::restore_chain_state(&storage, &genesis_data, tip).unwrap();
+        assert_eq!(&chain_state.last_block, tip);
+        assert_eq!(chain_state.chain_length, blocks.len() as u64);
+    }
+
+    #[test]
+    fn compact_keeps_the_tip_loose() {
+        let mut storage = TempStorage::new();
+        let genesis_data = genesis_data();
+        let blocks = chain(&genesis_data.genesis_prev, &epoch_dates(0, 2, 3));
+        write_blocks(&storage, &blocks);
+
+        // the whole chain is within the depth
+        assert_eq!(
+            compact_at(&mut storage, &genesis_data, 10),
+            CompactReport::default()
+        );
+        // the first epoch is stable, but not the boundary block following
+        // it: more blocks of the epoch may still be received
+        assert_eq!(
+            compact_at(&mut storage, &genesis_data, 4),
+            CompactReport::default()
+        );
+        assert_eq!(loose(&storage, &blocks), 8);
+
+        assert_eq!(
+            compact_at(&mut storage, &genesis_data, 3),
+            CompactReport {
+                packed_epochs: vec![0],
+                removed_blobs: 4,
+            }
+        );
+        assert!(!epoch::epoch_exists(&storage.config, 1).unwrap());
+        assert_eq!(loose(&storage, &blocks[4..]), 4);
+    }
+
+    #[test]
+    fn compact_requires_the_previous_epoch() {
+        let mut storage = TempStorage::new();
+        let genesis_data = genesis_data();
+        let blocks = chain(&genesis_data.genesis_prev, &epoch_dates(0, 4, 3));
+        // the blocks of the first epoch are neither loose nor packed
+        write_blocks(&storage, &blocks[4..]);
+
+        assert_eq!(
+            compact_at(&mut storage, &genesis_data, 0),
+            CompactReport::default()
+        );
+        for epoch_id in 0..4 {
+            assert!(!epoch::epoch_exists(&storage.config, epoch_id).unwrap());
+        }
+        assert_eq!(loose(&storage, &blocks), 12);
+    }
+
+    #[test]
+    fn compact_after_interrupted_pack() {
+        let mut storage = TempStorage::new();
+        let genesis_data = genesis_data();
+        let blocks = chain(&genesis_data.genesis_prev, &epoch_dates(0, 3, 3));
+        write_blocks(&storage, &blocks);
+
+        // the first epoch was packed, but the compaction was interrupted
+        // before its loose blocks were removed
+        let first_epoch: Vec<_> = blocks[..4]
+            .iter()
+            .map(|(hash, block)| (hash.clone(), block.header().blockdate()))
+            .collect();
+        assert_eq!(
+            pack_epoch(&mut storage, &genesis_data, 0, &first_epoch).unwrap(),
+            4
+        );
+        for (hash, block) in blocks[..4].iter() {
+            blob::write(&storage, &header_to_blockhash(hash), &raw(block)).unwrap();
+        }
+        storage.reopen();
+
+        assert_eq!(
+            compact_at(&mut storage, &genesis_data, 0),
+            CompactReport {
+                packed_epochs: vec![1],
+                removed_blobs: 8,
+            }
+        );
+        assert_eq!(loose(&storage, &blocks[..8]), 0);
+        assert_eq!(loose(&storage, &blocks), 4);
+
+        // running it again does nothing
+        assert_eq!(remove_packed_blobs(&storage).unwrap(), 0);
+        storage.reopen();
+        assert_eq!(
+            compact_at(&mut storage, &genesis_data, 0),
+            CompactReport::default()
+        );
+        for (hash, _) in blocks.iter() {
+            assert!(storage.block_exists(&header_to_blockhash(hash)).unwrap());
+        }
+        let tip = &blocks[blocks.len() - 1].0;
+        chain_state::restore_chain_state(&storage, &genesis_data, tip).unwrap();
+    }
+}
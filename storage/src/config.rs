@@ -58,6 +58,23 @@ impl StorageConfig {
         p.push(s.as_ref());
         p
     }
+    pub fn get_tag_journal_dir(&self) -> PathBuf {
+        let mut p = self.get_path();
+        p.push("journal/");
+        p
+    }
+    /// the journal of the updates of the given tag, the tag names are hex
+    /// encoded as they may hold directory separators
+    pub fn get_tag_journal_filepath<P: AsRef<str>>(&self, s: P) -> PathBuf {
+        let mut p = self.get_tag_journal_dir();
+        p.push(hex::encode(s.as_ref().as_bytes()));
+        p
+    }
+    pub fn get_explorer_index_filepath(&self) -> PathBuf {
+        let mut p = self.get_path();
+        p.push("explorer.index");
+        p
+    }
     pub fn get_refpack_filepath<S: AsRef<str>>(&self, name: S) -> PathBuf {
         let mut p = self.get_filetype_dir(StorageFileType::RefPack);
         p.push(name.as_ref());
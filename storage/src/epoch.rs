@@ -1,18 +1,22 @@
-use cardano::block::{BlockDate, ChainState, EpochId};
+use cardano::block::{BlockDate, ChainState, EpochId, HeaderHash, RawBlock};
 use cardano::config::GenesisData;
 use cardano::util::hex;
+use chain_core::metrics::names;
 use chain_state;
 use std::fs;
 use std::io::Read;
 
 use super::{
-    header_to_blockhash, packreader_block_next, packreader_init, Error, PackHash, Result, Storage,
+    header_to_blockhash, packreader_header_next, packreader_init, Error, PackHash, Result, Storage,
     StorageConfig,
 };
+use storage_units::hash::HASH_SIZE;
 use storage_units::utils::error::StorageError;
+use storage_units::utils::magic;
+use storage_units::utils::serialize::Offset;
 use storage_units::utils::tmpfile;
 use storage_units::utils::tmpfile::TmpFile;
-use storage_units::{packfile, reffile};
+use storage_units::{indexfile, packfile, reffile};
 
 pub fn epoch_create_with_refpack(
     config: &StorageConfig,
@@ -45,11 +49,9 @@ pub fn epoch_create(
 
     let mut current_slotid = BlockDate::Boundary(epochid);
     let mut last_block = None;
-    while let Some(rblk) = packreader_block_next(&mut reader).unwrap() {
-        let blk = rblk.decode().unwrap();
-        let hdr = blk.header();
-        let hash = hdr.compute_hash();
-        let blockdate = hdr.blockdate();
+    while let Some(raw_hdr) = packreader_header_next(&mut reader).unwrap() {
+        let hash = raw_hdr.compute_hash();
+        let blockdate = raw_hdr.decode().unwrap().blockdate();
 
         while current_slotid != blockdate {
             rp.append_missing_hash();
@@ -88,6 +90,8 @@ pub fn epoch_create(
         assert_eq!(chain_state.last_block, last_block.unwrap());
         chain_state::write_chain_state(storage, genesis_data, chain_state).unwrap();
     }
+
+    storage.metrics().counter(names::STORAGE_EPOCHS_PACKED, 1);
 }
 
 pub fn epoch_read_pack(config: &StorageConfig, epochid: EpochId) -> Result<PackHash> {
@@ -162,3 +166,302 @@ pub fn epoch_exists(config: &StorageConfig, epochid: EpochId) -> Result<bool> {
         Err(err) => Err(err),
     }
 }
+
+/// The first corruption found in the files of an epoch by
+/// `verify_epoch_pack`.
+///
+/// The offsets are in bytes from the start of the file, magic header
+/// included.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EpochCorruption {
+    /// the block at this offset of the pack cannot be read or decoded
+    UnreadableBlock(Offset),
+    /// the block at this offset of the pack is not in the epoch, or is not
+    /// after the previous block of the pack
+    UnexpectedDate(Offset, BlockDate),
+    /// the block at this offset of the pack does not follow the previous
+    /// block of the pack: its parent is not the expected hash
+    BrokenChain(Offset, HeaderHash, HeaderHash),
+    /// the name of the pack is not the hash of its blocks (second)
+    PackHashMismatch(PackHash, PackHash),
+    /// the index of the pack differs from the index of its blocks from
+    /// this offset
+    IndexMismatch(Offset),
+    /// the refpack of the epoch differs from the blocks of the pack from
+    /// this offset
+    RefPackMismatch(Offset),
+}
+
+fn first_difference(expected: &[u8], got: &[u8]) -> Option<Offset> {
+    expected
+        .iter()
+        .zip(got.iter())
+        .position(|(e, g)| e != g)
+        .or_else(|| {
+            if expected.len() == got.len() {
+                None
+            } else {
+                Some(expected.len().min(got.len()))
+            }
+        })
+        .map(|ofs| ofs as Offset)
+}
+
+/// Verify the pack of the epoch and its index and refpack, as written by
+/// `epoch_create`, to detect the corruption of the files on disk.
+///
+/// The blocks of the pack are read in order: each block must be decodable,
+/// be in the epoch after the previous block, and have the previous block
+/// as parent. The hash of the pack, its index and the refpack of the
+/// epoch are then recomputed from the blocks and compared with the files.
+///
+/// Returns the first corruption found, or `None` if the epoch is sound.
+/// The parent of the first block of the pack is not verified, it is in
+/// the previous epoch.
+pub fn verify_epoch_pack(
+    config: &StorageConfig,
+    epochid: EpochId,
+) -> Result<Option<EpochCorruption>> {
+    let packref = epoch_read_pack(config, epochid)?;
+    let pack_filepath = config.get_pack_filepath(&packref);
+    let pack_len = fs::metadata(&pack_filepath)?.len();
+    let mut reader = packfile::Reader::open(&pack_filepath)?;
+
+    let mut index = indexfile::Index::new();
+    let mut refpack = reffile::Lookup::new();
+    let mut next_date = BlockDate::Boundary(epochid);
+    let mut previous: Option<HeaderHash> = None;
+    loop {
+        let offset = magic::HEADER_SIZE as Offset + reader.pos();
+        let blk = match reader.next_block() {
+            // a block truncated by the end of the file is read as the end
+            // of the pack
+            Ok(None) if offset < pack_len => {
+                return Ok(Some(EpochCorruption::UnreadableBlock(offset)))
+            }
+            Ok(None) => break,
+            Ok(Some(data)) => match RawBlock(data).decode() {
+                Ok(blk) => blk,
+                Err(_) => return Ok(Some(EpochCorruption::UnreadableBlock(offset))),
+            },
+            Err(_) => return Ok(Some(EpochCorruption::UnreadableBlock(offset))),
+        };
+        let hdr = blk.header();
+        let hash = hdr.compute_hash();
+        let blockdate = hdr.blockdate();
+
+        if blockdate.get_epochid() != epochid || blockdate < next_date {
+            return Ok(Some(EpochCorruption::UnexpectedDate(offset, blockdate)));
+        }
+        if let Some(previous) = previous {
+            let parent = hdr.previous_header();
+            if parent != previous {
+                return Ok(Some(EpochCorruption::BrokenChain(offset, previous, parent)));
+            }
+        }
+
+        while next_date != blockdate {
+            refpack.append_missing_hash();
+            next_date = next_date.next();
+        }
+        refpack.append_hash(header_to_blockhash(&hash));
+        next_date = next_date.next();
+        index.append(&header_to_blockhash(&hash), offset);
+
+        previous = Some(hash);
+    }
+
+    let got = reader.finalize();
+    if got != packref {
+        return Ok(Some(EpochCorruption::PackHashMismatch(packref, got)));
+    }
+
+    let mut expected = Vec::new();
+    index.write(&mut expected)?;
+    let got = fs::read(config.get_index_filepath(&packref))?;
+    if let Some(offset) = first_difference(&expected, &got) {
+        return Ok(Some(EpochCorruption::IndexMismatch(offset)));
+    }
+
+    // the refpack may have been received with the pack, with the missing
+    // slots at the end of the epoch
+    let mut expected = Vec::new();
+    refpack.write(&mut expected)?;
+    let mut got = fs::read(config.get_epoch_refpack_filepath(epochid))?;
+    while got.len() >= expected.len() + HASH_SIZE
+        && got[got.len() - HASH_SIZE..].iter().all(|b| *b == 0)
+    {
+        let len = got.len() - HASH_SIZE;
+        got.truncate(len);
+    }
+    if let Some(offset) = first_difference(&expected, &got) {
+        return Ok(Some(EpochCorruption::RefPackMismatch(offset)));
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cardano::block::{Block, HeaderHash, RawBlock};
+    use compact::{compact, CompactParameters};
+    use std::fs::{self, OpenOptions};
+    use std::path::{Path, PathBuf};
+    use storage_units::utils::magic;
+    use storage_units::utils::serialize::Offset;
+    use test::{chain, epoch_dates, genesis_data, raw, write_blocks, TempStorage};
+    use StorageConfig;
+
+    /// a storage of 3 epochs of 4 blocks, the first 2 epochs are packed,
+    /// with the blocks of the second epoch and their offsets in its pack
+    fn storage() -> (TempStorage, Vec<(HeaderHash, Block)>, Vec<Offset>) {
+        let mut storage = TempStorage::new();
+        let genesis_data = genesis_data();
+        let blocks = chain(&genesis_data.genesis_prev, &epoch_dates(0, 3, 3));
+        write_blocks(&storage, &blocks);
+        let report = compact(&mut storage, &genesis_data, &CompactParameters { depth: 0 }).unwrap();
+        assert_eq!(report.packed_epochs, vec![0, 1]);
+
+        let blocks = blocks[4..8].to_vec();
+        let mut offset = magic::HEADER_SIZE as Offset;
+        let offsets = blocks
+            .iter()
+            .map(|(_, block)| {
+                let block_offset = offset;
+                let len = raw(block).len() as Offset;
+                offset += 4 + (len + 3) / 4 * 4;
+                block_offset
+            })
+            .collect();
+        (storage, blocks, offsets)
+    }
+
+    fn pack_filepath(config: &StorageConfig) -> PathBuf {
+        config.get_pack_filepath(&epoch_read_pack(config, 1).unwrap())
+    }
+
+    /// replace the first occurrence of `from` in the file, after `offset`
+    fn patch(path: &Path, offset: Offset, from: &[u8], to: &[u8]) {
+        let mut bytes = fs::read(path).unwrap();
+        let start = offset as usize
+            + bytes[offset as usize..]
+                .windows(from.len())
+                .position(|window| window == from)
+                .unwrap();
+        bytes[start..start + to.len()].copy_from_slice(to);
+        fs::write(path, bytes).unwrap();
+    }
+
+    #[test]
+    fn verify_sound_epochs() {
+        let (storage, _, _) = storage();
+        assert_eq!(verify_epoch_pack(&storage.config, 0).unwrap(), None);
+        assert_eq!(verify_epoch_pack(&storage.config, 1).unwrap(), None);
+    }
+
+    #[test]
+    fn verify_unreadable_block() {
+        let (storage, _, offsets) = storage();
+        let path = pack_filepath(&storage.config);
+        // the first byte of the third block is not a CBOR item
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[offsets[2] as usize + 4] = 0xff;
+        fs::write(&path, bytes).unwrap();
+
+        assert_eq!(
+            verify_epoch_pack(&storage.config, 1).unwrap(),
+            Some(EpochCorruption::UnreadableBlock(offsets[2]))
+        );
+    }
+
+    #[test]
+    fn verify_modified_block() {
+        let (storage, blocks, offsets) = storage();
+        let path = pack_filepath(&storage.config);
+        // the second block is still valid CBOR, but is not the parent of
+        // the third block anymore
+        patch(&path, offsets[1], b"cardano-sl", b"cardano-xx");
+        let start = offsets[1] as usize + 4;
+        let len = raw(&blocks[1].1).len();
+        let modified = RawBlock(fs::read(&path).unwrap()[start..start + len].to_vec());
+        let modified = modified.decode().unwrap().header().compute_hash();
+
+        assert_eq!(
+            verify_epoch_pack(&storage.config, 1).unwrap(),
+            Some(EpochCorruption::BrokenChain(
+                offsets[2],
+                modified,
+                blocks[1].0.clone()
+            ))
+        );
+    }
+
+    #[test]
+    fn verify_modified_last_block() {
+        let (storage, _, offsets) = storage();
+        let path = pack_filepath(&storage.config);
+        patch(&path, offsets[3], b"cardano-sl", b"cardano-xx");
+
+        match verify_epoch_pack(&storage.config, 1).unwrap() {
+            Some(EpochCorruption::PackHashMismatch(expected, got)) => {
+                assert_eq!(expected, epoch_read_pack(&storage.config, 1).unwrap());
+                assert!(expected != got);
+            }
+            corruption => panic!("unexpected corruption {:?}", corruption),
+        }
+    }
+
+    #[test]
+    fn verify_corrupted_index() {
+        let (storage, _, _) = storage();
+        let path = storage
+            .config
+            .get_index_filepath(&epoch_read_pack(&storage.config, 1).unwrap());
+        // the last byte of the index is in the offset of a block
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0x01;
+        fs::write(&path, bytes).unwrap();
+
+        assert_eq!(
+            verify_epoch_pack(&storage.config, 1).unwrap(),
+            Some(EpochCorruption::IndexMismatch(last as Offset))
+        );
+    }
+
+    #[test]
+    fn verify_truncated_pack() {
+        let (storage, _, offsets) = storage();
+        let path = pack_filepath(&storage.config);
+
+        // in the middle of the last block
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(offsets[3] + 10).unwrap();
+        assert_eq!(
+            verify_epoch_pack(&storage.config, 1).unwrap(),
+            Some(EpochCorruption::UnreadableBlock(offsets[3]))
+        );
+
+        // without the last block
+        file.set_len(offsets[3]).unwrap();
+        match verify_epoch_pack(&storage.config, 1).unwrap() {
+            Some(EpochCorruption::PackHashMismatch(_, _)) => {}
+            corruption => panic!("unexpected corruption {:?}", corruption),
+        }
+    }
+
+    #[test]
+    fn verify_truncated_refpack() {
+        let (storage, _, _) = storage();
+        let path = storage.config.get_epoch_refpack_filepath(1);
+        let len = fs::metadata(&path).unwrap().len();
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(len - 1).unwrap();
+
+        assert_eq!(
+            verify_epoch_pack(&storage.config, 1).unwrap(),
+            Some(EpochCorruption::RefPackMismatch(len - 1))
+        );
+    }
+}
@@ -0,0 +1,719 @@
+//! secondary indexes of the blockchain, for the explorers
+//!
+//! The indexes map:
+//!
+//! * an address to the transactions paying to it or spending from it;
+//! * a transaction to the block including it;
+//! * a stakeholder to the delegation and VSS certificates it issued.
+//!
+//! They are maintained incrementally as the blocks are appended to the
+//! storage: every block applied to the indexes is recorded, with what is
+//! needed to undo it, in an append only log (`explorer.index` at the root of
+//! the storage) and opening the indexes replays the log, so the queries
+//! never rescan the packs. The rollbacks of the chain are followed as long
+//! as they are not deeper than `MAX_ROLLBACK_DEPTH` blocks.
+//!
+//! The outputs of the genesis data are not indexed: the inputs spending them
+//! are not related to any address.
+//!
+
+use cardano::address::{Addr, StakeholderId};
+use cardano::block::{normal::DlgPayload, Block, EpochId, HeaderHash};
+use cardano::hdwallet::XPub;
+use cardano::tx::{TxId, TxoPointer};
+use cbor_event::{self, de, se, Len, Value};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+
+use storage_units::utils::magic;
+use storage_units::utils::serialize::{read_size, write_size, SIZE_SIZE};
+
+use super::{Error, Result, Storage};
+
+const FILE_TYPE: magic::FileType = 0x45585049; // = EXPI
+const VERSION: magic::Version = 1;
+
+/// number of the latest applied blocks which can be rolled back, the
+/// security parameter `k` of the mainnet
+pub const MAX_ROLLBACK_DEPTH: usize = 2160;
+
+/// a certificate indexed by the stakeholder issuing it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Certificate {
+    /// heavyweight delegation of the issuer's stake to `delegate`, starting
+    /// at the given epoch
+    Delegation {
+        epoch: EpochId,
+        delegate: StakeholderId,
+    },
+    /// VSS certificate of the issuer, valid until the given epoch
+    Vss { expiry_epoch: EpochId },
+}
+
+/// a certificate and the block including it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CertificateRef {
+    pub block: HeaderHash,
+    pub certificate: Certificate,
+}
+
+/// an indexed transaction: the addresses of its outputs and the indexed
+/// outputs it spends
+struct TxEntry {
+    id: TxId,
+    outputs: Vec<Addr>,
+    spent: Vec<(TxoPointer, Addr)>,
+}
+
+impl TxEntry {
+    /// the addresses the transaction is related to, without duplicates
+    fn addresses(&self) -> BTreeSet<&Addr> {
+        self.outputs
+            .iter()
+            .chain(self.spent.iter().map(|(_, addr)| addr))
+            .collect()
+    }
+}
+
+/// the changes of the indexes brought by a block
+struct BlockEntry {
+    hash: HeaderHash,
+    parent: HeaderHash,
+    transactions: Vec<TxEntry>,
+    certificates: Vec<(StakeholderId, Certificate)>,
+}
+
+/// an entry of the log, see `apply_record` and `rollback_record`
+enum Record {
+    Apply(BlockEntry),
+    Rollback(HeaderHash),
+}
+
+pub struct Indexes {
+    tip: Option<HeaderHash>,
+    addresses: BTreeMap<Addr, Vec<TxId>>,
+    transactions: BTreeMap<TxId, HeaderHash>,
+    certificates: BTreeMap<StakeholderId, Vec<CertificateRef>>,
+    unspent: BTreeMap<TxoPointer, Addr>,
+    applied: VecDeque<BlockEntry>,
+    log: Option<fs::File>,
+}
+
+impl Indexes {
+    /// empty indexes, kept in memory only
+    pub fn new() -> Self {
+        Indexes {
+            tip: None,
+            addresses: BTreeMap::new(),
+            transactions: BTreeMap::new(),
+            certificates: BTreeMap::new(),
+            unspent: BTreeMap::new(),
+            applied: VecDeque::new(),
+            log: None,
+        }
+    }
+
+    /// open the indexes of the storage, replaying their log
+    ///
+    /// A record of the log interrupted by a crash is discarded.
+    pub fn open(storage: &Storage) -> Result<Self> {
+        let path = storage.config.get_explorer_index_filepath();
+        let mut indexes = Indexes::new();
+
+        if !path.exists() {
+            let mut file = fs::File::create(&path)?;
+            magic::write_header(&mut file, FILE_TYPE, VERSION)?;
+            file.sync_all()?;
+            indexes.log = Some(file);
+            return Ok(indexes);
+        }
+
+        let mut file = fs::OpenOptions::new().read(true).write(true).open(&path)?;
+        magic::check_header(&mut file, FILE_TYPE, VERSION, VERSION)?;
+        let mut offset = magic::HEADER_SIZE as u64;
+        {
+            let mut reader = io::BufReader::new(&mut file);
+            while let Some(bytes) = read_record_bytes(&mut reader)? {
+                let mut raw = de::Deserializer::from(io::Cursor::new(&bytes));
+                match Record::deserialize(&mut raw)? {
+                    Record::Apply(entry) => indexes.apply(entry),
+                    Record::Rollback(hash) => {
+                        if indexes.undo() != Some(hash) {
+                            return Err(cbor_event::Error::CustomError(
+                                "index log rolls back a block which is not the tip".to_owned(),
+                            )
+                            .into());
+                        }
+                    }
+                }
+                offset += (SIZE_SIZE + bytes.len()) as u64;
+            }
+        }
+        if file.metadata()?.len() != offset {
+            warn!("discarding the interrupted record of the explorer index log");
+            file.set_len(offset)?;
+        }
+        file.seek(SeekFrom::End(0))?;
+        indexes.log = Some(file);
+        Ok(indexes)
+    }
+
+    /// the last block applied to the indexes
+    pub fn tip(&self) -> Option<&HeaderHash> {
+        self.tip.as_ref()
+    }
+
+    /// the transactions paying to or spending from the address, in the
+    /// order of the chain
+    pub fn transactions_of(&self, address: &Addr) -> &[TxId] {
+        self.addresses
+            .get(address)
+            .map(|txs| txs.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// the block including the transaction
+    pub fn block_of(&self, txid: &TxId) -> Option<&HeaderHash> {
+        self.transactions.get(txid)
+    }
+
+    /// the certificates issued by the stakeholder, in the order of the chain
+    pub fn certificates_of(&self, stakeholder: &StakeholderId) -> &[CertificateRef] {
+        self.certificates
+            .get(stakeholder)
+            .map(|certificates| certificates.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// index the block, following the current tip
+    pub fn apply_block(&mut self, block: &Block) -> Result<()> {
+        let entry = self.block_entry(block);
+        if let Some(ref tip) = self.tip {
+            if &entry.parent != tip {
+                return Err(Error::IndexChainInvalid(tip.clone(), entry.parent));
+            }
+        }
+        self.write_record(&apply_record(&entry)?)?;
+        self.apply(entry);
+        Ok(())
+    }
+
+    /// remove the tip from the indexes, returning the new tip
+    pub fn rollback(&mut self) -> Result<Option<HeaderHash>> {
+        let hash = match (self.applied.back(), &self.tip) {
+            (Some(entry), _) => entry.hash.clone(),
+            (None, Some(tip)) => return Err(Error::IndexForkTooDeep(tip.clone())),
+            (None, None) => return Ok(None),
+        };
+        self.write_record(&rollback_record(&hash)?)?;
+        self.undo();
+        Ok(self.tip.clone())
+    }
+
+    /// update the indexes to the given tip of the storage, rolling back the
+    /// blocks of the current tip which are not in its chain
+    ///
+    /// The blocks are read backward from the given tip up to a block of the
+    /// indexes (or up to the beginning of the stored chain if the indexes are
+    /// empty) then applied forward.
+    pub fn update(&mut self, storage: &Storage, tip: &HeaderHash) -> Result<()> {
+        let mut blocks_to_apply = vec![];
+        let mut current = tip.clone();
+        loop {
+            if self.tip.as_ref() == Some(&current)
+                || self.applied.iter().any(|entry| entry.hash == current)
+            {
+                break;
+            }
+            match storage.read_block(current.as_hash_bytes()) {
+                Ok(raw_block) => {
+                    let parent = raw_block.decode()?.header().previous_header();
+                    blocks_to_apply.push(current);
+                    current = parent;
+                }
+                Err(Error::BlockNotFound(_)) if self.tip.is_none() => break,
+                Err(Error::BlockNotFound(_)) => {
+                    return Err(Error::IndexForkTooDeep(self.tip.clone().unwrap()))
+                }
+                Err(err) => return Err(err),
+            }
+        }
+
+        debug!(
+            "updating the explorer index to {}, {} blocks to apply",
+            tip,
+            blocks_to_apply.len()
+        );
+
+        if self.tip.is_some() {
+            while self.tip.as_ref() != Some(&current) {
+                self.rollback()?;
+            }
+        }
+        for hash in blocks_to_apply.iter().rev() {
+            let block = storage.read_block(hash.as_hash_bytes())?.decode()?;
+            self.apply_block(&block)?;
+        }
+        Ok(())
+    }
+
+    fn block_entry(&self, block: &Block) -> BlockEntry {
+        let header = block.header();
+        let mut entry = BlockEntry {
+            hash: header.compute_hash(),
+            parent: header.previous_header(),
+            transactions: Vec::new(),
+            certificates: Vec::new(),
+        };
+        let blk = match block {
+            Block::BoundaryBlock(_) => return entry,
+            Block::MainBlock(blk) => blk,
+        };
+
+        // the outputs created by the previous transactions of the block
+        let mut created: BTreeMap<TxoPointer, Addr> = BTreeMap::new();
+        for txaux in blk.body.tx.iter() {
+            let id = txaux.tx.id();
+            let spent = txaux
+                .tx
+                .inputs
+                .iter()
+                .filter_map(|ptr| {
+                    created
+                        .remove(ptr)
+                        .or_else(|| self.unspent.get(ptr).cloned())
+                        .map(|addr| (ptr.clone(), addr))
+                })
+                .collect();
+            let outputs: Vec<Addr> = txaux
+                .tx
+                .outputs
+                .iter()
+                .map(|output| Addr::from(output.address.clone()))
+                .collect();
+            for (index, addr) in outputs.iter().enumerate() {
+                created.insert(TxoPointer::new(id, index as u32), addr.clone());
+            }
+            entry.transactions.push(TxEntry { id, outputs, spent });
+        }
+
+        for vss in blk.body.ssc.get_vss_certificates().iter() {
+            entry.certificates.push((
+                StakeholderId::new(&vss.signing_key),
+                Certificate::Vss {
+                    expiry_epoch: vss.expiry_epoch,
+                },
+            ));
+        }
+        entry
+            .certificates
+            .extend(delegation_certificates(&blk.body.delegation));
+        entry
+    }
+
+    fn apply(&mut self, entry: BlockEntry) {
+        for tx in entry.transactions.iter() {
+            for (ptr, _) in tx.spent.iter() {
+                self.unspent.remove(ptr);
+            }
+            for (index, addr) in tx.outputs.iter().enumerate() {
+                self.unspent
+                    .insert(TxoPointer::new(tx.id, index as u32), addr.clone());
+            }
+            self.transactions.insert(tx.id, entry.hash.clone());
+            for addr in tx.addresses() {
+                self.addresses
+                    .entry(addr.clone())
+                    .or_insert_with(Vec::new)
+                    .push(tx.id);
+            }
+        }
+        for (stakeholder, certificate) in entry.certificates.iter() {
+            self.certificates
+                .entry(*stakeholder)
+                .or_insert_with(Vec::new)
+                .push(CertificateRef {
+                    block: entry.hash.clone(),
+                    certificate: certificate.clone(),
+                });
+        }
+
+        self.tip = Some(entry.hash.clone());
+        self.applied.push_back(entry);
+        if self.applied.len() > MAX_ROLLBACK_DEPTH {
+            self.applied.pop_front();
+        }
+    }
+
+    /// undo the last applied block, returning its hash
+    fn undo(&mut self) -> Option<HeaderHash> {
+        let entry = self.applied.pop_back()?;
+        for (stakeholder, _) in entry.certificates.iter().rev() {
+            pop_value(&mut self.certificates, stakeholder);
+        }
+        for tx in entry.transactions.iter().rev() {
+            for addr in tx.addresses() {
+                pop_value(&mut self.addresses, addr);
+            }
+            self.transactions.remove(&tx.id);
+            for index in 0..tx.outputs.len() {
+                self.unspent.remove(&TxoPointer::new(tx.id, index as u32));
+            }
+            for (ptr, addr) in tx.spent.iter() {
+                self.unspent.insert(ptr.clone(), addr.clone());
+            }
+        }
+        self.tip = Some(entry.parent);
+        Some(entry.hash)
+    }
+
+    /// append the record to the log, if any, flushed to the disk
+    fn write_record(&mut self, bytes: &[u8]) -> Result<()> {
+        let file = match self.log {
+            None => return Ok(()),
+            Some(ref mut file) => file,
+        };
+        let mut len = [0u8; SIZE_SIZE];
+        write_size(&mut len, bytes.len() as u32);
+        file.write_all(&len)?;
+        file.write_all(bytes)?;
+        file.sync_data()?;
+        Ok(())
+    }
+}
+
+/// remove the last value of the key, and the key once it has no value
+fn pop_value<K: Ord, V>(map: &mut BTreeMap<K, Vec<V>>, key: &K) {
+    let empty = match map.get_mut(key) {
+        None => return,
+        Some(values) => {
+            values.pop();
+            values.is_empty()
+        }
+    };
+    if empty {
+        map.remove(key);
+    }
+}
+
+/// the heavyweight delegation certificates of the payload, each of them
+/// being `[epoch, issuer public key, delegate public key, signature]`. The
+/// malformed entries are ignored.
+fn delegation_certificates(payload: &DlgPayload) -> Vec<(StakeholderId, Certificate)> {
+    let items = match payload.0 {
+        Value::Array(ref items) | Value::IArray(ref items) => items,
+        _ => return Vec::new(),
+    };
+    items
+        .iter()
+        .filter_map(|item| {
+            let fields = match *item {
+                Value::Array(ref fields) | Value::IArray(ref fields) if fields.len() == 4 => fields,
+                _ => return None,
+            };
+            match (&fields[0], &fields[1], &fields[2]) {
+                (Value::U64(epoch), Value::Bytes(issuer), Value::Bytes(delegate)) => {
+                    let issuer = XPub::from_slice(issuer).ok()?;
+                    let delegate = XPub::from_slice(delegate).ok()?;
+                    Some((
+                        StakeholderId::new(&issuer),
+                        Certificate::Delegation {
+                            epoch: *epoch,
+                            delegate: StakeholderId::new(&delegate),
+                        },
+                    ))
+                }
+                _ => None,
+            }
+        })
+        .collect()
+}
+
+/// the bytes of the next record of the log, `None` at the end of the log or
+/// if the last record is incomplete
+fn read_record_bytes<R: Read>(reader: &mut R) -> Result<Option<Vec<u8>>> {
+    let mut len = [0u8; SIZE_SIZE];
+    let mut bytes = Vec::new();
+    if reader
+        .by_ref()
+        .take(SIZE_SIZE as u64)
+        .read_to_end(&mut bytes)?
+        < SIZE_SIZE
+    {
+        return Ok(None);
+    }
+    len.copy_from_slice(&bytes);
+    let len = read_size(&len) as usize;
+
+    bytes.clear();
+    if reader.by_ref().take(len as u64).read_to_end(&mut bytes)? < len {
+        return Ok(None);
+    }
+    Ok(Some(bytes))
+}
+
+impl cbor_event::se::Serialize for TxEntry {
+    fn serialize<'se, W: Write>(
+        &self,
+        serializer: &'se mut se::Serializer<W>,
+    ) -> cbor_event::Result<&'se mut se::Serializer<W>> {
+        serializer.write_array(Len::Len(3))?.serialize(&self.id)?;
+        se::serialize_fixed_array(self.outputs.iter(), serializer)?;
+        serializer.write_array(Len::Len(self.spent.len() as u64))?;
+        for (ptr, addr) in self.spent.iter() {
+            serializer
+                .write_array(Len::Len(2))?
+                .serialize(ptr)?
+                .serialize(addr)?;
+        }
+        Ok(serializer)
+    }
+}
+
+impl TxEntry {
+    fn deserialize<R: io::BufRead>(raw: &mut de::Deserializer<R>) -> cbor_event::Result<Self> {
+        raw.tuple(3, "index transaction")?;
+        let id = raw.deserialize()?;
+        let outputs = raw.deserialize()?;
+        let spent = match raw.array()? {
+            Len::Len(len) => (0..len)
+                .map(|_| -> cbor_event::Result<(TxoPointer, Addr)> {
+                    raw.tuple(2, "index spent output")?;
+                    Ok((raw.deserialize()?, raw.deserialize()?))
+                })
+                .collect::<cbor_event::Result<Vec<_>>>()?,
+            Len::Indefinite => return Err(indefinite_array()),
+        };
+        Ok(TxEntry { id, outputs, spent })
+    }
+}
+
+fn serialize_certificate<'se, W: Write>(
+    serializer: &'se mut se::Serializer<W>,
+    stakeholder: &StakeholderId,
+    certificate: &Certificate,
+) -> cbor_event::Result<&'se mut se::Serializer<W>> {
+    match certificate {
+        Certificate::Delegation { epoch, delegate } => serializer
+            .write_array(Len::Len(4))?
+            .serialize(stakeholder)?
+            .write_unsigned_integer(0)?
+            .write_unsigned_integer(*epoch)?
+            .serialize(delegate),
+        Certificate::Vss { expiry_epoch } => serializer
+            .write_array(Len::Len(3))?
+            .serialize(stakeholder)?
+            .write_unsigned_integer(1)?
+            .write_unsigned_integer(*expiry_epoch),
+    }
+}
+
+fn deserialize_certificate<R: io::BufRead>(
+    raw: &mut de::Deserializer<R>,
+) -> cbor_event::Result<(StakeholderId, Certificate)> {
+    let len = raw.array()?;
+    let stakeholder = raw.deserialize()?;
+    let certificate = match (len, raw.unsigned_integer()?) {
+        (Len::Len(4), 0) => Certificate::Delegation {
+            epoch: raw.unsigned_integer()?,
+            delegate: raw.deserialize()?,
+        },
+        (Len::Len(3), 1) => Certificate::Vss {
+            expiry_epoch: raw.unsigned_integer()?,
+        },
+        (_, kind) => {
+            return Err(cbor_event::Error::CustomError(format!(
+                "invalid index certificate of kind {}",
+                kind
+            )));
+        }
+    };
+    Ok((stakeholder, certificate))
+}
+
+fn apply_record(entry: &BlockEntry) -> cbor_event::Result<Vec<u8>> {
+    let mut serializer = se::Serializer::new_vec();
+    serializer
+        .write_array(Len::Len(5))?
+        .write_unsigned_integer(0)?
+        .serialize(&entry.hash)?
+        .serialize(&entry.parent)?;
+    se::serialize_fixed_array(entry.transactions.iter(), &mut serializer)?;
+    serializer.write_array(Len::Len(entry.certificates.len() as u64))?;
+    for (stakeholder, certificate) in entry.certificates.iter() {
+        serialize_certificate(&mut serializer, stakeholder, certificate)?;
+    }
+    Ok(serializer.finalize())
+}
+
+fn rollback_record(hash: &HeaderHash) -> cbor_event::Result<Vec<u8>> {
+    let mut serializer = se::Serializer::new_vec();
+    serializer
+        .write_array(Len::Len(2))?
+        .write_unsigned_integer(1)?
+        .serialize(hash)?;
+    Ok(serializer.finalize())
+}
+
+impl Record {
+    fn deserialize<R: io::BufRead>(raw: &mut de::Deserializer<R>) -> cbor_event::Result<Self> {
+        let len = raw.array()?;
+        match (len, raw.unsigned_integer()?) {
+            (Len::Len(2), 1) => Ok(Record::Rollback(raw.deserialize()?)),
+            (Len::Len(5), 0) => {
+                let hash = raw.deserialize()?;
+                let parent = raw.deserialize()?;
+                let transactions = match raw.array()? {
+                    Len::Len(len) => (0..len)
+                        .map(|_| TxEntry::deserialize(raw))
+                        .collect::<cbor_event::Result<Vec<_>>>()?,
+                    Len::Indefinite => return Err(indefinite_array()),
+                };
+                let certificates = match raw.array()? {
+                    Len::Len(len) => (0..len)
+                        .map(|_| deserialize_certificate(raw))
+                        .collect::<cbor_event::Result<Vec<_>>>()?,
+                    Len::Indefinite => return Err(indefinite_array()),
+                };
+                Ok(Record::Apply(BlockEntry {
+                    hash,
+                    parent,
+                    transactions,
+                    certificates,
+                }))
+            }
+            (_, kind) => Err(cbor_event::Error::CustomError(format!(
+                "invalid index log record of kind {}",
+                kind
+            ))),
+        }
+    }
+}
+
+fn indefinite_array() -> cbor_event::Error {
+    cbor_event::Error::CustomError("unexpected indefinite array in the index log".to_owned())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cardano::address::{Addr, ExtendedAddr};
+    use cardano::block::{BlockDate, EpochSlotId};
+    use cardano::coin::Coin;
+    use cardano::config::NetworkMagic;
+    use cardano::hash::Blake2b256;
+    use cardano::hdwallet::{XPrv, XPRV_SIZE};
+    use cardano::tx::{Tx, TxAux, TxInWitness, TxOut, TxWitness, TxoPointer};
+    use test::{chain, genesis_data, main_block, write_blocks, TempStorage};
+    use Error;
+
+    fn address(seed: u8) -> Addr {
+        let xpub = XPrv::normalize_bytes([seed; XPRV_SIZE]).public();
+        Addr::from(ExtendedAddr::new_simple(xpub, NetworkMagic::NoMagic))
+    }
+
+    fn transaction(inputs: Vec<TxoPointer>, outputs: &[&Addr]) -> TxAux {
+        let witness: Vec<_> = inputs.iter().map(|_| TxInWitness::fake()).collect();
+        let outputs = outputs
+            .iter()
+            .map(|addr| TxOut::new(addr.deconstruct(), Coin::new(1).unwrap()))
+            .collect();
+        TxAux::new(Tx::new_with(inputs, outputs), TxWitness::from(witness))
+    }
+
+    fn slot(slotid: u16) -> EpochSlotId {
+        EpochSlotId { epoch: 0, slotid }
+    }
+
+    #[test]
+    fn index_rollback_and_reindex() {
+        let storage = TempStorage::new();
+        let (a, b, c) = (address(1), address(2), address(3));
+
+        // tx1 pays A and B from a genesis output, tx2 pays C from A
+        let boundary = chain(&genesis_data().genesis_prev, &[BlockDate::Boundary(0)]);
+        let b0 = boundary[0].0.clone();
+        let tx1 = transaction(
+            vec![TxoPointer::new(Blake2b256::new(b"genesis"), 0)],
+            &[&a, &b],
+        );
+        let id1 = tx1.tx.id();
+        let m1 = main_block(&b0, slot(0), vec![tx1]);
+        let h1 = m1.header().compute_hash();
+        let tx2 = transaction(vec![TxoPointer::new(id1, 0)], &[&c]);
+        let id2 = tx2.tx.id();
+        let m2 = main_block(&h1, slot(1), vec![tx2]);
+        let h2 = m2.header().compute_hash();
+        write_blocks(
+            &storage,
+            &[
+                boundary[0].clone(),
+                (h1.clone(), m1),
+                (h2.clone(), m2.clone()),
+            ],
+        );
+
+        let mut indexes = Indexes::open(&storage).unwrap();
+        indexes.update(&storage, &h2).unwrap();
+        assert_eq!(indexes.tip(), Some(&h2));
+        assert_eq!(indexes.transactions_of(&a), &[id1, id2]);
+        assert_eq!(indexes.transactions_of(&b), &[id1]);
+        assert_eq!(indexes.transactions_of(&c), &[id2]);
+        assert_eq!(indexes.block_of(&id1), Some(&h1));
+        assert_eq!(indexes.block_of(&id2), Some(&h2));
+
+        // a fork from the first main block: tx3 pays C from B, then tx4
+        // pays B from A
+        let tx3 = transaction(vec![TxoPointer::new(id1, 1)], &[&c]);
+        let id3 = tx3.tx.id();
+        let m2_fork = main_block(&h1, slot(1), vec![tx3]);
+        let h2_fork = m2_fork.header().compute_hash();
+        let tx4 = transaction(vec![TxoPointer::new(id1, 0)], &[&b]);
+        let id4 = tx4.tx.id();
+        let m3_fork = main_block(&h2_fork, slot(2), vec![tx4]);
+        let h3_fork = m3_fork.header().compute_hash();
+        write_blocks(
+            &storage,
+            &[
+                (h2_fork.clone(), m2_fork),
+                (h3_fork.clone(), m3_fork.clone()),
+            ],
+        );
+
+        indexes.update(&storage, &h3_fork).unwrap();
+        let check_fork = |indexes: &Indexes| {
+            assert_eq!(indexes.tip(), Some(&h3_fork));
+            assert_eq!(indexes.transactions_of(&a), &[id1, id4]);
+            assert_eq!(indexes.transactions_of(&b), &[id1, id3, id4]);
+            assert_eq!(indexes.transactions_of(&c), &[id3]);
+            assert_eq!(indexes.block_of(&id2), None);
+            assert_eq!(indexes.block_of(&id3), Some(&h2_fork));
+            assert_eq!(indexes.block_of(&id4), Some(&h3_fork));
+        };
+        check_fork(&indexes);
+
+        // the log replays the rollback
+        drop(indexes);
+        let mut indexes = Indexes::open(&storage).unwrap();
+        check_fork(&indexes);
+
+        // roll back the tip and index it again
+        assert_eq!(indexes.rollback().unwrap(), Some(h2_fork.clone()));
+        assert_eq!(indexes.transactions_of(&a), &[id1]);
+        assert_eq!(indexes.transactions_of(&b), &[id1, id3]);
+        assert_eq!(indexes.block_of(&id4), None);
+        match indexes.apply_block(&m2) {
+            Err(Error::IndexChainInvalid(ref tip, ref parent)) => {
+                assert_eq!(tip, &h2_fork);
+                assert_eq!(parent, &h1);
+            }
+            res => panic!("unexpected result {:?}", res.map(|_| ())),
+        }
+        indexes.apply_block(&m3_fork).unwrap();
+        check_fork(&indexes);
+
+        drop(indexes);
+        check_fork(&Indexes::open(&storage).unwrap());
+    }
+}
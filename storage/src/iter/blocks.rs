@@ -0,0 +1,349 @@
+//! iterator over the blocks between two bounds, in either direction
+//!
+//! The blocks of the packed epochs are listed with the epoch refpacks and
+//! read through the pack indexes, one epoch at a time. Only the loose blocks
+//! of the range (the ones which are not packed in an epoch yet) are listed
+//! upfront, by walking the chain back from the end of the range.
+
+use std::collections::VecDeque;
+
+use cardano::block::{Block, BlockDate, EpochId, HeaderHash, RawBlock};
+use storage_units::hash::BlockHash;
+
+use super::super::{blob, epoch, header_to_blockhash, tag, Error, Result, Storage};
+
+/// bound of a range of blocks, included in the range
+#[derive(Debug, Clone)]
+pub enum Bound {
+    /// the block of the given hash
+    Hash(HeaderHash),
+    /// the given epoch/slot
+    Date(BlockDate),
+}
+
+/// the order in which the blocks of a range are iterated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// from the oldest block to the newest
+    Forward,
+    /// from the newest block to the oldest
+    Backward,
+}
+
+/// iterator over the blocks between two bounds
+pub struct Blocks<'a> {
+    storage: &'a Storage,
+    from: BlockDate,
+    to: BlockDate,
+    /// the epochs left to visit, in the iteration order
+    epochs: VecDeque<EpochId>,
+    /// the loose blocks of the range, from the oldest to the newest
+    loose: VecDeque<(HeaderHash, BlockDate)>,
+    /// the blocks left in the epoch being visited, in the iteration order
+    current: VecDeque<BlockHash>,
+    direction: Direction,
+}
+
+fn bound_date(storage: &Storage, bound: &Bound) -> Result<BlockDate> {
+    match bound {
+        Bound::Hash(hh) => Ok(storage
+            .read_block(hh.as_hash_bytes())?
+            .decode()?
+            .header()
+            .blockdate()),
+        Bound::Date(date) => Ok(date.clone()),
+    }
+}
+
+/// the loose blocks of the chain between the two dates, from the oldest to
+/// the newest, walking back from the given block
+fn loose_blocks(
+    storage: &Storage,
+    start: HeaderHash,
+    from: &BlockDate,
+    to: &BlockDate,
+) -> Result<VecDeque<(HeaderHash, BlockDate)>> {
+    let mut blocks = VecDeque::new();
+    let mut current = start;
+    loop {
+        let hash = header_to_blockhash(&current);
+        if !blob::exist(storage, &hash) {
+            break;
+        }
+        let block = blob::read(storage, &hash)?.decode()?;
+        let header = block.header();
+        let date = header.blockdate();
+        if &date < from || epoch::epoch_exists(&storage.config, date.get_epochid())? {
+            break;
+        }
+        if &date <= to {
+            blocks.push_front((current, date));
+        }
+        current = header.previous_header();
+    }
+    Ok(blocks)
+}
+
+/// whether the block is in the pack of the epoch
+fn epoch_contains(storage: &Storage, epoch_id: EpochId, hash: &BlockHash) -> Result<bool> {
+    let mut refpack = epoch::epoch_open_packref(&storage.config, epoch_id)?;
+    while let Some(packed) = refpack.next()? {
+        if &packed == hash {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+pub fn iter<'a>(
+    storage: &'a Storage,
+    from: Bound,
+    to: Bound,
+    direction: Direction,
+) -> Result<Blocks<'a>> {
+    let from_date = bound_date(storage, &from)?;
+    let to_date = bound_date(storage, &to)?;
+
+    let mut epochs = VecDeque::new();
+    let mut loose = VecDeque::new();
+    if from_date <= to_date {
+        epochs.extend(from_date.get_epochid()..=to_date.get_epochid());
+        if direction == Direction::Backward {
+            epochs = epochs.into_iter().rev().collect();
+        }
+
+        // the blocks after the last packed epoch are only found by walking
+        // back the chain, from the end of the range or from the `HEAD`
+        if !epoch::epoch_exists(&storage.config, to_date.get_epochid())? {
+            let start = match to {
+                Bound::Hash(hh) => Some(hh),
+                Bound::Date(_) => tag::read_hash(storage, &tag::HEAD),
+            };
+            if let Some(start) = start {
+                loose = loose_blocks(storage, start, &from_date, &to_date)?;
+            }
+        }
+
+        // the block starting the range must be in the chain of the range
+        if let Bound::Hash(hh) = from {
+            let hash = header_to_blockhash(&hh);
+            let found = if epoch::epoch_exists(&storage.config, from_date.get_epochid())? {
+                epoch_contains(storage, from_date.get_epochid(), &hash)?
+            } else {
+                loose.front().map_or(false, |(first, _)| first == &hh)
+            };
+            if !found {
+                return Err(Error::BlockNotFound(hash));
+            }
+        }
+    }
+
+    Ok(Blocks {
+        storage: storage,
+        from: from_date,
+        to: to_date,
+        epochs: epochs,
+        loose: loose,
+        current: VecDeque::new(),
+        direction: direction,
+    })
+}
+
+impl<'a> Blocks<'a> {
+    /// list the blocks of the epoch in `current`, in the iteration order
+    fn load_epoch(&mut self, epoch_id: EpochId) -> Result<()> {
+        let mut hashes = Vec::new();
+        if epoch::epoch_exists(&self.storage.config, epoch_id)? {
+            let mut refpack = epoch::epoch_open_packref(&self.storage.config, epoch_id)?;
+            while let Some(hash) = refpack.next()? {
+                hashes.push(hash);
+            }
+        } else {
+            while self
+                .loose
+                .front()
+                .map_or(false, |(_, date)| date.get_epochid() == epoch_id)
+            {
+                let (hh, _) = self.loose.pop_front().unwrap();
+                hashes.push(header_to_blockhash(&hh));
+            }
+        }
+        if self.direction == Direction::Backward {
+            hashes.reverse();
+        }
+        self.current = hashes.into();
+        Ok(())
+    }
+}
+
+impl<'a> Iterator for Blocks<'a> {
+    type Item = Result<(RawBlock, Block)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(hash) = self.current.pop_front() {
+                let raw_block = match self.storage.read_block(&hash) {
+                    Ok(raw_block) => raw_block,
+                    Err(err) => return Some(Err(err)),
+                };
+                let block = match raw_block.decode() {
+                    Ok(block) => block,
+                    Err(err) => return Some(Err(err.into())),
+                };
+                // only the first and last epochs have blocks out of the range
+                let date = block.header().blockdate();
+                if date < self.from || date > self.to {
+                    continue;
+                }
+                return Some(Ok((raw_block, block)));
+            }
+
+            let epoch_id = self.epochs.pop_front()?;
+            if let Err(err) = self.load_epoch(epoch_id) {
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use cardano::block::{BlockDate, EpochSlotId, HeaderHash};
+    use compact::{compact, CompactParameters};
+    use test::{chain, epoch_dates, genesis_data, main_block, raw, write_blocks, TempStorage};
+    use {blob, header_to_blockhash, Error, Storage};
+
+    /// a storage of 4 epochs of 4 blocks, the first 2 epochs are packed
+    fn storage() -> (TempStorage, Vec<HeaderHash>) {
+        let mut storage = TempStorage::new();
+        let genesis_data = genesis_data();
+        let blocks = chain(&genesis_data.genesis_prev, &epoch_dates(0, 4, 3));
+        write_blocks(&storage, &blocks);
+        let report = compact(&mut storage, &genesis_data, &CompactParameters { depth: 6 }).unwrap();
+        assert_eq!(report.packed_epochs, vec![0, 1]);
+        (storage, blocks.into_iter().map(|(hash, _)| hash).collect())
+    }
+
+    fn hashes(storage: &Storage, from: Bound, to: Bound, direction: Direction) -> Vec<HeaderHash> {
+        iter(storage, from, to, direction)
+            .unwrap()
+            .map(|res| res.unwrap().1.header().compute_hash())
+            .collect()
+    }
+
+    fn reversed(hashes: &[HeaderHash]) -> Vec<HeaderHash> {
+        hashes.iter().rev().cloned().collect()
+    }
+
+    #[test]
+    fn blocks_forward_and_backward() {
+        let (storage, blocks) = storage();
+        let from = Bound::Hash(blocks[0].clone());
+        let to = Bound::Hash(blocks[15].clone());
+
+        assert_eq!(
+            hashes(&storage, from.clone(), to.clone(), Direction::Forward),
+            blocks
+        );
+        assert_eq!(
+            hashes(&storage, from, to, Direction::Backward),
+            reversed(&blocks)
+        );
+
+        // the end of the range given as a date is found from the `HEAD`
+        let from = Bound::Date(BlockDate::Normal(EpochSlotId {
+            epoch: 1,
+            slotid: 1,
+        }));
+        let to = Bound::Date(BlockDate::Boundary(3));
+        assert_eq!(
+            hashes(&storage, from.clone(), to.clone(), Direction::Forward),
+            &blocks[6..13]
+        );
+        assert_eq!(
+            hashes(&storage, from, to, Direction::Backward),
+            reversed(&blocks[6..13])
+        );
+    }
+
+    #[test]
+    fn blocks_equal_bounds() {
+        let (storage, blocks) = storage();
+        // a packed block, the boundary block of a packed epoch and a loose
+        // block
+        for index in [5, 4, 10].iter() {
+            let bound = Bound::Hash(blocks[*index].clone());
+            for direction in [Direction::Forward, Direction::Backward].iter() {
+                assert_eq!(
+                    hashes(&storage, bound.clone(), bound.clone(), *direction),
+                    &blocks[*index..*index + 1]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn blocks_across_packed_and_loose_epochs() {
+        let (storage, blocks) = storage();
+        // from the last packed epoch to the first loose epoch
+        assert!(blob::exist(&storage, &header_to_blockhash(&blocks[8])));
+        assert!(!blob::exist(&storage, &header_to_blockhash(&blocks[7])));
+        let from = Bound::Hash(blocks[5].clone());
+        let to = Bound::Hash(blocks[10].clone());
+
+        assert_eq!(
+            hashes(&storage, from.clone(), to.clone(), Direction::Forward),
+            &blocks[5..11]
+        );
+        assert_eq!(
+            hashes(&storage, from, to, Direction::Backward),
+            reversed(&blocks[5..11])
+        );
+    }
+
+    #[test]
+    fn blocks_from_another_chain() {
+        let (storage, blocks) = storage();
+        // a fork of the last epoch, leaving its first slot empty
+        let fork_block = main_block(
+            &blocks[12],
+            EpochSlotId {
+                epoch: 3,
+                slotid: 1,
+            },
+            vec![],
+        );
+        let fork_hash = fork_block.header().compute_hash();
+        blob::write(
+            &storage,
+            &header_to_blockhash(&fork_hash),
+            &raw(&fork_block),
+        )
+        .unwrap();
+
+        for direction in [Direction::Forward, Direction::Backward].iter() {
+            match iter(
+                &storage,
+                Bound::Hash(fork_hash.clone()),
+                Bound::Hash(blocks[15].clone()),
+                *direction,
+            ) {
+                Err(Error::BlockNotFound(hash)) => {
+                    assert_eq!(hash, header_to_blockhash(&fork_hash))
+                }
+                Err(err) => panic!("unexpected error {:?}", err),
+                Ok(_) => panic!("the fork is not in the chain of the range"),
+            }
+        }
+
+        // nor is the block of the first slot in the chain of the fork
+        assert!(iter(
+            &storage,
+            Bound::Hash(blocks[13].clone()),
+            Bound::Hash(fork_hash.clone()),
+            Direction::Forward,
+        )
+        .is_err());
+    }
+}
@@ -1,7 +1,16 @@
+mod blocks;
 pub mod epoch;
 mod range;
 mod reverse;
 
+pub use self::blocks::{Blocks, Bound, Direction};
+
+/// Constructs a `Blocks` iterator.
+///
+/// This function is meant for internal use.
+/// It's more convenient to use the `blocks` method of `Storage`.
+pub use self::blocks::iter as blocks_iter;
+
 pub use self::range::Range;
 
 /// Constructs a `Range` iterator.
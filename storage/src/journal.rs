@@ -0,0 +1,289 @@
+//! write-ahead journal of the tag updates
+//!
+//! A tag update is first recorded in the journal, with the previous value
+//! of the tag, then applied and the journal is cleared. If the storage is
+//! opened with a journal, the update was interrupted: it is replayed if the
+//! new value of the tag refers to data of the storage, otherwise it is
+//! rolled back, so a crash never leaves a tag (such as `HEAD`) pointing at a
+//! missing block.
+//!
+//! Each tag has its own journal file, so the updates of different tags do
+//! not overwrite each other's entry.
+
+use cbor_event::{self, de, se, Len};
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::Path;
+
+use storage_units::utils::magic;
+use storage_units::utils::tmpfile::{sync_dir, TmpFile};
+
+use super::{tag, Error, Result, Storage};
+use types::HASH_SIZE;
+
+const FILE_TYPE: magic::FileType = 0x4a524e4c; // = JRNL
+const VERSION: magic::Version = 1;
+
+/// an update of the tag `name` from `previous` to `next`, `None` being the
+/// absence of the tag
+struct Entry {
+    name: String,
+    previous: Option<Vec<u8>>,
+    next: Option<Vec<u8>>,
+}
+
+fn write_optional<W: Write>(
+    serializer: &mut se::Serializer<W>,
+    value: &Option<Vec<u8>>,
+) -> cbor_event::Result<()> {
+    match value {
+        None => {
+            serializer.write_array(Len::Len(0))?;
+        }
+        Some(bytes) => {
+            serializer.write_array(Len::Len(1))?.write_bytes(bytes)?;
+        }
+    }
+    Ok(())
+}
+
+fn read_optional<R: BufRead>(raw: &mut de::Deserializer<R>) -> cbor_event::Result<Option<Vec<u8>>> {
+    match raw.array()? {
+        Len::Len(0) => Ok(None),
+        Len::Len(1) => Ok(Some(raw.bytes()?)),
+        _ => Err(cbor_event::Error::CustomError(
+            "expected an optional value".to_owned(),
+        )),
+    }
+}
+
+/// record the update of the tag in the journal, before it is applied
+///
+/// The journal is written in a temporary file flushed to the disk before
+/// it is renamed, so it is either complete or absent.
+pub(crate) fn begin(
+    storage: &Storage,
+    name: &str,
+    previous: Option<Vec<u8>>,
+    next: Option<&[u8]>,
+) -> Result<()> {
+    let entry = Entry {
+        name: name.to_owned(),
+        previous: previous,
+        next: next.map(|next| next.to_vec()),
+    };
+
+    let dir = storage.config.get_tag_journal_dir();
+    fs::create_dir_all(&dir)?;
+    let mut tmpfile = TmpFile::create(dir.clone())?;
+    magic::write_header(&mut tmpfile, FILE_TYPE, VERSION)?;
+    {
+        let mut serializer = se::Serializer::new(&mut tmpfile);
+        serializer
+            .write_array(Len::Len(3))?
+            .write_text(&entry.name)?;
+        write_optional(&mut serializer, &entry.previous)?;
+        write_optional(&mut serializer, &entry.next)?;
+    }
+    tmpfile.sync()?;
+    tmpfile.render_permanent(&storage.config.get_tag_journal_filepath(name))?;
+    sync_dir(&dir)?;
+    Ok(())
+}
+
+/// clear the journal once the update of the tag is applied
+///
+/// A journal already cleared (e.g. by a concurrent update of the same tag)
+/// is not an error.
+pub(crate) fn commit(storage: &Storage, name: &str) -> Result<()> {
+    clear(storage, &storage.config.get_tag_journal_filepath(name))
+}
+
+fn clear(storage: &Storage, path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => {}
+        r => r?,
+    }
+    sync_dir(storage.config.get_tag_journal_dir())?;
+    Ok(())
+}
+
+fn read_entry(path: &Path) -> Result<Entry> {
+    let mut file = fs::File::open(path)?;
+    magic::check_header(&mut file, FILE_TYPE, VERSION, VERSION)?;
+
+    let mut raw = de::Deserializer::from(io::BufReader::new(file));
+    raw.tuple(3, "tag journal entry")?;
+    let name = raw.text()?;
+    let previous = read_optional(&mut raw)?;
+    let next = read_optional(&mut raw)?;
+    Ok(Entry {
+        name,
+        previous,
+        next,
+    })
+}
+
+/// whether the tag value does not refer to missing data: a hash is the
+/// one of a pack or of a (readable) block of the storage
+fn is_valid(storage: &Storage, value: &[u8]) -> bool {
+    if value.len() != HASH_SIZE {
+        return true;
+    }
+    let mut hash = [0u8; HASH_SIZE];
+    hash.copy_from_slice(value);
+    storage.config.get_pack_filepath(&hash).exists()
+        || storage
+            .read_block(&hash)
+            .and_then(|raw_block| raw_block.decode().map_err(Error::from))
+            .is_ok()
+}
+
+/// complete or roll back the tag updates interrupted by a crash, if any
+pub(crate) fn recover(storage: &Storage) -> Result<()> {
+    let dir = storage.config.get_tag_journal_dir();
+    let entries = match fs::read_dir(&dir) {
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(()),
+        r => r?,
+    };
+    for entry in entries {
+        let entry = entry?;
+        if entry.file_name().to_string_lossy().starts_with(".tmp.") {
+            // the journal was not complete, the update did not start
+            fs::remove_file(entry.path())?;
+        } else {
+            recover_entry(storage, &entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+fn recover_entry(storage: &Storage, path: &Path) -> Result<()> {
+    let entry = match read_entry(path) {
+        Ok(entry) => entry,
+        Err(err) => {
+            // the journal is renamed in place once complete, so the
+            // update of the tag did not start
+            warn!("discarding the invalid tag journal {:?}: {}", path, err);
+            return clear(storage, path);
+        }
+    };
+
+    let value = match entry.next {
+        Some(ref next) if !is_valid(storage, next) => {
+            warn!(
+                "rolling back the interrupted update of the tag `{}'",
+                entry.name
+            );
+            &entry.previous
+        }
+        _ => {
+            info!(
+                "completing the interrupted update of the tag `{}'",
+                entry.name
+            );
+            &entry.next
+        }
+    };
+    match value {
+        Some(content) => tag::write_file(storage, &entry.name, content),
+        None => tag::remove_file(storage, &entry.name),
+    }
+    clear(storage, path)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use test::TempStorage;
+
+    const VALID: [u8; HASH_SIZE] = [1; HASH_SIZE];
+    const MISSING: [u8; HASH_SIZE] = [2; HASH_SIZE];
+
+    fn temp_storage() -> TempStorage {
+        let storage = TempStorage::new();
+        // the hash of a pack is a valid tag value
+        fs::File::create(storage.config.get_pack_filepath(&VALID)).unwrap();
+        storage
+    }
+
+    fn journals(storage: &Storage) -> usize {
+        fs::read_dir(storage.config.get_tag_journal_dir())
+            .unwrap()
+            .count()
+    }
+
+    #[test]
+    fn update_clears_the_journal() {
+        let storage = temp_storage();
+        tag::write(&storage, &"HEAD", &VALID);
+        tag::write(&storage, &"EPOCH_0", &VALID);
+        assert_eq!(tag::read(&storage, &"HEAD"), Some(VALID.to_vec()));
+        assert_eq!(journals(&storage), 0);
+
+        tag::remove_tag(&storage, &"HEAD");
+        assert_eq!(tag::read(&storage, &"HEAD"), None);
+        assert_eq!(journals(&storage), 0);
+
+        // clearing a journal already cleared is not an error
+        commit(&storage, "HEAD").unwrap();
+    }
+
+    #[test]
+    fn journal_per_tag() {
+        let storage = temp_storage();
+        begin(&storage, "HEAD", None, Some(&VALID[..])).unwrap();
+        begin(&storage, "OLDEST_BLOCK", None, Some(&VALID[..])).unwrap();
+        assert_eq!(journals(&storage), 2);
+        commit(&storage, "HEAD").unwrap();
+        assert_eq!(journals(&storage), 1);
+        commit(&storage, "OLDEST_BLOCK").unwrap();
+        assert_eq!(journals(&storage), 0);
+    }
+
+    #[test]
+    fn recover_valid_target() {
+        let mut storage = temp_storage();
+        tag::write(&storage, &"HEAD", b"previous");
+        // interrupted before the tag is written
+        begin(
+            &storage,
+            "HEAD",
+            Some(b"previous".to_vec()),
+            Some(&VALID[..]),
+        )
+        .unwrap();
+
+        storage.reopen();
+        assert_eq!(tag::read(&storage, &"HEAD"), Some(VALID.to_vec()));
+        assert_eq!(journals(&storage), 0);
+    }
+
+    #[test]
+    fn recover_invalid_target() {
+        let mut storage = temp_storage();
+        tag::write(&storage, &"HEAD", &VALID);
+        // interrupted after the tag is written, the block is missing
+        begin(&storage, "HEAD", Some(VALID.to_vec()), Some(&MISSING[..])).unwrap();
+        tag::write_file(&storage, &"HEAD", &MISSING);
+        // a new tag pointing at a missing block is removed
+        begin(&storage, "OLDEST_BLOCK", None, Some(&MISSING[..])).unwrap();
+        tag::write_file(&storage, &"OLDEST_BLOCK", &MISSING);
+
+        storage.reopen();
+        assert_eq!(tag::read(&storage, &"HEAD"), Some(VALID.to_vec()));
+        assert_eq!(tag::read(&storage, &"OLDEST_BLOCK"), None);
+        assert_eq!(journals(&storage), 0);
+    }
+
+    #[test]
+    fn recover_discards_invalid_journal() {
+        let mut storage = temp_storage();
+        tag::write(&storage, &"HEAD", &VALID);
+        fs::write(storage.config.get_tag_journal_filepath("HEAD"), b"garbage").unwrap();
+
+        storage.reopen();
+        assert_eq!(tag::read(&storage, &"HEAD"), Some(VALID.to_vec()));
+        assert_eq!(journals(&storage), 0);
+    }
+}
@@ -2,30 +2,37 @@
 extern crate log;
 extern crate cardano;
 extern crate cbor_event;
+extern crate chain_core;
 extern crate rand;
 extern crate storage_units;
 
 pub mod chain_state;
+pub mod compact;
 pub mod config;
 pub mod epoch;
+pub mod index;
 pub mod iter;
+mod journal;
 pub mod pack;
 pub mod refpack;
 pub mod tag;
 pub mod types;
 use std::{fs, io, result};
 
+pub use compact::{compact, CompactParameters, CompactReport};
 pub use config::StorageConfig;
+pub use epoch::{verify_epoch_pack, EpochCorruption};
 
 use cardano::block::{Block, BlockDate, EpochId, HeaderHash, RawBlock, SlotId};
-use std::{collections::BTreeMap, error, fmt};
+use chain_core::metrics::{names, Metrics, NoMetrics};
+use std::{collections::BTreeMap, error, fmt, sync::Arc};
 
 use storage_units::utils::error::StorageError;
 use storage_units::utils::magic;
 use storage_units::utils::tmpfile::*;
 use types::*;
 
-use pack::{packreader_block_next, packreader_init};
+use pack::{packreader_header_next, packreader_init};
 use std::cmp::Ordering;
 use storage_units::{indexfile, packfile, reffile};
 
@@ -45,6 +52,10 @@ pub enum Error {
     EpochSlotRewind(EpochId, SlotId),
     EpochChainInvalid(BlockDate, HeaderHash, HeaderHash),
     NoSuchTag,
+
+    // ** Explorer index errors
+    IndexChainInvalid(HeaderHash, HeaderHash),
+    IndexForkTooDeep(HeaderHash),
 }
 impl From<io::Error> for Error {
     fn from(e: io::Error) -> Self {
@@ -80,6 +91,8 @@ impl fmt::Display for Error {
             Error::EpochSlotRewind(eid, sid) => write!(f, "Cannot pack block {} because is prior to {} already packed", sid, eid),
             Error::EpochChainInvalid(bd, rhh, ehh) => write!(f, "Cannot pack block {} ({}) because it does not follow the blockchain hash (expected: {})", bd, ehh, rhh),
             Error::NoSuchTag => write!(f, "Tag not found"),
+            Error::IndexChainInvalid(tip, parent) => write!(f, "Cannot index a block following {} on top of the index tip {}", parent, tip),
+            Error::IndexForkTooDeep(tip) => write!(f, "Cannot roll back the index tip {}, the fork is too deep", tip),
         }
     }
 }
@@ -96,6 +109,8 @@ impl error::Error for Error {
             Error::EpochSlotRewind(_, _) => None,
             Error::EpochChainInvalid(_, _, _) => None,
             Error::NoSuchTag => None,
+            Error::IndexChainInvalid(_, _) => None,
+            Error::IndexForkTooDeep(_) => None,
         }
     }
 }
@@ -105,6 +120,7 @@ pub type Result<T> = result::Result<T, Error>;
 pub struct Storage {
     pub config: StorageConfig,
     lookups: BTreeMap<PackHash, indexfile::Lookup>,
+    metrics: Arc<dyn Metrics>,
 }
 
 macro_rules! try_open {
@@ -150,10 +166,22 @@ impl Storage {
         let storage = Storage {
             config: cfg.clone(),
             lookups: lookups,
+            metrics: Arc::new(NoMetrics),
         };
+        journal::recover(&storage)?;
         Ok(storage)
     }
 
+    /// report the writes of the storage, and of the synchronisation
+    /// using it, to the given metrics
+    pub fn set_metrics(&mut self, metrics: Arc<dyn Metrics>) {
+        self.metrics = metrics;
+    }
+
+    pub fn metrics(&self) -> &dyn Metrics {
+        &*self.metrics
+    }
+
     /// Returns an iterator over blocks in the given block range.
     ///
     /// The range is given inclusively. The blocks are iterated in order from
@@ -162,6 +190,23 @@ impl Storage {
         iter::range_iter(self, from, to)
     }
 
+    /// Returns an iterator over the blocks between the given bounds.
+    ///
+    /// The bounds are included and can be given as block hashes or as
+    /// epoch/slot dates. The blocks of the packed epochs are read one
+    /// epoch at a time, so the range can span the whole chain.
+    ///
+    /// Fails with `BlockNotFound` if `from` is the hash of a block which is
+    /// not in the chain of `to`.
+    pub fn blocks(
+        &self,
+        from: iter::Bound,
+        to: iter::Bound,
+        direction: iter::Direction,
+    ) -> Result<iter::Blocks> {
+        iter::blocks_iter(self, from, to, direction)
+    }
+
     /// Returns an iterator over blocks in reverse from the given header hash.
     pub fn reverse_from(&self, hh: HeaderHash) -> Result<iter::ReverseIter> {
         iter::reverse_iter(self, hh)
@@ -260,6 +305,12 @@ pub mod blob {
         magic::write_header(&mut tmp_file, FILE_TYPE, VERSION)?;
         tmp_file.write_all(block)?;
         tmp_file.render_permanent(&path)?;
+        storage
+            .metrics
+            .counter(super::names::STORAGE_BLOBS_WRITTEN, 1);
+        storage
+            .metrics
+            .counter(super::names::STORAGE_BLOB_BYTES_WRITTEN, block.len() as u64);
         Ok(())
     }
 
@@ -441,9 +492,8 @@ pub fn refpack_epoch_pack<S: AsRef<str>>(storage: &Storage, tag: &S) -> Result<(
 
     let mut current_state = None;
 
-    while let Some(raw_block) = packreader_block_next(&mut pack)? {
-        let block = raw_block.decode()?;
-        let hdr = block.header();
+    while let Some(raw_hdr) = packreader_header_next(&mut pack)? {
+        let hdr = raw_hdr.decode()?;
         let hash = hdr.compute_hash();
         let date = hdr.blockdate();
 
@@ -509,9 +559,8 @@ fn epoch_integrity_check(
 
     let mut current_state = None;
 
-    while let Some(raw_block) = packreader_block_next(&mut pack)? {
-        let block = raw_block.decode()?;
-        let hdr = block.header();
+    while let Some(raw_hdr) = packreader_header_next(&mut pack)? {
+        let hdr = raw_hdr.decode()?;
         let hash = hdr.compute_hash();
         let prevhash = hdr.previous_header();
         let date = hdr.blockdate();
@@ -561,3 +610,232 @@ fn epoch_integrity_check(
         Some((_, _, prevhash)) => return Ok(prevhash),
     }
 }
+
+#[cfg(test)]
+pub mod test {
+    use super::{blob, header_to_blockhash, tag, Storage, StorageConfig};
+    use cardano::block::{
+        boundary, normal, sign, update, Block, BlockDate, BlockHeaderAttributes, BlockVersion,
+        ChainDifficulty, EpochId, EpochSlotId, HeaderExtraData, HeaderHash, SoftwareVersion,
+    };
+    use cardano::config::{GenesisData, ProtocolMagic};
+    use cardano::fee::LinearFee;
+    use cardano::hash::Blake2b256;
+    use cardano::hdwallet::{XPrv, XPRV_SIZE};
+    use cardano::tags::SigningTag;
+    use cardano::{address, tx};
+    use cbor_event::{se, Value};
+    use std::collections::BTreeMap;
+    use std::ops::{Deref, DerefMut};
+    use std::time::{Duration, SystemTime};
+    use std::{env, fs, path::PathBuf};
+
+    /// a storage in a temporary directory, removed on drop
+    pub struct TempStorage {
+        storage: Storage,
+        path: PathBuf,
+    }
+
+    impl TempStorage {
+        pub fn new() -> Self {
+            let mut path = env::temp_dir();
+            path.push(format!("cardano-storage-test-{}", ::rand::random::<u64>()));
+            let storage = Storage::init(&StorageConfig::new(&path)).unwrap();
+            TempStorage { storage, path }
+        }
+
+        /// open the storage again, as after a restart
+        pub fn reopen(&mut self) {
+            self.storage = Storage::init(&StorageConfig::new(&self.path)).unwrap();
+        }
+    }
+
+    impl Deref for TempStorage {
+        type Target = Storage;
+        fn deref(&self) -> &Storage {
+            &self.storage
+        }
+    }
+
+    impl DerefMut for TempStorage {
+        fn deref_mut(&mut self) -> &mut Storage {
+            &mut self.storage
+        }
+    }
+
+    impl Drop for TempStorage {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.path);
+        }
+    }
+
+    /// the number of slots of an epoch of the test chains
+    pub const SLOTS_PER_EPOCH: u16 = 10;
+
+    const PROTOCOL_MAGIC: u32 = 0x7e57;
+
+    /// the genesis data of the test chains: no initial utxos, the blocks
+    /// of every slot are issued by the same stakeholder
+    pub fn genesis_data() -> GenesisData {
+        GenesisData {
+            genesis_prev: HeaderHash::new(b"test genesis"),
+            // an epoch is `10 * k` slots
+            epoch_stability_depth: SLOTS_PER_EPOCH as usize / 10,
+            start_time: SystemTime::UNIX_EPOCH,
+            slot_duration: Duration::from_secs(20),
+            protocol_magic: ProtocolMagic::from(PROTOCOL_MAGIC),
+            fee_policy: LinearFee::default(),
+            avvm_distr: BTreeMap::new(),
+            non_avvm_balances: BTreeMap::new(),
+            boot_stakeholders: BTreeMap::new(),
+        }
+    }
+
+    fn issuer_key() -> XPrv {
+        XPrv::normalize_bytes([1; XPRV_SIZE])
+    }
+
+    fn delegate_key() -> XPrv {
+        XPrv::normalize_bytes([2; XPRV_SIZE])
+    }
+
+    fn empty_attributes() -> Value {
+        Value::Object(BTreeMap::new())
+    }
+
+    /// the boundary block of the epoch, the test issuer leads every slot
+    pub fn boundary_block(parent: &HeaderHash, epoch: EpochId) -> Block {
+        let body = boundary::Body {
+            slot_leaders: vec![
+                address::StakeholderId::new(&issuer_key().public());
+                SLOTS_PER_EPOCH as usize
+            ],
+        };
+        let mut body_bytes = se::Serializer::new_vec();
+        body_bytes.serialize(&body).unwrap();
+        let header = boundary::BlockHeader::new(
+            ProtocolMagic::from(PROTOCOL_MAGIC),
+            parent.clone(),
+            boundary::BodyProof(Blake2b256::new(&body_bytes.finalize())),
+            boundary::Consensus {
+                epoch,
+                chain_difficulty: ChainDifficulty::from(epoch * SLOTS_PER_EPOCH as u64),
+            },
+            BlockHeaderAttributes(empty_attributes()),
+        );
+        Block::BoundaryBlock(boundary::Block {
+            header,
+            body,
+            extra: Value::Array(vec![empty_attributes()]),
+        })
+    }
+
+    /// a main block of the slot including the transactions, signed by the
+    /// delegate of the test issuer
+    ///
+    /// The witnesses of the transactions are not verified by the storage,
+    /// the transactions only need to be verified if the chain state is
+    /// restored through the block.
+    pub fn main_block(parent: &HeaderHash, slot: EpochSlotId, txs: Vec<tx::TxAux>) -> Block {
+        let protocol_magic = ProtocolMagic::from(PROTOCOL_MAGIC);
+        let body = normal::Body::new(
+            normal::TxPayload::new(txs),
+            normal::SscPayload::fake(),
+            normal::DlgPayload(Value::Array(vec![])),
+            update::UpdatePayload {
+                proposal: None,
+                votes: vec![],
+            },
+        );
+        let extra = Value::Array(vec![empty_attributes()]);
+        let mut extra_bytes = se::Serializer::new_vec();
+        extra_bytes.serialize(&extra).unwrap();
+
+        let issuer = issuer_key();
+        let delegate = delegate_key();
+        let psk =
+            sign::ProxySecretKey::sign(&issuer, delegate.public(), slot.epoch, protocol_magic);
+        let mut header = normal::BlockHeader::new(
+            protocol_magic,
+            parent.clone(),
+            normal::BodyProof::generate_from_body(&body),
+            normal::Consensus {
+                slot_id: slot,
+                leader_key: issuer.public(),
+                chain_difficulty: ChainDifficulty::from(
+                    slot.epoch * SLOTS_PER_EPOCH as u64 + slot.slotid as u64 + 1,
+                ),
+                block_signature: sign::BlockSignature::Signature(delegate.sign(&[])),
+            },
+            HeaderExtraData::new(
+                BlockVersion::new(0, 1, 0),
+                SoftwareVersion::new("cardano-sl", 1).unwrap(),
+                BlockHeaderAttributes(empty_attributes()),
+                Blake2b256::new(&extra_bytes.finalize()),
+            ),
+        );
+
+        // the heavyweight proxy signature of the header, see
+        // `sign::MainToSign::verify_proxy_sig`
+        let mut to_sign = b"01".to_vec();
+        to_sign.extend(issuer.public().as_ref());
+        to_sign.push(SigningTag::MainBlockHeavy as u8);
+        se::Serializer::new(&mut to_sign)
+            .serialize(&protocol_magic)
+            .unwrap()
+            .serialize(&sign::MainToSign::from_header(&header))
+            .unwrap();
+        header.consensus.block_signature = sign::BlockSignature::ProxyHeavy(sign::ProxySignature {
+            psk,
+            sig: delegate.sign(&to_sign),
+        });
+
+        Block::MainBlock(normal::Block::new(header, body, extra))
+    }
+
+    /// the blocks of the dates, without transactions, chained from `parent`
+    pub fn chain(parent: &HeaderHash, dates: &[BlockDate]) -> Vec<(HeaderHash, Block)> {
+        let mut parent = parent.clone();
+        dates
+            .iter()
+            .map(|date| {
+                let block = match date {
+                    BlockDate::Boundary(epoch) => boundary_block(&parent, *epoch),
+                    BlockDate::Normal(slot) => main_block(&parent, *slot, vec![]),
+                };
+                parent = block.header().compute_hash();
+                (parent.clone(), block)
+            })
+            .collect()
+    }
+
+    /// the dates of `epochs` epochs of a boundary block followed by
+    /// `blocks` main blocks, from `first`
+    pub fn epoch_dates(first: EpochId, epochs: EpochId, blocks: u16) -> Vec<BlockDate> {
+        assert!(blocks <= SLOTS_PER_EPOCH);
+        let mut dates = vec![];
+        for epoch in first..first + epochs {
+            dates.push(BlockDate::Boundary(epoch));
+            dates
+                .extend((0..blocks).map(|slotid| BlockDate::Normal(EpochSlotId { epoch, slotid })));
+        }
+        dates
+    }
+
+    /// the serialization of the block, as stored
+    pub fn raw(block: &Block) -> Vec<u8> {
+        let mut bytes = se::Serializer::new_vec();
+        bytes.serialize(block).unwrap();
+        bytes.finalize()
+    }
+
+    /// write the blocks as loose blocks and make the last one the `HEAD`
+    pub fn write_blocks(storage: &Storage, blocks: &[(HeaderHash, Block)]) {
+        for (hash, block) in blocks {
+            blob::write(storage, &header_to_blockhash(hash), &raw(block)).unwrap();
+        }
+        if let Some((tip, _)) = blocks.last() {
+            tag::write_hash(storage, &tag::HEAD, tip);
+        }
+    }
+}
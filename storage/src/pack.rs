@@ -1,6 +1,8 @@
 use super::Result;
 use cardano;
+use cardano::block::RawBlockHeader;
 use std::fs;
+use std::io::BufReader;
 use storage_units::utils::tmpfile::TmpFile;
 
 use storage_units::indexfile;
@@ -71,3 +73,14 @@ pub fn packreader_block_next(
     let next = reader.next_block()?;
     Ok(next.map(|x| cardano::block::RawBlock(x)))
 }
+
+/// read the header of the next block of the pack, the body of the block is
+/// streamed through without being held in memory
+pub fn packreader_header_next(
+    reader: &mut packfile::Reader<fs::File>,
+) -> Result<Option<RawBlockHeader>> {
+    match reader.next_block_with(|block| RawBlockHeader::read_from_block(BufReader::new(block)))? {
+        None => Ok(None),
+        Some(header) => Ok(Some(header?)),
+    }
+}
@@ -1,8 +1,10 @@
 use cardano::util::{hex, try_from_slice::TryFromSlice};
-use std::io::{Read, Write};
+use std::io::{self, Read, Write};
 use std::{fs, path::PathBuf};
 
 use cardano::block;
+use journal;
+use storage_units::utils::tmpfile::sync_dir;
 
 pub const OLDEST_BLOCK: &str = "OLDEST_BLOCK";
 pub const HEAD: &str = "HEAD";
@@ -11,9 +13,19 @@ pub fn get_epoch_tag(epoch: block::EpochId) -> String {
     format!("EPOCH_{}", epoch)
 }
 
+/// write the tag, the update is recorded in the journal so it is completed
+/// or rolled back if it is interrupted by a crash
 pub fn write<S: AsRef<str>>(storage: &super::Storage, name: &S, content: &[u8]) {
+    let previous = read(storage, name);
+    journal::begin(storage, name.as_ref(), previous, Some(content)).unwrap();
+    write_file(storage, name, content);
+    journal::commit(storage, name.as_ref()).unwrap();
+}
+
+pub(crate) fn write_file<S: AsRef<str>>(storage: &super::Storage, name: &S, content: &[u8]) {
     let mut tmp_file = super::tmpfile_create_type(storage, super::StorageFileType::Tag);
     tmp_file.write_all(hex::encode(content).as_bytes()).unwrap();
+    tmp_file.sync().unwrap();
 
     let path = storage.config.get_tag_filepath(name);
     let dir = PathBuf::from(path);
@@ -27,9 +39,8 @@ pub fn write<S: AsRef<str>>(storage: &super::Storage, name: &S, content: &[u8])
         }
     };
 
-    tmp_file
-        .render_permanent(&storage.config.get_tag_filepath(name))
-        .unwrap();
+    tmp_file.render_permanent(&dir).unwrap();
+    sync_dir(dir.parent().unwrap()).unwrap();
 }
 
 pub fn write_hash<S: AsRef<str>>(storage: &super::Storage, name: &S, content: &block::HeaderHash) {
@@ -60,6 +71,19 @@ pub fn exist<S: AsRef<str>>(storage: &super::Storage, name: &S) -> bool {
 }
 
 pub fn remove_tag<S: AsRef<str>>(storage: &super::Storage, name: &S) {
+    let previous = read(storage, name);
+    journal::begin(storage, name.as_ref(), previous, None).unwrap();
+    remove_file(storage, name);
+    journal::commit(storage, name.as_ref()).unwrap();
+}
+
+pub(crate) fn remove_file<S: AsRef<str>>(storage: &super::Storage, name: &S) {
     let p = storage.config.get_tag_filepath(name);
-    fs::remove_file(p).unwrap()
+    match fs::remove_file(&p) {
+        Err(ref err) if err.kind() == io::ErrorKind::NotFound => {}
+        r => {
+            r.unwrap();
+            sync_dir(p.parent().unwrap()).unwrap();
+        }
+    }
 }
@@ -0,0 +1,54 @@
+//! hexadecimal decoding of the fields of the vectors
+
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DecodeError {
+    InvalidHexChar(usize),
+    OddLength,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::InvalidHexChar(idx) => {
+                write!(f, "non-hexadecimal character at byte index {}", idx)
+            }
+            DecodeError::OddLength => write!(f, "odd number of hexadecimal digits"),
+        }
+    }
+}
+
+fn digit(idx: usize, c: u8) -> Result<u8, DecodeError> {
+    match c {
+        b'A'..=b'F' => Ok(c - b'A' + 10),
+        b'a'..=b'f' => Ok(c - b'a' + 10),
+        b'0'..=b'9' => Ok(c - b'0'),
+        _ => Err(DecodeError::InvalidHexChar(idx)),
+    }
+}
+
+pub fn decode(input: &str) -> Result<Vec<u8>, DecodeError> {
+    let input = input.as_bytes();
+    if input.len() % 2 != 0 {
+        return Err(DecodeError::OddLength);
+    }
+    input
+        .chunks(2)
+        .enumerate()
+        .map(|(i, pair)| Ok(digit(2 * i, pair[0])? << 4 | digit(2 * i + 1, pair[1])?))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_hex() {
+        assert_eq!(decode(""), Ok(vec![]));
+        assert_eq!(decode("00ff1A"), Ok(vec![0x00, 0xff, 0x1a]));
+        assert_eq!(decode("0"), Err(DecodeError::OddLength));
+        assert_eq!(decode("0g"), Err(DecodeError::InvalidHexChar(1)));
+    }
+}
@@ -0,0 +1,180 @@
+//! Golden test vectors shared across the crates of the workspace
+//!
+//! The vectors are JSON files of the `vectors` directory, embedded in the
+//! crate at compile time. Every binary field is hex encoded in the files and
+//! decoded by the loaders, so the tests only deal with bytes.
+//!
+//! The crate depends on none of the crates it provides vectors for: each of
+//! them uses it as a dev-dependency and checks its own encoding against the
+//! same fixtures, so a change of encoding in one crate is caught even when
+//! the other crates relying on it are not rebuilt.
+//!
+//! ```
+//! for vector in test_vectors::chain_addresses() {
+//!     assert!(vector.bech32.len() > vector.bytes.len());
+//! }
+//! ```
+
+#[macro_use]
+extern crate serde_derive;
+
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+
+mod hex;
+
+const CHAIN_ADDRESSES: &str = include_str!("../vectors/chain_addresses.json");
+const CARDANO_ADDRESSES: &str = include_str!("../vectors/cardano_addresses.json");
+const ED25519: &str = include_str!("../vectors/ed25519.json");
+const CARDANO_TRANSACTIONS: &str = include_str!("../vectors/cardano_transactions.json");
+const CARDANO_BLOCK_HEADERS: &str = include_str!("../vectors/cardano_block_headers.json");
+const CONFIG_PARAMS: &str = include_str!("../vectors/config_params.json");
+const CARDANO_WALLETS: &str = include_str!("../vectors/cardano_wallets.json");
+
+/// a `chain-addr` address and its encodings
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChainAddress {
+    pub name: String,
+    /// `production` or `test`
+    pub discrimination: String,
+    /// `single`, `group`, `account`, `multisig` or `script`
+    pub kind: String,
+    #[serde(deserialize_with = "hex_bytes")]
+    pub bytes: Vec<u8>,
+    pub bech32: String,
+    pub base32: String,
+}
+
+/// a Cardano (Byron) address in its base58 form
+#[derive(Debug, Clone, Deserialize)]
+pub struct CardanoAddress {
+    pub name: String,
+    pub base58: String,
+}
+
+/// an Ed25519 key pair and the signature of a message
+#[derive(Debug, Clone, Deserialize)]
+pub struct Ed25519 {
+    pub name: String,
+    #[serde(deserialize_with = "hex_bytes")]
+    pub secret_key: Vec<u8>,
+    #[serde(deserialize_with = "hex_bytes")]
+    pub public_key: Vec<u8>,
+    pub public_key_bech32: String,
+    #[serde(deserialize_with = "hex_bytes")]
+    pub message: Vec<u8>,
+    #[serde(deserialize_with = "hex_bytes")]
+    pub signature: Vec<u8>,
+}
+
+/// a signed Cardano transaction (`TxAux`) and the id of the transaction
+#[derive(Debug, Clone, Deserialize)]
+pub struct CardanoTransaction {
+    pub name: String,
+    /// hex encoded transaction id
+    pub txid: String,
+    #[serde(deserialize_with = "hex_bytes")]
+    pub cbor: Vec<u8>,
+}
+
+/// a Cardano block header and its hash
+#[derive(Debug, Clone, Deserialize)]
+pub struct CardanoBlockHeader {
+    pub name: String,
+    /// hex encoded header hash
+    pub hash: String,
+    #[serde(deserialize_with = "hex_bytes")]
+    pub cbor: Vec<u8>,
+}
+
+/// a mockchain `ConfigParam`, in its textual and binary forms
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConfigParam {
+    /// the name of the parameter, as in the configuration files
+    pub name: String,
+    /// the value of the parameter, as in the configuration files
+    pub value: String,
+    #[serde(deserialize_with = "hex_bytes")]
+    pub bytes: Vec<u8>,
+}
+
+/// the keys and addresses of a Cardano wallet, derived from its mnemonics
+#[derive(Debug, Clone, Deserialize)]
+pub struct CardanoWallet {
+    pub name: String,
+    /// `random-index` or `bip44`
+    pub wallet: String,
+    /// `v1` or `v2`
+    pub derivation_scheme: String,
+    /// english mnemonics
+    pub mnemonics: String,
+    /// only used by the `bip44` wallets
+    pub password: String,
+    #[serde(deserialize_with = "hex_bytes")]
+    pub root_key: Vec<u8>,
+    pub addresses: Vec<CardanoWalletAddress>,
+}
+
+/// a key derived from the root key of a Cardano wallet, and its address
+#[derive(Debug, Clone, Deserialize)]
+pub struct CardanoWalletAddress {
+    /// the derivation path from the root key
+    pub path: Vec<u32>,
+    #[serde(deserialize_with = "hex_bytes")]
+    pub private_key: Vec<u8>,
+    #[serde(deserialize_with = "hex_bytes")]
+    pub public_key: Vec<u8>,
+    pub base58: String,
+}
+
+fn load<T: DeserializeOwned>(file: &str, json: &str) -> Vec<T> {
+    serde_json::from_str(json).unwrap_or_else(|e| panic!("invalid test vectors {}: {}", file, e))
+}
+
+fn hex_bytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    hex::decode(&s).map_err(serde::de::Error::custom)
+}
+
+pub fn chain_addresses() -> Vec<ChainAddress> {
+    load("chain_addresses.json", CHAIN_ADDRESSES)
+}
+
+pub fn cardano_addresses() -> Vec<CardanoAddress> {
+    load("cardano_addresses.json", CARDANO_ADDRESSES)
+}
+
+pub fn ed25519() -> Vec<Ed25519> {
+    load("ed25519.json", ED25519)
+}
+
+pub fn cardano_transactions() -> Vec<CardanoTransaction> {
+    load("cardano_transactions.json", CARDANO_TRANSACTIONS)
+}
+
+pub fn cardano_block_headers() -> Vec<CardanoBlockHeader> {
+    load("cardano_block_headers.json", CARDANO_BLOCK_HEADERS)
+}
+
+pub fn config_params() -> Vec<ConfigParam> {
+    load("config_params.json", CONFIG_PARAMS)
+}
+
+pub fn cardano_wallets() -> Vec<CardanoWallet> {
+    load("cardano_wallets.json", CARDANO_WALLETS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn all_vectors_load() {
+        assert!(!chain_addresses().is_empty());
+        assert!(!cardano_addresses().is_empty());
+        assert!(!ed25519().is_empty());
+        assert!(!cardano_transactions().is_empty());
+        assert!(!cardano_block_headers().is_empty());
+        assert!(!config_params().is_empty());
+        assert!(!cardano_wallets().is_empty());
+    }
+}